@@ -542,10 +542,7 @@ async fn status_payload(state: &Arc<BridgeState>) -> Value {
     let tasks = state.tasks.lock().await;
     let ai_state = state.ai_state.lock().await.clone();
     let window_count = state.app.webview_windows().len();
-    let log_dir = state
-        .app
-        .path()
-        .app_log_dir()
+    let log_dir = crate::logging::log_dir()
         .map(|path| path.to_string_lossy().to_string())
         .ok();
     let app_data_dir = state
@@ -604,7 +601,8 @@ async fn logs_payload(state: &Arc<BridgeState>, request: &HttpRequest) -> Value
         .and_then(|value| value.parse::<usize>().ok())
         .unwrap_or(200)
         .min(2000);
-    let text = read_recent_log_lines(&state.app, lines)
+    let text = crate::logging::log_dir()
+        .and_then(|dir| crate::logging::recent_lines(&dir, lines))
         .unwrap_or_else(|error| format!("[agent_bridge] failed to read app log: {error}"));
     json!({
         "schemaVersion": 1,
@@ -1352,22 +1350,6 @@ fn unsupported_response(capability: &str) -> Vec<u8> {
     )
 }
 
-fn read_recent_log_lines(app: &AppHandle, lines: usize) -> Result<String, String> {
-    let log_path = app
-        .path()
-        .app_log_dir()
-        .map_err(|e| format!("resolve log dir: {e}"))?
-        .join("classnoteai.log");
-    if !log_path.exists() {
-        return Ok(String::new());
-    }
-    let text = fs::read_to_string(&log_path)
-        .map_err(|e| format!("read log {}: {e}", log_path.display()))?;
-    let all_lines: Vec<_> = text.lines().collect();
-    let start = all_lines.len().saturating_sub(lines.min(2000));
-    Ok(all_lines[start..].join("\n"))
-}
-
 fn is_authorized(request: &HttpRequest, token: &str) -> bool {
     let bearer = request
         .headers
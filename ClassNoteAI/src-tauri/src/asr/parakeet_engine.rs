@@ -34,12 +34,15 @@
 //! NOT a substitute for real word-level timing if we ever want that.
 
 use std::path::Path;
-use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::sync::{Mutex, MutexGuard, OnceLock, TryLockError};
 use std::time::Instant;
 
 use parakeet_rs::Nemotron;
 
 use super::parakeet_model::Variant;
+use crate::audio::denoise::Denoiser;
+use crate::audio::loudness::{Agc, AgcConfig};
+use crate::audio::resample::Resampler;
 
 /// Sample rate the model was trained on. Anything else upstream MUST
 /// resample first; the model has no resampler of its own.
@@ -83,6 +86,15 @@ struct ActiveSession {
     /// Cumulative samples that have been pushed through
     /// `transcribe_chunk`. Used to compute audio_end timestamps.
     samples_processed: usize,
+    /// Created on the first push at a non-16 kHz rate. Kept per session
+    /// so the filter state carries across chunk boundaries.
+    resampler: Option<Resampler>,
+    /// Opt-in noise suppression, between resampling and AGC so the
+    /// AGC doesn't boost the noise floor we're about to remove.
+    denoiser: Option<Denoiser>,
+    /// Opt-in gain normalisation, applied after resampling so the AGC
+    /// always sees 16 kHz blocks.
+    agc: Option<Agc>,
 }
 
 impl EngineState {
@@ -153,19 +165,50 @@ impl EngineState {
             started_at: Instant::now(),
             pcm_buffer: Vec::with_capacity(CHUNK_SAMPLES * 2),
             samples_processed: 0,
+            resampler: None,
+            denoiser: None,
+            agc: None,
         });
         Ok(())
     }
 
+    /// Turn noise suppression on or off for the active session.
+    pub fn set_session_denoise(&mut self, session_id: &str, enabled: bool) -> Result<(), String> {
+        let session = self
+            .active
+            .as_mut()
+            .filter(|s| s.id == session_id)
+            .ok_or_else(|| format!("no active session {}", session_id))?;
+        session.denoiser = enabled.then(Denoiser::new);
+        Ok(())
+    }
+
+    /// Turn gain normalisation on or off for the active session.
+    pub fn set_session_agc(&mut self, session_id: &str, enabled: bool) -> Result<(), String> {
+        let session = self
+            .active
+            .as_mut()
+            .filter(|s| s.id == session_id)
+            .ok_or_else(|| format!("no active session {}", session_id))?;
+        session.agc = enabled.then(|| Agc::new(AgcConfig::default()));
+        Ok(())
+    }
+
     /// Push int16 PCM. Drains the buffer in 8960-sample chunks and
     /// invokes `emit(delta, transcript, audio_end_sec)` once per
     /// non-empty delta. `transcript` is the model's cumulative text
     /// after applying Nemotron's own stabilization/cleanup.
     /// Sub-chunk leftovers stay in the buffer until the next push.
+    ///
+    /// `input_rate` other than [`SAMPLE_RATE`] goes through the session's
+    /// streaming resampler first. A session is expected to keep one rate;
+    /// switching mid-stream rebuilds the resampler (and drops its
+    /// look-ahead, ~1 ms of audio).
     pub fn push_pcm_i16<F>(
         &mut self,
         session_id: &str,
         pcm: &[i16],
+        input_rate: u32,
         mut emit: F,
     ) -> Result<(), String>
     where
@@ -190,10 +233,26 @@ impl EngineState {
         // /32768.0; we mirror it. Slightly asymmetric (i16 min is
         // -32768, max is +32767) but the half-LSB skew at peak is
         // inaudible and matches every other ASR pipeline's convention.
-        session.pcm_buffer.reserve(pcm.len());
-        for &s in pcm {
-            session.pcm_buffer.push(s as f32 / 32_768.0);
+        let normalized: Vec<f32> = pcm.iter().map(|&s| s as f32 / 32_768.0).collect();
+        let mut samples = if input_rate == SAMPLE_RATE {
+            session.resampler = None;
+            normalized
+        } else {
+            if session.resampler.as_ref().map(|r| r.from_rate()) != Some(input_rate) {
+                session.resampler = Some(Resampler::new(input_rate, SAMPLE_RATE)?);
+            }
+            match session.resampler.as_mut() {
+                Some(r) => r.process(&normalized),
+                None => normalized,
+            }
+        };
+        if let Some(d) = session.denoiser.as_mut() {
+            samples = d.process(&samples);
+        }
+        if let Some(agc) = session.agc.as_mut() {
+            agc.process(&mut samples);
         }
+        session.pcm_buffer.extend(samples);
 
         while session.pcm_buffer.len() >= CHUNK_SAMPLES {
             let chunk: Vec<f32> = session.pcm_buffer.drain(..CHUNK_SAMPLES).collect();
@@ -236,6 +295,20 @@ impl EngineState {
             ));
         }
 
+        // Drain the resampler's look-ahead into the tail first.
+        let mut tail = match session.resampler.take() {
+            Some(mut r) => r.flush(),
+            None => Vec::new(),
+        };
+        if let Some(mut d) = session.denoiser.take() {
+            tail = d.process(&tail);
+            tail.extend(d.flush());
+        }
+        if let Some(agc) = session.agc.as_mut() {
+            agc.process(&mut tail);
+        }
+        session.pcm_buffer.extend(tail);
+
         // Pad-and-process any sub-chunk tail (up to 559 ms).
         if !session.pcm_buffer.is_empty() {
             let mut tail = std::mem::take(&mut session.pcm_buffer);
@@ -285,8 +358,25 @@ pub fn has_session() -> bool {
     engine_lock().has_session()
 }
 
+/// [`loaded_variant`] without waiting for the engine lock. The outer
+/// `None` means the lock is held right now (e.g. by a thread that is
+/// panicking mid-inference) — for the crash reporter, which must never
+/// block.
+pub fn try_loaded_variant() -> Option<Option<Variant>> {
+    let Some(engine) = ENGINE.get() else {
+        return Some(None);
+    };
+    match engine.try_lock() {
+        Ok(state) => Some(state.loaded_variant()),
+        Err(TryLockError::Poisoned(p)) => Some(p.into_inner().loaded_variant()),
+        Err(TryLockError::WouldBlock) => None,
+    }
+}
+
 pub fn ensure_loaded(variant: Variant, dir: &Path) -> Result<(), String> {
-    engine_lock().ensure_loaded(variant, dir)
+    engine_lock().ensure_loaded(variant, dir)?;
+    crate::downloads::installed::mark_used(dir);
+    Ok(())
 }
 
 pub fn unload() {
@@ -297,11 +387,32 @@ pub fn start_session(id: String) -> Result<(), String> {
     engine_lock().start_session(id)
 }
 
+pub fn set_session_agc(session_id: &str, enabled: bool) -> Result<(), String> {
+    engine_lock().set_session_agc(session_id, enabled)
+}
+
+pub fn set_session_denoise(session_id: &str, enabled: bool) -> Result<(), String> {
+    engine_lock().set_session_denoise(session_id, enabled)
+}
+
 pub fn push_pcm_i16<F>(session_id: &str, pcm: &[i16], emit: F) -> Result<(), String>
 where
     F: FnMut(&str, &str, f32),
 {
-    engine_lock().push_pcm_i16(session_id, pcm, emit)
+    engine_lock().push_pcm_i16(session_id, pcm, SAMPLE_RATE, emit)
+}
+
+/// Same as [`push_pcm_i16`] for capture devices that don't run at 16 kHz.
+pub fn push_pcm_i16_at<F>(
+    session_id: &str,
+    pcm: &[i16],
+    input_rate: u32,
+    emit: F,
+) -> Result<(), String>
+where
+    F: FnMut(&str, &str, f32),
+{
+    engine_lock().push_pcm_i16(session_id, pcm, input_rate, emit)
 }
 
 pub fn end_session<F>(session_id: &str, emit: F) -> Result<String, String>
@@ -333,6 +444,9 @@ pub fn _test_force_session_active(active: bool) {
             started_at: Instant::now(),
             pcm_buffer: Vec::new(),
             samples_processed: 0,
+            resampler: None,
+            denoiser: None,
+            agc: None,
         });
     } else {
         engine.active = None;
@@ -39,7 +39,7 @@
 use std::path::PathBuf;
 
 use crate::paths;
-use crate::whisper::download::ModelDownloadConfig;
+use crate::whisper::download::{hugging_face_config, ModelDownloadConfig};
 
 /// Quantization variant. Renderer passes this as `"int8"` / `"fp32"`
 /// strings via Tauri commands; serde handles the conversion.
@@ -136,11 +136,11 @@ pub fn download_config_for(
     file: &ModelFile,
 ) -> Result<ModelDownloadConfig, String> {
     let dir = model_dir(variant)?;
-    Ok(ModelDownloadConfig {
-        url: format!("{}/{}", variant.base_url(), file.name),
-        output_path: dir.join(file.name),
-        expected_size: Some(file.size),
-    })
+    Ok(hugging_face_config(
+        &format!("{}/{}", variant.base_url(), file.name),
+        dir.join(file.name),
+        Some(file.size),
+    ))
 }
 
 /// All download configs for one variant in size-ascending order
@@ -0,0 +1,197 @@
+//! Decoding consumer audio formats to the 16 kHz mono the ASR expects.
+//!
+//! symphonia demuxes and decodes in-process: mp3, AAC / ALAC in mp4 /
+//! m4a, raw ADTS AAC, Vorbis in ogg, flac and wav. Opus has no decoder
+//! in symphonia 0.5, so `.opus` files (and Opus-in-ogg) fail at
+//! [`AudioDecoder::open`] with a readable error.
+//!
+//! Channels are averaged down to mono and the result goes through
+//! [`Resampler`], the same band-limited conversion the capture path
+//! uses, so an imported 44.1 kHz memo and a live 48 kHz mic reach the
+//! model with the same filtering.
+//!
+//! The caller opens the file; this module only sees a
+//! [`MediaSource`] and hands out i16 chunks.
+
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader};
+use symphonia::core::io::{MediaSource, MediaSourceStream};
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use super::resample::Resampler;
+
+pub const TARGET_SAMPLE_RATE: u32 = 16_000;
+
+/// The first audio track of an opened file, ready to decode.
+pub struct AudioDecoder {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    /// Seconds, when the container records the frame count. Raw ADTS
+    /// AAC and mp3 without a Xing header don't.
+    pub duration_sec: Option<f64>,
+}
+
+impl AudioDecoder {
+    /// Probe `source`; `extension` (without the dot) helps pick the
+    /// demuxer for formats without a magic number, like ADTS AAC.
+    pub fn open(source: Box<dyn MediaSource>, extension: Option<&str>) -> Result<Self, String> {
+        let mut hint = Hint::new();
+        if let Some(ext) = extension {
+            hint.with_extension(ext);
+        }
+        let stream = MediaSourceStream::new(source, Default::default());
+        let probed = symphonia::default::get_probe()
+            .format(
+                &hint,
+                stream,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .map_err(|e| format!("unrecognised audio format: {e}"))?;
+        let format = probed.format;
+        let track = format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or_else(|| "no audio track in file".to_string())?;
+        let params = &track.codec_params;
+        let duration_sec = match (params.n_frames, params.sample_rate) {
+            (Some(frames), Some(rate)) if rate > 0 => Some(frames as f64 / rate as f64),
+            _ => None,
+        };
+        let track_id = track.id;
+        let decoder = symphonia::default::get_codecs()
+            .make(params, &DecoderOptions::default())
+            .map_err(|e| format!("unsupported audio codec: {e}"))?;
+        Ok(Self {
+            format,
+            decoder,
+            track_id,
+            duration_sec,
+        })
+    }
+
+    /// Decode to the end, passing each chunk of 16 kHz mono samples to
+    /// `sink` with the seconds of input decoded so far. Returns the
+    /// number of samples produced.
+    ///
+    /// A damaged packet is skipped with a warning, like ffmpeg does, so
+    /// one bad frame in a two-hour recording doesn't lose the rest.
+    pub fn decode_16k_mono<F>(mut self, mut sink: F) -> Result<u64, String>
+    where
+        F: FnMut(&[i16], f64) -> Result<(), String>,
+    {
+        // Built from the first decoded buffer: HE-AAC reports half its
+        // real rate in the container.
+        let mut resampler: Option<Resampler> = None;
+        let mut buffer: Option<SampleBuffer<f32>> = None;
+        let mut frames: u64 = 0;
+        let mut written: u64 = 0;
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(e))
+                    if e.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    break
+                }
+                // A chained ogg stream changes parameters mid-file;
+                // what came before is kept.
+                Err(SymphoniaError::ResetRequired) => break,
+                Err(e) => return Err(format!("demux: {e}")),
+            };
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+            let audio = match self.decoder.decode(&packet) {
+                Ok(audio) => audio,
+                Err(SymphoniaError::DecodeError(e)) => {
+                    log::warn!("[audio_import] skipped a damaged packet: {e}");
+                    continue;
+                }
+                Err(e) => return Err(format!("decode: {e}")),
+            };
+            let spec = *audio.spec();
+            let channels = spec.channels.count().max(1);
+            if buffer
+                .as_ref()
+                .is_none_or(|b| b.capacity() < audio.capacity() * channels)
+            {
+                buffer = Some(SampleBuffer::new(audio.capacity() as u64, spec));
+            }
+            let Some(buffer) = buffer.as_mut() else {
+                continue;
+            };
+            buffer.copy_interleaved_ref(audio);
+            let mono: Vec<i16> = buffer
+                .samples()
+                .chunks_exact(channels)
+                .map(|frame| {
+                    let mean = frame.iter().sum::<f32>() / channels as f32;
+                    (mean * 32_768.0).round().clamp(-32_768.0, 32_767.0) as i16
+                })
+                .collect();
+            frames += mono.len() as u64;
+            if resampler.is_none() {
+                resampler = Some(Resampler::new(spec.rate, TARGET_SAMPLE_RATE)?);
+            }
+            let Some(resampler) = resampler.as_mut() else {
+                continue;
+            };
+            let out = resampler.process_i16(&mono);
+            written += out.len() as u64;
+            sink(&out, frames as f64 / resampler.from_rate() as f64)?;
+        }
+        let Some(mut resampler) = resampler else {
+            return Err("no audio could be decoded".to_string());
+        };
+        let tail = resampler.flush_i16();
+        written += tail.len() as u64;
+        sink(&tail, frames as f64 / resampler.from_rate() as f64)?;
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn decodes_stereo_wav_to_16k_mono() {
+        // One second of 48 kHz stereo: left at +0.5, right at -0.25.
+        let frame: Vec<u8> = [16_384i16, -8_192]
+            .iter()
+            .flat_map(|s| s.to_le_bytes())
+            .collect();
+        let pcm = frame.repeat(48_000);
+        let wav = crate::recording::wrap_pcm_as_wav(&pcm, 48_000, 2);
+
+        let decoder = AudioDecoder::open(Box::new(Cursor::new(wav)), Some("wav")).unwrap();
+        assert_eq!(decoder.duration_sec, Some(1.0));
+        let mut samples = Vec::new();
+        let mut last_sec = 0.0;
+        let count = decoder
+            .decode_16k_mono(|chunk, sec| {
+                samples.extend_from_slice(chunk);
+                last_sec = sec;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(count, 16_000);
+        assert_eq!(samples.len(), 16_000);
+        assert_eq!(last_sec, 1.0);
+        // Mean of the channels, away from the filter's edges.
+        assert!((samples[8_000] - 4_096).abs() < 64, "{}", samples[8_000]);
+    }
+
+    #[test]
+    fn rejects_what_is_not_audio() {
+        let junk = Cursor::new(b"definitely not audio".to_vec());
+        assert!(AudioDecoder::open(Box::new(junk), Some("mp3")).is_err());
+    }
+}
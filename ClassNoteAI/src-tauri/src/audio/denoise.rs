@@ -0,0 +1,280 @@
+//! Stationary-noise suppression for the capture path.
+//!
+//! Lecture halls have a lot of steady broadband noise — HVAC, projector
+//! fans, the laptop's own fan right next to the mic — and the ASR model
+//! treats it as part of the signal. This is an RNNoise-style opt-in
+//! stage in the classic form RNNoise itself falls back to when its
+//! network isn't available: a short-time Fourier transform, a per-bin
+//! noise floor tracked by minimum statistics, and a decision-directed
+//! Wiener gain with a floor so the residual stays smooth instead of
+//! turning into "musical noise".
+//!
+//! Frame layout is 32 ms (512 samples at 16 kHz) with 50 % overlap and a
+//! square-root Hann window on both analysis and synthesis, which
+//! overlap-adds back to unity — with every gain at 1.0 the output is the
+//! input, delayed by nothing (we compensate the 256-sample pipeline
+//! delay internally). The stage must run *after* resampling; the
+//! constants below assume 16 kHz.
+//!
+//! Streaming contract matches [`super::resample::Resampler`]: any chunk
+//! sizes in, output identical to one-shot processing, [`Denoiser::flush`]
+//! at end of stream, total output length == total input length.
+
+use realfft::num_complex::Complex;
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+use std::sync::Arc;
+
+const FRAME: usize = 512;
+const HOP: usize = FRAME / 2;
+const BINS: usize = FRAME / 2 + 1;
+
+/// Smoothing of the periodogram before minimum tracking.
+const POWER_SMOOTHING: f32 = 0.7;
+/// Per-frame ceiling on how fast the noise floor may rise (~1 dB/s),
+/// so a held vowel isn't learned as noise.
+const NOISE_RISE: f32 = 1.004;
+/// Minimum statistics under-estimate the mean; compensate.
+const OVERSUBTRACTION: f32 = 2.0;
+/// Decision-directed a-priori SNR smoothing (Ephraim–Malah).
+const DD_ALPHA: f32 = 0.98;
+/// -20 dB. Lower floors remove more noise but the residual starts to
+/// warble, which hurts ASR more than the noise did.
+const GAIN_FLOOR: f32 = 0.1;
+const EPS: f32 = 1e-10;
+
+#[derive(Clone, Copy)]
+struct BinState {
+    noise: f32,
+    smoothed: f32,
+    prev_gain: f32,
+    prev_post: f32,
+}
+
+pub struct Denoiser {
+    fft: Arc<dyn RealToComplex<f32>>,
+    ifft: Arc<dyn ComplexToReal<f32>>,
+    window: Vec<f32>,
+    pending: Vec<f32>,
+    overlap: Vec<f32>,
+    bins: Vec<BinState>,
+    started: bool,
+    /// Output samples still to drop to cancel the pipeline delay.
+    skip: usize,
+    input_len: u64,
+    output_len: u64,
+    frame_buf: Vec<f32>,
+    spectrum: Vec<Complex<f32>>,
+}
+
+impl Default for Denoiser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Denoiser {
+    pub fn new() -> Self {
+        let mut planner = RealFftPlanner::<f32>::new();
+        let fft = planner.plan_fft_forward(FRAME);
+        let ifft = planner.plan_fft_inverse(FRAME);
+        let window = (0..FRAME)
+            .map(|i| {
+                let hann = 0.5 - 0.5 * (2.0 * std::f32::consts::PI * i as f32 / FRAME as f32).cos();
+                hann.sqrt()
+            })
+            .collect();
+        let spectrum = fft.make_output_vec();
+        Self {
+            fft,
+            ifft,
+            window,
+            // Leading zeros put input sample 0 under two frames like
+            // every other sample.
+            pending: vec![0.0; FRAME - HOP],
+            overlap: vec![0.0; FRAME],
+            bins: vec![
+                BinState {
+                    noise: 0.0,
+                    smoothed: 0.0,
+                    prev_gain: 1.0,
+                    prev_post: 1.0,
+                };
+                BINS
+            ],
+            started: false,
+            skip: FRAME - HOP,
+            input_len: 0,
+            output_len: 0,
+            frame_buf: vec![0.0; FRAME],
+            spectrum,
+        }
+    }
+
+    fn update_gains(&mut self) {
+        for (c, bin) in self.spectrum.iter_mut().zip(self.bins.iter_mut()) {
+            let power = c.re * c.re + c.im * c.im;
+            if !self.started {
+                bin.smoothed = power;
+                bin.noise = power;
+            } else {
+                bin.smoothed = POWER_SMOOTHING * bin.smoothed + (1.0 - POWER_SMOOTHING) * power;
+                bin.noise = (bin.noise * NOISE_RISE).min(bin.smoothed);
+            }
+
+            let post = power / (bin.noise * OVERSUBTRACTION + EPS);
+            let prior = DD_ALPHA * bin.prev_gain * bin.prev_gain * bin.prev_post
+                + (1.0 - DD_ALPHA) * (post - 1.0).max(0.0);
+            let gain = (prior / (1.0 + prior)).max(GAIN_FLOOR);
+            bin.prev_gain = gain;
+            bin.prev_post = post;
+            *c *= gain;
+        }
+        self.started = true;
+    }
+
+    fn process_frame(&mut self, out: &mut Vec<f32>) {
+        for ((dst, &x), &w) in self
+            .frame_buf
+            .iter_mut()
+            .zip(&self.pending[..FRAME])
+            .zip(&self.window)
+        {
+            *dst = x * w;
+        }
+        // Both plans only fail on length mismatches, which the fixed
+        // FRAME sizing rules out.
+        let _ = self.fft.process(&mut self.frame_buf, &mut self.spectrum);
+        self.update_gains();
+        self.spectrum[0].im = 0.0;
+        self.spectrum[BINS - 1].im = 0.0;
+        let _ = self.ifft.process(&mut self.spectrum, &mut self.frame_buf);
+
+        let scale = 1.0 / FRAME as f32;
+        for ((acc, &y), &w) in self
+            .overlap
+            .iter_mut()
+            .zip(&self.frame_buf)
+            .zip(&self.window)
+        {
+            *acc += y * w * scale;
+        }
+        for &s in &self.overlap[..HOP] {
+            if self.skip > 0 {
+                self.skip -= 1;
+            } else {
+                out.push(s);
+                self.output_len += 1;
+            }
+        }
+        self.overlap.copy_within(HOP.., 0);
+        self.overlap[FRAME - HOP..].fill(0.0);
+        self.pending.drain(..HOP);
+    }
+
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        self.input_len += input.len() as u64;
+        self.pending.extend_from_slice(input);
+        let mut out = Vec::with_capacity(input.len() + HOP);
+        while self.pending.len() >= FRAME {
+            self.process_frame(&mut out);
+        }
+        out
+    }
+
+    /// End of stream: push silence through until every real input
+    /// sample has come out the other side.
+    pub fn flush(&mut self) -> Vec<f32> {
+        let mut out = Vec::new();
+        while self.output_len < self.input_len {
+            let padded = self.pending.len().max(FRAME);
+            self.pending.resize(padded, 0.0);
+            self.process_frame(&mut out);
+        }
+        let excess = (self.output_len - self.input_len) as usize;
+        out.truncate(out.len().saturating_sub(excess));
+        self.output_len = self.input_len;
+        out
+    }
+}
+
+/// One-shot convenience for whole 16 kHz buffers (the settings
+/// "before / after" preview).
+pub fn denoise_i16(samples: &[i16]) -> Vec<i16> {
+    let mut d = Denoiser::new();
+    let f: Vec<f32> = samples.iter().map(|&s| s as f32 / 32_768.0).collect();
+    let mut out = d.process(&f);
+    out.extend(d.flush());
+    out.iter()
+        .map(|&s| (s * 32_768.0).round().clamp(-32_768.0, 32_767.0) as i16)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+
+    fn rms(x: &[f32]) -> f32 {
+        (x.iter().map(|s| s * s).sum::<f32>() / x.len() as f32).sqrt()
+    }
+
+    fn one_shot(x: &[f32]) -> Vec<f32> {
+        let mut d = Denoiser::new();
+        let mut out = d.process(x);
+        out.extend(d.flush());
+        out
+    }
+
+    fn noise(len: usize, amp: f32, seed: u64) -> Vec<f32> {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        (0..len).map(|_| rng.gen_range(-amp..amp)).collect()
+    }
+
+    #[test]
+    fn preserves_length() {
+        for len in [0, 1, 255, 256, 512, 1_000, 16_000] {
+            assert_eq!(one_shot(&vec![0.01; len]).len(), len);
+        }
+    }
+
+    #[test]
+    fn steady_noise_is_attenuated() {
+        let x = noise(16_000 * 2, 0.05, 7);
+        let y = one_shot(&x);
+        let tail = 16_000..32_000;
+        let reduction_db = 20.0 * (rms(&y[tail.clone()]) / rms(&x[tail])).log10();
+        assert!(reduction_db < -8.0, "only {reduction_db} dB");
+    }
+
+    #[test]
+    fn tone_over_noise_survives() {
+        let mut x = noise(16_000 * 2, 0.01, 3);
+        for (i, s) in x.iter_mut().enumerate().skip(16_000) {
+            *s += 0.3 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / 16_000.0).sin();
+        }
+        let y = one_shot(&x);
+        let seg = 20_000..30_000;
+        let ratio_db = 20.0 * (rms(&y[seg.clone()]) / rms(&x[seg.clone()])).log10();
+        assert!(ratio_db.abs() < 1.5, "tone level changed by {ratio_db} dB");
+        // Delay compensation: output lines up with input sample-for-sample.
+        let diff: Vec<f32> = y[seg.clone()]
+            .iter()
+            .zip(&x[seg])
+            .map(|(a, b)| a - b)
+            .collect();
+        assert!(rms(&diff) < 0.02);
+    }
+
+    #[test]
+    fn streaming_in_odd_chunks_matches_one_shot() {
+        let x = noise(5_003, 0.1, 11);
+        let whole = one_shot(&x);
+        let mut d = Denoiser::new();
+        let mut streamed = Vec::new();
+        for chunk in x.chunks(97) {
+            streamed.extend(d.process(chunk));
+        }
+        streamed.extend(d.flush());
+        assert_eq!(streamed, whole);
+    }
+}
@@ -0,0 +1,257 @@
+//! Gain normalisation ahead of VAD / ASR.
+//!
+//! A lecturer pacing at the far end of a hall lands at -45 to -50 dBFS on
+//! a laptop mic. The energy VAD threshold sits above that, Silero's
+//! speech probability drops, and Nemotron starts dropping function words.
+//! Two tools, both operating on normalised f32 samples:
+//!
+//! - [`Agc`]: streaming automatic gain control for the live path. Tracks
+//!   a fast-attack / slow-release level estimate in dB, steers the gain
+//!   towards `target_dbfs`, and never boosts blocks under `gate_dbfs` so
+//!   room tone between sentences isn't pumped up into something the VAD
+//!   mistakes for speech. A per-block peak check keeps the output under
+//!   `ceiling` (no wrap-around when we convert back to i16).
+//! - [`measure_gated_loudness`] / [`normalize_i16`]: whole-buffer
+//!   normalisation for imports, using the BS.1770 / EBU R128 gating
+//!   scheme (400 ms blocks, -70 absolute gate, -10 relative gate). The
+//!   K-weighting pre-filter is omitted — for speech-band material the
+//!   difference is a fairly constant offset, and what we want is a
+//!   consistent level for the models, not a broadcast-compliant LUFS.
+
+/// Processing block for the AGC envelope. 10 ms at 16 kHz.
+const AGC_BLOCK: usize = 160;
+
+/// Gating block for whole-buffer loudness (400 ms at 16 kHz).
+const GATE_BLOCK: usize = 6_400;
+const ABSOLUTE_GATE_DB: f64 = -70.0;
+const RELATIVE_GATE_DB: f64 = -10.0;
+
+fn to_db(linear: f64) -> f64 {
+    if linear <= 1e-10 {
+        -200.0
+    } else {
+        20.0 * linear.log10()
+    }
+}
+
+fn from_db(db: f64) -> f64 {
+    10f64.powf(db / 20.0)
+}
+
+fn block_rms(block: &[f32]) -> f64 {
+    if block.is_empty() {
+        return 0.0;
+    }
+    let sum: f64 = block.iter().map(|&s| (s as f64) * (s as f64)).sum();
+    (sum / block.len() as f64).sqrt()
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AgcConfig {
+    /// Level the AGC steers speech towards.
+    pub target_dbfs: f64,
+    /// Upper bound on boost; beyond this we'd mostly be amplifying noise.
+    pub max_gain_db: f64,
+    /// Upper bound on attenuation for someone shouting into the mic.
+    pub max_cut_db: f64,
+    /// Blocks quieter than this don't move the level estimate.
+    pub gate_dbfs: f64,
+    pub attack_ms: f64,
+    pub release_ms: f64,
+    /// Output peak ceiling, linear.
+    pub ceiling: f32,
+    pub sample_rate: u32,
+}
+
+impl Default for AgcConfig {
+    fn default() -> Self {
+        Self {
+            target_dbfs: -20.0,
+            max_gain_db: 24.0,
+            max_cut_db: 12.0,
+            gate_dbfs: -55.0,
+            attack_ms: 50.0,
+            release_ms: 800.0,
+            ceiling: 0.95,
+            sample_rate: 16_000,
+        }
+    }
+}
+
+pub struct Agc {
+    config: AgcConfig,
+    /// Smoothed speech level estimate, dB. `None` until the first
+    /// block above the gate, so the first words aren't processed with
+    /// a gain derived from silence.
+    level_db: Option<f64>,
+    gain_db: f64,
+    attack_coef: f64,
+    release_coef: f64,
+}
+
+impl Agc {
+    pub fn new(config: AgcConfig) -> Self {
+        let block_ms = AGC_BLOCK as f64 * 1000.0 / config.sample_rate.max(1) as f64;
+        let coef = |tau_ms: f64| 1.0 - (-block_ms / tau_ms.max(1e-3)).exp();
+        Self {
+            attack_coef: coef(config.attack_ms),
+            release_coef: coef(config.release_ms),
+            config,
+            level_db: None,
+            gain_db: 0.0,
+        }
+    }
+
+    pub fn current_gain_db(&self) -> f64 {
+        self.gain_db
+    }
+
+    /// Apply gain in place. Output length always equals input length;
+    /// there is no look-ahead, so the live path adds no latency.
+    pub fn process(&mut self, samples: &mut [f32]) {
+        for block in samples.chunks_mut(AGC_BLOCK) {
+            let level = to_db(block_rms(block));
+            if level > self.config.gate_dbfs {
+                let est = match self.level_db {
+                    None => level,
+                    Some(prev) => {
+                        let coef = if level > prev {
+                            self.attack_coef
+                        } else {
+                            self.release_coef
+                        };
+                        prev + (level - prev) * coef
+                    }
+                };
+                self.level_db = Some(est);
+                self.gain_db = (self.config.target_dbfs - est)
+                    .clamp(-self.config.max_cut_db, self.config.max_gain_db);
+            }
+
+            let mut gain = from_db(self.gain_db) as f32;
+            let peak = block.iter().fold(0.0f32, |m, &s| m.max(s.abs()));
+            if peak * gain > self.config.ceiling {
+                gain = self.config.ceiling / peak;
+            }
+            for s in block.iter_mut() {
+                *s *= gain;
+            }
+        }
+    }
+}
+
+/// Gated loudness (dB relative to full scale) of a whole buffer, or
+/// `None` when every block is below the absolute gate (silence).
+pub fn measure_gated_loudness(samples: &[f32]) -> Option<f64> {
+    let powers: Vec<f64> = samples
+        .chunks(GATE_BLOCK)
+        .map(|b| block_rms(b).powi(2))
+        .filter(|p| to_db(p.sqrt()) > ABSOLUTE_GATE_DB)
+        .collect();
+    if powers.is_empty() {
+        return None;
+    }
+    let mean = powers.iter().sum::<f64>() / powers.len() as f64;
+    let relative_gate = to_db(mean.sqrt()) + RELATIVE_GATE_DB;
+    let gated: Vec<f64> = powers
+        .into_iter()
+        .filter(|p| to_db(p.sqrt()) > relative_gate)
+        .collect();
+    if gated.is_empty() {
+        return None;
+    }
+    Some(to_db(
+        (gated.iter().sum::<f64>() / gated.len() as f64).sqrt(),
+    ))
+}
+
+/// Scale a whole buffer so its gated loudness hits `target_dbfs`,
+/// capped so the loudest sample stays under -1 dBFS and the boost
+/// never exceeds `max_gain_db`. Silence is returned unchanged.
+pub fn normalize_i16(samples: &[i16], target_dbfs: f64, max_gain_db: f64) -> Vec<i16> {
+    let f: Vec<f32> = samples.iter().map(|&s| s as f32 / 32_768.0).collect();
+    let Some(loudness) = measure_gated_loudness(&f) else {
+        return samples.to_vec();
+    };
+    let peak = f.iter().fold(0.0f32, |m, &s| m.max(s.abs())) as f64;
+    let mut gain_db = (target_dbfs - loudness).min(max_gain_db);
+    if peak > 0.0 {
+        gain_db = gain_db.min(-1.0 - to_db(peak));
+    }
+    let gain = from_db(gain_db) as f32;
+    f.iter()
+        .map(|&s| (s * gain * 32_768.0).round().clamp(-32_768.0, 32_767.0) as i16)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(db: f64, len: usize) -> Vec<f32> {
+        // Sine RMS = amp / sqrt(2).
+        let amp = from_db(db) * std::f64::consts::SQRT_2;
+        (0..len)
+            .map(|i| {
+                (amp * (2.0 * std::f64::consts::PI * 300.0 * i as f64 / 16_000.0).sin()) as f32
+            })
+            .collect()
+    }
+
+    #[test]
+    fn quiet_speech_is_brought_up_towards_target() {
+        let mut agc = Agc::new(AgcConfig::default());
+        let mut x = tone(-40.0, 16_000 * 3);
+        agc.process(&mut x);
+        let tail = &x[16_000 * 2..];
+        let out_db = to_db(block_rms(tail));
+        assert!((out_db - -20.0).abs() < 1.0, "got {out_db} dBFS");
+    }
+
+    #[test]
+    fn boost_is_capped_at_max_gain() {
+        let mut agc = Agc::new(AgcConfig::default());
+        let mut x = tone(-50.0, 16_000 * 2);
+        agc.process(&mut x);
+        assert!((agc.current_gain_db() - 24.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn room_tone_below_gate_is_not_amplified() {
+        let mut agc = Agc::new(AgcConfig::default());
+        let mut x = tone(-65.0, 16_000);
+        let before = block_rms(&x);
+        agc.process(&mut x);
+        assert_eq!(agc.current_gain_db(), 0.0);
+        assert!((block_rms(&x) - before).abs() < 1e-9);
+    }
+
+    #[test]
+    fn loud_input_never_exceeds_ceiling() {
+        let mut agc = Agc::new(AgcConfig::default());
+        let mut x = tone(-1.0, 16_000);
+        agc.process(&mut x);
+        assert!(x.iter().all(|s| s.abs() <= 0.95 + 1e-6));
+    }
+
+    #[test]
+    fn gated_loudness_ignores_long_silences() {
+        let mut x = tone(-30.0, 16_000 * 2);
+        x.extend(vec![0.0; 16_000 * 20]);
+        let l = measure_gated_loudness(&x).unwrap();
+        assert!((l - -30.0).abs() < 0.5, "got {l}");
+        assert_eq!(measure_gated_loudness(&[0.0; 16_000]), None);
+    }
+
+    #[test]
+    fn normalize_hits_target_without_clipping() {
+        let x: Vec<i16> = tone(-40.0, 16_000 * 2)
+            .iter()
+            .map(|&s| (s * 32_768.0) as i16)
+            .collect();
+        let y = normalize_i16(&x, -23.0, 30.0);
+        let f: Vec<f32> = y.iter().map(|&s| s as f32 / 32_768.0).collect();
+        let l = measure_gated_loudness(&f).unwrap();
+        assert!((l - -23.0).abs() < 0.5, "got {l}");
+    }
+}
@@ -0,0 +1,19 @@
+//! Audio DSP shared by the capture and import paths.
+//!
+//! Everything here is pure sample-in / sample-out with no Tauri or
+//! filesystem dependency, so each stage is unit-testable on synthetic
+//! signals. Callers (the ASR push path, VAD, recording) own the I/O.
+//!
+//!   * [`decode`] — symphonia decoding of imported mp3 / m4a / ogg /
+//!     flac / wav to 16 kHz mono, through [`resample`].
+//!   * [`resample`] — band-limited polyphase sample-rate conversion to
+//!     the 16 kHz the ASR and VAD models expect.
+//!   * [`loudness`] — streaming AGC for the live path and gated
+//!     whole-buffer normalisation for imports / VAD.
+//!   * [`denoise`] — STFT Wiener-gain suppression of steady background
+//!     noise (HVAC, projector fans).
+
+pub mod decode;
+pub mod denoise;
+pub mod loudness;
+pub mod resample;
@@ -0,0 +1,230 @@
+//! Crash reports for Rust panics.
+//!
+//! [`install_panic_hook`] (called first thing in `main`) still routes
+//! each panic into the app log, and additionally writes a JSON
+//! [`CrashReport`] to `{app_data_dir}/crash_reports/`: message,
+//! location, thread, backtrace, app version and which models were
+//! loaded. On the next launch the frontend asks for
+//! [`last_report`] and offers to show / file it once; dismissing it
+//! records its id so it isn't offered again.
+//!
+//! Everything here runs inside a panic hook, possibly while the
+//! panicking thread holds a model lock, so nothing may block: model
+//! state is probed with `try_lock` and shows up as "in use" when busy.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::paths;
+
+/// Reports kept on disk; older ones are removed when a new one is written.
+const KEEP_REPORTS: usize = 10;
+/// Holds the id of the last report the user dismissed.
+const DISMISSED_FILE: &str = "dismissed";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrashReport {
+    /// File stem, e.g. `crash-20261015-093012-417`.
+    pub id: String,
+    /// RFC 3339, local time.
+    pub created_at: String,
+    pub app_version: String,
+    /// `std::env::consts::{OS, ARCH}`, e.g. `macos-aarch64`.
+    pub platform: String,
+    pub thread: String,
+    pub message: String,
+    /// `file:line:column` of the panic, when known.
+    pub location: Option<String>,
+    pub backtrace: String,
+    /// e.g. `asr:int8`, `embedding`, `translation-sidecar`.
+    pub loaded_models: Vec<String>,
+}
+
+/// `{app_data_dir}/crash_reports/`.
+pub fn crash_dir() -> Result<PathBuf, String> {
+    paths::get_crash_reports_dir()
+}
+
+/// Replace the default panic hook. Keeps logging each panic (so it
+/// shows up in `classnoteai.log` next to what led to it) and writes a
+/// crash report.
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        let payload = info.payload();
+        let msg = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("<non-string panic payload>");
+        let location = info
+            .location()
+            .map(|loc| format!("{}:{}:{}", loc.file(), loc.line(), loc.column()));
+        match &location {
+            Some(loc) => log::error!("PANIC at {} — {}", loc, msg),
+            None => log::error!("PANIC (no location) — {}", msg),
+        }
+
+        let now = chrono::Local::now();
+        let report = CrashReport {
+            id: format!("crash-{}", now.format("%Y%m%d-%H%M%S-%3f")),
+            created_at: now.to_rfc3339(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            platform: format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH),
+            thread: std::thread::current()
+                .name()
+                .unwrap_or("<unnamed>")
+                .to_string(),
+            message: msg.to_string(),
+            location,
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+            loaded_models: loaded_models(),
+        };
+        match crash_dir().and_then(|dir| write_report(&dir, &report)) {
+            Ok(path) => log::error!("crash report written to {}", path.display()),
+            Err(e) => log::error!("could not write crash report: {}", e),
+        }
+    }));
+}
+
+/// What was loaded at the time of the panic, without blocking.
+fn loaded_models() -> Vec<String> {
+    let mut out = Vec::new();
+    match crate::asr::parakeet_engine::try_loaded_variant() {
+        Some(Some(variant)) => out.push(format!("asr:{}", variant.label())),
+        Some(None) => {}
+        None => out.push("asr (in use)".to_string()),
+    }
+    match crate::EMBEDDING_SERVICE.try_lock() {
+        Ok(service) if service.is_some() => out.push("embedding".to_string()),
+        Ok(_) => {}
+        Err(_) => out.push("embedding (in use)".to_string()),
+    }
+    match crate::translation::gemma_sidecar::try_has_child() {
+        Some(true) => out.push("translation-sidecar".to_string()),
+        Some(false) => {}
+        None => out.push("translation-sidecar (in use)".to_string()),
+    }
+    out
+}
+
+/// Report files in `dir`, oldest first (ids sort chronologically).
+fn report_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("crash-") && n.ends_with(".json"))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn write_report(dir: &Path, report: &CrashReport) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("create {}: {}", dir.display(), e))?;
+    let path = dir.join(format!("{}.json", report.id));
+    let json = serde_json::to_vec_pretty(report).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| format!("write {}: {}", path.display(), e))?;
+
+    let files = report_files(dir);
+    for old in &files[..files.len().saturating_sub(KEEP_REPORTS)] {
+        let _ = fs::remove_file(old);
+    }
+    Ok(path)
+}
+
+/// The newest report, unless the user already dismissed it.
+pub fn last_report(dir: &Path) -> Result<Option<CrashReport>, String> {
+    let Some(path) = report_files(dir).pop() else {
+        return Ok(None);
+    };
+    let bytes = fs::read(&path).map_err(|e| format!("read {}: {}", path.display(), e))?;
+    let report: CrashReport =
+        serde_json::from_slice(&bytes).map_err(|e| format!("parse {}: {}", path.display(), e))?;
+    let dismissed = fs::read_to_string(dir.join(DISMISSED_FILE)).unwrap_or_default();
+    if dismissed.trim() == report.id {
+        return Ok(None);
+    }
+    Ok(Some(report))
+}
+
+/// Reports newer than `after` (every report when `None`), oldest first.
+/// Unreadable files are skipped.
+pub fn reports_after(dir: &Path, after: Option<&str>) -> Vec<CrashReport> {
+    report_files(dir)
+        .into_iter()
+        .filter_map(|path| serde_json::from_slice::<CrashReport>(&fs::read(path).ok()?).ok())
+        .filter(|report| after.is_none_or(|id| report.id.as_str() > id))
+        .collect()
+}
+
+/// Stop offering report `id` (the file itself is kept).
+pub fn dismiss(dir: &Path, id: &str) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("create {}: {}", dir.display(), e))?;
+    fs::write(dir.join(DISMISSED_FILE), id).map_err(|e| format!("dismiss crash report: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(id: &str) -> CrashReport {
+        CrashReport {
+            id: id.to_string(),
+            created_at: "2026-10-15T09:30:12+08:00".to_string(),
+            app_version: "0.7.1".to_string(),
+            platform: "macos-aarch64".to_string(),
+            thread: "main".to_string(),
+            message: "index out of bounds".to_string(),
+            location: Some("src/lib.rs:1:1".to_string()),
+            backtrace: String::new(),
+            loaded_models: vec!["asr:int8".to_string()],
+        }
+    }
+
+    #[test]
+    fn newest_report_is_offered_until_dismissed() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(last_report(dir.path()).unwrap(), None);
+
+        write_report(dir.path(), &report("crash-20261014-080000-000")).unwrap();
+        write_report(dir.path(), &report("crash-20261015-093012-417")).unwrap();
+        let last = last_report(dir.path()).unwrap().unwrap();
+        assert_eq!(last, report("crash-20261015-093012-417"));
+
+        dismiss(dir.path(), &last.id).unwrap();
+        assert_eq!(last_report(dir.path()).unwrap(), None);
+        assert_eq!(
+            reports_after(dir.path(), Some("crash-20261014-080000-000")),
+            vec![report("crash-20261015-093012-417")]
+        );
+
+        write_report(dir.path(), &report("crash-20261016-000000-000")).unwrap();
+        assert_eq!(
+            last_report(dir.path()).unwrap().map(|r| r.id),
+            Some("crash-20261016-000000-000".to_string())
+        );
+    }
+
+    #[test]
+    fn old_reports_are_pruned() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..KEEP_REPORTS + 3 {
+            write_report(
+                dir.path(),
+                &report(&format!("crash-20261015-0000{:02}-000", i)),
+            )
+            .unwrap();
+        }
+        let files = report_files(dir.path());
+        assert_eq!(files.len(), KEEP_REPORTS);
+        assert!(files[0].ends_with("crash-20261015-000003-000.json"));
+    }
+}
@@ -0,0 +1,300 @@
+//! `run_diagnostics`: the state of this install in one JSON payload, so
+//! the support flow can ask a user to paste a single blob instead of
+//! walking them through Settings screen by screen.
+//!
+//! Every section degrades on its own — an unreachable endpoint or a
+//! database that won't open is reported inside the payload rather than
+//! failing the whole command. Audio devices are enumerated by the
+//! webview (capture runs there) and added by the frontend.
+
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::{paths, setup, storage};
+
+/// Settings key the sync outbox stamps when the server accepts an
+/// action (under `default_user`: the outbox isn't per user).
+pub const LAST_SYNC_SETTING: &str = "sync.last_success_at";
+
+/// Remote services the app depends on. A HEAD answering with any status
+/// counts as reachable; the status itself is reported.
+const ENDPOINTS: &[(&str, &str)] = &[
+    (
+        "updates",
+        "https://sklonely.github.io/ClassNoteAI/updater/stable/latest.json",
+    ),
+    ("models", "https://huggingface.co"),
+];
+
+const ENDPOINT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsReport {
+    pub generated_at: String,
+    pub app_version: String,
+    pub build_variant: String,
+    pub platform: String,
+    pub database: DatabaseHealth,
+    pub models: Vec<ModelFileInfo>,
+    pub disk: DiskHealth,
+    pub endpoints: Vec<EndpointHealth>,
+    pub sync: SyncHealth,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DatabaseHealth {
+    pub ok: bool,
+    pub size_bytes: Option<u64>,
+    /// `PRAGMA quick_check` output; `["ok"]` when healthy.
+    pub quick_check: Vec<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelFileInfo {
+    /// Relative to the models directory, `/`-separated.
+    pub path: String,
+    pub size_bytes: u64,
+    /// Lower-case hex SHA-256; `None` when hashing was skipped or failed.
+    pub sha256: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskHealth {
+    pub free_bytes: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointHealth {
+    pub name: String,
+    pub url: String,
+    pub reachable: bool,
+    pub status: Option<u16>,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncHealth {
+    /// RFC 3339 time of the offline queue's last successful flush.
+    pub last_success_at: Option<String>,
+    pub pending_actions: usize,
+    pub failed_actions: usize,
+}
+
+/// Build the report. `hash_models` can be turned off for a quick run —
+/// hashing reads every model file (a few GB with the larger models).
+pub async fn run(user_id: &str, hash_models: bool) -> DiagnosticsReport {
+    let (database, sync) = database_and_sync(user_id).await;
+
+    let models = match paths::get_models_dir() {
+        Ok(dir) => tokio::task::spawn_blocking(move || model_files(&dir, hash_models))
+            .await
+            .unwrap_or_default(),
+        Err(_) => Vec::new(),
+    };
+
+    let disk = tokio::task::spawn_blocking(|| {
+        match paths::get_app_data_dir()
+            .and_then(|dir| setup::requirements::available_disk_bytes(&dir))
+        {
+            Ok(free) => DiskHealth {
+                free_bytes: Some(free),
+                error: None,
+            },
+            Err(e) => DiskHealth {
+                free_bytes: None,
+                error: Some(e),
+            },
+        }
+    })
+    .await
+    .unwrap_or_else(|e| DiskHealth {
+        free_bytes: None,
+        error: Some(format!("disk check task join error: {e}")),
+    });
+
+    let endpoints = futures_util::future::join_all(
+        ENDPOINTS
+            .iter()
+            .map(|(name, url)| check_endpoint(name, url)),
+    )
+    .await;
+
+    DiagnosticsReport {
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        build_variant: crate::gpu::get_build_variant().to_string(),
+        platform: format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH),
+        database,
+        models,
+        disk,
+        endpoints,
+        sync,
+    }
+}
+
+async fn database_and_sync(user_id: &str) -> (DatabaseHealth, SyncHealth) {
+    let size_bytes = paths::get_database_path()
+        .ok()
+        .and_then(|p| std::fs::metadata(p).ok())
+        .map(|m| m.len());
+    let mut database = DatabaseHealth {
+        ok: false,
+        size_bytes,
+        quick_check: Vec::new(),
+        error: None,
+    };
+    let mut sync = SyncHealth {
+        last_success_at: None,
+        pending_actions: 0,
+        failed_actions: 0,
+    };
+
+    let manager = match storage::get_db_manager().await {
+        Ok(manager) => manager,
+        Err(e) => {
+            database.error = Some(format!("數據庫未初始化: {}", e));
+            return (database, sync);
+        }
+    };
+    let db = match manager.get_db() {
+        Ok(db) => db,
+        Err(e) => {
+            database.error = Some(format!("數據庫連接失敗: {}", e));
+            return (database, sync);
+        }
+    };
+
+    match db.quick_check() {
+        Ok(rows) => {
+            database.ok = rows.len() == 1 && rows[0] == "ok";
+            database.quick_check = rows;
+        }
+        Err(e) => database.error = Some(format!("quick_check: {}", e)),
+    }
+
+    // Builds before the sync outbox moved to the backend stamped it
+    // per user.
+    sync.last_success_at = ["default_user", user_id]
+        .iter()
+        .find_map(|user| db.get_setting(LAST_SYNC_SETTING, user).ok().flatten());
+    if let Ok(actions) = db.list_pending_actions() {
+        for (_, _, _, status, _) in &actions {
+            match status.as_str() {
+                "failed" => sync.failed_actions += 1,
+                _ => sync.pending_actions += 1,
+            }
+        }
+    }
+    (database, sync)
+}
+
+/// Every file under `dir`, sorted by path, with its size and (when
+/// `hash`) SHA-256.
+pub fn model_files(dir: &Path, hash: bool) -> Vec<ModelFileInfo> {
+    let mut files: Vec<ModelFileInfo> = walkdir::WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| {
+            let rel = e.path().strip_prefix(dir).unwrap_or(e.path());
+            ModelFileInfo {
+                path: rel
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/"),
+                size_bytes: e.metadata().map(|m| m.len()).unwrap_or(0),
+                sha256: if hash {
+                    sha256_file(e.path()).ok()
+                } else {
+                    None
+                },
+            }
+        })
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    files
+}
+
+pub(crate) fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+async fn check_endpoint(name: &str, url: &str) -> EndpointHealth {
+    let mut health = EndpointHealth {
+        name: name.to_string(),
+        url: url.to_string(),
+        reachable: false,
+        status: None,
+        latency_ms: None,
+        error: None,
+    };
+    let client = match reqwest::Client::builder().timeout(ENDPOINT_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            health.error = Some(e.to_string());
+            return health;
+        }
+    };
+    let started = Instant::now();
+    match client.head(url).send().await {
+        Ok(resp) => {
+            health.reachable = true;
+            health.status = Some(resp.status().as_u16());
+            health.latency_ms = Some(started.elapsed().as_millis() as u64);
+        }
+        Err(e) => health.error = Some(e.to_string()),
+    }
+    health
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn model_files_lists_sizes_and_hashes() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("embedding/bge")).unwrap();
+        std::fs::write(dir.path().join("embedding/bge/config.json"), "abc").unwrap();
+        std::fs::write(dir.path().join("a.bin"), "").unwrap();
+
+        let files = model_files(dir.path(), true);
+        assert_eq!(
+            files,
+            vec![
+                ModelFileInfo {
+                    path: "a.bin".into(),
+                    size_bytes: 0,
+                    sha256: Some(
+                        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855".into()
+                    ),
+                },
+                ModelFileInfo {
+                    path: "embedding/bge/config.json".into(),
+                    size_bytes: 3,
+                    sha256: Some(
+                        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad".into()
+                    ),
+                },
+            ]
+        );
+        assert!(model_files(dir.path(), false)
+            .iter()
+            .all(|f| f.sha256.is_none()));
+    }
+}
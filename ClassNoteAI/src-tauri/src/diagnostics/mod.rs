@@ -1,3 +1,5 @@
+pub mod health;
+
 use std::fs::{self, File};
 use std::io::Write;
 use std::path::{Path, PathBuf};
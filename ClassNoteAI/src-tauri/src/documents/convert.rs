@@ -0,0 +1,1081 @@
+//! Office / iWork documents → PDF.
+//!
+//! Layered like before: Keynote / Pages / Microsoft Office via
+//! AppleScript on macOS, Office over COM on Windows, LibreOffice
+//! everywhere as the fallback. A conversion can take 30 s or more (the
+//! app has to launch and export), so it runs on the blocking pool and
+//! reports each step as a [`EVENT`] event:
+//!
+//! `launching` (with the app name) → `exporting` → `validating` →
+//! `done` / `failed` / `cancelled`.
+//!
+//! Only one conversion runs at a time — the native apps are single
+//! instances — and AppleScript runs that fail transiently (app still
+//! launching, AppleEvent timeout) are retried. Several files should go
+//! through `queue`, which converts them in order.
+//!
+//! Output lands in `{app_data}/documents/{content hash}.pdf`, so the
+//! same file imported twice is converted once.
+//! `cleanup_converted_documents` removes PDFs no lecture uses any more.
+//!
+//! Callers may pass their own `jobId` to `convert_to_pdf` so they can
+//! call `cancel_conversion` while it runs. The job id doubles as its
+//! id in the [`crate::tasks`] registry, so `cancel_task` works too.
+//! Cancelling kills the
+//! converter process (osascript / PowerShell / soffice) and stops
+//! waiting for output; an app that was already driven over AppleScript
+//! or COM may be left open.
+
+use crate::tasks::{Task, TaskKind};
+use crate::utils::command::{find_on_path, no_window};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+pub const EVENT: &str = "conversion-progress";
+
+/// Error string of a cancelled conversion, so fallbacks can tell it
+/// apart from "this converter failed, try the next one".
+pub const CANCELLED: &str = "Conversion cancelled";
+
+const POLL: Duration = Duration::from_millis(100);
+
+/// Tries per AppleScript converter when it fails transiently.
+const APPLESCRIPT_ATTEMPTS: u32 = 3;
+const APPLESCRIPT_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Held for the whole of a conversion. Keynote, Pages and Office are
+/// single instances; two exports driving one at once fail each other.
+static CONVERTING: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConversionStage {
+    /// Waiting behind other files in the conversion queue.
+    Queued,
+    Launching,
+    Exporting,
+    Validating,
+    /// A transient AppleScript failure; trying the same app again.
+    Retrying,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversionProgress {
+    pub job_id: String,
+    pub file_path: String,
+    pub stage: ConversionStage,
+    /// Queue position for `queued`, app name for `launching`, app and
+    /// attempt for `retrying`, output path for `done`, error for `failed`.
+    pub message: Option<String>,
+}
+
+/// A running conversion: where its progress goes, and its [`Task`]
+/// (which holds the cancel flag).
+pub struct ConversionJob {
+    file_path: String,
+    app: AppHandle,
+    task: Task,
+}
+
+impl ConversionJob {
+    pub fn register(app: AppHandle, id: String, file_path: String) -> Result<Self, String> {
+        let label = Path::new(&file_path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| file_path.clone());
+        let task = Task::start_with_id(&app, id, TaskKind::Conversion, label)?.cancellable();
+        Ok(Self {
+            file_path,
+            app,
+            task,
+        })
+    }
+
+    pub fn id(&self) -> &str {
+        self.task.id()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.task.is_cancelled()
+    }
+
+    fn cancel_flag(&self) -> &AtomicBool {
+        self.task.cancel_flag()
+    }
+
+    pub fn progress(&self, stage: ConversionStage, message: Option<String>) {
+        match stage {
+            ConversionStage::Queued => self.task.set_queued(message.clone()),
+            ConversionStage::Done | ConversionStage::Failed | ConversionStage::Cancelled => {}
+            _ => self.task.set_detail(message.clone()),
+        }
+        let _ = self.app.emit(
+            EVENT,
+            ConversionProgress {
+                job_id: self.id().to_string(),
+                file_path: self.file_path.clone(),
+                stage,
+                message,
+            },
+        );
+    }
+
+    /// Run a converter: `launching` before spawning, `exporting` once
+    /// it's up.
+    fn run(&self, cmd: &mut Command, what: &str) -> Result<Output, String> {
+        self.progress(ConversionStage::Launching, Some(what.to_string()));
+        run_cancellable(cmd, self.cancel_flag(), what, || {
+            self.progress(ConversionStage::Exporting, None)
+        })
+    }
+
+    /// [`Self::run`] for an AppleScript, retrying errors that
+    /// [`is_transient_applescript_error`] says will likely go away.
+    #[cfg(target_os = "macos")]
+    fn run_osascript(&self, script: &str, what: &str) -> Result<Output, String> {
+        let mut attempt = 1;
+        loop {
+            let output = self.run(no_window("osascript").arg("-e").arg(script), what)?;
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if output.status.success()
+                || attempt >= APPLESCRIPT_ATTEMPTS
+                || !is_transient_applescript_error(&stderr)
+            {
+                return Ok(output);
+            }
+            println!("⚠ {} busy ({}), retrying", what, stderr.trim());
+            attempt += 1;
+            self.progress(
+                ConversionStage::Retrying,
+                Some(format!(
+                    "{} (attempt {}/{})",
+                    what, attempt, APPLESCRIPT_ATTEMPTS
+                )),
+            );
+            sleep_cancellable(APPLESCRIPT_RETRY_DELAY * (attempt - 1), self.cancel_flag())?;
+        }
+    }
+
+    fn wait_and_validate(&self, path: &Path) -> Result<(), String> {
+        wait_for_file(path, self.cancel_flag())?;
+        self.progress(ConversionStage::Validating, None);
+        validate_pdf(path)
+    }
+}
+
+/// AppleEvent errors from an app that is still launching, busy with a
+/// modal dialog, or was quit mid-script — worth another try, unlike a
+/// file the app can't open. osascript ends the message with `(-1712)`.
+pub fn is_transient_applescript_error(stderr: &str) -> bool {
+    // -1712 timed out, -600 not running, -609 connection invalid,
+    // -903 no user interaction allowed, -10810 launch failed.
+    ["(-1712)", "(-600)", "(-609)", "(-903)", "(-10810)"]
+        .iter()
+        .any(|code| stderr.contains(code))
+}
+
+fn sleep_cancellable(duration: Duration, cancel: &AtomicBool) -> Result<(), String> {
+    let deadline = std::time::Instant::now() + duration;
+    while std::time::Instant::now() < deadline {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(CANCELLED.to_string());
+        }
+        std::thread::sleep(POLL);
+    }
+    Ok(())
+}
+
+fn drain(mut pipe: impl Read + Send + 'static) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        let _ = pipe.read_to_end(&mut buf);
+        buf
+    })
+}
+
+/// `Command::output()` that gives up — and kills the child — as soon as
+/// `cancel` is set. `on_spawn` runs once the process has started.
+pub fn run_cancellable(
+    cmd: &mut Command,
+    cancel: &AtomicBool,
+    what: &str,
+    on_spawn: impl FnOnce(),
+) -> Result<Output, String> {
+    if cancel.load(Ordering::Relaxed) {
+        return Err(CANCELLED.to_string());
+    }
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to execute {}: {}", what, e))?;
+    on_spawn();
+    let stdout = child.stdout.take().map(drain);
+    let stderr = child.stderr.take().map(drain);
+    let status = loop {
+        if cancel.load(Ordering::Relaxed) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(CANCELLED.to_string());
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) => std::thread::sleep(POLL),
+            Err(e) => return Err(format!("Failed to wait for {}: {}", what, e)),
+        }
+    };
+    let collect =
+        |h: Option<JoinHandle<Vec<u8>>>| h.and_then(|h| h.join().ok()).unwrap_or_default();
+    Ok(Output {
+        status,
+        stdout: collect(stdout),
+        stderr: collect(stderr),
+    })
+}
+
+/// Keep going with the next converter unless the user cancelled.
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+fn fall_through(result: Result<String, String>, what: &str) -> Result<Option<String>, String> {
+    match result {
+        Ok(path) => Ok(Some(path)),
+        Err(e) if e == CANCELLED => Err(e),
+        Err(e) => {
+            println!("⚠ {} conversion failed: {}", what, e);
+            Ok(None)
+        }
+    }
+}
+
+/// Convert the Office / iWork file at `file_path` to a PDF under
+/// `{app_data}/documents/` and return its path. Progress is emitted as
+/// [`EVENT`] tagged with `job_id` (generated when omitted).
+#[tauri::command]
+pub async fn convert_to_pdf(
+    app: AppHandle,
+    file_path: String,
+    job_id: Option<String>,
+) -> Result<String, String> {
+    let id = job_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let job = ConversionJob::register(app, id, file_path.clone())?;
+    tokio::task::spawn_blocking(move || convert_job(&job, &file_path))
+        .await
+        .map_err(|e| format!("convert task join error: {e}"))?
+}
+
+/// Cancel a running conversion. Returns false if `job_id` isn't running.
+#[tauri::command]
+pub fn cancel_conversion(job_id: String) -> Result<bool, String> {
+    crate::tasks::cancel(&job_id)
+}
+
+/// [`convert_blocking`] plus the terminal progress event. Waits for any
+/// other conversion to finish first.
+pub fn convert_job(job: &ConversionJob, file_path: &str) -> Result<String, String> {
+    let result = {
+        let _busy = CONVERTING.lock().unwrap_or_else(|e| e.into_inner());
+        if job.is_cancelled() {
+            Err(CANCELLED.to_string())
+        } else {
+            convert_blocking(job, file_path)
+        }
+    };
+    match &result {
+        Ok(path) => job.progress(ConversionStage::Done, Some(path.clone())),
+        Err(e) if e == CANCELLED => job.progress(ConversionStage::Cancelled, None),
+        Err(e) => job.progress(ConversionStage::Failed, Some(e.clone())),
+    }
+    job.task.finish(&result);
+    result
+}
+
+fn convert_blocking(job: &ConversionJob, file_path: &str) -> Result<String, String> {
+    use std::fs;
+
+    let input_path = Path::new(file_path);
+    if !input_path.exists() {
+        return Err(format!("File not found: {}", file_path));
+    }
+
+    // Determine file type
+    let extension = input_path
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_lowercase())
+        .ok_or("Unknown file type")?;
+
+    // Use persistent app data directory for output
+    let documents_dir = crate::paths::get_app_data_dir()?.join("documents");
+
+    if !documents_dir.exists() {
+        fs::create_dir_all(&documents_dir)
+            .map_err(|e| format!("Failed to create documents dir: {}", e))?;
+    }
+
+    // Same bytes → same PDF, so re-importing a deck (or the same deck
+    // from another folder) reuses the earlier conversion.
+    let output_pdf_path = documents_dir.join(format!("{}.pdf", content_hash(input_path)?));
+    if output_pdf_path.exists() {
+        if validate_pdf(&output_pdf_path).is_ok() {
+            println!("✓ Reusing cached conversion {:?}", output_pdf_path);
+            return Ok(output_pdf_path.to_string_lossy().into_owned());
+        }
+        fs::remove_file(&output_pdf_path).ok();
+    }
+
+    println!("Converting {} to PDF", file_path);
+    println!("Output path: {:?}", output_pdf_path);
+    println!("File type: {}", extension);
+
+    let result = convert_with_fallbacks(job, file_path, &extension, &output_pdf_path);
+    if result.is_err() {
+        // Don't leave a half-exported PDF behind.
+        let _ = fs::remove_file(&output_pdf_path);
+    }
+    result
+}
+
+fn convert_with_fallbacks(
+    job: &ConversionJob,
+    file_path: &str,
+    extension: &str,
+    output_pdf_path: &Path,
+) -> Result<String, String> {
+    // Platform-specific conversion with layered fallback
+    #[cfg(target_os = "macos")]
+    {
+        // Try macOS native conversions first
+        match extension {
+            "ppt" | "pptx" => {
+                // Try Keynote first (best quality, built-in)
+                let keynote = try_keynote_conversion(job, file_path, output_pdf_path);
+                if let Some(path) = fall_through(keynote, "Keynote")? {
+                    println!("✓ Converted using Keynote (highest quality)");
+                    return Ok(path);
+                }
+
+                // Try PowerPoint for Mac
+                let powerpoint =
+                    try_office_mac_conversion(job, file_path, output_pdf_path, "PowerPoint");
+                if let Some(path) = fall_through(powerpoint, "PowerPoint")? {
+                    println!("✓ Converted using Microsoft PowerPoint");
+                    return Ok(path);
+                }
+            }
+            "doc" | "docx" => {
+                // Try Pages first
+                let pages = try_pages_conversion(job, file_path, output_pdf_path);
+                if let Some(path) = fall_through(pages, "Pages")? {
+                    println!("✓ Converted using Pages (highest quality)");
+                    return Ok(path);
+                }
+
+                // Try Word for Mac
+                let word = try_office_mac_conversion(job, file_path, output_pdf_path, "Word");
+                if let Some(path) = fall_through(word, "Word")? {
+                    println!("✓ Converted using Microsoft Word");
+                    return Ok(path);
+                }
+            }
+            _ => {}
+        }
+
+        // Fallback to LibreOffice
+        println!("⚠ Native apps not available, falling back to LibreOffice");
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // Most Windows machines have Office but not LibreOffice; drive
+        // it over COM before falling back.
+        let app = match extension {
+            "ppt" | "pptx" => Some("PowerPoint"),
+            "doc" | "docx" => Some("Word"),
+            _ => None,
+        };
+        if let Some(app) = app {
+            let com = try_office_windows_conversion(job, file_path, output_pdf_path, app);
+            if let Some(path) = fall_through(com, app)? {
+                println!("✓ Converted using Microsoft {} (COM)", app);
+                return Ok(path);
+            }
+        }
+        println!("⚠ Office not available, falling back to LibreOffice");
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let _ = extension;
+
+    // Use LibreOffice (cross-platform fallback)
+    convert_with_libreoffice(job, file_path, output_pdf_path)
+}
+
+#[cfg(target_os = "macos")]
+fn try_keynote_conversion(
+    job: &ConversionJob,
+    input_path: &str,
+    output_path: &Path,
+) -> Result<String, String> {
+    // Check if Keynote is available
+    if !Path::new("/Applications/Keynote.app").exists() {
+        return Err("Keynote not installed".to_string());
+    }
+
+    // Use AppleScript instead of JXA for better reliability
+    let script = format!(
+        r#"
+        tell application "Keynote"
+            set theDoc to open POSIX file "{}"
+            delay 2
+            export theDoc to POSIX file "{}" as PDF
+            delay 1
+            close theDoc
+        end tell
+        "#,
+        input_path.replace("\"", "\\\""),
+        output_path.to_string_lossy().replace("\"", "\\\"")
+    );
+
+    println!("Executing Keynote conversion...");
+
+    let output = job.run_osascript(&script, "Keynote")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Keynote conversion error: {}", stderr));
+    }
+
+    job.wait_and_validate(output_path)?;
+
+    Ok(output_path.to_string_lossy().into_owned())
+}
+
+#[cfg(target_os = "macos")]
+fn try_pages_conversion(
+    job: &ConversionJob,
+    input_path: &str,
+    output_path: &Path,
+) -> Result<String, String> {
+    if !Path::new("/Applications/Pages.app").exists() {
+        return Err("Pages not installed".to_string());
+    }
+
+    let script = format!(
+        r#"
+        tell application "Pages"
+            set theDoc to open POSIX file "{}"
+            delay 2
+            export theDoc to POSIX file "{}" as PDF
+            delay 1
+            close theDoc
+        end tell
+        "#,
+        input_path.replace("\"", "\\\""),
+        output_path.to_string_lossy().replace("\"", "\\\"")
+    );
+
+    let output = job.run_osascript(&script, "Pages")?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("Pages conversion error: {}", stderr));
+    }
+
+    job.wait_and_validate(output_path)?;
+
+    Ok(output_path.to_string_lossy().into_owned())
+}
+
+#[cfg(target_os = "macos")]
+fn try_office_mac_conversion(
+    job: &ConversionJob,
+    input_path: &str,
+    output_path: &Path,
+    app_name: &str,
+) -> Result<String, String> {
+    let app_path = format!("/Applications/Microsoft {}.app", app_name);
+    if !Path::new(&app_path).exists() {
+        return Err(format!("Microsoft {} not installed", app_name));
+    }
+
+    let script = format!(
+        r#"
+        tell application "Microsoft {}"
+            set theDoc to open POSIX file "{}"
+            delay 2
+            save as theDoc file name (POSIX file "{}") file format PDF file format
+            delay 1
+            close theDoc
+        end tell
+        "#,
+        app_name,
+        input_path.replace("\"", "\\\""),
+        output_path.to_string_lossy().replace("\"", "\\\"")
+    );
+
+    let output = job.run_osascript(&script, app_name)?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{} conversion error: {}", app_name, stderr));
+    }
+
+    job.wait_and_validate(output_path)?;
+
+    Ok(output_path.to_string_lossy().into_owned())
+}
+
+/// Office for Windows via COM automation, scripted through PowerShell
+/// so we don't need COM bindings of our own. Paths travel in env vars
+/// rather than being spliced into the script, so quotes and `$` in file
+/// names can't break (or inject into) it.
+#[cfg(target_os = "windows")]
+fn try_office_windows_conversion(
+    job: &ConversionJob,
+    input_path: &str,
+    output_path: &Path,
+    app_name: &str,
+) -> Result<String, String> {
+    // ppSaveAsPDF = 32; wdExportFormatPDF = 17; msoTrue = -1.
+    let convert = match app_name {
+        "PowerPoint" => {
+            r#"
+            $app = New-Object -ComObject PowerPoint.Application
+            try {
+                $doc = $app.Presentations.Open($env:CLASSNOTE_CONVERT_IN, -1, 0, 0)
+                try { $doc.SaveAs($env:CLASSNOTE_CONVERT_OUT, 32) } finally { $doc.Close() }
+            } finally { $app.Quit() }
+            "#
+        }
+        "Word" => {
+            r#"
+            $app = New-Object -ComObject Word.Application
+            $app.Visible = $false
+            $app.DisplayAlerts = 0
+            try {
+                $doc = $app.Documents.Open($env:CLASSNOTE_CONVERT_IN, $false, $true)
+                try { $doc.ExportAsFixedFormat($env:CLASSNOTE_CONVERT_OUT, 17) } finally { $doc.Close(0) }
+            } finally { $app.Quit() }
+            "#
+        }
+        other => return Err(format!("Unsupported Office app: {}", other)),
+    };
+    let script = format!("$ErrorActionPreference = 'Stop'\n{}", convert);
+
+    let absolute_input = std::fs::canonicalize(input_path)
+        .map_err(|e| format!("Cannot resolve {}: {}", input_path, e))?;
+    // canonicalize yields a `\\?\` path, which Office rejects.
+    let absolute_input = absolute_input
+        .to_string_lossy()
+        .trim_start_matches(r"\\?\")
+        .to_string();
+
+    println!("Executing {} COM conversion...", app_name);
+
+    let output = job.run(
+        no_window("powershell")
+            .args([
+                "-NoProfile",
+                "-NonInteractive",
+                "-ExecutionPolicy",
+                "Bypass",
+            ])
+            .arg("-Command")
+            .arg(&script)
+            .env("CLASSNOTE_CONVERT_IN", &absolute_input)
+            .env("CLASSNOTE_CONVERT_OUT", output_path),
+        app_name,
+    )?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{} conversion error: {}", app_name, stderr.trim()));
+    }
+
+    job.wait_and_validate(output_path)?;
+
+    Ok(output_path.to_string_lossy().into_owned())
+}
+
+/// How to launch LibreOffice: `program` followed by `prefix_args`, then
+/// soffice's own flags. Flatpak needs `run <app-id>` in front; Snap can
+/// only see `$HOME/snap/libreoffice/...`, so it converts in `staging_dir`
+/// and we move the PDF out afterwards.
+struct SofficeCommand {
+    program: PathBuf,
+    prefix_args: Vec<String>,
+    staging_dir: Option<PathBuf>,
+}
+
+impl SofficeCommand {
+    fn plain(program: impl Into<PathBuf>) -> Self {
+        Self {
+            program: program.into(),
+            prefix_args: Vec::new(),
+            staging_dir: None,
+        }
+    }
+}
+
+const FLATPAK_LIBREOFFICE: &str = "org.libreoffice.LibreOffice";
+
+/// `LibreOffice-7.6.4.1.basic-x86_64.AppImage`, `libreoffice-fresh.AppImage` …
+fn is_libreoffice_appimage(file_name: &str) -> bool {
+    let lower = file_name.to_ascii_lowercase();
+    lower.starts_with("libreoffice") && lower.ends_with(".appimage")
+}
+
+fn locate_soffice() -> Option<SofficeCommand> {
+    if cfg!(target_os = "macos") {
+        let bundled = Path::new("/Applications/LibreOffice.app/Contents/MacOS/soffice");
+        if bundled.exists() {
+            return Some(SofficeCommand::plain(bundled));
+        }
+    } else if cfg!(target_os = "windows") {
+        // LibreOffice on Windows isn't on PATH by default. Prefer soffice.com
+        // (the console wrapper that waits for completion) under the standard
+        // install directories, falling back to "soffice" on PATH.
+        const WIN_CANDIDATES: &[&str] = &[
+            r"C:\Program Files\LibreOffice\program\soffice.com",
+            r"C:\Program Files\LibreOffice\program\soffice.exe",
+            r"C:\Program Files (x86)\LibreOffice\program\soffice.com",
+            r"C:\Program Files (x86)\LibreOffice\program\soffice.exe",
+        ];
+        if let Some(p) = WIN_CANDIDATES.iter().find(|p| Path::new(p).exists()) {
+            return Some(SofficeCommand::plain(p));
+        }
+    }
+
+    // Distro packages put `soffice` (Debian/Fedora) or `libreoffice`
+    // (Arch wrapper) on PATH.
+    if let Some(p) = find_on_path("soffice").or_else(|| find_on_path("libreoffice")) {
+        return Some(SofficeCommand::plain(p));
+    }
+
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+
+    // Upstream .deb/.rpm tarballs install under /opt/libreofficeX.Y.
+    let mut opt_dirs: Vec<PathBuf> = std::fs::read_dir("/opt")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("libreoffice"))
+        })
+        .collect();
+    opt_dirs.sort();
+    for dir in opt_dirs
+        .iter()
+        .rev()
+        .chain([PathBuf::from("/usr/lib/libreoffice")].iter())
+    {
+        let soffice = dir.join("program").join("soffice");
+        if soffice.exists() {
+            return Some(SofficeCommand::plain(soffice));
+        }
+    }
+
+    let home = dirs::home_dir();
+
+    // Snap: /snap/bin isn't always on a desktop session's PATH.
+    let snap = Path::new("/snap/bin/libreoffice");
+    if snap.exists() {
+        return Some(SofficeCommand {
+            program: snap.to_path_buf(),
+            prefix_args: Vec::new(),
+            staging_dir: home
+                .as_ref()
+                .map(|h| h.join("snap/libreoffice/common/classnoteai-convert")),
+        });
+    }
+
+    // Flatpak, system-wide or per-user install.
+    let flatpak_installed = no_window("flatpak")
+        .args(["info", FLATPAK_LIBREOFFICE])
+        .output()
+        .is_ok_and(|o| o.status.success());
+    if flatpak_installed {
+        return Some(SofficeCommand {
+            program: PathBuf::from("flatpak"),
+            prefix_args: vec![
+                "run".to_string(),
+                "--filesystem=host".to_string(),
+                FLATPAK_LIBREOFFICE.to_string(),
+            ],
+            staging_dir: None,
+        });
+    }
+
+    // AppImage dropped in one of the usual places.
+    let mut appimage_dirs: Vec<PathBuf> = Vec::new();
+    if let Some(h) = &home {
+        appimage_dirs.extend(
+            ["Applications", ".local/bin", "bin", "Downloads"]
+                .iter()
+                .map(|d| h.join(d)),
+        );
+    }
+    appimage_dirs.push(PathBuf::from("/opt"));
+    for dir in appimage_dirs {
+        let found = std::fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| e.path())
+            .find(|p| {
+                p.is_file()
+                    && p.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(is_libreoffice_appimage)
+            });
+        if let Some(p) = found {
+            return Some(SofficeCommand::plain(p));
+        }
+    }
+
+    None
+}
+
+fn libreoffice_install_hint() -> &'static str {
+    if cfg!(target_os = "macos") {
+        "LibreOffice not found. Install it with `brew install --cask libreoffice` or from https://www.libreoffice.org/download/, then retry."
+    } else if cfg!(target_os = "windows") {
+        "LibreOffice not found. Install it with `winget install TheDocumentFoundation.LibreOffice` or from https://www.libreoffice.org/download/, then retry."
+    } else {
+        "LibreOffice not found. Install it with your package manager (e.g. `sudo apt install libreoffice`), `flatpak install flathub org.libreoffice.LibreOffice`, or `sudo snap install libreoffice`, then retry."
+    }
+}
+
+fn convert_with_libreoffice(
+    job: &ConversionJob,
+    input_path: &str,
+    output_path: &Path,
+) -> Result<String, String> {
+    use std::fs;
+
+    let soffice = locate_soffice().ok_or_else(|| libreoffice_install_hint().to_string())?;
+    println!(
+        "Using LibreOffice: {} {}",
+        soffice.program.display(),
+        soffice.prefix_args.join(" ")
+    );
+
+    // soffice names the result after the input (`<stem>.pdf`) inside
+    // --outdir; convert into a scratch dir and move it to `output_path`.
+    let documents_dir = output_path.parent().ok_or("Invalid output path")?;
+    let work_dir = soffice
+        .staging_dir
+        .clone()
+        .unwrap_or_else(|| documents_dir.join(".convert"));
+    fs::create_dir_all(&work_dir)
+        .map_err(|e| format!("Failed to create {}: {}", work_dir.display(), e))?;
+    let input = Path::new(input_path);
+    let source = if soffice.staging_dir.is_some() {
+        let staged = work_dir.join(input.file_name().ok_or("Invalid filename")?);
+        fs::copy(input, &staged).map_err(|e| format!("Failed to stage {}: {}", input_path, e))?;
+        staged
+    } else {
+        input.to_path_buf()
+    };
+    let produced = work_dir
+        .join(source.file_stem().ok_or("Invalid filename")?)
+        .with_extension("pdf");
+    let _ = fs::remove_file(&produced);
+
+    let output = job.run(
+        no_window(&soffice.program)
+            .args(&soffice.prefix_args)
+            .arg("--headless")
+            .arg("--convert-to")
+            .arg("pdf")
+            .arg("--outdir")
+            .arg(&work_dir)
+            .arg(&source),
+        "LibreOffice",
+    );
+    if soffice.staging_dir.is_some() {
+        let _ = fs::remove_file(&source);
+    }
+    let output = output.map_err(|e| {
+        if e == CANCELLED {
+            e
+        } else {
+            format!("{}. {}", e, libreoffice_install_hint())
+        }
+    })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("LibreOffice conversion failed: {}", stderr));
+    }
+
+    job.wait_and_validate(&produced)?;
+    if fs::rename(&produced, output_path).is_err() {
+        // Snap staging may sit on another filesystem.
+        fs::copy(&produced, output_path)
+            .map_err(|e| format!("Failed to move converted PDF: {}", e))?;
+        let _ = fs::remove_file(&produced);
+    }
+
+    Ok(output_path.to_string_lossy().into_owned())
+}
+
+/// Hex SHA-256 (first 128 bits) of the file's bytes.
+pub fn content_hash(path: &Path) -> Result<String, String> {
+    let mut file =
+        std::fs::File::open(path).map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)
+        .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let digest = hasher.finalize();
+    Ok(digest[..16].iter().map(|b| format!("{b:02x}")).collect())
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CleanupReport {
+    pub removed: Vec<String>,
+    pub freed_bytes: u64,
+}
+
+/// Converted PDFs younger than this are kept even when unreferenced:
+/// the lecture that will point at them may not be saved yet.
+const CLEANUP_GRACE: Duration = Duration::from_secs(60 * 60);
+
+/// Delete files in `documents_dir` (converted PDFs, including the old
+/// timestamped ones, and LibreOffice scratch output) that no path in
+/// `keep` refers to.
+pub fn cleanup_documents_dir(
+    documents_dir: &Path,
+    keep: &[PathBuf],
+    grace: Duration,
+) -> Result<CleanupReport, String> {
+    let keep: Vec<PathBuf> = keep
+        .iter()
+        .map(|p| std::fs::canonicalize(p).unwrap_or_else(|_| p.clone()))
+        .collect();
+    let mut report = CleanupReport::default();
+    let scratch = documents_dir.join(".convert");
+    let entries = std::fs::read_dir(documents_dir)
+        .into_iter()
+        .chain(std::fs::read_dir(&scratch))
+        .flatten()
+        .flatten();
+    for entry in entries {
+        let path = entry.path();
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if !meta.is_file() {
+            continue;
+        }
+        let recent = meta
+            .modified()
+            .ok()
+            .and_then(|m| m.elapsed().ok())
+            .is_none_or(|age| age < grace);
+        let canonical = std::fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+        if recent || keep.contains(&canonical) {
+            continue;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            report.freed_bytes += meta.len();
+            report.removed.push(path.to_string_lossy().into_owned());
+        }
+    }
+    Ok(report)
+}
+
+/// Remove converted PDFs no lecture (trashed ones included) points to.
+#[tauri::command]
+pub async fn cleanup_converted_documents() -> Result<CleanupReport, String> {
+    let manager = crate::storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let keep: Vec<PathBuf> = db
+        .list_pdf_paths()
+        .map_err(|e| format!("讀取課堂失敗: {}", e))?
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+    let documents_dir = crate::paths::get_app_data_dir()?.join("documents");
+    tokio::task::spawn_blocking(move || {
+        // Not while a conversion is writing its output.
+        let _busy = CONVERTING.lock().unwrap_or_else(|e| e.into_inner());
+        cleanup_documents_dir(&documents_dir, &keep, CLEANUP_GRACE)
+    })
+    .await
+    .map_err(|e| format!("cleanup task join error: {e}"))?
+}
+
+fn wait_for_file(path: &Path, cancel: &AtomicBool) -> Result<(), String> {
+    use std::fs;
+
+    let max_wait = 120; // 60 seconds (120 * 500ms)
+    let mut waited = 0;
+    let mut last_size = 0;
+
+    while waited < max_wait {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(CANCELLED.to_string());
+        }
+        if path.exists() {
+            if let Ok(metadata) = fs::metadata(path) {
+                let current_size = metadata.len();
+                if current_size > 0 && current_size == last_size {
+                    println!("File ready. Size: {} bytes", current_size);
+                    return Ok(());
+                }
+                last_size = current_size;
+            }
+        }
+        std::thread::sleep(Duration::from_millis(500));
+        waited += 1;
+    }
+
+    if path.exists() {
+        Ok(())
+    } else {
+        Err("Timeout waiting for PDF file".to_string())
+    }
+}
+
+fn validate_pdf(path: &Path) -> Result<(), String> {
+    use std::fs;
+
+    let metadata = fs::metadata(path).map_err(|e| format!("Cannot read PDF: {}", e))?;
+
+    if metadata.len() < 100 {
+        return Err(format!("PDF too small ({} bytes)", metadata.len()));
+    }
+
+    let mut file = fs::File::open(path).map_err(|e| format!("Cannot open PDF: {}", e))?;
+    let mut header = [0u8; 5];
+    file.read_exact(&mut header).ok();
+
+    if &header != b"%PDF-" {
+        return Err("Invalid PDF header".to_string());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn libreoffice_appimage_names() {
+        assert!(is_libreoffice_appimage(
+            "LibreOffice-7.6.4.1.basic-x86_64.AppImage"
+        ));
+        assert!(is_libreoffice_appimage("libreoffice-fresh.appimage"));
+        assert!(!is_libreoffice_appimage("LibreOffice.tar.gz"));
+        assert!(!is_libreoffice_appimage("Obsidian-1.5.AppImage"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_cancellable_collects_output_and_honours_cancel() {
+        let cancel = AtomicBool::new(false);
+        let mut spawned = false;
+        let out = run_cancellable(
+            Command::new("sh").args(["-c", "echo out; echo err >&2"]),
+            &cancel,
+            "sh",
+            || spawned = true,
+        )
+        .unwrap();
+        assert!(spawned && out.status.success());
+        assert_eq!(String::from_utf8_lossy(&out.stdout).trim(), "out");
+        assert_eq!(String::from_utf8_lossy(&out.stderr).trim(), "err");
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        let flag = cancel.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            flag.store(true, Ordering::Relaxed);
+        });
+        let started = std::time::Instant::now();
+        let err =
+            run_cancellable(Command::new("sleep").arg("30"), &cancel, "sleep", || {}).unwrap_err();
+        assert_eq!(err, CANCELLED);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn transient_applescript_errors() {
+        assert!(is_transient_applescript_error(
+            "execution error: Keynote got an error: AppleEvent timed out. (-1712)"
+        ));
+        assert!(is_transient_applescript_error(
+            "execution error: Pages got an error: Application isn't running. (-600)"
+        ));
+        assert!(!is_transient_applescript_error(
+            "execution error: Keynote got an error: The document can't be opened. (-1728)"
+        ));
+        assert!(!is_transient_applescript_error(""));
+    }
+
+    #[test]
+    fn content_hash_ignores_name_and_location() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let a = dir.path().join("week1.pptx");
+        let b = dir.path().join("copy of week1.pptx");
+        std::fs::write(&a, b"deck").unwrap();
+        std::fs::write(&b, b"deck").unwrap();
+        assert_eq!(content_hash(&a).unwrap(), content_hash(&b).unwrap());
+        assert_eq!(content_hash(&a).unwrap().len(), 32);
+        std::fs::write(&b, b"deck v2").unwrap();
+        assert_ne!(content_hash(&a).unwrap(), content_hash(&b).unwrap());
+    }
+
+    #[test]
+    fn cleanup_keeps_referenced_and_recent_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let kept = dir.path().join("aaaa.pdf");
+        let stale = dir.path().join("deck_1700000000.pdf");
+        std::fs::create_dir(dir.path().join(".convert")).unwrap();
+        let scratch = dir.path().join(".convert").join("deck.pdf");
+        for p in [&kept, &stale, &scratch] {
+            std::fs::write(p, b"%PDF-1.4").unwrap();
+        }
+
+        let report =
+            cleanup_documents_dir(dir.path(), &[kept.clone()], Duration::from_secs(3600)).unwrap();
+        assert!(report.removed.is_empty(), "fresh files are left alone");
+
+        let report = cleanup_documents_dir(dir.path(), &[kept.clone()], Duration::ZERO).unwrap();
+        assert_eq!(report.removed.len(), 2);
+        assert_eq!(report.freed_bytes, 16);
+        assert!(kept.exists() && !stale.exists() && !scratch.exists());
+    }
+
+    #[test]
+    fn wait_for_file_stops_when_cancelled() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let cancel = AtomicBool::new(true);
+        let err = wait_for_file(&dir.path().join("never.pdf"), &cancel).unwrap_err();
+        assert_eq!(err, CANCELLED);
+    }
+}
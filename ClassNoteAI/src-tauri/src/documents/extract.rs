@@ -0,0 +1,386 @@
+//! Text straight out of `.pptx` / `.docx`, no PDF conversion.
+//!
+//! Indexing and syllabus generation only need the words, and going
+//! through Keynote / LibreOffice for that is slow (tens of seconds) and
+//! fragile (AppleScript timeouts, missing installs). Both formats are
+//! zipped OOXML, so we read the parts directly:
+//!
+//! - pptx: `ppt/slides/slideN.xml`, one section per slide in slide
+//!   number order; the title placeholder becomes the section title.
+//! - docx: `word/document.xml`, split into sections at `Title` /
+//!   `HeadingN` paragraphs.
+//!
+//! The XML handling is a deliberately small tag scanner: we only need
+//! text runs (`a:t` / `w:t`), paragraph breaks and a couple of
+//! attributes, not a DOM.
+
+use serde::Serialize;
+use std::io::Read;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TextSection {
+    /// Slide number (pptx) or 1-based section ordinal (docx).
+    pub index: u32,
+    pub title: Option<String>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentText {
+    /// `pptx` | `docx`.
+    pub kind: String,
+    pub sections: Vec<TextSection>,
+}
+
+/// One `<...>` tag: its name, whether it closes, whether it self-closes,
+/// and the raw attribute text.
+struct Tag<'a> {
+    name: &'a str,
+    closing: bool,
+    self_closing: bool,
+    attrs: &'a str,
+}
+
+enum Token<'a> {
+    Tag(Tag<'a>),
+    Text(&'a str),
+}
+
+fn tokens(xml: &str) -> impl Iterator<Item = Token<'_>> {
+    let mut rest = xml;
+    std::iter::from_fn(move || loop {
+        if rest.is_empty() {
+            return None;
+        }
+        if let Some(body) = rest.strip_prefix('<') {
+            let end = body.find('>')?;
+            let inner = &body[..end];
+            rest = &body[end + 1..];
+            if inner.starts_with('?') || inner.starts_with('!') {
+                continue;
+            }
+            let closing = inner.starts_with('/');
+            let inner = inner.trim_start_matches('/');
+            let self_closing = inner.ends_with('/');
+            let inner = inner.trim_end_matches('/');
+            let (name, attrs) = inner.split_once(char::is_whitespace).unwrap_or((inner, ""));
+            return Some(Token::Tag(Tag {
+                name,
+                closing,
+                self_closing,
+                attrs,
+            }));
+        }
+        let end = rest.find('<').unwrap_or(rest.len());
+        let text = &rest[..end];
+        rest = &rest[end..];
+        return Some(Token::Text(text));
+    })
+}
+
+fn attr<'a>(attrs: &'a str, key: &str) -> Option<&'a str> {
+    let needle = format!("{key}=\"");
+    let start = attrs.find(&needle)? + needle.len();
+    let len = attrs[start..].find('"')?;
+    Some(&attrs[start..start + len])
+}
+
+pub fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';') else { break };
+        let entity = &rest[1..semi];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .and_then(|h| u32::from_str_radix(h, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|d| d.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn tidy(paragraphs: &[String]) -> String {
+    paragraphs
+        .iter()
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Text and title of one slide's XML.
+pub fn slide_text(xml: &str) -> (Option<String>, String) {
+    let mut paragraphs: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut in_run = false;
+    // Per `p:sp` shape: is it the title placeholder, and where its
+    // paragraphs start.
+    let mut shape_is_title = false;
+    let mut shape_start = 0;
+    let mut title: Option<String> = None;
+
+    for token in tokens(xml) {
+        match token {
+            Token::Tag(t) => match (t.name, t.closing) {
+                ("p:sp", false) => {
+                    shape_is_title = false;
+                    shape_start = paragraphs.len();
+                }
+                ("p:ph", false) => {
+                    shape_is_title |=
+                        matches!(attr(t.attrs, "type"), Some("title") | Some("ctrTitle"));
+                }
+                ("p:sp", true) => {
+                    if shape_is_title && title.is_none() {
+                        let text = tidy(&paragraphs[shape_start..]).replace('\n', " ");
+                        title = (!text.is_empty()).then_some(text);
+                    }
+                }
+                ("a:t", false) => in_run = !t.self_closing,
+                ("a:t", true) => in_run = false,
+                ("a:br", false) => current.push('\n'),
+                ("a:p", true) => paragraphs.push(std::mem::take(&mut current)),
+                _ => {}
+            },
+            Token::Text(s) if in_run => current.push_str(&unescape(s)),
+            Token::Text(_) => {}
+        }
+    }
+    if !current.is_empty() {
+        paragraphs.push(current);
+    }
+    (title, tidy(&paragraphs))
+}
+
+fn heading_level(style: &str) -> Option<u8> {
+    let lower = style.to_ascii_lowercase();
+    if lower == "title" {
+        return Some(0);
+    }
+    lower.strip_prefix("heading")?.parse().ok()
+}
+
+/// Sections of a `word/document.xml`, split at title / heading
+/// paragraphs.
+pub fn docx_sections(xml: &str) -> Vec<TextSection> {
+    let mut sections: Vec<TextSection> = Vec::new();
+    let mut body: Vec<String> = Vec::new();
+    let mut title: Option<String> = None;
+    let mut current = String::new();
+    let mut is_heading = false;
+    let mut in_run = false;
+
+    let flush = |title: Option<String>, body: &mut Vec<String>, sections: &mut Vec<TextSection>| {
+        let text = tidy(body);
+        body.clear();
+        if title.is_some() || !text.is_empty() {
+            sections.push(TextSection {
+                index: sections.len() as u32 + 1,
+                title,
+                text,
+            });
+        }
+    };
+
+    for token in tokens(xml) {
+        match token {
+            Token::Tag(t) => match (t.name, t.closing) {
+                ("w:p", false) if !t.self_closing => {
+                    current.clear();
+                    is_heading = false;
+                }
+                ("w:pStyle", false) => {
+                    is_heading |= attr(t.attrs, "w:val").and_then(heading_level).is_some();
+                }
+                ("w:t", false) => in_run = !t.self_closing,
+                ("w:t", true) => in_run = false,
+                ("w:tab", false) => current.push('\t'),
+                ("w:br", false) | ("w:cr", false) => current.push('\n'),
+                ("w:p", true) => {
+                    let text = std::mem::take(&mut current);
+                    if is_heading && !text.trim().is_empty() {
+                        flush(title.take(), &mut body, &mut sections);
+                        title = Some(text.trim().to_string());
+                    } else {
+                        body.push(text);
+                    }
+                }
+                _ => {}
+            },
+            Token::Text(s) if in_run => current.push_str(&unescape(s)),
+            Token::Text(_) => {}
+        }
+    }
+    flush(title, &mut body, &mut sections);
+    sections
+}
+
+fn read_part<R: Read + std::io::Seek>(
+    zip: &mut zip::ZipArchive<R>,
+    name: &str,
+) -> Result<String, String> {
+    let mut part = zip
+        .by_name(name)
+        .map_err(|e| format!("{} 不在文件中: {}", name, e))?;
+    let mut xml = String::new();
+    part.read_to_string(&mut xml)
+        .map_err(|e| format!("讀取 {} 失敗: {}", name, e))?;
+    Ok(xml)
+}
+
+/// `ppt/slides/slide12.xml` → 12.
+fn slide_number(name: &str) -> Option<u32> {
+    name.strip_prefix("ppt/slides/slide")?
+        .strip_suffix(".xml")?
+        .parse()
+        .ok()
+}
+
+pub fn extract_document_text_inner(path: &Path) -> Result<DocumentText, String> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    let file =
+        std::fs::File::open(path).map_err(|e| format!("開啟 {} 失敗: {}", path.display(), e))?;
+    let mut zip = zip::ZipArchive::new(std::io::BufReader::new(file))
+        .map_err(|e| format!("不是有效的 Office 文件: {}", e))?;
+
+    match ext.as_str() {
+        "pptx" => {
+            let mut slides: Vec<(u32, String)> = zip
+                .file_names()
+                .filter_map(|n| slide_number(n).map(|i| (i, n.to_string())))
+                .collect();
+            slides.sort_by_key(|(i, _)| *i);
+            let mut sections = Vec::with_capacity(slides.len());
+            for (index, name) in slides {
+                let (title, text) = slide_text(&read_part(&mut zip, &name)?);
+                sections.push(TextSection { index, title, text });
+            }
+            Ok(DocumentText {
+                kind: "pptx".to_string(),
+                sections,
+            })
+        }
+        "docx" => Ok(DocumentText {
+            kind: "docx".to_string(),
+            sections: docx_sections(&read_part(&mut zip, "word/document.xml")?),
+        }),
+        other => Err(format!("不支援的文件格式: {} (pptx / docx)", other)),
+    }
+}
+
+/// Per-slide (pptx) or per-heading (docx) text of an Office file.
+#[tauri::command]
+pub async fn extract_document_text(path: String) -> Result<DocumentText, String> {
+    tokio::task::spawn_blocking(move || extract_document_text_inner(Path::new(&path)))
+        .await
+        .map_err(|e| format!("extract task join error: {e}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn write_zip(path: &Path, parts: &[(&str, &str)]) {
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        for (name, body) in parts {
+            zip.start_file(*name, zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(body.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    const SLIDE: &str = r#"<?xml version="1.0"?><p:sld><p:cSld><p:spTree>
+        <p:sp><p:nvSpPr><p:nvPr><p:ph type="title"/></p:nvPr></p:nvSpPr>
+          <p:txBody><a:p><a:r><a:t>Limits &amp; Continuity</a:t></a:r></a:p></p:txBody></p:sp>
+        <p:sp><p:nvSpPr><p:nvPr><p:ph idx="1"/></p:nvPr></p:nvSpPr>
+          <p:txBody><a:p><a:r><a:t>ε-δ </a:t></a:r><a:r><a:t>definition</a:t></a:r></a:p>
+          <a:p><a:r><a:t/></a:r></a:p><a:p><a:r><a:t>x &lt; 1</a:t></a:r></a:p></p:txBody></p:sp>
+        </p:spTree></p:cSld></p:sld>"#;
+
+    #[test]
+    fn slide_title_and_body() {
+        let (title, text) = slide_text(SLIDE);
+        assert_eq!(title.as_deref(), Some("Limits & Continuity"));
+        assert_eq!(text, "Limits & Continuity\nε-δ definition\nx < 1");
+    }
+
+    #[test]
+    fn docx_splits_at_headings() {
+        let xml = r#"<w:document><w:body>
+            <w:p><w:r><w:t>Preface text</w:t></w:r></w:p>
+            <w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Week 1</w:t></w:r></w:p>
+            <w:p><w:r><w:t xml:space="preserve">Sets </w:t></w:r><w:r><w:tab/><w:t>and logic</w:t></w:r></w:p>
+            <w:p/>
+            <w:p><w:pPr><w:pStyle w:val="Heading2"/></w:pPr><w:r><w:t>Week 2</w:t></w:r></w:p>
+            </w:body></w:document>"#;
+        let sections = docx_sections(xml);
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections[0].title, None);
+        assert_eq!(sections[0].text, "Preface text");
+        assert_eq!(sections[1].title.as_deref(), Some("Week 1"));
+        assert_eq!(sections[1].text, "Sets \tand logic");
+        assert_eq!(sections[2].index, 3);
+        assert_eq!(sections[2].text, "");
+    }
+
+    #[test]
+    fn entities() {
+        assert_eq!(
+            unescape("a &amp; b &#x4E2D;&#25991; &bogus; &"),
+            "a & b 中文 &bogus; &"
+        );
+    }
+
+    #[test]
+    fn pptx_slides_in_numeric_order() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("deck.pptx");
+        let second = SLIDE.replace("Limits", "Derivatives");
+        write_zip(
+            &path,
+            &[
+                ("ppt/slides/slide10.xml", &second),
+                ("ppt/slides/slide2.xml", SLIDE),
+                ("ppt/slides/_rels/slide2.xml.rels", "<Relationships/>"),
+            ],
+        );
+        let doc = extract_document_text_inner(&path).unwrap();
+        assert_eq!(doc.kind, "pptx");
+        let idx: Vec<u32> = doc.sections.iter().map(|s| s.index).collect();
+        assert_eq!(idx, vec![2, 10]);
+        assert_eq!(
+            doc.sections[1].title.as_deref(),
+            Some("Derivatives & Continuity")
+        );
+        assert!(extract_document_text_inner(&dir.path().join("x.pdf")).is_err());
+    }
+}
@@ -0,0 +1,18 @@
+//! Lecture documents (slides / handouts) on the Rust side.
+//!
+//! `convert` normalises Office / iWork files to PDF, and `queue` runs
+//! several of those in a row. The other modules work on that PDF
+//! without a JS renderer:
+//!
+//! - `render` rasterises pages to cached PNGs for the slide viewer and
+//!   slide alignment.
+//! - `ocr` runs Tesseract over pages that have no text layer.
+//!
+//! `extract` is the exception: it reads text straight out of pptx /
+//! docx for callers that don't need a PDF at all.
+
+pub mod convert;
+pub mod extract;
+pub mod ocr;
+pub mod queue;
+pub mod render;
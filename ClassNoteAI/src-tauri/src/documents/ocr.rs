@@ -0,0 +1,227 @@
+//! Local OCR for image-only PDF pages (scanned slides, photographed
+//! handouts) via the Tesseract CLI.
+//!
+//! The renderer's text layer is tried first; this is only for the pages
+//! where that came back empty. Those pages are rendered at [`OCR_DPI`]
+//! through `render`'s cache and fed to `tesseract`. Nothing leaves the
+//! machine, unlike the cloud-vision path in `remoteOcrService.ts`.
+//!
+//! Language hints are app-style codes (`en`, `zh-TW`, `ja` …) mapped to
+//! Tesseract traineddata names and filtered to what is installed, so a
+//! missing `chi_tra` degrades to English-only instead of failing.
+//! Recognised text is cached next to the page PNG per language set.
+
+use super::render::{render_pdf_pages_inner, RenderedPage};
+use crate::utils::command::{find_on_path, no_window};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+/// Tesseract is tuned for ~300 DPI; lower loses small slide text.
+pub const OCR_DPI: u32 = 300;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OcrPage {
+    pub page: u32,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OcrResult {
+    /// `+`-joined Tesseract languages actually used, e.g. `eng+chi_tra`.
+    pub languages: String,
+    pub pages: Vec<OcrPage>,
+}
+
+fn locate_tesseract() -> Result<PathBuf, String> {
+    if let Some(p) = find_on_path("tesseract") {
+        return Ok(p);
+    }
+    #[cfg(windows)]
+    {
+        // The UB-Mannheim installer doesn't add itself to PATH.
+        let p = PathBuf::from(r"C:\Program Files\Tesseract-OCR\tesseract.exe");
+        if p.exists() {
+            return Ok(p);
+        }
+    }
+    Err(
+        "tesseract not found on PATH. Install via WinGet/Homebrew/apt (plus the \
+         `chi_tra` language pack for Chinese) and retry."
+            .to_string(),
+    )
+}
+
+/// `tesseract --list-langs` prints a header line, then one code per line.
+pub fn parse_list_langs(stdout: &str) -> Vec<String> {
+    stdout
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.contains(' ') && !l.ends_with(':'))
+        .filter(|l| *l != "osd")
+        .map(str::to_string)
+        .collect()
+}
+
+fn to_tesseract_code(hint: &str) -> Option<&'static str> {
+    let lower = hint.trim().to_ascii_lowercase().replace('_', "-");
+    Some(match lower.as_str() {
+        "en" | "en-us" | "en-gb" | "eng" => "eng",
+        "zh" | "zh-tw" | "zh-hk" | "zh-hant" => "chi_tra",
+        "zh-cn" | "zh-sg" | "zh-hans" => "chi_sim",
+        "ja" | "jpn" => "jpn",
+        "ko" | "kor" => "kor",
+        "fr" | "fra" => "fra",
+        "de" | "deu" => "deu",
+        "es" | "spa" => "spa",
+        _ => return None,
+    })
+}
+
+/// Tesseract `-l` argument for `hints`, restricted to `installed`.
+/// English is always added when available since slides mix it in.
+pub fn select_languages(hints: &[String], installed: &[String]) -> Option<String> {
+    let mut langs: Vec<String> = Vec::new();
+    for hint in hints {
+        let code = to_tesseract_code(hint)
+            .map(str::to_string)
+            .unwrap_or_else(|| hint.trim().to_string());
+        if installed.contains(&code) && !langs.contains(&code) {
+            langs.push(code);
+        }
+    }
+    if installed.iter().any(|l| l == "eng") && !langs.iter().any(|l| l == "eng") {
+        langs.push("eng".to_string());
+    }
+    if langs.is_empty() {
+        langs.extend(installed.first().cloned());
+    }
+    (!langs.is_empty()).then(|| langs.join("+"))
+}
+
+fn run_tesseract(tesseract: &Path, image: &Path, langs: &str) -> Result<String, String> {
+    let output = no_window(tesseract)
+        .arg(image)
+        .arg("stdout")
+        .args(["-l", langs, "--psm", "3"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("tesseract spawn: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "tesseract exited {:?}: {}",
+            output.status.code(),
+            stderr.trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn ocr_page(tesseract: &Path, rendered: &RenderedPage, langs: &str) -> Result<String, String> {
+    let png = Path::new(&rendered.path);
+    let cached = png.with_extension(format!("{}.txt", langs));
+    if let Ok(text) = std::fs::read_to_string(&cached) {
+        return Ok(text);
+    }
+    let text = run_tesseract(tesseract, png, langs)?;
+    // A failed cache write only costs a re-run next time.
+    let _ = std::fs::write(&cached, &text);
+    Ok(text)
+}
+
+pub fn ocr_pdf_pages_inner(
+    pdf: &Path,
+    cache_root: &Path,
+    pages: Option<&[u32]>,
+    hints: &[String],
+) -> Result<OcrResult, String> {
+    let tesseract = locate_tesseract()?;
+    let listed = no_window(&tesseract)
+        .arg("--list-langs")
+        .output()
+        .map_err(|e| format!("tesseract spawn: {e}"))?;
+    // Older builds print the list on stderr.
+    let installed = parse_list_langs(&format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&listed.stdout),
+        String::from_utf8_lossy(&listed.stderr)
+    ));
+    let languages = select_languages(hints, &installed)
+        .ok_or_else(|| "tesseract has no language data installed".to_string())?;
+
+    let rendered = render_pdf_pages_inner(pdf, cache_root, OCR_DPI, pages)?;
+    let mut out = Vec::with_capacity(rendered.pages.len());
+    for page in &rendered.pages {
+        out.push(OcrPage {
+            page: page.page,
+            text: ocr_page(&tesseract, page, &languages)?,
+        });
+    }
+    Ok(OcrResult {
+        languages,
+        pages: out,
+    })
+}
+
+/// OCR `pages` (1-based; all when omitted) of the PDF at `path`.
+/// `languages` are hints such as `["zh-TW", "en"]`.
+#[tauri::command]
+pub async fn ocr_pdf_pages(
+    path: String,
+    pages: Option<Vec<u32>>,
+    languages: Option<Vec<String>>,
+) -> Result<OcrResult, String> {
+    let cache_root = crate::paths::get_cache_dir()?.join("pdf-pages");
+    tokio::task::spawn_blocking(move || {
+        ocr_pdf_pages_inner(
+            Path::new(&path),
+            &cache_root,
+            pages.as_deref(),
+            &languages.unwrap_or_default(),
+        )
+    })
+    .await
+    .map_err(|e| format!("ocr task join error: {e}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owned(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn list_langs_output() {
+        let out =
+            "List of available languages in \"/usr/share/tessdata/\" (3):\nchi_tra\neng\nosd\n";
+        assert_eq!(parse_list_langs(out), owned(&["chi_tra", "eng"]));
+    }
+
+    #[test]
+    fn language_hints_map_to_installed_codes() {
+        let installed = owned(&["chi_tra", "eng", "jpn"]);
+        assert_eq!(
+            select_languages(&owned(&["zh-TW", "en"]), &installed).as_deref(),
+            Some("chi_tra+eng")
+        );
+        // Missing pack: fall back to English rather than failing.
+        assert_eq!(
+            select_languages(&owned(&["zh-CN"]), &installed).as_deref(),
+            Some("eng")
+        );
+        assert_eq!(
+            select_languages(&owned(&["ja"]), &installed).as_deref(),
+            Some("jpn+eng")
+        );
+        assert_eq!(
+            select_languages(&[], &owned(&["deu"])).as_deref(),
+            Some("deu")
+        );
+        assert_eq!(select_languages(&owned(&["en"]), &[]), None);
+    }
+}
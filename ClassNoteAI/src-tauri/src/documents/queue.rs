@@ -0,0 +1,70 @@
+//! Converts several files one after another, e.g. a multi-file drop.
+//!
+//! `enqueue_conversions` returns a job id per file straight away; a
+//! single worker thread then runs them through `convert::convert_job`
+//! in order. Each file reports on the usual `conversion-progress`
+//! event, starting with `queued`, and can be cancelled with
+//! `cancel_conversion` whether it's waiting or running.
+
+use super::convert::{convert_job, ConversionJob, ConversionStage};
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Mutex, OnceLock};
+use tauri::AppHandle;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedConversion {
+    pub job_id: String,
+    pub file_path: String,
+}
+
+struct Item {
+    job: ConversionJob,
+    file_path: String,
+}
+
+static QUEUE: OnceLock<Mutex<Sender<Item>>> = OnceLock::new();
+
+/// Files queued or converting, for the `queued` position.
+static PENDING: AtomicUsize = AtomicUsize::new(0);
+
+fn queue() -> &'static Mutex<Sender<Item>> {
+    QUEUE.get_or_init(|| {
+        let (tx, rx) = mpsc::channel::<Item>();
+        std::thread::spawn(move || {
+            for item in rx {
+                // Outcome goes out as a progress event.
+                let _ = convert_job(&item.job, &item.file_path);
+                PENDING.fetch_sub(1, Ordering::SeqCst);
+            }
+        });
+        Mutex::new(tx)
+    })
+}
+
+/// Queue `file_paths` for conversion to PDF, in order.
+#[tauri::command]
+pub fn enqueue_conversions(
+    app: AppHandle,
+    file_paths: Vec<String>,
+) -> Result<Vec<QueuedConversion>, String> {
+    let tx = queue().lock().map_err(|e| e.to_string())?;
+    let mut queued = Vec::with_capacity(file_paths.len());
+    for file_path in file_paths {
+        let id = uuid::Uuid::new_v4().to_string();
+        let job = ConversionJob::register(app.clone(), id, file_path.clone())?;
+        let ahead = PENDING.fetch_add(1, Ordering::SeqCst);
+        job.progress(ConversionStage::Queued, Some(ahead.to_string()));
+        queued.push(QueuedConversion {
+            job_id: job.id().to_string(),
+            file_path: file_path.clone(),
+        });
+        if tx.send(Item { job, file_path }).is_err() {
+            PENDING.fetch_sub(1, Ordering::SeqCst);
+            return Err("Conversion queue worker has stopped".to_string());
+        }
+    }
+    Ok(queued)
+}
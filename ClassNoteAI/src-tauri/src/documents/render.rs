@@ -0,0 +1,286 @@
+//! PDF pages → PNG, cached under `{app_data}/cache/pdf-pages/`.
+//!
+//! Same approach as ffmpeg for audio: shell out to a renderer the user
+//! (or the OS package manager) already has rather than linking one in.
+//! Poppler's `pdftoppm` is tried first, then MuPDF's `mutool`. Page
+//! counts come from the matching `pdfinfo` / `mutool show`.
+//!
+//! Each source PDF gets its own cache directory keyed by its path,
+//! size and mtime, so re-importing or editing the file invalidates it.
+//! Inside, one PNG per page and DPI (`p0001@150.png`); repeated calls
+//! only render what's missing.
+
+use crate::utils::command::{find_on_path, no_window};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+pub const MIN_DPI: u32 = 24;
+pub const MAX_DPI: u32 = 300;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenderedPage {
+    /// 1-based.
+    pub page: u32,
+    pub path: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PdfRender {
+    pub page_count: u32,
+    pub dpi: u32,
+    pub pages: Vec<RenderedPage>,
+}
+
+#[derive(Debug, Clone)]
+enum Renderer {
+    Poppler { pdftoppm: PathBuf, pdfinfo: PathBuf },
+    Mupdf { mutool: PathBuf },
+}
+
+fn locate_renderer() -> Result<Renderer, String> {
+    if let (Some(pdftoppm), Some(pdfinfo)) = (find_on_path("pdftoppm"), find_on_path("pdfinfo")) {
+        return Ok(Renderer::Poppler { pdftoppm, pdfinfo });
+    }
+    if let Some(mutool) = find_on_path("mutool") {
+        return Ok(Renderer::Mupdf { mutool });
+    }
+    Err(
+        "pdftoppm (poppler) or mutool (MuPDF) not found on PATH. Install poppler via \
+         WinGet/Homebrew/apt (`poppler-utils` on Debian/Ubuntu) and retry."
+            .to_string(),
+    )
+}
+
+fn run(cmd: &mut std::process::Command, what: &str) -> Result<String, String> {
+    let output = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("{what} spawn: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "{what} exited {:?}: {}",
+            output.status.code(),
+            stderr.trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// `Pages:          24` line of `pdfinfo` output.
+pub fn parse_pdfinfo_pages(stdout: &str) -> Option<u32> {
+    stdout
+        .lines()
+        .find_map(|l| l.strip_prefix("Pages:"))
+        .and_then(|v| v.trim().parse().ok())
+}
+
+fn page_count(renderer: &Renderer, pdf: &Path) -> Result<u32, String> {
+    let count = match renderer {
+        Renderer::Poppler { pdfinfo, .. } => {
+            parse_pdfinfo_pages(&run(no_window(pdfinfo).arg(pdf), "pdfinfo")?)
+        }
+        Renderer::Mupdf { mutool } => run(
+            no_window(mutool)
+                .arg("show")
+                .arg(pdf)
+                .arg("trailer/Root/Pages/Count"),
+            "mutool",
+        )?
+        .trim()
+        .parse()
+        .ok(),
+    };
+    count.ok_or_else(|| format!("無法讀取 PDF 頁數: {}", pdf.display()))
+}
+
+fn render_page(
+    renderer: &Renderer,
+    pdf: &Path,
+    page: u32,
+    dpi: u32,
+    dest: &Path,
+) -> Result<(), String> {
+    let part = dest.with_extension("part.png");
+    let result = match renderer {
+        Renderer::Poppler { pdftoppm, .. } => {
+            // -singlefile writes `<prefix>.png` with no page suffix.
+            let prefix = part.with_extension("");
+            run(
+                no_window(pdftoppm)
+                    .args(["-png", "-singlefile", "-r", &dpi.to_string()])
+                    .args(["-f", &page.to_string(), "-l", &page.to_string()])
+                    .arg(pdf)
+                    .arg(&prefix),
+                "pdftoppm",
+            )
+        }
+        Renderer::Mupdf { mutool } => run(
+            no_window(mutool)
+                .args(["draw", "-q", "-r", &dpi.to_string(), "-o"])
+                .arg(&part)
+                .arg(pdf)
+                .arg(page.to_string()),
+            "mutool",
+        ),
+    };
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&part);
+        return Err(e);
+    }
+    std::fs::rename(&part, dest).map_err(|e| {
+        let _ = std::fs::remove_file(&part);
+        format!("rename {} -> {}: {e}", part.display(), dest.display())
+    })
+}
+
+/// Width and height from the PNG IHDR chunk.
+pub fn png_dimensions(path: &Path) -> Option<(u32, u32)> {
+    use std::io::Read;
+    let mut head = [0u8; 24];
+    std::fs::File::open(path).ok()?.read_exact(&mut head).ok()?;
+    if &head[..8] != b"\x89PNG\r\n\x1a\n" || &head[12..16] != b"IHDR" {
+        return None;
+    }
+    let w = u32::from_be_bytes(head[16..20].try_into().ok()?);
+    let h = u32::from_be_bytes(head[20..24].try_into().ok()?);
+    Some((w, h))
+}
+
+/// Cache directory name for `pdf`: changes with its path, size or mtime.
+pub fn cache_key(pdf: &Path) -> Result<String, String> {
+    let meta = std::fs::metadata(pdf).map_err(|e| format!("stat {}: {e}", pdf.display()))?;
+    let mtime_ms = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let canonical = std::fs::canonicalize(pdf).unwrap_or_else(|_| pdf.to_path_buf());
+    let mut hasher = Sha256::new();
+    hasher.update(canonical.to_string_lossy().as_bytes());
+    hasher.update(meta.len().to_le_bytes());
+    hasher.update(mtime_ms.to_le_bytes());
+    let digest = hasher.finalize();
+    Ok(digest[..8].iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// Sorted, de-duplicated pages to render; `None` means all of them.
+pub fn select_pages(requested: Option<&[u32]>, page_count: u32) -> Result<Vec<u32>, String> {
+    let Some(requested) = requested else {
+        return Ok((1..=page_count).collect());
+    };
+    let mut pages = requested.to_vec();
+    pages.sort_unstable();
+    pages.dedup();
+    if let Some(bad) = pages.iter().find(|&&p| p == 0 || p > page_count) {
+        return Err(format!("頁碼超出範圍: {} (共 {} 頁)", bad, page_count));
+    }
+    Ok(pages)
+}
+
+pub fn render_pdf_pages_inner(
+    pdf: &Path,
+    cache_root: &Path,
+    dpi: u32,
+    pages: Option<&[u32]>,
+) -> Result<PdfRender, String> {
+    if !pdf.is_file() {
+        return Err(format!("File not found: {}", pdf.display()));
+    }
+    let dpi = dpi.clamp(MIN_DPI, MAX_DPI);
+    let renderer = locate_renderer()?;
+    let page_count = page_count(&renderer, pdf)?;
+    let pages = select_pages(pages, page_count)?;
+
+    let dir = cache_root.join(cache_key(pdf)?);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("create {}: {e}", dir.display()))?;
+
+    let mut out = Vec::with_capacity(pages.len());
+    for page in pages {
+        let dest = dir.join(format!("p{:04}@{}.png", page, dpi));
+        if !dest.is_file() {
+            render_page(&renderer, pdf, page, dpi, &dest)?;
+        }
+        let (width, height) =
+            png_dimensions(&dest).ok_or_else(|| format!("無效的 PNG 輸出: {}", dest.display()))?;
+        out.push(RenderedPage {
+            page,
+            path: dest.to_string_lossy().to_string(),
+            width,
+            height,
+        });
+    }
+    Ok(PdfRender {
+        page_count,
+        dpi,
+        pages: out,
+    })
+}
+
+/// Render `pages` (1-based; all when omitted) of the PDF at `path` to
+/// PNG at `dpi`, reusing cached renders.
+#[tauri::command]
+pub async fn render_pdf_pages(
+    path: String,
+    dpi: u32,
+    pages: Option<Vec<u32>>,
+) -> Result<PdfRender, String> {
+    let cache_root = crate::paths::get_cache_dir()?.join("pdf-pages");
+    tokio::task::spawn_blocking(move || {
+        render_pdf_pages_inner(Path::new(&path), &cache_root, dpi, pages.as_deref())
+    })
+    .await
+    .map_err(|e| format!("render task join error: {e}"))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn pdfinfo_page_line() {
+        let out = "Title:          Week 3\nProducer:       LibreOffice\nPages:          24\nEncrypted:      no\n";
+        assert_eq!(parse_pdfinfo_pages(out), Some(24));
+        assert_eq!(parse_pdfinfo_pages("Title: x\n"), None);
+    }
+
+    #[test]
+    fn page_selection() {
+        assert_eq!(select_pages(None, 3).unwrap(), vec![1, 2, 3]);
+        assert_eq!(select_pages(Some(&[3, 1, 3]), 3).unwrap(), vec![1, 3]);
+        assert!(select_pages(Some(&[0]), 3).is_err());
+        assert!(select_pages(Some(&[4]), 3).is_err());
+    }
+
+    #[test]
+    fn png_header_dimensions() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("p.png");
+        let mut bytes = b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec();
+        bytes.extend_from_slice(&800u32.to_be_bytes());
+        bytes.extend_from_slice(&600u32.to_be_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+        assert_eq!(png_dimensions(&path), Some((800, 600)));
+        std::fs::write(&path, b"not a png at all, definitely not").unwrap();
+        assert_eq!(png_dimensions(&path), None);
+    }
+
+    #[test]
+    fn cache_key_follows_content_changes() {
+        let dir = TempDir::new().unwrap();
+        let pdf = dir.path().join("a.pdf");
+        std::fs::write(&pdf, b"%PDF-1.4 one").unwrap();
+        let first = cache_key(&pdf).unwrap();
+        assert_eq!(first, cache_key(&pdf).unwrap());
+        std::fs::write(&pdf, b"%PDF-1.4 two, longer").unwrap();
+        assert_ne!(first, cache_key(&pdf).unwrap());
+    }
+}
@@ -0,0 +1,400 @@
+//! Pause, resume and cancel for model downloads, and picking up the
+//! ones a quit cut short.
+//!
+//! The downloads that go through `whisper::download` (Whisper, Nemotron,
+//! TranslateGemma) continue a partial file with an HTTP Range request,
+//! so stopping one loses nothing. Each runs as a pausable
+//! [`tasks::Task`] under the fixed id of its [`DownloadSource`] and is
+//! recorded in [`STATE_FILE`] until it completes, fails or is cancelled:
+//!
+//! * pausing stops the task and keeps the partial file and the record,
+//! * cancelling stops it and deletes both,
+//! * a record that wasn't paused on launch is a download the app quit
+//!   in the middle of, and `lib.rs` starts it again.
+//!
+//! Starting, resuming and the launch-time restart all go through the
+//! same download commands; this module only keeps the bookkeeping.
+//!
+//! [`STATE_FILE`] is the only record of unfinished downloads. The setup
+//! wizard's files are kept in it too, as [`DownloadSource::Setup`], with
+//! the bytes done at the last [`checkpoint`]; the wizard picks those up
+//! on launch, not `lib.rs`, and they don't show in the downloads list.
+//! The file is replaced with a rename, so a crash mid-write leaves the
+//! previous state rather than a truncated one that reads as empty.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tauri::AppHandle;
+
+use crate::asr::parakeet_model;
+use crate::paths;
+use crate::tasks;
+use crate::translation::gemma_model;
+use crate::whisper::download::{self, ModelDownloadConfig};
+
+/// `{app_data_dir}/downloads.json`.
+pub const STATE_FILE: &str = "downloads.json";
+
+/// What a resumable download fetches; enough to start it again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case")]
+pub enum DownloadSource {
+    Whisper {
+        model_type: String,
+        output_dir: String,
+    },
+    Parakeet {
+        variant: parakeet_model::Variant,
+    },
+    Gemma {
+        variant: gemma_model::Variant,
+    },
+    /// One file a setup wizard run downloads (see `setup::installer`).
+    Setup {
+        /// The setup requirement it belongs to, e.g. `whisper_model`.
+        requirement_id: String,
+        url: String,
+        dest: String,
+    },
+}
+
+impl DownloadSource {
+    /// Task id the download runs under, the same for every attempt.
+    pub fn task_id(&self) -> String {
+        match self {
+            Self::Whisper { model_type, .. } => format!("download:whisper:{model_type}"),
+            Self::Parakeet { variant } => format!("download:{}", variant.dir_name()),
+            Self::Gemma { variant } => format!("download:{}", variant.filename()),
+            Self::Setup { dest, .. } => format!("download:setup:{dest}"),
+        }
+    }
+
+    pub fn is_setup(&self) -> bool {
+        matches!(self, Self::Setup { .. })
+    }
+
+    /// The files it writes.
+    pub fn configs(&self) -> Result<Vec<ModelDownloadConfig>, String> {
+        match self {
+            Self::Whisper {
+                model_type,
+                output_dir,
+            } => download::config_for(model_type, Path::new(output_dir))
+                .map(|c| vec![c])
+                .ok_or_else(|| format!("不支持的模型類型: {}", model_type)),
+            Self::Parakeet { variant } => parakeet_model::all_download_configs(*variant),
+            Self::Gemma { variant } => Ok(vec![gemma_model::download_config_for(*variant)?]),
+            Self::Setup { url, dest, .. } => Ok(vec![ModelDownloadConfig {
+                url: url.clone(),
+                output_path: PathBuf::from(dest),
+                expected_size: None,
+                mirrors: Vec::new(),
+            }]),
+        }
+    }
+}
+
+/// A download that was started and hasn't completed, failed or been
+/// cancelled yet.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingDownload {
+    pub id: String,
+    pub source: DownloadSource,
+    /// `false` while running, or if the app quit mid-download.
+    pub paused: bool,
+    /// Bytes on disk at the last [`checkpoint`].
+    #[serde(default)]
+    pub bytes_done: u64,
+    #[serde(default)]
+    pub total_bytes: Option<u64>,
+}
+
+/// Serialises read-modify-write of [`STATE_FILE`].
+static STATE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Ids `cancel` was called for while they were running; the download
+/// deletes its partial files once it has stopped writing them.
+fn discard() -> std::sync::MutexGuard<'static, HashSet<String>> {
+    static DISCARD: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+    DISCARD
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+}
+
+fn state_path() -> Result<PathBuf, String> {
+    Ok(paths::get_app_data_dir()?.join(STATE_FILE))
+}
+
+fn read_state(path: &Path) -> Vec<PendingDownload> {
+    std::fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn write_state(path: &Path, pending: &[PendingDownload]) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(pending).map_err(|e| e.to_string())?;
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, json).map_err(|e| format!("write {}: {}", tmp.display(), e))?;
+    std::fs::rename(&tmp, path).map_err(|e| {
+        let _ = std::fs::remove_file(&tmp);
+        format!("write {}: {}", path.display(), e)
+    })
+}
+
+fn modify(f: impl FnOnce(&mut Vec<PendingDownload>)) {
+    let _guard = STATE_LOCK.lock().unwrap_or_else(|p| p.into_inner());
+    let result = state_path().and_then(|path| {
+        let mut pending = read_state(&path);
+        f(&mut pending);
+        write_state(&path, &pending)
+    });
+    if let Err(e) = result {
+        log::warn!("[downloads] could not save download state: {}", e);
+    }
+}
+
+/// Record `source` last. Its progress is kept unless it now comes from
+/// somewhere else.
+fn upsert(pending: &mut Vec<PendingDownload>, source: &DownloadSource, paused: bool) {
+    let id = source.task_id();
+    let (bytes_done, total_bytes) = pending
+        .iter()
+        .find(|p| p.id == id && p.source == *source)
+        .map_or((0, None), |p| (p.bytes_done, p.total_bytes));
+    pending.retain(|p| p.id != id);
+    pending.push(PendingDownload {
+        id,
+        source: source.clone(),
+        paused,
+        bytes_done,
+        total_bytes,
+    });
+}
+
+/// Like [`upsert`], but a download already recorded keeps its place.
+fn enqueue(pending: &mut Vec<PendingDownload>, source: &DownloadSource) {
+    let id = source.task_id();
+    match pending.iter_mut().find(|p| p.id == id) {
+        Some(entry) if entry.source == *source => {}
+        Some(entry) => {
+            // A different URL means different bytes.
+            entry.source = source.clone();
+            entry.bytes_done = 0;
+            entry.total_bytes = None;
+        }
+        None => pending.push(PendingDownload {
+            id,
+            source: source.clone(),
+            paused: false,
+            bytes_done: 0,
+            total_bytes: None,
+        }),
+    }
+}
+
+/// Every pending download, in the order they were started.
+pub fn pending() -> Vec<PendingDownload> {
+    let _guard = STATE_LOCK.lock().unwrap_or_else(|p| p.into_inner());
+    state_path().map(|p| read_state(&p)).unwrap_or_default()
+}
+
+pub fn find(id: &str) -> Option<PendingDownload> {
+    pending().into_iter().find(|p| p.id == id)
+}
+
+/// Record a download that's about to run without registering a task,
+/// for the setup wizard's files. One already recorded keeps its place.
+pub fn queue(source: &DownloadSource) {
+    modify(|pending| enqueue(pending, source));
+}
+
+/// Save how far a recorded download has got.
+pub fn checkpoint(id: &str, bytes_done: u64, total_bytes: Option<u64>) {
+    modify(|pending| {
+        if let Some(entry) = pending.iter_mut().find(|p| p.id == id) {
+            entry.bytes_done = bytes_done;
+            entry.total_bytes = total_bytes;
+        }
+    });
+}
+
+/// Drop the record of a download that completed or was given up.
+pub fn forget(id: &str) {
+    modify(|pending| pending.retain(|p| p.id != id));
+}
+
+/// Register the task for a download that's about to run and record it.
+/// Fails if the same download is already running.
+pub fn start(
+    app: &AppHandle,
+    source: &DownloadSource,
+    label: impl Into<String>,
+) -> Result<tasks::Task, String> {
+    let task = tasks::Task::start_with_id(app, source.task_id(), tasks::TaskKind::Download, label)?
+        .pausable();
+    discard().remove(task.id());
+    modify(|pending| upsert(pending, source, false));
+    Ok(task)
+}
+
+/// Update the record once the download has stopped, for whatever reason.
+pub fn settle<T>(source: &DownloadSource, result: &Result<T, String>) {
+    let id = source.task_id();
+    match result {
+        Err(e) if e == tasks::PAUSED => modify(|pending| upsert(pending, source, true)),
+        _ => {
+            if discard().remove(&id) {
+                remove_partial_files(source);
+            }
+            forget(&id);
+        }
+    }
+}
+
+/// Stop a running download; `settle` finishes the job.
+pub fn pause(id: &str) -> Result<bool, String> {
+    tasks::pause(id)
+}
+
+/// Stop a download and delete what it has written so far. Returns
+/// `false` if there was nothing to cancel.
+pub fn cancel(id: &str) -> Result<bool, String> {
+    discard().insert(id.to_string());
+    match tasks::cancel(id) {
+        Ok(true) => return Ok(true),
+        Ok(false) => {}
+        Err(e) => {
+            discard().remove(id);
+            return Err(e);
+        }
+    }
+    // Not running: paused, or left over from a quit.
+    discard().remove(id);
+    let Some(entry) = find(id) else {
+        return Ok(false);
+    };
+    remove_partial_files(&entry.source);
+    forget(id);
+    Ok(true)
+}
+
+/// Files that were already complete (e.g. the small files of a
+/// multi-file model) are kept.
+fn remove_partial_files(source: &DownloadSource) {
+    let configs = match source.configs() {
+        Ok(configs) => configs,
+        Err(e) => {
+            log::warn!("[downloads] cannot resolve files of {:?}: {}", source, e);
+            return;
+        }
+    };
+    for config in configs {
+        let _ = std::fs::remove_file(download::segmented_part_path(&config.output_path));
+        let _ = std::fs::remove_file(download::segments_path(&config.output_path));
+        let Ok(meta) = std::fs::metadata(&config.output_path) else {
+            continue;
+        };
+        if config.expected_size != Some(meta.len()) {
+            if let Err(e) = std::fs::remove_file(&config.output_path) {
+                log::warn!(
+                    "[downloads] could not delete {}: {}",
+                    config.output_path.display(),
+                    e
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parakeet() -> DownloadSource {
+        DownloadSource::Parakeet {
+            variant: parakeet_model::Variant::Int8,
+        }
+    }
+
+    #[test]
+    fn state_round_trips_and_keeps_one_record_per_download() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(STATE_FILE);
+        assert!(read_state(&path).is_empty());
+
+        let whisper = DownloadSource::Whisper {
+            model_type: "small".into(),
+            output_dir: "/models/whisper".into(),
+        };
+        let mut pending = Vec::new();
+        upsert(&mut pending, &parakeet(), false);
+        upsert(&mut pending, &whisper, false);
+        upsert(&mut pending, &parakeet(), true);
+        write_state(&path, &pending).unwrap();
+
+        let loaded = read_state(&path);
+        assert!(!path.with_extension("json.tmp").exists());
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].source, whisper);
+        assert_eq!(loaded[1].id, "download:parakeet-nemotron-int8");
+        assert!(loaded[1].paused);
+    }
+
+    #[test]
+    fn setup_files_keep_their_place_when_queued_again() {
+        let setup = |url: &str, dest: &str| DownloadSource::Setup {
+            requirement_id: "embedding_model".into(),
+            url: url.into(),
+            dest: dest.into(),
+        };
+        let mut pending = Vec::new();
+        enqueue(
+            &mut pending,
+            &setup("https://a/model.safetensors", "/m/model"),
+        );
+        enqueue(
+            &mut pending,
+            &setup("https://a/tokenizer.json", "/m/tokenizer"),
+        );
+        pending[0].bytes_done = 4_096;
+        pending[0].total_bytes = Some(133_466_304);
+
+        // A re-run after a restart keeps the progress...
+        enqueue(
+            &mut pending,
+            &setup("https://a/model.safetensors", "/m/model"),
+        );
+        assert_eq!(pending[0].bytes_done, 4_096);
+
+        // ...unless the file now comes from a mirror.
+        enqueue(
+            &mut pending,
+            &setup("https://mirror/model.safetensors", "/m/model"),
+        );
+
+        assert_eq!(pending.len(), 2);
+        assert_eq!(pending[0].id, "download:setup:/m/model");
+        assert_eq!(
+            pending[0].source,
+            setup("https://mirror/model.safetensors", "/m/model")
+        );
+        assert_eq!((pending[0].bytes_done, pending[0].total_bytes), (0, None));
+        assert!(pending.iter().all(|p| p.source.is_setup() && !p.paused));
+    }
+
+    #[test]
+    fn sources_serialize_with_a_model_tag() {
+        let json = serde_json::to_value(DownloadSource::Gemma {
+            variant: gemma_model::Variant::B12,
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "model": "gemma", "variant": "b12" })
+        );
+    }
+}
@@ -1,5 +1,4 @@
 use futures_util::StreamExt;
-use reqwest::Client;
 /**
  * Unified Downloader
  *
@@ -34,7 +33,7 @@ where
 
     println!("[Downloader] 開始下載: {} -> {:?}", url, dest);
 
-    let client = Client::builder()
+    let client = super::proxy::client_builder()
         .timeout(std::time::Duration::from_secs(1800)) // 30 minutes
         .build()
         .map_err(|e| format!("創建 HTTP 客戶端失敗: {}", e))?;
@@ -70,6 +69,7 @@ where
             .map_err(|e| format!("寫入文件失敗: {}", e))?;
 
         downloaded += chunk.len() as u64;
+        super::throttle::throttle(chunk.len()).await;
 
         // Report progress every 100ms or at completion
         let now = std::time::Instant::now();
@@ -0,0 +1,304 @@
+//! The models on disk, for the model manager in Settings.
+//!
+//! Everything lives under `{app_data_dir}/models/`. The category
+//! directories in [`CATEGORY_DIRS`] hold one model per entry (a Whisper
+//! `.bin`, a TranslateGemma `.gguf`, an M2M100 or embedding directory);
+//! any other top-level entry, such as `parakeet-nemotron-int8/`, is a
+//! model by itself. A model's id is its path relative to the models
+//! directory, e.g. `whisper/ggml-base.bin`.
+//!
+//! When each model was last loaded is kept in [`USAGE_FILE`];
+//! [`mark_used`] is called from the places that load one.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::asr::parakeet_model;
+use crate::paths;
+use crate::translation::gemma_model;
+use crate::whisper::download;
+
+/// Sub-directories of the models directory that group several models.
+const CATEGORY_DIRS: &[&str] = &["whisper", "llm", "translation", "embedding"];
+/// `{app_data_dir}/model_usage.json`: model id → when it was last loaded.
+pub const USAGE_FILE: &str = "model_usage.json";
+
+/// Serialises read-modify-write of [`USAGE_FILE`].
+static USAGE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InstalledModel {
+    /// Path relative to the models directory; what [`delete`] takes.
+    pub id: String,
+    /// `whisper`, `parakeet`, `translategemma`, `translation`,
+    /// `embedding`, or `other` for anything unrecognised.
+    pub engine: String,
+    pub variant: String,
+    pub path: String,
+    /// Bytes on disk, every file of the model included.
+    pub size: u64,
+    /// RFC 3339; `None` if it hasn't been loaded since this was tracked.
+    pub last_used: Option<String>,
+}
+
+/// Every model under the models directory, by id.
+pub fn list() -> Result<Vec<InstalledModel>, String> {
+    let models_dir = paths::get_models_dir()?;
+    let usage = read_usage(&usage_path()?);
+    Ok(scan(&models_dir, &usage))
+}
+
+/// Delete model `id` (and any partial download of it). Returns the
+/// bytes freed.
+pub fn delete(id: &str) -> Result<u64, String> {
+    let models_dir = paths::get_models_dir()?;
+    // Only ever delete something `list` would show, so an id can't
+    // point outside the models directory.
+    let (_, path) = entries(&models_dir)
+        .into_iter()
+        .find(|(entry_id, _)| entry_id == id)
+        .ok_or_else(|| format!("找不到模型: {}", id))?;
+    let size = size_of(&path);
+    let result = if path.is_dir() {
+        std::fs::remove_dir_all(&path)
+    } else {
+        std::fs::remove_file(&path)
+    };
+    result.map_err(|e| format!("刪除模型失敗 {}: {}", path.display(), e))?;
+    let _ = std::fs::remove_file(download::segmented_part_path(&path));
+    let _ = std::fs::remove_file(download::segments_path(&path));
+
+    modify_usage(|usage| {
+        usage.remove(id);
+    });
+    log::info!("[models] deleted {} ({} bytes)", id, size);
+    Ok(size)
+}
+
+/// Record that the model at `path`, or the model a file at `path`
+/// belongs to, was loaded just now. Paths outside the models directory
+/// are ignored.
+pub fn mark_used(path: &Path) {
+    let Ok(models_dir) = paths::get_models_dir() else {
+        return;
+    };
+    let Some(id) = model_id(&models_dir, path) else {
+        return;
+    };
+    modify_usage(|usage| {
+        usage.insert(id, chrono::Local::now().to_rfc3339());
+    });
+}
+
+fn scan(models_dir: &Path, usage: &BTreeMap<String, String>) -> Vec<InstalledModel> {
+    let mut models: Vec<_> = entries(models_dir)
+        .into_iter()
+        .map(|(id, path)| {
+            let (engine, variant) = describe(&id);
+            InstalledModel {
+                engine: engine.to_string(),
+                variant,
+                size: size_of(&path),
+                path: path.to_string_lossy().into_owned(),
+                last_used: usage.get(&id).cloned(),
+                id,
+            }
+        })
+        .collect();
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    models
+}
+
+/// `(id, path)` of every model under `models_dir`. Hidden files, the
+/// leftovers of a segmented download and staged updates aren't models.
+fn entries(models_dir: &Path) -> Vec<(String, PathBuf)> {
+    fn visible(dir: &Path) -> Vec<(String, PathBuf)> {
+        let Ok(read) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        read.filter_map(|entry| entry.ok())
+            .filter_map(|entry| Some((entry.file_name().into_string().ok()?, entry.path())))
+            .filter(|(name, _)| {
+                !name.starts_with('.')
+                    && !name.ends_with(".download")
+                    && !name.ends_with(".segments")
+                    && !name.ends_with(super::updates::STAGED_SUFFIX)
+            })
+            .collect()
+    }
+
+    let mut entries = Vec::new();
+    for (name, path) in visible(models_dir) {
+        if CATEGORY_DIRS.contains(&name.as_str()) && path.is_dir() {
+            entries.extend(
+                visible(&path)
+                    .into_iter()
+                    .map(|(sub, path)| (format!("{}/{}", name, sub), path)),
+            );
+        } else {
+            entries.push((name, path));
+        }
+    }
+    entries
+}
+
+/// The id of the model `path` is (or is inside of).
+fn model_id(models_dir: &Path, path: &Path) -> Option<String> {
+    let mut parts = path
+        .strip_prefix(models_dir)
+        .ok()?
+        .components()
+        .map(|c| match c {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        });
+    let first = parts.next()??;
+    if CATEGORY_DIRS.contains(&first) {
+        Some(format!("{}/{}", first, parts.next()??))
+    } else {
+        Some(first.to_string())
+    }
+}
+
+/// `(engine, variant)` for a model id.
+fn describe(id: &str) -> (&'static str, String) {
+    let (category, name) = id.split_once('/').unwrap_or(("", id));
+    match category {
+        "whisper" => (
+            "whisper",
+            name.trim_start_matches("ggml-")
+                .trim_end_matches(".bin")
+                .to_string(),
+        ),
+        "llm" => (
+            "translategemma",
+            gemma_model::Variant::all()
+                .iter()
+                .find(|v| v.filename() == name)
+                .map_or_else(|| name.to_string(), |v| v.label().to_string()),
+        ),
+        "translation" => ("translation", name.to_string()),
+        "embedding" => ("embedding", name.to_string()),
+        _ => match parakeet_model::Variant::all()
+            .iter()
+            .find(|v| v.dir_name() == name)
+        {
+            Some(v) => ("parakeet", v.label().to_string()),
+            None => ("other", name.to_string()),
+        },
+    }
+}
+
+fn size_of(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.metadata().ok())
+        .filter(|m| m.is_file())
+        .map(|m| m.len())
+        .sum()
+}
+
+fn usage_path() -> Result<PathBuf, String> {
+    Ok(paths::get_app_data_dir()?.join(USAGE_FILE))
+}
+
+fn read_usage(path: &Path) -> BTreeMap<String, String> {
+    std::fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn modify_usage(f: impl FnOnce(&mut BTreeMap<String, String>)) {
+    let _guard = USAGE_LOCK.lock().unwrap_or_else(|p| p.into_inner());
+    let result = usage_path().and_then(|path| {
+        let mut usage = read_usage(&path);
+        f(&mut usage);
+        let json = serde_json::to_vec_pretty(&usage).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json).map_err(|e| format!("write {}: {}", path.display(), e))
+    });
+    if let Err(e) = result {
+        log::warn!("[models] could not save model usage: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_models_by_category_and_skips_partials() {
+        let dir = tempfile::tempdir().unwrap();
+        let models = dir.path();
+        std::fs::create_dir_all(models.join("whisper")).unwrap();
+        std::fs::write(models.join("whisper/ggml-base.bin"), vec![0u8; 10]).unwrap();
+        std::fs::write(models.join("whisper/ggml-large.bin.download"), b"x").unwrap();
+        std::fs::write(models.join("whisper/ggml-large.bin.segments"), b"x").unwrap();
+        std::fs::create_dir_all(models.join("llm")).unwrap();
+        std::fs::write(models.join("llm/translategemma-4b_Q4_K_M.gguf"), b"gguf").unwrap();
+        std::fs::create_dir_all(models.join("parakeet-nemotron-int8")).unwrap();
+        std::fs::write(
+            models.join("parakeet-nemotron-int8/encoder.onnx"),
+            vec![0u8; 7],
+        )
+        .unwrap();
+        std::fs::write(
+            models.join("parakeet-nemotron-int8/tokenizer.model"),
+            b"tok",
+        )
+        .unwrap();
+        std::fs::write(models.join(".DS_Store"), b"").unwrap();
+
+        let mut usage = BTreeMap::new();
+        usage.insert(
+            "whisper/ggml-base.bin".to_string(),
+            "2026-10-15T09:00:00+08:00".to_string(),
+        );
+        let listed = scan(models, &usage);
+        let summary: Vec<_> = listed
+            .iter()
+            .map(|m| (m.id.as_str(), m.engine.as_str(), m.variant.as_str(), m.size))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    "llm/translategemma-4b_Q4_K_M.gguf",
+                    "translategemma",
+                    "4B",
+                    4
+                ),
+                ("parakeet-nemotron-int8", "parakeet", "int8", 10),
+                ("whisper/ggml-base.bin", "whisper", "base", 10),
+            ]
+        );
+        assert_eq!(
+            listed[2].last_used.as_deref(),
+            Some("2026-10-15T09:00:00+08:00")
+        );
+        assert_eq!(listed[0].last_used, None);
+    }
+
+    #[test]
+    fn model_id_maps_files_to_their_model() {
+        let models = Path::new("/data/models");
+        assert_eq!(
+            model_id(models, &models.join("parakeet-nemotron-fp32/encoder.onnx")).as_deref(),
+            Some("parakeet-nemotron-fp32")
+        );
+        assert_eq!(
+            model_id(
+                models,
+                &models.join("embedding/bge-small-en-v1.5/model.safetensors")
+            )
+            .as_deref(),
+            Some("embedding/bge-small-en-v1.5")
+        );
+        assert_eq!(model_id(models, &models.join("whisper")), None);
+        assert_eq!(model_id(models, &models.join("../secrets")), None);
+        assert_eq!(model_id(models, Path::new("/elsewhere/model.bin")), None);
+    }
+}
@@ -0,0 +1,242 @@
+//! Alternative hosts for model downloads.
+//!
+//! Every [`ModelDownloadConfig`] has its origin `url` plus the `mirrors`
+//! known for that file (an hf-mirror.com copy of a Hugging Face file, a
+//! GitHub Releases copy, ...). On top of those the user can add a custom
+//! Hugging Face endpoint (a self-hosted or company mirror, the same thing
+//! `HF_ENDPOINT` points at) and choose which host is tried first
+//! ([`MirrorSettings`], stored under [`MIRRORS_SETTING`]). Many users in
+//! mainland China can't reach huggingface.co at all.
+//!
+//! `whisper::download::download_model` walks [`candidates`] in order and
+//! moves on to the next host when one refuses the file (HTTP 4xx) or
+//! can't be reached in time; the partial file carries over, since every
+//! host serves the same bytes.
+
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+use crate::whisper::download::ModelDownloadConfig;
+
+/// Settings key holding [`MirrorSettings`] as JSON. Per install, so
+/// stored for the default user.
+pub const MIRRORS_SETTING: &str = "download.mirrors";
+const SETTINGS_USER: &str = "default_user";
+
+const HF_ORIGIN: &str = "https://huggingface.co";
+const HF_MIRROR: &str = "https://hf-mirror.com";
+
+/// Which host a download tries first. The others stay as fallbacks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PreferredHost {
+    /// The URL the model is published at.
+    #[default]
+    Origin,
+    /// hf-mirror.com, for files published on Hugging Face.
+    HfMirror,
+    /// [`MirrorSettings::custom_url`].
+    Custom,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirrorSettings {
+    #[serde(default)]
+    pub prefer: PreferredHost,
+    /// Base URL that stands in for `https://huggingface.co`, e.g.
+    /// `https://hf.example.edu`.
+    #[serde(default)]
+    pub custom_url: Option<String>,
+}
+
+impl MirrorSettings {
+    fn validate(mut self) -> Result<Self, String> {
+        self.custom_url = self
+            .custom_url
+            .map(|url| url.trim().trim_end_matches('/').to_string())
+            .filter(|url| !url.is_empty());
+        if let Some(url) = &self.custom_url {
+            if !(url.starts_with("https://") || url.starts_with("http://")) {
+                return Err(format!(
+                    "自訂鏡像網址必須以 http:// 或 https:// 開頭: {}",
+                    url
+                ));
+            }
+        }
+        if self.prefer == PreferredHost::Custom && self.custom_url.is_none() {
+            return Err("選擇自訂鏡像時必須填寫網址".to_string());
+        }
+        Ok(self)
+    }
+}
+
+/// The hf-mirror.com copy of a Hugging Face file; for the `mirrors` of
+/// download configs. Empty for files hosted elsewhere.
+pub fn hf_mirrors(url: &str) -> Vec<String> {
+    rehost(url, HF_MIRROR).into_iter().collect()
+}
+
+/// `url` on `host` instead of huggingface.co.
+fn rehost(url: &str, host: &str) -> Option<String> {
+    url.strip_prefix(HF_ORIGIN)
+        .filter(|path| path.starts_with('/'))
+        .map(|path| format!("{}{}", host, path))
+}
+
+/// Every URL `config` can be fetched from, in the order to try them.
+pub fn order(config: &ModelDownloadConfig, settings: &MirrorSettings) -> Vec<String> {
+    let custom = settings
+        .custom_url
+        .as_deref()
+        .and_then(|host| rehost(&config.url, host));
+    let mut urls = vec![config.url.clone()];
+    urls.extend(config.mirrors.iter().cloned());
+    urls.extend(custom.clone());
+
+    let first = match settings.prefer {
+        PreferredHost::Origin => None,
+        PreferredHost::HfMirror => urls.iter().position(|u| u.starts_with(HF_MIRROR)),
+        PreferredHost::Custom => custom.and_then(|c| urls.iter().position(|u| *u == c)),
+    };
+    if let Some(i) = first {
+        let url = urls.remove(i);
+        urls.insert(0, url);
+    }
+    let mut seen = std::collections::HashSet::new();
+    urls.retain(|u| seen.insert(u.clone()));
+    urls
+}
+
+/// [`order`] with the saved settings; the origin first if they can't be
+/// read.
+pub async fn candidates(config: &ModelDownloadConfig) -> Vec<String> {
+    let settings = settings().await.unwrap_or_else(|e| {
+        log::warn!("[downloads] {}; using default mirror settings", e);
+        MirrorSettings::default()
+    });
+    order(config, &settings)
+}
+
+/// The saved settings; the defaults if none are saved.
+pub async fn settings() -> Result<MirrorSettings, String> {
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let Some(json) = db
+        .get_setting(MIRRORS_SETTING, SETTINGS_USER)
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(MirrorSettings::default());
+    };
+    serde_json::from_str(&json).map_err(|e| format!("無法解析下載鏡像設定: {}", e))
+}
+
+/// Validate and save `settings`; returns them normalised.
+pub async fn set_settings(settings: MirrorSettings) -> Result<MirrorSettings, String> {
+    let settings = settings.validate()?;
+    let json = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    db.save_setting(MIRRORS_SETTING, &json, SETTINGS_USER)
+        .map_err(|e| e.to_string())?;
+    log::info!("[downloads] mirror settings set to {}", json);
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin";
+
+    fn config() -> ModelDownloadConfig {
+        ModelDownloadConfig {
+            url: URL.to_string(),
+            output_path: PathBuf::from("ggml-base.bin"),
+            expected_size: None,
+            mirrors: hf_mirrors(URL),
+        }
+    }
+
+    #[test]
+    fn origin_first_then_mirrors_then_custom() {
+        let settings = MirrorSettings {
+            prefer: PreferredHost::Origin,
+            custom_url: Some("https://hf.example.edu".into()),
+        };
+        assert_eq!(
+            order(&config(), &settings),
+            vec![
+                URL.to_string(),
+                "https://hf-mirror.com/ggerganov/whisper.cpp/resolve/main/ggml-base.bin".into(),
+                "https://hf.example.edu/ggerganov/whisper.cpp/resolve/main/ggml-base.bin".into(),
+            ]
+        );
+    }
+
+    #[test]
+    fn preferred_host_moves_to_the_front() {
+        let hf = MirrorSettings {
+            prefer: PreferredHost::HfMirror,
+            custom_url: None,
+        };
+        let urls = order(&config(), &hf);
+        assert!(urls[0].starts_with(HF_MIRROR));
+        assert_eq!(urls[1], URL);
+
+        let custom = MirrorSettings {
+            prefer: PreferredHost::Custom,
+            custom_url: Some("https://hf.example.edu".into()),
+        };
+        assert!(order(&config(), &custom)[0].starts_with("https://hf.example.edu/"));
+    }
+
+    #[test]
+    fn files_off_hugging_face_have_no_derived_mirrors() {
+        let url = "https://github.com/sklonely/ClassNoteAI/releases/download/v0.1.2-models/m2m100-418M-ct2-int8.zip";
+        assert!(hf_mirrors(url).is_empty());
+        let config = ModelDownloadConfig {
+            url: url.to_string(),
+            output_path: PathBuf::from("m2m100.zip"),
+            expected_size: None,
+            mirrors: Vec::new(),
+        };
+        let settings = MirrorSettings {
+            prefer: PreferredHost::Custom,
+            custom_url: Some("https://hf.example.edu".into()),
+        };
+        assert_eq!(order(&config, &settings), vec![url.to_string()]);
+    }
+
+    #[test]
+    fn validate_normalises_and_rejects() {
+        let ok = MirrorSettings {
+            prefer: PreferredHost::Custom,
+            custom_url: Some(" https://hf.example.edu/ ".into()),
+        }
+        .validate()
+        .unwrap();
+        assert_eq!(ok.custom_url.as_deref(), Some("https://hf.example.edu"));
+
+        assert!(MirrorSettings {
+            prefer: PreferredHost::Origin,
+            custom_url: Some("hf.example.edu".into()),
+        }
+        .validate()
+        .is_err());
+        assert!(MirrorSettings {
+            prefer: PreferredHost::Custom,
+            custom_url: Some("  ".into()),
+        }
+        .validate()
+        .is_err());
+    }
+}
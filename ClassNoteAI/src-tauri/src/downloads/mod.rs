@@ -3,9 +3,22 @@
  *
  * Unified download management for all models and files.
  * Consolidates download logic from setup/installer.rs and translation/download.rs.
+ * `control` adds pause / resume / cancel for the resumable model downloads,
+ * `mirrors` the alternative hosts they fall back to, `throttle` the
+ * bandwidth limit every download loop honours, `proxy` the proxy their
+ * HTTP clients go through. `installed` lists and deletes what ended up
+ * on disk, `updates` finds and installs newer releases of it, using the
+ * binary diffs in `patch` when the manifest offers one.
  */
+pub mod control;
 mod downloader;
+pub mod installed;
+pub mod mirrors;
 mod model_manager;
+mod patch;
+pub mod proxy;
+pub mod throttle;
+pub mod updates;
 
 pub use downloader::*;
 pub use model_manager::*;
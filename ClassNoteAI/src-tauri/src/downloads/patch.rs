@@ -0,0 +1,168 @@
+//! Binary patches between model releases.
+//!
+//! A new release of a model often shares most of its bytes with one the
+//! user already has (a re-export, a fixed tokenizer table, a requantized
+//! layer or two). The model manifest can list patches that rebuild the
+//! new file from the old one, so [`super::updates`] downloads only what
+//! changed.
+//!
+//! A patch is gzip-compressed and holds the magic `CNPATCH1` followed by
+//! operations, all integers little-endian u64:
+//!
+//! - `0` END
+//! - `1` COPY `offset` `len`: `len` bytes of the old file from `offset`
+//! - `2` INSERT `len`: the next `len` bytes of the patch
+//!
+//! The release tooling produces them. [`apply`] streams through both
+//! files, so a multi-gigabyte model needs no more memory than a copy
+//! buffer.
+
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+const MAGIC: &[u8; 8] = b"CNPATCH1";
+const OP_END: u8 = 0;
+const OP_COPY: u8 = 1;
+const OP_INSERT: u8 = 2;
+
+/// Rebuild `out` from `old` and `patch`. Returns the bytes written.
+pub fn apply(old: &Path, patch: &Path, out: &Path) -> Result<u64, String> {
+    let old = File::open(old).map_err(|e| format!("無法開啟舊版模型: {}", e))?;
+    let patch = File::open(patch).map_err(|e| format!("無法開啟更新檔: {}", e))?;
+    let out = File::create(out).map_err(|e| format!("無法建立檔案: {}", e))?;
+    apply_streams(
+        BufReader::new(old),
+        GzDecoder::new(BufReader::new(patch)),
+        BufWriter::new(out),
+    )
+}
+
+fn apply_streams(
+    mut old: impl Read + Seek,
+    mut patch: impl Read,
+    mut out: impl Write,
+) -> Result<u64, String> {
+    let invalid = |e: io::Error| format!("更新檔格式錯誤: {}", e);
+    let mut magic = [0u8; 8];
+    patch.read_exact(&mut magic).map_err(invalid)?;
+    if &magic != MAGIC {
+        return Err("不是模型更新檔".to_string());
+    }
+
+    let mut written = 0u64;
+    loop {
+        let mut op = [0u8; 1];
+        patch.read_exact(&mut op).map_err(invalid)?;
+        match op[0] {
+            OP_END => break,
+            OP_COPY => {
+                let offset = read_u64(&mut patch).map_err(invalid)?;
+                let len = read_u64(&mut patch).map_err(invalid)?;
+                old.seek(SeekFrom::Start(offset))
+                    .map_err(|e| format!("無法讀取舊版模型: {}", e))?;
+                let copied = io::copy(&mut (&mut old).take(len), &mut out)
+                    .map_err(|e| format!("寫入失敗: {}", e))?;
+                if copied != len {
+                    return Err("更新檔與舊版模型不符".to_string());
+                }
+                written += len;
+            }
+            OP_INSERT => {
+                let len = read_u64(&mut patch).map_err(invalid)?;
+                let copied = io::copy(&mut (&mut patch).take(len), &mut out)
+                    .map_err(|e| format!("寫入失敗: {}", e))?;
+                if copied != len {
+                    return Err("更新檔不完整".to_string());
+                }
+                written += len;
+            }
+            other => return Err(format!("更新檔格式錯誤: 未知的操作 {}", other)),
+        }
+    }
+    out.flush().map_err(|e| format!("寫入失敗: {}", e))?;
+    Ok(written)
+}
+
+fn read_u64(r: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    enum Op<'a> {
+        Copy(u64, u64),
+        Insert(&'a [u8]),
+    }
+
+    fn encode(ops: &[Op], end: bool) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        for op in ops {
+            match op {
+                Op::Copy(offset, len) => {
+                    bytes.push(OP_COPY);
+                    bytes.extend(offset.to_le_bytes());
+                    bytes.extend(len.to_le_bytes());
+                }
+                Op::Insert(data) => {
+                    bytes.push(OP_INSERT);
+                    bytes.extend((data.len() as u64).to_le_bytes());
+                    bytes.extend(*data);
+                }
+            }
+        }
+        if end {
+            bytes.push(OP_END);
+        }
+        bytes
+    }
+
+    fn run(old: &[u8], patch: &[u8]) -> Result<Vec<u8>, String> {
+        let mut out = Vec::new();
+        apply_streams(Cursor::new(old), patch, &mut out)?;
+        Ok(out)
+    }
+
+    #[test]
+    fn rebuilds_the_new_file_from_copies_and_inserts() {
+        let old = b"layer0:aaaa|layer1:bbbb|vocab";
+        let patch = encode(
+            &[Op::Copy(0, 12), Op::Insert(b"layer1:BBBB"), Op::Copy(23, 6)],
+            true,
+        );
+        assert_eq!(run(old, &patch).unwrap(), b"layer0:aaaa|layer1:BBBB|vocab");
+    }
+
+    #[test]
+    fn rejects_truncated_or_mismatched_patches() {
+        let old = b"short";
+        assert!(run(old, &encode(&[Op::Copy(0, 5)], false)).is_err());
+        assert!(run(old, &encode(&[Op::Copy(2, 10)], true)).is_err());
+        assert!(run(old, b"PK\x03\x04").is_err());
+        let mut truncated = encode(&[Op::Insert(b"abcdef")], true);
+        truncated.truncate(truncated.len() - 4);
+        assert!(run(old, &truncated).is_err());
+    }
+
+    #[test]
+    fn applies_gzip_compressed_patch_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("ggml-base.bin");
+        let patch = dir.path().join("ggml-base.bin.patch");
+        let out = dir.path().join("ggml-base.bin.update");
+        std::fs::write(&old, b"0123456789").unwrap();
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(&encode(&[Op::Copy(5, 5), Op::Insert(b"!")], true))
+            .unwrap();
+        std::fs::write(&patch, gz.finish().unwrap()).unwrap();
+
+        assert_eq!(apply(&old, &patch, &out).unwrap(), 6);
+        assert_eq!(std::fs::read(&out).unwrap(), b"56789!");
+    }
+}
@@ -0,0 +1,272 @@
+//! Proxy for model downloads.
+//!
+//! Campus and company networks often only let traffic out through an
+//! HTTP proxy. By default downloads use the system's proxy, which is
+//! reqwest's own behaviour: `HTTP_PROXY` / `HTTPS_PROXY` / `NO_PROXY`
+//! plus the OS settings on macOS and Windows. The user can instead set
+//! an explicit proxy, with basic auth, or turn proxies off
+//! ([`ProxySettings`], stored under [`PROXY_SETTING`]).
+//!
+//! Every download builds its client from [`client_builder`], so the
+//! settings are kept in memory; a change applies to the next request.
+//! The password sits in the local settings table like the rest of the
+//! settings, and is never logged or sent back to the frontend.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+use crate::storage;
+
+/// Settings key holding [`ProxySettings`] as JSON. Per install, so
+/// stored for the default user.
+pub const PROXY_SETTING: &str = "download.proxy";
+const SETTINGS_USER: &str = "default_user";
+
+static CURRENT: Mutex<Option<ProxySettings>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyMode {
+    /// Environment variables and the OS proxy settings.
+    #[default]
+    System,
+    /// [`ProxySettings::url`] for every download.
+    Manual,
+    /// Connect directly, ignoring any system proxy.
+    Off,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxySettings {
+    #[serde(default)]
+    pub mode: ProxyMode,
+    /// e.g. `http://proxy.example.edu:3128`.
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// Comma-separated hosts to reach directly, in `NO_PROXY` syntax.
+    #[serde(default)]
+    pub no_proxy: Option<String>,
+}
+
+impl ProxySettings {
+    fn validate(self) -> Result<Self, String> {
+        fn clean(value: Option<String>) -> Option<String> {
+            value
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        }
+        let settings = Self {
+            mode: self.mode,
+            url: clean(self.url).map(|url| url.trim_end_matches('/').to_string()),
+            username: clean(self.username),
+            // Passwords may legitimately start or end with spaces.
+            password: self.password.filter(|p| !p.is_empty()),
+            no_proxy: clean(self.no_proxy),
+        };
+        if let Some(url) = &settings.url {
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                return Err(format!("代理網址必須以 http:// 或 https:// 開頭: {}", url));
+            }
+            reqwest::Proxy::all(url.as_str()).map_err(|e| format!("代理網址無效: {}", e))?;
+        }
+        if settings.mode == ProxyMode::Manual && settings.url.is_none() {
+            return Err("選擇手動代理時必須填寫網址".to_string());
+        }
+        if settings.password.is_some() && settings.username.is_none() {
+            return Err("填寫代理密碼時必須一併填寫使用者名稱".to_string());
+        }
+        Ok(settings)
+    }
+
+    /// What the frontend gets back: whether a password is saved, not
+    /// the password itself.
+    fn redacted(&self) -> Self {
+        Self {
+            password: self.password.as_ref().map(|_| String::new()),
+            ..self.clone()
+        }
+    }
+
+    fn apply(&self, builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder, String> {
+        match self.mode {
+            ProxyMode::System => Ok(builder),
+            ProxyMode::Off => Ok(builder.no_proxy()),
+            ProxyMode::Manual => {
+                let url = self.url.as_deref().ok_or("手動代理未設定網址")?;
+                let mut proxy =
+                    reqwest::Proxy::all(url).map_err(|e| format!("代理網址無效: {}", e))?;
+                if let Some(username) = &self.username {
+                    proxy = proxy.basic_auth(username, self.password.as_deref().unwrap_or(""));
+                }
+                proxy = proxy.no_proxy(
+                    self.no_proxy
+                        .as_deref()
+                        .and_then(reqwest::NoProxy::from_string),
+                );
+                Ok(builder.proxy(proxy))
+            }
+        }
+    }
+}
+
+/// A reqwest client builder with the proxy settings applied. Use it for
+/// every client that downloads models.
+pub fn client_builder() -> reqwest::ClientBuilder {
+    let settings = current();
+    settings
+        .apply(reqwest::Client::builder())
+        .unwrap_or_else(|e| {
+            log::warn!("[downloads] {}; using the system proxy", e);
+            reqwest::Client::builder()
+        })
+}
+
+fn current() -> ProxySettings {
+    CURRENT
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+        .clone()
+        .unwrap_or_default()
+}
+
+fn apply(settings: ProxySettings) {
+    *CURRENT.lock().unwrap_or_else(|p| p.into_inner()) = Some(settings);
+}
+
+async fn saved() -> Result<ProxySettings, String> {
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let Some(json) = db
+        .get_setting(PROXY_SETTING, SETTINGS_USER)
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(ProxySettings::default());
+    };
+    serde_json::from_str(&json).map_err(|e| format!("無法解析下載代理設定: {}", e))
+}
+
+/// Apply the saved settings; called once the database is up. The system
+/// proxy if they can't be read.
+pub async fn load() {
+    match saved().await {
+        Ok(settings) => apply(settings),
+        Err(e) => log::warn!("[downloads] {}; using the system proxy", e),
+    }
+}
+
+/// The saved settings, password redacted.
+pub async fn settings() -> Result<ProxySettings, String> {
+    Ok(saved().await?.redacted())
+}
+
+/// Validate, apply and save `settings`; returns them normalised and
+/// redacted. An empty `password` keeps the saved one if the username is
+/// unchanged, so the redacted settings can be saved back as they are;
+/// `None` removes it.
+pub async fn set_settings(mut settings: ProxySettings) -> Result<ProxySettings, String> {
+    if settings.password.as_deref() == Some("") {
+        let previous = saved().await?;
+        let same_user = settings.username.as_deref().map(str::trim) == previous.username.as_deref();
+        settings.password = previous.password.filter(|_| same_user);
+    }
+    let settings = settings.validate()?;
+    let json = serde_json::to_string(&settings).map_err(|e| e.to_string())?;
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    db.save_setting(PROXY_SETTING, &json, SETTINGS_USER)
+        .map_err(|e| e.to_string())?;
+    // Not the URL: it may carry credentials too.
+    log::info!("[downloads] proxy mode set to {:?}", settings.mode);
+    let redacted = settings.redacted();
+    apply(settings);
+    Ok(redacted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manual(url: &str) -> ProxySettings {
+        ProxySettings {
+            mode: ProxyMode::Manual,
+            url: Some(url.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn validate_normalises_and_rejects() {
+        let ok = ProxySettings {
+            username: Some(" student ".into()),
+            password: Some(" secret".into()),
+            no_proxy: Some("  ".into()),
+            ..manual(" http://proxy.example.edu:3128/ ")
+        }
+        .validate()
+        .unwrap();
+        assert_eq!(ok.url.as_deref(), Some("http://proxy.example.edu:3128"));
+        assert_eq!(ok.username.as_deref(), Some("student"));
+        assert_eq!(ok.password.as_deref(), Some(" secret"));
+        assert_eq!(ok.no_proxy, None);
+
+        assert!(manual("proxy.example.edu:3128").validate().is_err());
+        assert!(manual(" ").validate().is_err());
+        assert!(ProxySettings {
+            password: Some("secret".into()),
+            ..manual("http://proxy.example.edu:3128")
+        }
+        .validate()
+        .is_err());
+        // A URL kept around while the mode is System is still checked.
+        assert!(ProxySettings {
+            mode: ProxyMode::System,
+            url: Some("socks://proxy".into()),
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn redacted_hides_the_password_but_not_that_there_is_one() {
+        let settings = ProxySettings {
+            username: Some("student".into()),
+            password: Some("secret".into()),
+            ..manual("http://proxy.example.edu:3128")
+        };
+        assert_eq!(settings.redacted().password.as_deref(), Some(""));
+        assert_eq!(ProxySettings::default().redacted().password, None);
+    }
+
+    #[test]
+    fn every_mode_builds_a_client() {
+        for settings in [
+            ProxySettings::default(),
+            ProxySettings {
+                mode: ProxyMode::Off,
+                ..Default::default()
+            },
+            ProxySettings {
+                username: Some("student".into()),
+                password: Some("secret".into()),
+                no_proxy: Some("localhost,127.0.0.1".into()),
+                ..manual("http://proxy.example.edu:3128")
+            },
+        ] {
+            let builder = settings.apply(reqwest::Client::builder()).unwrap();
+            assert!(builder.build().is_ok(), "{:?}", settings.mode);
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Bandwidth limit for downloads.
+//!
+//! One limit, in bytes per second, shared by every download the app
+//! runs at the same time (all segments of a parallel download count
+//! together), so fetching a model during class leaves room for the
+//! live translation traffic. `0` means unlimited, the default.
+//!
+//! The limit is stored under [`RATE_LIMIT_SETTING`] and kept in memory
+//! so the download loops can call [`throttle`] for every chunk without
+//! touching the database; a change applies to running downloads too.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::storage;
+
+/// Settings key holding the limit in bytes per second. Per install, so
+/// stored for the default user.
+pub const RATE_LIMIT_SETTING: &str = "download.rate_limit";
+const SETTINGS_USER: &str = "default_user";
+/// Lowest non-zero limit; below this a multi-GB model would never finish.
+pub const MIN_BYTES_PER_SEC: u64 = 100_000;
+
+static LIMIT: AtomicU64 = AtomicU64::new(0);
+static BUCKET: Mutex<Option<Bucket>> = Mutex::new(None);
+
+/// Token bucket holding up to one second's worth of bytes. Taking more
+/// than is available leaves it in debt, which the caller sleeps off.
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last: Instant,
+}
+
+impl Bucket {
+    fn take(&mut self, bytes: usize, rate: u64, now: Instant) -> Duration {
+        let rate = rate as f64;
+        let refill = now.saturating_duration_since(self.last).as_secs_f64() * rate;
+        self.tokens = (self.tokens + refill).min(rate) - bytes as f64;
+        self.last = now;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / rate)
+        }
+    }
+}
+
+/// The current limit in bytes per second; `0` when unlimited.
+pub fn limit() -> u64 {
+    LIMIT.load(Ordering::Relaxed)
+}
+
+fn apply(bytes_per_sec: u64) {
+    LIMIT.store(bytes_per_sec, Ordering::Relaxed);
+}
+
+/// Wait until `bytes` more may be downloaded. Call after each chunk.
+pub async fn throttle(bytes: usize) {
+    let rate = limit();
+    if rate == 0 {
+        return;
+    }
+    let wait = {
+        let mut bucket = BUCKET.lock().unwrap_or_else(|p| p.into_inner());
+        let now = Instant::now();
+        bucket
+            .get_or_insert(Bucket {
+                tokens: rate as f64,
+                last: now,
+            })
+            .take(bytes, rate, now)
+    };
+    if !wait.is_zero() {
+        tokio::time::sleep(wait).await;
+    }
+}
+
+async fn saved_limit() -> Result<u64, String> {
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let Some(value) = db
+        .get_setting(RATE_LIMIT_SETTING, SETTINGS_USER)
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(0);
+    };
+    value
+        .trim()
+        .parse()
+        .map_err(|e| format!("無法解析下載限速設定 {:?}: {}", value, e))
+}
+
+/// Apply the saved limit; called once the database is up. Unlimited if
+/// it can't be read.
+pub async fn load() {
+    match saved_limit().await {
+        Ok(bytes_per_sec) => apply(bytes_per_sec),
+        Err(e) => log::warn!("[downloads] {}; not limiting download speed", e),
+    }
+}
+
+/// Validate, apply and save a new limit.
+pub async fn set_limit(bytes_per_sec: u64) -> Result<u64, String> {
+    if bytes_per_sec != 0 && bytes_per_sec < MIN_BYTES_PER_SEC {
+        return Err(format!(
+            "下載限速不能低於 {} KB/s",
+            MIN_BYTES_PER_SEC / 1000
+        ));
+    }
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    db.save_setting(
+        RATE_LIMIT_SETTING,
+        &bytes_per_sec.to_string(),
+        SETTINGS_USER,
+    )
+    .map_err(|e| e.to_string())?;
+    apply(bytes_per_sec);
+    log::info!("[downloads] rate limit set to {} B/s", bytes_per_sec);
+    Ok(bytes_per_sec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_allows_a_burst_then_paces_to_the_rate() {
+        let start = Instant::now();
+        let mut bucket = Bucket {
+            tokens: 1_000_000.0,
+            last: start,
+        };
+        // The first second's worth goes through at once.
+        assert_eq!(bucket.take(1_000_000, 1_000_000, start), Duration::ZERO);
+        // Half a second more has to be waited for.
+        let wait = bucket.take(500_000, 1_000_000, start);
+        assert!((wait.as_secs_f64() - 0.5).abs() < 1e-9);
+        // Once that time has passed the debt is paid off.
+        let later = start + Duration::from_millis(600);
+        assert_eq!(bucket.take(50_000, 1_000_000, later), Duration::ZERO);
+    }
+
+    #[test]
+    fn idle_time_refills_no_more_than_one_second() {
+        let start = Instant::now();
+        let mut bucket = Bucket {
+            tokens: 0.0,
+            last: start,
+        };
+        let later = start + Duration::from_secs(60);
+        assert_eq!(bucket.take(200_000, 200_000, later), Duration::ZERO);
+        let wait = bucket.take(200_000, 200_000, later);
+        assert!((wait.as_secs_f64() - 1.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,663 @@
+//! Newer releases of the installed models.
+//!
+//! A manifest published next to the app updates ([`MANIFEST_URL`])
+//! lists the current release of each model the app offers: its files
+//! with size and SHA-256, a version, a changelog, and the older models it
+//! `replaces` (e.g. a new quantization of the same base). [`check`]
+//! compares the installed models against it and reports two kinds of
+//! [`ModelUpdate`]: an installed model whose files differ from its
+//! current release, and an installed model superseded by one that isn't
+//! installed yet.
+//!
+//! [`apply`] downloads the files that differ next to the old ones, checks
+//! their hashes and only then swaps them in, so a failed update leaves
+//! the installed model as it was. An upgrade installs the new model
+//! alongside the old one; deleting the old one is up to the user.
+//!
+//! A manifest file can also list binary patches (see [`super::patch`])
+//! from older files. When one of them is installed, the patch is
+//! downloaded instead and applied to it; if that fails for any reason
+//! the whole file is downloaded after all.
+//!
+//! Hashing a multi-gigabyte model takes a while, so digests are cached
+//! in [`HASH_CACHE_FILE`] by path, size and modification time.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
+
+use super::installed::{self, InstalledModel};
+use super::{mirrors, patch, proxy};
+use crate::diagnostics::health::sha256_file;
+use crate::paths;
+use crate::whisper::download::{self, ModelDownloadConfig};
+
+pub const MANIFEST_URL: &str = "https://sklonely.github.io/ClassNoteAI/models/manifest.json";
+/// `{app_data_dir}/model_hashes.json`: path → size, mtime and SHA-256.
+pub const HASH_CACHE_FILE: &str = "model_hashes.json";
+/// Suffix of a file an update downloaded and hasn't swapped in yet.
+pub const STAGED_SUFFIX: &str = ".update";
+const MANIFEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Manifest {
+    #[serde(default)]
+    pub models: Vec<ManifestModel>,
+}
+
+/// The current release of one model.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManifestModel {
+    /// Installed-model id, e.g. `whisper/ggml-base.bin`.
+    pub id: String,
+    pub version: String,
+    #[serde(default)]
+    pub label: Option<String>,
+    pub files: Vec<ManifestFile>,
+    /// Ids of older models this one supersedes.
+    #[serde(default)]
+    pub replaces: Vec<String>,
+    #[serde(default)]
+    pub changelog: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManifestFile {
+    /// Path inside the model directory; empty for a single-file model.
+    #[serde(default)]
+    pub path: String,
+    pub url: String,
+    pub size: u64,
+    pub sha256: String,
+    /// Patches that turn an older file into this one.
+    #[serde(default)]
+    pub patches: Vec<ManifestPatch>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ManifestPatch {
+    /// The file it applies to, relative to the models directory; empty
+    /// for the older release of this same file.
+    #[serde(default)]
+    pub from: String,
+    pub from_sha256: String,
+    pub url: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpdateKind {
+    /// A newer release of the installed model, replacing it in place.
+    Update,
+    /// A different model that supersedes the installed one.
+    Upgrade,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelUpdate {
+    /// The installed model this is offered for; what [`find`] takes.
+    pub installed_id: String,
+    /// The model that gets installed: `installed_id` itself for an
+    /// [`UpdateKind::Update`].
+    pub id: String,
+    pub kind: UpdateKind,
+    pub label: String,
+    pub version: String,
+    /// Bytes to download.
+    pub size: u64,
+    pub changelog: String,
+}
+
+/// An update ready for [`apply`].
+pub struct PlannedUpdate {
+    pub update: ModelUpdate,
+    /// Every file to download.
+    files: Vec<PlannedFile>,
+}
+
+struct PlannedFile {
+    file: ManifestFile,
+    /// Where it goes.
+    dest: PathBuf,
+    /// A patch to fetch instead, and the installed file it applies to.
+    patch: Option<(ManifestPatch, PathBuf)>,
+}
+
+impl PlannedFile {
+    /// Bytes to download.
+    fn size(&self) -> u64 {
+        self.patch
+            .as_ref()
+            .map_or(self.file.size, |(patch, _)| patch.size)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CachedHash {
+    size: u64,
+    modified: u64,
+    sha256: String,
+}
+
+/// Installed models with a newer release, in installed-model order.
+pub async fn check() -> Result<Vec<ModelUpdate>, String> {
+    let manifest = fetch_manifest().await?;
+    let plans = tokio::task::spawn_blocking(move || plan_all(&manifest))
+        .await
+        .map_err(|e| format!("check model updates task join error: {e}"))??;
+    Ok(plans.into_iter().map(|plan| plan.update).collect())
+}
+
+/// The update on offer for installed model `installed_id`, if any.
+pub async fn find(installed_id: &str) -> Result<Option<PlannedUpdate>, String> {
+    let manifest = fetch_manifest().await?;
+    let installed_id = installed_id.to_string();
+    tokio::task::spawn_blocking(move || {
+        Ok(plan_all(&manifest)?
+            .into_iter()
+            .find(|plan| plan.update.installed_id == installed_id))
+    })
+    .await
+    .map_err(|e| format!("check model updates task join error: {e}"))?
+}
+
+/// Download `plan`'s files, verify them and swap them in. `on_progress`
+/// gets bytes done and total across all files. Returns the id of the
+/// model installed.
+pub async fn apply(
+    plan: PlannedUpdate,
+    on_progress: Arc<dyn Fn(u64, u64) + Send + Sync>,
+) -> Result<String, String> {
+    let mut total: u64 = plan.files.iter().map(PlannedFile::size).sum();
+    let mut done_before = 0;
+    let mut staged = Vec::new();
+    for planned in &plan.files {
+        let (file, dest) = (&planned.file, &planned.dest);
+        let stage = staged_path(dest);
+        let mut patched = false;
+        if let Some((file_patch, source)) = &planned.patch {
+            let progress = progress_from(&on_progress, done_before, total);
+            match apply_patch(file_patch, source, &stage, progress).await {
+                Ok(()) => patched = verify(file, &stage).await.is_ok(),
+                Err(e) => log::warn!("[models] patch {} failed: {}", file_patch.url, e),
+            }
+            done_before += file_patch.size;
+            if !patched {
+                log::warn!("[models] downloading all of {} instead", file.url);
+                total += file.size;
+            }
+        }
+        if !patched {
+            let config = ModelDownloadConfig {
+                url: file.url.clone(),
+                output_path: stage.clone(),
+                expected_size: Some(file.size),
+                mirrors: mirrors::hf_mirrors(&file.url),
+            };
+            let callback = progress_from(&on_progress, done_before, total);
+            download::download_model(&config, Some(callback))
+                .await
+                .map_err(|e| format!("下載 {} 失敗: {}", file.url, e))?;
+            verify(file, &stage).await?;
+            done_before += file.size;
+        }
+        staged.push((stage, dest.clone()));
+    }
+
+    for (stage, dest) in staged {
+        tokio::fs::rename(&stage, &dest)
+            .await
+            .map_err(|e| format!("無法替換 {}: {}", dest.display(), e))?;
+    }
+    log::info!(
+        "[models] {} {} → {} {}",
+        match plan.update.kind {
+            UpdateKind::Update => "updated",
+            UpdateKind::Upgrade => "upgraded",
+        },
+        plan.update.installed_id,
+        plan.update.id,
+        plan.update.version
+    );
+    Ok(plan.update.id)
+}
+
+fn progress_from(
+    on_progress: &Arc<dyn Fn(u64, u64) + Send + Sync>,
+    done_before: u64,
+    total: u64,
+) -> Box<dyn Fn(u64, u64) + Send + Sync> {
+    let on_progress = on_progress.clone();
+    Box::new(move |downloaded, _| on_progress(done_before + downloaded, total))
+}
+
+/// Download `file_patch` and rebuild the file at `stage` from `source`.
+async fn apply_patch(
+    file_patch: &ManifestPatch,
+    source: &Path,
+    stage: &Path,
+    on_progress: Box<dyn Fn(u64, u64) + Send + Sync>,
+) -> Result<(), String> {
+    let patch_file = patch_download_path(stage);
+    let config = ModelDownloadConfig {
+        url: file_patch.url.clone(),
+        output_path: patch_file.clone(),
+        expected_size: Some(file_patch.size),
+        mirrors: mirrors::hf_mirrors(&file_patch.url),
+    };
+    download::download_model(&config, Some(on_progress))
+        .await
+        .map_err(|e| format!("下載更新檔失敗: {}", e))?;
+
+    let (source, out, patch_path) = (
+        source.to_path_buf(),
+        stage.to_path_buf(),
+        patch_file.clone(),
+    );
+    let result = tokio::task::spawn_blocking(move || patch::apply(&source, &patch_path, &out))
+        .await
+        .map_err(|e| format!("patch task join error: {e}"))
+        .and_then(|applied| applied.map(|_| ()));
+    let _ = tokio::fs::remove_file(&patch_file).await;
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(stage).await;
+    }
+    result
+}
+
+/// Check the SHA-256 of `stage` against `file`'s, deleting it on a
+/// mismatch.
+async fn verify(file: &ManifestFile, stage: &Path) -> Result<(), String> {
+    let path = stage.to_path_buf();
+    let actual = tokio::task::spawn_blocking(move || sha256_file(&path))
+        .await
+        .map_err(|e| format!("hash task join error: {e}"))?
+        .map_err(|e| format!("無法讀取 {}: {}", stage.display(), e))?;
+    if !actual.eq_ignore_ascii_case(&file.sha256) {
+        let _ = tokio::fs::remove_file(stage).await;
+        return Err(format!("{} 的 SHA-256 不符，已捨棄下載的檔案", file.url));
+    }
+    Ok(())
+}
+
+async fn fetch_manifest() -> Result<Manifest, String> {
+    let client = proxy::client_builder()
+        .timeout(MANIFEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    client
+        .get(MANIFEST_URL)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("無法取得模型更新清單: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("無法解析模型更新清單: {}", e))
+}
+
+fn plan_all(manifest: &Manifest) -> Result<Vec<PlannedUpdate>, String> {
+    let models_dir = paths::get_models_dir()?;
+    let installed = installed::list()?;
+    let cache_path = paths::get_app_data_dir()?.join(HASH_CACHE_FILE);
+    let mut cache = read_cache(&cache_path);
+    let plans = plan(manifest, &installed, &models_dir, &mut |path, size| {
+        cached_digest(&mut cache, path, size)
+    });
+    write_cache(&cache_path, &cache);
+    Ok(plans)
+}
+
+/// What to download for each installed model with a newer release.
+/// `digest` returns a file's SHA-256, given its size.
+fn plan(
+    manifest: &Manifest,
+    installed: &[InstalledModel],
+    models_dir: &Path,
+    digest: &mut dyn FnMut(&Path, u64) -> Option<String>,
+) -> Vec<PlannedUpdate> {
+    let is_installed = |id: &str| installed.iter().any(|m| m.id == id);
+    let mut plans = Vec::new();
+    for model in installed {
+        let (release, kind) = match manifest.models.iter().find(|r| r.id == model.id) {
+            Some(release) => (release, UpdateKind::Update),
+            None => match manifest
+                .models
+                .iter()
+                .find(|r| r.replaces.contains(&model.id) && !is_installed(&r.id))
+            {
+                Some(release) => (release, UpdateKind::Upgrade),
+                None => continue,
+            },
+        };
+        let Some(files) = release_files(models_dir, release) else {
+            log::warn!("[models] manifest entry {} has an invalid path", release.id);
+            continue;
+        };
+        let outdated: Vec<_> = files
+            .into_iter()
+            .filter(|(file, dest)| {
+                kind == UpdateKind::Upgrade || !is_current(file, dest, &mut *digest)
+            })
+            .map(|(file, dest)| PlannedFile {
+                patch: usable_patch(&file, &dest, models_dir, &mut *digest),
+                file,
+                dest,
+            })
+            .collect();
+        if outdated.is_empty() {
+            continue;
+        }
+        plans.push(PlannedUpdate {
+            update: ModelUpdate {
+                installed_id: model.id.clone(),
+                id: release.id.clone(),
+                kind,
+                label: release.label.clone().unwrap_or_else(|| release.id.clone()),
+                version: release.version.clone(),
+                size: outdated.iter().map(PlannedFile::size).sum(),
+                changelog: release.changelog.clone(),
+            },
+            files: outdated,
+        });
+    }
+    plans
+}
+
+/// Where each of `release`'s files goes; `None` if the manifest names a
+/// path outside the models directory.
+fn release_files(
+    models_dir: &Path,
+    release: &ManifestModel,
+) -> Option<Vec<(ManifestFile, PathBuf)>> {
+    let base = join_relative(models_dir, &release.id)?;
+    release
+        .files
+        .iter()
+        .map(|file| {
+            let dest = if file.path.is_empty() {
+                base.clone()
+            } else {
+                join_relative(&base, &file.path)?
+            };
+            Some((file.clone(), dest))
+        })
+        .collect()
+}
+
+fn join_relative(base: &Path, relative: &str) -> Option<PathBuf> {
+    let relative = Path::new(relative);
+    let plain = relative
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)));
+    (plain && !relative.as_os_str().is_empty()).then(|| base.join(relative))
+}
+
+fn is_current(
+    file: &ManifestFile,
+    path: &Path,
+    digest: &mut dyn FnMut(&Path, u64) -> Option<String>,
+) -> bool {
+    // A size mismatch settles it without hashing.
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.len() == file.size)
+        && digest(path, file.size).is_some_and(|hash| hash.eq_ignore_ascii_case(&file.sha256))
+}
+
+/// The first of `file`'s patches whose source is installed as the
+/// patch expects, with that source.
+fn usable_patch(
+    file: &ManifestFile,
+    dest: &Path,
+    models_dir: &Path,
+    digest: &mut dyn FnMut(&Path, u64) -> Option<String>,
+) -> Option<(ManifestPatch, PathBuf)> {
+    file.patches.iter().find_map(|file_patch| {
+        let source = if file_patch.from.is_empty() {
+            dest.to_path_buf()
+        } else {
+            join_relative(models_dir, &file_patch.from)?
+        };
+        let size = std::fs::metadata(&source)
+            .ok()
+            .filter(|m| m.is_file())?
+            .len();
+        digest(&source, size)
+            .is_some_and(|hash| hash.eq_ignore_ascii_case(&file_patch.from_sha256))
+            .then(|| (file_patch.clone(), source))
+    })
+}
+
+fn staged_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(STAGED_SUFFIX);
+    dest.with_file_name(name)
+}
+
+/// Where a patch for `stage` downloads to; ends in [`STAGED_SUFFIX`] too,
+/// so the installed-model list skips it.
+fn patch_download_path(stage: &Path) -> PathBuf {
+    let mut name = stage.file_name().unwrap_or_default().to_os_string();
+    name.push(".patch");
+    name.push(STAGED_SUFFIX);
+    stage.with_file_name(name)
+}
+
+fn cached_digest(
+    cache: &mut BTreeMap<String, CachedHash>,
+    path: &Path,
+    size: u64,
+) -> Option<String> {
+    let modified = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    let key = path.to_string_lossy().into_owned();
+    if let Some(hit) = cache.get(&key) {
+        if hit.size == size && hit.modified == modified {
+            return Some(hit.sha256.clone());
+        }
+    }
+    let sha256 = sha256_file(path)
+        .map_err(|e| log::warn!("[models] could not hash {}: {}", path.display(), e))
+        .ok()?;
+    cache.insert(
+        key,
+        CachedHash {
+            size,
+            modified,
+            sha256: sha256.clone(),
+        },
+    );
+    Some(sha256)
+}
+
+fn read_cache(path: &Path) -> BTreeMap<String, CachedHash> {
+    std::fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn write_cache(path: &Path, cache: &BTreeMap<String, CachedHash>) {
+    let result = serde_json::to_vec_pretty(cache)
+        .map_err(|e| e.to_string())
+        .and_then(|json| std::fs::write(path, json).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        log::warn!("[models] could not save model hashes: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn installed(id: &str) -> InstalledModel {
+        InstalledModel {
+            id: id.to_string(),
+            engine: "whisper".to_string(),
+            variant: String::new(),
+            path: String::new(),
+            size: 0,
+            last_used: None,
+        }
+    }
+
+    fn release(id: &str, size: u64, sha256: &str, replaces: &[&str]) -> ManifestModel {
+        ManifestModel {
+            id: id.to_string(),
+            version: "2".to_string(),
+            label: None,
+            files: vec![ManifestFile {
+                path: String::new(),
+                url: format!("https://huggingface.co/x/resolve/main/{}", id),
+                size,
+                sha256: sha256.to_string(),
+                patches: Vec::new(),
+            }],
+            replaces: replaces.iter().map(|s| s.to_string()).collect(),
+            changelog: "修正".to_string(),
+        }
+    }
+
+    #[test]
+    fn plans_updates_for_changed_files_and_upgrades_for_replaced_models() {
+        let dir = tempfile::tempdir().unwrap();
+        let models = dir.path();
+        std::fs::create_dir_all(models.join("whisper")).unwrap();
+        std::fs::write(models.join("whisper/ggml-base.bin"), b"base").unwrap();
+        std::fs::write(models.join("whisper/ggml-small.bin"), b"small").unwrap();
+        std::fs::write(models.join("whisper/ggml-medium.bin"), b"medium").unwrap();
+
+        let manifest = Manifest {
+            models: vec![
+                // Same size, different hash.
+                release("whisper/ggml-base.bin", 4, "AAAA", &[]),
+                // Current.
+                release("whisper/ggml-small.bin", 5, "s", &[]),
+                release(
+                    "whisper/ggml-medium-q5.bin",
+                    3,
+                    "q",
+                    &["whisper/ggml-medium.bin"],
+                ),
+                // Points outside the models directory: ignored.
+                release("../outside.bin", 1, "x", &["whisper/ggml-tiny.bin"]),
+            ],
+        };
+        let list = [
+            installed("whisper/ggml-base.bin"),
+            installed("whisper/ggml-medium.bin"),
+            installed("whisper/ggml-small.bin"),
+            installed("whisper/ggml-tiny.bin"),
+        ];
+        let mut hashed = Vec::new();
+        let plans = plan(&manifest, &list, models, &mut |path, _| {
+            hashed.push(path.file_name().unwrap().to_string_lossy().into_owned());
+            Some(match path.file_name().unwrap().to_str().unwrap() {
+                "ggml-small.bin" => "S".to_string(),
+                _ => "b".to_string(),
+            })
+        });
+
+        let summary: Vec<_> = plans
+            .iter()
+            .map(|p| {
+                (
+                    p.update.installed_id.as_str(),
+                    p.update.id.as_str(),
+                    p.update.kind,
+                    p.update.size,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    "whisper/ggml-base.bin",
+                    "whisper/ggml-base.bin",
+                    UpdateKind::Update,
+                    4
+                ),
+                (
+                    "whisper/ggml-medium.bin",
+                    "whisper/ggml-medium-q5.bin",
+                    UpdateKind::Upgrade,
+                    3
+                ),
+            ]
+        );
+        assert_eq!(plans[0].files[0].dest, models.join("whisper/ggml-base.bin"));
+        // The medium upgrade never hashes anything.
+        assert_eq!(hashed, ["ggml-base.bin", "ggml-small.bin"]);
+    }
+
+    #[test]
+    fn plans_a_patch_from_an_installed_file_it_applies_to() {
+        let dir = tempfile::tempdir().unwrap();
+        let models = dir.path();
+        std::fs::create_dir_all(models.join("whisper")).unwrap();
+        std::fs::write(models.join("whisper/ggml-medium.bin"), b"medium").unwrap();
+
+        let patch = |from: &str, from_sha256: &str, size| ManifestPatch {
+            from: from.to_string(),
+            from_sha256: from_sha256.to_string(),
+            url: format!("https://huggingface.co/x/resolve/main/{}.patch", size),
+            size,
+        };
+        let mut upgrade = release(
+            "whisper/ggml-medium-q5.bin",
+            500,
+            "q",
+            &["whisper/ggml-medium.bin"],
+        );
+        upgrade.files[0].patches = vec![
+            // The same file's older release isn't installed.
+            patch("", "m", 10),
+            // Installed, but a different build.
+            patch("whisper/ggml-medium.bin", "other", 20),
+            patch("whisper/ggml-medium.bin", "M", 30),
+        ];
+        let manifest = Manifest {
+            models: vec![upgrade],
+        };
+        let plans = plan(
+            &manifest,
+            &[installed("whisper/ggml-medium.bin")],
+            models,
+            &mut |_, _| Some("m".to_string()),
+        );
+
+        assert_eq!(plans[0].update.size, 30);
+        let (chosen, source) = plans[0].files[0].patch.as_ref().unwrap();
+        assert_eq!(chosen.size, 30);
+        assert_eq!(*source, models.join("whisper/ggml-medium.bin"));
+        assert_eq!(
+            patch_download_path(&staged_path(&plans[0].files[0].dest))
+                .file_name()
+                .unwrap(),
+            "ggml-medium-q5.bin.update.patch.update"
+        );
+    }
+
+    #[test]
+    fn digests_are_cached_until_the_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ggml-base.bin");
+        std::fs::write(&path, b"abc").unwrap();
+        let mut cache = BTreeMap::new();
+        let sha = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+        assert_eq!(cached_digest(&mut cache, &path, 3).as_deref(), Some(sha));
+        cache.get_mut(path.to_str().unwrap()).unwrap().sha256 = "cached".into();
+        assert_eq!(
+            cached_digest(&mut cache, &path, 3).as_deref(),
+            Some("cached")
+        );
+        assert_eq!(cached_digest(&mut cache, &path, 4).as_deref(), Some(sha));
+    }
+}
@@ -19,7 +19,7 @@ pub struct EmbeddingModelConfig {
 
 impl EmbeddingModelConfig {
     /// Default config for BAAI/bge-small-en-v1.5 — the embedding model
-    /// used by the app since v0.5.2. 384-d, ~33 MB, standard BERT
+    /// used by the app since v0.5.2. 384-d, ~130 MB, standard BERT
     /// (Candle-compatible). Cross-lingual queries are handled by
     /// translating the query to English before embedding, so an
     /// English-only encoder is appropriate here.
@@ -102,7 +102,7 @@ async fn download_file(
     println!("[Embedding Download] Downloading from: {}", url);
     println!("[Embedding Download] Output: {:?}", output_path);
 
-    let client = reqwest::Client::builder()
+    let client = crate::downloads::proxy::client_builder()
         .timeout(std::time::Duration::from_secs(600))
         .build()?;
 
@@ -179,6 +179,7 @@ async fn download_file(
         let chunk = chunk?;
         file.write_all(&chunk).await?;
         downloaded += chunk.len() as u64;
+        crate::downloads::throttle::throttle(chunk.len()).await;
 
         if let Some(callback) = progress_callback {
             callback(downloaded, total_size);
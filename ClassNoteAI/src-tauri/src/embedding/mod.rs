@@ -12,6 +12,17 @@ pub use download::{download_embedding_model, EmbeddingModelConfig};
 #[cfg(feature = "candle-embed")]
 pub use service::EmbeddingService;
 
+/// Smallest believable size of the safetensors file (bge-small-en-v1.5
+/// is ~130 MB); anything below is a truncated download.
+pub const MIN_MODEL_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Whether the model or tokenizer file is missing or the model is
+/// truncated — the load failures that downloading again fixes.
+pub fn files_incomplete(model_path: &std::path::Path, tokenizer_path: &std::path::Path) -> bool {
+    !tokenizer_path.is_file()
+        || !std::fs::metadata(model_path).is_ok_and(|m| m.len() >= MIN_MODEL_BYTES)
+}
+
 // Stub when candle-embed feature is disabled
 #[cfg(not(feature = "candle-embed"))]
 pub struct EmbeddingService;
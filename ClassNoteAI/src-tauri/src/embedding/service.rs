@@ -119,6 +119,9 @@ pub struct EmbeddingService {
     tokenizer: Tokenizer,
     #[cfg(feature = "candle-embed")]
     device: Device,
+    /// Size of the safetensors file — what the loaded model holds in
+    /// memory, give or take (see `crate::memory`).
+    weights_bytes: u64,
 }
 
 impl EmbeddingService {
@@ -172,16 +175,15 @@ impl EmbeddingService {
             .map_err(|e| anyhow!("Failed to parse converted config: {}", e))?;
 
         // Sanity-check the safetensors file. BAAI/bge-small-en-v1.5 is
-        // ~33 MB; a truncated download (e.g. user quit mid-download)
+        // ~130 MB; a truncated download (e.g. user quit mid-download)
         // typically weighs <2 MB and would later surface a confusing
         // "cannot find tensor …" error from Candle's BertModel::load.
         // Catching it here gives a direct, actionable message instead.
-        const MIN_PLAUSIBLE_SIZE: u64 = 20 * 1024 * 1024; // 20 MB
         let metadata = std::fs::metadata(model_path)
             .map_err(|e| anyhow!("Failed to stat model file: {}", e))?;
-        if metadata.len() < MIN_PLAUSIBLE_SIZE {
+        if metadata.len() < super::MIN_MODEL_BYTES {
             return Err(anyhow!(
-                "Embedding 模型檔案疑似損壞或下載未完成（僅 {} MB，預期 ~33 MB）。\
+                "Embedding 模型檔案疑似損壞或下載未完成（僅 {} MB，預期 ~130 MB）。\
                  請到「設定 → AI 模型 → Embedding」重新下載 bge-small-en-v1.5。",
                 metadata.len() / 1024 / 1024
             ));
@@ -211,9 +213,14 @@ impl EmbeddingService {
             model,
             tokenizer,
             device,
+            weights_bytes: metadata.len(),
         })
     }
 
+    pub fn weights_bytes(&self) -> u64 {
+        self.weights_bytes
+    }
+
     /// Stub constructor when candle-embed feature is disabled
     #[cfg(not(feature = "candle-embed"))]
     pub fn new<P: AsRef<Path>>(_model_path: P, _tokenizer_path: P) -> Result<Self> {
@@ -0,0 +1,225 @@
+//! The error type Tauri commands reject with.
+//!
+//! A command error reaches the frontend as
+//! `{ kind, message, retryable, context? }`, so it can branch on
+//! [`ErrorKind`] (offer a download for `model_missing`, a cleanup for
+//! `disk_full`, a retry for `network`) instead of matching message text.
+//! `message` stays the user-facing (mostly Chinese) text it always was.
+//!
+//! Most of the modules behind the commands still return
+//! `Result<_, String>`; `?` turns those into an [`AppError`] through
+//! [`From<String>`], which recognises the few failures that are
+//! unambiguous in any module (cancellation or pausing, a full disk,
+//! the database being unavailable) and leaves the rest as
+//! [`ErrorKind::Internal`].
+//! Commands that know better say so with the constructors or
+//! [`AppError::or_kind`].
+
+use serde::Serialize;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// A model the command needs isn't downloaded (or isn't loaded).
+    ModelMissing,
+    DiskFull,
+    /// Download / remote API failure.
+    Network,
+    NotFound,
+    InvalidInput,
+    /// Refused because something else is running (e.g. a recording).
+    Busy,
+    /// Not available in this build or on this platform.
+    Unsupported,
+    Cancelled,
+    /// A download was paused; `resume_download` continues it.
+    Paused,
+    /// The user doesn't own the record.
+    PermissionDenied,
+    Database,
+    Io,
+    Internal,
+}
+
+impl ErrorKind {
+    /// Whether trying the same call again later can succeed.
+    fn retryable(self) -> bool {
+        matches!(self, Self::Network | Self::Busy | Self::Database)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AppError {
+    pub kind: ErrorKind,
+    pub message: String,
+    pub retryable: bool,
+    /// What was being worked on (a model variant, a lecture id, a path).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+}
+
+/// How "no space left" (ENOSPC on Unix, ERROR_DISK_FULL on Windows)
+/// ends up in a formatted `io::Error`.
+const DISK_FULL_MARKERS: &[&str] = &["(os error 28)", "(os error 112)"];
+/// Prefixes of the database-unavailable messages the commands produce.
+const DATABASE_MARKERS: &[&str] = &["數據庫未初始化", "數據庫連接失敗", "db init:", "db conn:"];
+
+impl AppError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            retryable: kind.retryable(),
+            context: None,
+        }
+    }
+
+    pub fn model_missing(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::ModelMissing, message)
+    }
+
+    pub fn network(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Network, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::NotFound, message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::InvalidInput, message)
+    }
+
+    pub fn busy(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Busy, message)
+    }
+
+    pub fn unsupported(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Unsupported, message)
+    }
+
+    pub fn permission_denied(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::PermissionDenied, message)
+    }
+
+    pub fn with_context(mut self, context: impl Into<String>) -> Self {
+        self.context = Some(context.into());
+        self
+    }
+
+    /// Use `kind` unless the message was already recognised as
+    /// something more specific — e.g. a failed download is a network
+    /// error unless it was cancelled or the disk filled up.
+    pub fn or_kind(self, kind: ErrorKind) -> Self {
+        if self.kind != ErrorKind::Internal {
+            return self;
+        }
+        Self {
+            kind,
+            retryable: kind.retryable(),
+            ..self
+        }
+    }
+
+    fn classify(message: &str) -> ErrorKind {
+        if message == crate::tasks::CANCELLED {
+            ErrorKind::Cancelled
+        } else if message == crate::tasks::PAUSED {
+            ErrorKind::Paused
+        } else if DISK_FULL_MARKERS.iter().any(|m| message.contains(m)) {
+            ErrorKind::DiskFull
+        } else if DATABASE_MARKERS.iter().any(|m| message.starts_with(m)) {
+            ErrorKind::Database
+        } else {
+            ErrorKind::Internal
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        Self::new(Self::classify(&message), message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        message.to_string().into()
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        let kind = match e.kind() {
+            std::io::ErrorKind::NotFound => ErrorKind::NotFound,
+            std::io::ErrorKind::StorageFull => ErrorKind::DiskFull,
+            std::io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+            _ => ErrorKind::Io,
+        };
+        Self::new(kind, e.to_string())
+    }
+}
+
+/// For helpers that still return `Result<_, String>` and call commands.
+impl From<AppError> for String {
+    fn from(e: AppError) -> Self {
+        e.message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognises_unambiguous_string_errors() {
+        let cancelled = AppError::from(crate::tasks::CANCELLED.to_string());
+        assert_eq!(cancelled.kind, ErrorKind::Cancelled);
+        assert!(!cancelled.retryable);
+        assert_eq!(AppError::from(crate::tasks::PAUSED).kind, ErrorKind::Paused);
+
+        let full = AppError::from("寫入失敗: No space left on device (os error 28)");
+        assert_eq!(full.kind, ErrorKind::DiskFull);
+
+        let db = AppError::from("數據庫未初始化: not ready".to_string());
+        assert_eq!(db.kind, ErrorKind::Database);
+        assert!(db.retryable);
+
+        assert_eq!(AppError::from("找不到講堂").kind, ErrorKind::Internal);
+    }
+
+    #[test]
+    fn or_kind_only_replaces_internal() {
+        let network = AppError::from("connection reset").or_kind(ErrorKind::Network);
+        assert_eq!(network.kind, ErrorKind::Network);
+        assert!(network.retryable);
+
+        let cancelled = AppError::from(crate::tasks::CANCELLED).or_kind(ErrorKind::Network);
+        assert_eq!(cancelled.kind, ErrorKind::Cancelled);
+    }
+
+    #[test]
+    fn serializes_for_the_frontend() {
+        let e = AppError::model_missing("尚未下載模型").with_context("int8");
+        assert_eq!(
+            serde_json::to_value(&e).unwrap(),
+            serde_json::json!({
+                "kind": "model_missing",
+                "message": "尚未下載模型",
+                "retryable": false,
+                "context": "int8",
+            })
+        );
+        let plain = serde_json::to_value(AppError::from("x")).unwrap();
+        assert!(plain.get("context").is_none());
+    }
+}
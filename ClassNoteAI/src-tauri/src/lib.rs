@@ -4,6 +4,8 @@
 // reach `parakeet_model::Variant` for the INT8/FP32 bake-off.
 pub mod asr;
 mod whisper;
+// 音頻 DSP（重採樣等），採集與匯入路徑共用
+pub mod audio;
 // 工具模塊
 // `pub` so example binaries (e.g. `examples/ort_minimal.rs`) can call
 // `utils::onnx::init_onnx` and exercise the same Windows DLL-search
@@ -21,6 +23,10 @@ mod embedding;
 mod setup;
 // 統一路徑管理模塊
 pub mod paths;
+// 原生錄音播放（feature = "native-playback"）
+pub mod playback;
+// 即時轉錄管線（擷取 → VAD → ASR → 粗翻譯）
+pub mod pipeline;
 // 統一下載管理模塊
 pub mod diagnostics;
 pub mod agent_bridge;
@@ -30,17 +36,38 @@ pub mod downloads;
 mod oauth;
 // Crash-safe recording — incremental PCM persistence + orphan recovery
 pub mod recording;
+// 講義文件（PDF 頁面渲染等）
+pub mod documents;
+// 筆記匯出（PDF 等）
+pub mod notes;
 // GPU backend detection (CUDA via nvidia-smi, Metal via cfg, Vulkan via filesystem)
 mod gpu;
 mod updater;
+// App log file: plugin setup, location, reading it back
+pub mod logging;
+// Ordered model teardown on quit / update-restart
+mod shutdown;
+// Panic hook + crash reports (installed from `main`)
+pub mod crash;
+// Per-model memory accounting for load-time headroom checks
+mod memory;
+// Opt-in anonymous usage counters + crash fingerprints
+mod telemetry;
+// Startup self-test, crash-loop detection and safe mode
+mod startup;
+
+mod sync;
+// Registry of long-running work (downloads, conversions, exports) with list / cancel
+mod tasks;
+// Structured error type the commands reject with
+mod error;
 // Pre-WebView2 experimental toggles (remote debug port, etc). Public
 // so `main()` can `remote_debug_enabled()` before Tauri spins up.
 pub mod dev_flags;
 
 use embedding::EmbeddingService;
-use log::LevelFilter;
+use error::{AppError, ErrorKind};
 use tauri::{Emitter, Manager};
-use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
 use tokio::sync::Mutex;
 // 全局 Embedding 服務實例
 static EMBEDDING_SERVICE: Mutex<Option<EmbeddingService>> = Mutex::const_new(None);
@@ -56,7 +83,7 @@ fn greet(name: &str) -> String {
 /// lazily on first session. Returns success so any UI that still gates
 /// on this in legacy code paths doesn't error.
 #[tauri::command]
-async fn load_whisper_model(_model_path: String) -> Result<String, String> {
+async fn load_whisper_model(_model_path: String) -> Result<String, AppError> {
     Ok("Whisper backend removed in v2 streaming refactor; ASR is now in-process Nemotron".to_string())
 }
 
@@ -73,11 +100,31 @@ async fn detect_speech_segments(
     energy_threshold: Option<f32>,
     min_speech_duration_ms: Option<u64>,
     max_speech_duration_ms: Option<u64>,
-) -> Result<Vec<vad::SpeechSegment>, String> {
+    normalize_gain: Option<bool>,
+) -> Result<Vec<vad::SpeechSegment>, AppError> {
     use crate::vad::{VadConfig, VadDetector};
 
+    // Silero only runs at 16 kHz; resample up front instead of letting a
+    // 44.1/48 kHz buffer be read as 16 kHz. Segment times are in ms, so
+    // they stay valid against the caller's original buffer.
+    let audio_data = if sample_rate != asr::parakeet_engine::SAMPLE_RATE {
+        audio::resample::resample_i16(
+            &audio_data,
+            sample_rate,
+            asr::parakeet_engine::SAMPLE_RATE,
+        )?
+    } else {
+        audio_data
+    };
+    // Far-from-mic lecturers sit below the energy threshold; normalise
+    // the buffer to a speech-level target before detection.
+    let audio_data = if normalize_gain.unwrap_or(false) {
+        audio::loudness::normalize_i16(&audio_data, -23.0, 24.0)
+    } else {
+        audio_data
+    };
     let mut config = VadConfig::default();
-    config.sample_rate = sample_rate;
+    config.sample_rate = asr::parakeet_engine::SAMPLE_RATE;
 
     if let Some(threshold) = energy_threshold {
         config.energy_threshold = threshold;
@@ -117,13 +164,14 @@ async fn transcribe_audio(
     _sample_rate: u32,
     _initial_prompt: Option<String>,
     _language: Option<String>,
-) -> Result<serde_json::Value, String> {
+) -> Result<serde_json::Value, AppError> {
     Err(
         "transcribe_audio (Whisper) was removed in the v2 streaming \
          refactor. Use the in-process Nemotron engine via \
          asr_start_session / asr_push_audio / asr_end_session instead."
-            .to_string(),
+            .into(),
     )
+
 }
 
 /// 下載 Whisper 模型（支持進度事件和斷點續傳）
@@ -132,26 +180,26 @@ async fn download_whisper_model(
     app: tauri::AppHandle,
     model_type: String, // "base", "small", "tiny" 等
     output_dir: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     use std::path::Path;
     use whisper::download;
 
     let output_path = Path::new(&output_dir);
-    let config = match model_type.as_str() {
-        "tiny" => download::get_tiny_model_config(output_path),
-        "base" => download::get_base_model_config(output_path),
-        "small" => download::get_small_model_config(output_path),
-        "medium" => download::get_medium_model_config(output_path),
-        "large" => download::get_large_model_config(output_path),
-        "small-q5" => download::get_small_quantized_model_config(output_path),
-        "medium-q5" => download::get_medium_quantized_model_config(output_path),
-        "large-v3-turbo-q5" => download::get_large_v3_turbo_quantized_model_config(output_path),
-        _ => return Err(format!("不支持的模型類型: {}。支持的類型: tiny, base, small, medium, large, small-q5, medium-q5, large-v3-turbo-q5", model_type)),
+    let Some(config) = download::config_for(&model_type, output_path) else {
+        return Err(AppError::invalid_input(format!("不支持的模型類型: {}。支持的類型: tiny, base, small, medium, large, small-q5, medium-q5, large-v3-turbo-q5", model_type)));
     };
 
     // 下載模型（通過 Tauri 事件發送進度）
     let app_clone = app.clone();
     let model_type_clone = model_type.clone();
+    let source = downloads::control::DownloadSource::Whisper {
+        model_type: model_type.clone(),
+        output_dir: output_dir.clone(),
+    };
+    let task = std::sync::Arc::new(
+        downloads::control::start(&app, &source, format!("Whisper {}", model_type))
+            .map_err(AppError::busy)?,
+    );
 
     // 用於計算速度的變量
     let progress_last_time = std::sync::Arc::new(std::sync::Mutex::new(std::time::Instant::now()));
@@ -162,9 +210,11 @@ async fn download_whisper_model(
         let model_type_clone = model_type_clone.clone();
         let progress_last_time = progress_last_time.clone();
         let progress_last_downloaded = progress_last_downloaded.clone();
+        let task = task.clone();
 
         move |downloaded, total| {
             use tauri::Emitter; // 在閉包內部導入 Emitter trait
+            task.set_progress(downloaded, total);
             let now = std::time::Instant::now();
             let mut last_time = progress_last_time.lock().unwrap();
             let mut last_downloaded = progress_last_downloaded.lock().unwrap();
@@ -222,10 +272,15 @@ async fn download_whisper_model(
     use tauri::Emitter;
     let _ = app.emit(&format!("download-started-{}", model_type), &model_type);
 
-    let result = download::download_model(&config, progress_callback)
-        .await
-        .map(|path| format!("模型下載成功: {:?}", path))
-        .map_err(|e| format!("下載失敗: {}", e));
+    let result = task
+        .run(async {
+            download::download_model(&config, progress_callback)
+                .await
+                .map(|path| format!("模型下載成功: {:?}", path))
+                .map_err(|e| format!("下載失敗: {}", e))
+        })
+        .await;
+    downloads::control::settle(&source, &result);
 
     // 下載完成後發送完成事件
     match &result {
@@ -237,12 +292,12 @@ async fn download_whisper_model(
         }
     }
 
-    result
+    result.map_err(|e| AppError::from(e).or_kind(ErrorKind::Network))
 }
 
 /// 檢查模型文件是否存在
 #[tauri::command]
-async fn check_whisper_model(model_path: String) -> Result<bool, String> {
+async fn check_whisper_model(model_path: String) -> Result<bool, AppError> {
     use std::path::Path;
     use whisper::download;
 
@@ -275,7 +330,7 @@ async fn check_whisper_model(model_path: String) -> Result<bool, String> {
 
     download::check_model_file(path, expected_size)
         .await
-        .map_err(|e| format!("檢查失敗: {}", e))
+        .map_err(|e| format!("檢查失敗: {}", e).into())
 }
 
 /// 粗翻譯（本地 CT2 / TranslateGemma LLM / Google API）
@@ -287,7 +342,7 @@ async fn translate_rough(
     provider: Option<String>,       // "local" / "gemma" / "google"
     google_api_key: Option<String>, // Google API 密鑰（可選，僅 google provider 使用）
     gemma_endpoint: Option<String>, // llama-server URL（可選，僅 gemma provider 使用）
-) -> Result<translation::TranslationResult, String> {
+) -> Result<translation::TranslationResult, AppError> {
     // Default fallback differs by build: if `nmt-local` is compiled in we
     // honor the historical `local` default; otherwise default to `gemma`
     // (the only on-device backend available without the CT2 feature).
@@ -305,7 +360,7 @@ async fn translate_rough(
             google_api_key.as_deref(),
         )
         .await
-        .map_err(|e| e.to_string()),
+        .map_err(|e| AppError::network(e.to_string())),
         "gemma" => {
             // cp75.1: forward source/target lang to TranslateGemma so the
             // PTranslate language pickers actually take effect. Before
@@ -318,23 +373,25 @@ async fn translate_rough(
                 gemma_endpoint.as_deref(),
             )
             .await
-            .map_err(|e| e.to_string())
+            .map_err(|e| AppError::network(e.to_string()))
         }
         #[cfg(feature = "nmt-local")]
         "local" => translation::rough::translate_rough(&text, &source_lang, &target_lang)
             .await
-            .map_err(|e| e.to_string()),
+            .map_err(|e| e.to_string().into()),
         // When `nmt-local` is off and the user picked the local backend
         // anyway (e.g. legacy settings), surface a clear error rather than
         // silently falling back to a different language model.
         #[cfg(not(feature = "nmt-local"))]
-        "local" => Err(
+        "local" => Err(AppError::unsupported(
             "Local CTranslate2 backend not available in this build. \
              Switch to TranslateGemma (gemma) or Google in 設定 → 翻譯，\
-             or rebuild with `--features nmt-local`."
-                .to_string(),
-        ),
-        other => Err(format!("Unknown translation provider: {other}")),
+             or rebuild with `--features nmt-local`.",
+        )),
+        other => Err(AppError::invalid_input(format!(
+            "Unknown translation provider: {other}"
+        ))),
+
     }
 }
 
@@ -357,7 +414,7 @@ fn get_build_features() -> serde_json::Value {
 /// Probe the TranslateGemma sidecar's `/health` endpoint so the UI can
 /// show a green/red indicator without trying a full translation request.
 #[tauri::command]
-async fn check_gemma_server(endpoint: Option<String>) -> Result<bool, String> {
+async fn check_gemma_server(endpoint: Option<String>) -> Result<bool, AppError> {
     let base = endpoint
         .as_deref()
         .unwrap_or(translation::gemma::DEFAULT_ENDPOINT);
@@ -383,7 +440,7 @@ async fn start_gemma_sidecar(
     model_path: String,
     port: Option<u16>,
     app: tauri::AppHandle,
-) -> Result<translation::gemma_sidecar::BringUpResult, String> {
+) -> Result<translation::gemma_sidecar::BringUpResult, AppError> {
     let resource_dir = app.path().resource_dir().ok();
     let port = port.unwrap_or(translation::gemma_sidecar::DEFAULT_PORT);
     Ok(translation::gemma_sidecar::ensure_running(&model_path, port, resource_dir).await)
@@ -393,7 +450,7 @@ async fn start_gemma_sidecar(
 /// the user switches away from gemma in settings, or when the renderer
 /// wants to free the GPU for another task.
 #[tauri::command]
-fn stop_gemma_sidecar() -> Result<(), String> {
+fn stop_gemma_sidecar() -> Result<(), AppError> {
     translation::gemma_sidecar::shutdown();
     Ok(())
 }
@@ -402,7 +459,7 @@ fn stop_gemma_sidecar() -> Result<(), String> {
 /// without spawning. Lets the Settings UI show "binary missing — please
 /// install / wait for download" before the user tries to start it.
 #[tauri::command]
-fn locate_gemma_binary(app: tauri::AppHandle) -> Result<Option<String>, String> {
+fn locate_gemma_binary(app: tauri::AppHandle) -> Result<Option<String>, AppError> {
     let resource_dir = app.path().resource_dir().ok();
     Ok(translation::gemma_sidecar::locate_binary(resource_dir.as_ref())
         .map(|p| p.to_string_lossy().to_string()))
@@ -455,7 +512,7 @@ fn variant_from_str(s: &str) -> Result<Variant, String> {
 }
 
 #[tauri::command]
-fn get_parakeet_status() -> Result<ParakeetStatus, String> {
+fn get_parakeet_status() -> Result<ParakeetStatus, AppError> {
     let variants = Variant::all()
         .iter()
         .map(|&v| VariantStatus {
@@ -496,15 +553,46 @@ struct ParakeetDownloadProgress {
 async fn parakeet_download_model(
     app: tauri::AppHandle,
     variant: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     use tauri::Emitter as _;
 
-    let variant = variant_from_str(&variant)?;
+    let variant = variant_from_str(&variant).map_err(AppError::invalid_input)?;
     let configs = asr::parakeet_model::all_download_configs(variant)?;
     let total = asr::parakeet_model::total_size(variant);
 
+    let source = downloads::control::DownloadSource::Parakeet { variant };
+    let task = std::sync::Arc::new(
+        downloads::control::start(&app, &source, format!("Nemotron {}", variant.label()))
+            .map_err(AppError::busy)?,
+    );
     let _ = app.emit("parakeet-download-started", (variant, total));
+    let result = task
+        .run(parakeet_download_files(
+            &app, &task, variant, &configs, total,
+        ))
+        .await;
+    downloads::control::settle(&source, &result);
+    result.map_err(|e| AppError::from(e).or_kind(ErrorKind::Network))?;
+    let _ = app.emit("parakeet-download-completed", (variant, total));
+    Ok(format!(
+        "downloaded {} files for {} ({:.2} GB)",
+        configs.len(),
+        variant.label(),
+        total as f64 / 1e9
+    ))
+}
+
+async fn parakeet_download_files(
+    app: &tauri::AppHandle,
+    task: &std::sync::Arc<tasks::Task>,
+    variant: Variant,
+    configs: &[whisper::download::ModelDownloadConfig],
+    total: u64,
+) -> Result<(), String> {
+    use tauri::Emitter as _;
 
+    // Bytes of the files before the current one, for overall progress.
+    let mut done_before = 0u64;
     for (idx, config) in configs.iter().enumerate() {
         let file_name = config
             .output_path
@@ -515,8 +603,10 @@ async fn parakeet_download_model(
 
         let app_for_callback = app.clone();
         let file_name_for_cb = file_name.clone();
+        let task_for_cb = task.clone();
 
         let cb: Box<dyn Fn(u64, u64) + Send + Sync> = Box::new(move |downloaded, _file_total| {
+            task_for_cb.set_progress(done_before + downloaded, total);
             let _ = app_for_callback.emit(
                 "parakeet-download-progress",
                 ParakeetDownloadProgress {
@@ -547,15 +637,9 @@ async fn parakeet_download_model(
                 completed: true,
             },
         );
+        done_before += file_size;
     }
-
-    let _ = app.emit("parakeet-download-completed", (variant, total));
-    Ok(format!(
-        "downloaded {} files for {} ({:.2} GB)",
-        configs.len(),
-        variant.label(),
-        total as f64 / 1e9
-    ))
+    Ok(())
 }
 
 /// Load (or swap) the Nemotron model. Different variant than what's
@@ -569,28 +653,30 @@ async fn parakeet_download_model(
 /// localized error so the UI can prompt the user to stop the recording
 /// first instead of silently producing a corrupt transcript.
 #[tauri::command]
-async fn parakeet_load_model(variant: String) -> Result<(), String> {
+async fn parakeet_load_model(variant: String) -> Result<(), AppError> {
     if asr::parakeet_engine::has_session() {
-        return Err("錄音進行中無法切換模型，請先停止錄音".to_string());
+        return Err(AppError::busy("錄音進行中無法切換模型，請先停止錄音"));
     }
-    let variant = variant_from_str(&variant)?;
+    let variant = variant_from_str(&variant).map_err(AppError::invalid_input)?;
     if !asr::parakeet_model::is_present(variant) {
-        return Err(format!(
+        return Err(AppError::model_missing(format!(
             "Nemotron {} model files not on disk. Download first.",
             variant.label()
-        ));
+        ))
+        .with_context(variant.label()));
     }
     let dir = asr::parakeet_model::model_dir(variant)?;
     tokio::task::spawn_blocking(move || asr::parakeet_engine::ensure_loaded(variant, &dir))
         .await
         .map_err(|e| format!("load_model task join error: {e}"))?
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-async fn parakeet_unload_model() -> Result<(), String> {
+async fn parakeet_unload_model() -> Result<(), AppError> {
     tokio::task::spawn_blocking(asr::parakeet_engine::unload)
         .await
-        .map_err(|e| format!("unload_model task join error: {e}"))
+        .map_err(|e| format!("unload_model task join error: {e}").into())
 }
 
 /// Begin an ASR session.
@@ -605,15 +691,23 @@ async fn parakeet_unload_model() -> Result<(), String> {
 ///     warm).
 /// If no variant is preferred or the requested variant isn't downloaded,
 /// fall back to first_present() (legacy behaviour).
+///
+/// `normalize_gain`: settings.experimental.normalizeGain — runs the
+/// session's audio through `audio::loudness::Agc` before the model.
+/// `denoise`: settings.experimental.denoiseInput — `audio::denoise`
+/// ahead of the AGC.
 #[tauri::command]
 async fn asr_start_session(
     session_id: String,
     preferred_variant: Option<String>,
-) -> Result<(), String> {
+    normalize_gain: Option<bool>,
+    denoise: Option<bool>,
+) -> Result<(), AppError> {
     let want: Option<asr::parakeet_model::Variant> = preferred_variant
         .as_deref()
         .map(variant_from_str)
-        .transpose()?;
+        .transpose()
+        .map_err(AppError::invalid_input)?;
 
     let needs_load = !asr::parakeet_engine::is_loaded()
         || want
@@ -626,7 +720,9 @@ async fn asr_start_session(
             .filter(|v| asr::parakeet_model::is_present(*v))
             .or_else(asr::parakeet_model::first_present)
             .ok_or_else(|| {
-                "No Nemotron model downloaded — open 設定 → 本地轉錄 to download.".to_string()
+                AppError::model_missing(
+                    "No Nemotron model downloaded — open 設定 → 本地轉錄 to download.",
+                )
             })?;
         let dir = asr::parakeet_model::model_dir(variant)?;
         tokio::task::spawn_blocking(move || asr::parakeet_engine::ensure_loaded(variant, &dir))
@@ -634,9 +730,43 @@ async fn asr_start_session(
             .map_err(|e| format!("auto-load task join error: {e}"))??;
     }
     let id = session_id.clone();
-    tokio::task::spawn_blocking(move || asr::parakeet_engine::start_session(id))
-        .await
-        .map_err(|e| format!("start_session task join error: {e}"))?
+    let agc = normalize_gain.unwrap_or(false);
+    let denoise = denoise.unwrap_or(false);
+    tokio::task::spawn_blocking(move || {
+        asr::parakeet_engine::start_session(id.clone())?;
+        if denoise {
+            asr::parakeet_engine::set_session_denoise(&id, true)?;
+        }
+        if agc {
+            asr::parakeet_engine::set_session_agc(&id, true)?;
+        }
+        Ok::<(), String>(())
+    })
+    .await
+    .map_err(|e| format!("start_session task join error: {e}"))?
+    .map_err(AppError::from)
+}
+
+/// Settings "before / after" preview for the noise-suppression toggle.
+/// Returns the clip denoised at 16 kHz (resampled first if needed) so
+/// the renderer can play both versions back to back.
+#[tauri::command]
+async fn preview_denoise(audio_data: Vec<i16>, sample_rate: u32) -> Result<Vec<i16>, AppError> {
+    tokio::task::spawn_blocking(move || {
+        let pcm = if sample_rate != asr::parakeet_engine::SAMPLE_RATE {
+            audio::resample::resample_i16(
+                &audio_data,
+                sample_rate,
+                asr::parakeet_engine::SAMPLE_RATE,
+            )?
+        } else {
+            audio_data
+        };
+        Ok::<_, String>(audio::denoise::denoise_i16(&pcm))
+    })
+    .await
+    .map_err(|e| format!("preview_denoise task join error: {e}"))?
+    .map_err(AppError::from)
 }
 
 /// Push int16 PCM. Drains pending chunks through the model and emits
@@ -655,7 +785,8 @@ async fn asr_push_audio(
     app: tauri::AppHandle,
     session_id: String,
     pcm: Vec<i16>,
-) -> Result<(), String> {
+    sample_rate: Option<u32>,
+) -> Result<(), AppError> {
     use tauri::Emitter as _;
     let sid_for_engine = session_id.clone();
     let sid_for_event = session_id.clone();
@@ -664,9 +795,10 @@ async fn asr_push_audio(
         // engine Mutex across `app.emit` (which can do non-trivial
         // work serializing JSON for every webview window).
         let mut deltas: Vec<(String, String, f32)> = Vec::new();
-        let res = asr::parakeet_engine::push_pcm_i16(
+        let res = asr::parakeet_engine::push_pcm_i16_at(
             &sid_for_engine,
             &pcm,
+            sample_rate.unwrap_or(asr::parakeet_engine::SAMPLE_RATE),
             |delta, transcript, audio_end_sec| {
                 deltas.push((delta.to_string(), transcript.to_string(), audio_end_sec));
             },
@@ -686,6 +818,7 @@ async fn asr_push_audio(
     })
     .await
     .map_err(|e| format!("push_audio task join error: {e}"))?
+    .map_err(AppError::from)
 }
 
 /// End the session. Pads + flushes the decoder, returns the cumulative
@@ -703,7 +836,7 @@ struct AsrSessionEndedEvent {
 async fn asr_end_session(
     app: tauri::AppHandle,
     session_id: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     use tauri::Emitter as _;
     let sid_for_engine = session_id.clone();
     let sid_for_event = session_id.clone();
@@ -738,6 +871,7 @@ async fn asr_end_session(
     })
     .await
     .map_err(|e| format!("end_session task join error: {e}"))?
+    .map_err(AppError::from)
 }
 
 /// Combined status snapshot for the TranslateGemma backend. Single round
@@ -776,7 +910,7 @@ struct GemmaVariantStatus {
 }
 
 #[tauri::command]
-fn get_gemma_status(app: tauri::AppHandle) -> Result<GemmaStatus, String> {
+fn get_gemma_status(app: tauri::AppHandle) -> Result<GemmaStatus, AppError> {
     let resource_dir = app.path().resource_dir().ok();
     let variants: Vec<GemmaVariantStatus> = translation::gemma_model::Variant::all()
         .iter()
@@ -823,7 +957,7 @@ fn get_gemma_status(app: tauri::AppHandle) -> Result<GemmaStatus, String> {
 async fn download_gemma_model(
     app: tauri::AppHandle,
     variant: Option<String>,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     use std::sync::{Arc, Mutex};
     use std::time::Instant;
 
@@ -833,8 +967,9 @@ async fn download_gemma_model(
 
     let v = match variant.as_deref() {
         None | Some("") => translation::gemma_model::Variant::B4,
-        Some(s) => translation::gemma_model::Variant::from_str(s)
-            .ok_or_else(|| format!("unknown gemma variant: {s} (expected 4b|12b|27b)"))?,
+        Some(s) => translation::gemma_model::Variant::from_str(s).ok_or_else(|| {
+            AppError::invalid_input(format!("unknown gemma variant: {s} (expected 4b|12b|27b)"))
+        })?,
     };
     let config = translation::gemma_model::download_config_for(v)?;
 
@@ -849,12 +984,19 @@ async fn download_gemma_model(
     let app_clone = app.clone();
     let last_time = Arc::new(Mutex::new(Instant::now()));
     let last_downloaded = Arc::new(Mutex::new(0u64));
+    let source = downloads::control::DownloadSource::Gemma { variant: v };
+    let task = Arc::new(
+        downloads::control::start(&app, &source, format!("TranslateGemma {}", v.label()))
+            .map_err(AppError::busy)?,
+    );
 
     let progress_callback: Option<Box<dyn Fn(u64, u64) + Send + Sync>> = Some(Box::new({
         let app_clone = app_clone.clone();
         let last_time = last_time.clone();
         let last_downloaded = last_downloaded.clone();
+        let task = task.clone();
         move |downloaded, total| {
+            task.set_progress(downloaded, total);
             let now = Instant::now();
             let mut lt = last_time.lock().unwrap();
             let mut ld = last_downloaded.lock().unwrap();
@@ -898,36 +1040,258 @@ async fn download_gemma_model(
         }
     }));
 
-    let path = download::download_model(&config, progress_callback)
+    let result = task
+        .run(async {
+            let path = download::download_model(&config, progress_callback)
+                .await
+                .map_err(|e| format!("Gemma 模型下載失敗: {e}"))?;
+
+            // cp75.13 — post-download integrity check. The HTTP-layer guards in
+            // `whisper::download::download_model` (cp75.12) catch 4xx/5xx, but a
+            // legit 200 with the wrong body (HF redirect index page, partial
+            // CDN truncation, etc.) still writes garbage to disk. For 12B / 27B
+            // we use a wide ±5% expected-size band; verify the downloaded file
+            // actually sits in that band, otherwise delete it and bubble the
+            // error up so the UI can react instead of falsely declaring success.
+            if !translation::gemma_model::is_present_for(v) {
+                let actual = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                let expected = v.expected_size();
+                // Best-effort cleanup so the next click re-downloads instead of
+                // hitting the "file exists, skip" fast path.
+                let _ = std::fs::remove_file(&path);
+                return Err(format!(
+                    "Gemma {} download finished but file size looks wrong: \
+                     {} bytes on disk vs. expected ~{} bytes. The HuggingFace URL \
+                     may not exist or the response was a redirect/index page. \
+                     URL: {}",
+                    v.label(),
+                    actual,
+                    expected,
+                    v.url(),
+                ));
+            }
+
+            Ok(path)
+        })
+        .await;
+    downloads::control::settle(&source, &result);
+    let path = result.map_err(|e| AppError::from(e).or_kind(ErrorKind::Network))?;
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Model downloads that were started and haven't finished: running,
+/// paused, or interrupted by a quit (see `downloads::control`). The
+/// setup wizard's own files are listed by `list_setup_downloads`.
+#[tauri::command]
+fn list_downloads() -> Vec<downloads::control::PendingDownload> {
+    downloads::control::pending()
+        .into_iter()
+        .filter(|p| !p.source.is_setup())
+        .collect()
+}
+
+/// Stop a running model download, keeping what it has fetched so far.
+/// Returns `false` if it wasn't running.
+#[tauri::command]
+fn pause_download(id: String) -> Result<bool, AppError> {
+    Ok(downloads::control::pause(&id)?)
+}
+
+/// Continue a paused or interrupted download where it stopped.
+/// Resolves when the download finishes, like the command that started it.
+#[tauri::command]
+async fn resume_download(app: tauri::AppHandle, id: String) -> Result<String, AppError> {
+    let pending = downloads::control::find(&id)
+        .ok_or_else(|| AppError::not_found(format!("找不到可繼續的下載: {}", id)))?;
+    run_download(app, pending.source).await
+}
+
+/// Stop a download and delete its partial files. Returns `false` if
+/// there was nothing to cancel.
+#[tauri::command]
+fn cancel_download(id: String) -> Result<bool, AppError> {
+    Ok(downloads::control::cancel(&id)?)
+}
+
+/// Which hosts model downloads try first (see `downloads::mirrors`).
+#[tauri::command]
+async fn get_download_mirrors() -> Result<downloads::mirrors::MirrorSettings, AppError> {
+    Ok(downloads::mirrors::settings().await?)
+}
+
+/// Takes effect from the next download; a running one keeps its order.
+#[tauri::command]
+async fn set_download_mirrors(
+    settings: downloads::mirrors::MirrorSettings,
+) -> Result<downloads::mirrors::MirrorSettings, AppError> {
+    downloads::mirrors::set_settings(settings)
+        .await
+        .map_err(|e| AppError::from(e).or_kind(ErrorKind::InvalidInput))
+}
+
+/// Download speed limit in bytes per second; `0` when unlimited.
+#[tauri::command]
+fn get_download_rate_limit() -> u64 {
+    downloads::throttle::limit()
+}
+
+/// Applies to running downloads as well.
+#[tauri::command]
+async fn set_download_rate_limit(bytes_per_sec: u64) -> Result<u64, AppError> {
+    downloads::throttle::set_limit(bytes_per_sec)
         .await
-        .map_err(|e| format!("Gemma 模型下載失敗: {e}"))?;
-
-    // cp75.13 — post-download integrity check. The HTTP-layer guards in
-    // `whisper::download::download_model` (cp75.12) catch 4xx/5xx, but a
-    // legit 200 with the wrong body (HF redirect index page, partial
-    // CDN truncation, etc.) still writes garbage to disk. For 12B / 27B
-    // we use a wide ±5% expected-size band; verify the downloaded file
-    // actually sits in that band, otherwise delete it and bubble the
-    // error up so the UI can react instead of falsely declaring success.
-    if !translation::gemma_model::is_present_for(v) {
-        let actual = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
-        let expected = v.expected_size();
-        // Best-effort cleanup so the next click re-downloads instead of
-        // hitting the "file exists, skip" fast path.
-        let _ = std::fs::remove_file(&path);
-        return Err(format!(
-            "Gemma {} download finished but file size looks wrong: \
-             {} bytes on disk vs. expected ~{} bytes. The HuggingFace URL \
-             may not exist or the response was a redirect/index page. \
-             URL: {}",
-            v.label(),
-            actual,
-            expected,
-            v.url(),
-        ));
+        .map_err(|e| AppError::from(e).or_kind(ErrorKind::InvalidInput))
+}
+
+/// Proxy for model downloads (see `downloads::proxy`); the password is
+/// redacted to an empty string when one is saved.
+#[tauri::command]
+async fn get_download_proxy() -> Result<downloads::proxy::ProxySettings, AppError> {
+    Ok(downloads::proxy::settings().await?)
+}
+
+/// Takes effect from the next request; a running download keeps its
+/// connection.
+#[tauri::command]
+async fn set_download_proxy(
+    settings: downloads::proxy::ProxySettings,
+) -> Result<downloads::proxy::ProxySettings, AppError> {
+    downloads::proxy::set_settings(settings)
+        .await
+        .map_err(|e| AppError::from(e).or_kind(ErrorKind::InvalidInput))
+}
+
+/// Every model under the models directory with its size and when it
+/// was last loaded, for the model manager.
+#[tauri::command]
+async fn list_installed_models() -> Result<Vec<downloads::installed::InstalledModel>, AppError> {
+    tokio::task::spawn_blocking(downloads::installed::list)
+        .await
+        .map_err(|e| format!("list models task join error: {e}"))?
+        .map_err(AppError::from)
+}
+
+/// Delete an installed model by id. Returns the bytes freed. Refuses
+/// while the model is in use or still downloading.
+#[tauri::command]
+async fn delete_model(id: String) -> Result<u64, AppError> {
+    let model = downloads::installed::list()?
+        .into_iter()
+        .find(|m| m.id == id)
+        .ok_or_else(|| AppError::not_found(format!("找不到模型: {}", id)))?;
+    release_model(&model, "刪除").await?;
+
+    tokio::task::spawn_blocking(move || downloads::installed::delete(&id))
+        .await
+        .map_err(|e| format!("delete model task join error: {e}"))?
+        .map_err(AppError::from)
+}
+
+/// Make sure nothing holds `model`'s files before they're deleted or
+/// replaced: unload an idle Parakeet model, refuse (`busy`) while it's
+/// recording, served by the TranslateGemma sidecar, or downloading.
+/// `action` names what's refused, e.g. `刪除`.
+async fn release_model(
+    model: &downloads::installed::InstalledModel,
+    action: &str,
+) -> Result<(), AppError> {
+    let id = model.id.clone();
+    let path = std::path::Path::new(&model.path);
+    let downloading = downloads::control::pending().into_iter().any(|p| {
+        p.source
+            .configs()
+            .is_ok_and(|configs| configs.iter().any(|c| c.output_path.starts_with(path)))
+    });
+    if downloading {
+        return Err(AppError::busy("這個模型還有未完成的下載，請先取消下載").with_context(id));
+    }
+    match model.engine.as_str() {
+        "parakeet" => {
+            let loaded = asr::parakeet_engine::loaded_variant().map(|v| v.dir_name());
+            if loaded == Some(model.id.as_str()) {
+                if asr::parakeet_engine::has_session() {
+                    return Err(AppError::busy(format!(
+                        "錄音進行中無法{}使用中的模型，請先停止錄音",
+                        action
+                    ))
+                    .with_context(id));
+                }
+                tokio::task::spawn_blocking(asr::parakeet_engine::unload)
+                    .await
+                    .map_err(|e| format!("unload_model task join error: {e}"))?;
+            }
+        }
+        "translategemma" if translation::gemma_sidecar::is_running() => {
+            return Err(AppError::busy(format!(
+                "TranslateGemma 翻譯服務運行中，請先停止再{}模型",
+                action
+            ))
+            .with_context(id));
+        }
+        _ => {}
     }
+    Ok(())
+}
 
-    Ok(path.to_string_lossy().to_string())
+/// Installed models with a newer release in the published model
+/// manifest, with the changelog of each.
+#[tauri::command]
+async fn check_model_updates() -> Result<Vec<downloads::updates::ModelUpdate>, AppError> {
+    Ok(downloads::updates::check().await?)
+}
+
+/// Install the update `check_model_updates` offered for installed model
+/// `id`. Returns the id of the model installed: `id` itself for an
+/// update, the new model for an upgrade.
+#[tauri::command]
+async fn apply_model_update(app: tauri::AppHandle, id: String) -> Result<String, AppError> {
+    let plan = downloads::updates::find(&id)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("模型 {} 沒有可用的更新", id)))?;
+    if plan.update.kind == downloads::updates::UpdateKind::Update {
+        // Replaced in place, so it can't be in use.
+        let model = downloads::installed::list()?
+            .into_iter()
+            .find(|m| m.id == id)
+            .ok_or_else(|| AppError::not_found(format!("找不到模型: {}", id)))?;
+        release_model(&model, "更新").await?;
+    }
+
+    let task = std::sync::Arc::new(tasks::Task::start(
+        &app,
+        tasks::TaskKind::Download,
+        format!("更新模型 {} {}", plan.update.label, plan.update.version),
+    ));
+    let on_progress: std::sync::Arc<dyn Fn(u64, u64) + Send + Sync> = {
+        let task = task.clone();
+        std::sync::Arc::new(move |done, total| task.set_progress(done, total))
+    };
+    Ok(task
+        .run(downloads::updates::apply(plan, on_progress))
+        .await?)
+}
+
+async fn run_download(
+    app: tauri::AppHandle,
+    source: downloads::control::DownloadSource,
+) -> Result<String, AppError> {
+    use downloads::control::DownloadSource;
+    match source {
+        DownloadSource::Whisper {
+            model_type,
+            output_dir,
+        } => download_whisper_model(app, model_type, output_dir).await,
+        DownloadSource::Parakeet { variant } => {
+            parakeet_download_model(app, variant.label().to_string()).await
+        }
+        DownloadSource::Gemma { variant } => {
+            download_gemma_model(app, Some(variant.label().to_string())).await
+        }
+        DownloadSource::Setup { .. } => Err(AppError::invalid_input(
+            "設定精靈的下載請回到設定精靈繼續",
+        )),
+    }
 }
 
 // Fine translation + remote service check were removed in v0.5.0.
@@ -946,15 +1310,15 @@ const NMT_LOCAL_DISABLED: &str =
 
 /// 載入 CTranslate2 翻譯模型
 #[tauri::command]
-async fn load_ct2_model(model_path: String) -> Result<(), String> {
+async fn load_ct2_model(model_path: String) -> Result<(), AppError> {
     #[cfg(feature = "nmt-local")]
     {
-        translation::ctranslate2::load_ct2_model(&model_path).await
+        Ok(translation::ctranslate2::load_ct2_model(&model_path).await?)
     }
     #[cfg(not(feature = "nmt-local"))]
     {
         let _ = model_path;
-        Err(NMT_LOCAL_DISABLED.to_string())
+        Err(AppError::unsupported(NMT_LOCAL_DISABLED))
     }
 }
 
@@ -973,29 +1337,29 @@ async fn is_ct2_loaded() -> bool {
 
 /// 使用 CTranslate2 進行翻譯
 #[tauri::command]
-async fn translate_ct2(text: String) -> Result<String, String> {
+async fn translate_ct2(text: String) -> Result<String, AppError> {
     #[cfg(feature = "nmt-local")]
     {
-        translation::ctranslate2::translate_ct2(&text).await
+        Ok(translation::ctranslate2::translate_ct2(&text).await?)
     }
     #[cfg(not(feature = "nmt-local"))]
     {
         let _ = text;
-        Err(NMT_LOCAL_DISABLED.to_string())
+        Err(AppError::unsupported(NMT_LOCAL_DISABLED))
     }
 }
 
 /// 使用 CTranslate2 進行批量翻譯
 #[tauri::command]
-async fn translate_ct2_batch(texts: Vec<String>) -> Result<Vec<String>, String> {
+async fn translate_ct2_batch(texts: Vec<String>) -> Result<Vec<String>, AppError> {
     #[cfg(feature = "nmt-local")]
     {
-        translation::ctranslate2::translate_ct2_batch(&texts).await
+        Ok(translation::ctranslate2::translate_ct2_batch(&texts).await?)
     }
     #[cfg(not(feature = "nmt-local"))]
     {
         let _ = texts;
-        Err(NMT_LOCAL_DISABLED.to_string())
+        Err(AppError::unsupported(NMT_LOCAL_DISABLED))
     }
 }
 
@@ -1007,15 +1371,16 @@ async fn download_translation_model(
     model_name: String,
     _output_dir: String, // Ignored - uses unified paths
     window: tauri::Window,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     use downloads::{download_model, get_translation_model_configs, DownloadProgress};
+    use setup::progress::{Progress, PROGRESS_EVENT};
 
     // Find model config
     let configs = get_translation_model_configs();
     let config = configs
         .iter()
         .find(|c| c.name == model_name)
-        .ok_or_else(|| format!("不支持的模型: {}", model_name))?
+        .ok_or_else(|| AppError::invalid_input(format!("不支持的模型: {}", model_name)))?
         .clone();
 
     println!(
@@ -1023,20 +1388,34 @@ async fn download_translation_model(
         config.name, config.download_url
     );
 
+    let task = tasks::Task::start(
+        window.app_handle(),
+        tasks::TaskKind::Download,
+        model_name.clone(),
+    );
+
+    let _ = window.emit(
+        PROGRESS_EVENT,
+        Progress::pending(&model_name, &config.display_name),
+    );
+
     // Progress callback that emits to frontend
     let window_clone = window.clone();
     let model_name_clone = model_name.clone();
+    let display_name = config.display_name.clone();
+    let task_ref = &task;
     let progress_callback = move |progress: DownloadProgress| {
+        task_ref.set_progress(progress.downloaded, progress.total);
         // Emit progress event to frontend
         let _ = window_clone.emit(
-            "translation_download_progress",
-            serde_json::json!({
-                "model": model_name_clone,
-                "downloaded": progress.downloaded,
-                "total": progress.total,
-                "percent": progress.percent,
-                "speed_mbps": progress.speed_mbps,
-            }),
+            PROGRESS_EVENT,
+            Progress::in_progress(
+                &model_name_clone,
+                &display_name,
+                progress.downloaded,
+                progress.total,
+            )
+            .with_speed((progress.speed_mbps * 1_000_000.0) as u64),
         );
 
         // Log progress
@@ -1049,16 +1428,28 @@ async fn download_translation_model(
     };
 
     // Download using unified downloader
-    let model_path = download_model(&config, Some(progress_callback))
-        .await
-        .map_err(|e| format!("下載失敗: {}", e))?;
+    let result = task
+        .run(async {
+            download_model(&config, Some(progress_callback))
+                .await
+                .map_err(|e| format!("下載失敗: {}", e))
+        })
+        .await;
+    let _ = window.emit(
+        PROGRESS_EVENT,
+        match &result {
+            Ok(_) => Progress::completed(&model_name, &config.display_name),
+            Err(e) => Progress::failed(&model_name, &config.display_name, e),
+        },
+    );
+    let model_path = result.map_err(|e| AppError::from(e).or_kind(ErrorKind::Network))?;
 
     Ok(format!("翻譯模型下載成功: {:?}", model_path))
 }
 
 /// 檢查翻譯模型文件是否存在
 #[tauri::command]
-async fn check_translation_model(model_path: String) -> Result<bool, String> {
+async fn check_translation_model(model_path: String) -> Result<bool, AppError> {
     use std::path::Path;
 
     let path = Path::new(&model_path);
@@ -1075,11 +1466,11 @@ async fn check_translation_model(model_path: String) -> Result<bool, String> {
 async fn load_translation_model(
     model_dir: String,
     _tokenizer_path: Option<String>,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     #[cfg(not(feature = "nmt-local"))]
     {
         let _ = model_dir;
-        return Err(NMT_LOCAL_DISABLED.to_string());
+        return Err(AppError::unsupported(NMT_LOCAL_DISABLED));
     }
     #[cfg(feature = "nmt-local")]
     {
@@ -1087,7 +1478,10 @@ async fn load_translation_model(
         let path = Path::new(&model_dir);
         let model_bin_path = path.join("model.bin");
         if !model_bin_path.exists() {
-            return Err(format!("CT2 模型文件不存在: {:?}", model_bin_path));
+            return Err(AppError::model_missing(format!(
+                "CT2 模型文件不存在: {:?}",
+                model_bin_path
+            )));
         }
         translation::ctranslate2::load_ct2_model(&model_dir).await?;
         Ok("CTranslate2 翻譯模型加載成功".to_string())
@@ -1098,7 +1492,7 @@ async fn load_translation_model(
 ///
 /// 使用統一路徑掃描 translation 目錄，查找所有可用的翻譯模型
 #[tauri::command]
-async fn list_available_translation_models() -> Result<Vec<String>, String> {
+async fn list_available_translation_models() -> Result<Vec<String>, AppError> {
     use std::fs;
 
     // 使用統一路徑: {app_data}/models/translation/
@@ -1238,16 +1632,17 @@ async fn load_translation_model_by_name_impl(model_name: String) -> Result<Strin
 /// returns a descriptive error so the renderer can guide the user to a
 /// supported provider rather than seeing a generic "command not found".
 #[tauri::command]
-async fn load_translation_model_by_name(model_name: String) -> Result<String, String> {
+async fn load_translation_model_by_name(model_name: String) -> Result<String, AppError> {
     #[cfg(feature = "nmt-local")]
     {
-        load_translation_model_by_name_impl(model_name).await
+        Ok(load_translation_model_by_name_impl(model_name).await?)
     }
     #[cfg(not(feature = "nmt-local"))]
     {
         let _ = model_name;
-        Err(NMT_LOCAL_DISABLED.to_string())
+        Err(AppError::unsupported(NMT_LOCAL_DISABLED))
     }
+
 }
 
 // ========== 數據存儲相關 Commands ==========
@@ -1272,7 +1667,7 @@ async fn load_translation_model_by_name(model_name: String) -> Result<String, St
 async fn save_course(
     course: storage::Course,
     user_id: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1286,7 +1681,9 @@ async fn save_course(
 
     let user = user_id.unwrap_or_else(|| "default_user".to_string());
     if course.user_id != user {
-        return Err("無權保存此課程（user_id 不一致）".to_string());
+        return Err(AppError::permission_denied(
+            "無權保存此課程（user_id 不一致）",
+        ));
     }
     // If the course already exists, the existing row must belong to the
     // caller. New rows have no existing owner — fall through.
@@ -1302,7 +1699,7 @@ async fn save_course(
 
 /// 獲取科目
 #[tauri::command]
-async fn get_course(id: String) -> Result<Option<storage::Course>, String> {
+async fn get_course(id: String) -> Result<Option<storage::Course>, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1312,12 +1709,12 @@ async fn get_course(id: String) -> Result<Option<storage::Course>, String> {
         .map_err(|e| format!("數據庫連接失敗: {}", e))?;
 
     db.get_course(&id)
-        .map_err(|e| format!("獲取科目失敗: {}", e))
+        .map_err(|e| format!("獲取科目失敗: {}", e).into())
 }
 
 /// 列出所有科目
 #[tauri::command]
-async fn list_courses(user_id: String) -> Result<Vec<storage::Course>, String> {
+async fn list_courses(user_id: String) -> Result<Vec<storage::Course>, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1327,7 +1724,7 @@ async fn list_courses(user_id: String) -> Result<Vec<storage::Course>, String> {
         .map_err(|e| format!("數據庫連接失敗: {}", e))?;
 
     db.list_courses(&user_id)
-        .map_err(|e| format!("列出科目失敗: {}", e))
+        .map_err(|e| format!("列出科目失敗: {}", e).into())
 }
 
 /// 刪除科目
@@ -1339,7 +1736,7 @@ async fn list_courses(user_id: String) -> Result<Vec<storage::Course>, String> {
 /// non-cascade path to parity so the Tauri command surface has no
 /// holes.
 #[tauri::command]
-async fn delete_course(id: String, user_id: Option<String>) -> Result<(), String> {
+async fn delete_course(id: String, user_id: Option<String>) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1362,7 +1759,7 @@ async fn delete_course(id: String, user_id: Option<String>) -> Result<(), String
 async fn list_lectures_by_course(
     course_id: String,
     user_id: String,
-) -> Result<Vec<storage::Lecture>, String> {
+) -> Result<Vec<storage::Lecture>, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1372,12 +1769,12 @@ async fn list_lectures_by_course(
         .map_err(|e| format!("數據庫連接失敗: {}", e))?;
 
     db.list_lectures_by_course(&course_id, &user_id)
-        .map_err(|e| format!("列出課程失敗: {}", e))
+        .map_err(|e| format!("列出課程失敗: {}", e).into())
 }
 
 /// 保存課程
 #[tauri::command]
-async fn save_lecture(lecture: storage::Lecture, user_id: String) -> Result<(), String> {
+async fn save_lecture(lecture: storage::Lecture, user_id: String) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1397,7 +1794,7 @@ async fn save_lecture(lecture: storage::Lecture, user_id: String) -> Result<(),
 
 /// 獲取課程
 #[tauri::command]
-async fn get_lecture(id: String) -> Result<Option<storage::Lecture>, String> {
+async fn get_lecture(id: String) -> Result<Option<storage::Lecture>, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1407,12 +1804,12 @@ async fn get_lecture(id: String) -> Result<Option<storage::Lecture>, String> {
         .map_err(|e| format!("數據庫連接失敗: {}", e))?;
 
     db.get_lecture(&id)
-        .map_err(|e| format!("獲取課程失敗: {}", e))
+        .map_err(|e| format!("獲取課程失敗: {}", e).into())
 }
 
 /// 列出所有課程
 #[tauri::command]
-async fn list_lectures(user_id: String) -> Result<Vec<storage::Lecture>, String> {
+async fn list_lectures(user_id: String) -> Result<Vec<storage::Lecture>, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1422,12 +1819,12 @@ async fn list_lectures(user_id: String) -> Result<Vec<storage::Lecture>, String>
         .map_err(|e| format!("數據庫連接失敗: {}", e))?;
 
     db.list_lectures(&user_id)
-        .map_err(|e| format!("列出課程失敗: {}", e))
+        .map_err(|e| format!("列出課程失敗: {}", e).into())
 }
 
 /// 刪除課堂 (soft-delete)。cp75.6 加 user_id ownership check 防跨 user 動作。
 #[tauri::command]
-async fn delete_lecture(id: String, user_id: Option<String>) -> Result<(), String> {
+async fn delete_lecture(id: String, user_id: Option<String>) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1456,7 +1853,7 @@ async fn update_lecture_status(
     id: String,
     status: String,
     user_id: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1480,7 +1877,7 @@ async fn update_lecture_status(
 #[tauri::command]
 async fn list_orphaned_recording_lectures(
     user_id: Option<String>,
-) -> Result<Vec<storage::Lecture>, String> {
+) -> Result<Vec<storage::Lecture>, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1491,7 +1888,7 @@ async fn list_orphaned_recording_lectures(
 
     let user = user_id.unwrap_or_else(|| "default_user".to_string());
     db.list_orphaned_recording_lectures(&user)
-        .map_err(|e| format!("查詢 orphan lectures 失敗: {}", e))
+        .map_err(|e| format!("查詢 orphan lectures 失敗: {}", e).into())
 }
 
 /// 保存字幕
@@ -1504,7 +1901,7 @@ async fn list_orphaned_recording_lectures(
 async fn save_subtitle(
     subtitle: storage::Subtitle,
     user_id: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1533,7 +1930,7 @@ async fn save_subtitle(
 async fn save_subtitles(
     subtitles: Vec<storage::Subtitle>,
     user_id: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1562,17 +1959,72 @@ async fn save_subtitles(
 
 /// 獲取課程的所有字幕
 #[tauri::command]
-async fn get_subtitles(lecture_id: String) -> Result<Vec<storage::Subtitle>, String> {
+async fn get_subtitles(lecture_id: String) -> Result<Vec<storage::Subtitle>, AppError> {
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+
+    // Lazy path: only lectures with a `transcript_blobs` pointer touch
+    // the filesystem; everything else is the plain SQLite read.
+    let transcripts_dir = paths::get_transcripts_dir()?;
+    Ok(storage::transcript_store::load_subtitles_merged(
+        &db,
+        &transcripts_dir,
+        &lecture_id,
+    )?)
+}
+
+/// Move a lecture's subtitles out of SQLite into a compressed blob under
+/// `{app_data}/transcripts/`. Opt-in from Settings → 儲存空間 for users on
+/// slow disks; `get_subtitles` keeps returning the same rows afterwards.
+#[tauri::command]
+async fn externalize_lecture_transcript(
+    lecture_id: String,
+    user_id: Option<String>,
+) -> Result<storage::transcript_store::TranscriptPointer, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    verify_lecture_ownership(&db, &lecture_id, &user)?;
 
+    let transcripts_dir = paths::get_transcripts_dir()?;
+    Ok(storage::transcript_store::externalize_lecture(
+        &db,
+        &transcripts_dir,
+        &lecture_id,
+    )?)
+}
+
+/// Inverse of `externalize_lecture_transcript`. Returns the number of
+/// rows written back (0 if the lecture was never externalized).
+#[tauri::command]
+async fn internalize_lecture_transcript(
+    lecture_id: String,
+    user_id: Option<String>,
+) -> Result<usize, AppError> {
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
     let db = manager
         .get_db()
         .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    verify_lecture_ownership(&db, &lecture_id, &user)?;
 
-    db.get_subtitles(&lecture_id)
-        .map_err(|e| format!("獲取字幕失敗: {}", e))
+    let transcripts_dir = paths::get_transcripts_dir()?;
+    Ok(storage::transcript_store::internalize_lecture(
+        &db,
+        &transcripts_dir,
+        &lecture_id,
+    )?)
 }
 
 /// 刪除單條字幕
@@ -1582,8 +2034,16 @@ async fn get_subtitles(lecture_id: String) -> Result<Vec<storage::Subtitle>, Str
 /// usual ownership check. Missing subtitle → silent Ok (idempotent
 /// delete: deleting an already-deleted row is not an error and never
 /// has been on this entry point).
+///
+/// A row of an externalized lecture lives only in its blob, where
+/// `find_subtitle_lecture` can't see it; the caller passes `lecture_id`
+/// for that case.
 #[tauri::command]
-async fn delete_subtitle(id: String, user_id: Option<String>) -> Result<(), String> {
+async fn delete_subtitle(
+    id: String,
+    lecture_id: Option<String>,
+    user_id: Option<String>,
+) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1594,16 +2054,15 @@ async fn delete_subtitle(id: String, user_id: Option<String>) -> Result<(), Stri
 
     let user = user_id.unwrap_or_else(|| "default_user".to_string());
 
-    if let Some(lecture_id) = db.find_subtitle_lecture(&id) {
-        verify_lecture_ownership(&db, &lecture_id, &user)?;
-    } else {
+    let Some(lecture_id) = db.find_subtitle_lecture(&id).or(lecture_id) else {
         // No-op — preserves the pre-cp75.21 idempotent contract for
         // callers retrying a delete after a prior successful run.
         return Ok(());
-    }
+    };
+    verify_lecture_ownership(&db, &lecture_id, &user)?;
 
-    db.delete_subtitle_by_id(&id)
-        .map_err(|e| format!("刪除字幕失敗: {}", e))?;
+    let transcripts_dir = paths::get_transcripts_dir()?;
+    storage::transcript_store::delete_subtitle(&db, &transcripts_dir, &lecture_id, &id)?;
 
     Ok(())
 }
@@ -1620,7 +2079,7 @@ async fn save_setting(
     key: String,
     value: String,
     user_id: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1641,7 +2100,7 @@ async fn save_setting(
 async fn get_setting(
     key: String,
     user_id: Option<String>,
-) -> Result<Option<String>, String> {
+) -> Result<Option<String>, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1652,12 +2111,12 @@ async fn get_setting(
 
     let user = user_id.unwrap_or_else(|| "default_user".to_string());
     db.get_setting(&key, &user)
-        .map_err(|e| format!("獲取設置失敗: {}", e))
+        .map_err(|e| format!("獲取設置失敗: {}", e).into())
 }
 
 /// 獲取所有設置
 #[tauri::command]
-async fn get_all_settings() -> Result<Vec<storage::Setting>, String> {
+async fn get_all_settings() -> Result<Vec<storage::Setting>, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1667,12 +2126,12 @@ async fn get_all_settings() -> Result<Vec<storage::Setting>, String> {
         .map_err(|e| format!("數據庫連接失敗: {}", e))?;
 
     db.get_all_settings()
-        .map_err(|e| format!("獲取所有設置失敗: {}", e))
+        .map_err(|e| format!("獲取所有設置失敗: {}", e).into())
 }
 
 /// 註冊本地使用者
 #[tauri::command]
-async fn register_local_user(username: String) -> Result<(), String> {
+async fn register_local_user(username: String) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1682,12 +2141,12 @@ async fn register_local_user(username: String) -> Result<(), String> {
         .map_err(|e| format!("數據庫連接失敗: {}", e))?;
 
     db.create_local_user(&username)
-        .map_err(|e| format!("創建本地使用者失敗: {}", e))
+        .map_err(|e| format!("創建本地使用者失敗: {}", e).into())
 }
 
 /// 檢查本地使用者
 #[tauri::command]
-async fn check_local_user(username: String) -> Result<bool, String> {
+async fn check_local_user(username: String) -> Result<bool, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1697,7 +2156,7 @@ async fn check_local_user(username: String) -> Result<bool, String> {
         .map_err(|e| format!("數據庫連接失敗: {}", e))?;
 
     db.check_local_user(&username)
-        .map_err(|e| format!("檢查使用者失敗: {}", e))
+        .map_err(|e| format!("檢查使用者失敗: {}", e).into())
 }
 
 /// 保存筆記
@@ -1707,7 +2166,7 @@ async fn check_local_user(username: String) -> Result<bool, String> {
 /// `notes` table itself has no user_id column (P3 schema work to add
 /// one), so this is the strongest guard available without a migration.
 #[tauri::command]
-async fn save_note(note: storage::Note, user_id: Option<String>) -> Result<(), String> {
+async fn save_note(note: storage::Note, user_id: Option<String>) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1727,7 +2186,7 @@ async fn save_note(note: storage::Note, user_id: Option<String>) -> Result<(), S
 
 /// 獲取筆記
 #[tauri::command]
-async fn get_note(lecture_id: String) -> Result<Option<storage::Note>, String> {
+async fn get_note(lecture_id: String) -> Result<Option<storage::Note>, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -1737,7 +2196,7 @@ async fn get_note(lecture_id: String) -> Result<Option<storage::Note>, String> {
         .map_err(|e| format!("數據庫連接失敗: {}", e))?;
 
     db.get_note(&lecture_id)
-        .map_err(|e| format!("獲取筆記失敗: {}", e))
+        .map_err(|e| format!("獲取筆記失敗: {}", e).into())
 }
 
 // ===== Embeddings (local RAG store) =====
@@ -1761,7 +2220,7 @@ pub struct EmbeddingInput {
 async fn save_embedding(
     input: EmbeddingInput,
     user_id: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("db init: {}", e))?;
@@ -1780,7 +2239,7 @@ async fn save_embedding(
         input.page_number,
         &input.created_at,
     )
-    .map_err(|e| format!("save embedding: {}", e))
+    .map_err(|e| format!("save embedding: {}", e).into())
 }
 
 /// cp75.34 — batch variant. Mirrors `save_subtitles` (cp75.21): verify
@@ -1790,7 +2249,7 @@ async fn save_embedding(
 async fn save_embeddings(
     inputs: Vec<EmbeddingInput>,
     user_id: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("db init: {}", e))?;
@@ -1840,7 +2299,7 @@ async fn replace_embeddings_for_lecture(
     lecture_id: String,
     inputs: Vec<EmbeddingInput>,
     user_id: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("db init: {}", e))?;
@@ -1866,19 +2325,19 @@ async fn replace_embeddings_for_lecture(
         })
         .collect();
     db.replace_embeddings_for_lecture(&lecture_id, &rows)
-        .map_err(|e| format!("replace embeddings: {}", e))
+        .map_err(|e| format!("replace embeddings: {}", e).into())
 }
 
 #[tauri::command]
 async fn get_embeddings_by_lecture(
     lecture_id: String,
-) -> Result<Vec<storage::EmbeddingRow>, String> {
+) -> Result<Vec<storage::EmbeddingRow>, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("db init: {}", e))?;
     let db = manager.get_db().map_err(|e| format!("db conn: {}", e))?;
     db.get_embeddings_by_lecture(&lecture_id)
-        .map_err(|e| format!("get embeddings: {}", e))
+        .map_err(|e| format!("get embeddings: {}", e).into())
 }
 
 /// cp75.34 — verify the lecture belongs to the caller. Embeddings are
@@ -1888,7 +2347,7 @@ async fn get_embeddings_by_lecture(
 async fn delete_embeddings_by_lecture(
     lecture_id: String,
     user_id: Option<String>,
-) -> Result<usize, String> {
+) -> Result<usize, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("db init: {}", e))?;
@@ -1898,17 +2357,65 @@ async fn delete_embeddings_by_lecture(
     verify_lecture_ownership(&db, &lecture_id, &user)?;
 
     db.delete_embeddings_by_lecture(&lecture_id)
-        .map_err(|e| format!("delete embeddings: {}", e))
+        .map_err(|e| format!("delete embeddings: {}", e).into())
 }
 
 #[tauri::command]
-async fn count_embeddings(lecture_id: String) -> Result<i64, String> {
+async fn count_embeddings(lecture_id: String) -> Result<i64, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("db init: {}", e))?;
     let db = manager.get_db().map_err(|e| format!("db conn: {}", e))?;
     db.count_embeddings(&lecture_id)
-        .map_err(|e| format!("count embeddings: {}", e))
+        .map_err(|e| format!("count embeddings: {}", e).into())
+}
+
+/// Store the lecture's slide ↔ time alignment (replacing any previous
+/// run) so playback can follow slides without recomputing it.
+#[tauri::command]
+async fn save_slide_alignments(
+    lecture_id: String,
+    mut alignments: Vec<storage::SlideAlignment>,
+    user_id: Option<String>,
+) -> Result<(), AppError> {
+    alignments.sort_by_key(|a| a.start_ms);
+    if let Some(bad) = alignments
+        .iter()
+        .find(|a| a.page_number < 1 || a.start_ms < 0 || a.end_ms <= a.start_ms)
+    {
+        return Err(AppError::invalid_input(format!(
+            "投影片對齊範圍無效: p.{} {}–{} ms",
+            bad.page_number, bad.start_ms, bad.end_ms
+        )));
+    }
+    if alignments.windows(2).any(|w| w[1].start_ms < w[0].end_ms) {
+        return Err(AppError::invalid_input("投影片對齊範圍重疊"));
+    }
+
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("db init: {}", e))?;
+    let db = manager.get_db().map_err(|e| format!("db conn: {}", e))?;
+
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    verify_lecture_ownership(&db, &lecture_id, &user)?;
+
+    db.replace_slide_alignments(&lecture_id, &alignments)
+        .map_err(|e| format!("save slide alignments: {}", e).into())
+}
+
+/// The stored slide timeline, in playback order. Empty means the
+/// lecture hasn't been aligned yet.
+#[tauri::command]
+async fn get_slide_alignments(
+    lecture_id: String,
+) -> Result<Vec<storage::SlideAlignment>, AppError> {
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("db init: {}", e))?;
+    let db = manager.get_db().map_err(|e| format!("db conn: {}", e))?;
+    db.get_slide_alignments(&lecture_id)
+        .map_err(|e| format!("get slide alignments: {}", e).into())
 }
 
 /// 寫入文本文件
@@ -1967,7 +2474,7 @@ fn validate_user_writable_path(path: &str) -> Result<std::path::PathBuf, String>
 }
 
 #[tauri::command]
-async fn write_text_file(path: String, contents: String) -> Result<(), String> {
+async fn write_text_file(path: String, contents: String) -> Result<(), AppError> {
     use std::fs;
     let safe = validate_user_writable_path(&path)?;
     fs::write(&safe, contents).map_err(|e| format!("寫入文件失敗: {}", e))?;
@@ -1976,23 +2483,23 @@ async fn write_text_file(path: String, contents: String) -> Result<(), String> {
 
 /// 讀取文本文件
 #[tauri::command]
-async fn read_text_file(path: String) -> Result<String, String> {
+async fn read_text_file(path: String) -> Result<String, AppError> {
     use std::fs;
     let safe = validate_user_writable_path(&path)?;
-    fs::read_to_string(&safe).map_err(|e| format!("讀取文件失敗: {}", e))
+    fs::read_to_string(&safe).map_err(|e| format!("讀取文件失敗: {}", e).into())
 }
 
 /// 讀取二進制文件（用於 PDF 等）
 #[tauri::command]
-async fn read_binary_file(path: String) -> Result<Vec<u8>, String> {
+async fn read_binary_file(path: String) -> Result<Vec<u8>, AppError> {
     use std::fs;
     let safe = validate_user_writable_path(&path)?;
-    fs::read(&safe).map_err(|e| format!("讀取文件失敗: {}", e))
+    fs::read(&safe).map_err(|e| format!("讀取文件失敗: {}", e).into())
 }
 
 /// 寫入二進制文件
 #[tauri::command]
-async fn write_binary_file(path: String, data: Vec<u8>) -> Result<(), String> {
+async fn write_binary_file(path: String, data: Vec<u8>) -> Result<(), AppError> {
     use std::fs::{self, File};
     use std::io::Write;
 
@@ -2011,14 +2518,14 @@ async fn write_binary_file(path: String, data: Vec<u8>) -> Result<(), String> {
 
 /// 檢查設置狀態
 #[tauri::command]
-async fn check_setup_status() -> Result<setup::SetupStatus, String> {
-    setup::get_setup_status().await
+async fn check_setup_status() -> Result<setup::SetupStatus, AppError> {
+    Ok(setup::get_setup_status().await?)
 }
 
 /// 檢查設置是否已完成
 #[tauri::command]
-async fn is_setup_complete() -> Result<bool, String> {
-    setup::is_setup_complete().await
+async fn is_setup_complete() -> Result<bool, AppError> {
+    Ok(setup::is_setup_complete().await?)
 }
 
 /// 開始安裝所需組件
@@ -2026,26 +2533,32 @@ async fn is_setup_complete() -> Result<bool, String> {
 async fn start_setup_installation(
     requirement_ids: Vec<String>,
     window: tauri::Window,
-) -> Result<(), String> {
-    setup::install_requirements(requirement_ids, window).await
+) -> Result<(), AppError> {
+    Ok(setup::install_requirements(requirement_ids, window).await?)
 }
 
 /// 取消安裝
 #[tauri::command]
-async fn cancel_setup_installation() -> Result<(), String> {
-    setup::cancel_current_installation()
+async fn cancel_setup_installation() -> Result<(), AppError> {
+    Ok(setup::cancel_current_installation()?)
+}
+
+/// 上次安裝中斷、尚未完成的下載（重新啟動後繼續）
+#[tauri::command]
+async fn list_setup_downloads() -> Vec<setup::SetupDownload> {
+    setup::pending_downloads()
 }
 
 /// 標記設置完成
 #[tauri::command]
-async fn mark_setup_complete() -> Result<(), String> {
-    setup::save_setup_status(true).await
+async fn mark_setup_complete() -> Result<(), AppError> {
+    Ok(setup::save_setup_status(true).await?)
 }
 
 /// 重置設置狀態（用於調試）
 #[tauri::command]
-async fn reset_setup_status() -> Result<(), String> {
-    setup::save_setup_status(false).await
+async fn reset_setup_status() -> Result<(), AppError> {
+    Ok(setup::save_setup_status(false).await?)
 }
 
 // ========== Embedding 相關 Commands ==========
@@ -2055,24 +2568,35 @@ async fn reset_setup_status() -> Result<(), String> {
 async fn load_embedding_model(
     model_path: String,
     tokenizer_path: String,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let mut service_guard = EMBEDDING_SERVICE.lock().await;
-    let service = EmbeddingService::new(&model_path, &tokenizer_path)
-        .map_err(|e| format!("Embedding 模型加載失敗: {}", e))?;
+    let service = EmbeddingService::new(&model_path, &tokenizer_path).map_err(|e| {
+        let error = AppError::from(format!("Embedding 模型加載失敗: {}", e));
+        let (model, tokenizer) = (
+            std::path::Path::new(&model_path),
+            std::path::Path::new(&tokenizer_path),
+        );
+        if embedding::files_incomplete(model, tokenizer) {
+            error.or_kind(ErrorKind::ModelMissing)
+        } else {
+            error
+        }
+    })?;
     *service_guard = Some(service);
+    downloads::installed::mark_used(std::path::Path::new(&model_path));
     Ok("Embedding 模型加載成功".to_string())
 }
 
 /// 生成文本 Embedding
 #[tauri::command]
-async fn generate_embedding(text: String) -> Result<Vec<f32>, String> {
+async fn generate_embedding(text: String) -> Result<Vec<f32>, AppError> {
     let mut service_guard = EMBEDDING_SERVICE.lock().await;
     let service = service_guard
         .as_mut()
-        .ok_or("Embedding 模型未加載".to_string())?;
+        .ok_or_else(|| AppError::model_missing("Embedding 模型未加載"))?;
     service
         .generate_embedding(&text)
-        .map_err(|e| format!("生成 Embedding 失敗: {}", e))
+        .map_err(|e| format!("生成 Embedding 失敗: {}", e).into())
 }
 
 /// Batched version of `generate_embedding`. Processes N texts in one
@@ -2083,23 +2607,23 @@ async fn generate_embedding(text: String) -> Result<Vec<f32>, String> {
 /// squeeze, to_vec1, IPC serialize, IPC deserialize) only happens
 /// once instead of N times.
 #[tauri::command]
-async fn generate_embeddings_batch(texts: Vec<String>) -> Result<Vec<Vec<f32>>, String> {
+async fn generate_embeddings_batch(texts: Vec<String>) -> Result<Vec<Vec<f32>>, AppError> {
     let mut service_guard = EMBEDDING_SERVICE.lock().await;
     let service = service_guard
         .as_mut()
-        .ok_or("Embedding 模型未加載".to_string())?;
+        .ok_or_else(|| AppError::model_missing("Embedding 模型未加載"))?;
     service
         .generate_embeddings_batch(&texts)
-        .map_err(|e| format!("批次生成 Embedding 失敗: {}", e))
+        .map_err(|e| format!("批次生成 Embedding 失敗: {}", e).into())
 }
 
 /// 計算餘弦相似度
 #[tauri::command]
-async fn calculate_similarity(text_a: String, text_b: String) -> Result<f32, String> {
+async fn calculate_similarity(text_a: String, text_b: String) -> Result<f32, AppError> {
     let mut service_guard = EMBEDDING_SERVICE.lock().await;
     let service = service_guard
         .as_mut()
-        .ok_or("Embedding 模型未加載".to_string())?;
+        .ok_or_else(|| AppError::model_missing("Embedding 模型未加載"))?;
 
     let emb_a = service
         .generate_embedding(&text_a)
@@ -2124,10 +2648,10 @@ fn get_remote_debug_enabled() -> bool {
 /// the next `main()` runs, because WEBVIEW2_ADDITIONAL_BROWSER_ARGUMENTS
 /// is read at WebView2 process-start time only.
 #[tauri::command]
-fn set_remote_debug_enabled(enabled: bool) -> Result<(), String> {
+fn set_remote_debug_enabled(enabled: bool) -> Result<(), AppError> {
     let mut flags = dev_flags::load();
     flags.remote_debug_port_enabled = enabled;
-    dev_flags::save(&flags)
+    Ok(dev_flags::save(&flags)?)
 }
 
 /// Given N sentence-groups (one per Note section), return `top_k`
@@ -2141,15 +2665,15 @@ fn set_remote_debug_enabled(enabled: bool) -> Result<(), String> {
 async fn extract_section_highlights(
     sections: Vec<Vec<String>>,
     top_k: Option<usize>,
-) -> Result<Vec<Vec<String>>, String> {
+) -> Result<Vec<Vec<String>>, AppError> {
     let top_k = top_k.unwrap_or(3).max(1);
     let mut service_guard = EMBEDDING_SERVICE.lock().await;
     let service = service_guard
         .as_mut()
-        .ok_or("Embedding 模型未加載".to_string())?;
+        .ok_or_else(|| AppError::model_missing("Embedding 模型未加載"))?;
     service
         .extract_representative_sentences(&sections, top_k)
-        .map_err(|e| format!("section highlight extraction failed: {}", e))
+        .map_err(|e| format!("section highlight extraction failed: {}", e).into())
 }
 
 /// Structured search hit returned by `semantic_search_*`. Wraps the
@@ -2200,7 +2724,7 @@ async fn semantic_search_lecture(
     query: String,
     top_k: Option<usize>,
     preferred_page: Option<i64>,
-) -> Result<Vec<SearchHit>, String> {
+) -> Result<Vec<SearchHit>, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("db init: {}", e))?;
@@ -2235,7 +2759,7 @@ async fn semantic_search_lecture(
     let mut service_guard = EMBEDDING_SERVICE.lock().await;
     let service = service_guard
         .as_mut()
-        .ok_or("Embedding 模型未加載".to_string())?;
+        .ok_or_else(|| AppError::model_missing("Embedding 模型未加載"))?;
     let query_emb = service
         .generate_embedding(&query)
         .map_err(|e| format!("query embed: {}", e))?;
@@ -2282,7 +2806,7 @@ async fn semantic_search_course(
     user_id: String,
     query: String,
     top_k: Option<usize>,
-) -> Result<Vec<SearchHit>, String> {
+) -> Result<Vec<SearchHit>, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("db init: {}", e))?;
@@ -2307,7 +2831,7 @@ async fn semantic_search_course(
     let mut service_guard = EMBEDDING_SERVICE.lock().await;
     let service = service_guard
         .as_mut()
-        .ok_or("Embedding 模型未加載".to_string())?;
+        .ok_or_else(|| AppError::model_missing("Embedding 模型未加載"))?;
     let query_emb = service
         .generate_embedding(&query)
         .map_err(|e| format!("query embed: {}", e))?;
@@ -2343,16 +2867,20 @@ async fn semantic_search_course(
 #[cfg(feature = "candle-embed")]
 #[tauri::command]
 async fn download_embedding_model_cmd(
-    _app: tauri::AppHandle,
+    app: tauri::AppHandle,
     window: tauri::Window,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     use embedding::{download_embedding_model, EmbeddingModelConfig};
+    use setup::progress::{Progress, PROGRESS_EVENT};
     use tauri::Emitter;
 
+    const TASK_ID: &str = "bge-small-en-v1.5";
+    const TASK_NAME: &str = "BGE-small-en Embedding 模型";
+
     // Get models directory using unified path
     let models_dir = paths::get_embedding_models_dir()?;
 
-    // Use bge-small-en-v1.5 (standard BERT, Candle-compatible, ~33 MB).
+    // Use bge-small-en-v1.5 (standard BERT, Candle-compatible, ~130 MB).
     // Replaces nomic-embed-text-v1 in v0.5.2 — nomic uses the NomicBert
     // architecture with rotary position embeddings + SwiGLU, which
     // Candle's stock `BertModel::load` cannot load (it hard-requires
@@ -2360,23 +2888,38 @@ async fn download_embedding_model_cmd(
     // Cross-lingual zh→en retrieval is handled upstream in ragService.ts
     // by translating the query to English before embedding.
     let config = EmbeddingModelConfig::bge_small(models_dir);
+    let task = std::sync::Arc::new(tasks::Task::start(&app, tasks::TaskKind::Download, TASK_ID));
+    let _ = window.emit(PROGRESS_EVENT, Progress::pending(TASK_ID, TASK_NAME));
 
     // Progress callback
+    let task_for_cb = task.clone();
+    let window_for_cb = window.clone();
     let progress_callback = Box::new(move |downloaded: u64, total: u64| {
-        let progress = if total > 0 {
-            (downloaded as f64 / total as f64 * 100.0) as u64
-        } else {
-            0
-        };
+        task_for_cb.set_progress(downloaded, total);
 
         // Emit progress event
-        let _ = window.emit("embedding_download_progress", progress);
+        let _ = window_for_cb.emit(
+            PROGRESS_EVENT,
+            Progress::in_progress(TASK_ID, TASK_NAME, downloaded, total),
+        );
     });
 
     // Download with retry
-    download_embedding_model(&config, Some(progress_callback))
-        .await
-        .map_err(|e| format!("下載失敗: {}", e))?;
+    let result = task
+        .run(async {
+            download_embedding_model(&config, Some(progress_callback))
+                .await
+                .map_err(|e| format!("下載失敗: {}", e))
+        })
+        .await;
+    let _ = window.emit(
+        PROGRESS_EVENT,
+        match &result {
+            Ok(()) => Progress::completed(TASK_ID, TASK_NAME),
+            Err(e) => Progress::failed(TASK_ID, TASK_NAME, e),
+        },
+    );
+    result?;
 
     Ok(())
 }
@@ -2386,55 +2929,102 @@ async fn download_embedding_model_cmd(
 async fn download_embedding_model_cmd(
     _app: tauri::AppHandle,
     _window: tauri::Window,
-) -> Result<(), String> {
-    Err("Candle Embedding 功能未啟用。使用 --features candle-embed 重新編譯以啟用。".to_string())
+) -> Result<(), AppError> {
+    Err(AppError::unsupported(
+        "Candle Embedding 功能未啟用。使用 --features candle-embed 重新編譯以啟用。",
+    ))
 }
 
-fn get_app_data_dir_path() -> Result<std::path::PathBuf, String> {
-    paths::get_app_data_dir()
+#[tauri::command]
+fn get_app_data_dir() -> Result<String, AppError> {
+    Ok(paths::get_app_data_dir()?.to_string_lossy().into_owned())
 }
 
 #[tauri::command]
-fn get_app_data_dir() -> Result<String, String> {
-    paths::get_app_data_dir().map(|p| p.to_string_lossy().into_owned())
+fn get_whisper_models_dir() -> Result<String, AppError> {
+    Ok(paths::get_whisper_models_dir()?
+        .to_string_lossy()
+        .into_owned())
 }
 
 #[tauri::command]
-fn get_whisper_models_dir() -> Result<String, String> {
-    paths::get_whisper_models_dir().map(|p| p.to_string_lossy().into_owned())
+fn get_translation_models_dir() -> Result<String, AppError> {
+    Ok(paths::get_translation_models_dir()?
+        .to_string_lossy()
+        .into_owned())
 }
 
 #[tauri::command]
-fn get_translation_models_dir() -> Result<String, String> {
-    paths::get_translation_models_dir().map(|p| p.to_string_lossy().into_owned())
+fn get_embedding_models_dir() -> Result<String, AppError> {
+    Ok(paths::get_embedding_models_dir()?
+        .to_string_lossy()
+        .into_owned())
 }
 
 #[tauri::command]
-fn get_embedding_models_dir() -> Result<String, String> {
-    paths::get_embedding_models_dir().map(|p| p.to_string_lossy().into_owned())
+fn get_audio_dir() -> Result<String, AppError> {
+    Ok(paths::get_audio_dir()?.to_string_lossy().into_owned())
 }
 
 #[tauri::command]
-fn get_audio_dir() -> Result<String, String> {
-    paths::get_audio_dir().map(|p| p.to_string_lossy().into_owned())
+fn get_documents_dir() -> Result<String, AppError> {
+    Ok(paths::get_documents_dir()?.to_string_lossy().into_owned())
 }
 
+// ========== Storage Management Commands (Phase 3) ==========
+
+/// Copy all app data (database, audio, models, documents, …) to
+/// `new_dir`, e.g. an external SSD, and restart into it. The restart
+/// follows right away: anything the app wrote to the old copy from now
+/// on would not be in the new one. The old copy is deleted on the next
+/// launch, once the new one is in use.
 #[tauri::command]
-fn get_documents_dir() -> Result<String, String> {
-    paths::get_documents_dir().map(|p| p.to_string_lossy().into_owned())
+async fn move_app_data(
+    app: tauri::AppHandle,
+    new_dir: String,
+) -> Result<paths::relocate::MoveReport, AppError> {
+    if asr::parakeet_engine::has_session() {
+        return Err(AppError::busy("錄音進行中無法搬移資料，請先停止錄音"));
+    }
+    let task =
+        tasks::Task::start(&app, tasks::TaskKind::Relocation, "搬移應用程式資料").cancellable();
+    let report = tokio::task::spawn_blocking(move || {
+        let result = paths::relocate::move_app_data(
+            std::path::Path::new(&new_dir),
+            task.cancel_flag(),
+            |done, total| task.set_progress(done, total),
+        );
+        task.finish(&result);
+        result
+    })
+    .await
+    .map_err(|e| format!("move task join error: {e}"))?
+    .map_err(AppError::from)?;
+    // Long enough for the UI to say so.
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(1500)).await;
+        shutdown::run();
+        app.restart();
+    });
+    Ok(report)
 }
 
-// ========== Storage Management Commands (Phase 3) ==========
+/// Restart the app, e.g. so a data move takes effect.
+#[tauri::command]
+fn restart_app(app: tauri::AppHandle) {
+    shutdown::run();
+    app.restart()
+}
 
 /// Get storage usage for all app data
 #[tauri::command]
-fn get_storage_usage() -> Result<paths::StorageUsage, String> {
-    paths::get_storage_usage()
+fn get_storage_usage() -> Result<paths::StorageUsage, AppError> {
+    Ok(paths::get_storage_usage()?)
 }
 
 /// Clear model cache for a specific model type
 #[tauri::command]
-async fn clear_model_cache(model_type: String) -> Result<String, String> {
+async fn clear_model_cache(model_type: String) -> Result<String, AppError> {
     use std::fs;
 
     let dir = match model_type.as_str() {
@@ -2442,7 +3032,12 @@ async fn clear_model_cache(model_type: String) -> Result<String, String> {
         "whisper" => paths::get_whisper_models_dir()?,
         "embedding" => paths::get_embedding_models_dir()?,
         "all" => paths::get_models_dir()?,
-        _ => return Err(format!("未知的模型類型: {}", model_type)),
+        _ => {
+            return Err(AppError::invalid_input(format!(
+                "未知的模型類型: {}",
+                model_type
+            )))
+        }
     };
 
     if dir.exists() {
@@ -2462,7 +3057,7 @@ async fn clear_model_cache(model_type: String) -> Result<String, String> {
 
 /// Reset app to fresh state (delete all data except settings)
 #[tauri::command]
-async fn reset_app_data() -> Result<String, String> {
+async fn reset_app_data() -> Result<String, AppError> {
     use std::fs;
 
     // Clear models
@@ -2485,7 +3080,7 @@ async fn reset_app_data() -> Result<String, String> {
 
 /// Completely uninstall app data (for complete removal)
 #[tauri::command]
-async fn uninstall_app_data() -> Result<String, String> {
+async fn uninstall_app_data() -> Result<String, AppError> {
     use std::fs;
 
     let app_dir = paths::get_app_data_dir()?;
@@ -2497,351 +3092,15 @@ async fn uninstall_app_data() -> Result<String, String> {
 }
 
 #[tauri::command]
-async fn convert_to_pdf(file_path: String) -> Result<String, String> {
-    use std::fs;
-    use std::path::Path;
-
-    let input_path = Path::new(&file_path);
-    if !input_path.exists() {
-        return Err(format!("File not found: {}", file_path));
-    }
-
-    // Determine file type
-    let extension = input_path
-        .extension()
-        .and_then(|s| s.to_str())
-        .map(|s| s.to_lowercase())
-        .ok_or("Unknown file type")?;
-
-    // Use persistent app data directory for output
-    let app_data_dir = get_app_data_dir_path()?;
-    let documents_dir = app_data_dir.join("documents");
+fn get_temp_dir() -> String {
+    std::env::temp_dir().to_string_lossy().into_owned()
+}
 
-    if !documents_dir.exists() {
-        fs::create_dir_all(&documents_dir)
-            .map_err(|e| format!("Failed to create documents dir: {}", e))?;
-    }
-
-    let file_stem = input_path
-        .file_stem()
-        .ok_or("Invalid filename")?
-        .to_string_lossy();
-    // Use a hash of the input path to avoid collisions if files have same name but different locations
-    // Or just append timestamp/random string. Let's use timestamp for simplicity and uniqueness.
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
-
-    let output_filename = format!("{}_{}.pdf", file_stem, timestamp);
-    let output_pdf_path = documents_dir.join(&output_filename);
-
-    // Remove existing output (unlikely with timestamp, but good practice)
-    if output_pdf_path.exists() {
-        fs::remove_file(&output_pdf_path).ok();
-    }
-
-    println!("Converting {} to PDF", file_path);
-    println!("Output path: {:?}", output_pdf_path);
-    println!("File type: {}", extension);
-
-    // Platform-specific conversion with layered fallback
-    #[cfg(target_os = "macos")]
-    {
-        // Try macOS native conversions first
-        match extension.as_str() {
-            "ppt" | "pptx" => {
-                // Try Keynote first (best quality, built-in)
-                if let Ok(path) = try_keynote_conversion(&file_path, &output_pdf_path) {
-                    println!("✓ Converted using Keynote (highest quality)");
-                    return Ok(path);
-                }
-
-                // Try PowerPoint for Mac
-                if let Ok(path) =
-                    try_office_mac_conversion(&file_path, &output_pdf_path, "PowerPoint")
-                {
-                    println!("✓ Converted using Microsoft PowerPoint");
-                    return Ok(path);
-                }
-            }
-            "doc" | "docx" => {
-                // Try Pages first
-                if let Ok(path) = try_pages_conversion(&file_path, &output_pdf_path) {
-                    println!("✓ Converted using Pages (highest quality)");
-                    return Ok(path);
-                }
-
-                // Try Word for Mac
-                if let Ok(path) = try_office_mac_conversion(&file_path, &output_pdf_path, "Word") {
-                    println!("✓ Converted using Microsoft Word");
-                    return Ok(path);
-                }
-            }
-            _ => {}
-        }
-
-        // Fallback to LibreOffice
-        println!("⚠ Native apps not available, falling back to LibreOffice");
-    }
-
-    // Use LibreOffice (cross-platform fallback)
-    convert_with_libreoffice(&file_path, &output_pdf_path)
-}
-
-#[cfg(target_os = "macos")]
-fn try_keynote_conversion(
-    input_path: &str,
-    output_path: &std::path::Path,
-) -> Result<String, String> {
-    use std::process::Command;
-
-    // Check if Keynote is available
-    if !std::path::Path::new("/Applications/Keynote.app").exists() {
-        return Err("Keynote not installed".to_string());
-    }
-
-    // Use AppleScript instead of JXA for better reliability
-    let script = format!(
-        r#"
-        tell application "Keynote"
-            set theDoc to open POSIX file "{}"
-            delay 2
-            export theDoc to POSIX file "{}" as PDF
-            delay 1
-            close theDoc
-        end tell
-        "#,
-        input_path.replace("\"", "\\\""),
-        output_path.to_string_lossy().replace("\"", "\\\"")
-    );
-
-    println!("Executing Keynote conversion...");
-
-    let output = crate::utils::command::no_window("osascript")
-        .arg("-e")
-        .arg(&script)
-        .output()
-        .map_err(|e| format!("Failed to execute Keynote: {}", e))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Keynote conversion error: {}", stderr));
-    }
-
-    wait_for_file(output_path)?;
-    validate_pdf(output_path)?;
-
-    Ok(output_path.to_string_lossy().into_owned())
-}
-
-#[cfg(target_os = "macos")]
-fn try_pages_conversion(input_path: &str, output_path: &std::path::Path) -> Result<String, String> {
-    use std::process::Command;
-
-    if !std::path::Path::new("/Applications/Pages.app").exists() {
-        return Err("Pages not installed".to_string());
-    }
-
-    let script = format!(
-        r#"
-        tell application "Pages"
-            set theDoc to open POSIX file "{}"
-            delay 2
-            export theDoc to POSIX file "{}" as PDF
-            delay 1
-            close theDoc
-        end tell
-        "#,
-        input_path.replace("\"", "\\\""),
-        output_path.to_string_lossy().replace("\"", "\\\"")
-    );
-
-    let output = crate::utils::command::no_window("osascript")
-        .arg("-e")
-        .arg(&script)
-        .output()
-        .map_err(|e| format!("Failed to execute Pages: {}", e))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Pages conversion error: {}", stderr));
-    }
-
-    wait_for_file(output_path)?;
-    validate_pdf(output_path)?;
-
-    Ok(output_path.to_string_lossy().into_owned())
-}
-
-#[cfg(target_os = "macos")]
-fn try_office_mac_conversion(
-    input_path: &str,
-    output_path: &std::path::Path,
-    app_name: &str,
-) -> Result<String, String> {
-    use std::process::Command;
-
-    let app_path = format!("/Applications/Microsoft {}.app", app_name);
-    if !std::path::Path::new(&app_path).exists() {
-        return Err(format!("Microsoft {} not installed", app_name));
-    }
-
-    let script = format!(
-        r#"
-        tell application "Microsoft {}"
-            set theDoc to open POSIX file "{}"
-            delay 2
-            save as theDoc file name (POSIX file "{}") file format PDF file format
-            delay 1
-            close theDoc
-        end tell
-        "#,
-        app_name,
-        input_path.replace("\"", "\\\""),
-        output_path.to_string_lossy().replace("\"", "\\\"")
-    );
-
-    let output = crate::utils::command::no_window("osascript")
-        .arg("-e")
-        .arg(&script)
-        .output()
-        .map_err(|e| format!("Failed to execute {}: {}", app_name, e))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("{} conversion error: {}", app_name, stderr));
-    }
-
-    wait_for_file(output_path)?;
-    validate_pdf(output_path)?;
-
-    Ok(output_path.to_string_lossy().into_owned())
-}
-
-fn convert_with_libreoffice(
-    input_path: &str,
-    output_path: &std::path::Path,
-) -> Result<String, String> {
-    use std::path::Path;
-    use std::process::Command;
-
-    let temp_dir = output_path.parent().ok_or("Invalid output path")?;
-
-    let soffice_cmd: String = if cfg!(target_os = "macos") {
-        if Path::new("/Applications/LibreOffice.app/Contents/MacOS/soffice").exists() {
-            "/Applications/LibreOffice.app/Contents/MacOS/soffice".to_string()
-        } else {
-            "soffice".to_string()
-        }
-    } else if cfg!(target_os = "windows") {
-        // LibreOffice on Windows isn't on PATH by default. Prefer soffice.com
-        // (the console wrapper that waits for completion) under the standard
-        // install directories, falling back to "soffice" on PATH.
-        const WIN_CANDIDATES: &[&str] = &[
-            r"C:\Program Files\LibreOffice\program\soffice.com",
-            r"C:\Program Files\LibreOffice\program\soffice.exe",
-            r"C:\Program Files (x86)\LibreOffice\program\soffice.com",
-            r"C:\Program Files (x86)\LibreOffice\program\soffice.exe",
-        ];
-        WIN_CANDIDATES
-            .iter()
-            .find(|p| Path::new(p).exists())
-            .map(|p| (*p).to_string())
-            .unwrap_or_else(|| "soffice".to_string())
-    } else {
-        "soffice".to_string()
-    };
-
-    println!("Using LibreOffice: {}", soffice_cmd);
-
-    let output = crate::utils::command::no_window(soffice_cmd)
-        .arg("--headless")
-        .arg("--convert-to")
-        .arg("pdf")
-        .arg("--outdir")
-        .arg(temp_dir)
-        .arg(input_path)
-        .output()
-        .map_err(|e| {
-            format!(
-                "Failed to execute LibreOffice: {}. Please install LibreOffice.",
-                e
-            )
-        })?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("LibreOffice conversion failed: {}", stderr));
-    }
-
-    wait_for_file(output_path)?;
-    validate_pdf(output_path)?;
-
-    Ok(output_path.to_string_lossy().into_owned())
-}
-
-fn wait_for_file(path: &std::path::Path) -> Result<(), String> {
-    use std::fs;
-    use std::time::Duration;
-
-    let max_wait = 120; // 60 seconds (120 * 500ms)
-    let mut waited = 0;
-    let mut last_size = 0;
-
-    while waited < max_wait {
-        if path.exists() {
-            if let Ok(metadata) = fs::metadata(path) {
-                let current_size = metadata.len();
-                if current_size > 0 && current_size == last_size {
-                    println!("File ready. Size: {} bytes", current_size);
-                    return Ok(());
-                }
-                last_size = current_size;
-            }
-        }
-        std::thread::sleep(Duration::from_millis(500));
-        waited += 1;
-    }
-
-    if path.exists() {
-        Ok(())
-    } else {
-        Err("Timeout waiting for PDF file".to_string())
-    }
-}
-
-fn validate_pdf(path: &std::path::Path) -> Result<(), String> {
-    use std::fs;
-    use std::io::Read;
-
-    let metadata = fs::metadata(path).map_err(|e| format!("Cannot read PDF: {}", e))?;
-
-    if metadata.len() < 100 {
-        return Err(format!("PDF too small ({} bytes)", metadata.len()));
-    }
-
-    let mut file = fs::File::open(path).map_err(|e| format!("Cannot open PDF: {}", e))?;
-    let mut header = [0u8; 5];
-    file.read_exact(&mut header).ok();
-
-    if &header != b"%PDF-" {
-        return Err("Invalid PDF header".to_string());
-    }
-
-    Ok(())
-}
-
-#[tauri::command]
-fn get_temp_dir() -> String {
-    std::env::temp_dir().to_string_lossy().into_owned()
-}
-
-#[tauri::command]
-async fn write_temp_file(path: String, data: Vec<u8>) -> Result<(), String> {
-    use std::fs::{self, File};
-    use std::io::Write;
-    use std::path::Path;
+#[tauri::command]
+async fn write_temp_file(path: String, data: Vec<u8>) -> Result<(), AppError> {
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::path::Path;
 
     // Create parent directory if it doesn't exist — lets callers that
     // want to drop files under a new subfolder (e.g. `lecture-pdfs/`)
@@ -2863,7 +3122,7 @@ async fn write_temp_file(path: String, data: Vec<u8>) -> Result<(), String> {
 
 /// 開啟開發者工具 (Developer Mode)
 #[tauri::command]
-async fn open_devtools(app: tauri::AppHandle) -> Result<(), String> {
+async fn open_devtools(app: tauri::AppHandle) -> Result<(), AppError> {
     use tauri::Manager;
     if let Some(window) = app.get_webview_window("main") {
         window.open_devtools();
@@ -2873,7 +3132,7 @@ async fn open_devtools(app: tauri::AppHandle) -> Result<(), String> {
 
 /// 關閉開發者工具
 #[tauri::command]
-async fn close_devtools(app: tauri::AppHandle) -> Result<(), String> {
+async fn close_devtools(app: tauri::AppHandle) -> Result<(), AppError> {
     use tauri::Manager;
     if let Some(window) = app.get_webview_window("main") {
         window.close_devtools();
@@ -2881,57 +3140,135 @@ async fn close_devtools(app: tauri::AppHandle) -> Result<(), String> {
     Ok(())
 }
 
+/// The last `lines` lines of the app log, reaching into rotated files
+/// when needed (see `logging::recent_lines`).
 #[tauri::command]
-async fn read_recent_log(lines: usize, app_handle: tauri::AppHandle) -> Result<String, String> {
-    use std::fs::File;
-    use std::io::{BufRead, BufReader};
-
-    let log_dir = app_handle
-        .path()
-        .app_log_dir()
-        .map_err(|e| format!("Failed to resolve log dir: {}", e))?;
-    let log_path = log_dir.join("classnoteai.log");
-
-    if !log_path.exists() {
-        return Ok(String::new());
-    }
-
-    let file = File::open(&log_path).map_err(|e| format!("Failed to open log file: {}", e))?;
-    let reader = BufReader::new(file);
-    let all_lines = reader
-        .lines()
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| format!("Failed to read log file: {}", e))?;
+async fn get_recent_logs(lines: usize) -> Result<String, AppError> {
+    let dir = logging::log_dir()?;
+    tokio::task::spawn_blocking(move || logging::recent_lines(&dir, lines))
+        .await
+        .map_err(|e| format!("read log task join error: {e}"))?
+        .map_err(AppError::from)
+}
 
-    let limit = lines.min(2000);
-    if limit == 0 {
-        return Ok(String::new());
-    }
+/// Log levels saved with `set_log_levels` (the defaults if none are).
+#[tauri::command]
+async fn get_log_levels() -> Result<logging::LogLevels, AppError> {
+    Ok(logging::levels().await?)
+}
 
-    let start = all_lines.len().saturating_sub(limit);
-    Ok(all_lines[start..].join("\n"))
+/// Change log verbosity, globally or per module, without a restart.
+#[tauri::command]
+async fn set_log_levels(levels: logging::LogLevels) -> Result<logging::LogLevels, AppError> {
+    Ok(logging::set_levels(levels).await?)
 }
 
 #[tauri::command]
-async fn open_log_folder(app_handle: tauri::AppHandle) -> Result<(), String> {
+async fn open_log_folder(app_handle: tauri::AppHandle) -> Result<(), AppError> {
     use tauri_plugin_opener::OpenerExt;
 
-    let log_dir = app_handle
-        .path()
-        .app_log_dir()
-        .map_err(|e| format!("Failed to resolve log dir: {}", e))?;
+    let log_dir = logging::log_dir()?;
 
     app_handle
         .opener()
         .open_path(log_dir.to_string_lossy().to_string(), None::<&str>)
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string().into())
+}
+
+/// The crash report from the last panic, unless the user already
+/// dismissed it. The frontend asks once per launch.
+#[tauri::command]
+async fn get_last_crash_report() -> Result<Option<crash::CrashReport>, AppError> {
+    Ok(crash::last_report(&crash::crash_dir()?)?)
+}
+
+/// Don't offer crash report `id` again.
+#[tauri::command]
+async fn dismiss_crash_report(id: String) -> Result<(), AppError> {
+    Ok(crash::dismiss(&crash::crash_dir()?, &id)?)
+}
+
+/// Health snapshot of this install for support (database, model files,
+/// disk, endpoints, sync queue) — see `diagnostics::health`.
+#[tauri::command]
+async fn run_diagnostics(
+    user_id: Option<String>,
+    hash_models: Option<bool>,
+) -> Result<diagnostics::health::DiagnosticsReport, AppError> {
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    Ok(diagnostics::health::run(&user, hash_models.unwrap_or(true)).await)
+}
+
+/// Approximate memory per model (loaded and loadable) plus process and
+/// system totals, so the UI can warn before loading one more model.
+#[tauri::command]
+async fn get_memory_usage() -> Result<memory::MemoryUsage, AppError> {
+    Ok(memory::usage().await)
+}
+
+#[tauri::command]
+fn get_telemetry_settings() -> telemetry::TelemetrySettings {
+    telemetry::settings()
+}
+
+/// Opt in / out of anonymous telemetry. Opting out discards anything
+/// collected but not yet sent.
+#[tauri::command]
+async fn set_telemetry_settings(
+    enabled: bool,
+    endpoint: Option<String>,
+) -> Result<telemetry::TelemetrySettings, AppError> {
+    Ok(telemetry::configure(enabled, endpoint).await?)
+}
+
+/// Count a frontend event. Silently ignored while telemetry is off.
+#[tauri::command]
+fn record_telemetry_event(name: String) {
+    telemetry::record(&name);
+}
+
+/// Upload queued telemetry now; returns the number of batches sent.
+#[tauri::command]
+async fn flush_telemetry() -> Result<usize, AppError> {
+    Ok(telemetry::flush().await?)
+}
+
+/// This launch's self-test result and whether it came up in safe mode.
+#[tauri::command]
+fn get_startup_status() -> startup::StartupStatus {
+    startup::status()
+}
+
+/// Called once the UI has rendered, so this launch doesn't count
+/// towards crash-loop detection.
+#[tauri::command]
+fn mark_startup_complete() -> Result<(), AppError> {
+    Ok(startup::mark_complete()?)
+}
+
+/// Run one of the repairs a failed self-test check offers.
+#[tauri::command]
+async fn repair_startup(action: String) -> Result<String, AppError> {
+    tokio::task::spawn_blocking(move || startup::repair(&action))
+        .await
+        .map_err(|e| format!("repair task join error: {e}"))?
+        .map_err(AppError::from)
+}
+
+/// Restart normally. The self-test runs again, so a failure that wasn't
+/// repaired lands back in safe mode.
+#[tauri::command]
+fn exit_safe_mode(app: tauri::AppHandle) -> Result<(), AppError> {
+    startup::mark_complete()?;
+    shutdown::run();
+    app.restart()
 }
 
 #[tauri::command]
 async fn export_diagnostic_package(
     input: crate::diagnostics::DiagnosticPackageInput,
     include_audio: bool,
-) -> Result<String, String> {
+) -> Result<String, AppError> {
     let path = crate::diagnostics::build_diagnostic_zip(input, include_audio)?;
     Ok(path.to_string_lossy().into_owned())
 }
@@ -2957,22 +3294,7 @@ pub fn run() {
                 let _ = window.set_focus();
             }
         }))
-        .plugin(
-            tauri_plugin_log::Builder::new()
-                .targets([
-                    Target::new(TargetKind::LogDir {
-                        file_name: Some("classnoteai".into()),
-                    }),
-                    Target::new(TargetKind::Stdout),
-                ])
-                .level(if cfg!(debug_assertions) {
-                    LevelFilter::Info
-                } else {
-                    LevelFilter::Warn
-                })
-                .rotation_strategy(RotationStrategy::KeepSome(5))
-                .build(),
-        )
+        .plugin(logging::plugin())
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_opener::init())
@@ -2984,6 +3306,22 @@ pub fn run() {
             // 前端可透過 invoke 呼叫開啟
             // 不再自動開啟
 
+            // App data moved with `move_app_data`: delete the old copy now
+            // that nothing has it open, and let the webview read the new
+            // location (the static asset / fs scopes only cover the default).
+            paths::relocate::finish_pending();
+            if paths::relocate::is_relocated() {
+                use tauri_plugin_fs::FsExt as _;
+                if let Ok(dir) = paths::get_app_data_dir() {
+                    let _ = app.asset_protocol_scope().allow_directory(&dir, true);
+                    let _ = app.fs_scope().allow_directory(&dir, true);
+                }
+            }
+
+            // Counts this launch until the frontend reports in; a crash
+            // loop or CLASSNOTEAI_SAFE_MODE forces safe mode from here.
+            let forced_safe_mode = startup::begin();
+
             // Phase 2 follow-up: point ORT at the bundled onnxruntime
             // binary BEFORE init_onnx(). Without this override the
             // `ort` crate's `load-dynamic` walks PATH and typically
@@ -3028,8 +3366,13 @@ pub fn run() {
             // falls back to the energy VAD, so recording still works.
             // This keeps the "user can record their lecture" invariant
             // even if the ONNX Runtime DLL is missing / incompatible.
-            if let Ok(resource_dir) = app.handle().path().resource_dir() {
-                let model_path = resource_dir.join("resources").join("silero").join("silero_vad.onnx");
+            let silero_model = app
+                .handle()
+                .path()
+                .resource_dir()
+                .ok()
+                .map(|dir| dir.join("resources").join("silero").join("silero_vad.onnx"));
+            if let Some(model_path) = silero_model.clone().filter(|_| forced_safe_mode.is_none()) {
                 if model_path.exists() {
                     match vad::silero::init(&model_path) {
                         Ok(()) => println!("[VAD] Silero v5 initialised from bundle"),
@@ -3040,6 +3383,16 @@ pub fn run() {
                 }
             }
 
+            // Post-update migrations (legacy model files included) finish
+            // before the self-test and autoloads look for them.
+            if let Err(e) = storage::run_migrations(app.handle()) {
+                eprintln!("[Migrations] Could not open the database: {}", e);
+            }
+
+            // Self-test before anything heavy loads; on failure the app
+            // comes up in safe mode and the autoloads below stand down.
+            startup::run(forced_safe_mode, silero_model.as_deref());
+
             // Initialization of database
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
@@ -3047,6 +3400,11 @@ pub fn run() {
                     eprintln!("數據庫初始化失敗: {}", e);
                 } else {
                     println!("數據庫初始化成功");
+                    telemetry::init().await;
+                    logging::load_levels().await;
+                    downloads::throttle::load().await;
+                    downloads::proxy::load().await;
+                    sync::outbox::start(app_handle.clone());
                 }
             });
 
@@ -3063,6 +3421,10 @@ pub fn run() {
             // saves the user from a ~3-5 s cold start the first time
             // they hit Record.
             tauri::async_runtime::spawn(async move {
+                if startup::is_safe_mode() {
+                    println!("[startup] Safe mode — skipping Nemotron auto-load");
+                    return;
+                }
                 let variant = match asr::parakeet_model::first_present() {
                     Some(v) => v,
                     None => {
@@ -3106,6 +3468,10 @@ pub fn run() {
             let app_for_gemma = app.handle().clone();
             tauri::async_runtime::spawn(async move {
                 use tauri::Manager as _;
+                if startup::is_safe_mode() {
+                    println!("[startup] Safe mode — skipping sidecar auto-start");
+                    return;
+                }
                 if !translation::gemma_model::is_present() {
                     println!(
                         "[startup] TranslateGemma model not yet downloaded — \
@@ -3130,6 +3496,28 @@ pub fn run() {
                 println!("[startup] TranslateGemma sidecar bring-up: {result:?}");
             });
 
+            // Pick up model downloads the last session quit in the middle
+            // of. Paused ones wait for the user to resume them.
+            let app_for_downloads = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if startup::is_safe_mode() {
+                    return;
+                }
+                // Setup wizard files are resumed by the wizard itself.
+                for pending in downloads::control::pending()
+                    .into_iter()
+                    .filter(|p| !p.paused && !p.source.is_setup())
+                {
+                    println!("[startup] Resuming download {}", pending.id);
+                    let app = app_for_downloads.clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = run_download(app, pending.source).await {
+                            eprintln!("[startup] Download {} failed: {e}", pending.id);
+                        }
+                    });
+                }
+            });
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
@@ -3140,7 +3528,21 @@ pub fn run() {
             agent_bridge::agent_bridge_complete_ui_action,
             agent_bridge::agent_bridge_workflow_progress,
             agent_bridge::agent_bridge_complete_workflow,
-            read_recent_log,
+            get_recent_logs,
+            get_log_levels,
+            set_log_levels,
+            get_last_crash_report,
+            dismiss_crash_report,
+            run_diagnostics,
+            get_memory_usage,
+            get_telemetry_settings,
+            set_telemetry_settings,
+            record_telemetry_event,
+            flush_telemetry,
+            get_startup_status,
+            mark_startup_complete,
+            repair_startup,
+            exit_safe_mode,
             open_log_folder,
             export_diagnostic_package,
             detect_speech_segments,
@@ -3156,11 +3558,26 @@ pub fn run() {
             locate_gemma_binary,
             get_gemma_status,
             download_gemma_model,
+            list_downloads,
+            pause_download,
+            resume_download,
+            cancel_download,
+            get_download_mirrors,
+            set_download_mirrors,
+            get_download_rate_limit,
+            set_download_rate_limit,
+            get_download_proxy,
+            set_download_proxy,
+            list_installed_models,
+            delete_model,
+            check_model_updates,
+            apply_model_update,
             get_parakeet_status,
             parakeet_load_model,
             parakeet_unload_model,
             parakeet_download_model,
             asr_start_session,
+            preview_denoise,
             asr_push_audio,
             asr_end_session,
             get_build_features,
@@ -3188,6 +3605,8 @@ pub fn run() {
             save_subtitles,
             get_subtitles,
             delete_subtitle,
+            externalize_lecture_transcript,
+            internalize_lecture_transcript,
             save_setting,
             get_setting,
             get_all_settings,
@@ -3202,6 +3621,9 @@ pub fn run() {
             get_embeddings_by_lecture,
             delete_embeddings_by_lecture,
             count_embeddings,
+            // Slide ↔ time alignment
+            save_slide_alignments,
+            get_slide_alignments,
             write_text_file,
             read_text_file,
             read_binary_file,
@@ -3210,6 +3632,7 @@ pub fn run() {
             is_setup_complete,
             start_setup_installation,
             cancel_setup_installation,
+            list_setup_downloads,
             mark_setup_complete,
             reset_setup_status,
             // CTranslate2 翻譯相關
@@ -3229,7 +3652,17 @@ pub fn run() {
             set_remote_debug_enabled,
             download_embedding_model_cmd,
             // 文檔轉換相關
-            convert_to_pdf,
+            documents::convert::convert_to_pdf,
+            documents::convert::cancel_conversion,
+            tasks::list_tasks,
+            tasks::cancel_task,
+            move_app_data,
+            restart_app,
+            documents::convert::cleanup_converted_documents,
+            documents::queue::enqueue_conversions,
+            documents::render::render_pdf_pages,
+            documents::ocr::ocr_pdf_pages,
+            documents::extract::extract_document_text,
             get_temp_dir,
             get_app_data_dir,
             get_whisper_models_dir,
@@ -3251,6 +3684,22 @@ pub fn run() {
             list_pending_actions,
             update_pending_action,
             remove_pending_action,
+            get_sync_status,
+            get_last_sync_report,
+            flush_sync_queue,
+            get_sync_conflict_policy,
+            set_sync_conflict_policy,
+            list_note_conflicts,
+            resolve_note_conflict,
+            get_sync_encryption,
+            enable_sync_encryption,
+            disable_sync_encryption,
+            get_sync_selection,
+            set_sync_selection,
+            list_server_profiles,
+            save_server_profile,
+            remove_server_profile,
+            set_active_server_profile,
             // Trash Bin
             list_deleted_courses,
             list_deleted_lectures,
@@ -3277,6 +3726,30 @@ pub fn run() {
             recording::finalize_recording,
             recording::find_orphaned_recordings,
             recording::discard_orphaned_recording,
+            recording::recover_incomplete_recording,
+            recording::power::acquire_sleep_guard,
+            recording::power::release_sleep_guard,
+            compress_lecture_audio,
+            generate_waveform,
+            trim_lecture_audio,
+            merge_recordings,
+            export_condensed_audio,
+            export_lecture_with_chapters,
+            export_note_pdf,
+            export_note_docx,
+            export_anki,
+            export_markdown_bundle,
+            playback::playback_is_available,
+            playback::playback_load,
+            playback::playback_play,
+            playback::playback_pause,
+            playback::playback_seek,
+            playback::playback_get_position,
+            playback::playback_stop,
+            pipeline::start_live_pipeline,
+            pipeline::live_pipeline_push_audio,
+            pipeline::stop_live_pipeline,
+            recording::loopback::get_loopback_capture_support,
             // Phase 1 of speech-pipeline-v0.6.5 (#52): transcript JSONL sidecar
             recording::append_transcript_segment,
             recording::read_orphaned_transcript,
@@ -3286,30 +3759,33 @@ pub fn run() {
             recording::video_import::extract_video_pcm_to_temp,
             recording::video_import::read_pcm_slice,
             recording::video_import::delete_temp_pcm,
+            recording::audio_import::probe_audio_duration,
+            recording::audio_import::probe_audio,
+            recording::audio_import::import_audio_file_to_temp,
             gpu::detect_gpu_backends,
             gpu::get_build_variant,
+            crate::updater::set_update_channel,
+            crate::updater::get_update_channel,
             crate::updater::check_update_for_channel,
+            crate::updater::get_release_notes,
             crate::updater::download_and_install_update,
             list_orphaned_recording_lectures,
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
         .run(|_app, event| {
-            // Reap the TranslateGemma sidecar on every exit pathway so we
-            // don't leave a 2 GB llama-server process orphaned in the
-            // background after the app closes (taskmgr would still see it).
-            // `Exit` fires on graceful quit, `ExitRequested` is the
-            // pre-flight callback if a handler wants to veto — for our
-            // sidecar shutting down twice is a no-op so we don't bother.
-            //
-            // The in-process Nemotron engine doesn't have a separate
-            // process to reap, but we still drop the model so the OS
-            // sees the RAM (~600 MB) released cleanly before the
-            // process exits. Useful for crash diagnostics where ort's
-            // memory accounting matters.
+            // Release the models in order before the process goes away
+            // (see `shutdown`): reaps the TranslateGemma sidecar so no
+            // 2 GB llama-server is left orphaned, and frees ASR /
+            // embedding GPU buffers ourselves instead of leaving them to
+            // static teardown, which aborted in `ggml_metal_device_free`.
+            // `Exit` fires on graceful quit; the updater calls the same
+            // sequence before it restarts or hands off to the installer.
             if matches!(event, tauri::RunEvent::Exit) {
-                translation::gemma_sidecar::shutdown();
-                asr::parakeet_engine::unload();
+                if let Err(e) = telemetry::spool() {
+                    log::warn!("[telemetry] spool on exit failed: {}", e);
+                }
+                shutdown::run();
             }
         });
 }
@@ -3322,7 +3798,7 @@ pub fn run() {
 /// the frontend calls twice without a new migration running, the
 /// second call returns empty.
 #[tauri::command]
-async fn consume_migration_notices() -> Result<Vec<String>, String> {
+async fn consume_migration_notices() -> Result<Vec<String>, AppError> {
     Ok(storage::drain_migration_notices())
 }
 
@@ -3334,7 +3810,7 @@ async fn consume_migration_notices() -> Result<Vec<String>, String> {
 /// `write_temp_file` succeeded but the subsequent `save_lecture`
 /// failed (logged-only before v0.5.2 audit fix).
 #[tauri::command]
-async fn try_recover_pdf_path(lecture_id: String) -> Result<Option<String>, String> {
+async fn try_recover_pdf_path(lecture_id: String) -> Result<Option<String>, AppError> {
     use std::fs;
 
     let manager = storage::get_db_manager()
@@ -3441,6 +3917,714 @@ fn to_stored_audio_path(audio_dir: &std::path::Path, absolute_path: &std::path::
     absolute_path.to_string_lossy().to_string()
 }
 
+/// Transcode a lecture's finished audio to Opus or FLAC and point
+/// `audio_path` at the new file. The original is deleted only after the
+/// DB update lands, and only if it lives under audio_dir (an imported
+/// file the user picked from elsewhere is left alone).
+#[tauri::command]
+async fn compress_lecture_audio(
+    lecture_id: String,
+    codec: String,
+    bitrate: Option<u32>,
+    user_id: Option<String>,
+) -> Result<recording::compress::CompressResult, AppError> {
+    use recording::compress::{transcode_audio_inner, AudioCodec, CompressResult};
+
+    let codec = AudioCodec::parse(&codec)?;
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    verify_lecture_ownership(&db, &lecture_id, &user)?;
+
+    let lecture = db
+        .get_lecture(&lecture_id)
+        .map_err(|e| format!("獲取課程失敗: {}", e))?
+        .ok_or_else(|| "找不到此課堂".to_string())?;
+    let audio_dir = paths::get_audio_dir()?;
+    let src = lecture
+        .audio_path
+        .as_deref()
+        .and_then(|p| resolve_stored_audio_path(&audio_dir, p))
+        .filter(|p| p.is_file())
+        .ok_or_else(|| "此課堂沒有可用的錄音檔".to_string())?;
+    let already = src
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case(codec.extension()))
+        .unwrap_or(false);
+    if already {
+        return Err(AppError::invalid_input(format!(
+            "錄音已是 {} 格式",
+            codec.extension()
+        )));
+    }
+
+    let original_bytes = std::fs::metadata(&src).map(|m| m.len()).unwrap_or(0);
+    let ts = chrono::Utc::now().timestamp_millis();
+    let dest = audio_dir.join(format!("lecture_{}_{}.{}", lecture_id, ts, codec.extension()));
+    let (src_for_task, dest_for_task) = (src.clone(), dest.clone());
+    let compressed_bytes = tokio::task::spawn_blocking(move || {
+        transcode_audio_inner(&src_for_task, &dest_for_task, codec, bitrate)
+    })
+    .await
+    .map_err(|e| format!("compress task join error: {e}"))??;
+
+    let stored = to_stored_audio_path(&audio_dir, &dest);
+    if let Err(e) = db.set_lecture_audio_path(&lecture_id, &stored) {
+        let _ = std::fs::remove_file(&dest);
+        return Err(format!("更新錄音路徑失敗: {}", e).into());
+    }
+    if src.starts_with(&audio_dir) {
+        let _ = std::fs::remove_file(&src);
+    }
+
+    Ok(CompressResult {
+        audio_path: stored,
+        original_bytes,
+        compressed_bytes,
+    })
+}
+
+/// Min/max peaks for the playback waveform, `buckets` pairs spanning
+/// the whole recording. Cached under `{app_data}/cache/waveforms/` and
+/// recomputed when the audio file changes.
+#[tauri::command]
+async fn generate_waveform(
+    lecture_id: String,
+    buckets: usize,
+    user_id: Option<String>,
+) -> Result<recording::waveform::WaveformPeaks, AppError> {
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    verify_lecture_ownership(&db, &lecture_id, &user)?;
+
+    let lecture = db
+        .get_lecture(&lecture_id)
+        .map_err(|e| format!("獲取課程失敗: {}", e))?
+        .ok_or_else(|| "找不到此課堂".to_string())?;
+    let audio_dir = paths::get_audio_dir()?;
+    let src = lecture
+        .audio_path
+        .as_deref()
+        .and_then(|p| resolve_stored_audio_path(&audio_dir, p))
+        .filter(|p| p.is_file())
+        .ok_or_else(|| "此課堂沒有可用的錄音檔".to_string())?;
+    let cache_dir = paths::get_cache_dir()?.join("waveforms");
+
+    tokio::task::spawn_blocking(move || {
+        recording::waveform::generate_waveform_inner(&src, &cache_dir, &lecture_id, buckets)
+    })
+    .await
+    .map_err(|e| format!("waveform task join error: {e}"))?
+    .map_err(AppError::from)
+}
+
+/// Cut a lecture recording down to `[start_ms, end_ms)` and move every
+/// subtitle back by `start_ms`. The trimmed audio is written to a new
+/// file first; subtitles, `audio_path` and `duration` then change in one
+/// DB transaction, and the old file is removed only after that commits
+/// (and only if it lives under audio_dir).
+#[tauri::command]
+async fn trim_lecture_audio(
+    lecture_id: String,
+    start_ms: u64,
+    end_ms: u64,
+    user_id: Option<String>,
+) -> Result<recording::trim::TrimResult, AppError> {
+    use recording::trim::{trim_audio_inner, trimmed_extension, TrimResult};
+
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    verify_lecture_ownership(&db, &lecture_id, &user)?;
+
+    // Externalized transcripts live in a compressed blob, not in the
+    // subtitles table — shifting only the (empty) table would leave the
+    // blob out of sync with the trimmed audio.
+    if db
+        .get_transcript_pointer(&lecture_id)
+        .map_err(|e| format!("讀取字幕封存狀態失敗: {}", e))?
+        .is_some()
+    {
+        return Err("此課堂字幕已封存，請先還原字幕再裁剪錄音".into());
+    }
+
+    let lecture = db
+        .get_lecture(&lecture_id)
+        .map_err(|e| format!("獲取課程失敗: {}", e))?
+        .ok_or_else(|| "找不到此課堂".to_string())?;
+    let audio_dir = paths::get_audio_dir()?;
+    let src = lecture
+        .audio_path
+        .as_deref()
+        .and_then(|p| resolve_stored_audio_path(&audio_dir, p))
+        .filter(|p| p.is_file())
+        .ok_or_else(|| "此課堂沒有可用的錄音檔".to_string())?;
+
+    let ts = chrono::Utc::now().timestamp_millis();
+    let dest = audio_dir.join(format!(
+        "lecture_{}_{}.{}",
+        lecture_id,
+        ts,
+        trimmed_extension(&src)
+    ));
+    let (src_for_task, dest_for_task) = (src.clone(), dest.clone());
+    let duration_ms = tokio::task::spawn_blocking(move || {
+        trim_audio_inner(&src_for_task, &dest_for_task, start_ms, end_ms)
+    })
+    .await
+    .map_err(|e| format!("trim task join error: {e}"))??;
+
+    let stored = to_stored_audio_path(&audio_dir, &dest);
+    let (subtitles_shifted, subtitles_removed) = match db.trim_lecture_timeline(
+        &lecture_id,
+        start_ms as f64 / 1000.0,
+        end_ms as f64 / 1000.0,
+        &stored,
+        (duration_ms as f64 / 1000.0).round() as i64,
+    ) {
+        Ok(counts) => counts,
+        Err(e) => {
+            let _ = std::fs::remove_file(&dest);
+            return Err(format!("更新字幕時間軸失敗: {}", e).into());
+        }
+    };
+    if src.starts_with(&audio_dir) {
+        let _ = std::fs::remove_file(&src);
+    }
+
+    Ok(TrimResult {
+        audio_path: stored,
+        duration_ms,
+        subtitles_shifted,
+        subtitles_removed,
+    })
+}
+
+/// Append `paths` (in order) to a lecture's recording. The lecture's
+/// current audio, if any, stays first. When an input is another of the
+/// user's lectures' recording — the usual "started a new lecture after
+/// the break" case — that lecture's subtitles move over, shifted by
+/// where its audio now starts. The inputs themselves are left on disk;
+/// only the target lecture's previous file is replaced.
+#[tauri::command]
+async fn merge_recordings(
+    lecture_id: String,
+    paths: Vec<String>,
+    user_id: Option<String>,
+) -> Result<recording::merge::MergeResult, AppError> {
+    use recording::merge::{concat_audio_inner, merged_extension, MergeResult};
+
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    verify_lecture_ownership(&db, &lecture_id, &user)?;
+
+    let lecture = db
+        .get_lecture(&lecture_id)
+        .map_err(|e| format!("獲取課程失敗: {}", e))?
+        .ok_or_else(|| "找不到此課堂".to_string())?;
+    let audio_dir = paths::get_audio_dir()?;
+    let existing = lecture
+        .audio_path
+        .as_deref()
+        .and_then(|p| resolve_stored_audio_path(&audio_dir, p))
+        .filter(|p| p.is_file());
+
+    let mut srcs: Vec<std::path::PathBuf> = existing.iter().cloned().collect();
+    for p in &paths {
+        let resolved = resolve_stored_audio_path(&audio_dir, p)
+            .ok_or_else(|| format!("無效的錄音路徑: {}", p))?;
+        if srcs.contains(&resolved) {
+            return Err(AppError::invalid_input(format!(
+                "錄音檔重複: {}",
+                resolved.display()
+            )));
+        }
+        srcs.push(resolved);
+    }
+
+    // Map each input back to the lecture that owns it (if any) so its
+    // subtitles can follow the audio.
+    let owners: Vec<Option<String>> = {
+        let lectures = db
+            .list_lectures(&user)
+            .map_err(|e| format!("獲取課程列表失敗: {}", e))?;
+        srcs.iter()
+            .map(|src| {
+                lectures
+                    .iter()
+                    .filter(|l| l.id != lecture_id)
+                    .find(|l| {
+                        l.audio_path
+                            .as_deref()
+                            .and_then(|p| resolve_stored_audio_path(&audio_dir, p))
+                            .as_ref()
+                            == Some(src)
+                    })
+                    .map(|l| l.id.clone())
+            })
+            .collect()
+    };
+    for id in std::iter::once(&lecture_id).chain(owners.iter().flatten()) {
+        if db
+            .get_transcript_pointer(id)
+            .map_err(|e| format!("讀取字幕封存狀態失敗: {}", e))?
+            .is_some()
+        {
+            return Err("有課堂字幕已封存，請先還原字幕再合併錄音".into());
+        }
+    }
+
+    let ts = chrono::Utc::now().timestamp_millis();
+    let dest = audio_dir.join(format!(
+        "lecture_{}_{}.{}",
+        lecture_id,
+        ts,
+        merged_extension(&srcs)
+    ));
+    let (srcs_for_task, dest_for_task) = (srcs.clone(), dest.clone());
+    let outcome =
+        tokio::task::spawn_blocking(move || concat_audio_inner(&srcs_for_task, &dest_for_task))
+            .await
+            .map_err(|e| format!("merge task join error: {e}"))??;
+
+    let moves: Vec<(String, f64)> = owners
+        .iter()
+        .zip(&outcome.offsets_ms)
+        .filter_map(|(owner, &offset)| owner.clone().map(|id| (id, offset as f64 / 1000.0)))
+        .collect();
+    let stored = to_stored_audio_path(&audio_dir, &dest);
+    let subtitles_moved = match db.merge_lecture_timelines(
+        &lecture_id,
+        &moves,
+        &stored,
+        (outcome.duration_ms as f64 / 1000.0).round() as i64,
+    ) {
+        Ok(n) => n,
+        Err(e) => {
+            let _ = std::fs::remove_file(&dest);
+            return Err(format!("更新字幕時間軸失敗: {}", e).into());
+        }
+    };
+    if let Some(old) = existing.filter(|p| p.starts_with(&audio_dir)) {
+        let _ = std::fs::remove_file(old);
+    }
+
+    Ok(MergeResult {
+        audio_path: stored,
+        duration_ms: outcome.duration_ms,
+        offsets_ms: outcome.offsets_ms,
+        subtitles_moved,
+    })
+}
+
+/// Export a lecture's recording with the silences cut out, for quick
+/// revision. Speech spans come from the stored VAD segments (detected
+/// and cached on first use). `speed` (0.5–2.0, default 1.0) also speeds
+/// the export up; the returned time map is in export time at that
+/// speed. `dest_path`'s extension is replaced with the format actually
+/// written. With `write_time_map` the map is also saved next to the
+/// audio as `*.timemap.json`.
+#[tauri::command]
+async fn export_condensed_audio(
+    app: tauri::AppHandle,
+    lecture_id: String,
+    dest_path: String,
+    speed: Option<f64>,
+    write_time_map: Option<bool>,
+    user_id: Option<String>,
+) -> Result<recording::condense::CondenseResult, AppError> {
+    use recording::condense::{
+        build_time_map, condense_audio_inner, condensed_extension, keep_ranges,
+        speech_segments_inner, CondenseResult,
+    };
+
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    verify_lecture_ownership(&db, &lecture_id, &user)?;
+
+    let lecture = db
+        .get_lecture(&lecture_id)
+        .map_err(|e| format!("獲取課程失敗: {}", e))?
+        .ok_or_else(|| "找不到此課堂".to_string())?;
+    let audio_dir = paths::get_audio_dir()?;
+    let src = lecture
+        .audio_path
+        .as_deref()
+        .and_then(|p| resolve_stored_audio_path(&audio_dir, p))
+        .filter(|p| p.is_file())
+        .ok_or_else(|| "此課堂沒有可用的錄音檔".to_string())?;
+    let cache_dir = paths::get_cache_dir()?.join("vad");
+    let speed = speed.unwrap_or(1.0);
+    let dest = std::path::PathBuf::from(&dest_path).with_extension(condensed_extension(&src, speed));
+    if dest == src {
+        return Err(AppError::invalid_input("匯出路徑不可與原始錄音相同"));
+    }
+
+    let task = tasks::Task::start(&app, tasks::TaskKind::Export, lecture.title.clone());
+    let result = tokio::task::spawn_blocking(move || {
+        let (segments, source_duration_ms) =
+            speech_segments_inner(&src, &cache_dir, &lecture_id)?;
+        let ranges = keep_ranges(&segments, source_duration_ms);
+        let duration_ms = condense_audio_inner(&src, &dest, &ranges, speed)?;
+        let map = build_time_map(&ranges, speed);
+
+        let map_path = if write_time_map.unwrap_or(false) {
+            let path = dest.with_extension("timemap.json");
+            let json = serde_json::to_string_pretty(&map)
+                .map_err(|e| format!("序列化時間對照表失敗: {e}"))?;
+            std::fs::write(&path, json)
+                .map_err(|e| format!("write {}: {e}", path.display()))?;
+            Some(path.to_string_lossy().to_string())
+        } else {
+            None
+        };
+        Ok(CondenseResult {
+            output_path: dest.to_string_lossy().to_string(),
+            map_path,
+            source_duration_ms,
+            duration_ms,
+            speed,
+            map,
+        })
+    })
+    .await
+    .map_err(|e| format!("condense task join error: {e}"))?;
+    task.finish(&result);
+    result.map_err(AppError::from)
+}
+
+/// Export the lecture's recording as `.m4a` / `.ogg` with embedded
+/// chapter markers for podcast players. Chapters come from the note's
+/// topic sections plus any `bookmarks` (e.g. exam marks) the caller
+/// passes; `dest_path`'s extension is replaced to match `format`.
+#[tauri::command]
+async fn export_lecture_with_chapters(
+    app: tauri::AppHandle,
+    lecture_id: String,
+    dest_path: String,
+    format: Option<String>,
+    bookmarks: Option<Vec<recording::chapters::ChapterMark>>,
+    user_id: Option<String>,
+) -> Result<recording::chapters::ChapterExportResult, AppError> {
+    use recording::chapters::{
+        build_chapters, export_with_chapters_inner, marks_from_note_content, ChapterExportResult,
+        ChapterFormat,
+    };
+
+    let format = ChapterFormat::parse(format.as_deref().unwrap_or("m4a"))?;
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    verify_lecture_ownership(&db, &lecture_id, &user)?;
+
+    let lecture = db
+        .get_lecture(&lecture_id)
+        .map_err(|e| format!("獲取課程失敗: {}", e))?
+        .ok_or_else(|| "找不到此課堂".to_string())?;
+    let mut marks = db
+        .get_note(&lecture_id)
+        .map_err(|e| format!("獲取筆記失敗: {}", e))?
+        .map(|note| marks_from_note_content(&note.content))
+        .unwrap_or_default();
+    marks.extend(bookmarks.unwrap_or_default());
+
+    let audio_dir = paths::get_audio_dir()?;
+    let src = lecture
+        .audio_path
+        .as_deref()
+        .and_then(|p| resolve_stored_audio_path(&audio_dir, p))
+        .filter(|p| p.is_file())
+        .ok_or_else(|| "此課堂沒有可用的錄音檔".to_string())?;
+    let dest = std::path::PathBuf::from(&dest_path).with_extension(format.extension());
+    if dest == src {
+        return Err(AppError::invalid_input("匯出路徑不可與原始錄音相同"));
+    }
+
+    let task = tasks::Task::start(&app, tasks::TaskKind::Export, lecture.title.clone());
+    let result = tokio::task::spawn_blocking(move || {
+        let source_sec = recording::audio_import::probe_media_duration(&src)?
+            .ok_or_else(|| "無法讀取錄音長度".to_string())?;
+        let chapters = build_chapters(&marks, (source_sec * 1000.0).round() as u64);
+        let duration_ms =
+            export_with_chapters_inner(&src, &dest, format, &lecture.title, &chapters, source_sec)?;
+        Ok(ChapterExportResult {
+            output_path: dest.to_string_lossy().to_string(),
+            duration_ms,
+            chapters,
+        })
+    })
+    .await
+    .map_err(|e| format!("chapter export task join error: {e}"))?;
+    task.finish(&result);
+    result.map_err(AppError::from)
+}
+
+/// Header details and Markdown body of a lecture's note, for the note
+/// exporters.
+async fn load_note_export(
+    lecture_id: &str,
+    user_id: Option<String>,
+) -> Result<(notes::ExportMeta, String), String> {
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    verify_lecture_ownership(&db, lecture_id, &user)?;
+
+    let lecture = db
+        .get_lecture(lecture_id)
+        .map_err(|e| format!("獲取課程失敗: {}", e))?
+        .ok_or_else(|| "找不到此課堂".to_string())?;
+    let note = db
+        .get_note(lecture_id)
+        .map_err(|e| format!("獲取筆記失敗: {}", e))?
+        .ok_or_else(|| "此課堂尚未產生筆記".to_string())?;
+    let course = db
+        .get_course(&lecture.course_id)
+        .map_err(|e| format!("獲取課程失敗: {}", e))?
+        .map(|c| c.title);
+    let meta = notes::ExportMeta {
+        title: lecture.title,
+        course,
+        date: Some(lecture.date),
+        duration_sec: Some(lecture.duration),
+    };
+    Ok((meta, notes::note_markdown(&note.content)))
+}
+
+/// Render the lecture's note to a paginated PDF at `dest_path` (its
+/// extension is replaced with `.pdf`) and return the written path.
+/// `template` is `standard` (default), `compact` or `letter`.
+#[tauri::command]
+async fn export_note_pdf(
+    app: tauri::AppHandle,
+    lecture_id: String,
+    template: Option<String>,
+    dest_path: String,
+    user_id: Option<String>,
+) -> Result<String, AppError> {
+    use notes::pdf::{render_note_pdf, PdfTemplate};
+
+    let template = PdfTemplate::parse(template.as_deref().unwrap_or_default())?;
+    let (meta, markdown) = load_note_export(&lecture_id, user_id).await?;
+    let dest = std::path::PathBuf::from(&dest_path).with_extension("pdf");
+
+    let task = tasks::Task::start(&app, tasks::TaskKind::Export, meta.title.clone());
+    let result = tokio::task::spawn_blocking(move || {
+        let bytes = render_note_pdf(&meta, &markdown, template);
+        std::fs::write(&dest, bytes).map_err(|e| format!("寫入 PDF 失敗: {}", e))?;
+        Ok(dest.to_string_lossy().to_string())
+    })
+    .await
+    .map_err(|e| format!("note pdf task join error: {e}"))?;
+    task.finish(&result);
+    result.map_err(AppError::from)
+}
+
+/// Write the lecture's note as an editable Word document at `dest_path`
+/// (its extension is replaced with `.docx`) and return the written path.
+#[tauri::command]
+async fn export_note_docx(
+    app: tauri::AppHandle,
+    lecture_id: String,
+    dest_path: String,
+    user_id: Option<String>,
+) -> Result<String, AppError> {
+    let (meta, markdown) = load_note_export(&lecture_id, user_id).await?;
+    let dest = std::path::PathBuf::from(&dest_path).with_extension("docx");
+
+    let task = tasks::Task::start(&app, tasks::TaskKind::Export, meta.title.clone());
+    let result = tokio::task::spawn_blocking(move || {
+        notes::docx::write_note_docx(&dest, &meta, &markdown)?;
+        Ok(dest.to_string_lossy().to_string())
+    })
+    .await
+    .map_err(|e| format!("note docx task join error: {e}"))?;
+    task.finish(&result);
+    result.map_err(AppError::from)
+}
+
+/// Turn the Q&A records of every note in the course into an Anki deck,
+/// with a clip of the source subtitle for each card when the lecture has
+/// a recording. `format` is `apkg` (default; `dest_path` gets `.apkg`)
+/// or `csv` (`dest_path` becomes a folder of `cards.txt` + clips).
+#[tauri::command]
+async fn export_anki(
+    app: tauri::AppHandle,
+    course_id: String,
+    dest_path: String,
+    format: Option<String>,
+    user_id: Option<String>,
+) -> Result<notes::anki::AnkiExportReport, AppError> {
+    use notes::anki::{export_course_deck, AnkiFormat, LectureCards};
+
+    let format = AnkiFormat::parse(format.as_deref().unwrap_or_default())?;
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    verify_course_ownership(&db, &course_id, &user)?;
+
+    let course_title = db
+        .get_course(&course_id)
+        .map_err(|e| format!("讀取課程失敗: {}", e))?
+        .map(|c| c.title)
+        .unwrap_or_default();
+    let transcripts_dir = paths::get_transcripts_dir()?;
+    let mut lectures = db
+        .list_lectures_by_course(&course_id, &user)
+        .map_err(|e| format!("讀取課堂失敗: {}", e))?;
+    // Oldest first, so the deck follows the course.
+    lectures.reverse();
+
+    let mut decks = Vec::new();
+    for lecture in lectures {
+        let qa = match db
+            .get_note(&lecture.id)
+            .map_err(|e| format!("讀取筆記失敗: {}", e))?
+        {
+            Some(note) => notes::note_qa_records(&note.content),
+            None => continue,
+        };
+        if qa.is_empty() {
+            continue;
+        }
+        let subtitle_starts =
+            storage::transcript_store::load_subtitles_merged(&db, &transcripts_dir, &lecture.id)?
+                .iter()
+                .map(|s| s.timestamp)
+                .collect();
+        let audio = lecture
+            .audio_path
+            .map(std::path::PathBuf::from)
+            .filter(|p| p.is_file());
+        decks.push(LectureCards {
+            lecture_id: lecture.id,
+            title: lecture.title,
+            qa,
+            subtitle_starts,
+            audio,
+        });
+    }
+    drop(db);
+
+    let dest = std::path::PathBuf::from(&dest_path);
+    let dest = match format {
+        AnkiFormat::Apkg => dest.with_extension("apkg"),
+        AnkiFormat::Csv => dest.with_extension(""),
+    };
+    let task = tasks::Task::start(&app, tasks::TaskKind::Export, course_title.clone());
+    let result = tokio::task::spawn_blocking(move || {
+        export_course_deck(&dest, format, &course_id, &course_title, &decks)
+    })
+    .await
+    .map_err(|e| format!("anki export task join error: {e}"))?;
+    task.finish(&result);
+    result.map_err(AppError::from)
+}
+
+/// Write the course as a folder of Markdown files under `dest_dir` (one
+/// per lecture with a note, plus an index) with section audio clips and
+/// slide images linked from `assets/`, for Obsidian / Logseq vaults.
+#[tauri::command]
+async fn export_markdown_bundle(
+    app: tauri::AppHandle,
+    course_id: String,
+    dest_dir: String,
+    user_id: Option<String>,
+) -> Result<notes::bundle::BundleReport, AppError> {
+    use notes::bundle::{export_course_bundle, BundleLecture};
+
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    verify_course_ownership(&db, &course_id, &user)?;
+
+    let course_title = db
+        .get_course(&course_id)
+        .map_err(|e| format!("讀取課程失敗: {}", e))?
+        .map(|c| c.title)
+        .unwrap_or_default();
+    let mut lectures = db
+        .list_lectures_by_course(&course_id, &user)
+        .map_err(|e| format!("讀取課堂失敗: {}", e))?;
+    lectures.reverse();
+
+    let existing = |p: Option<String>| p.map(std::path::PathBuf::from).filter(|p| p.is_file());
+    let mut bundle = Vec::new();
+    for lecture in lectures {
+        let Some(note) = db
+            .get_note(&lecture.id)
+            .map_err(|e| format!("讀取筆記失敗: {}", e))?
+        else {
+            continue;
+        };
+        bundle.push(BundleLecture {
+            lecture_id: lecture.id,
+            title: lecture.title,
+            date: lecture.date,
+            duration_sec: lecture.duration,
+            note: note.content,
+            audio: existing(lecture.audio_path),
+            pdf: existing(lecture.pdf_path),
+        });
+    }
+    drop(db);
+
+    let slide_cache = paths::get_cache_dir()?.join("pdf-pages");
+    let task = tasks::Task::start(&app, tasks::TaskKind::Export, course_title.clone());
+    let result = tokio::task::spawn_blocking(move || {
+        export_course_bundle(
+            std::path::Path::new(&dest_dir),
+            &course_title,
+            &bundle,
+            &slide_cache,
+        )
+    })
+    .await
+    .map_err(|e| format!("markdown bundle task join error: {e}"))?;
+    task.finish(&result);
+    result.map_err(AppError::from)
+}
+
 /// 嘗試恢復丟失的 audio_path.
 ///
 /// v0.5.2: extended to also recover from orphaned `.pcm` files in the
@@ -3454,7 +4638,7 @@ fn to_stored_audio_path(audio_dir: &std::path::Path, absolute_path: &std::path::
 ///
 /// Recovery order:
 ///   1. DB already has a non-empty audio_path → return it as-is.
-///   2. Scan audio_dir for `lecture_<id>_*.wav`; pick the NEWEST (by
+///   2. Scan audio_dir for `lecture_<id>_*.{wav,webm,flac}`; pick the NEWEST (by
 ///      mtime) so re-recordings on the same lecture don't silently
 ///      lose audio to an older file.
 ///   3. Scan in-progress dir for `<id>.pcm`; finalize it into a new
@@ -3463,7 +4647,7 @@ fn to_stored_audio_path(audio_dir: &std::path::Path, absolute_path: &std::path::
 ///      same file can't get recovered twice.
 ///   4. Nothing found → Ok(None).
 #[tauri::command]
-async fn try_recover_audio_path(lecture_id: String) -> Result<Option<String>, String> {
+async fn try_recover_audio_path(lecture_id: String) -> Result<Option<String>, AppError> {
     use std::fs;
 
     // Step 1: check DB state. If audio_path is already populated, nothing to recover.
@@ -3511,7 +4695,11 @@ async fn try_recover_audio_path(lecture_id: String) -> Result<Option<String>, St
                     Some(s) => s,
                     None => continue,
                 };
-                if !(name.starts_with(&prefix) && name.ends_with(".wav")) {
+                // `.webm` / `.flac` come from `compress_lecture_audio`.
+                let is_audio = [".wav", ".webm", ".flac"]
+                    .iter()
+                    .any(|ext| name.ends_with(ext));
+                if !(name.starts_with(&prefix) && is_audio) {
                     continue;
                 }
                 // Prefer the newest re-recording over an older one. An
@@ -3537,7 +4725,7 @@ async fn try_recover_audio_path(lecture_id: String) -> Result<Option<String>, St
         let in_progress_dir =
             paths::get_in_progress_audio_dir().map_err(|e| format!("Path Error: {}", e))?;
         let pcm_path = in_progress_dir.join(format!("{}.pcm", lecture_id));
-        if pcm_path.exists() {
+        if pcm_path.exists() || recording::segments::has_segments(&in_progress_dir, &lecture_id) {
             // Synthesise a new timestamped WAV target under audio_dir.
             let ts = chrono::Utc::now().timestamp_millis();
             let wav_path = audio_dir.join(format!("lecture_{}_{}.wav", lecture_id, ts));
@@ -3605,7 +4793,7 @@ async fn add_pending_action(
     action_type: String,
     payload: String,
     user_id: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -3614,15 +4802,18 @@ async fn add_pending_action(
         .map_err(|e| format!("數據庫連接失敗: {}", e))?;
     let user = user_id.unwrap_or_else(|| "default_user".to_string());
     if user.is_empty() {
-        return Err("無權新增待處理動作（user_id 為空）".to_string());
+        return Err(AppError::permission_denied(
+            "無權新增待處理動作（user_id 為空）",
+        ));
     }
     db.add_pending_action(&id, &action_type, &payload)
         .map_err(|e| format!("新增待處理動作失敗: {}", e))?;
+    sync::outbox::wake();
     Ok(())
 }
 
 #[tauri::command]
-async fn list_pending_actions() -> Result<Vec<(String, String, String, String, i32)>, String> {
+async fn list_pending_actions() -> Result<Vec<(String, String, String, String, i32)>, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -3630,7 +4821,7 @@ async fn list_pending_actions() -> Result<Vec<(String, String, String, String, i
         .get_db()
         .map_err(|e| format!("數據庫連接失敗: {}", e))?;
     db.list_pending_actions()
-        .map_err(|e| format!("列出待處理動作失敗: {}", e))
+        .map_err(|e| format!("列出待處理動作失敗: {}", e).into())
 }
 
 /// cp75.34 — same defense-in-depth user_id gate as `add_pending_action`.
@@ -3644,7 +4835,7 @@ async fn update_pending_action(
     status: String,
     retry_count: i32,
     user_id: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -3653,7 +4844,9 @@ async fn update_pending_action(
         .map_err(|e| format!("數據庫連接失敗: {}", e))?;
     let user = user_id.unwrap_or_else(|| "default_user".to_string());
     if user.is_empty() {
-        return Err("無權更新待處理動作（user_id 為空）".to_string());
+        return Err(AppError::permission_denied(
+            "無權更新待處理動作（user_id 為空）",
+        ));
     }
     db.update_pending_action(&id, &status, retry_count)
         .map_err(|e| format!("更新待處理動作失敗: {}", e))?;
@@ -3661,7 +4854,7 @@ async fn update_pending_action(
 }
 
 #[tauri::command]
-async fn remove_pending_action(id: String) -> Result<(), String> {
+async fn remove_pending_action(id: String) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -3673,10 +4866,180 @@ async fn remove_pending_action(id: String) -> Result<(), String> {
     Ok(())
 }
 
+/// The outbox's counts, whether it's flushing and the last error.
+#[tauri::command]
+async fn get_sync_status() -> Result<sync::SyncStatus, AppError> {
+    Ok(sync::status().await?)
+}
+
+/// The running flush's report, else the last finished one; `None`
+/// before the first sync.
+#[tauri::command]
+async fn get_last_sync_report() -> Result<Option<sync::report::SyncReport>, AppError> {
+    Ok(sync::report::last().await?)
+}
+
+/// Send the outbox now. `retry_failed` also puts the actions that ran
+/// out of retries back in line.
+#[tauri::command]
+async fn flush_sync_queue(retry_failed: Option<bool>) -> Result<(), AppError> {
+    if retry_failed.unwrap_or(false) {
+        sync::outbox::retry_failed().await?;
+    } else {
+        sync::outbox::wake();
+    }
+    Ok(())
+}
+
+/// How note conflicts are settled for this user: `lww`, `prefer_local`
+/// or `ask`.
+#[tauri::command]
+async fn get_sync_conflict_policy(
+    user_id: Option<String>,
+) -> Result<sync::conflicts::ConflictPolicy, AppError> {
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    Ok(sync::conflicts::policy(&user).await?)
+}
+
+#[tauri::command]
+async fn set_sync_conflict_policy(
+    policy: String,
+    user_id: Option<String>,
+) -> Result<(), AppError> {
+    let policy = sync::conflicts::ConflictPolicy::parse(&policy)
+        .ok_or_else(|| AppError::invalid_input(format!("未知的衝突處理方式: {}", policy)))?;
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    Ok(sync::conflicts::set_policy(&user, policy).await?)
+}
+
+/// Conflicted copies of the user's notes, newest first; only the ones
+/// waiting on a choice unless `include_resolved`.
+#[tauri::command]
+async fn list_note_conflicts(
+    user_id: Option<String>,
+    include_resolved: Option<bool>,
+) -> Result<Vec<storage::NoteConflict>, AppError> {
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    db.list_note_conflicts(&user, include_resolved.unwrap_or(false))
+        .map_err(|e| format!("列出筆記衝突失敗: {}", e).into())
+}
+
+/// Keep `keep` (`local` or `server`) for a conflict the user was asked
+/// about.
+#[tauri::command]
+async fn resolve_note_conflict(
+    conflict_id: String,
+    keep: String,
+    user_id: Option<String>,
+) -> Result<(), AppError> {
+    let keep_local = match keep.as_str() {
+        "local" => true,
+        "server" => false,
+        other => {
+            return Err(AppError::invalid_input(format!(
+                "keep 必須是 local 或 server: {}",
+                other
+            )))
+        }
+    };
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    Ok(sync::conflicts::resolve(&conflict_id, keep_local, &user).await?)
+}
+
+/// Whether synced notes are encrypted on this device first, and whether
+/// it has the key.
+#[tauri::command]
+async fn get_sync_encryption(
+    user_id: Option<String>,
+) -> Result<sync::crypto::EncryptionStatus, AppError> {
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    Ok(sync::crypto::status(&user).await?)
+}
+
+/// Turn on sync encryption with `passphrase`; on another device, the
+/// same passphrase gives the same key.
+#[tauri::command]
+async fn enable_sync_encryption(
+    passphrase: String,
+    user_id: Option<String>,
+) -> Result<(), AppError> {
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    Ok(sync::crypto::enable(&user, &passphrase).await?)
+}
+
+#[tauri::command]
+async fn disable_sync_encryption(user_id: Option<String>) -> Result<(), AppError> {
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    Ok(sync::crypto::disable(&user).await?)
+}
+
+/// Which data types and courses sync for this user.
+#[tauri::command]
+async fn get_sync_selection(
+    user_id: Option<String>,
+) -> Result<sync::selection::SyncSelection, AppError> {
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    Ok(sync::selection::get(&user).await?)
+}
+
+#[tauri::command]
+async fn set_sync_selection(
+    selection: sync::selection::SyncSelection,
+    user_id: Option<String>,
+) -> Result<(), AppError> {
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    Ok(sync::selection::set(&user, selection).await?)
+}
+
+/// The user's server profiles, with which one is active.
+#[tauri::command]
+async fn list_server_profiles(
+    user_id: Option<String>,
+) -> Result<Vec<sync::profiles::ProfileStatus>, AppError> {
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    Ok(sync::profiles::list(&user).await?)
+}
+
+/// Add or update a server profile. `credentials` replaces its password
+/// or token (empty removes it); left out, the saved one is kept.
+#[tauri::command]
+async fn save_server_profile(
+    profile: sync::profiles::ServerProfile,
+    credentials: Option<String>,
+    user_id: Option<String>,
+) -> Result<sync::profiles::ServerProfile, AppError> {
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    Ok(sync::profiles::save(&user, profile, credentials).await?)
+}
+
+#[tauri::command]
+async fn remove_server_profile(
+    profile_id: String,
+    user_id: Option<String>,
+) -> Result<(), AppError> {
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    Ok(sync::profiles::remove(&user, &profile_id).await?)
+}
+
+#[tauri::command]
+async fn set_active_server_profile(
+    profile_id: String,
+    user_id: Option<String>,
+) -> Result<(), AppError> {
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    Ok(sync::profiles::set_active(&user, &profile_id).await?)
+}
+
 // ========== Trash Bin Commands ==========
 
 #[tauri::command]
-async fn list_deleted_courses(user_id: String) -> Result<Vec<storage::models::Course>, String> {
+async fn list_deleted_courses(user_id: String) -> Result<Vec<storage::models::Course>, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -3684,11 +5047,11 @@ async fn list_deleted_courses(user_id: String) -> Result<Vec<storage::models::Co
         .get_db()
         .map_err(|e| format!("數據庫連接失敗: {}", e))?;
     db.list_deleted_courses(&user_id)
-        .map_err(|e| format!("列出已刪除課程失敗: {}", e))
+        .map_err(|e| format!("列出已刪除課程失敗: {}", e).into())
 }
 
 #[tauri::command]
-async fn list_deleted_lectures(user_id: String) -> Result<Vec<storage::models::Lecture>, String> {
+async fn list_deleted_lectures(user_id: String) -> Result<Vec<storage::models::Lecture>, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -3696,7 +5059,7 @@ async fn list_deleted_lectures(user_id: String) -> Result<Vec<storage::models::L
         .get_db()
         .map_err(|e| format!("數據庫連接失敗: {}", e))?;
     db.list_deleted_lectures(&user_id)
-        .map_err(|e| format!("列出已刪除課堂失敗: {}", e))
+        .map_err(|e| format!("列出已刪除課堂失敗: {}", e).into())
 }
 
 /// Restore a course from the trash and reverse-cascade any lectures
@@ -3707,7 +5070,7 @@ async fn list_deleted_lectures(user_id: String) -> Result<Vec<storage::models::L
 /// 課堂". Frontends that previously ignored the unit return type just
 /// have to accept (and discard) the integer now.
 #[tauri::command]
-async fn restore_course(id: String, user_id: Option<String>) -> Result<i64, String> {
+async fn restore_course(id: String, user_id: Option<String>) -> Result<i64, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -3721,7 +5084,7 @@ async fn restore_course(id: String, user_id: Option<String>) -> Result<i64, Stri
     // or the call would fail with "找不到此課程".
     verify_course_ownership_including_trashed(&db, &id, &user)?;
     db.restore_course(&id)
-        .map_err(|e| format!("還原課程失敗: {}", e))
+        .map_err(|e| format!("還原課程失敗: {}", e).into())
 }
 
 /// Restore a lecture from the trash. Errors with the
@@ -3729,7 +5092,7 @@ async fn restore_course(id: String, user_id: Option<String>) -> Result<i64, Stri
 /// soft-deleted — the frontend uses that signal to prompt
 /// "需要連同課程一起回復".
 #[tauri::command]
-async fn restore_lecture(id: String, user_id: Option<String>) -> Result<(), String> {
+async fn restore_lecture(id: String, user_id: Option<String>) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -3744,7 +5107,7 @@ async fn restore_lecture(id: String, user_id: Option<String>) -> Result<(), Stri
 }
 
 #[tauri::command]
-async fn purge_course(id: String, user_id: Option<String>) -> Result<(), String> {
+async fn purge_course(id: String, user_id: Option<String>) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -3762,7 +5125,7 @@ async fn purge_course(id: String, user_id: Option<String>) -> Result<(), String>
 }
 
 #[tauri::command]
-async fn purge_lecture(id: String, user_id: Option<String>) -> Result<(), String> {
+async fn purge_lecture(id: String, user_id: Option<String>) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -3773,6 +5136,10 @@ async fn purge_lecture(id: String, user_id: Option<String>) -> Result<(), String
     verify_lecture_ownership_including_trashed(&db, &id, &user)?;
     db.purge_lecture(&id)
         .map_err(|e| format!("永久刪除課堂失敗: {}", e))?;
+    // The pointer row goes with the FK cascade; the blob file doesn't.
+    if let Ok(dir) = paths::get_transcripts_dir() {
+        storage::transcript_store::remove_blob_file(&dir, &id);
+    }
     Ok(())
 }
 
@@ -3789,7 +5156,7 @@ async fn purge_lecture(id: String, user_id: Option<String>) -> Result<(), String
 async fn delete_course_cascade(
     course_id: String,
     user_id: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -3809,7 +5176,7 @@ async fn delete_course_cascade(
 #[tauri::command]
 async fn list_trashed_lectures(
     user_id: Option<String>,
-) -> Result<Vec<storage::models::Lecture>, String> {
+) -> Result<Vec<storage::models::Lecture>, AppError> {
     let user = user_id.unwrap_or_else(|| "default_user".to_string());
     let manager = storage::get_db_manager()
         .await
@@ -3818,7 +5185,7 @@ async fn list_trashed_lectures(
         .get_db()
         .map_err(|e| format!("數據庫連接失敗: {}", e))?;
     db.list_deleted_lectures(&user)
-        .map_err(|e| format!("列出垃圾桶課堂失敗: {}", e))
+        .map_err(|e| format!("列出垃圾桶課堂失敗: {}", e).into())
 }
 
 /// Phase 7 §9.5 W3 + S3.f-RS-3: hard-delete trash rows older than
@@ -3829,7 +5196,7 @@ async fn list_trashed_lectures(
 async fn hard_delete_trashed_older_than(
     days: i64,
     user_id: Option<String>,
-) -> Result<Vec<String>, String> {
+) -> Result<Vec<String>, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -3838,7 +5205,7 @@ async fn hard_delete_trashed_older_than(
         .map_err(|e| format!("數據庫連接失敗: {}", e))?;
     let user = user_id.unwrap_or_else(|| "default_user".to_string());
     db.hard_delete_trashed_older_than(days, &user)
-        .map_err(|e| format!("永久清除過期垃圾桶失敗: {}", e))
+        .map_err(|e| format!("永久清除過期垃圾桶失敗: {}", e).into())
 }
 
 /// Phase 7 cp74.1: list every soft-deleted COURSE for the user. Mirrors
@@ -3848,7 +5215,7 @@ async fn hard_delete_trashed_older_than(
 #[tauri::command]
 async fn list_trashed_courses(
     user_id: Option<String>,
-) -> Result<Vec<storage::models::Course>, String> {
+) -> Result<Vec<storage::models::Course>, AppError> {
     let user = user_id.unwrap_or_else(|| "default_user".to_string());
     let manager = storage::get_db_manager()
         .await
@@ -3857,7 +5224,7 @@ async fn list_trashed_courses(
         .get_db()
         .map_err(|e| format!("數據庫連接失敗: {}", e))?;
     db.list_deleted_courses(&user)
-        .map_err(|e| format!("列出垃圾桶課程失敗: {}", e))
+        .map_err(|e| format!("列出垃圾桶課程失敗: {}", e).into())
 }
 
 /// cp75.27 P1-G — list every lecture still soft-deleted under a given
@@ -3873,7 +5240,7 @@ async fn list_trashed_courses(
 async fn list_trashed_lectures_in_course(
     course_id: String,
     user_id: Option<String>,
-) -> Result<Vec<storage::models::Lecture>, String> {
+) -> Result<Vec<storage::models::Lecture>, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -3883,7 +5250,7 @@ async fn list_trashed_lectures_in_course(
     let user = user_id.unwrap_or_else(|| "default_user".to_string());
     verify_course_ownership(&db, &course_id, &user)?;
     db.find_trashed_lectures_in_course(&course_id)
-        .map_err(|e| format!("列出課程內垃圾桶課堂失敗: {}", e))
+        .map_err(|e| format!("列出課程內垃圾桶課堂失敗: {}", e).into())
 }
 
 /// cp75.6 — Verify a lecture's owning course belongs to `user_id`.
@@ -3895,11 +5262,13 @@ fn verify_lecture_ownership(
     db: &storage::Database,
     lecture_id: &str,
     user_id: &str,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     match db.find_lecture_owner(lecture_id) {
         Some(o) if o == user_id => Ok(()),
-        Some(_) => Err("無權操作此課堂（屬於其他帳號）".to_string()),
-        None => Err("找不到此課堂".to_string()),
+        Some(_) => Err(AppError::permission_denied(
+            "無權操作此課堂（屬於其他帳號）",
+        )),
+        None => Err(AppError::not_found("找不到此課堂")),
     }
 }
 
@@ -3908,11 +5277,13 @@ fn verify_course_ownership(
     db: &storage::Database,
     course_id: &str,
     user_id: &str,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     match db.find_course_owner(course_id) {
         Some(o) if o == user_id => Ok(()),
-        Some(_) => Err("無權操作此課程（屬於其他帳號）".to_string()),
-        None => Err("找不到此課程".to_string()),
+        Some(_) => Err(AppError::permission_denied(
+            "無權操作此課程（屬於其他帳號）",
+        )),
+        None => Err(AppError::not_found("找不到此課程")),
     }
 }
 
@@ -3923,11 +5294,13 @@ fn verify_lecture_ownership_including_trashed(
     db: &storage::Database,
     lecture_id: &str,
     user_id: &str,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     match db.find_lecture_owner_including_trashed(lecture_id) {
         Some(o) if o == user_id => Ok(()),
-        Some(_) => Err("無權操作此課堂（屬於其他帳號）".to_string()),
-        None => Err("找不到此課堂".to_string()),
+        Some(_) => Err(AppError::permission_denied(
+            "無權操作此課堂（屬於其他帳號）",
+        )),
+        None => Err(AppError::not_found("找不到此課堂")),
     }
 }
 
@@ -3941,11 +5314,13 @@ fn verify_course_ownership_including_trashed(
     db: &storage::Database,
     course_id: &str,
     user_id: &str,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     match db.find_course_owner_including_trashed(course_id) {
         Some(o) if o == user_id => Ok(()),
-        Some(_) => Err("無權操作此課程（屬於其他帳號）".to_string()),
-        None => Err("找不到此課程".to_string()),
+        Some(_) => Err(AppError::permission_denied(
+            "無權操作此課程（屬於其他帳號）",
+        )),
+        None => Err(AppError::not_found("找不到此課程")),
     }
 }
 
@@ -3956,11 +5331,13 @@ fn verify_chat_session_ownership(
     db: &storage::Database,
     session_id: &str,
     user_id: &str,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     match db.find_chat_session_owner(session_id) {
         Some(o) if o == user_id => Ok(()),
-        Some(_) => Err("無權操作此對話（屬於其他帳號）".to_string()),
-        None => Err("找不到對話 session".to_string()),
+        Some(_) => Err(AppError::permission_denied(
+            "無權操作此對話（屬於其他帳號）",
+        )),
+        None => Err(AppError::not_found("找不到對話 session")),
     }
 }
 
@@ -3977,7 +5354,7 @@ fn verify_chat_session_ownership(
 async fn hard_delete_lectures_by_ids(
     ids: Vec<String>,
     user_id: Option<String>,
-) -> Result<Vec<String>, String> {
+) -> Result<Vec<String>, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -3992,7 +5369,7 @@ async fn hard_delete_lectures_by_ids(
         .filter(|id| verify_lecture_ownership_including_trashed(&db, id, &user).is_ok())
         .collect();
     db.hard_delete_lectures_by_ids(&owned)
-        .map_err(|e| format!("永久刪除選取課堂失敗: {}", e))
+        .map_err(|e| format!("永久刪除選取課堂失敗: {}", e).into())
 }
 
 // ========== Sync 相關 Commands ==========
@@ -4004,7 +5381,7 @@ async fn hard_delete_lectures_by_ids(
 async fn delete_subtitles_by_lecture(
     lecture_id: String,
     user_id: Option<String>,
-) -> Result<usize, String> {
+) -> Result<usize, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -4014,7 +5391,7 @@ async fn delete_subtitles_by_lecture(
     let user = user_id.unwrap_or_else(|| "default_user".to_string());
     verify_lecture_ownership(&db, &lecture_id, &user)?;
     db.delete_subtitles_by_lecture(&lecture_id)
-        .map_err(|e| format!("刪除字幕失敗: {}", e))
+        .map_err(|e| format!("刪除字幕失敗: {}", e).into())
 }
 
 #[tauri::command]
@@ -4031,7 +5408,7 @@ async fn get_all_chat_sessions(
         String,
         bool,
     )>,
-    String,
+    AppError,
 > {
     let manager = storage::get_db_manager()
         .await
@@ -4053,7 +5430,7 @@ async fn save_chat_session(
     created_at: String,
     updated_at: String,
     is_deleted: bool,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -4070,13 +5447,13 @@ async fn save_chat_session(
         &updated_at,
         is_deleted,
     )
-    .map_err(|e| format!("保存聊天會話失敗: {}", e))
+    .map_err(|e| format!("保存聊天會話失敗: {}", e).into())
 }
 
 #[tauri::command]
 async fn get_all_chat_messages(
     user_id: String,
-) -> Result<Vec<(String, String, String, String, Option<String>, String)>, String> {
+) -> Result<Vec<(String, String, String, String, Option<String>, String)>, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -4084,7 +5461,7 @@ async fn get_all_chat_messages(
         .get_db()
         .map_err(|e| format!("數據庫連接失敗: {}", e))?;
     db.get_all_chat_messages(&user_id)
-        .map_err(|e| format!("獲取聊天訊息失敗: {}", e))
+        .map_err(|e| format!("獲取聊天訊息失敗: {}", e).into())
 }
 
 /// cp75.21 — verify the target session belongs to the caller before
@@ -4099,7 +5476,7 @@ async fn save_chat_message(
     sources: Option<String>,
     timestamp: String,
     user_id: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -4118,7 +5495,7 @@ async fn save_chat_message(
         sources.as_deref(),
         &timestamp,
     )
-    .map_err(|e| format!("保存聊天訊息失敗: {}", e))
+    .map_err(|e| format!("保存聊天訊息失敗: {}", e).into())
 }
 
 /// cp75.34 — verify the chat session belongs to the caller before
@@ -4128,7 +5505,7 @@ async fn save_chat_message(
 async fn delete_chat_messages_by_session(
     session_id: String,
     user_id: Option<String>,
-) -> Result<usize, String> {
+) -> Result<usize, AppError> {
     let manager = storage::get_db_manager()
         .await
         .map_err(|e| format!("數據庫未初始化: {}", e))?;
@@ -4138,7 +5515,7 @@ async fn delete_chat_messages_by_session(
     let user = user_id.unwrap_or_else(|| "default_user".to_string());
     verify_chat_session_ownership(&db, &session_id, &user)?;
     db.delete_chat_messages_by_session(&session_id)
-        .map_err(|e| format!("刪除聊天訊息失敗: {}", e))
+        .map_err(|e| format!("刪除聊天訊息失敗: {}", e).into())
 }
 
 #[cfg(test)]
@@ -4200,6 +5577,7 @@ mod tests {
     // the verifier layer to lock the error-message contract the
     // frontend relies on.
 
+    use crate::error::ErrorKind;
     use crate::storage::database::Database;
     use crate::verify_chat_session_ownership;
     use chrono::Utc;
@@ -4235,8 +5613,9 @@ mod tests {
     fn verify_chat_session_ownership_refuses_other_user() {
         let db = seed_chat_session_fixture();
         let err = verify_chat_session_ownership(&db, "session_a", "userB").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::PermissionDenied);
         assert!(
-            err.contains("無權"),
+            err.message.contains("無權"),
             "expected '無權' in error string, got: {err}"
         );
     }
@@ -4245,8 +5624,9 @@ mod tests {
     fn verify_chat_session_ownership_errors_on_missing_session() {
         let db = seed_chat_session_fixture();
         let err = verify_chat_session_ownership(&db, "does_not_exist", "userA").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::NotFound);
         assert!(
-            err.contains("找不到"),
+            err.message.contains("找不到"),
             "expected '找不到' in error string, got: {err}"
         );
     }
@@ -4350,7 +5730,7 @@ mod tests {
         let err = verify_course_ownership_including_trashed(&db, "course_a", "userB")
             .unwrap_err();
         assert!(
-            err.contains("無權"),
+            err.message.contains("無權"),
             "expected '無權' for cross-user course update; got: {err}"
         );
     }
@@ -4387,7 +5767,7 @@ mod tests {
     fn cp75_34_update_lecture_status_verify_rejects_other_user() {
         let db = seed_cp75_34_fixture();
         let err = verify_lecture_ownership(&db, "lec_a", "userB").unwrap_err();
-        assert!(err.contains("無權"));
+        assert!(err.message.contains("無權"));
     }
 
     #[test]
@@ -4403,7 +5783,7 @@ mod tests {
         let db = seed_cp75_34_fixture();
         // save_note resolves note.lecture_id then calls verify_lecture_ownership.
         let err = verify_lecture_ownership(&db, "lec_a", "userB").unwrap_err();
-        assert!(err.contains("無權"));
+        assert!(err.message.contains("無權"));
     }
 
     // ── A4-A7 embeddings — all hang off lecture ownership ───────────────
@@ -4412,7 +5792,7 @@ mod tests {
     fn cp75_34_save_embedding_verify_rejects_other_user() {
         let db = seed_cp75_34_fixture();
         let err = verify_lecture_ownership(&db, "lec_b", "userA").unwrap_err();
-        assert!(err.contains("無權"));
+        assert!(err.message.contains("無權"));
     }
 
     #[test]
@@ -4433,14 +5813,14 @@ mod tests {
     fn cp75_34_replace_embeddings_for_lecture_verify_rejects_other_user() {
         let db = seed_cp75_34_fixture();
         let err = verify_lecture_ownership(&db, "lec_a", "userB").unwrap_err();
-        assert!(err.contains("無權"));
+        assert!(err.message.contains("無權"));
     }
 
     #[test]
     fn cp75_34_delete_embeddings_by_lecture_verify_rejects_other_user() {
         let db = seed_cp75_34_fixture();
         let err = verify_lecture_ownership(&db, "lec_a", "userB").unwrap_err();
-        assert!(err.contains("無權"));
+        assert!(err.message.contains("無權"));
     }
 
     // ── A8-A9 pending_actions — empty-user-id defense-in-depth ──────────
@@ -4473,7 +5853,7 @@ mod tests {
     fn cp75_34_delete_chat_messages_by_session_verify_rejects_other_user() {
         let db = seed_cp75_34_fixture();
         let err = verify_chat_session_ownership(&db, "sess_a", "userB").unwrap_err();
-        assert!(err.contains("無權"));
+        assert!(err.message.contains("無權"));
     }
 
     #[test]
@@ -4488,7 +5868,7 @@ mod tests {
     fn cp75_34_delete_subtitles_by_lecture_verify_rejects_other_user() {
         let db = seed_cp75_34_fixture();
         let err = verify_lecture_ownership(&db, "lec_a", "userB").unwrap_err();
-        assert!(err.contains("無權"));
+        assert!(err.message.contains("無權"));
     }
 
     /// Sanity: verify_course_ownership (alive-only variant) still works
@@ -4626,6 +6006,7 @@ mod tests {
     // the message verbatim in a toast) keeps working.
 
     use crate::asr::parakeet_engine;
+    use crate::error::ErrorKind;
     use crate::parakeet_load_model;
 
     /// cp75.24 — variant-switch guard: refuses to swap models while a
@@ -4653,8 +6034,9 @@ mod tests {
         parakeet_engine::_test_force_session_active(false);
 
         let err = result.expect_err("should refuse variant switch during recording");
+        assert_eq!(err.kind, ErrorKind::Busy);
         assert!(
-            err.contains("錄音進行中"),
+            err.message.contains("錄音進行中"),
             "expected '錄音進行中' guard message, got: {err}"
         );
     }
@@ -0,0 +1,363 @@
+//! The app's own log file.
+//!
+//! Everything that goes through the `log` macros is written by
+//! `tauri-plugin-log` to `{app_data_dir}/logs/classnoteai.log`. When it
+//! passes [`MAX_FILE_BYTES`] the plugin renames it to
+//! `classnoteai_<UTC timestamp>.log` and starts a new one, keeping the
+//! last [`KEEP_ROTATED`] of those.
+//!
+//! Release builds log at `Info`: an updater crash report from a user
+//! only helps if the run leading up to it left a trail. Chatty HTTP /
+//! windowing crates stay at `Warn` so that trail isn't mostly noise.
+//!
+//! Levels can be changed at runtime ([`LogLevels`], stored under
+//! [`LEVELS_SETTING`]) — e.g. `debug` for just `asr` when support asks
+//! for a trail — so the plugin itself lets everything through and
+//! [`enabled`] does the filtering.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+use log::LevelFilter;
+use serde::{Deserialize, Serialize};
+use tauri::plugin::TauriPlugin;
+use tauri::Runtime;
+use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
+
+use crate::{paths, storage};
+
+/// File name (without `.log`) of the current log; rotated files add
+/// `_<timestamp>` to it.
+pub const LOG_FILE_STEM: &str = "classnoteai";
+/// Size at which the current log is rotated.
+pub const MAX_FILE_BYTES: u128 = 5 * 1024 * 1024;
+/// Rotated files kept besides the current one.
+pub const KEEP_ROTATED: usize = 5;
+/// Upper bound for [`recent_lines`], whatever the caller asks for.
+pub const MAX_RECENT_LINES: usize = 2000;
+
+/// Settings key holding [`LogLevels`] as JSON. Per install, so stored for
+/// the default user.
+pub const LEVELS_SETTING: &str = "log.levels";
+const SETTINGS_USER: &str = "default_user";
+/// Our own log targets start with this; module names in [`LogLevels`]
+/// are given without it (`asr::parakeet_engine`, not
+/// `classnoteai_lib::asr::parakeet_engine`).
+const CRATE_PREFIX: &str = "classnoteai_lib::";
+/// Crates kept at `Warn` unless [`LogLevels::modules`] says otherwise.
+const QUIET_CRATES: &[&str] = &["hyper", "hyper_util", "reqwest", "tao", "wry"];
+
+/// Runtime log verbosity: a default plus per-module overrides. Levels
+/// are `off`, `error`, `warn`, `info`, `debug` or `trace`; modules are
+/// paths inside the app (`asr`, `asr::parakeet_engine`) or other crates
+/// (`reqwest`), and the most specific match wins.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LogLevels {
+    pub default: String,
+    #[serde(default)]
+    pub modules: BTreeMap<String, String>,
+}
+
+impl Default for LogLevels {
+    fn default() -> Self {
+        Self {
+            default: "info".to_string(),
+            modules: BTreeMap::new(),
+        }
+    }
+}
+
+/// [`LogLevels`] parsed, as consulted on every log call.
+#[derive(Debug, Clone, PartialEq)]
+struct Filter {
+    default: LevelFilter,
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl Filter {
+    fn parse(levels: &LogLevels) -> Result<Self, String> {
+        let parse = |level: &str| {
+            level
+                .trim()
+                .parse::<LevelFilter>()
+                .map_err(|_| format!("無效的日誌等級: {}", level))
+        };
+        let mut modules = Vec::with_capacity(levels.modules.len());
+        for (module, level) in &levels.modules {
+            let module = module.trim().trim_matches(':');
+            if module.is_empty() {
+                return Err("模組名稱不可為空".to_string());
+            }
+            modules.push((module.to_string(), parse(level)?));
+        }
+        Ok(Self {
+            default: parse(&levels.default)?,
+            modules,
+        })
+    }
+
+    fn level_for(&self, target: &str) -> LevelFilter {
+        let target = target.strip_prefix(CRATE_PREFIX).unwrap_or(target);
+        let covers = |module: &str| {
+            target
+                .strip_prefix(module)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+        };
+        self.modules
+            .iter()
+            .filter(|(module, _)| covers(module))
+            .max_by_key(|(module, _)| module.len())
+            .map(|(_, level)| *level)
+            .or_else(|| {
+                QUIET_CRATES
+                    .iter()
+                    .any(|c| covers(c))
+                    .then_some(LevelFilter::Warn)
+            })
+            .unwrap_or(self.default)
+    }
+
+    /// Most verbose level anything can log at, for `log::set_max_level`.
+    fn max(&self) -> LevelFilter {
+        self.modules
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+fn filter() -> &'static RwLock<Filter> {
+    static FILTER: OnceLock<RwLock<Filter>> = OnceLock::new();
+    FILTER.get_or_init(|| {
+        RwLock::new(Filter::parse(&LogLevels::default()).expect("default log levels"))
+    })
+}
+
+/// Whether a record from `metadata.target()` passes the current levels.
+fn enabled(metadata: &log::Metadata) -> bool {
+    let filter = filter().read().unwrap_or_else(|p| p.into_inner());
+    metadata.level() <= filter.level_for(metadata.target())
+}
+
+/// Switch to `levels` for the rest of the process.
+pub fn apply(levels: &LogLevels) -> Result<(), String> {
+    let parsed = Filter::parse(levels)?;
+    // The `log` macros skip anything above this without calling the
+    // logger, so raise it only as far as some module needs.
+    log::set_max_level(parsed.max());
+    *filter().write().unwrap_or_else(|p| p.into_inner()) = parsed;
+    Ok(())
+}
+
+/// The saved levels; the defaults if none are saved.
+pub async fn levels() -> Result<LogLevels, String> {
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let Some(json) = db
+        .get_setting(LEVELS_SETTING, SETTINGS_USER)
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(LogLevels::default());
+    };
+    serde_json::from_str(&json).map_err(|e| format!("無法解析日誌等級設定: {}", e))
+}
+
+/// Apply the saved levels; called once the database is up. Falls back
+/// to the defaults if they can't be read.
+pub async fn load_levels() {
+    let levels = match levels().await {
+        Ok(levels) => levels,
+        Err(e) => {
+            log::warn!("[logging] {}; using default levels", e);
+            LogLevels::default()
+        }
+    };
+    if let Err(e) = apply(&levels) {
+        log::warn!("[logging] {}; using default levels", e);
+        let _ = apply(&LogLevels::default());
+    }
+}
+
+/// Validate, apply and save `levels`.
+pub async fn set_levels(levels: LogLevels) -> Result<LogLevels, String> {
+    apply(&levels)?;
+    let json = serde_json::to_string(&levels).map_err(|e| e.to_string())?;
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    db.save_setting(LEVELS_SETTING, &json, SETTINGS_USER)
+        .map_err(|e| e.to_string())?;
+    log::info!("[logging] levels set to {}", json);
+    Ok(levels)
+}
+
+/// `{app_data_dir}/logs/`, created if missing.
+pub fn log_dir() -> Result<PathBuf, String> {
+    let dir = paths::get_logs_dir()?;
+    paths::ensure_dir_exists(&dir)?;
+    Ok(dir)
+}
+
+/// The configured `tauri-plugin-log` instance. Falls back to the OS log
+/// directory if the app data directory can't be resolved, so logging
+/// never stops the app from starting.
+pub fn plugin<R: Runtime>() -> TauriPlugin<R> {
+    let file_target = match log_dir() {
+        Ok(path) => TargetKind::Folder {
+            path,
+            file_name: Some(LOG_FILE_STEM.into()),
+        },
+        Err(e) => {
+            eprintln!("[logging] {e}; logging to the OS log directory instead");
+            TargetKind::LogDir {
+                file_name: Some(LOG_FILE_STEM.into()),
+            }
+        }
+    };
+
+    tauri_plugin_log::Builder::new()
+        .targets([Target::new(file_target), Target::new(TargetKind::Stdout)])
+        // Everything reaches `enabled`; `apply` narrows the global max
+        // level again once the saved levels are loaded.
+        .level(LevelFilter::Trace)
+        .filter(enabled)
+        .max_file_size(MAX_FILE_BYTES)
+        .rotation_strategy(RotationStrategy::KeepSome(KEEP_ROTATED))
+        .build()
+}
+
+/// Log files in `dir`, oldest first: rotated files (their timestamp
+/// suffix sorts chronologically) followed by the current one.
+fn log_files(dir: &Path) -> Vec<PathBuf> {
+    let current = format!("{LOG_FILE_STEM}.log");
+    let rotated_prefix = format!("{LOG_FILE_STEM}_");
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut rotated: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(&rotated_prefix) && n.ends_with(".log"))
+        })
+        .collect();
+    rotated.sort();
+    let current = dir.join(current);
+    if current.is_file() {
+        rotated.push(current);
+    }
+    rotated
+}
+
+/// The last `lines` lines logged in `dir` (capped at
+/// [`MAX_RECENT_LINES`]), reaching back into rotated files when the
+/// current one is shorter. Empty when nothing has been logged yet.
+pub fn recent_lines(dir: &Path, lines: usize) -> Result<String, String> {
+    let wanted = lines.min(MAX_RECENT_LINES);
+    let mut collected: Vec<String> = Vec::new();
+    for path in log_files(dir).iter().rev() {
+        if collected.len() >= wanted {
+            break;
+        }
+        let bytes =
+            fs::read(path).map_err(|e| format!("Failed to read log {}: {}", path.display(), e))?;
+        let text = String::from_utf8_lossy(&bytes);
+        let file_lines: Vec<&str> = text.lines().collect();
+        let take = (wanted - collected.len()).min(file_lines.len());
+        let mut chunk: Vec<String> = file_lines[file_lines.len() - take..]
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        chunk.append(&mut collected);
+        collected = chunk;
+    }
+    Ok(collected.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn most_specific_module_level_wins() {
+        let levels = LogLevels {
+            default: "info".into(),
+            modules: BTreeMap::from([
+                ("asr".into(), "debug".into()),
+                ("asr::parakeet_engine".into(), "trace".into()),
+                ("reqwest".into(), "error".into()),
+            ]),
+        };
+        let filter = Filter::parse(&levels).unwrap();
+        assert_eq!(filter.level_for("classnoteai_lib::asr"), LevelFilter::Debug);
+        assert_eq!(
+            filter.level_for("classnoteai_lib::asr::parakeet_engine"),
+            LevelFilter::Trace
+        );
+        assert_eq!(filter.level_for("classnoteai_lib::asrx"), LevelFilter::Info);
+        assert_eq!(
+            filter.level_for("classnoteai_lib::storage"),
+            LevelFilter::Info
+        );
+        assert_eq!(filter.level_for("reqwest::connect"), LevelFilter::Error);
+        assert_eq!(filter.level_for("hyper_util::client"), LevelFilter::Warn);
+        assert_eq!(filter.max(), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn rejects_unknown_levels_and_empty_modules() {
+        let bad_level = LogLevels {
+            default: "loud".into(),
+            modules: BTreeMap::new(),
+        };
+        assert!(Filter::parse(&bad_level).is_err());
+        let empty_module = LogLevels {
+            default: "info".into(),
+            modules: BTreeMap::from([("::".into(), "debug".into())]),
+        };
+        assert!(Filter::parse(&empty_module).is_err());
+        assert_eq!(
+            Filter::parse(&LogLevels::default()).unwrap().max(),
+            LevelFilter::Info
+        );
+    }
+
+    #[test]
+    fn recent_lines_reach_into_rotated_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join("classnoteai_2026-10-14_08-00-00.log"),
+            "a1\na2\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("classnoteai_2026-10-15_08-00-00.log"),
+            "b1\nb2\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("classnoteai.log"), "c1\nc2\n").unwrap();
+        fs::write(dir.path().join("llama-server.log"), "x\n").unwrap();
+
+        assert_eq!(recent_lines(dir.path(), 1).unwrap(), "c2");
+        assert_eq!(recent_lines(dir.path(), 3).unwrap(), "b2\nc1\nc2");
+        assert_eq!(
+            recent_lines(dir.path(), 100).unwrap(),
+            "a1\na2\nb1\nb2\nc1\nc2"
+        );
+        assert_eq!(recent_lines(dir.path(), 0).unwrap(), "");
+
+        let empty = tempfile::tempdir().unwrap();
+        assert_eq!(recent_lines(empty.path(), 10).unwrap(), "");
+    }
+}
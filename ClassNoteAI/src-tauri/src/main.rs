@@ -4,28 +4,11 @@
 fn main() {
     // Route Rust panics through the `log` crate so they land in the
     // tauri-plugin-log file at `{APP_DATA}/logs/classnoteai.log`
-    // instead of dying with the process. Without this hook, native
+    // instead of dying with the process, and write a crash report the
+    // next launch can offer to the user. Without this hook, native
     // panics leave zero post-mortem trail, which is what made #72
     // so hard to diagnose before alpha.4.
-    std::panic::set_hook(Box::new(|info| {
-        let payload = info.payload();
-        let msg = payload
-            .downcast_ref::<&str>()
-            .copied()
-            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
-            .unwrap_or("<non-string panic payload>");
-        if let Some(loc) = info.location() {
-            log::error!(
-                "PANIC at {}:{}:{} — {}",
-                loc.file(),
-                loc.line(),
-                loc.column(),
-                msg
-            );
-        } else {
-            log::error!("PANIC (no location) — {}", msg);
-        }
-    }));
+    classnoteai_lib::crash::install_panic_hook();
 
     // Developer / agent-mode opt-in: if the user flipped the
     // experimental "Remote debug port" toggle in Settings, we honour
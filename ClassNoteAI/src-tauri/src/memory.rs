@@ -0,0 +1,287 @@
+//! Approximate memory held by each model, for `get_memory_usage`.
+//!
+//! In-process models (Nemotron ASR via ort, BGE embedding via Candle)
+//! can't be measured separately from the rest of the app, so they're
+//! estimated by their weight size — both load the weights whole, and
+//! on an 8 GB machine that is the number that decides whether a second
+//! model fits. The TranslateGemma sidecar is its own process, so its
+//! resident set is measured directly.
+//!
+//! Models that are on disk but not loaded are listed too (`loaded:
+//! false`), so the UI can check the headroom *before* loading one.
+
+use serde::Serialize;
+
+use crate::asr::parakeet_model;
+use crate::translation::{gemma_model, gemma_sidecar};
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModelMemory {
+    /// `asr:int8`, `embedding`, `translation:4b`, …
+    pub id: String,
+    /// `asr` | `embedding` | `translation`
+    pub kind: String,
+    pub loaded: bool,
+    pub approx_bytes: u64,
+    /// `true` when `approx_bytes` is a measured resident set rather than
+    /// the weight size.
+    pub measured: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MemoryUsage {
+    pub models: Vec<ModelMemory>,
+    /// Sum of `approx_bytes` over loaded models.
+    pub loaded_bytes: u64,
+    /// Resident set of this process (includes the in-process models).
+    pub process_rss_bytes: Option<u64>,
+    pub system_total_bytes: Option<u64>,
+    pub system_available_bytes: Option<u64>,
+}
+
+/// Snapshot of what's loaded and what could be.
+pub async fn usage() -> MemoryUsage {
+    let mut models = Vec::new();
+
+    let loaded_asr = crate::asr::parakeet_engine::loaded_variant();
+    for &variant in parakeet_model::Variant::all() {
+        let loaded = loaded_asr == Some(variant);
+        if loaded || parakeet_model::is_present(variant) {
+            models.push(ModelMemory {
+                id: format!("asr:{}", variant.label()),
+                kind: "asr".into(),
+                loaded,
+                approx_bytes: parakeet_model::total_size(variant),
+                measured: false,
+            });
+        }
+    }
+
+    let embedding_bytes = crate::EMBEDDING_SERVICE
+        .lock()
+        .await
+        .as_ref()
+        .map(|s| s.weights_bytes());
+    let on_disk = embedding_weights_on_disk();
+    if let Some(bytes) = embedding_bytes.or(on_disk) {
+        models.push(ModelMemory {
+            id: "embedding".into(),
+            kind: "embedding".into(),
+            loaded: embedding_bytes.is_some(),
+            approx_bytes: bytes,
+            measured: false,
+        });
+    }
+
+    if let Some(rss) = gemma_sidecar::child_pid().and_then(process_rss_bytes) {
+        models.push(ModelMemory {
+            id: "translation:sidecar".into(),
+            kind: "translation".into(),
+            loaded: true,
+            approx_bytes: rss,
+            measured: true,
+        });
+    }
+    for &variant in gemma_model::Variant::all() {
+        if gemma_model::is_present_for(variant) {
+            models.push(ModelMemory {
+                id: format!("translation:{}", variant.label().to_lowercase()),
+                kind: "translation".into(),
+                loaded: false,
+                approx_bytes: variant.expected_size(),
+                measured: false,
+            });
+        }
+    }
+
+    let (system_total_bytes, system_available_bytes) = system_memory();
+    MemoryUsage {
+        loaded_bytes: models
+            .iter()
+            .filter(|m| m.loaded)
+            .map(|m| m.approx_bytes)
+            .sum(),
+        models,
+        process_rss_bytes: process_rss_bytes(std::process::id()),
+        system_total_bytes,
+        system_available_bytes,
+    }
+}
+
+fn embedding_weights_on_disk() -> Option<u64> {
+    let config = crate::embedding::EmbeddingModelConfig::bge_small(
+        crate::paths::get_embedding_models_dir().ok()?,
+    );
+    std::fs::metadata(config.model_path()).ok().map(|m| m.len())
+}
+
+/// Resident set size of process `pid`.
+pub fn process_rss_bytes(pid: u32) -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        let status = std::fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+        parse_kib_field(&status, "VmRSS:")
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let output = crate::utils::command::no_window("ps")
+            .args(["-o", "rss=", "-p", &pid.to_string()])
+            .output()
+            .ok()?;
+        let kib: u64 = String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse()
+            .ok()?;
+        Some(kib * 1024)
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::Foundation::CloseHandle;
+        use windows_sys::Win32::System::ProcessStatus::{
+            K32GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS,
+        };
+        use windows_sys::Win32::System::Threading::{
+            OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION,
+        };
+        // SAFETY: the handle is checked for null and closed on every
+        // path; the counters struct is sized via `cb`.
+        unsafe {
+            let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+            if handle.is_null() {
+                return None;
+            }
+            let mut counters: PROCESS_MEMORY_COUNTERS = std::mem::zeroed();
+            counters.cb = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+            let ok = K32GetProcessMemoryInfo(handle, &mut counters, counters.cb);
+            CloseHandle(handle);
+            (ok != 0).then_some(counters.WorkingSetSize as u64)
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    {
+        let _ = pid;
+        None
+    }
+}
+
+/// `(total, available)` physical memory.
+pub fn system_memory() -> (Option<u64>, Option<u64>) {
+    #[cfg(target_os = "linux")]
+    {
+        let Ok(meminfo) = std::fs::read_to_string("/proc/meminfo") else {
+            return (None, None);
+        };
+        (
+            parse_kib_field(&meminfo, "MemTotal:"),
+            parse_kib_field(&meminfo, "MemAvailable:"),
+        )
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        use crate::utils::command::no_window;
+        let total = no_window("sysctl")
+            .args(["-n", "hw.memsize"])
+            .output()
+            .ok()
+            .and_then(|o| String::from_utf8_lossy(&o.stdout).trim().parse().ok());
+        let available = no_window("vm_stat")
+            .output()
+            .ok()
+            .and_then(|o| parse_vm_stat_available(&String::from_utf8_lossy(&o.stdout)));
+        (total, available)
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use windows_sys::Win32::System::SystemInformation::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+        // SAFETY: `dwLength` is set as the API requires.
+        unsafe {
+            let mut status: MEMORYSTATUSEX = std::mem::zeroed();
+            status.dwLength = std::mem::size_of::<MEMORYSTATUSEX>() as u32;
+            if GlobalMemoryStatusEx(&mut status) == 0 {
+                return (None, None);
+            }
+            (Some(status.ullTotalPhys), Some(status.ullAvailPhys))
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    {
+        (None, None)
+    }
+}
+
+/// `Field:   1234 kB` lines from `/proc/*/status` and `/proc/meminfo`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_kib_field(text: &str, field: &str) -> Option<u64> {
+    let line = text.lines().find(|l| l.starts_with(field))?;
+    let kib: u64 = line[field.len()..]
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()?;
+    Some(kib * 1024)
+}
+
+/// Memory macOS can hand out without swapping: free + inactive +
+/// speculative pages.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_vm_stat_available(text: &str) -> Option<u64> {
+    let page_size: u64 = text
+        .lines()
+        .next()?
+        .split("page size of ")
+        .nth(1)?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()?;
+    let pages = |name: &str| -> u64 {
+        text.lines()
+            .find(|l| l.starts_with(name))
+            .and_then(|l| l.rsplit(':').next())
+            .and_then(|v| v.trim().trim_end_matches('.').parse().ok())
+            .unwrap_or(0)
+    };
+    Some((pages("Pages free") + pages("Pages inactive") + pages("Pages speculative")) * page_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proc_fields_parse_as_bytes() {
+        let status = "Name:\tclassnoteai\nVmPeak:\t 2048 kB\nVmRSS:\t  1024 kB\n";
+        assert_eq!(parse_kib_field(status, "VmRSS:"), Some(1024 * 1024));
+        assert_eq!(parse_kib_field(status, "VmSwap:"), None);
+    }
+
+    #[test]
+    fn vm_stat_available_sums_reclaimable_pages() {
+        let out = "Mach Virtual Memory Statistics: (page size of 16384 bytes)\n\
+                   Pages free:                               10.\n\
+                   Pages active:                            500.\n\
+                   Pages inactive:                           20.\n\
+                   Pages speculative:                         2.\n";
+        assert_eq!(parse_vm_stat_available(out), Some(32 * 16384));
+        assert_eq!(parse_vm_stat_available(""), None);
+    }
+
+    #[test]
+    fn own_process_reports_memory() {
+        if cfg!(any(
+            target_os = "linux",
+            target_os = "macos",
+            target_os = "windows"
+        )) {
+            assert!(process_rss_bytes(std::process::id()).unwrap() > 0);
+            let (total, available) = system_memory();
+            assert!(total.unwrap() >= available.unwrap());
+        }
+    }
+}
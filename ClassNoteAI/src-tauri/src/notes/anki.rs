@@ -0,0 +1,555 @@
+//! A course's Q&A records → Anki deck.
+//!
+//! Every lecture note's `qa_records` becomes one Basic-style card:
+//! question on the front, answer plus the lecture and `MM:SS` it came
+//! from on the back, and — when the lecture has a recording — a clip of
+//! the subtitle segment the question points at, so the student can
+//! re-hear the explanation while reviewing.
+//!
+//! Two outputs:
+//! - `apkg`: a legacy (schema 11) Anki collection zipped with its media,
+//!   which every Anki version from 2.1 on imports. Note guids and the
+//!   deck / note type ids are derived from course and lecture ids, so
+//!   re-exporting the same course updates cards instead of duplicating
+//!   them.
+//! - `csv`: a folder with a tab-separated `cards.txt` (with the
+//!   `#separator` / `#deck` header lines Anki's text importer reads) and
+//!   the clips beside it, for when a `.apkg` isn't wanted. The clips
+//!   have to be copied into Anki's `collection.media` by hand.
+
+use super::{format_timestamp, QaRecord};
+use crate::recording::trim::{trim_audio_inner, trimmed_extension};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use serde_json::json;
+use sha1::{Digest, Sha1};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Clips never run longer than this, however long the subtitle segment.
+const MAX_CLIP_MS: u64 = 30_000;
+/// Length used when there is no following subtitle to end the clip on.
+const DEFAULT_CLIP_MS: u64 = 15_000;
+
+const CARD_CSS: &str = ".card { font-family: -apple-system, \"PingFang TC\", \"Microsoft JhengHei\", sans-serif; font-size: 20px; text-align: left; color: #1a1a1a; background: #fff; }\n.source { margin-top: 16px; font-size: 13px; color: #888; }";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnkiFormat {
+    Apkg,
+    Csv,
+}
+
+impl AnkiFormat {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "" | "apkg" => Ok(Self::Apkg),
+            "csv" => Ok(Self::Csv),
+            other => Err(format!("不支援的 Anki 匯出格式: {}", other)),
+        }
+    }
+}
+
+/// Everything the deck needs from one lecture.
+#[derive(Debug, Clone)]
+pub struct LectureCards {
+    pub lecture_id: String,
+    pub title: String,
+    pub qa: Vec<QaRecord>,
+    /// Start times of the lecture's subtitles, in seconds.
+    pub subtitle_starts: Vec<f64>,
+    /// Recording to cut clips from, if the lecture has one on disk.
+    pub audio: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AnkiExportReport {
+    pub path: String,
+    pub cards: usize,
+    pub clips: usize,
+}
+
+struct Card {
+    guid: String,
+    question: String,
+    answer: String,
+    source: String,
+    /// Media file name, relative to the deck's media.
+    audio: Option<String>,
+}
+
+/// `[start_ms, end_ms)` of the subtitle segment that contains `at` (in
+/// seconds): from the last subtitle starting at or before it up to the
+/// next subtitle's start, capped at [`MAX_CLIP_MS`].
+pub fn clip_range(subtitle_starts: &[f64], at: f64) -> (u64, u64) {
+    let at = if at.is_finite() { at.max(0.0) } else { 0.0 };
+    let mut starts: Vec<f64> = subtitle_starts
+        .iter()
+        .copied()
+        .filter(|s| s.is_finite())
+        .collect();
+    starts.sort_by(f64::total_cmp);
+
+    let idx = starts.iter().rposition(|&s| s <= at);
+    let start = idx.map_or(at, |i| starts[i]);
+    let next = match idx {
+        Some(i) => starts.get(i + 1).copied(),
+        None => starts.first().copied(),
+    };
+    let start_ms = (start * 1000.0) as u64;
+    let end_ms = match next {
+        Some(next) if next > start => ((next * 1000.0) as u64).min(start_ms + MAX_CLIP_MS),
+        _ => start_ms + DEFAULT_CLIP_MS,
+    };
+    (start_ms, end_ms)
+}
+
+fn digest(seed: &str) -> [u8; 20] {
+    Sha1::digest(seed.as_bytes()).into()
+}
+
+/// Positive id that stays the same for the same seed, so a re-export
+/// lands in the same deck / note type.
+fn stable_id(seed: &str) -> i64 {
+    // 2^40 and up keeps clear of Anki's built-in ids (1 = Default deck).
+    (1 << 40) + field_checksum(seed)
+}
+
+/// Anki's duplicate-check checksum: the first 8 hex digits of the SHA-1
+/// of the sort field.
+fn field_checksum(field: &str) -> i64 {
+    let d = digest(field);
+    u32::from_be_bytes([d[0], d[1], d[2], d[3]]) as i64
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\n', "<br>")
+}
+
+fn sound_tag(name: Option<&str>) -> String {
+    name.map(|n| format!("[sound:{}]", n)).unwrap_or_default()
+}
+
+/// Anki tags can't contain spaces.
+fn tag(text: &str) -> String {
+    let tag: String = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("_")
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .collect();
+    if tag.is_empty() {
+        "lecture".to_string()
+    } else {
+        tag
+    }
+}
+
+/// Cut clips into `media_dir` and build the cards. A clip that can't be
+/// cut (no ffmpeg for a compressed recording, range past the end) just
+/// leaves that card without audio.
+fn build_cards(lectures: &[LectureCards], media_dir: &Path) -> Vec<Card> {
+    let mut cards = Vec::new();
+    for lecture in lectures {
+        let short_id: String = lecture.lecture_id.chars().take(8).collect();
+        for (i, qa) in lecture.qa.iter().enumerate() {
+            let audio = lecture.audio.as_deref().and_then(|src| {
+                let (start_ms, end_ms) = clip_range(&lecture.subtitle_starts, qa.timestamp);
+                let name = format!(
+                    "classnote-{}-{}.{}",
+                    short_id,
+                    i + 1,
+                    trimmed_extension(src)
+                );
+                match trim_audio_inner(src, &media_dir.join(&name), start_ms, end_ms) {
+                    Ok(_) => Some(name),
+                    Err(e) => {
+                        eprintln!(
+                            "[anki] clip for {} #{} skipped: {}",
+                            lecture.lecture_id,
+                            i + 1,
+                            e
+                        );
+                        None
+                    }
+                }
+            });
+            cards.push(Card {
+                guid: hex(&digest(&format!("{}\u{1f}{}", lecture.lecture_id, qa.question))[..8]),
+                question: qa.question.trim().to_string(),
+                answer: qa.answer.trim().to_string(),
+                source: format!("{} · {}", lecture.title, format_timestamp(qa.timestamp)),
+                audio,
+            });
+        }
+    }
+    cards
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn note_fields(card: &Card) -> [String; 4] {
+    [
+        html_escape(&card.question),
+        html_escape(&card.answer),
+        html_escape(&card.source),
+        sound_tag(card.audio.as_deref()),
+    ]
+}
+
+/// Write a schema 11 collection holding `cards` in one deck.
+fn write_collection(
+    path: &Path,
+    course_id: &str,
+    deck_name: &str,
+    lecture_tags: &[String],
+    cards: &[Card],
+) -> Result<(), String> {
+    let conn = Connection::open(path).map_err(|e| format!("建立 Anki 資料庫失敗: {}", e))?;
+    let sql = |e: rusqlite::Error| format!("寫入 Anki 資料庫失敗: {}", e);
+    conn.execute_batch(
+        "CREATE TABLE col (id integer primary key, crt integer not null, mod integer not null, scm integer not null, ver integer not null, dty integer not null, usn integer not null, ls integer not null, conf text not null, models text not null, decks text not null, dconf text not null, tags text not null);
+         CREATE TABLE notes (id integer primary key, guid text not null, mid integer not null, mod integer not null, usn integer not null, tags text not null, flds text not null, sfld integer not null, csum integer not null, flags integer not null, data text not null);
+         CREATE TABLE cards (id integer primary key, nid integer not null, did integer not null, ord integer not null, mod integer not null, usn integer not null, type integer not null, queue integer not null, due integer not null, ivl integer not null, factor integer not null, reps integer not null, lapses integer not null, left integer not null, odue integer not null, odid integer not null, flags integer not null, data text not null);
+         CREATE TABLE revlog (id integer primary key, cid integer not null, usn integer not null, ease integer not null, ivl integer not null, lastIvl integer not null, factor integer not null, time integer not null, type integer not null);
+         CREATE TABLE graves (usn integer not null, oid integer not null, type integer not null);
+         CREATE INDEX ix_notes_usn on notes (usn);
+         CREATE INDEX ix_cards_usn on cards (usn);
+         CREATE INDEX ix_revlog_usn on revlog (usn);
+         CREATE INDEX ix_cards_nid on cards (nid);
+         CREATE INDEX ix_cards_sched on cards (did, queue, due);
+         CREATE INDEX ix_revlog_cid on revlog (cid);
+         CREATE INDEX ix_notes_csum on notes (csum);",
+    )
+    .map_err(sql)?;
+
+    let now = chrono::Utc::now();
+    let now_s = now.timestamp();
+    let now_ms = now.timestamp_millis();
+    let deck_id = stable_id(&format!("deck:{}", course_id));
+    let model_id = stable_id("model:classnote-qa");
+
+    let field = |name: &str, ord: u32| json!({"name": name, "ord": ord, "sticky": false, "rtl": false, "font": "Arial", "size": 20, "media": []});
+    let models = json!({
+        model_id.to_string(): {
+            "id": model_id,
+            "name": "ClassNoteAI Q&A",
+            "type": 0,
+            "mod": now_s,
+            "usn": -1,
+            "sortf": 0,
+            "did": deck_id,
+            "tmpls": [{
+                "name": "Card 1",
+                "ord": 0,
+                "qfmt": "{{Question}}",
+                "afmt": "{{FrontSide}}\n\n<hr id=answer>\n\n{{Answer}}\n<div class=\"source\">{{Source}}</div>\n{{Audio}}",
+                "did": null,
+                "bqfmt": "",
+                "bafmt": ""
+            }],
+            "flds": [field("Question", 0), field("Answer", 1), field("Source", 2), field("Audio", 3)],
+            "css": CARD_CSS,
+            "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage[utf8]{inputenc}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+            "latexPost": "\\end{document}",
+            "tags": [],
+            "vers": [],
+            "req": [[0, "any", [0]]]
+        }
+    });
+    let deck = |id: i64, name: &str| {
+        json!({
+            "id": id, "name": name, "desc": "", "mod": now_s, "usn": -1, "collapsed": false,
+            "newToday": [0, 0], "revToday": [0, 0], "lrnToday": [0, 0], "timeToday": [0, 0],
+            "dyn": 0, "extendNew": 10, "extendRev": 50, "conf": 1
+        })
+    };
+    let decks = json!({ "1": deck(1, "Default"), deck_id.to_string(): deck(deck_id, deck_name) });
+    let dconf = json!({
+        "1": {
+            "id": 1, "name": "Default", "mod": 0, "usn": 0, "maxTaken": 60, "autoplay": true,
+            "timer": 0, "replayq": true, "dyn": false,
+            "new": {"delays": [1, 10], "ints": [1, 4, 7], "initialFactor": 2500, "order": 1, "perDay": 20, "bury": true, "separate": true},
+            "rev": {"perDay": 200, "ease4": 1.3, "fuzz": 0.05, "minSpace": 1, "ivlFct": 1, "maxIvl": 36500, "bury": true, "hardFactor": 1.2},
+            "lapse": {"delays": [10], "mult": 0, "minInt": 1, "leechFails": 8, "leechAction": 0}
+        }
+    });
+    let conf = json!({
+        "nextPos": cards.len() + 1, "estTimes": true, "activeDecks": [deck_id], "sortType": "noteFld",
+        "timeLim": 0, "sortBackwards": false, "addToCur": true, "curDeck": deck_id,
+        "newSpread": 0, "dueCounts": true, "curModel": model_id.to_string(), "collapseTime": 1200
+    });
+    conn.execute(
+        "INSERT INTO col VALUES (1, ?1, ?2, ?2, 11, 0, 0, 0, ?3, ?4, ?5, ?6, '{}')",
+        params![
+            now_s,
+            now_ms,
+            conf.to_string(),
+            models.to_string(),
+            decks.to_string(),
+            dconf.to_string()
+        ],
+    )
+    .map_err(sql)?;
+
+    let mut insert_note = conn
+        .prepare("INSERT INTO notes VALUES (?1, ?2, ?3, ?4, -1, ?5, ?6, ?7, ?8, 0, '')")
+        .map_err(sql)?;
+    let mut insert_card = conn
+        .prepare("INSERT INTO cards VALUES (?1, ?2, ?3, 0, ?4, -1, 0, 0, ?5, 0, 0, 0, 0, 0, 0, 0, 0, '')")
+        .map_err(sql)?;
+    for (i, card) in cards.iter().enumerate() {
+        let id = now_ms + i as i64;
+        let tags = format!(
+            " ClassNoteAI {} ",
+            lecture_tags.get(i).map_or("", String::as_str)
+        );
+        insert_note
+            .execute(params![
+                id,
+                card.guid,
+                model_id,
+                now_s,
+                tags,
+                note_fields(card).join("\u{1f}"),
+                card.question,
+                field_checksum(&card.question)
+            ])
+            .map_err(sql)?;
+        insert_card
+            .execute(params![id, id, deck_id, now_s, i as i64 + 1])
+            .map_err(sql)?;
+    }
+    Ok(())
+}
+
+fn write_apkg(
+    dest: &Path,
+    collection: &Path,
+    media_dir: &Path,
+    cards: &[Card],
+) -> Result<(), String> {
+    let zip_err = |e: zip::result::ZipError| format!("寫入 apkg 失敗: {}", e);
+    let io_err = |e: std::io::Error| format!("寫入 apkg 失敗: {}", e);
+    let file = std::fs::File::create(dest).map_err(io_err)?;
+    let mut zip = ZipWriter::new(file);
+    // The collection and the manifest compress well; audio doesn't.
+    let deflated = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+
+    zip.start_file("collection.anki2", deflated)
+        .map_err(zip_err)?;
+    zip.write_all(&std::fs::read(collection).map_err(io_err)?)
+        .map_err(io_err)?;
+
+    // Media travel as `0`, `1`, … with a JSON manifest naming them.
+    let mut manifest = serde_json::Map::new();
+    for name in cards.iter().filter_map(|c| c.audio.as_ref()) {
+        let key = manifest.len().to_string();
+        zip.start_file(key.as_str(), stored).map_err(zip_err)?;
+        let mut clip = std::fs::File::open(media_dir.join(name)).map_err(io_err)?;
+        std::io::copy(&mut clip, &mut zip).map_err(io_err)?;
+        manifest.insert(key, json!(name));
+    }
+    zip.start_file("media", deflated).map_err(zip_err)?;
+    zip.write_all(serde_json::Value::Object(manifest).to_string().as_bytes())
+        .map_err(io_err)?;
+    zip.finish().map_err(zip_err)?;
+    Ok(())
+}
+
+/// Tab-separated text for Anki's "Import File": header lines, then one
+/// `question \t answer \t tags` row per card.
+fn cards_txt(deck_name: &str, lecture_tags: &[String], cards: &[Card]) -> String {
+    let mut out = format!(
+        "#separator:tab\n#html:true\n#notetype:Basic\n#deck:{}\n#tags column:3\n",
+        deck_name
+    );
+    for (i, card) in cards.iter().enumerate() {
+        let [question, answer, source, audio] = note_fields(card);
+        let back = format!("{}<div class=\"source\">{}</div>{}", answer, source, audio);
+        let tags = format!(
+            "ClassNoteAI {}",
+            lecture_tags.get(i).map_or("", String::as_str)
+        );
+        out.push_str(&format!(
+            "{}\t{}\t{}\n",
+            question.replace('\t', " "),
+            back.replace('\t', " "),
+            tags.trim()
+        ));
+    }
+    out
+}
+
+/// Export every lecture's Q&A as one deck named after the course.
+/// `dest` is the `.apkg` file, or for CSV the folder to create.
+pub fn export_course_deck(
+    dest: &Path,
+    format: AnkiFormat,
+    course_id: &str,
+    course_title: &str,
+    lectures: &[LectureCards],
+) -> Result<AnkiExportReport, String> {
+    let lecture_tags: Vec<String> = lectures
+        .iter()
+        .flat_map(|l| std::iter::repeat(tag(&l.title)).take(l.qa.len()))
+        .collect();
+    if lecture_tags.is_empty() {
+        return Err("此課程的筆記還沒有問答，無法產生卡片".to_string());
+    }
+    let deck_name = course_title.replace("::", ":");
+
+    match format {
+        AnkiFormat::Csv => {
+            std::fs::create_dir_all(dest).map_err(|e| format!("建立匯出資料夾失敗: {}", e))?;
+            let cards = build_cards(lectures, dest);
+            std::fs::write(
+                dest.join("cards.txt"),
+                cards_txt(&deck_name, &lecture_tags, &cards),
+            )
+            .map_err(|e| format!("寫入 cards.txt 失敗: {}", e))?;
+            Ok(AnkiExportReport {
+                path: dest.to_string_lossy().to_string(),
+                clips: cards.iter().filter(|c| c.audio.is_some()).count(),
+                cards: cards.len(),
+            })
+        }
+        AnkiFormat::Apkg => {
+            let work =
+                std::env::temp_dir().join(format!("classnote-anki-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(&work).map_err(|e| format!("建立暫存資料夾失敗: {}", e))?;
+            let result = (|| {
+                let cards = build_cards(lectures, &work);
+                let collection = work.join("collection.anki2");
+                write_collection(&collection, course_id, &deck_name, &lecture_tags, &cards)?;
+                write_apkg(dest, &collection, &work, &cards)?;
+                Ok(AnkiExportReport {
+                    path: dest.to_string_lossy().to_string(),
+                    clips: cards.iter().filter(|c| c.audio.is_some()).count(),
+                    cards: cards.len(),
+                })
+            })();
+            let _ = std::fs::remove_dir_all(&work);
+            if result.is_err() {
+                let _ = std::fs::remove_file(dest);
+            }
+            result
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clip_covers_the_subtitle_segment() {
+        let starts = [0.0, 4.0, 9.5, 80.0];
+        // Inside a segment: from its start to the next one.
+        assert_eq!(clip_range(&starts, 5.0), (4_000, 9_500));
+        assert_eq!(clip_range(&starts, 4.0), (4_000, 9_500));
+        // Long gap: capped.
+        assert_eq!(clip_range(&starts, 20.0), (9_500, 39_500));
+        // Past the last subtitle / no subtitles at all: default length.
+        assert_eq!(clip_range(&starts, 90.0), (80_000, 95_000));
+        assert_eq!(clip_range(&[], 12.0), (12_000, 27_000));
+        // Before the first subtitle: up to it.
+        assert_eq!(clip_range(&[3.0, 6.0], 1.0), (1_000, 3_000));
+    }
+
+    #[test]
+    fn ids_and_checksums_are_stable() {
+        assert_eq!(stable_id("deck:c1"), stable_id("deck:c1"));
+        assert_ne!(stable_id("deck:c1"), stable_id("deck:c2"));
+        assert!(stable_id("deck:c1") > 1 << 40);
+        assert_eq!(field_checksum("abc"), 0xa9993e36);
+        assert_eq!(tag(" Week 3  Sorting "), "Week_3_Sorting");
+        assert_eq!(AnkiFormat::parse("").unwrap(), AnkiFormat::Apkg);
+        assert!(AnkiFormat::parse("xlsx").is_err());
+    }
+
+    fn lecture(audio: Option<PathBuf>) -> LectureCards {
+        LectureCards {
+            lecture_id: "lecture-1".into(),
+            title: "Week 1".into(),
+            qa: vec![QaRecord {
+                question: "What is <O(n)>?".into(),
+                answer: "Linear\ntime.".into(),
+                timestamp: 1.5,
+            }],
+            subtitle_starts: vec![0.0, 1.0, 2.0],
+            audio,
+        }
+    }
+
+    #[test]
+    fn apkg_holds_collection_and_clip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let wav = dir.path().join("l.wav");
+        let pcm = vec![0u8; 16_000 * 2 * 3];
+        std::fs::write(&wav, crate::recording::wrap_pcm_as_wav(&pcm, 16_000, 1)).unwrap();
+        let dest = dir.path().join("deck.apkg");
+
+        let report = export_course_deck(
+            &dest,
+            AnkiFormat::Apkg,
+            "c1",
+            "演算法",
+            &[lecture(Some(wav))],
+        )
+        .unwrap();
+        assert_eq!((report.cards, report.clips), (1, 1));
+
+        let mut zip = zip::ZipArchive::new(std::fs::File::open(&dest).unwrap()).unwrap();
+        let mut manifest = String::new();
+        std::io::Read::read_to_string(&mut zip.by_name("media").unwrap(), &mut manifest).unwrap();
+        assert_eq!(manifest, r#"{"0":"classnote-lecture--1.wav"}"#);
+        // 1 s of 16 kHz mono = 32000 bytes of samples + 44 byte header.
+        assert_eq!(zip.by_name("0").unwrap().size(), 32_044);
+
+        let col = dir.path().join("col.anki2");
+        std::io::copy(
+            &mut zip.by_name("collection.anki2").unwrap(),
+            &mut std::fs::File::create(&col).unwrap(),
+        )
+        .unwrap();
+        let conn = Connection::open(&col).unwrap();
+        let (flds, tags): (String, String) = conn
+            .query_row("SELECT flds, tags FROM notes", [], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(
+            flds,
+            "What is &lt;O(n)&gt;?\u{1f}Linear<br>time.\u{1f}Week 1 · 00:01\u{1f}[sound:classnote-lecture--1.wav]"
+        );
+        assert_eq!(tags, " ClassNoteAI Week_1 ");
+        let cards: i64 = conn
+            .query_row("SELECT count(*) FROM cards", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(cards, 1);
+    }
+
+    #[test]
+    fn csv_folder_without_audio() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let dest = dir.path().join("deck");
+        let report =
+            export_course_deck(&dest, AnkiFormat::Csv, "c1", "A::B", &[lecture(None)]).unwrap();
+        assert_eq!((report.cards, report.clips), (1, 0));
+        let txt = std::fs::read_to_string(dest.join("cards.txt")).unwrap();
+        assert!(txt.contains("#deck:A:B\n"));
+        assert!(txt.ends_with(
+            "What is &lt;O(n)&gt;?\tLinear<br>time.<div class=\"source\">Week 1 · 00:01</div>\tClassNoteAI Week_1\n"
+        ));
+        assert!(export_course_deck(&dest, AnkiFormat::Csv, "c1", "A", &[]).is_err());
+    }
+}
@@ -0,0 +1,376 @@
+//! Course → folder of Markdown files, for Obsidian / Logseq vaults.
+//!
+//! ```text
+//! <dest>/<course>/
+//!   <course>.md                   index: front matter + [[wiki links]]
+//!   2026-10-15 Week 3.md          one per lecture with a note
+//!   assets/<lecture id>/
+//!     section-01.wav              audio of each note section
+//!     slide-003.png               pages listed in a section's page_range
+//! ```
+//!
+//! Media are embedded with plain relative `![](assets/…)` links, which
+//! both apps render inline (audio gets a player). Everything is best
+//! effort: a clip that can't be cut (no ffmpeg for a compressed
+//! recording) or slides that can't be rendered (no poppler / MuPDF)
+//! leave the section without them rather than failing the export.
+//! Re-exporting into the same folder overwrites the files in place.
+
+use super::{format_timestamp, note_markdown_with, note_sections, SectionSpan};
+use crate::documents::render::render_pdf_pages_inner;
+use crate::recording::trim::{trim_audio_inner, trimmed_extension};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Section clips stop here even when the section runs longer.
+const MAX_SNIPPET_MS: u64 = 5 * 60 * 1000;
+const SLIDE_DPI: u32 = 110;
+
+/// One lecture's share of the bundle.
+#[derive(Debug, Clone)]
+pub struct BundleLecture {
+    pub lecture_id: String,
+    pub title: String,
+    /// RFC 3339, as stored.
+    pub date: String,
+    pub duration_sec: i64,
+    /// The note's stored content (JSON, or Markdown for old notes).
+    pub note: String,
+    pub audio: Option<PathBuf>,
+    pub pdf: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleReport {
+    /// The course folder that was written.
+    pub path: String,
+    pub lectures: usize,
+    pub clips: usize,
+    pub slides: usize,
+}
+
+/// File name stem that's safe on every OS and doesn't break wiki links
+/// (`[[`, `]]`, `#`, `^`, `|` all mean something to Obsidian).
+pub fn safe_file_stem(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' | '#' | '^' | '[' | ']' => '-',
+            c if c.is_control() => ' ',
+            c => c,
+        })
+        .collect();
+    let cleaned = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+    let cleaned: String = cleaned.chars().take(80).collect();
+    let cleaned = cleaned.trim_matches(|c: char| c == '.' || c == ' ');
+    if cleaned.is_empty() {
+        "untitled".to_string()
+    } else {
+        cleaned.to_string()
+    }
+}
+
+fn yaml_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn front_matter(pairs: &[(&str, String)]) -> String {
+    let mut out = String::from("---\n");
+    for (key, value) in pairs {
+        out.push_str(&format!("{}: {}\n", key, value));
+    }
+    out.push_str("---\n\n");
+    out
+}
+
+/// `[start_ms, end_ms)` of each section: from its timestamp to the next
+/// section's (or the end of the lecture), capped at [`MAX_SNIPPET_MS`].
+/// `None` for sections with nothing to play.
+pub fn section_ranges(sections: &[SectionSpan], duration_sec: i64) -> Vec<Option<(u64, u64)>> {
+    let to_ms = |sec: f64| {
+        if sec.is_finite() {
+            (sec.max(0.0) * 1000.0) as u64
+        } else {
+            0
+        }
+    };
+    let end_of_lecture = (duration_sec > 0).then(|| duration_sec as u64 * 1000);
+    sections
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let start = to_ms(s.timestamp);
+            let end = sections
+                .get(i + 1)
+                .map(|next| to_ms(next.timestamp))
+                .or(end_of_lecture)
+                .unwrap_or(start + MAX_SNIPPET_MS)
+                .min(start + MAX_SNIPPET_MS);
+            (end > start).then_some((start, end))
+        })
+        .collect()
+}
+
+/// Render the slide pages the sections refer to and copy them into
+/// `assets`. Returns page → file name for those that made it.
+fn export_slides(
+    pdf: &Path,
+    sections: &[SectionSpan],
+    assets: &Path,
+    slide_cache: &Path,
+) -> BTreeMap<u32, String> {
+    let pages: Vec<u32> = sections
+        .iter()
+        .filter_map(|s| s.pages)
+        .flat_map(|r| r.min..=r.max)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    if pages.is_empty() {
+        return BTreeMap::new();
+    }
+    let rendered = match render_pdf_pages_inner(pdf, slide_cache, SLIDE_DPI, Some(&pages)) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("[bundle] slides for {} skipped: {}", pdf.display(), e);
+            return BTreeMap::new();
+        }
+    };
+    rendered
+        .pages
+        .into_iter()
+        .filter_map(|page| {
+            let name = format!("slide-{:03}.png", page.page);
+            if let Err(e) = std::fs::copy(&page.path, assets.join(&name)) {
+                eprintln!("[bundle] copy slide {}: {}", page.page, e);
+                return None;
+            }
+            Some((page.page, name))
+        })
+        .collect()
+}
+
+/// Write one lecture's Markdown file and its media. Returns
+/// `(clips, slides)` written.
+fn export_lecture(
+    root: &Path,
+    stem: &str,
+    course_title: &str,
+    lecture: &BundleLecture,
+    slide_cache: &Path,
+) -> Result<(usize, usize), String> {
+    let sections = note_sections(&lecture.note);
+    let asset_dir = format!("assets/{}", safe_file_stem(&lecture.lecture_id));
+    let assets = root.join(&asset_dir);
+    std::fs::create_dir_all(&assets).map_err(|e| format!("建立資料夾失敗: {}", e))?;
+
+    let mut extras = vec![String::new(); sections.len()];
+    let mut clips = 0;
+    if let Some(src) = lecture.audio.as_deref() {
+        let ext = trimmed_extension(src);
+        for (i, range) in section_ranges(&sections, lecture.duration_sec)
+            .into_iter()
+            .enumerate()
+        {
+            let Some((start_ms, end_ms)) = range else {
+                continue;
+            };
+            let name = format!("section-{:02}.{}", i + 1, ext);
+            match trim_audio_inner(src, &assets.join(&name), start_ms, end_ms) {
+                Ok(_) => {
+                    clips += 1;
+                    extras[i].push_str(&format!(
+                        "![▶ {}]({}/{})\n",
+                        format_timestamp(start_ms as f64 / 1000.0),
+                        asset_dir,
+                        name
+                    ));
+                }
+                Err(e) => eprintln!(
+                    "[bundle] clip {} of {} skipped: {}",
+                    i + 1,
+                    lecture.lecture_id,
+                    e
+                ),
+            }
+        }
+    }
+
+    let slides = match lecture.pdf.as_deref() {
+        Some(pdf) => export_slides(pdf, &sections, &assets, slide_cache),
+        None => BTreeMap::new(),
+    };
+    for (i, section) in sections.iter().enumerate() {
+        let Some(range) = section.pages else {
+            continue;
+        };
+        for (page, name) in slides.range(range.min..=range.max) {
+            extras[i].push_str(&format!("\n![投影片 {}]({}/{})\n", page, asset_dir, name));
+        }
+    }
+
+    let mut md = front_matter(&[
+        ("title", yaml_string(&lecture.title)),
+        ("course", yaml_string(course_title)),
+        (
+            "date",
+            lecture.date.get(..10).unwrap_or(&lecture.date).to_string(),
+        ),
+        (
+            "duration",
+            yaml_string(&format_timestamp(lecture.duration_sec as f64)),
+        ),
+        ("tags", "[classnoteai, lecture]".to_string()),
+    ]);
+    md.push_str(&format!("# {}\n\n", lecture.title));
+    md.push_str(&note_markdown_with(&lecture.note, &extras));
+    md.push('\n');
+    std::fs::write(root.join(format!("{}.md", stem)), md)
+        .map_err(|e| format!("寫入 {}.md 失敗: {}", stem, e))?;
+
+    // Nothing was exported for this lecture; don't leave an empty folder.
+    if clips == 0 && slides.is_empty() {
+        let _ = std::fs::remove_dir(&assets);
+    }
+    Ok((clips, slides.len()))
+}
+
+/// Write the course folder under `dest` and return what went into it.
+/// `slide_cache` is where rendered PDF pages are cached between runs.
+pub fn export_course_bundle(
+    dest: &Path,
+    course_title: &str,
+    lectures: &[BundleLecture],
+    slide_cache: &Path,
+) -> Result<BundleReport, String> {
+    if lectures.is_empty() {
+        return Err("此課程還沒有任何筆記可以匯出".to_string());
+    }
+    let course_stem = safe_file_stem(course_title);
+    let root = dest.join(&course_stem);
+    std::fs::create_dir_all(&root).map_err(|e| format!("建立資料夾失敗: {}", e))?;
+
+    let mut used: HashSet<String> = HashSet::from([course_stem.to_lowercase()]);
+    let mut stems = Vec::new();
+    let (mut clips, mut slides) = (0, 0);
+    for lecture in lectures {
+        let base = safe_file_stem(&format!(
+            "{} {}",
+            lecture.date.get(..10).unwrap_or(""),
+            lecture.title
+        ));
+        // Case-insensitive: two lectures must not collide on macOS / Windows.
+        let mut stem = base.clone();
+        let mut n = 2;
+        while !used.insert(stem.to_lowercase()) {
+            stem = format!("{} ({})", base, n);
+            n += 1;
+        }
+        let (c, s) = export_lecture(&root, &stem, course_title, lecture, slide_cache)?;
+        clips += c;
+        slides += s;
+        stems.push(stem);
+    }
+
+    let mut index = front_matter(&[
+        ("title", yaml_string(course_title)),
+        ("tags", "[classnoteai, course]".to_string()),
+    ]);
+    index.push_str(&format!("# {}\n\n", course_title));
+    for stem in &stems {
+        index.push_str(&format!("- [[{}]]\n", stem));
+    }
+    std::fs::write(root.join(format!("{}.md", course_stem)), index)
+        .map_err(|e| format!("寫入索引失敗: {}", e))?;
+
+    Ok(BundleReport {
+        path: root.to_string_lossy().to_string(),
+        lectures: stems.len(),
+        clips,
+        slides,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stems_are_safe_for_wiki_links() {
+        assert_eq!(
+            safe_file_stem("Week 3: Sorting [draft]"),
+            "Week 3- Sorting -draft-"
+        );
+        assert_eq!(safe_file_stem("  a/b\\c  "), "a-b-c");
+        assert_eq!(safe_file_stem("..."), "untitled");
+        assert_eq!(safe_file_stem(&"長".repeat(100)).chars().count(), 80);
+    }
+
+    #[test]
+    fn sections_clip_to_the_next_one() {
+        let span = |t: f64| SectionSpan {
+            timestamp: t,
+            pages: None,
+        };
+        let sections = [span(0.0), span(65.0), span(65.0), span(700.0)];
+        assert_eq!(
+            section_ranges(&sections, 800),
+            vec![
+                Some((0, 65_000)),
+                None,
+                Some((65_000, 365_000)),
+                Some((700_000, 800_000)),
+            ]
+        );
+        // Unknown duration: the last section gets the cap.
+        assert_eq!(
+            section_ranges(&[span(10.0)], 0),
+            vec![Some((10_000, 310_000))]
+        );
+    }
+
+    #[test]
+    fn writes_lecture_files_index_and_clips() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let wav = dir.path().join("l.wav");
+        let pcm = vec![0u8; 16_000 * 2 * 4];
+        std::fs::write(&wav, crate::recording::wrap_pcm_as_wav(&pcm, 16_000, 1)).unwrap();
+        let note = r#"{"summary": "S", "sections": [
+            {"title": "Intro", "content": "c", "timestamp": 0, "page_range": {"min": 1, "max": 2}},
+            {"title": "Body", "content": "d", "timestamp": 2}
+        ]}"#;
+        let lecture = |id: &str, audio: Option<PathBuf>| BundleLecture {
+            lecture_id: id.into(),
+            title: "Week 1".into(),
+            date: "2026-10-15T09:00:00+08:00".into(),
+            duration_sec: 4,
+            note: note.into(),
+            audio,
+            // Missing file: slides are skipped, the export still succeeds.
+            pdf: Some(dir.path().join("missing.pdf")),
+        };
+        let out = dir.path().join("vault");
+        let report = export_course_bundle(
+            &out,
+            "演算法",
+            &[lecture("l1", Some(wav)), lecture("l2", None)],
+            &dir.path().join("cache"),
+        )
+        .unwrap();
+        assert_eq!((report.lectures, report.clips, report.slides), (2, 2, 0));
+
+        let root = out.join("演算法");
+        let md = std::fs::read_to_string(root.join("2026-10-15 Week 1.md")).unwrap();
+        assert!(md.starts_with("---\ntitle: \"Week 1\"\ncourse: \"演算法\"\ndate: 2026-10-15\n"));
+        assert!(md.contains("### [00:00] Intro\n\n![▶ 00:00](assets/l1/section-01.wav)\n\n"));
+        assert!(md.contains("### [00:02] Body\n\n![▶ 00:02](assets/l1/section-02.wav)\n\n"));
+        assert!(root.join("assets/l1/section-02.wav").is_file());
+        // Same title and date: the second file gets a suffix, no empty asset dir.
+        assert!(root.join("2026-10-15 Week 1 (2).md").is_file());
+        assert!(!root.join("assets/l2").exists());
+
+        let index = std::fs::read_to_string(root.join("演算法.md")).unwrap();
+        assert!(index.ends_with("- [[2026-10-15 Week 1]]\n- [[2026-10-15 Week 1 (2)]]\n"));
+    }
+}
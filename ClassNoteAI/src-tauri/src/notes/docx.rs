@@ -0,0 +1,378 @@
+//! Note → `.docx` (WordprocessingML), so generated notes can be edited
+//! in Word or Pages.
+//!
+//! Markdown blocks map onto Word's built-in styles rather than direct
+//! formatting: headings become `Heading1`‥`Heading6` (so they show up
+//! in the navigation pane), lists use real numbering definitions (one
+//! bulleted, one decimal that restarts for each list), tables get the
+//! `TableGrid` style with a repeating header row. Code and `$$` math
+//! are plain paragraphs in `Code` / `Math` styles; math goes through
+//! the same LaTeX → Unicode pass as the PDF export.
+
+use super::markdown::{self, Block, Span, SpanKind};
+use super::pdf::math_to_text;
+use super::ExportMeta;
+use std::io::Write;
+use std::path::Path;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const W_NS: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            // Not allowed in XML 1.0 at all.
+            c if (c as u32) < 0x20 && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn run(text: &str, kind: SpanKind, force_bold: bool) -> String {
+    let mut props = String::new();
+    if force_bold || matches!(kind, SpanKind::Bold | SpanKind::BoldItalic) {
+        props.push_str("<w:b/>");
+    }
+    if matches!(
+        kind,
+        SpanKind::Italic | SpanKind::BoldItalic | SpanKind::Math
+    ) {
+        props.push_str("<w:i/>");
+    }
+    match kind {
+        SpanKind::Code => props.push_str(
+            r#"<w:rFonts w:ascii="Consolas" w:hAnsi="Consolas"/><w:shd w:val="clear" w:color="auto" w:fill="F2F2F2"/>"#,
+        ),
+        SpanKind::Math => {
+            props.push_str(r#"<w:rFonts w:ascii="Cambria Math" w:hAnsi="Cambria Math"/>"#)
+        }
+        _ => {}
+    }
+    let text = if kind == SpanKind::Math {
+        math_to_text(text)
+    } else {
+        text.to_string()
+    };
+    let props = if props.is_empty() {
+        String::new()
+    } else {
+        format!("<w:rPr>{}</w:rPr>", props)
+    };
+    format!(
+        r#"<w:r>{}<w:t xml:space="preserve">{}</w:t></w:r>"#,
+        props,
+        escape(&text)
+    )
+}
+
+fn runs(spans: &[Span], force_bold: bool) -> String {
+    spans
+        .iter()
+        .map(|s| run(&s.text, s.kind, force_bold))
+        .collect()
+}
+
+fn paragraph(style: Option<&str>, extra_props: &str, content: &str) -> String {
+    let style = style
+        .map(|s| format!(r#"<w:pStyle w:val="{}"/>"#, s))
+        .unwrap_or_default();
+    let props = format!("{}{}", style, extra_props);
+    if props.is_empty() {
+        format!("<w:p>{}</w:p>", content)
+    } else {
+        format!("<w:p><w:pPr>{}</w:pPr>{}</w:p>", props, content)
+    }
+}
+
+fn table(header: &[markdown::Cell], rows: &[Vec<markdown::Cell>]) -> String {
+    let columns = rows
+        .iter()
+        .map(Vec::len)
+        .chain([header.len()])
+        .max()
+        .unwrap_or(1)
+        .max(1);
+    // 9000 twips ≈ the text width of an A4 page with default margins.
+    let column_width = 9000 / columns;
+    let mut xml = String::from(
+        r#"<w:tbl><w:tblPr><w:tblStyle w:val="TableGrid"/><w:tblW w:w="5000" w:type="pct"/></w:tblPr><w:tblGrid>"#,
+    );
+    for _ in 0..columns {
+        xml.push_str(&format!(r#"<w:gridCol w:w="{}"/>"#, column_width));
+    }
+    xml.push_str("</w:tblGrid>");
+    let all_rows =
+        std::iter::once((header, true)).chain(rows.iter().map(|r| (r.as_slice(), false)));
+    for (cells, is_header) in all_rows {
+        xml.push_str("<w:tr>");
+        if is_header {
+            xml.push_str("<w:trPr><w:tblHeader/></w:trPr>");
+        }
+        for i in 0..columns {
+            let spans = cells.get(i).map(Vec::as_slice).unwrap_or_default();
+            xml.push_str(&format!(
+                r#"<w:tc><w:tcPr><w:tcW w:w="{}" w:type="dxa"/></w:tcPr>{}</w:tc>"#,
+                column_width,
+                paragraph(None, "", &runs(spans, is_header))
+            ));
+        }
+        xml.push_str("</w:tr>");
+    }
+    xml.push_str("</w:tbl>");
+    xml
+}
+
+/// Numbering instances: `1` is the shared bullet list, each ordered
+/// list gets its own instance from `2` up so its count restarts.
+#[derive(Default)]
+struct Numbering {
+    ordered_lists: u32,
+}
+
+impl Numbering {
+    const BULLET: u32 = 1;
+
+    fn next_ordered(&mut self) -> u32 {
+        self.ordered_lists += 1;
+        Self::BULLET + self.ordered_lists
+    }
+
+    fn xml(&self) -> String {
+        let levels = |ordered: bool| -> String {
+            (0..9)
+                .map(|lvl| {
+                    let indent = 720 + 360 * lvl;
+                    let (fmt, text) = if ordered {
+                        ("decimal".to_string(), format!("%{}.", lvl + 1))
+                    } else {
+                        ("bullet".to_string(), ["•", "◦", "▪"][lvl % 3].to_string())
+                    };
+                    format!(
+                        r#"<w:lvl w:ilvl="{lvl}"><w:start w:val="1"/><w:numFmt w:val="{fmt}"/><w:lvlText w:val="{text}"/><w:lvlJc w:val="left"/><w:pPr><w:ind w:left="{indent}" w:hanging="360"/></w:pPr></w:lvl>"#
+                    )
+                })
+                .collect()
+        };
+        let mut xml = format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><w:numbering xmlns:w="{W_NS}"><w:abstractNum w:abstractNumId="0"><w:multiLevelType w:val="hybridMultilevel"/>{}</w:abstractNum><w:abstractNum w:abstractNumId="1"><w:multiLevelType w:val="hybridMultilevel"/>{}</w:abstractNum>"#,
+            levels(false),
+            levels(true)
+        );
+        xml.push_str(&format!(
+            r#"<w:num w:numId="{}"><w:abstractNumId w:val="0"/></w:num>"#,
+            Self::BULLET
+        ));
+        for n in 1..=self.ordered_lists {
+            xml.push_str(&format!(
+                r#"<w:num w:numId="{}"><w:abstractNumId w:val="1"/><w:lvlOverride w:ilvl="0"><w:startOverride w:val="1"/></w:lvlOverride></w:num>"#,
+                Self::BULLET + n
+            ));
+        }
+        xml.push_str("</w:numbering>");
+        xml
+    }
+}
+
+/// `word/document.xml` and `word/numbering.xml` for a note.
+pub fn document_xml(meta: &ExportMeta, markdown: &str) -> (String, String) {
+    let mut body = String::new();
+    body.push_str(&paragraph(
+        Some("Title"),
+        "",
+        &run(&meta.title, SpanKind::Plain, false),
+    ));
+    let info = meta.summary_line();
+    if !info.is_empty() {
+        body.push_str(&paragraph(
+            Some("Subtitle"),
+            "",
+            &run(&info, SpanKind::Plain, false),
+        ));
+    }
+
+    let mut numbering = Numbering::default();
+    // numId of the ordered list currently being written, if any.
+    let mut open_ordered: Option<u32> = None;
+    for block in markdown::parse(markdown) {
+        // Anything but a list item — or a top-level bullet — ends the
+        // current ordered list, so the next one starts again at 1.
+        if !matches!(
+            block,
+            Block::ListItem {
+                ordered: Some(_),
+                ..
+            } | Block::ListItem { depth: 1.., .. }
+        ) {
+            open_ordered = None;
+        }
+        match block {
+            Block::Heading { level, spans } => {
+                let style = format!("Heading{}", level.clamp(1, 6));
+                body.push_str(&paragraph(Some(&style), "", &runs(&spans, false)));
+            }
+            Block::Paragraph(spans) => body.push_str(&paragraph(None, "", &runs(&spans, false))),
+            Block::ListItem {
+                ordered,
+                depth,
+                spans,
+            } => {
+                let num_id = match ordered {
+                    None => Numbering::BULLET,
+                    Some(_) => *open_ordered.get_or_insert_with(|| numbering.next_ordered()),
+                };
+                let props = format!(
+                    r#"<w:numPr><w:ilvl w:val="{}"/><w:numId w:val="{}"/></w:numPr>"#,
+                    depth.min(8),
+                    num_id
+                );
+                body.push_str(&paragraph(Some("ListParagraph"), &props, &runs(&spans, false)));
+            }
+            Block::Quote(spans) => {
+                body.push_str(&paragraph(Some("Quote"), "", &runs(&spans, false)))
+            }
+            Block::Code { text, .. } => {
+                let content = text
+                    .lines()
+                    .map(|line| run(line, SpanKind::Plain, false))
+                    .collect::<Vec<_>>()
+                    .join("<w:r><w:br/></w:r>");
+                body.push_str(&paragraph(Some("Code"), "", &content));
+            }
+            Block::Math(tex) => {
+                body.push_str(&paragraph(Some("Math"), "", &run(&tex, SpanKind::Math, false)))
+            }
+            Block::Table { header, rows } => {
+                body.push_str(&table(&header, &rows));
+                // Keeps adjacent tables from merging and gives the cursor
+                // somewhere to go after a trailing table.
+                body.push_str("<w:p/>");
+            }
+            Block::Rule => body.push_str(&paragraph(
+                None,
+                r#"<w:pBdr><w:bottom w:val="single" w:sz="6" w:space="1" w:color="999999"/></w:pBdr>"#,
+                "",
+            )),
+        }
+    }
+
+    let document = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><w:document xmlns:w="{W_NS}"><w:body>{body}<w:sectPr><w:pgSz w:w="11906" w:h="16838"/><w:pgMar w:top="1440" w:right="1440" w:bottom="1440" w:left="1440" w:header="708" w:footer="708" w:gutter="0"/></w:sectPr></w:body></w:document>"#
+    );
+    (document, numbering.xml())
+}
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/><Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/><Override PartName="/word/numbering.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.numbering+xml"/><Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/></Types>"#;
+
+const ROOT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/></Relationships>"#;
+
+const DOCUMENT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/numbering" Target="numbering.xml"/></Relationships>"#;
+
+fn heading_style(level: u8, half_points: u32) -> String {
+    format!(
+        r#"<w:style w:type="paragraph" w:styleId="Heading{level}"><w:name w:val="heading {level}"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:uiPriority w:val="9"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="240" w:after="80"/><w:outlineLvl w:val="{}"/></w:pPr><w:rPr><w:b/><w:sz w:val="{half_points}"/></w:rPr></w:style>"#,
+        level - 1
+    )
+}
+
+fn styles_xml() -> String {
+    let mut styles = format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><w:styles xmlns:w="{W_NS}"><w:docDefaults><w:rPrDefault><w:rPr><w:rFonts w:ascii="Calibri" w:hAnsi="Calibri" w:eastAsia="Microsoft JhengHei" w:cs="Calibri"/><w:sz w:val="22"/><w:lang w:val="en-US" w:eastAsia="zh-TW"/></w:rPr></w:rPrDefault><w:pPrDefault><w:pPr><w:spacing w:after="120" w:line="276" w:lineRule="auto"/></w:pPr></w:pPrDefault></w:docDefaults><w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:qFormat/></w:style><w:style w:type="paragraph" w:styleId="Title"><w:name w:val="Title"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:rPr><w:b/><w:sz w:val="40"/></w:rPr></w:style><w:style w:type="paragraph" w:styleId="Subtitle"><w:name w:val="Subtitle"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:pBdr><w:bottom w:val="single" w:sz="6" w:space="4" w:color="999999"/></w:pBdr><w:spacing w:after="240"/></w:pPr><w:rPr><w:color w:val="666666"/><w:sz w:val="19"/></w:rPr></w:style>"#
+    );
+    for (level, size) in [(1, 32), (2, 28), (3, 25), (4, 23), (5, 22), (6, 22)] {
+        styles.push_str(&heading_style(level, size));
+    }
+    styles.push_str(concat!(
+        r#"<w:style w:type="paragraph" w:styleId="ListParagraph"><w:name w:val="List Paragraph"/><w:basedOn w:val="Normal"/><w:qFormat/><w:pPr><w:spacing w:after="40"/><w:contextualSpacing/></w:pPr></w:style>"#,
+        r#"<w:style w:type="paragraph" w:styleId="Quote"><w:name w:val="Quote"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:pBdr><w:left w:val="single" w:sz="18" w:space="8" w:color="BFBFBF"/></w:pBdr><w:ind w:left="284"/></w:pPr><w:rPr><w:color w:val="595959"/></w:rPr></w:style>"#,
+        r#"<w:style w:type="paragraph" w:styleId="Code"><w:name w:val="Code"/><w:basedOn w:val="Normal"/><w:qFormat/><w:pPr><w:shd w:val="clear" w:color="auto" w:fill="F2F2F2"/><w:spacing w:after="120" w:line="240" w:lineRule="auto"/></w:pPr><w:rPr><w:rFonts w:ascii="Consolas" w:hAnsi="Consolas"/><w:sz w:val="19"/></w:rPr></w:style>"#,
+        r#"<w:style w:type="paragraph" w:styleId="Math"><w:name w:val="Math"/><w:basedOn w:val="Normal"/><w:qFormat/><w:pPr><w:jc w:val="center"/></w:pPr></w:style>"#,
+        r#"<w:style w:type="table" w:styleId="TableGrid"><w:name w:val="Table Grid"/><w:tblPr><w:tblBorders><w:top w:val="single" w:sz="4" w:space="0" w:color="A6A6A6"/><w:left w:val="single" w:sz="4" w:space="0" w:color="A6A6A6"/><w:bottom w:val="single" w:sz="4" w:space="0" w:color="A6A6A6"/><w:right w:val="single" w:sz="4" w:space="0" w:color="A6A6A6"/><w:insideH w:val="single" w:sz="4" w:space="0" w:color="A6A6A6"/><w:insideV w:val="single" w:sz="4" w:space="0" w:color="A6A6A6"/></w:tblBorders><w:tblCellMar><w:left w:w="108" w:type="dxa"/><w:right w:w="108" w:type="dxa"/></w:tblCellMar></w:tblPr></w:style>"#,
+        "</w:styles>",
+    ));
+    styles
+}
+
+fn core_xml(meta: &ExportMeta) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:dcterms="http://purl.org/dc/terms/" xmlns:xsi="http://www.w3.org/2001/XMLSchema-instance"><dc:title>{}</dc:title><dc:creator>ClassNoteAI</dc:creator><dcterms:created xsi:type="dcterms:W3CDTF">{}</dcterms:created></cp:coreProperties>"#,
+        escape(&meta.title),
+        chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ")
+    )
+}
+
+/// Write the note as a `.docx` package at `dest`.
+pub fn write_note_docx(dest: &Path, meta: &ExportMeta, markdown: &str) -> Result<(), String> {
+    let (document, numbering) = document_xml(meta, markdown);
+    let parts: [(&str, String); 7] = [
+        ("[Content_Types].xml", CONTENT_TYPES.to_string()),
+        ("_rels/.rels", ROOT_RELS.to_string()),
+        ("docProps/core.xml", core_xml(meta)),
+        ("word/_rels/document.xml.rels", DOCUMENT_RELS.to_string()),
+        ("word/document.xml", document),
+        ("word/styles.xml", styles_xml()),
+        ("word/numbering.xml", numbering),
+    ];
+
+    let file = std::fs::File::create(dest).map_err(|e| format!("建立 DOCX 失敗: {}", e))?;
+    let mut zip = ZipWriter::new(file);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, xml) in parts {
+        zip.start_file(name, options)
+            .map_err(|e| format!("寫入 DOCX 失敗 ({}): {}", name, e))?;
+        zip.write_all(xml.as_bytes())
+            .map_err(|e| format!("寫入 DOCX 失敗 ({}): {}", name, e))?;
+    }
+    zip.finish().map_err(|e| format!("寫入 DOCX 失敗: {}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta() -> ExportMeta {
+        ExportMeta {
+            title: "Week 3 <Sorting>".into(),
+            course: Some("演算法".into()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn blocks_map_to_word_styles() {
+        let md = "## 摘要\n\nA & B **bold**\n\n1. one\n2. two\n\n- dot\n  - nested\n\n1. again\n\n| a | b |\n|---|---|\n| 1 | 2 |\n";
+        let (doc, numbering) = document_xml(&meta(), md);
+        assert!(doc.contains(r#"<w:pStyle w:val="Title"/></w:pPr><w:r><w:t xml:space="preserve">Week 3 &lt;Sorting&gt;</w:t>"#));
+        assert!(doc.contains(r#"<w:pStyle w:val="Subtitle"/>"#));
+        assert!(doc.contains(r#"<w:pStyle w:val="Heading2"/>"#));
+        assert!(doc.contains("A &amp; B "));
+        assert!(doc.contains(r#"<w:rPr><w:b/></w:rPr><w:t xml:space="preserve">bold</w:t>"#));
+        // Two ordered lists, each restarting; bullets share numId 1.
+        assert_eq!(doc.matches(r#"<w:numId w:val="2"/>"#).count(), 2);
+        assert_eq!(doc.matches(r#"<w:numId w:val="3"/>"#).count(), 1);
+        assert!(doc.contains(r#"<w:ilvl w:val="1"/><w:numId w:val="1"/>"#));
+        assert!(numbering.contains(r#"<w:num w:numId="3"><w:abstractNumId w:val="1"/>"#));
+        assert!(doc.contains("<w:tblHeader/>"));
+    }
+
+    #[test]
+    fn writes_a_readable_package() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let dest = dir.path().join("note.docx");
+        write_note_docx(&dest, &meta(), "# Hi\n\n```\nx\ny\n```").unwrap();
+        let mut zip = zip::ZipArchive::new(std::fs::File::open(&dest).unwrap()).unwrap();
+        let mut doc = String::new();
+        std::io::Read::read_to_string(&mut zip.by_name("word/document.xml").unwrap(), &mut doc)
+            .unwrap();
+        assert!(doc.contains(r#"x</w:t></w:r><w:r><w:br/></w:r><w:r><w:t xml:space="preserve">y"#));
+        assert!(zip.by_name("word/styles.xml").is_ok());
+        assert!(zip.by_name("[Content_Types].xml").is_ok());
+    }
+}
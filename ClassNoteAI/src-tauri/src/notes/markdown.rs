@@ -0,0 +1,469 @@
+//! Just enough Markdown for generated notes: ATX headings, paragraphs,
+//! (nested) lists, block quotes, fenced code, `$$` math, pipe tables
+//! and rules; inline bold / italic / code / `$math$` / links.
+//!
+//! Not CommonMark. Anything it doesn't recognise comes through as
+//! paragraph text, which is the right failure mode for an exporter.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanKind {
+    Plain,
+    Bold,
+    Italic,
+    BoldItalic,
+    Code,
+    Math,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub kind: SpanKind,
+}
+
+impl Span {
+    pub fn plain(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            kind: SpanKind::Plain,
+        }
+    }
+}
+
+pub type Cell = Vec<Span>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    Heading {
+        level: u8,
+        spans: Vec<Span>,
+    },
+    Paragraph(Vec<Span>),
+    /// `ordered` is the item number for `1.` lists; `depth` 0 is top level.
+    ListItem {
+        ordered: Option<u32>,
+        depth: usize,
+        spans: Vec<Span>,
+    },
+    Quote(Vec<Span>),
+    Code {
+        lang: String,
+        text: String,
+    },
+    Math(String),
+    Table {
+        header: Vec<Cell>,
+        rows: Vec<Vec<Cell>>,
+    },
+    Rule,
+}
+
+/// Concatenated text of `spans`, styling dropped.
+pub fn plain_text(spans: &[Span]) -> String {
+    spans.iter().map(|s| s.text.as_str()).collect()
+}
+
+fn heading(line: &str) -> Option<(u8, &str)> {
+    let hashes = line.bytes().take_while(|b| *b == b'#').count();
+    if !(1..=6).contains(&hashes) {
+        return None;
+    }
+    let rest = &line[hashes..];
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    Some((hashes as u8, rest.trim().trim_end_matches('#').trim_end()))
+}
+
+fn is_rule(line: &str) -> bool {
+    let mut chars = line.chars().filter(|c| !c.is_whitespace());
+    let Some(first @ ('-' | '*' | '_')) = chars.next() else {
+        return false;
+    };
+    let rest: Vec<char> = chars.collect();
+    rest.len() >= 2 && rest.iter().all(|c| *c == first)
+}
+
+/// `(ordered number, depth, text)` for a list item line.
+fn list_item(line: &str) -> Option<(Option<u32>, usize, &str)> {
+    let indent = line.len() - line.trim_start().len();
+    let depth = indent / 2;
+    let rest = line.trim_start();
+    for marker in ["- ", "* ", "+ "] {
+        if let Some(text) = rest.strip_prefix(marker) {
+            return Some((None, depth, text.trim()));
+        }
+    }
+    let digits = rest.bytes().take_while(u8::is_ascii_digit).count();
+    if digits == 0 || digits > 9 {
+        return None;
+    }
+    let after = &rest[digits..];
+    let text = after
+        .strip_prefix(". ")
+        .or_else(|| after.strip_prefix(") "))?;
+    Some((rest[..digits].parse().ok(), depth, text.trim()))
+}
+
+fn table_cells(line: &str) -> Vec<&str> {
+    let inner = line.trim().trim_start_matches('|');
+    let inner = inner.strip_suffix('|').unwrap_or(inner);
+    inner.split('|').map(str::trim).collect()
+}
+
+fn is_table_separator(line: &str) -> bool {
+    let line = line.trim();
+    line.contains('-')
+        && line.contains('|')
+        && line
+            .chars()
+            .all(|c| matches!(c, '|' | '-' | ':' | ' ' | '\t'))
+}
+
+/// Join wrapped source lines; no space between CJK characters.
+fn join_line(buf: &mut String, line: &str) {
+    let line = line.trim();
+    if buf.is_empty() {
+        buf.push_str(line);
+        return;
+    }
+    let ascii_edge = buf.chars().last().is_some_and(|c| c.is_ascii())
+        || line.chars().next().is_some_and(|c| c.is_ascii());
+    if ascii_edge {
+        buf.push(' ');
+    }
+    buf.push_str(line);
+}
+
+pub fn parse(markdown: &str) -> Vec<Block> {
+    let lines: Vec<&str> = markdown.lines().collect();
+    let mut blocks = Vec::new();
+    let mut para = String::new();
+    let mut i = 0;
+
+    let flush = |para: &mut String, blocks: &mut Vec<Block>| {
+        if !para.is_empty() {
+            blocks.push(Block::Paragraph(parse_inline(para)));
+            para.clear();
+        }
+    };
+
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim();
+
+        if trimmed.is_empty() {
+            flush(&mut para, &mut blocks);
+            i += 1;
+            continue;
+        }
+
+        if let Some(fence) = ["```", "~~~"].into_iter().find(|f| trimmed.starts_with(f)) {
+            flush(&mut para, &mut blocks);
+            let lang = trimmed[fence.len()..].trim().to_string();
+            let mut body = Vec::new();
+            i += 1;
+            while i < lines.len() && !lines[i].trim().starts_with(fence) {
+                body.push(lines[i]);
+                i += 1;
+            }
+            blocks.push(Block::Code {
+                lang,
+                text: body.join("\n"),
+            });
+            i += 1;
+            continue;
+        }
+
+        if let Some(rest) = trimmed.strip_prefix("$$") {
+            flush(&mut para, &mut blocks);
+            if let Some(single) = rest.strip_suffix("$$") {
+                blocks.push(Block::Math(single.trim().to_string()));
+                i += 1;
+                continue;
+            }
+            let mut body = vec![rest];
+            i += 1;
+            while i < lines.len() {
+                let l = lines[i].trim();
+                i += 1;
+                if let Some(last) = l.strip_suffix("$$") {
+                    body.push(last);
+                    break;
+                }
+                body.push(l);
+            }
+            let text = body
+                .iter()
+                .map(|l| l.trim())
+                .filter(|l| !l.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            blocks.push(Block::Math(text));
+            continue;
+        }
+
+        if let Some((level, text)) = heading(trimmed) {
+            flush(&mut para, &mut blocks);
+            blocks.push(Block::Heading {
+                level,
+                spans: parse_inline(text),
+            });
+            i += 1;
+            continue;
+        }
+
+        if is_rule(trimmed) {
+            flush(&mut para, &mut blocks);
+            blocks.push(Block::Rule);
+            i += 1;
+            continue;
+        }
+
+        if trimmed.starts_with('|') && lines.get(i + 1).is_some_and(|l| is_table_separator(l)) {
+            flush(&mut para, &mut blocks);
+            let header = table_cells(trimmed).into_iter().map(parse_inline).collect();
+            let mut rows = Vec::new();
+            i += 2;
+            while i < lines.len() && lines[i].trim().starts_with('|') {
+                rows.push(
+                    table_cells(lines[i])
+                        .into_iter()
+                        .map(parse_inline)
+                        .collect(),
+                );
+                i += 1;
+            }
+            blocks.push(Block::Table { header, rows });
+            continue;
+        }
+
+        if let Some((ordered, depth, text)) = list_item(line) {
+            flush(&mut para, &mut blocks);
+            let mut item = text.to_string();
+            i += 1;
+            // Lazy continuation: indented lines that aren't new items.
+            while i < lines.len()
+                && lines[i].starts_with("  ")
+                && !lines[i].trim().is_empty()
+                && list_item(lines[i]).is_none()
+            {
+                join_line(&mut item, lines[i]);
+                i += 1;
+            }
+            blocks.push(Block::ListItem {
+                ordered,
+                depth,
+                spans: parse_inline(&item),
+            });
+            continue;
+        }
+
+        if trimmed.starts_with('>') {
+            flush(&mut para, &mut blocks);
+            let mut quote = String::new();
+            while i < lines.len() && lines[i].trim().starts_with('>') {
+                join_line(&mut quote, lines[i].trim().trim_start_matches('>'));
+                i += 1;
+            }
+            blocks.push(Block::Quote(parse_inline(&quote)));
+            continue;
+        }
+
+        join_line(&mut para, line);
+        i += 1;
+    }
+    flush(&mut para, &mut blocks);
+    blocks
+}
+
+fn kind_for(bold: bool, italic: bool) -> SpanKind {
+    match (bold, italic) {
+        (true, true) => SpanKind::BoldItalic,
+        (true, false) => SpanKind::Bold,
+        (false, true) => SpanKind::Italic,
+        (false, false) => SpanKind::Plain,
+    }
+}
+
+/// Index just past the closing `delim` starting the search at `from`.
+fn find_closing(chars: &[char], from: usize, delim: char) -> Option<usize> {
+    (from..chars.len()).find(|&j| chars[j] == delim && (j == 0 || chars[j - 1] != '\\'))
+}
+
+pub fn parse_inline(text: &str) -> Vec<Span> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut spans: Vec<Span> = Vec::new();
+    let mut buf = String::new();
+    let (mut bold, mut italic) = (false, false);
+    let mut i = 0;
+
+    let push = |spans: &mut Vec<Span>, buf: &mut String, kind: SpanKind| {
+        if buf.is_empty() {
+            return;
+        }
+        let text = std::mem::take(buf);
+        match spans.last_mut() {
+            Some(last) if last.kind == kind => last.text.push_str(&text),
+            _ => spans.push(Span { text, kind }),
+        }
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '\\' if next.is_some_and(|n| n.is_ascii_punctuation()) => {
+                buf.push(next.unwrap_or_default());
+                i += 2;
+            }
+            '`' => match find_closing(&chars, i + 1, '`') {
+                Some(end) => {
+                    push(&mut spans, &mut buf, kind_for(bold, italic));
+                    buf.extend(&chars[i + 1..end]);
+                    push(&mut spans, &mut buf, SpanKind::Code);
+                    i = end + 1;
+                }
+                None => {
+                    buf.push(c);
+                    i += 1;
+                }
+            },
+            // `$x$` but not `$5 and $10`: no space just inside the dollars.
+            '$' if next.is_some_and(|n| !n.is_whitespace() && n != '$') => {
+                match find_closing(&chars, i + 1, '$')
+                    .filter(|&end| !chars[end - 1].is_whitespace())
+                {
+                    Some(end) => {
+                        push(&mut spans, &mut buf, kind_for(bold, italic));
+                        buf.extend(&chars[i + 1..end]);
+                        push(&mut spans, &mut buf, SpanKind::Math);
+                        i = end + 1;
+                    }
+                    None => {
+                        buf.push(c);
+                        i += 1;
+                    }
+                }
+            }
+            '*' | '_' if next == Some(c) => {
+                push(&mut spans, &mut buf, kind_for(bold, italic));
+                bold = !bold;
+                i += 2;
+            }
+            '*' => {
+                push(&mut spans, &mut buf, kind_for(bold, italic));
+                italic = !italic;
+                i += 1;
+            }
+            // snake_case words keep their underscores.
+            '_' if (i == 0 || !chars[i - 1].is_alphanumeric()) || italic => {
+                push(&mut spans, &mut buf, kind_for(bold, italic));
+                italic = !italic;
+                i += 1;
+            }
+            '!' if next == Some('[') => i += 1,
+            '[' => {
+                // `[text](url)` → text.
+                let close = find_closing(&chars, i + 1, ']');
+                let link_end = close
+                    .filter(|&j| chars.get(j + 1) == Some(&'('))
+                    .and_then(|j| find_closing(&chars, j + 2, ')').map(|k| (j, k)));
+                match link_end {
+                    Some((j, k)) => {
+                        buf.extend(&chars[i + 1..j]);
+                        i = k + 1;
+                    }
+                    None => {
+                        buf.push(c);
+                        i += 1;
+                    }
+                }
+            }
+            _ => {
+                buf.push(c);
+                i += 1;
+            }
+        }
+    }
+    push(&mut spans, &mut buf, kind_for(bold, italic));
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn span(text: &str, kind: SpanKind) -> Span {
+        Span {
+            text: text.to_string(),
+            kind,
+        }
+    }
+
+    #[test]
+    fn inline_styles() {
+        assert_eq!(
+            parse_inline("a **b** *c* `d` $x^2$ [e](http://x) snake_case"),
+            vec![
+                span("a ", SpanKind::Plain),
+                span("b", SpanKind::Bold),
+                span(" ", SpanKind::Plain),
+                span("c", SpanKind::Italic),
+                span(" ", SpanKind::Plain),
+                span("d", SpanKind::Code),
+                span(" ", SpanKind::Plain),
+                span("x^2", SpanKind::Math),
+                span(" e snake_case", SpanKind::Plain),
+            ]
+        );
+        assert_eq!(
+            parse_inline("costs $5 and $10"),
+            vec![span("costs $5 and $10", SpanKind::Plain)]
+        );
+    }
+
+    #[test]
+    fn blocks() {
+        let md = "# Title\n\nline one\nline two\n\n- a\n  - b\n    more\n1. c\n\n```rust\nfn x() {}\n```\n\n$$\nE = mc^2\n$$\n\n| h1 | h2 |\n|----|:--:|\n| 1 | 2 |\n\n> quoted\n\n---\n";
+        let blocks = parse(md);
+        assert_eq!(blocks.len(), 10, "{blocks:#?}");
+        assert!(matches!(&blocks[0], Block::Heading { level: 1, .. }));
+        assert_eq!(
+            blocks[1],
+            Block::Paragraph(vec![Span::plain("line one line two")])
+        );
+        assert!(
+            matches!(&blocks[3], Block::ListItem { ordered: None, depth: 1, spans } if plain_text(spans) == "b more")
+        );
+        assert!(matches!(
+            &blocks[4],
+            Block::ListItem {
+                ordered: Some(1),
+                depth: 0,
+                ..
+            }
+        ));
+        assert_eq!(
+            blocks[5],
+            Block::Code {
+                lang: "rust".into(),
+                text: "fn x() {}".into()
+            }
+        );
+        assert_eq!(blocks[6], Block::Math("E = mc^2".into()));
+        assert!(
+            matches!(&blocks[7], Block::Table { header, rows } if header.len() == 2 && rows.len() == 1)
+        );
+        assert!(matches!(&blocks[8], Block::Quote(_)));
+        assert_eq!(blocks[9], Block::Rule);
+    }
+
+    #[test]
+    fn cjk_lines_join_without_spaces() {
+        assert_eq!(
+            parse("第一行\n第二行"),
+            vec![Block::Paragraph(vec![Span::plain("第一行第二行")])]
+        );
+    }
+}
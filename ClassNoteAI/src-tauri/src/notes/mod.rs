@@ -0,0 +1,258 @@
+//! Lecture notes out of the app.
+//!
+//! A stored note is JSON (`summary`, `sections`, `qa_records`,
+//! `action_items` — see `Note` in `src/types/index.ts`). Exporters
+//! don't read that directly: [`note_markdown`] flattens it to the same
+//! Markdown the frontend shows, and [`markdown::parse`] turns Markdown
+//! into blocks each output format lays out its own way.
+//!
+//! - `pdf` writes a paginated PDF with a lecture header.
+//! - `docx` writes an editable Word document using Word's own styles.
+//! - `anki` turns a course's Q&A records into an Anki deck with audio.
+//! - `bundle` writes a course as a folder of Markdown with linked media.
+
+pub mod anki;
+pub mod bundle;
+pub mod docx;
+pub mod markdown;
+pub mod pdf;
+
+use serde::Deserialize;
+
+/// Lecture details shown above the note.
+#[derive(Debug, Clone, Default)]
+pub struct ExportMeta {
+    pub title: String,
+    pub course: Option<String>,
+    pub date: Option<String>,
+    pub duration_sec: Option<i64>,
+}
+
+impl ExportMeta {
+    /// `課程：… · 日期：… · 時長：…`, skipping what's missing.
+    pub fn summary_line(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(course) = self.course.as_deref().filter(|c| !c.is_empty()) {
+            parts.push(format!("課程：{}", course));
+        }
+        if let Some(date) = self.date.as_deref().filter(|d| !d.is_empty()) {
+            // Stored as RFC 3339; the day is enough here.
+            parts.push(format!("日期：{}", date.get(..10).unwrap_or(date)));
+        }
+        if let Some(sec) = self.duration_sec.filter(|s| *s > 0) {
+            parts.push(format!("時長：{}", format_timestamp(sec as f64)));
+        }
+        parts.join(" · ")
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct NoteContent {
+    summary: Option<String>,
+    sections: Vec<NoteSection>,
+    qa_records: Vec<QaRecord>,
+    action_items: Vec<ActionItem>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct NoteSection {
+    title: String,
+    content: String,
+    timestamp: f64,
+    bullets: Vec<String>,
+    page_range: Option<PageRange>,
+}
+
+/// Slide / PDF pages a section covers, 1-based and inclusive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub struct PageRange {
+    pub min: u32,
+    pub max: u32,
+}
+
+/// Where a note section sits in the lecture.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SectionSpan {
+    pub timestamp: f64,
+    pub pages: Option<PageRange>,
+}
+
+/// One generated study question (`QARecord` on the frontend).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct QaRecord {
+    pub question: String,
+    pub answer: String,
+    /// Seconds into the lecture where the concept is discussed.
+    pub timestamp: f64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ActionItem {
+    description: String,
+    due_date: Option<String>,
+}
+
+/// `MM:SS`, like `formatRelativeTime` on the frontend.
+pub fn format_timestamp(sec: f64) -> String {
+    let total = if sec.is_finite() {
+        sec.max(0.0) as u64
+    } else {
+        0
+    };
+    format!("{:02}:{:02}", total / 60, total % 60)
+}
+
+/// The note's Q&A records with both sides filled in. Content that isn't
+/// note JSON has none.
+pub fn note_qa_records(content: &str) -> Vec<QaRecord> {
+    serde_json::from_str::<NoteContent>(content)
+        .map(|note| note.qa_records)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|qa| !qa.question.trim().is_empty() && !qa.answer.trim().is_empty())
+        .collect()
+}
+
+/// The note's sections in order, for exporters that attach media to
+/// them (see [`note_markdown_with`]).
+pub fn note_sections(content: &str) -> Vec<SectionSpan> {
+    serde_json::from_str::<NoteContent>(content)
+        .map(|note| note.sections)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|s| SectionSpan {
+            timestamp: s.timestamp,
+            pages: s.page_range.filter(|r| r.min >= 1 && r.min <= r.max),
+        })
+        .collect()
+}
+
+/// Markdown for a note's content JSON. Blocks that are missing or empty
+/// are left out; content that isn't JSON is taken as Markdown already.
+pub fn note_markdown(content: &str) -> String {
+    note_markdown_with(content, &[])
+}
+
+/// [`note_markdown`] with `section_extras[i]` (already Markdown) placed
+/// right under the heading of section `i`.
+pub fn note_markdown_with(content: &str, section_extras: &[String]) -> String {
+    let note = match serde_json::from_str::<NoteContent>(content) {
+        Ok(note) => note,
+        Err(_) => return content.to_string(),
+    };
+    let mut out = String::new();
+
+    if let Some(summary) = note.summary.as_deref().map(str::trim) {
+        if !summary.is_empty() {
+            out.push_str("## 摘要\n\n");
+            out.push_str(summary);
+            out.push_str("\n\n");
+        }
+    }
+
+    if !note.sections.is_empty() {
+        out.push_str("## 章節\n\n");
+        for (i, section) in note.sections.iter().enumerate() {
+            out.push_str(&format!(
+                "### [{}] {}\n\n",
+                format_timestamp(section.timestamp),
+                section.title.trim()
+            ));
+            if let Some(extra) = section_extras.get(i).filter(|e| !e.trim().is_empty()) {
+                out.push_str(extra.trim_end());
+                out.push_str("\n\n");
+            }
+            for bullet in section.bullets.iter().filter(|b| !b.trim().is_empty()) {
+                out.push_str(&format!("- {}\n", bullet.trim()));
+            }
+            if !section.bullets.is_empty() {
+                out.push('\n');
+            }
+            if !section.content.trim().is_empty() {
+                out.push_str(section.content.trim());
+                out.push_str("\n\n");
+            }
+        }
+    }
+
+    if !note.action_items.is_empty() {
+        out.push_str("## 待辦事項\n\n");
+        for item in &note.action_items {
+            match item.due_date.as_deref().filter(|d| !d.is_empty()) {
+                Some(due) => out.push_str(&format!("- {}（截止：{}）\n", item.description, due)),
+                None => out.push_str(&format!("- {}\n", item.description)),
+            }
+        }
+        out.push('\n');
+    }
+
+    if !note.qa_records.is_empty() {
+        out.push_str("## 問答\n\n");
+        for qa in &note.qa_records {
+            out.push_str(&format!(
+                "**Q：{}**\n\n{}\n\n",
+                qa.question.trim(),
+                qa.answer.trim()
+            ));
+        }
+    }
+
+    out.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn note_json_flattens_to_markdown() {
+        let content = r#"{
+            "summary": "Sorting recap.",
+            "sections": [
+                {"title": "Merge sort", "content": "Divide and **conquer**.", "timestamp": 65, "bullets": ["O(n log n)"]}
+            ],
+            "qa_records": [{"question": "Stable?", "answer": "Yes.", "timestamp": 0}],
+            "action_items": [{"description": "HW 3", "due_date": "2026-10-20", "mentioned_at_timestamp": 1}]
+        }"#;
+        let md = note_markdown(content);
+        assert!(md.starts_with("## 摘要\n\nSorting recap."));
+        assert!(md.contains("### [01:05] Merge sort\n\n- O(n log n)\n\nDivide and **conquer**."));
+        assert!(md.contains("- HW 3（截止：2026-10-20）"));
+        assert!(md.ends_with("**Q：Stable?**\n\nYes."));
+        assert_eq!(note_markdown("# Plain"), "# Plain");
+
+        let qa = note_qa_records(content);
+        assert_eq!(qa.len(), 1);
+        assert_eq!((qa[0].question.as_str(), qa[0].timestamp), ("Stable?", 0.0));
+        assert!(note_qa_records("# Plain").is_empty());
+
+        assert_eq!(
+            note_sections(content),
+            vec![SectionSpan {
+                timestamp: 65.0,
+                pages: None
+            }]
+        );
+        let with = note_markdown_with(content, &["![](a.wav)".to_string()]);
+        assert!(with.contains("### [01:05] Merge sort\n\n![](a.wav)\n\n- O(n log n)"));
+    }
+
+    #[test]
+    fn meta_line_skips_missing_fields() {
+        let meta = ExportMeta {
+            title: "L1".into(),
+            course: Some("演算法".into()),
+            date: Some("2026-10-15T09:00:00+08:00".into()),
+            duration_sec: Some(3725),
+        };
+        assert_eq!(
+            meta.summary_line(),
+            "課程：演算法 · 日期：2026-10-15 · 時長：62:05"
+        );
+        assert_eq!(ExportMeta::default().summary_line(), "");
+    }
+}
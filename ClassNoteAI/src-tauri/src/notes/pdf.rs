@@ -0,0 +1,1078 @@
+//! Note → paginated PDF, written directly (no PDF or font crate).
+//!
+//! Latin text uses the standard Helvetica / Courier fonts every viewer
+//! ships, so nothing is embedded. Anything WinAnsi can't encode (CJK,
+//! Greek, math symbols) switches to Adobe's predefined Traditional
+//! Chinese font `MSung-Light` through the `UniCNS-UCS2-H` CMap; viewers
+//! substitute a local Ming/Song face for it, the same way Acrobat
+//! handles non-embedded CJK PDFs.
+//!
+//! Layout is a single column: a lecture header on page 1, Markdown
+//! blocks flowed top to bottom with greedy line breaking (between words
+//! for Latin, between any two characters for CJK), and a `title · n / N`
+//! footer on every page. Math isn't typeset: LaTeX is rewritten to
+//! Unicode text by [`math_to_text`] (`\frac{a}{b}` → `a/b`, `\alpha` →
+//! `α`), which reads fine for the formulas that turn up in lectures.
+
+use super::markdown::{self, Block, Span, SpanKind};
+use super::ExportMeta;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdfTemplate {
+    /// A4, 11 pt body.
+    Standard,
+    /// A4, 9 pt body and narrow margins, for printing.
+    Compact,
+    /// US Letter, 11 pt body.
+    Letter,
+}
+
+impl PdfTemplate {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "" | "standard" | "default" | "a4" => Ok(Self::Standard),
+            "compact" | "print" => Ok(Self::Compact),
+            "letter" => Ok(Self::Letter),
+            other => Err(format!(
+                "不支援的 PDF 範本: {} (standard / compact / letter)",
+                other
+            )),
+        }
+    }
+
+    fn page(self) -> Page {
+        let (width, height) = match self {
+            Self::Letter => (612.0, 792.0),
+            _ => (595.28, 841.89),
+        };
+        let (margin, body, leading) = match self {
+            Self::Compact => (40.0, 9.0, 1.35),
+            _ => (56.0, 11.0, 1.5),
+        };
+        Page {
+            width,
+            height,
+            margin,
+            body,
+            leading,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Page {
+    width: f32,
+    height: f32,
+    margin: f32,
+    body: f32,
+    /// Line height as a multiple of the font size.
+    leading: f32,
+}
+
+/// Room kept above the bottom margin for the footer.
+const FOOTER_SPACE: f32 = 14.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Font {
+    Regular,
+    Bold,
+    Italic,
+    BoldItalic,
+    Mono,
+    Cjk,
+    /// `Cjk` drawn with a stroked outline; MSung has no bold face.
+    CjkBold,
+}
+
+impl Font {
+    fn resource(self) -> &'static str {
+        match self {
+            Self::Regular => "F1",
+            Self::Bold => "F2",
+            Self::Italic => "F3",
+            Self::BoldItalic => "F4",
+            Self::Mono => "F5",
+            Self::Cjk | Self::CjkBold => "F6",
+        }
+    }
+
+    fn for_span(kind: SpanKind) -> Self {
+        match kind {
+            SpanKind::Plain => Self::Regular,
+            SpanKind::Bold => Self::Bold,
+            SpanKind::Italic | SpanKind::Math => Self::Italic,
+            SpanKind::BoldItalic => Self::BoldItalic,
+            SpanKind::Code => Self::Mono,
+        }
+    }
+
+    fn bold(self) -> Self {
+        match self {
+            Self::Regular => Self::Bold,
+            Self::Italic => Self::BoldItalic,
+            other => other,
+        }
+    }
+
+    /// The font `c` is actually drawn in when styled as `self`.
+    fn resolve(self, c: char) -> Self {
+        if win_ansi(c).is_some() {
+            return self;
+        }
+        match self {
+            Self::Bold | Self::BoldItalic => Self::CjkBold,
+            _ => Self::Cjk,
+        }
+    }
+}
+
+/// Helvetica advance widths for ASCII 32..=126, in 1/1000 em.
+const HELVETICA: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, // ' '../
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, // 0..9
+    278, 278, 584, 584, 584, 556, 1015, // :..@
+    667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667,
+    611, 722, 667, 944, 667, 667, 611, // A..Z
+    278, 278, 278, 469, 556, 333, // [..`
+    556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556, 556, 556, 333, 500,
+    278, 556, 500, 722, 500, 500, 500, // a..z
+    334, 260, 334, 584, // {..~
+];
+
+/// WinAnsiEncoding byte for `c`, if the standard fonts can draw it.
+fn win_ansi(c: char) -> Option<u8> {
+    Some(match c {
+        ' '..='~' => c as u8,
+        '\u{a0}'..='\u{ff}' => c as u32 as u8,
+        '€' => 0x80,
+        '‚' => 0x82,
+        '„' => 0x84,
+        '…' => 0x85,
+        '‘' => 0x91,
+        '’' => 0x92,
+        '“' => 0x93,
+        '”' => 0x94,
+        '•' => 0x95,
+        '–' => 0x96,
+        '—' => 0x97,
+        '™' => 0x99,
+        _ => return None,
+    })
+}
+
+/// Advance width of `c` in `font` at `size` points.
+fn char_width(font: Font, c: char, size: f32) -> f32 {
+    let units = match font {
+        Font::Mono => 600.0,
+        Font::Cjk | Font::CjkBold => 1000.0,
+        _ => {
+            let base = match c {
+                ' '..='~' => HELVETICA[c as usize - 32] as f32,
+                '‘' | '’' | '‚' => 222.0,
+                '“' | '”' | '„' => 333.0,
+                '•' => 350.0,
+                '·' => 278.0,
+                '…' | '—' => 1000.0,
+                '¹' | '²' | '³' => 333.0,
+                _ => 556.0,
+            };
+            // No bold metrics table; Helvetica-Bold runs ~6% wider.
+            if matches!(font, Font::Bold | Font::BoldItalic) {
+                base * 1.06
+            } else {
+                base
+            }
+        }
+    };
+    units * size / 1000.0
+}
+
+#[derive(Debug, Clone)]
+struct Piece {
+    font: Font,
+    text: String,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Line {
+    pieces: Vec<Piece>,
+    width: f32,
+}
+
+impl Line {
+    fn from_chars(chars: &[(Font, char)], size: f32) -> Self {
+        let mut line = Line::default();
+        for &(font, c) in chars {
+            line.width += char_width(font, c, size);
+            match line.pieces.last_mut() {
+                Some(p) if p.font == font => p.text.push(c),
+                _ => line.pieces.push(Piece {
+                    font,
+                    text: c.to_string(),
+                }),
+            }
+        }
+        line
+    }
+}
+
+/// Styled characters, with the per-character font already resolved.
+/// Characters outside the BMP can't go through UCS-2 and become `?`.
+fn styled_chars(runs: &[(Font, String)]) -> Vec<(Font, char)> {
+    runs.iter()
+        .flat_map(|(font, text)| {
+            text.chars().map(move |c| {
+                let c = match c {
+                    '\t' => ' ',
+                    c if (c as u32) > 0xffff || c.is_control() => '?',
+                    c => c,
+                };
+                (font.resolve(c), c)
+            })
+        })
+        .collect()
+}
+
+/// Break `chars` wherever the line is full, ignoring word boundaries.
+fn hard_wrap(chars: &[(Font, char)], size: f32, max_width: f32) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut width = 0.0;
+    for (i, &(font, c)) in chars.iter().enumerate() {
+        let w = char_width(font, c, size);
+        if i > start && width + w > max_width {
+            lines.push(Line::from_chars(&chars[start..i], size));
+            start = i;
+            width = 0.0;
+        }
+        width += w;
+    }
+    lines.push(Line::from_chars(&chars[start..], size));
+    lines
+}
+
+/// Greedy line breaking: between words, and around every CJK character.
+fn wrap(runs: &[(Font, String)], size: f32, max_width: f32) -> Vec<Line> {
+    let chars = styled_chars(runs);
+    // Split into unbreakable words; spaces are kept as their own atoms.
+    let mut atoms: Vec<&[(Font, char)]> = Vec::new();
+    let mut start = 0;
+    for (i, &(font, c)) in chars.iter().enumerate() {
+        let cjk = matches!(font, Font::Cjk | Font::CjkBold);
+        if c == ' ' || cjk {
+            if start < i {
+                atoms.push(&chars[start..i]);
+            }
+            atoms.push(&chars[i..i + 1]);
+            start = i + 1;
+        }
+    }
+    if start < chars.len() {
+        atoms.push(&chars[start..]);
+    }
+
+    let mut lines = Vec::new();
+    let mut current: Vec<(Font, char)> = Vec::new();
+    let mut width = 0.0;
+    let mut pending_space: Option<(Font, char)> = None;
+    for atom in atoms {
+        if atom.len() == 1 && atom[0].1 == ' ' {
+            if !current.is_empty() {
+                pending_space = Some(atom[0]);
+            }
+            continue;
+        }
+        let atom_width: f32 = atom.iter().map(|&(f, c)| char_width(f, c, size)).sum();
+        let space_width = pending_space.map_or(0.0, |(f, c)| char_width(f, c, size));
+        if !current.is_empty() && width + space_width + atom_width > max_width {
+            lines.push(Line::from_chars(&current, size));
+            current.clear();
+            width = 0.0;
+            pending_space = None;
+        } else if let Some(space) = pending_space.take() {
+            current.push(space);
+            width += space_width;
+        }
+        if atom_width > max_width {
+            // A URL or identifier longer than the line.
+            let mut pieces = hard_wrap(atom, size, max_width);
+            let last = pieces.pop().unwrap_or_default();
+            lines.extend(pieces);
+            current = last
+                .pieces
+                .iter()
+                .flat_map(|p| p.text.chars().map(move |c| (p.font, c)))
+                .collect();
+            width = last.width;
+        } else {
+            current.extend_from_slice(atom);
+            width += atom_width;
+        }
+    }
+    if !current.is_empty() || lines.is_empty() {
+        lines.push(Line::from_chars(&current, size));
+    }
+    lines
+}
+
+fn span_runs(spans: &[Span], bold: bool) -> Vec<(Font, String)> {
+    spans
+        .iter()
+        .map(|s| {
+            let font = Font::for_span(s.kind);
+            let font = if bold { font.bold() } else { font };
+            let text = if s.kind == SpanKind::Math {
+                math_to_text(&s.text)
+            } else {
+                s.text.clone()
+            };
+            (font, text)
+        })
+        .collect()
+}
+
+fn fmt(v: f32) -> String {
+    let s = format!("{:.2}", v);
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn encode_piece(piece: &Piece) -> String {
+    match piece.font {
+        Font::Cjk | Font::CjkBold => {
+            let mut hex = String::from("<");
+            for unit in piece.text.encode_utf16() {
+                hex.push_str(&format!("{:04X}", unit));
+            }
+            hex.push('>');
+            hex
+        }
+        _ => {
+            let mut lit = String::from("(");
+            for b in piece.text.chars().filter_map(win_ansi) {
+                match b {
+                    b'(' | b')' | b'\\' => {
+                        lit.push('\\');
+                        lit.push(b as char);
+                    }
+                    0x20..=0x7e => lit.push(b as char),
+                    _ => lit.push_str(&format!("\\{:03o}", b)),
+                }
+            }
+            lit.push(')');
+            lit
+        }
+    }
+}
+
+/// Content streams of the pages laid out so far.
+struct Doc {
+    page: Page,
+    pages: Vec<String>,
+    ops: String,
+    /// Top of the free space on the current page.
+    y: f32,
+}
+
+impl Doc {
+    fn new(page: Page) -> Self {
+        Self {
+            page,
+            pages: Vec::new(),
+            ops: String::new(),
+            y: page.height - page.margin,
+        }
+    }
+
+    fn content_width(&self) -> f32 {
+        self.page.width - 2.0 * self.page.margin
+    }
+
+    fn at_top(&self) -> bool {
+        self.y >= self.page.height - self.page.margin - 0.01
+    }
+
+    /// Start a new page unless `height` still fits on this one.
+    fn ensure(&mut self, height: f32) {
+        if self.y - height < self.page.margin + FOOTER_SPACE && !self.at_top() {
+            self.pages.push(std::mem::take(&mut self.ops));
+            self.y = self.page.height - self.page.margin;
+        }
+    }
+
+    /// Vertical space, dropped at the top of a page.
+    fn gap(&mut self, height: f32) {
+        if !self.at_top() {
+            self.y -= height;
+        }
+    }
+
+    fn text(&mut self, line: &Line, x: f32, baseline: f32, size: f32, gray: f32) {
+        let mut x = x;
+        for piece in &line.pieces {
+            let stroke = piece.font == Font::CjkBold;
+            // Fake bold: fill and stroke the outline (render mode 2),
+            // then reset it since text state outlives `ET`.
+            let (bold_on, bold_off) = if stroke {
+                (
+                    format!("{} G {} w 2 Tr ", fmt(gray), fmt(size / 30.0)),
+                    "0 Tr ",
+                )
+            } else {
+                (String::new(), "")
+            };
+            self.ops.push_str(&format!(
+                "BT /{} {} Tf {} g {}{} {} Td {} Tj {}ET\n",
+                piece.font.resource(),
+                fmt(size),
+                fmt(gray),
+                bold_on,
+                fmt(x),
+                fmt(baseline),
+                encode_piece(piece),
+                bold_off,
+            ));
+            x += piece
+                .text
+                .chars()
+                .map(|c| char_width(piece.font, c, size))
+                .sum::<f32>();
+        }
+    }
+
+    fn fill_rect(&mut self, x: f32, y: f32, w: f32, h: f32, gray: f32) {
+        self.ops.push_str(&format!(
+            "{} g {} {} {} {} re f\n",
+            fmt(gray),
+            fmt(x),
+            fmt(y),
+            fmt(w),
+            fmt(h)
+        ));
+    }
+
+    fn stroke(&mut self, from: (f32, f32), to: (f32, f32), width: f32, gray: f32) {
+        self.ops.push_str(&format!(
+            "{} G {} w {} {} m {} {} l S\n",
+            fmt(gray),
+            fmt(width),
+            fmt(from.0),
+            fmt(from.1),
+            fmt(to.0),
+            fmt(to.1)
+        ));
+    }
+
+    /// Flow `lines` down the page at `x`; `decorate` draws extras (code
+    /// background, quote bar) for each line's box before its text.
+    fn lines(
+        &mut self,
+        lines: &[Line],
+        x: f32,
+        size: f32,
+        gray: f32,
+        mut decorate: impl FnMut(&mut Self, f32, f32),
+    ) {
+        let height = size * self.page.leading;
+        for line in lines {
+            self.ensure(height);
+            let top = self.y;
+            decorate(self, top - height, height);
+            let baseline = top - height / 2.0 - size * 0.32;
+            self.text(line, x, baseline, size, gray);
+            self.y -= height;
+        }
+    }
+
+    fn header(&mut self, meta: &ExportMeta) {
+        let body = self.page.body;
+        let left = self.page.margin;
+        let width = self.content_width();
+        let title = wrap(&[(Font::Bold, meta.title.clone())], body * 1.8, width);
+        self.lines(&title, left, body * 1.8, 0.0, |_, _, _| {});
+        let info = meta.summary_line();
+        if !info.is_empty() {
+            let info = wrap(&[(Font::Regular, info)], body * 0.85, width);
+            self.lines(&info, left, body * 0.85, 0.4, |_, _, _| {});
+        }
+        self.y -= body * 0.4;
+        let y = self.y;
+        self.stroke((left, y), (left + width, y), 0.8, 0.6);
+        self.y -= body;
+    }
+
+    fn block(&mut self, block: &Block) {
+        let body = self.page.body;
+        let left = self.page.margin;
+        let width = self.content_width();
+        match block {
+            Block::Heading { level, spans } => {
+                let scale = [1.6, 1.35, 1.18, 1.05, 1.0, 1.0][(*level as usize).clamp(1, 6) - 1];
+                let size = body * scale;
+                let lines = wrap(&span_runs(spans, true), size, width);
+                self.gap(size * 0.7);
+                // Keep the heading with at least two lines of what follows.
+                let line_height = size * self.page.leading;
+                self.ensure(line_height * lines.len() as f32 + body * self.page.leading * 2.0);
+                self.lines(&lines, left, size, 0.0, |_, _, _| {});
+                self.y -= size * 0.2;
+            }
+            Block::Paragraph(spans) => {
+                let lines = wrap(&span_runs(spans, false), body, width);
+                self.lines(&lines, left, body, 0.0, |_, _, _| {});
+                self.y -= body * 0.5;
+            }
+            Block::ListItem {
+                ordered,
+                depth,
+                spans,
+            } => {
+                let indent = left + 14.0 * (*depth as f32);
+                let marker = match ordered {
+                    Some(n) => format!("{}.", n),
+                    None => "•".to_string(),
+                };
+                let marker_line = Line::from_chars(&styled_chars(&[(Font::Regular, marker)]), body);
+                let text_x = indent + marker_line.width.max(6.0) + 5.0;
+                let lines = wrap(&span_runs(spans, false), body, left + width - text_x);
+                let mut first = true;
+                self.lines(&lines, text_x, body, 0.0, |doc, bottom, height| {
+                    if std::mem::take(&mut first) {
+                        let baseline = bottom + height / 2.0 - body * 0.32;
+                        doc.text(&marker_line, indent, baseline, body, 0.0);
+                    }
+                });
+                self.y -= body * 0.2;
+            }
+            Block::Quote(spans) => {
+                let lines = wrap(&span_runs(spans, false), body, width - 12.0);
+                self.lines(&lines, left + 12.0, body, 0.35, |doc, bottom, height| {
+                    doc.fill_rect(left, bottom, 2.0, height, 0.75);
+                });
+                self.y -= body * 0.5;
+            }
+            Block::Code { text, .. } => {
+                let size = body * 0.85;
+                let pad = 4.0;
+                let mut lines = Vec::new();
+                for source in text.lines() {
+                    let chars = styled_chars(&[(Font::Mono, source.replace('\t', "    "))]);
+                    lines.extend(hard_wrap(&chars, size, width - 2.0 * pad));
+                }
+                self.gap(body * 0.2);
+                self.lines(&lines, left + pad, size, 0.1, |doc, bottom, height| {
+                    doc.fill_rect(left, bottom, width, height, 0.94);
+                });
+                self.y -= body * 0.6;
+            }
+            Block::Math(tex) => {
+                let size = body * 1.05;
+                let lines = wrap(&[(Font::Italic, math_to_text(tex))], size, width);
+                self.gap(body * 0.2);
+                for line in &lines {
+                    let x = left + ((width - line.width) / 2.0).max(0.0);
+                    self.lines(std::slice::from_ref(line), x, size, 0.0, |_, _, _| {});
+                }
+                self.y -= body * 0.6;
+            }
+            Block::Table { header, rows } => self.table(header, rows),
+            Block::Rule => {
+                self.ensure(body);
+                self.y -= body * 0.5;
+                let y = self.y;
+                self.stroke((left, y), (left + width, y), 0.5, 0.7);
+                self.y -= body * 0.5;
+            }
+        }
+    }
+
+    fn table(&mut self, header: &[markdown::Cell], rows: &[Vec<markdown::Cell>]) {
+        let size = self.page.body * 0.92;
+        let left = self.page.margin;
+        let width = self.content_width();
+        let columns = rows
+            .iter()
+            .map(Vec::len)
+            .chain([header.len()])
+            .max()
+            .unwrap_or(1)
+            .max(1);
+        let column_width = width / columns as f32;
+        let pad = 4.0;
+        let line_height = size * self.page.leading;
+
+        self.gap(self.page.body * 0.2);
+        let all_rows =
+            std::iter::once((header, true)).chain(rows.iter().map(|r| (r.as_slice(), false)));
+        for (cells, is_header) in all_rows {
+            let wrapped: Vec<Vec<Line>> = (0..columns)
+                .map(|i| {
+                    let spans = cells.get(i).map(Vec::as_slice).unwrap_or_default();
+                    wrap(&span_runs(spans, is_header), size, column_width - 2.0 * pad)
+                })
+                .collect();
+            let row_lines = wrapped.iter().map(Vec::len).max().unwrap_or(1);
+            let row_height = row_lines as f32 * line_height + 2.0 * pad;
+            self.ensure(row_height);
+            let top = self.y;
+            let bottom = top - row_height;
+            if is_header {
+                self.fill_rect(left, bottom, width, row_height, 0.92);
+            }
+            for (i, lines) in wrapped.iter().enumerate() {
+                let x = left + i as f32 * column_width + pad;
+                for (j, line) in lines.iter().enumerate() {
+                    let line_top = top - pad - j as f32 * line_height;
+                    let baseline = line_top - line_height / 2.0 - size * 0.32;
+                    self.text(line, x, baseline, size, 0.0);
+                }
+            }
+            self.stroke((left, top), (left + width, top), 0.5, 0.6);
+            self.stroke((left, bottom), (left + width, bottom), 0.5, 0.6);
+            for i in 0..=columns {
+                let x = left + i as f32 * column_width;
+                self.stroke((x, top), (x, bottom), 0.5, 0.6);
+            }
+            self.y = bottom;
+        }
+        self.y -= self.page.body * 0.6;
+    }
+
+    fn finish(mut self, title: &str) -> Vec<String> {
+        self.pages.push(std::mem::take(&mut self.ops));
+        let total = self.pages.len();
+        let short: String = title.chars().take(40).collect();
+        let (size, page) = (8.0, self.page);
+        for (i, ops) in self.pages.iter_mut().enumerate() {
+            let label = format!("{} · {} / {}", short, i + 1, total);
+            let line = Line::from_chars(&styled_chars(&[(Font::Regular, label)]), size);
+            let x = ((page.width - line.width) / 2.0).max(page.margin);
+            let mut footer = Doc::new(page);
+            footer.text(&line, x, page.margin - size, size, 0.5);
+            ops.push_str(&footer.ops);
+        }
+        self.pages
+    }
+}
+
+const MATH_SYMBOLS: &[(&str, &str)] = &[
+    ("alpha", "α"),
+    ("beta", "β"),
+    ("gamma", "γ"),
+    ("delta", "δ"),
+    ("epsilon", "ε"),
+    ("varepsilon", "ε"),
+    ("zeta", "ζ"),
+    ("eta", "η"),
+    ("theta", "θ"),
+    ("kappa", "κ"),
+    ("lambda", "λ"),
+    ("mu", "μ"),
+    ("nu", "ν"),
+    ("xi", "ξ"),
+    ("pi", "π"),
+    ("rho", "ρ"),
+    ("sigma", "σ"),
+    ("tau", "τ"),
+    ("phi", "φ"),
+    ("varphi", "φ"),
+    ("chi", "χ"),
+    ("psi", "ψ"),
+    ("omega", "ω"),
+    ("Gamma", "Γ"),
+    ("Delta", "Δ"),
+    ("Theta", "Θ"),
+    ("Lambda", "Λ"),
+    ("Pi", "Π"),
+    ("Sigma", "Σ"),
+    ("Phi", "Φ"),
+    ("Psi", "Ψ"),
+    ("Omega", "Ω"),
+    ("times", "×"),
+    ("cdot", "·"),
+    ("div", "÷"),
+    ("pm", "±"),
+    ("le", "≤"),
+    ("leq", "≤"),
+    ("ge", "≥"),
+    ("geq", "≥"),
+    ("ne", "≠"),
+    ("neq", "≠"),
+    ("approx", "≈"),
+    ("equiv", "≡"),
+    ("sim", "∼"),
+    ("propto", "∝"),
+    ("infty", "∞"),
+    ("sum", "∑"),
+    ("prod", "∏"),
+    ("int", "∫"),
+    ("partial", "∂"),
+    ("nabla", "∇"),
+    ("in", "∈"),
+    ("notin", "∉"),
+    ("subset", "⊂"),
+    ("subseteq", "⊆"),
+    ("cup", "∪"),
+    ("cap", "∩"),
+    ("emptyset", "∅"),
+    ("forall", "∀"),
+    ("exists", "∃"),
+    ("neg", "¬"),
+    ("land", "∧"),
+    ("lor", "∨"),
+    ("to", "→"),
+    ("rightarrow", "→"),
+    ("leftarrow", "←"),
+    ("Rightarrow", "⇒"),
+    ("implies", "⇒"),
+    ("Leftrightarrow", "⇔"),
+    ("iff", "⇔"),
+    ("ldots", "…"),
+    ("dots", "…"),
+    ("cdots", "⋯"),
+    ("quad", " "),
+    ("qquad", "  "),
+];
+
+/// Commands whose argument is printed as-is.
+const MATH_TEXT_COMMANDS: &[&str] = &[
+    "text",
+    "mathrm",
+    "mathbf",
+    "mathit",
+    "mathsf",
+    "mathtt",
+    "mathbb",
+    "mathcal",
+    "operatorname",
+    "boldsymbol",
+];
+
+/// Commands that only affect sizing / spacing.
+const MATH_IGNORED: &[&str] = &[
+    "left",
+    "right",
+    "big",
+    "Big",
+    "bigg",
+    "Bigg",
+    "displaystyle",
+    "textstyle",
+    "limits",
+];
+
+fn math_group(s: String) -> String {
+    if s.chars().count() <= 1 || s.chars().all(char::is_alphanumeric) {
+        s
+    } else {
+        format!("({})", s)
+    }
+}
+
+fn math_command(chars: &[char], i: &mut usize) -> String {
+    // At the backslash.
+    *i += 1;
+    let start = *i;
+    while *i < chars.len() && chars[*i].is_ascii_alphabetic() {
+        *i += 1;
+    }
+    let name: String = chars[start..*i].iter().collect();
+    if name.is_empty() {
+        // `\,` `\;` `\{` `\\` …
+        let c = chars.get(*i).copied().unwrap_or(' ');
+        *i += 1;
+        return match c {
+            ',' | ';' | ':' | ' ' | '\\' => " ".to_string(),
+            '!' => String::new(),
+            c => c.to_string(),
+        };
+    }
+    match name.as_str() {
+        "frac" | "dfrac" | "tfrac" => {
+            let num = math_arg(chars, i);
+            let den = math_arg(chars, i);
+            format!("{}/{}", math_group(num), math_group(den))
+        }
+        "sqrt" => format!("√{}", math_group(math_arg(chars, i))),
+        n if MATH_TEXT_COMMANDS.contains(&n) => math_arg(chars, i),
+        n if MATH_IGNORED.contains(&n) => String::new(),
+        n => MATH_SYMBOLS
+            .iter()
+            .find(|(k, _)| *k == n)
+            .map(|(_, v)| v.to_string())
+            // \sin, \log, \max … and anything unknown read as their name.
+            .unwrap_or(name),
+    }
+}
+
+/// One argument: a `{group}`, a command, or a single character.
+fn math_arg(chars: &[char], i: &mut usize) -> String {
+    while *i < chars.len() && chars[*i] == ' ' {
+        *i += 1;
+    }
+    match chars.get(*i) {
+        Some('{') => {
+            *i += 1;
+            math_until(chars, i, Some('}'))
+        }
+        Some('\\') => math_command(chars, i),
+        Some(&c) => {
+            *i += 1;
+            c.to_string()
+        }
+        None => String::new(),
+    }
+}
+
+fn math_until(chars: &[char], i: &mut usize, stop: Option<char>) -> String {
+    let mut out = String::new();
+    while *i < chars.len() {
+        let c = chars[*i];
+        if Some(c) == stop {
+            *i += 1;
+            break;
+        }
+        match c {
+            '\\' => out.push_str(&math_command(chars, i)),
+            '^' => {
+                *i += 1;
+                let sup = math_arg(chars, i);
+                match sup.as_str() {
+                    "1" => out.push('¹'),
+                    "2" => out.push('²'),
+                    "3" => out.push('³'),
+                    _ => {
+                        out.push('^');
+                        out.push_str(&math_group(sup));
+                    }
+                }
+            }
+            '_' => {
+                *i += 1;
+                out.push('_');
+                out.push_str(&math_group(math_arg(chars, i)));
+            }
+            '{' => {
+                *i += 1;
+                out.push_str(&math_until(chars, i, Some('}')));
+            }
+            '}' => *i += 1,
+            '~' => {
+                out.push(' ');
+                *i += 1;
+            }
+            c => {
+                out.push(c);
+                *i += 1;
+            }
+        }
+    }
+    out
+}
+
+/// LaTeX math → readable Unicode text.
+pub fn math_to_text(tex: &str) -> String {
+    let chars: Vec<char> = tex.trim().chars().collect();
+    let mut i = 0;
+    math_until(&chars, &mut i, None)
+}
+
+/// PDF text string: UTF-16BE with a byte-order mark, as hex.
+fn pdf_text_string(s: &str) -> String {
+    let mut hex = String::from("<FEFF");
+    for unit in s.encode_utf16() {
+        hex.push_str(&format!("{:04X}", unit));
+    }
+    hex.push('>');
+    hex
+}
+
+/// Serialise page content streams into a complete PDF file.
+fn assemble(pages: &[String], page: Page, title: &str) -> Vec<u8> {
+    const FIRST_PAGE_OBJ: usize = 11;
+    let mut objects: Vec<String> = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            (0..pages.len())
+                .map(|i| format!("{} 0 R", FIRST_PAGE_OBJ + 2 * i))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pages.len()
+        ),
+    ];
+    for base in [
+        "Helvetica",
+        "Helvetica-Bold",
+        "Helvetica-Oblique",
+        "Helvetica-BoldOblique",
+        "Courier",
+    ] {
+        objects.push(format!(
+            "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+            base
+        ));
+    }
+    // 8: Type0 CJK font, 9: its CIDFont, 10: descriptor.
+    objects.push(
+        "<< /Type /Font /Subtype /Type0 /BaseFont /MSung-Light-UniCNS-UCS2-H \
+         /Encoding /UniCNS-UCS2-H /DescendantFonts [9 0 R] >>"
+            .to_string(),
+    );
+    objects.push(
+        "<< /Type /Font /Subtype /CIDFontType0 /BaseFont /MSung-Light \
+         /CIDSystemInfo << /Registry (Adobe) /Ordering (CNS1) /Supplement 0 >> \
+         /FontDescriptor 10 0 R /DW 1000 >>"
+            .to_string(),
+    );
+    objects.push(
+        "<< /Type /FontDescriptor /FontName /MSung-Light /Flags 6 \
+         /FontBBox [-160 -249 1015 888] /ItalicAngle 0 /Ascent 880 /Descent -120 \
+         /CapHeight 880 /StemV 93 >>"
+            .to_string(),
+    );
+    let fonts = (1..=6)
+        .map(|n| format!("/F{} {} 0 R", n, n + 2))
+        .collect::<Vec<_>>()
+        .join(" ");
+    for (i, content) in pages.iter().enumerate() {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] \
+             /Resources << /Font << {} >> >> /Contents {} 0 R >>",
+            fmt(page.width),
+            fmt(page.height),
+            fonts,
+            FIRST_PAGE_OBJ + 2 * i + 1
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}endstream",
+            content.len(),
+            content
+        ));
+    }
+    let info_obj = objects.len() + 1;
+    objects.push(format!(
+        "<< /Title {} /Producer (ClassNoteAI) /CreationDate (D:{}) >>",
+        pdf_text_string(title),
+        chrono::Utc::now().format("%Y%m%d%H%M%SZ")
+    ));
+
+    let mut out = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, body) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        out.extend_from_slice(format!("{} 0 obj\n{}\nendobj\n", i + 1, body).as_bytes());
+    }
+    let xref = out.len();
+    out.extend_from_slice(
+        format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+    );
+    for offset in offsets {
+        out.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    out.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R /Info {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            info_obj,
+            xref
+        )
+        .as_bytes(),
+    );
+    out
+}
+
+/// PDF bytes for a note: `meta` as the header, then `markdown`.
+pub fn render_note_pdf(meta: &ExportMeta, markdown: &str, template: PdfTemplate) -> Vec<u8> {
+    let page = template.page();
+    let mut doc = Doc::new(page);
+    doc.header(meta);
+    for block in markdown::parse(markdown) {
+        doc.block(&block);
+    }
+    let pages = doc.finish(&meta.title);
+    assemble(&pages, page, &meta.title)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meta() -> ExportMeta {
+        ExportMeta {
+            title: "演算法 Week 3".into(),
+            course: Some("CS101".into()),
+            date: Some("2026-10-15".into()),
+            duration_sec: Some(3000),
+        }
+    }
+
+    #[test]
+    fn template_names() {
+        assert_eq!(PdfTemplate::parse("").unwrap(), PdfTemplate::Standard);
+        assert_eq!(PdfTemplate::parse("Compact").unwrap(), PdfTemplate::Compact);
+        assert!(PdfTemplate::parse("poster").is_err());
+    }
+
+    #[test]
+    fn latex_to_text() {
+        assert_eq!(math_to_text(r"\frac{a+b}{2}"), "(a+b)/2");
+        assert_eq!(
+            math_to_text(r"x^2 + \alpha_i \le \sqrt{n}"),
+            "x² + α_i ≤ √n"
+        );
+        assert_eq!(math_to_text(r"\sum_{i=1}^{n} i"), "∑_(i=1)^n i");
+        assert_eq!(math_to_text(r"\left( \text{cost} \right)"), "( cost )");
+    }
+
+    #[test]
+    fn wrapping_breaks_words_and_cjk() {
+        let lines = wrap(&[(Font::Regular, "aaaa bbbb cccc".into())], 10.0, 60.0);
+        let texts: Vec<String> = lines
+            .iter()
+            .map(|l| l.pieces.iter().map(|p| p.text.as_str()).collect())
+            .collect();
+        assert_eq!(texts, vec!["aaaa bbbb", "cccc"]);
+
+        let lines = wrap(&[(Font::Regular, "中文字測試".into())], 10.0, 30.0);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].pieces[0].font, Font::Cjk);
+    }
+
+    #[test]
+    fn output_is_a_well_formed_pdf() {
+        let md = "## 摘要\n\nSorting **recap** with $O(n \\log n)$.\n\n- a\n- b\n\n```\nfn main() {}\n```\n\n| k | v |\n|---|---|\n| 1 | 2 |\n";
+        let bytes = render_note_pdf(&meta(), md, PdfTemplate::Standard);
+        let text = String::from_utf8_lossy(&bytes);
+        assert!(text.starts_with("%PDF-1.4"));
+        assert!(text.trim_end().ends_with("%%EOF"));
+        // CJK goes through the Type0 font as UCS-2 hex.
+        assert!(text.contains("/F6") && text.contains("<64588981>"));
+
+        // Every xref entry points at its object.
+        let xref_at: usize = text
+            .rsplit("startxref\n")
+            .next()
+            .and_then(|t| t.lines().next())
+            .and_then(|n| n.parse().ok())
+            .unwrap();
+        assert!(bytes[xref_at..].starts_with(b"xref"));
+        let entries = text[xref_at..].lines().skip(3);
+        for (i, entry) in entries.take_while(|l| l.ends_with(" n ")).enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(bytes[offset..].starts_with(format!("{} 0 obj", i + 1).as_bytes()));
+        }
+    }
+
+    #[test]
+    fn long_notes_paginate() {
+        let md = (0..200)
+            .map(|i| format!("Paragraph {i} with enough words to take up a line."))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let bytes = render_note_pdf(&meta(), &md, PdfTemplate::Compact);
+        let text = String::from_utf8_lossy(&bytes);
+        let pages = text.matches("/Type /Page ").count();
+        assert!(pages > 2, "{pages} pages");
+        assert!(text.contains(&format!("/Count {pages}")));
+    }
+}
@@ -8,19 +8,39 @@
  * - macOS: ~/Library/Application Support/com.classnoteai/
  * - Windows: %APPDATA%/com.classnoteai/
  * - Linux: ~/.local/share/com.classnoteai/
+ *
+ * unless the user moved it elsewhere (see `relocate`).
  */
 use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use super::relocate;
 
 /// Bundle identifier for the app
 pub const BUNDLE_ID: &str = "com.classnoteai";
 
 /// Get the app data directory
 ///
+/// The directory the data was moved to with `move_app_data`, otherwise
+/// [`default_app_data_dir`]. Resolved once per process; a move takes
+/// effect on the next launch.
+pub fn get_app_data_dir() -> Result<PathBuf, String> {
+    static RESOLVED: OnceLock<Result<PathBuf, String>> = OnceLock::new();
+    RESOLVED
+        .get_or_init(|| {
+            let default_dir = default_app_data_dir()?;
+            Ok(relocate::redirected_dir(&default_dir).unwrap_or(default_dir))
+        })
+        .clone()
+}
+
+/// Get the platform default app data directory
+///
 /// Returns the platform-specific app data directory:
 /// - macOS: ~/Library/Application Support/com.classnoteai/
 /// - Windows: %APPDATA%/com.classnoteai/
 /// - Linux: ~/.local/share/com.classnoteai/
-pub fn get_app_data_dir() -> Result<PathBuf, String> {
+pub fn default_app_data_dir() -> Result<PathBuf, String> {
     #[cfg(target_os = "macos")]
     {
         if let Some(home) = dirs::home_dir() {
@@ -135,6 +155,18 @@ pub fn get_video_dir() -> Result<PathBuf, String> {
     Ok(get_app_data_dir()?.join("videos"))
 }
 
+/// Get the externalized-transcripts directory.
+///
+/// Returns: {app_data_dir}/transcripts/
+///
+/// Holds gzip-compressed subtitle blobs for lectures whose transcript
+/// was moved out of SQLite (see `storage::transcript_store`). The DB
+/// keeps a pointer + checksum; the blob is only read when the lecture
+/// is actually opened.
+pub fn get_transcripts_dir() -> Result<PathBuf, String> {
+    Ok(get_app_data_dir()?.join("transcripts"))
+}
+
 /// Get the database file path
 ///
 /// Returns: {app_data_dir}/classnoteai.db
@@ -156,6 +188,43 @@ pub fn get_cache_dir() -> Result<PathBuf, String> {
     Ok(get_app_data_dir()?.join("cache"))
 }
 
+/// Get the backups directory
+///
+/// Returns: {app_data_dir}/backups/
+///
+/// Holds the snapshots taken before an update installs (see
+/// `storage::backup`).
+pub fn get_backups_dir() -> Result<PathBuf, String> {
+    Ok(get_app_data_dir()?.join("backups"))
+}
+
+/// Get the logs directory
+///
+/// Returns: {app_data_dir}/logs/
+///
+/// The app log (`crate::logging`) and the llama-server sidecar's stderr
+/// both live here, next to the data they describe, so a support request
+/// only ever has to point at one folder.
+pub fn get_logs_dir() -> Result<PathBuf, String> {
+    Ok(get_app_data_dir()?.join("logs"))
+}
+
+/// Get the crash reports directory
+///
+/// Returns: {app_data_dir}/crash_reports/
+pub fn get_crash_reports_dir() -> Result<PathBuf, String> {
+    Ok(get_app_data_dir()?.join("crash_reports"))
+}
+
+/// Get the telemetry queue directory
+///
+/// Returns: {app_data_dir}/telemetry/
+///
+/// Only written to after the user opts in (see `crate::telemetry`).
+pub fn get_telemetry_dir() -> Result<PathBuf, String> {
+    Ok(get_app_data_dir()?.join("telemetry"))
+}
+
 /// Ensure a directory exists, creating it if necessary
 pub fn ensure_dir_exists(path: &PathBuf) -> Result<(), String> {
     if !path.exists() {
@@ -178,7 +247,9 @@ pub fn init_app_dirs() -> Result<(), String> {
         get_documents_dir()?,
         get_audio_dir()?,
         get_in_progress_audio_dir()?,
+        get_transcripts_dir()?,
         get_cache_dir()?,
+        get_logs_dir()?,
     ];
 
     for dir in dirs {
@@ -0,0 +1,232 @@
+//! Models left in locations used before the unified paths.
+//!
+//! Early builds looked for models in a `models/` directory next to the
+//! project (the repo root, or `src-tauri/..` under `tauri dev`) or next
+//! to the executable, some with the Whisper `ggml-*.bin` files loose at
+//! the top. [`migrate_legacy_models`] brings them into
+//! [`super::get_models_dir`] so those users don't download them again:
+//! each entry is renamed into place, or symlinked when it sits on another
+//! volume (copying gigabytes would stall startup). Anything already
+//! present at the destination is left alone. It runs once, as the
+//! `legacy-models-dir` post-update migration (`storage::migrations`).
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::get_models_dir;
+
+/// Subdirectories of the unified layout; a legacy copy of one is merged
+/// into it entry by entry.
+const KNOWN_SUBDIRS: &[&str] = &["whisper", "translation", "embedding", "llm"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Moved,
+    Linked,
+}
+
+/// Legacy `models/` directories that exist, excluding the current one.
+fn legacy_roots(models_dir: &Path) -> Vec<PathBuf> {
+    let mut candidates = Vec::new();
+    if let Ok(cwd) = std::env::current_dir() {
+        candidates.push(cwd.join("models"));
+        candidates.push(cwd.join("..").join("models"));
+    }
+    if let Some(exe_dir) = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+    {
+        candidates.push(exe_dir.join("models"));
+    }
+
+    let current = fs::canonicalize(models_dir).ok();
+    let mut roots: Vec<PathBuf> = Vec::new();
+    for candidate in candidates {
+        let Ok(root) = fs::canonicalize(&candidate) else {
+            continue;
+        };
+        if root.is_dir() && Some(&root) != current.as_ref() && !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+    roots
+}
+
+/// Where `entry`, found at the top of a legacy root, belongs.
+fn destination(models_dir: &Path, entry: &Path) -> PathBuf {
+    let name = entry.file_name().unwrap_or_default();
+    let name_str = name.to_string_lossy();
+    if entry.is_file() {
+        if name_str.starts_with("ggml-") && name_str.ends_with(".bin") {
+            return models_dir.join("whisper").join(name);
+        }
+        if name_str.ends_with(".gguf") {
+            return models_dir.join("llm").join(name);
+        }
+    } else if !KNOWN_SUBDIRS.contains(&name_str.as_ref()) {
+        // Model directories that used to sit at the top level.
+        if entry.join("model.bin").is_file() {
+            return models_dir.join("translation").join(name);
+        }
+        if entry.join("model.safetensors").is_file() {
+            return models_dir.join("embedding").join(name);
+        }
+    }
+    models_dir.join(name)
+}
+
+/// Rename `src` to `dest`, or symlink it there when they're on
+/// different volumes. `None` if `dest` already exists.
+fn relocate(src: &Path, dest: &Path) -> io::Result<Option<Outcome>> {
+    if dest.exists() || fs::symlink_metadata(dest).is_ok() {
+        return Ok(None);
+    }
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::rename(src, dest).is_ok() {
+        return Ok(Some(Outcome::Moved));
+    }
+    symlink(src, dest).map(|()| Some(Outcome::Linked))
+}
+
+#[cfg(unix)]
+fn symlink(src: &Path, dest: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(src, dest)
+}
+
+/// Needs Developer Mode or admin rights; without them the model is just
+/// downloaded again.
+#[cfg(windows)]
+fn symlink(src: &Path, dest: &Path) -> io::Result<()> {
+    if src.is_dir() {
+        std::os::windows::fs::symlink_dir(src, dest)
+    } else {
+        std::os::windows::fs::symlink_file(src, dest)
+    }
+}
+
+/// Bring everything under `root` into `models_dir`; returns what moved
+/// where, and a message for each entry that couldn't be moved.
+fn migrate_from(root: &Path, models_dir: &Path) -> (Vec<(PathBuf, Outcome)>, Vec<String>) {
+    let mut migrated = Vec::new();
+    let mut failed = Vec::new();
+    let Ok(entries) = fs::read_dir(root) else {
+        return (migrated, failed);
+    };
+    for entry in entries.flatten() {
+        let src = entry.path();
+        let dest = destination(models_dir, &src);
+        // A known subdirectory is merged, since the unified one usually
+        // exists already; a model directory moves whole or not at all.
+        let is_known = KNOWN_SUBDIRS.contains(&entry.file_name().to_string_lossy().as_ref());
+        let pairs: Vec<(PathBuf, PathBuf)> = if is_known && src.is_dir() {
+            fs::read_dir(&src)
+                .map(|children| {
+                    children
+                        .flatten()
+                        .map(|c| (c.path(), dest.join(c.file_name())))
+                        .collect()
+                })
+                .unwrap_or_default()
+        } else {
+            vec![(src, dest)]
+        };
+        for (src, dest) in pairs {
+            match relocate(&src, &dest) {
+                Ok(Some(outcome)) => migrated.push((dest, outcome)),
+                Ok(None) => {}
+                Err(e) => failed.push(format!("{} → {}: {}", src.display(), dest.display(), e)),
+            }
+        }
+    }
+    (migrated, failed)
+}
+
+/// Move models from the legacy locations into the models directory.
+/// Returns how many entries were brought over; an error if any entry
+/// could not be, so the migration is tried again on the next launch.
+pub fn migrate_legacy_models() -> Result<usize, String> {
+    let models_dir = get_models_dir()?;
+    let mut count = 0;
+    let mut failed = Vec::new();
+    for root in legacy_roots(&models_dir) {
+        let (migrated, root_failed) = migrate_from(&root, &models_dir);
+        for (dest, outcome) in &migrated {
+            println!(
+                "[Paths] Legacy model {:?} from {}: {}",
+                outcome,
+                root.display(),
+                dest.display()
+            );
+        }
+        count += migrated.len();
+        failed.extend(root_failed);
+        // Only succeeds once everything was moved out.
+        let _ = fs::remove_dir(&root);
+    }
+    if failed.is_empty() {
+        Ok(count)
+    } else {
+        Err(format!("無法搬移舊版模型：{}", failed.join("; ")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_into_the_unified_layout_without_overwriting() {
+        let tmp = tempfile::tempdir().unwrap();
+        let legacy = tmp.path().join("legacy");
+        let models = tmp.path().join("models");
+        fs::create_dir_all(legacy.join("whisper")).unwrap();
+        fs::create_dir_all(legacy.join("m2m100-418M-ct2-int8")).unwrap();
+        fs::write(legacy.join("ggml-base.bin"), b"base").unwrap();
+        fs::write(legacy.join("whisper/ggml-small.bin"), b"small").unwrap();
+        fs::write(legacy.join("m2m100-418M-ct2-int8/model.bin"), b"ct2").unwrap();
+        fs::create_dir_all(models.join("whisper")).unwrap();
+        fs::write(models.join("whisper/ggml-small.bin"), b"newer").unwrap();
+
+        let (migrated, failed) = migrate_from(&legacy, &models);
+        assert!(failed.is_empty(), "{failed:?}");
+        assert_eq!(migrated.len(), 2);
+        assert!(migrated.iter().all(|(_, o)| *o == Outcome::Moved));
+        assert_eq!(
+            fs::read(models.join("whisper/ggml-base.bin")).unwrap(),
+            b"base"
+        );
+        assert!(models
+            .join("translation/m2m100-418M-ct2-int8/model.bin")
+            .is_file());
+        // Already there: kept, and the legacy copy left in place.
+        assert_eq!(
+            fs::read(models.join("whisper/ggml-small.bin")).unwrap(),
+            b"newer"
+        );
+        assert!(legacy.join("whisper/ggml-small.bin").exists());
+
+        let (migrated, failed) = migrate_from(&legacy, &models);
+        assert!(migrated.is_empty() && failed.is_empty());
+    }
+
+    #[test]
+    fn reports_entries_it_could_not_move() {
+        let tmp = tempfile::tempdir().unwrap();
+        let legacy = tmp.path().join("legacy");
+        let models = tmp.path().join("models");
+        fs::create_dir_all(&legacy).unwrap();
+        fs::write(legacy.join("ggml-base.bin"), b"base").unwrap();
+        // `whisper` is taken by a file, so the destination can't be made.
+        fs::create_dir_all(&models).unwrap();
+        fs::write(models.join("whisper"), b"").unwrap();
+
+        let (migrated, failed) = migrate_from(&legacy, &models);
+        assert!(migrated.is_empty());
+        assert_eq!(failed.len(), 1);
+        assert!(failed[0].contains("ggml-base.bin"), "{}", failed[0]);
+        assert!(legacy.join("ggml-base.bin").is_file());
+    }
+}
@@ -5,5 +5,7 @@
  * All paths to app data, models, documents should go through this module.
  */
 mod app_dirs;
+pub mod legacy;
+pub mod relocate;
 
 pub use app_dirs::*;
@@ -0,0 +1,551 @@
+//! Moving the app data directory elsewhere (e.g. an external SSD).
+//!
+//! The platform default directory ([`super::default_app_data_dir`])
+//! always stays put and keeps a small [`REDIRECT_FILE`]; when it names
+//! another directory, [`super::get_app_data_dir`] resolves there for the
+//! rest of the process. A few files are pinned to the default directory
+//! because they're read before — or without — that lookup ([`PINNED`]).
+//!
+//! [`move_app_data`] copies every other top-level entry into
+//! `{new_dir}/com.classnoteai`, checks each copied file against its
+//! source (size and SHA-256), snapshots the database last with `VACUUM
+//! INTO` (it is open for writing, so a plain copy could catch it
+//! mid-transaction) and checks it with `quick_check`, then points the
+//! redirect at the new directory and records the old one as `previous`.
+//! The app restarts right after (`move_app_data` in lib.rs). Nothing is
+//! deleted yet: the next launch, already running from the new location,
+//! deletes the old copy in [`finish_pending`], unless a file in it was
+//! written after it was copied and the new copy doesn't have that
+//! version; then the old copy is kept. A failed or cancelled copy
+//! removes what it wrote and leaves the redirect alone.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{default_app_data_dir, get_app_data_dir, BUNDLE_ID};
+
+pub const REDIRECT_FILE: &str = "location.json";
+const DATABASE_FILE: &str = "classnoteai.db";
+
+/// Stay in the default directory: the redirect itself, dev flags (read
+/// before Tauri starts) and the agent bridge's attach file.
+pub const PINNED: &[&str] = &[REDIRECT_FILE, "dev-flags.toml", "agent-bridge.json"];
+
+/// Free space kept on the target beyond the size of the data.
+const SPACE_MARGIN_BYTES: u64 = 200 * 1024 * 1024;
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Redirect {
+    /// Where app data lives; `None` means the default directory.
+    pub path: Option<PathBuf>,
+    /// Data directory before the last move, deleted on the next launch.
+    #[serde(default)]
+    pub previous: Option<PathBuf>,
+    /// Top-level entries that were moved out of `previous`.
+    #[serde(default)]
+    pub moved: Vec<String>,
+    /// Unix ms when the files were copied, and when the database was.
+    /// Anything in `previous` written after that is checked against the
+    /// new copy before `previous` is deleted.
+    #[serde(default)]
+    pub copied_at: Option<u64>,
+    #[serde(default)]
+    pub database_copied_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MoveReport {
+    pub from: String,
+    pub to: String,
+    pub files: usize,
+    pub bytes: u64,
+    /// Always true: the app restarts right after a move.
+    pub restart_required: bool,
+}
+
+pub fn read_redirect(default_dir: &Path) -> Option<Redirect> {
+    let text = fs::read_to_string(default_dir.join(REDIRECT_FILE)).ok()?;
+    match serde_json::from_str(&text) {
+        Ok(redirect) => Some(redirect),
+        Err(e) => {
+            eprintln!("[Paths] Ignoring unreadable {}: {}", REDIRECT_FILE, e);
+            None
+        }
+    }
+}
+
+fn write_redirect(default_dir: &Path, redirect: &Redirect) -> Result<(), String> {
+    let path = default_dir.join(REDIRECT_FILE);
+    if redirect == &Redirect::default() {
+        return match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("無法刪除 {}: {}", path.display(), e))
+            }
+            _ => Ok(()),
+        };
+    }
+    let json = serde_json::to_vec_pretty(redirect).map_err(|e| e.to_string())?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, json).map_err(|e| format!("無法寫入 {}: {}", tmp.display(), e))?;
+    fs::rename(&tmp, &path).map_err(|e| format!("無法寫入 {}: {}", path.display(), e))
+}
+
+/// The directory a redirect points at, if it's usable.
+pub fn redirected_dir(default_dir: &Path) -> Option<PathBuf> {
+    let dir = read_redirect(default_dir)?.path?;
+    if dir.is_dir() {
+        Some(dir)
+    } else {
+        // Unplugged external drive: fall back rather than recreate an
+        // empty data directory under a mount point.
+        eprintln!(
+            "[Paths] Relocated app data {} is not available; using {}",
+            dir.display(),
+            default_dir.display()
+        );
+        None
+    }
+}
+
+/// Whether app data lives somewhere other than the default directory.
+pub fn is_relocated() -> bool {
+    match (default_app_data_dir(), get_app_data_dir()) {
+        (Ok(default_dir), Ok(current)) => default_dir != current,
+        _ => false,
+    }
+}
+
+/// Top-level entries of `from` that a move carries over.
+fn entries_to_move(from: &Path) -> Result<Vec<String>, String> {
+    let mut names: Vec<String> = fs::read_dir(from)
+        .map_err(|e| format!("無法讀取 {}: {}", from.display(), e))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|name| !PINNED.contains(&name.as_str()))
+        .collect();
+    names.sort();
+    Ok(names)
+}
+
+/// Where data chosen to go to `new_dir` ends up: the default directory
+/// itself when moving back, `{new_dir}/com.classnoteai` otherwise.
+pub fn target_dir(new_dir: &Path, default_dir: &Path) -> Result<PathBuf, String> {
+    if !new_dir.is_absolute() {
+        return Err("請選擇完整路徑的資料夾".to_string());
+    }
+    let new_dir = new_dir
+        .canonicalize()
+        .map_err(|e| format!("無法使用 {}: {}", new_dir.display(), e))?;
+    if !new_dir.is_dir() {
+        return Err(format!("{} 不是資料夾", new_dir.display()));
+    }
+    let default_dir = default_dir
+        .canonicalize()
+        .unwrap_or_else(|_| default_dir.to_path_buf());
+    if new_dir == default_dir || default_dir.parent() == Some(new_dir.as_path()) {
+        return Ok(default_dir);
+    }
+    Ok(new_dir.join(BUNDLE_ID))
+}
+
+/// Reject moves onto itself, into itself, or over existing files.
+fn check_target(from: &Path, to: &Path, names: &[String]) -> Result<(), String> {
+    let from = from.canonicalize().unwrap_or_else(|_| from.to_path_buf());
+    if to == from {
+        return Err("資料已經在這個位置".to_string());
+    }
+    if to.starts_with(&from) || from.starts_with(to) {
+        return Err("新位置不能在目前的資料夾之內，也不能包含它".to_string());
+    }
+    if let Some(name) = names.iter().find(|n| to.join(n).exists()) {
+        return Err(format!(
+            "目標位置已有 {}，請選擇空的資料夾",
+            to.join(name).display()
+        ));
+    }
+    Ok(())
+}
+
+fn walk_files(root: &Path, names: &[String]) -> Vec<(PathBuf, u64)> {
+    names
+        .iter()
+        .flat_map(|name| walkdir::WalkDir::new(root.join(name)))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let len = e.metadata().ok()?.len();
+            Some((e.into_path(), len))
+        })
+        .collect()
+}
+
+fn remove_entry(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Copy `names` from `from` into `to` and verify every file. On error
+/// the copied entries are removed again. Returns (files, bytes).
+pub fn copy_verified(
+    from: &Path,
+    to: &Path,
+    names: &[String],
+    cancel: &AtomicBool,
+    mut on_progress: impl FnMut(u64, u64),
+) -> Result<(usize, u64), String> {
+    let files = walk_files(from, names);
+    let total: u64 = files.iter().map(|(_, len)| len).sum();
+
+    let result = (|| {
+        let mut done = 0u64;
+        for (src, len) in &files {
+            if cancel.load(Ordering::SeqCst) {
+                return Err(crate::tasks::CANCELLED.to_string());
+            }
+            let rel = src.strip_prefix(from).map_err(|e| e.to_string())?;
+            let dest = to.join(rel);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("無法建立 {}: {}", parent.display(), e))?;
+            }
+            fs::copy(src, &dest).map_err(|e| format!("複製 {} 失敗: {}", rel.display(), e))?;
+            verify_copy(src, &dest, *len).map_err(|e| format!("{}: {}", rel.display(), e))?;
+            done += len;
+            on_progress(done, total);
+        }
+        // Empty directories carry no files but may still be expected.
+        for name in names {
+            if from.join(name).is_dir() {
+                fs::create_dir_all(to.join(name)).map_err(|e| e.to_string())?;
+            }
+        }
+        verify_database(&to.join("classnoteai.db"))?;
+        Ok((files.len(), total))
+    })();
+
+    if result.is_err() {
+        for name in names {
+            let _ = remove_entry(&to.join(name));
+        }
+    }
+    result
+}
+
+fn verify_copy(src: &Path, dest: &Path, len: u64) -> Result<(), String> {
+    let copied = fs::metadata(dest)
+        .map(|m| m.len())
+        .map_err(|e| e.to_string())?;
+    if copied != len {
+        return Err(format!("大小不符（{} / {} bytes）", copied, len));
+    }
+    let hash = |p: &Path| crate::diagnostics::health::sha256_file(p).map_err(|e| e.to_string());
+    if hash(src)? != hash(dest)? {
+        return Err("內容校驗失敗，原檔可能在搬移期間被修改，請重試".to_string());
+    }
+    Ok(())
+}
+
+fn verify_database(path: &Path) -> Result<(), String> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let conn =
+        rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .map_err(|e| format!("無法開啟複製的資料庫: {}", e))?;
+    let result: String = conn
+        .query_row("PRAGMA quick_check", [], |row| row.get(0))
+        .map_err(|e| format!("資料庫檢查失敗: {}", e))?;
+    if result != "ok" {
+        return Err(format!("複製的資料庫檢查未通過: {}", result));
+    }
+    Ok(())
+}
+
+/// Copy the app data to `new_dir`, verify it and redirect to it from the
+/// next launch on. See the module docs for the sequence.
+pub fn move_app_data(
+    new_dir: &Path,
+    cancel: &AtomicBool,
+    on_progress: impl FnMut(u64, u64),
+) -> Result<MoveReport, String> {
+    let default_dir = default_app_data_dir()?;
+    let from = get_app_data_dir()?;
+    if read_redirect(&default_dir).is_some_and(|r| r.previous.is_some()) {
+        return Err("上一次搬移尚未完成，請先重新啟動應用程式".to_string());
+    }
+    let to = target_dir(new_dir, &default_dir)?;
+    let names = entries_to_move(&from)?;
+    check_target(&from, &to, &names)?;
+
+    let needed: u64 = walk_files(&from, &names).iter().map(|(_, len)| len).sum();
+    fs::create_dir_all(&to).map_err(|e| format!("無法建立 {}: {}", to.display(), e))?;
+    if let Ok(free) = crate::setup::requirements::available_disk_bytes(&to) {
+        if free < needed + SPACE_MARGIN_BYTES {
+            let _ = fs::remove_dir(&to); // only if we just created it
+            return Err(format!(
+                "目標磁碟空間不足：需要 {:.1} GB，可用 {:.1} GB",
+                (needed + SPACE_MARGIN_BYTES) as f64 / 1e9,
+                free as f64 / 1e9
+            ));
+        }
+    }
+
+    // The database and its journal go last, as a snapshot.
+    let (database, files_only): (Vec<String>, Vec<String>) = names
+        .iter()
+        .cloned()
+        .partition(|n| n.starts_with(DATABASE_FILE));
+    let copied_at = unix_ms(SystemTime::now());
+    let (files, bytes) = copy_verified(&from, &to, &files_only, cancel, on_progress)?;
+    let database_copied_at = unix_ms(SystemTime::now());
+    let mut names = files_only;
+    if database.iter().any(|n| n == DATABASE_FILE) {
+        let db = to.join(DATABASE_FILE);
+        let snapshot = crate::storage::backup::snapshot_db(&from.join(DATABASE_FILE), &db)
+            .and_then(|_| verify_database(&db));
+        if let Err(e) = snapshot {
+            let _ = fs::remove_file(&db);
+            for name in &names {
+                let _ = remove_entry(&to.join(name));
+            }
+            return Err(e);
+        }
+        names.push(DATABASE_FILE.to_string());
+    }
+
+    let redirect = Redirect {
+        path: (to != default_dir).then(|| to.clone()),
+        previous: Some(from.clone()),
+        moved: names.clone(),
+        copied_at: Some(copied_at),
+        database_copied_at: Some(database_copied_at),
+    };
+    if let Err(e) = write_redirect(&default_dir, &redirect) {
+        for name in &names {
+            let _ = remove_entry(&to.join(name));
+        }
+        return Err(e);
+    }
+    log::info!(
+        "[Paths] Copied {} files ({} bytes) and the database from {} to {}; switching on restart",
+        files,
+        bytes,
+        from.display(),
+        to.display()
+    );
+    Ok(MoveReport {
+        from: from.to_string_lossy().to_string(),
+        to: to.to_string_lossy().to_string(),
+        files,
+        bytes,
+        restart_required: true,
+    })
+}
+
+/// Delete the old copy left by a move, now that nothing has it open.
+/// Call once at startup, before anything else touches app data.
+pub fn finish_pending() {
+    let Ok(default_dir) = default_app_data_dir() else {
+        return;
+    };
+    let Some(mut redirect) = read_redirect(&default_dir) else {
+        return;
+    };
+    let Some(previous) = redirect.previous.take() else {
+        return;
+    };
+    let Ok(current) = get_app_data_dir() else {
+        return;
+    };
+    // Only delete when this launch really runs from the new copy.
+    let expected = redirect.path.clone().unwrap_or_else(|| default_dir.clone());
+    if current != expected || redirect.moved.iter().any(|n| !current.join(n).exists()) {
+        eprintln!(
+            "[Paths] Relocated data not found in {}; keeping {}",
+            current.display(),
+            previous.display()
+        );
+        return;
+    }
+    let changed = changed_since_copy(&previous, &current, &redirect);
+    let moved = std::mem::take(&mut redirect.moved);
+    redirect.copied_at = None;
+    redirect.database_copied_at = None;
+    if !changed.is_empty() {
+        // Written between the copy and the restart; only the old copy
+        // has them. Keep it for the user to recover from.
+        eprintln!(
+            "[Paths] {} file(s) in {} changed after they were copied (e.g. {}); keeping it",
+            changed.len(),
+            previous.display(),
+            changed[0].display()
+        );
+        if let Err(e) = write_redirect(&default_dir, &redirect) {
+            eprintln!("[Paths] {}", e);
+        }
+        return;
+    }
+    for name in moved {
+        if let Err(e) = remove_entry(&previous.join(&name)) {
+            eprintln!("[Paths] Could not remove old {}: {}", name, e);
+        }
+    }
+    for suffix in ["-journal", "-wal", "-shm"] {
+        let _ = fs::remove_file(previous.join(format!("{}{}", DATABASE_FILE, suffix)));
+    }
+    if previous != default_dir {
+        let _ = fs::remove_dir(&previous); // only succeeds when empty
+    }
+    match write_redirect(&default_dir, &redirect) {
+        Ok(()) => println!(
+            "[Paths] Removed previous app data at {}",
+            previous.display()
+        ),
+        Err(e) => eprintln!("[Paths] {}", e),
+    }
+}
+
+/// Files of the old copy `previous` written after they were copied whose
+/// new copy in `current` differs, relative to `previous`.
+fn changed_since_copy(previous: &Path, current: &Path, redirect: &Redirect) -> Vec<PathBuf> {
+    // Moved by a version that didn't record when.
+    let Some(copied_at) = redirect.copied_at else {
+        return Vec::new();
+    };
+    walk_files(previous, &redirect.moved)
+        .into_iter()
+        .filter_map(|(path, len)| {
+            let rel = path.strip_prefix(previous).ok()?.to_path_buf();
+            let is_database = rel == Path::new(DATABASE_FILE);
+            let since = if is_database {
+                redirect.database_copied_at.unwrap_or(copied_at)
+            } else {
+                copied_at
+            };
+            let modified = fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .map(unix_ms)
+                .unwrap_or(u64::MAX);
+            if modified < since {
+                return None;
+            }
+            // The database copy is a snapshot, never byte-identical.
+            let same = !is_database && verify_copy(&path, &current.join(&rel), len).is_ok();
+            (!same).then_some(rel)
+        })
+        .collect()
+}
+
+fn unix_ms(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> (tempfile::TempDir, PathBuf, PathBuf) {
+        let tmp = tempfile::tempdir().unwrap();
+        let from = tmp.path().join("from");
+        let to = tmp.path().join("to");
+        fs::create_dir_all(from.join("audio/nested")).unwrap();
+        fs::create_dir_all(from.join("cache")).unwrap();
+        fs::write(from.join("audio/nested/a.wav"), b"audio").unwrap();
+        fs::write(from.join("setup_complete.json"), b"{}").unwrap();
+        fs::write(from.join(REDIRECT_FILE), b"{}").unwrap();
+        (tmp, from, to)
+    }
+
+    #[test]
+    fn pinned_files_stay_behind() {
+        let (_tmp, from, _) = fixture();
+        assert_eq!(
+            entries_to_move(&from).unwrap(),
+            vec!["audio", "cache", "setup_complete.json"]
+        );
+    }
+
+    #[test]
+    fn copies_and_verifies_every_file() {
+        let (_tmp, from, to) = fixture();
+        let names = entries_to_move(&from).unwrap();
+        let mut last = (0, 0);
+        let copied = copy_verified(&from, &to, &names, &AtomicBool::new(false), |d, t| {
+            last = (d, t)
+        })
+        .unwrap();
+        assert_eq!(copied, (2, 7));
+        assert_eq!(last, (7, 7));
+        assert_eq!(fs::read(to.join("audio/nested/a.wav")).unwrap(), b"audio");
+        assert!(to.join("cache").is_dir());
+        assert!(!to.join(REDIRECT_FILE).exists());
+    }
+
+    #[test]
+    fn cancelled_copy_removes_what_it_wrote() {
+        let (_tmp, from, to) = fixture();
+        let names = entries_to_move(&from).unwrap();
+        let err = copy_verified(&from, &to, &names, &AtomicBool::new(true), |_, _| {}).unwrap_err();
+        assert_eq!(err, crate::tasks::CANCELLED);
+        assert!(names.iter().all(|n| !to.join(n).exists()));
+    }
+
+    #[test]
+    fn files_written_after_the_copy_keep_the_old_copy() {
+        let (_tmp, from, to) = fixture();
+        let names = entries_to_move(&from).unwrap();
+        let copied_at = unix_ms(SystemTime::now());
+        copy_verified(&from, &to, &names, &AtomicBool::new(false), |_, _| {}).unwrap();
+        let redirect = Redirect {
+            path: Some(to.clone()),
+            previous: Some(from.clone()),
+            moved: names,
+            copied_at: Some(copied_at),
+            database_copied_at: Some(copied_at),
+        };
+        // Rewritten with the same content: nothing lost.
+        fs::write(from.join("setup_complete.json"), b"{}").unwrap();
+        assert!(changed_since_copy(&from, &to, &redirect).is_empty());
+
+        fs::write(from.join("audio/nested/a.wav"), b"audio, longer").unwrap();
+        assert_eq!(
+            changed_since_copy(&from, &to, &redirect),
+            vec![PathBuf::from("audio/nested/a.wav")]
+        );
+    }
+
+    #[test]
+    fn refuses_nested_or_occupied_targets() {
+        let (_tmp, from, to) = fixture();
+        let names = entries_to_move(&from).unwrap();
+        assert!(check_target(&from, &from.join("inner"), &names).is_err());
+        fs::create_dir_all(to.join("audio")).unwrap();
+        assert!(check_target(&from, &to, &names).is_err());
+        fs::remove_dir(to.join("audio")).unwrap();
+        assert!(check_target(&from, &to, &names).is_ok());
+    }
+
+    #[test]
+    fn empty_redirect_removes_the_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let redirect = Redirect {
+            path: Some(tmp.path().join("ssd")),
+            previous: None,
+            moved: vec![],
+            copied_at: None,
+            database_copied_at: None,
+        };
+        write_redirect(tmp.path(), &redirect).unwrap();
+        assert_eq!(read_redirect(tmp.path()), Some(redirect));
+        write_redirect(tmp.path(), &Redirect::default()).unwrap();
+        assert!(!tmp.path().join(REDIRECT_FILE).exists());
+    }
+}
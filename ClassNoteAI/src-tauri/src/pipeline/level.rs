@@ -0,0 +1,123 @@
+//! Input level meter for the live pipeline.
+//!
+//! Every pushed chunk is measured before it is queued for ASR, so the
+//! meter follows the microphone in real time even when the model is
+//! behind. Readings are taken over 100 ms windows (~10 Hz) and go out
+//! on [`EVENT`] for the VU meter and the clipping warning. The pushed
+//! PCM is what gets recorded, before the ASR-only gain normalisation,
+//! so a clip here is a clip in the saved audio too.
+
+use serde::Serialize;
+
+pub const EVENT: &str = "audio-level";
+/// Reported for digital silence instead of -inf.
+pub const FLOOR_DBFS: f32 = -100.0;
+/// Full-scale samples in one window before it counts as clipping; a
+/// single full-scale sample is usually a transient, not distortion.
+pub const CLIP_SAMPLES: u32 = 3;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioLevel {
+    pub session_id: String,
+    pub peak_dbfs: f32,
+    pub rms_dbfs: f32,
+    pub clipped_samples: u32,
+    pub clipping: bool,
+}
+
+fn to_dbfs(x: f64) -> f32 {
+    if x <= 0.0 {
+        FLOOR_DBFS
+    } else {
+        ((20.0 * x.log10()) as f32).max(FLOOR_DBFS)
+    }
+}
+
+/// Accumulates samples across pushes and yields one reading per
+/// completed 100 ms window.
+#[derive(Debug)]
+pub struct LevelMeter {
+    window: usize,
+    count: usize,
+    peak: i32,
+    sum_squares: f64,
+    clipped: u32,
+}
+
+impl LevelMeter {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            window: (sample_rate as usize / 10).max(1),
+            count: 0,
+            peak: 0,
+            sum_squares: 0.0,
+            clipped: 0,
+        }
+    }
+
+    pub fn push(&mut self, pcm: &[i16], session_id: &str) -> Vec<AudioLevel> {
+        let mut out = Vec::new();
+        for &s in pcm {
+            let a = (s as i32).abs();
+            self.peak = self.peak.max(a);
+            if a >= i16::MAX as i32 {
+                self.clipped += 1;
+            }
+            let x = s as f64 / 32768.0;
+            self.sum_squares += x * x;
+            self.count += 1;
+            if self.count == self.window {
+                out.push(AudioLevel {
+                    session_id: session_id.to_string(),
+                    peak_dbfs: to_dbfs(self.peak as f64 / 32768.0),
+                    rms_dbfs: to_dbfs((self.sum_squares / self.count as f64).sqrt()),
+                    clipped_samples: self.clipped,
+                    clipping: self.clipped >= CLIP_SAMPLES,
+                });
+                self.count = 0;
+                self.peak = 0;
+                self.sum_squares = 0.0;
+                self.clipped = 0;
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_reading_per_100ms_across_chunks() {
+        let mut m = LevelMeter::new(16_000);
+        assert!(m.push(&[0; 1_000], "s").is_empty());
+        let out = m.push(&[0; 2_500], "s");
+        assert_eq!(out.len(), 2);
+        assert_eq!(out[0].peak_dbfs, FLOOR_DBFS);
+        assert_eq!(out[0].rms_dbfs, FLOOR_DBFS);
+        assert!(!out[0].clipping);
+    }
+
+    #[test]
+    fn square_wave_levels_and_clipping() {
+        let mut m = LevelMeter::new(16_000);
+        let half: Vec<i16> = (0..1_600)
+            .map(|i| if i % 2 == 0 { 16_384 } else { -16_384 })
+            .collect();
+        let out = m.push(&half, "s");
+        assert_eq!(out.len(), 1);
+        assert!((out[0].peak_dbfs + 6.02).abs() < 0.01);
+        assert!((out[0].rms_dbfs + 6.02).abs() < 0.01);
+
+        let mut loud = half.clone();
+        loud[10] = i16::MAX;
+        loud[11] = i16::MIN;
+        assert!(!m.push(&loud, "s")[0].clipping);
+        loud[12] = i16::MAX;
+        let out = m.push(&loud, "s");
+        assert_eq!(out[0].clipped_samples, 3);
+        assert!(out[0].clipping);
+    }
+}
@@ -0,0 +1,718 @@
+//! Live lecture pipeline: capture → VAD → ASR → rough translation.
+//!
+//! Until now the renderer stitched these together itself —
+//! `asrPipeline.ts` listened for `asr-text`, segmented sentences and fed
+//! `translationPipeline.ts`. Chunks were pushed with fire-and-forget
+//! `invoke`s that could overtake each other, and a stop racing an
+//! in-flight push or translation lost the tail of the lecture.
+//!
+//! Here the stages are owned by Rust and connected by bounded channels:
+//!
+//! ```text
+//! live_pipeline_push_audio ──(CAPTURE_QUEUE)──▶ ASR thread ──(TRANSLATE_QUEUE)──▶ translator
+//!                                                 │ SilenceTracker + Segmenter       │
+//!                                                 ▼                                  ▼
+//!                                           live-subtitle                      live-subtitle
+//! ```
+//!
+//! Capture itself stays in the webview (all recording is `getUserMedia`
+//! based); pushes await channel capacity, so a slow model applies
+//! backpressure instead of reordering audio. The translator drops a
+//! sentence (with a `translation_failed` event) only when its queue is
+//! full. `stop_live_pipeline` closes the capture channel and waits for
+//! both stages to drain, so every subtitle has been emitted when it
+//! returns.
+//!
+//! Events go out on [`EVENT`] with the same shape as the renderer's
+//! `SubtitleEvent`, so the JS side only has to forward them. ASR and
+//! translation calls run under the [`watchdog`]'s wall-clock limits, so
+//! one hung inference call can't stall the stages behind it.
+
+pub mod level;
+pub mod segmenter;
+pub mod watchdog;
+
+use crate::asr::parakeet_engine;
+use crate::vad::VadConfig;
+use level::LevelMeter;
+use segmenter::{Segmenter, Sentence};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use watchdog::{Stalled, TaskStalled, Worker};
+
+pub const EVENT: &str = "live-subtitle";
+/// Audio chunks (≈ 250 ms each from the recorder) waiting for ASR.
+const CAPTURE_QUEUE: usize = 64;
+/// Committed sentences waiting for translation.
+const TRANSLATE_QUEUE: usize = 256;
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+/// Audio kept for replay while a stalled ASR call blocks the engine
+/// (≈ 2 min of 250 ms chunks); older chunks are dropped past this.
+const ASR_BACKLOG: usize = 480;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct LivePipelineConfig {
+    /// Rate of the pushed PCM; defaults to 16 kHz.
+    pub sample_rate: Option<u32>,
+    /// 'int8' | 'fp32', see `asr_start_session`.
+    pub preferred_variant: Option<String>,
+    pub normalize_gain: bool,
+    pub denoise: bool,
+    /// Defaults to on.
+    pub translate: Option<bool>,
+    pub source_lang: Option<String>,
+    pub target_lang: Option<String>,
+    /// 'gemma' | 'local' | 'google'; falls back through the others.
+    pub provider: Option<String>,
+    pub google_api_key: Option<String>,
+    pub gemma_endpoint: Option<String>,
+    /// Wall-clock limit per ASR push; see [`watchdog::DEFAULT_ASR_LIMIT`].
+    pub asr_timeout_ms: Option<u64>,
+    /// Limit per translation attempt; see
+    /// [`watchdog::DEFAULT_TRANSLATION_LIMIT`].
+    pub translation_timeout_ms: Option<u64>,
+}
+
+impl LivePipelineConfig {
+    fn asr_limit(&self) -> Duration {
+        self.asr_timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(watchdog::DEFAULT_ASR_LIMIT)
+    }
+
+    fn translation_limit(&self) -> Duration {
+        self.translation_timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or(watchdog::DEFAULT_TRANSLATION_LIMIT)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LiveEvent {
+    #[serde(rename_all = "camelCase")]
+    SessionStarted {
+        session_id: String,
+        lecture_id: String,
+        sample_rate: u32,
+        language: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    PartialText {
+        session_id: String,
+        text: String,
+        audio_end_sec: f32,
+    },
+    #[serde(rename_all = "camelCase")]
+    SentenceCommitted {
+        id: String,
+        session_id: String,
+        audio_start_sec: f32,
+        audio_end_sec: f32,
+        wall_clock_ms: u64,
+        text_en: String,
+        speaker_role: &'static str,
+    },
+    #[serde(rename_all = "camelCase")]
+    TranslationReady {
+        id: String,
+        session_id: String,
+        text_zh: String,
+        provider: String,
+        latency_ms: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    TranslationFailed {
+        id: String,
+        session_id: String,
+        error: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    PipelineStatus {
+        session_id: String,
+        translation_queue_depth: usize,
+        oldest_translation_age_ms: u64,
+    },
+    #[serde(rename_all = "camelCase")]
+    SessionEnded {
+        session_id: String,
+        final_wall_clock_ms: u64,
+    },
+}
+
+/// Trailing-silence length from 100 ms RMS windows, using the energy
+/// VAD's threshold. Cheap enough to run on every pushed chunk.
+pub struct SilenceTracker {
+    threshold: f32,
+    trailing_ms: u64,
+}
+
+impl SilenceTracker {
+    pub fn new(threshold: f32) -> Self {
+        Self {
+            threshold,
+            trailing_ms: 0,
+        }
+    }
+
+    /// Feed a chunk; returns how long the input has been quiet.
+    pub fn push(&mut self, pcm: &[i16], sample_rate: u32) -> u64 {
+        let rate = sample_rate.max(1);
+        for window in pcm.chunks((rate as usize / 10).max(1)) {
+            let sum_squares: f64 = window
+                .iter()
+                .map(|&s| {
+                    let x = s as f64 / 32768.0;
+                    x * x
+                })
+                .sum();
+            let rms = (sum_squares / window.len() as f64).sqrt() as f32;
+            if rms <= self.threshold {
+                self.trailing_ms += window.len() as u64 * 1000 / rate as u64;
+            } else {
+                self.trailing_ms = 0;
+            }
+        }
+        self.trailing_ms
+    }
+}
+
+struct TranslationJob {
+    id: String,
+    text: String,
+    enqueued_at: Instant,
+    /// Attempts that hit the watchdog limit so far.
+    attempts: u8,
+}
+
+struct Live {
+    lecture_id: String,
+    session_id: String,
+    app: AppHandle,
+    started: Instant,
+    audio_tx: mpsc::Sender<Vec<i16>>,
+    meter: LevelMeter,
+    asr: JoinHandle<Result<String, String>>,
+    translator: Option<JoinHandle<()>>,
+}
+
+static ACTIVE: Mutex<Option<Live>> = Mutex::const_new(None);
+
+fn emit(app: &AppHandle, event: LiveEvent) {
+    let _ = app.emit(EVENT, event);
+}
+
+/// Start the pipeline for `lecture_id` and return its session id.
+/// Starting again for the same lecture returns the running session.
+#[tauri::command]
+pub async fn start_live_pipeline(
+    app: AppHandle,
+    lecture_id: String,
+    config: Option<LivePipelineConfig>,
+) -> Result<String, String> {
+    let config = config.unwrap_or_default();
+    let mut active = ACTIVE.lock().await;
+    if let Some(live) = active.as_ref() {
+        if live.lecture_id == lecture_id {
+            return Ok(live.session_id.clone());
+        }
+        return Err(format!(
+            "另一堂課 ({}) 的即時轉錄仍在進行，請先停止",
+            live.lecture_id
+        ));
+    }
+
+    let session_id = uuid::Uuid::new_v4().to_string();
+    if let Err(e) = crate::asr_start_session(
+        session_id.clone(),
+        config.preferred_variant.clone(),
+        Some(config.normalize_gain),
+        Some(config.denoise),
+    )
+    .await
+    {
+        crate::telemetry::record("pipeline.live.start_failed");
+        return Err(e.into());
+    }
+    crate::telemetry::record("pipeline.live.start");
+
+    let sample_rate = config.sample_rate.unwrap_or(parakeet_engine::SAMPLE_RATE);
+    let depth = Arc::new(AtomicUsize::new(0));
+    let (translator, translate_tx) = if config.translate.unwrap_or(true) {
+        let (tx, rx) = mpsc::channel(TRANSLATE_QUEUE);
+        let handle = tokio::spawn(run_translator(
+            app.clone(),
+            session_id.clone(),
+            rx,
+            depth.clone(),
+            config.clone(),
+        ));
+        (Some(handle), Some(tx))
+    } else {
+        (None, None)
+    };
+
+    let started = Instant::now();
+    let (audio_tx, audio_rx) = mpsc::channel(CAPTURE_QUEUE);
+    let asr = {
+        let (app, session_id) = (app.clone(), session_id.clone());
+        let limit = config.asr_limit();
+        tokio::task::spawn_blocking(move || {
+            run_asr(
+                &app,
+                &session_id,
+                sample_rate,
+                audio_rx,
+                translate_tx,
+                &depth,
+                started,
+                limit,
+            )
+        })
+    };
+
+    emit(
+        &app,
+        LiveEvent::SessionStarted {
+            session_id: session_id.clone(),
+            lecture_id: lecture_id.clone(),
+            sample_rate,
+            language: config.source_lang.clone(),
+        },
+    );
+    *active = Some(Live {
+        lecture_id,
+        session_id: session_id.clone(),
+        app,
+        started,
+        audio_tx,
+        meter: LevelMeter::new(sample_rate),
+        asr,
+        translator,
+    });
+    Ok(session_id)
+}
+
+/// Queue a chunk of PCM. Waits for room rather than dropping audio, so
+/// callers should await each push before sending the next. The chunk is
+/// metered first, so `audio-level` keeps up even when ASR lags.
+#[tauri::command]
+pub async fn live_pipeline_push_audio(pcm: Vec<i16>) -> Result<(), String> {
+    let tx = {
+        let mut active = ACTIVE.lock().await;
+        let live = active
+            .as_mut()
+            .ok_or_else(|| "沒有進行中的即時轉錄".to_string())?;
+        for reading in live.meter.push(&pcm, &live.session_id) {
+            let _ = live.app.emit(level::EVENT, reading);
+        }
+        live.audio_tx.clone()
+    };
+    tx.send(pcm).await.map_err(|_| "即時轉錄已結束".to_string())
+}
+
+/// Flush the decoder, commit the remaining text, wait for outstanding
+/// translations, and return the final transcript.
+#[tauri::command]
+pub async fn stop_live_pipeline() -> Result<String, String> {
+    let Some(live) = ACTIVE.lock().await.take() else {
+        return Err("沒有進行中的即時轉錄".to_string());
+    };
+    drop(live.audio_tx);
+    let result = live
+        .asr
+        .await
+        .map_err(|e| format!("live ASR task join error: {e}"))?;
+    crate::telemetry::record(if result.is_ok() {
+        "pipeline.live.completed"
+    } else {
+        "pipeline.live.asr_failed"
+    });
+    // The ASR thread dropped the translation sender on exit, so this
+    // finishes once the queue is drained.
+    if let Some(translator) = live.translator {
+        let _ = translator.await;
+    }
+    emit(
+        &live.app,
+        LiveEvent::SessionEnded {
+            session_id: live.session_id,
+            final_wall_clock_ms: live.started.elapsed().as_millis() as u64,
+        },
+    );
+    result
+}
+
+/// Partial transcript and audio position reported by one ASR push.
+type AsrUpdate = Option<(String, f32)>;
+
+#[allow(clippy::too_many_arguments)]
+fn run_asr(
+    app: &AppHandle,
+    session_id: &str,
+    sample_rate: u32,
+    mut audio_rx: mpsc::Receiver<Vec<i16>>,
+    translate_tx: Option<mpsc::Sender<TranslationJob>>,
+    depth: &AtomicUsize,
+    started: Instant,
+    limit: Duration,
+) -> Result<String, String> {
+    let worker = Worker::spawn("live-asr")?;
+    let mut backlog: VecDeque<Vec<i16>> = VecDeque::new();
+    let mut segmenter = Segmenter::new();
+    let mut silence = SilenceTracker::new(VadConfig::default().energy_threshold);
+    let mut transcript = String::new();
+    let mut audio_end_sec = 0.0f32;
+
+    let commit = |sentences: Vec<Sentence>| {
+        for sentence in sentences {
+            let id = uuid::Uuid::new_v4().to_string();
+            emit(
+                app,
+                LiveEvent::SentenceCommitted {
+                    id: id.clone(),
+                    session_id: session_id.to_string(),
+                    audio_start_sec: sentence.start_sec,
+                    audio_end_sec: sentence.end_sec,
+                    wall_clock_ms: started.elapsed().as_millis() as u64,
+                    text_en: sentence.text.clone(),
+                    speaker_role: "unknown",
+                },
+            );
+            let Some(tx) = &translate_tx else { continue };
+            depth.fetch_add(1, Ordering::SeqCst);
+            let job = TranslationJob {
+                id: id.clone(),
+                text: sentence.text,
+                enqueued_at: Instant::now(),
+                attempts: 0,
+            };
+            if let Err(e) = tx.try_send(job) {
+                depth.fetch_sub(1, Ordering::SeqCst);
+                if matches!(e, TrySendError::Full(_)) {
+                    emit(
+                        app,
+                        LiveEvent::TranslationFailed {
+                            id,
+                            session_id: session_id.to_string(),
+                            error: "translation backlog full".to_string(),
+                        },
+                    );
+                }
+            }
+        }
+    };
+
+    // Feed queued audio to the engine until it's drained or the worker
+    // stalls. A timed-out chunk is still being decoded by the abandoned
+    // call (its text shows up in the next cumulative transcript), so it
+    // leaves the backlog; chunks behind it wait and are retried.
+    let drain = |backlog: &mut VecDeque<Vec<i16>>| -> AsrUpdate {
+        let mut latest = None;
+        while let Some(chunk) = backlog.front().cloned() {
+            let sid = session_id.to_string();
+            let pushed = worker.run(limit, move || {
+                let mut latest: AsrUpdate = None;
+                parakeet_engine::push_pcm_i16_at(&sid, &chunk, sample_rate, |_, t, end| {
+                    latest = Some((t.to_string(), end));
+                })
+                .map(|_| latest)
+            });
+            match pushed {
+                Ok(Ok(update)) => {
+                    backlog.pop_front();
+                    latest = update.or(latest);
+                }
+                Ok(Err(e)) => {
+                    eprintln!("[pipeline] push_pcm failed: {}", e);
+                    backlog.pop_front();
+                }
+                Err(Stalled::TimedOut(elapsed)) => {
+                    backlog.pop_front();
+                    crate::telemetry::record("pipeline.live.asr_stalled");
+                    let _ = app.emit(
+                        watchdog::EVENT,
+                        TaskStalled {
+                            session_id: session_id.to_string(),
+                            task: "asr",
+                            segment_id: None,
+                            elapsed_ms: elapsed.as_millis() as u64,
+                            limit_ms: limit.as_millis() as u64,
+                            action: "abandoned",
+                            retry: true,
+                        },
+                    );
+                    break;
+                }
+                Err(Stalled::Busy) => break,
+            }
+        }
+        latest
+    };
+
+    while let Some(pcm) = audio_rx.blocking_recv() {
+        let silence_ms = silence.push(&pcm, sample_rate);
+        backlog.push_back(pcm);
+        if backlog.len() > ASR_BACKLOG {
+            backlog.pop_front();
+            eprintln!("[pipeline] ASR stalled; dropped the oldest buffered chunk");
+        }
+        let latest = drain(&mut backlog);
+        let changed = latest.is_some();
+        if let Some((t, end)) = latest {
+            transcript = t;
+            audio_end_sec = end;
+        }
+        let sentences = segmenter.update(&transcript, audio_end_sec, silence_ms);
+        let committed = !sentences.is_empty();
+        commit(sentences);
+        if changed || committed {
+            emit(
+                app,
+                LiveEvent::PartialText {
+                    session_id: session_id.to_string(),
+                    text: segmenter.preview(&transcript).to_string(),
+                    audio_end_sec,
+                },
+            );
+        }
+    }
+
+    // Capture closed: give a stalled call one more limit to return,
+    // replay what it held up, then flush the decoder. If the engine is
+    // still stuck, the session is abandoned with the text we have.
+    let ended = if worker.wait_idle(limit) {
+        if let Some((t, end)) = drain(&mut backlog) {
+            transcript = t;
+            audio_end_sec = end;
+        }
+        let sid = session_id.to_string();
+        worker
+            .run(limit, move || {
+                let mut tail_end: Option<f32> = None;
+                parakeet_engine::end_session(&sid, |_, _, end| tail_end = Some(end))
+                    .map(|t| (t, tail_end))
+            })
+            .unwrap_or_else(|_| Err("ASR engine stalled".to_string()))
+    } else {
+        Err("ASR engine stalled".to_string())
+    };
+    if let Ok((t, tail_end)) = &ended {
+        if !t.trim().is_empty() {
+            transcript = t.clone();
+        }
+        if let Some(end) = tail_end {
+            audio_end_sec = *end;
+        }
+    }
+    commit(segmenter.finish(&transcript, audio_end_sec));
+    if worker.is_busy() {
+        // Keep what was transcribed rather than failing the stop.
+        eprintln!(
+            "[pipeline] ASR still stalled at stop; abandoned session {} with {} chunks unprocessed",
+            session_id,
+            backlog.len()
+        );
+        return Ok(transcript);
+    }
+    ended.map(|_| transcript)
+}
+
+async fn run_translator(
+    app: AppHandle,
+    session_id: String,
+    mut rx: mpsc::Receiver<TranslationJob>,
+    depth: Arc<AtomicUsize>,
+    config: LivePipelineConfig,
+) {
+    let limit = config.translation_limit();
+    // Sentences whose first attempt stalled; retried once the queue is
+    // idle so they don't hold up the ones behind them.
+    let mut retries: VecDeque<TranslationJob> = VecDeque::new();
+    loop {
+        let mut job = match rx.try_recv() {
+            Ok(job) => job,
+            Err(_) => match retries.pop_front() {
+                Some(job) => job,
+                None => match rx.recv().await {
+                    Some(job) => job,
+                    None => break,
+                },
+            },
+        };
+        let remaining = depth.fetch_sub(1, Ordering::SeqCst).saturating_sub(1);
+        emit(
+            &app,
+            LiveEvent::PipelineStatus {
+                session_id: session_id.clone(),
+                translation_queue_depth: remaining,
+                oldest_translation_age_ms: job.enqueued_at.elapsed().as_millis() as u64,
+            },
+        );
+        let t0 = Instant::now();
+        let event = match tokio::time::timeout(limit, translate(&job.text, &config)).await {
+            Ok(Ok((text_zh, provider))) => LiveEvent::TranslationReady {
+                id: job.id,
+                session_id: session_id.clone(),
+                text_zh,
+                provider,
+                latency_ms: t0.elapsed().as_millis() as u64,
+            },
+            Ok(Err(error)) => {
+                crate::telemetry::record("pipeline.live.translation_failed");
+                LiveEvent::TranslationFailed {
+                    id: job.id,
+                    session_id: session_id.clone(),
+                    error,
+                }
+            }
+            Err(_) => {
+                crate::telemetry::record("pipeline.live.translation_stalled");
+                let retry = job.attempts == 0;
+                let _ = app.emit(
+                    watchdog::EVENT,
+                    TaskStalled {
+                        session_id: session_id.clone(),
+                        task: "translation",
+                        segment_id: Some(job.id.clone()),
+                        elapsed_ms: t0.elapsed().as_millis() as u64,
+                        limit_ms: limit.as_millis() as u64,
+                        action: "cancelled",
+                        retry,
+                    },
+                );
+                if retry {
+                    job.attempts += 1;
+                    depth.fetch_add(1, Ordering::SeqCst);
+                    retries.push_back(job);
+                    continue;
+                }
+                LiveEvent::TranslationFailed {
+                    id: job.id,
+                    session_id: session_id.clone(),
+                    error: format!("translation timed out after {} ms", limit.as_millis()),
+                }
+            }
+        };
+        emit(&app, event);
+    }
+}
+
+/// Same fallback chain as `translationService.translateRough`: the
+/// chosen provider, then gemma, then google; first non-empty result
+/// wins. The whole chain is retried once after a short pause since
+/// llama-server hiccups mid-lecture are common.
+async fn translate(text: &str, config: &LivePipelineConfig) -> Result<(String, String), String> {
+    let source = config.source_lang.clone().unwrap_or_else(|| "en".into());
+    let source = if source == "auto" {
+        "en".into()
+    } else {
+        source
+    };
+    let target = config.target_lang.clone().unwrap_or_else(|| "zh-TW".into());
+    let mut order: Vec<&str> = Vec::with_capacity(3);
+    for p in [
+        config.provider.as_deref().unwrap_or("gemma"),
+        "gemma",
+        "google",
+    ] {
+        if !order.contains(&p) {
+            order.push(p);
+        }
+    }
+
+    let mut last_error = String::new();
+    for attempt in 0..2 {
+        if attempt > 0 {
+            tokio::time::sleep(RETRY_DELAY).await;
+        }
+        for provider in &order {
+            match crate::translate_rough(
+                text.to_string(),
+                source.clone(),
+                target.clone(),
+                Some(provider.to_string()),
+                config.google_api_key.clone(),
+                config.gemma_endpoint.clone(),
+            )
+            .await
+            {
+                Ok(r) if !r.translated_text.trim().is_empty() => {
+                    return Ok((r.translated_text, provider.to_string()));
+                }
+                Ok(_) => last_error = "translator returned empty".to_string(),
+                Err(e) => last_error = e.message,
+            }
+        }
+    }
+    Err(last_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn silence_accumulates_and_resets_on_speech() {
+        let mut t = SilenceTracker::new(0.01);
+        assert_eq!(t.push(&[0; 8_000], 16_000), 500);
+        assert_eq!(t.push(&[0; 8_000], 16_000), 1_000);
+        let loud: Vec<i16> = (0..1_600)
+            .map(|i| if i % 2 == 0 { 8_000 } else { -8_000 })
+            .collect();
+        assert_eq!(t.push(&loud, 16_000), 0);
+        assert_eq!(t.push(&[0; 1_600], 16_000), 100);
+    }
+
+    #[test]
+    fn events_match_the_renderer_subtitle_event_shape() {
+        let v = serde_json::to_value(LiveEvent::SentenceCommitted {
+            id: "s1".into(),
+            session_id: "abc".into(),
+            audio_start_sec: 1.0,
+            audio_end_sec: 2.5,
+            wall_clock_ms: 3_000,
+            text_en: "hello".into(),
+            speaker_role: "unknown",
+        })
+        .unwrap();
+        assert_eq!(v["kind"], "sentence_committed");
+        assert_eq!(v["sessionId"], "abc");
+        assert_eq!(v["audioEndSec"], 2.5);
+        assert_eq!(v["textEn"], "hello");
+        assert_eq!(v["speakerRole"], "unknown");
+
+        let v = serde_json::to_value(LiveEvent::PipelineStatus {
+            session_id: "abc".into(),
+            translation_queue_depth: 2,
+            oldest_translation_age_ms: 40,
+        })
+        .unwrap();
+        assert_eq!(v["kind"], "pipeline_status");
+        assert_eq!(v["translationQueueDepth"], 2);
+    }
+
+    #[test]
+    fn config_defaults_when_fields_are_missing() {
+        let c: LivePipelineConfig =
+            serde_json::from_str(r#"{"provider":"google","normalize_gain":true}"#).unwrap();
+        assert_eq!(c.provider.as_deref(), Some("google"));
+        assert!(c.normalize_gain && !c.denoise);
+        assert_eq!(c.translate, None);
+        assert_eq!(c.asr_limit(), watchdog::DEFAULT_ASR_LIMIT);
+
+        let c: LivePipelineConfig =
+            serde_json::from_str(r#"{"asr_timeout_ms":2500,"translation_timeout_ms":5000}"#)
+                .unwrap();
+        assert_eq!(c.asr_limit(), Duration::from_millis(2_500));
+        assert_eq!(c.translation_limit(), Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,231 @@
+//! Cumulative transcript → committed sentences.
+//!
+//! Rust port of the boundary rules `asrPipeline.ts` used to run on
+//! every `asr-text` event, plus a VAD signal the JS side never had: a
+//! long enough pause commits whatever is buffered, so a lecturer who
+//! trails off mid-sentence doesn't leave it hanging until the hard cap.
+//!
+//! The engine re-decodes as it goes, so a later snapshot may rewrite
+//! text we already committed. Committed sentences are never retracted;
+//! the committed prefix is shrunk to what still matches and decoding
+//! carries on from there, same as the JS version.
+
+/// Fewer words than this never end on punctuation alone ("Dr. Smith").
+pub const MIN_WORDS: usize = 6;
+/// Cut here if no terminator shows up in time.
+pub const HARD_MAX_WORDS: usize = 68;
+pub const HARD_MAX_SEC: f32 = 30.0;
+/// Forced cuts without punctuation land at most this many words in.
+pub const PREFERRED_MAX_WORDS: usize = 42;
+/// Trailing silence that commits the buffered tail.
+pub const SILENCE_FLUSH_MS: u64 = 900;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sentence {
+    pub text: String,
+    pub start_sec: f32,
+    pub end_sec: f32,
+}
+
+#[derive(Debug, Default)]
+pub struct Segmenter {
+    committed: String,
+    tail_start_sec: f32,
+}
+
+fn is_strong_end(word: &str) -> bool {
+    word.ends_with(['.', '!', '?', '。', '！', '？'])
+}
+
+fn is_soft_end(word: &str) -> bool {
+    word.ends_with([',', ';', ':', '，', '；', '：'])
+}
+
+/// Byte ranges of the whitespace-separated words in `s`.
+fn word_spans(s: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = None;
+    for (i, c) in s.char_indices() {
+        match (c.is_whitespace(), start) {
+            (true, Some(st)) => {
+                spans.push((st, i));
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    if let Some(st) = start {
+        spans.push((st, s.len()));
+    }
+    spans
+}
+
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, ca), cb)| ca != cb)
+        .map(|((i, _), _)| i)
+        .unwrap_or_else(|| a.len().min(b.len()))
+}
+
+impl Segmenter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Uncommitted text of `transcript`, for the live preview line.
+    pub fn preview<'a>(&self, transcript: &'a str) -> &'a str {
+        transcript.get(self.committed.len()..).unwrap_or("").trim()
+    }
+
+    /// Feed the latest cumulative transcript. `silence_ms` is how long
+    /// the input has been quiet as of `audio_end_sec`.
+    pub fn update(
+        &mut self,
+        transcript: &str,
+        audio_end_sec: f32,
+        silence_ms: u64,
+    ) -> Vec<Sentence> {
+        self.cut(transcript, audio_end_sec, silence_ms >= SILENCE_FLUSH_MS)
+    }
+
+    /// Commit everything left at the end of the session.
+    pub fn finish(&mut self, transcript: &str, audio_end_sec: f32) -> Vec<Sentence> {
+        self.cut(transcript, audio_end_sec, true)
+    }
+
+    fn cut(&mut self, transcript: &str, audio_end_sec: f32, flush: bool) -> Vec<Sentence> {
+        if !transcript.starts_with(&self.committed) {
+            // Back off to a word boundary so the revised word is
+            // re-emitted whole rather than from its first changed char.
+            let keep = common_prefix_len(&self.committed, transcript);
+            let keep = self.committed[..keep]
+                .char_indices()
+                .rev()
+                .find(|(_, c)| c.is_whitespace())
+                .map(|(i, c)| i + c.len_utf8())
+                .unwrap_or(0);
+            self.committed.truncate(keep);
+        }
+        let mut out = Vec::new();
+        loop {
+            let base = self.committed.len();
+            let tail = &transcript[base..];
+            let words = word_spans(tail);
+            if words.is_empty() {
+                return out;
+            }
+            let span_sec = (audio_end_sec - self.tail_start_sec).max(0.0);
+            let strong = words
+                .iter()
+                .enumerate()
+                .skip(MIN_WORDS - 1)
+                .find(|(_, &(a, b))| is_strong_end(&tail[a..b]))
+                .map(|(i, _)| i + 1);
+            let n = match strong {
+                Some(n) => n,
+                None if flush => words.len(),
+                None if words.len() >= HARD_MAX_WORDS || span_sec >= HARD_MAX_SEC => {
+                    let limit = words.len().min(PREFERRED_MAX_WORDS);
+                    words[..limit]
+                        .iter()
+                        .enumerate()
+                        .skip(MIN_WORDS - 1)
+                        .rev()
+                        .find(|(_, &(a, b))| is_soft_end(&tail[a..b]))
+                        .map(|(i, _)| i + 1)
+                        .unwrap_or(limit)
+                }
+                None => return out,
+            };
+
+            let end_byte = words[n - 1].1;
+            let end_sec = if n == words.len() {
+                audio_end_sec
+            } else {
+                self.tail_start_sec + span_sec * n as f32 / words.len() as f32
+            };
+            out.push(Sentence {
+                text: tail[words[0].0..end_byte].to_string(),
+                start_sec: self.tail_start_sec,
+                end_sec,
+            });
+            self.committed.push_str(&tail[..end_byte]);
+            self.tail_start_sec = end_sec;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn commits_at_terminator_after_min_words_only() {
+        let mut s = Segmenter::new();
+        assert!(s.update("Dr. Smith said", 1.0, 0).is_empty());
+        let out = s.update("Dr. Smith said this is the result. And then", 4.0, 0);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].text, "Dr. Smith said this is the result.");
+        assert_eq!(out[0].start_sec, 0.0);
+        assert_eq!(
+            s.preview("Dr. Smith said this is the result. And then"),
+            "And then"
+        );
+    }
+
+    #[test]
+    fn silence_flushes_the_buffered_tail() {
+        let mut s = Segmenter::new();
+        assert!(s.update("so what we have here", 2.0, 300).is_empty());
+        let out = s.update("so what we have here", 2.0, SILENCE_FLUSH_MS);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].end_sec, 2.0);
+        assert_eq!(s.preview("so what we have here"), "");
+    }
+
+    #[test]
+    fn hard_cap_prefers_soft_punctuation() {
+        let mut words: Vec<String> = (0..70).map(|i| format!("w{i}")).collect();
+        words[20] = "w20,".to_string();
+        let text = words.join(" ");
+        let mut s = Segmenter::new();
+        let out = s.update(&text, 10.0, 0);
+        assert_eq!(out.len(), 1);
+        assert!(out[0].text.ends_with("w20,"));
+        assert_eq!(out[0].text.split_whitespace().count(), 21);
+        assert!((out[0].end_sec - 10.0 * 21.0 / 70.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rewritten_prefix_is_not_committed_twice() {
+        let mut s = Segmenter::new();
+        let first = s.update("one two three four five six.", 3.0, 0);
+        assert_eq!(first.len(), 1);
+        // The decoder revised the last word of the committed sentence.
+        let out = s.finish("one two three four five sticks. seven", 5.0);
+        assert_eq!(out.len(), 1);
+        assert_eq!(out[0].text, "sticks. seven");
+    }
+
+    #[test]
+    fn finish_splits_multiple_sentences() {
+        let mut s = Segmenter::new();
+        let out = s.finish(
+            "this is the first full sentence. this is the second full sentence. tail",
+            9.0,
+        );
+        let texts: Vec<&str> = out.iter().map(|x| x.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "this is the first full sentence.",
+                "this is the second full sentence.",
+                "tail"
+            ]
+        );
+        assert_eq!(out[2].end_sec, 9.0);
+        assert!(out[0].end_sec < out[1].end_sec);
+    }
+}
@@ -0,0 +1,151 @@
+//! Wall-clock limits for inference calls in the live pipeline.
+//!
+//! A hung ONNX call used to freeze the whole pipeline: the ASR thread
+//! sat inside `push_pcm`, capture backpressure filled up, and the
+//! renderer's pushes stalled with it. ONNX Runtime can't interrupt a
+//! running session, so the ASR call runs on a [`Worker`] thread and the
+//! pipeline only waits [`Worker::run`]'s limit for it. Past that the
+//! call is *abandoned* — it keeps its thread (and the engine lock)
+//! until it returns on its own, while the pipeline keeps the audio it
+//! couldn't process and replays it once the worker is free again.
+//!
+//! Translation is plain async, so a stalled request is simply
+//! cancelled by timing it out.
+//!
+//! Either way a [`TaskStalled`] goes out on [`EVENT`].
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+pub const EVENT: &str = "task-stalled";
+/// Default limit for one ASR push (≈ 250 ms of audio; normally < 100 ms).
+pub const DEFAULT_ASR_LIMIT: Duration = Duration::from_secs(10);
+/// Default limit for one translation attempt through the provider chain.
+pub const DEFAULT_TRANSLATION_LIMIT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskStalled {
+    pub session_id: String,
+    /// `asr` | `translation`
+    pub task: &'static str,
+    /// Sentence id for translation; `None` for ASR audio.
+    pub segment_id: Option<String>,
+    pub elapsed_ms: u64,
+    pub limit_ms: u64,
+    /// `abandoned` (left running, result ignored) | `cancelled`
+    pub action: &'static str,
+    /// Whether the segment will be tried again.
+    pub retry: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stalled {
+    /// The call ran past the limit and was abandoned.
+    TimedOut(Duration),
+    /// An earlier abandoned call still hasn't returned.
+    Busy,
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A single thread that runs blocking calls one at a time.
+pub struct Worker {
+    tx: mpsc::Sender<Job>,
+    busy: Arc<AtomicBool>,
+}
+
+impl Worker {
+    pub fn spawn(name: &str) -> Result<Self, String> {
+        let (tx, rx) = mpsc::channel::<Job>();
+        std::thread::Builder::new()
+            .name(name.to_string())
+            .spawn(move || {
+                while let Ok(job) = rx.recv() {
+                    job();
+                }
+            })
+            .map_err(|e| format!("spawn {name} worker: {e}"))?;
+        Ok(Self {
+            tx,
+            busy: Arc::new(AtomicBool::new(false)),
+        })
+    }
+
+    /// Whether a call (possibly an abandoned one) is still running.
+    pub fn is_busy(&self) -> bool {
+        self.busy.load(Ordering::SeqCst)
+    }
+
+    /// Run `f` on the worker and wait at most `limit` for it.
+    pub fn run<T, F>(&self, limit: Duration, f: F) -> Result<T, Stalled>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        if self.busy.swap(true, Ordering::SeqCst) {
+            return Err(Stalled::Busy);
+        }
+        let (reply_tx, reply_rx) = mpsc::channel();
+        let busy = self.busy.clone();
+        let job: Job = Box::new(move || {
+            let out = f();
+            busy.store(false, Ordering::SeqCst);
+            let _ = reply_tx.send(out);
+        });
+        if self.tx.send(job).is_err() {
+            // Worker thread is gone; nothing is running.
+            self.busy.store(false, Ordering::SeqCst);
+            return Err(Stalled::Busy);
+        }
+        let started = Instant::now();
+        reply_rx
+            .recv_timeout(limit)
+            .map_err(|_| Stalled::TimedOut(started.elapsed()))
+    }
+
+    /// Wait up to `limit` for an abandoned call to return.
+    pub fn wait_idle(&self, limit: Duration) -> bool {
+        let deadline = Instant::now() + limit;
+        while self.is_busy() {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fast_calls_return_their_result() {
+        let worker = Worker::spawn("test").unwrap();
+        assert_eq!(worker.run(Duration::from_secs(1), || 1 + 1), Ok(2));
+        assert!(!worker.is_busy());
+    }
+
+    #[test]
+    fn hung_call_is_abandoned_and_blocks_until_it_returns() {
+        let worker = Worker::spawn("test").unwrap();
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let stalled = worker.run(Duration::from_millis(20), move || {
+            let _ = release_rx.recv();
+        });
+        assert!(matches!(stalled, Err(Stalled::TimedOut(_))));
+        assert!(worker.is_busy());
+        assert_eq!(
+            worker.run(Duration::from_millis(20), || ()),
+            Err(Stalled::Busy)
+        );
+
+        release_tx.send(()).unwrap();
+        assert!(worker.wait_idle(Duration::from_secs(1)));
+        assert_eq!(worker.run(Duration::from_secs(1), || "ok"), Ok("ok"));
+    }
+}
@@ -0,0 +1,266 @@
+//! rodio backend for [`super`]. One long-lived thread owns the output
+//! stream; commands talk to it over a channel and read the position
+//! straight off the shared [`PlaybackClock`].
+
+use super::{FrameReader, PlaybackClock, PlaybackStatus};
+use rodio::{OutputStream, Sink, Source};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+const TICK: Duration = Duration::from_millis(100);
+
+/// rodio pulls samples through this; all the interesting logic lives in
+/// [`FrameReader`] so it can be tested without an audio device.
+struct ReaderSource(FrameReader);
+
+impl Iterator for ReaderSource {
+    type Item = i16;
+
+    fn next(&mut self) -> Option<i16> {
+        self.0.next_sample()
+    }
+}
+
+impl Source for ReaderSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.0.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.0.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+enum Command {
+    /// Replace whatever is loaded; the new sink starts paused.
+    Load(AppHandle, FrameReader),
+    Play,
+    Pause,
+    Stop,
+}
+
+#[derive(Default)]
+struct Loaded {
+    lecture_id: Option<String>,
+    /// Kept so a source that ran off the end can be reopened by
+    /// play / seek, the way `<audio>` behaves after `ended`.
+    wav: Option<PathBuf>,
+    app: Option<AppHandle>,
+    clock: Option<Arc<PlaybackClock>>,
+    playing: bool,
+    ended: bool,
+}
+
+struct Engine {
+    tx: Sender<Command>,
+    state: Arc<Mutex<Loaded>>,
+}
+
+static ENGINE: OnceLock<Result<Engine, String>> = OnceLock::new();
+
+fn engine() -> Result<&'static Engine, String> {
+    ENGINE
+        .get_or_init(|| {
+            let (tx, rx) = mpsc::channel();
+            let (ready_tx, ready_rx) = mpsc::channel();
+            let state = Arc::new(Mutex::new(Loaded::default()));
+            let thread_state = state.clone();
+            std::thread::Builder::new()
+                .name("playback".into())
+                .spawn(move || run(rx, thread_state, ready_tx))
+                .map_err(|e| format!("spawn playback thread: {e}"))?;
+            ready_rx
+                .recv()
+                .map_err(|_| "playback thread exited during init".to_string())??;
+            Ok(Engine { tx, state })
+        })
+        .as_ref()
+        .map_err(|e| e.clone())
+}
+
+fn lock(state: &Mutex<Loaded>) -> MutexGuard<'_, Loaded> {
+    state.lock().unwrap_or_else(|p| p.into_inner())
+}
+
+fn snapshot(state: &Loaded) -> PlaybackStatus {
+    PlaybackStatus {
+        lecture_id: state.lecture_id.clone(),
+        position_ms: state.clock.as_ref().map(|c| c.position_ms()).unwrap_or(0),
+        duration_ms: state.clock.as_ref().map(|c| c.duration_ms()).unwrap_or(0),
+        playing: state.playing,
+        ended: state.ended,
+    }
+}
+
+fn run(rx: Receiver<Command>, state: Arc<Mutex<Loaded>>, ready: Sender<Result<(), String>>) {
+    // `_stream` must outlive every Sink created from `handle`.
+    let (_stream, handle) = match OutputStream::try_default() {
+        Ok(pair) => {
+            let _ = ready.send(Ok(()));
+            pair
+        }
+        Err(e) => {
+            let _ = ready.send(Err(format!("無法開啟音訊輸出裝置: {e}")));
+            return;
+        }
+    };
+    let mut sink: Option<Sink> = None;
+    let mut app: Option<AppHandle> = None;
+
+    loop {
+        match rx.recv_timeout(TICK) {
+            Ok(Command::Load(a, reader)) => match Sink::try_new(&handle) {
+                Ok(s) => {
+                    s.pause();
+                    s.append(ReaderSource(reader));
+                    sink = Some(s);
+                    app = Some(a);
+                }
+                Err(e) => log::error!("[playback] sink: {e}"),
+            },
+            Ok(Command::Play) => {
+                if let Some(s) = &sink {
+                    s.play();
+                }
+            }
+            Ok(Command::Pause) => {
+                if let Some(s) = &sink {
+                    s.pause();
+                }
+            }
+            Ok(Command::Stop) => {
+                sink = None;
+                app = None;
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        let Some(s) = &sink else { continue };
+        let status = {
+            let mut st = lock(&state);
+            let was_ended = st.ended;
+            st.ended = s.empty();
+            st.playing = !s.is_paused() && !st.ended;
+            // Emit while playing, plus once on the transition to ended.
+            (st.playing || (st.ended && !was_ended)).then(|| snapshot(&st))
+        };
+        if let (Some(status), Some(app)) = (status, &app) {
+            let _ = app.emit("playback-position", status);
+        }
+    }
+}
+
+/// Open `wav` positioned at `frame`, publish its clock, and hand it to
+/// the thread. The clock is published here (not by the thread) so a
+/// get_position right after load / seek already sees it.
+fn queue(
+    engine: &Engine,
+    app: AppHandle,
+    lecture_id: String,
+    wav: PathBuf,
+    frame: u64,
+) -> Result<(), String> {
+    let mut reader = FrameReader::open(&wav)?;
+    if frame > 0 {
+        reader.rewind_to(frame);
+    }
+    {
+        let mut st = lock(&engine.state);
+        *st = Loaded {
+            lecture_id: Some(lecture_id),
+            wav: Some(wav),
+            app: Some(app.clone()),
+            clock: Some(reader.clock()),
+            playing: false,
+            ended: false,
+        };
+    }
+    engine
+        .tx
+        .send(Command::Load(app, reader))
+        .map_err(|_| "playback thread is gone".to_string())
+}
+
+/// Re-open the loaded file at `frame` after it ran off the end.
+fn requeue(engine: &Engine, frame: u64) -> Result<(), String> {
+    let (app, lecture_id, wav) = {
+        let st = lock(&engine.state);
+        match (&st.app, &st.lecture_id, &st.wav) {
+            (Some(a), Some(id), Some(w)) => (a.clone(), id.clone(), w.clone()),
+            _ => return Err("尚未載入錄音".to_string()),
+        }
+    };
+    queue(engine, app, lecture_id, wav, frame)
+}
+
+fn send(engine: &Engine, cmd: Command) -> Result<(), String> {
+    engine
+        .tx
+        .send(cmd)
+        .map_err(|_| "playback thread is gone".to_string())
+}
+
+pub fn load(app: AppHandle, lecture_id: String, wav: PathBuf) -> Result<PlaybackStatus, String> {
+    let engine = engine()?;
+    queue(engine, app, lecture_id, wav, 0)?;
+    status()
+}
+
+pub fn play() -> Result<PlaybackStatus, String> {
+    let engine = engine()?;
+    if lock(&engine.state).ended {
+        // Play after the end restarts from the top, like <audio>.
+        requeue(engine, 0)?;
+    }
+    send(engine, Command::Play)?;
+    lock(&engine.state).playing = true;
+    status()
+}
+
+pub fn pause() -> Result<PlaybackStatus, String> {
+    let engine = engine()?;
+    send(engine, Command::Pause)?;
+    lock(&engine.state).playing = false;
+    status()
+}
+
+pub fn seek(position_ms: u64) -> Result<PlaybackStatus, String> {
+    let engine = engine()?;
+    let (clock, ended) = {
+        let st = lock(&engine.state);
+        (st.clock.clone(), st.ended)
+    };
+    let clock = clock.ok_or_else(|| "尚未載入錄音".to_string())?;
+    if ended {
+        let frame = (position_ms * clock.sample_rate as u64 / 1000).min(clock.total_frames);
+        requeue(engine, frame)?;
+    } else {
+        clock.request_seek_ms(position_ms);
+    }
+    status()
+}
+
+pub fn status() -> Result<PlaybackStatus, String> {
+    let engine = engine()?;
+    let st = lock(&engine.state);
+    Ok(snapshot(&st))
+}
+
+pub fn stop() -> Result<(), String> {
+    let engine = engine()?;
+    send(engine, Command::Stop)?;
+    *lock(&engine.state) = Loaded::default();
+    Ok(())
+}
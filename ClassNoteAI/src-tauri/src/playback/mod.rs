@@ -0,0 +1,389 @@
+//! Native lecture playback with sample-accurate seek.
+//!
+//! The review page used to play recordings through a webview `<audio>`
+//! element. On a 90-minute WAV served over the asset protocol, WebView2
+//! and WKWebView both land seeks a few hundred ms off (and WebKit
+//! occasionally snaps to the start of its range request), which drags
+//! the subtitle highlight out of sync. Here the audio is played by the
+//! OS mixer via rodio, and the position is the frame counter of the
+//! source feeding it — a seek moves the file cursor to an exact frame.
+//!
+//! Layout:
+//! - [`FrameReader`]: 16-bit WAV reader with a shared [`PlaybackClock`]
+//!   (current frame + pending seek). Pure std, always compiled, tested.
+//! - `engine` (feature `native-playback`): a dedicated thread owning the
+//!   rodio `OutputStream` (which is `!Send`) and a `Sink`, driven over a
+//!   channel, emitting `playback-position` every 100 ms while playing.
+//!
+//! Non-WAV recordings (Opus / FLAC after `compress_lecture_audio`) are
+//! decoded once by ffmpeg into `{app_data}/cache/playback/{id}.wav`.
+//!
+//! Builds without `native-playback` keep the commands (so the frontend
+//! can probe `playback_is_available`) but they return an error and the
+//! UI stays on `<audio>`.
+
+#[cfg(feature = "native-playback")]
+mod engine;
+
+use crate::recording::waveform::{parse_wav_header, WavFormat};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+const NO_SEEK: u64 = u64::MAX;
+
+#[cfg(not(feature = "native-playback"))]
+const PLAYBACK_DISABLED: &str =
+    "Native playback not compiled into this build. Rebuild with `--features native-playback`.";
+
+/// Shared between the audio callback (writer) and commands (readers).
+pub struct PlaybackClock {
+    frame: AtomicU64,
+    seek_to: AtomicU64,
+    pub sample_rate: u32,
+    pub total_frames: u64,
+}
+
+impl PlaybackClock {
+    pub fn position_ms(&self) -> u64 {
+        self.frame.load(Ordering::Acquire) * 1000 / self.sample_rate.max(1) as u64
+    }
+
+    pub fn duration_ms(&self) -> u64 {
+        self.total_frames * 1000 / self.sample_rate.max(1) as u64
+    }
+
+    /// Queue a seek; the reader applies it before its next frame.
+    pub fn request_seek_ms(&self, ms: u64) {
+        let frame = (ms * self.sample_rate as u64 / 1000).min(self.total_frames);
+        self.seek_to.store(frame, Ordering::Release);
+        // Report the new position immediately rather than after the
+        // mixer's next pull, so a seek-then-get_position round trip is
+        // consistent even while paused.
+        self.frame.store(frame, Ordering::Release);
+    }
+}
+
+/// Interleaved i16 samples from a WAV file, frame by frame.
+pub struct FrameReader {
+    reader: BufReader<File>,
+    fmt: WavFormat,
+    clock: Arc<PlaybackClock>,
+    frame: u64,
+    channel: u16,
+}
+
+impl FrameReader {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let mut file = File::open(path).map_err(|e| format!("open {}: {e}", path.display()))?;
+        let fmt = parse_wav_header(&mut file)
+            .ok_or_else(|| format!("not a 16-bit PCM WAV: {}", path.display()))?;
+        file.seek(SeekFrom::Start(fmt.data_offset))
+            .map_err(|e| format!("seek: {e}"))?;
+        let clock = Arc::new(PlaybackClock {
+            frame: AtomicU64::new(0),
+            seek_to: AtomicU64::new(NO_SEEK),
+            sample_rate: fmt.sample_rate,
+            total_frames: fmt.data_len / (fmt.channels as u64 * 2),
+        });
+        Ok(Self {
+            reader: BufReader::new(file),
+            fmt,
+            clock,
+            frame: 0,
+            channel: 0,
+        })
+    }
+
+    pub fn clock(&self) -> Arc<PlaybackClock> {
+        self.clock.clone()
+    }
+
+    pub fn channels(&self) -> u16 {
+        self.fmt.channels
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.fmt.sample_rate
+    }
+
+    /// Start (or restart, after the end) from `frame`.
+    pub fn rewind_to(&mut self, frame: u64) {
+        let frame = frame.min(self.clock.total_frames);
+        self.clock.seek_to.store(frame, Ordering::Release);
+        self.clock.frame.store(frame, Ordering::Release);
+    }
+
+    pub fn next_sample(&mut self) -> Option<i16> {
+        if self.channel == 0 {
+            let req = self.clock.seek_to.swap(NO_SEEK, Ordering::AcqRel);
+            if req != NO_SEEK {
+                let target = req.min(self.clock.total_frames);
+                let offset = self.fmt.data_offset + target * self.fmt.channels as u64 * 2;
+                if self.reader.seek(SeekFrom::Start(offset)).is_err() {
+                    return None;
+                }
+                self.frame = target;
+            }
+            if self.frame >= self.clock.total_frames {
+                self.clock.frame.store(self.frame, Ordering::Release);
+                return None;
+            }
+            self.clock.frame.store(self.frame, Ordering::Release);
+        }
+        let mut b = [0u8; 2];
+        self.reader.read_exact(&mut b).ok()?;
+        self.channel += 1;
+        if self.channel == self.fmt.channels {
+            self.channel = 0;
+            self.frame += 1;
+        }
+        Some(i16::from_le_bytes(b))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlaybackStatus {
+    pub lecture_id: Option<String>,
+    pub position_ms: u64,
+    pub duration_ms: u64,
+    pub playing: bool,
+    pub ended: bool,
+}
+
+/// WAV recordings play directly; anything else is decoded once into the
+/// playback cache (refreshed if the source is newer than the cache).
+fn playable_wav(src: &Path, lecture_id: &str) -> Result<PathBuf, String> {
+    let is_wav = File::open(src)
+        .ok()
+        .and_then(|mut f| parse_wav_header(&mut f))
+        .is_some();
+    if is_wav {
+        return Ok(src.to_path_buf());
+    }
+    let cache_dir = crate::paths::get_cache_dir()?.join("playback");
+    std::fs::create_dir_all(&cache_dir)
+        .map_err(|e| format!("create {}: {e}", cache_dir.display()))?;
+    let cached = cache_dir.join(format!("{}.wav", lecture_id));
+    let fresh = match (std::fs::metadata(src), std::fs::metadata(&cached)) {
+        (Ok(s), Ok(c)) => match (s.modified(), c.modified()) {
+            (Ok(sm), Ok(cm)) => cm >= sm,
+            _ => false,
+        },
+        _ => false,
+    };
+    if !fresh {
+        decode_to_wav(src, &cached)?;
+    }
+    Ok(cached)
+}
+
+fn decode_to_wav(src: &Path, dest: &Path) -> Result<(), String> {
+    let ffmpeg = crate::recording::video_import::locate_ffmpeg().ok_or_else(|| {
+        "ffmpeg not found on PATH. Install via WinGet/Homebrew/apt and retry.".to_string()
+    })?;
+    let part = dest.with_extension("wav.part");
+    let output = crate::utils::command::no_window(&ffmpeg)
+        .args([
+            "-hide_banner",
+            "-nostats",
+            "-y",
+            "-i",
+            src.to_string_lossy().as_ref(),
+            "-vn",
+            "-acodec",
+            "pcm_s16le",
+            "-f",
+            "wav",
+            part.to_string_lossy().as_ref(),
+        ])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::piped())
+        .output()
+        .map_err(|e| format!("ffmpeg spawn: {e}"))?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&part);
+        return Err(format!("ffmpeg exited {:?}", output.status.code()));
+    }
+    std::fs::rename(&part, dest).map_err(|e| {
+        let _ = std::fs::remove_file(&part);
+        format!("rename {} -> {}: {e}", part.display(), dest.display())
+    })
+}
+
+/// Whether this build has the native engine. The frontend falls back to
+/// `<audio>` when it doesn't.
+#[tauri::command]
+pub fn playback_is_available() -> bool {
+    cfg!(feature = "native-playback")
+}
+
+/// Load a lecture's recording (paused at 0).
+#[tauri::command]
+pub async fn playback_load(
+    app: tauri::AppHandle,
+    lecture_id: String,
+    user_id: Option<String>,
+) -> Result<PlaybackStatus, String> {
+    let manager = crate::storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let user = user_id.unwrap_or_else(|| "default_user".to_string());
+    crate::verify_lecture_ownership(&db, &lecture_id, &user)?;
+    let lecture = db
+        .get_lecture(&lecture_id)
+        .map_err(|e| format!("獲取課程失敗: {}", e))?
+        .ok_or_else(|| "找不到此課堂".to_string())?;
+    let audio_dir = crate::paths::get_audio_dir()?;
+    let src = lecture
+        .audio_path
+        .as_deref()
+        .and_then(|p| crate::resolve_stored_audio_path(&audio_dir, p))
+        .filter(|p| p.is_file())
+        .ok_or_else(|| "此課堂沒有可用的錄音檔".to_string())?;
+
+    let id = lecture_id.clone();
+    let wav = tokio::task::spawn_blocking(move || playable_wav(&src, &id))
+        .await
+        .map_err(|e| format!("playback task join error: {e}"))??;
+
+    #[cfg(feature = "native-playback")]
+    {
+        engine::load(app, lecture_id, wav)
+    }
+    #[cfg(not(feature = "native-playback"))]
+    {
+        let _ = (app, wav);
+        Err(PLAYBACK_DISABLED.to_string())
+    }
+}
+
+#[tauri::command]
+pub fn playback_play() -> Result<PlaybackStatus, String> {
+    #[cfg(feature = "native-playback")]
+    {
+        engine::play()
+    }
+    #[cfg(not(feature = "native-playback"))]
+    {
+        Err(PLAYBACK_DISABLED.to_string())
+    }
+}
+
+#[tauri::command]
+pub fn playback_pause() -> Result<PlaybackStatus, String> {
+    #[cfg(feature = "native-playback")]
+    {
+        engine::pause()
+    }
+    #[cfg(not(feature = "native-playback"))]
+    {
+        Err(PLAYBACK_DISABLED.to_string())
+    }
+}
+
+#[tauri::command]
+pub fn playback_seek(position_ms: u64) -> Result<PlaybackStatus, String> {
+    #[cfg(feature = "native-playback")]
+    {
+        engine::seek(position_ms)
+    }
+    #[cfg(not(feature = "native-playback"))]
+    {
+        let _ = position_ms;
+        Err(PLAYBACK_DISABLED.to_string())
+    }
+}
+
+#[tauri::command]
+pub fn playback_get_position() -> Result<PlaybackStatus, String> {
+    #[cfg(feature = "native-playback")]
+    {
+        engine::status()
+    }
+    #[cfg(not(feature = "native-playback"))]
+    {
+        Err(PLAYBACK_DISABLED.to_string())
+    }
+}
+
+#[tauri::command]
+pub fn playback_stop() -> Result<(), String> {
+    #[cfg(feature = "native-playback")]
+    {
+        engine::stop()
+    }
+    #[cfg(not(feature = "native-playback"))]
+    {
+        Err(PLAYBACK_DISABLED.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// 1 kHz stereo ramp: frame i holds (i, -i).
+    fn ramp(tmp: &TempDir, frames: usize) -> PathBuf {
+        let path = tmp.path().join("r.wav");
+        let mut pcm = Vec::new();
+        for i in 0..frames {
+            pcm.extend_from_slice(&(i as i16).to_le_bytes());
+            pcm.extend_from_slice(&(-(i as i16)).to_le_bytes());
+        }
+        std::fs::write(&path, crate::recording::wrap_pcm_as_wav(&pcm, 1_000, 2)).unwrap();
+        path
+    }
+
+    #[test]
+    fn reads_interleaved_frames_and_tracks_position() {
+        let tmp = TempDir::new().unwrap();
+        let mut r = FrameReader::open(&ramp(&tmp, 10)).unwrap();
+        let clock = r.clock();
+        assert_eq!(clock.duration_ms(), 10);
+        let first: Vec<i16> = (0..4).filter_map(|_| r.next_sample()).collect();
+        assert_eq!(first, vec![0, 0, 1, -1]);
+        // The clock reports the frame currently being handed out.
+        assert_eq!(clock.position_ms(), 1);
+    }
+
+    #[test]
+    fn seek_lands_on_the_exact_frame() {
+        let tmp = TempDir::new().unwrap();
+        let mut r = FrameReader::open(&ramp(&tmp, 2_000)).unwrap();
+        let clock = r.clock();
+        r.next_sample();
+        clock.request_seek_ms(1_234);
+        assert_eq!(clock.position_ms(), 1_234);
+        // Finish the current frame's second channel, then the seek applies.
+        r.next_sample();
+        assert_eq!(r.next_sample(), Some(1_234));
+        assert_eq!(r.next_sample(), Some(-1_234));
+    }
+
+    #[test]
+    fn ends_at_last_frame_and_can_be_rewound() {
+        let tmp = TempDir::new().unwrap();
+        let mut r = FrameReader::open(&ramp(&tmp, 3)).unwrap();
+        let all: Vec<i16> = std::iter::from_fn(|| r.next_sample()).collect();
+        assert_eq!(all.len(), 6);
+        assert_eq!(r.clock().position_ms(), 3);
+        r.rewind_to(1);
+        assert_eq!(r.next_sample(), Some(1));
+    }
+
+    #[test]
+    fn seek_past_the_end_clamps() {
+        let tmp = TempDir::new().unwrap();
+        let r = FrameReader::open(&ramp(&tmp, 100)).unwrap();
+        r.clock().request_seek_ms(60_000);
+        assert_eq!(r.clock().position_ms(), 100);
+    }
+}
@@ -0,0 +1,590 @@
+//! Consumer audio import (mp3 / m4a / aac / ogg / flac / wav).
+//!
+//! Phone voice memos and re-exported lecture recordings land here. The
+//! output contract is the same as `video_import::extract_video_pcm_to_temp`
+//! — 16 kHz mono s16le under `{app_data}/temp_pcm/` — so the renderer
+//! feeds the result through `read_pcm_slice` → `asrPipeline.pushAudio()`
+//! exactly like an imported video.
+//!
+//! Decoding is in-process through [`crate::audio::decode`] (symphonia),
+//! with the downmix and the 16 kHz conversion done by the same
+//! resampler as the capture path. No ffmpeg is needed to import audio.
+//! Opus isn't supported: symphonia has no decoder for it.
+//!
+//! What this adds over the video path:
+//! - duration from the container header up front, so the UI can show
+//!   "42:10 of audio" before committing to a decode;
+//! - `audio-import-progress` events as packets are decoded. A two-hour
+//!   m4a takes long enough that a frozen spinner reads as a hang.
+
+use super::video_import::{app_temp_pcm_dir, locate_ffmpeg, PcmExtractResult};
+use crate::audio::decode::{AudioDecoder, TARGET_SAMPLE_RATE};
+use crate::tasks::{Task, TaskKind, CANCELLED, PROGRESS_INTERVAL};
+use crate::utils::command::no_window;
+use serde::Serialize;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Instant;
+use tauri::Emitter;
+
+const SUPPORTED_AUDIO_EXTENSIONS: &[&str] = &["mp3", "m4a", "aac", "ogg", "oga", "flac", "wav"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AudioImportProgress {
+    pub source_path: String,
+    /// Seconds of input decoded so far.
+    pub processed_sec: f64,
+    /// Input duration from the container; `None` when it doesn't record
+    /// one (raw AAC streams, mp3 without a Xing header).
+    pub duration_sec: Option<f64>,
+    /// 0.0–1.0 when the duration is known.
+    pub fraction: Option<f64>,
+    pub done: bool,
+}
+
+fn supported_audio_extension(path: &Path) -> Result<String, String> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .ok_or_else(|| {
+            format!(
+                "unsupported audio file without extension: {}",
+                path.display()
+            )
+        })?;
+    if SUPPORTED_AUDIO_EXTENSIONS.contains(&ext.as_str()) {
+        Ok(ext)
+    } else {
+        Err(format!("unsupported audio extension .{}", ext))
+    }
+}
+
+/// Parse the `Duration: HH:MM:SS.xx` line ffmpeg prints on stderr for
+/// `ffmpeg -i <file>`. Returns `None` for `Duration: N/A`.
+pub(crate) fn parse_ffmpeg_duration(stderr: &str) -> Option<f64> {
+    let line = stderr
+        .lines()
+        .find(|l| l.trim_start().starts_with("Duration:"))?;
+    let value = line.trim_start().strip_prefix("Duration:")?.trim();
+    let stamp = value.split(',').next()?.trim();
+    parse_hms(stamp)
+}
+
+fn parse_hms(stamp: &str) -> Option<f64> {
+    let mut parts = stamp.split(':');
+    let h: f64 = parts.next()?.trim().parse().ok()?;
+    let m: f64 = parts.next()?.trim().parse().ok()?;
+    let s: f64 = parts.next()?.trim().parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(h * 3600.0 + m * 60.0 + s)
+}
+
+/// `ffmpeg -i <file>` with no output exits non-zero by design; we only
+/// want the header dump on stderr.
+pub fn probe_media_duration(path: &Path) -> Result<Option<f64>, String> {
+    let ffmpeg = locate_ffmpeg().ok_or_else(|| {
+        "ffmpeg not found on PATH. Install via WinGet/Homebrew/apt and retry.".to_string()
+    })?;
+    let output = no_window(&ffmpeg)
+        .args(["-hide_banner", "-i", path.to_string_lossy().as_ref()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("ffmpeg spawn: {e}"))?;
+    Ok(parse_ffmpeg_duration(&String::from_utf8_lossy(
+        &output.stderr,
+    )))
+}
+
+/// What `probe_audio` found out about a file. `playable == false` comes
+/// with an `error` the player can show instead of a dead 00:00 / 00:00.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AudioProbe {
+    pub path: String,
+    pub size_bytes: u64,
+    pub duration_sec: Option<f64>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+    /// ffmpeg codec name (`pcm_s16le`, `opus`, `aac`, ...).
+    pub codec: Option<String>,
+    /// First demuxer name ffmpeg reports (`wav`, `ogg`, `mov`, ...).
+    pub container: Option<String>,
+    pub playable: bool,
+    /// Why it isn't playable, or what looked damaged in a file that
+    /// still plays (e.g. decode errors in the last seconds).
+    pub error: Option<String>,
+}
+
+/// Stream facts scraped from the `ffmpeg -i` header dump.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct StreamInfo {
+    pub container: Option<String>,
+    pub codec: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub channels: Option<u16>,
+}
+
+fn channels_from_layout(layout: &str) -> Option<u16> {
+    let layout = layout.trim();
+    let base = layout.split('(').next().unwrap_or(layout).trim();
+    match base {
+        "mono" => Some(1),
+        "stereo" | "downmix" => Some(2),
+        "2.1" | "3.0" => Some(3),
+        "quad" | "4.0" | "3.1" => Some(4),
+        "5.0" | "4.1" => Some(5),
+        "5.1" | "6.0" => Some(6),
+        "7.0" | "6.1" => Some(7),
+        "7.1" => Some(8),
+        _ => base.strip_suffix(" channels")?.trim().parse().ok(),
+    }
+}
+
+/// Parse the first audio stream line, e.g.
+/// `Stream #0:0(und): Audio: aac (LC) (mp4a / 0x6134706D), 44100 Hz, stereo, fltp, 128 kb/s`.
+pub(crate) fn parse_stream_info(stderr: &str) -> StreamInfo {
+    let container = stderr
+        .lines()
+        .find_map(|l| l.trim_start().strip_prefix("Input #0, "))
+        .and_then(|rest| rest.split(',').next())
+        .map(|c| c.trim().to_string())
+        .filter(|c| !c.is_empty());
+    let mut info = StreamInfo {
+        container,
+        ..StreamInfo::default()
+    };
+    let Some(audio) = stderr
+        .lines()
+        .filter(|l| l.trim_start().starts_with("Stream #"))
+        .find_map(|l| l.split_once("Audio: ").map(|(_, rest)| rest))
+    else {
+        return info;
+    };
+    let parts: Vec<&str> = audio.split(',').map(str::trim).collect();
+    info.codec = parts
+        .first()
+        .and_then(|p| p.split_whitespace().next())
+        .map(str::to_string);
+    if let Some(i) = parts.iter().position(|p| p.ends_with(" Hz")) {
+        info.sample_rate = parts[i].trim_end_matches(" Hz").trim().parse().ok();
+        info.channels = parts.get(i + 1).and_then(|p| channels_from_layout(p));
+    }
+    info
+}
+
+/// Seconds decoded at each end of a compressed file for the damage
+/// check. Decoding a whole two-hour lecture just to open the player
+/// would take far too long; torn files are almost always broken at the
+/// end (crash / full disk mid-write) or at the start (bad header).
+const DECODE_CHECK_SEC: u32 = 30;
+
+/// Decode `DECODE_CHECK_SEC` of `path` (from the end when `tail`) and
+/// return ffmpeg's error lines. `Err` when ffmpeg couldn't decode at all.
+fn decode_errors(ffmpeg: &Path, path: &Path, tail: bool) -> Result<Vec<String>, String> {
+    let secs = DECODE_CHECK_SEC.to_string();
+    let mut args: Vec<String> = vec![
+        "-hide_banner".into(),
+        "-nostats".into(),
+        "-v".into(),
+        "error".into(),
+    ];
+    if tail {
+        args.extend(["-sseof".to_string(), format!("-{secs}")]);
+    }
+    args.extend([
+        "-i".to_string(),
+        path.to_string_lossy().to_string(),
+        "-vn".into(),
+        "-t".into(),
+        secs,
+        "-f".into(),
+        "null".into(),
+        "-".into(),
+    ]);
+    let output = no_window(ffmpeg)
+        .args(&args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("ffmpeg spawn: {e}"))?;
+    let lines: Vec<String> = String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect();
+    if output.status.success() {
+        Ok(lines)
+    } else {
+        Err(lines
+            .last()
+            .cloned()
+            .unwrap_or_else(|| format!("ffmpeg exited {:?}", output.status.code())))
+    }
+}
+
+/// Probe `path` for playback: format facts plus a quick damage check.
+/// Only I/O failures on the path itself are `Err`; a broken file is an
+/// `Ok` with `playable: false`.
+pub fn probe_audio_inner(path: &Path) -> Result<AudioProbe, String> {
+    let meta =
+        std::fs::metadata(path).map_err(|e| format!("找不到錄音檔 {}: {e}", path.display()))?;
+    let mut probe = AudioProbe {
+        path: path.to_string_lossy().to_string(),
+        size_bytes: meta.len(),
+        duration_sec: None,
+        sample_rate: None,
+        channels: None,
+        codec: None,
+        container: None,
+        playable: false,
+        error: None,
+    };
+    if !meta.is_file() {
+        probe.error = Some("路徑不是檔案".to_string());
+        return Ok(probe);
+    }
+    if meta.len() == 0 {
+        probe.error = Some("錄音檔是空的 (0 bytes)，錄音可能未成功寫入".to_string());
+        return Ok(probe);
+    }
+
+    // Our own recordings: answer from the header without ffmpeg.
+    let mut file =
+        std::fs::File::open(path).map_err(|e| format!("open {}: {e}", path.display()))?;
+    if let Some(fmt) = super::waveform::parse_wav_header(&mut file) {
+        let block_align = fmt.channels as u64 * 2;
+        let frames = fmt.data_len / block_align;
+        probe.container = Some("wav".into());
+        probe.codec = Some("pcm_s16le".into());
+        probe.sample_rate = Some(fmt.sample_rate);
+        probe.channels = Some(fmt.channels);
+        probe.duration_sec = Some(frames as f64 / fmt.sample_rate.max(1) as f64);
+        if fmt.sample_rate == 0 {
+            probe.error = Some("WAV 標頭的取樣率為 0，檔案已損毀".to_string());
+        } else if frames == 0 {
+            probe.error = Some("WAV 檔沒有任何音訊資料".to_string());
+        } else {
+            probe.playable = true;
+            if fmt.data_len % block_align != 0 {
+                probe.error =
+                    Some("WAV 資料結尾不完整（可能是錄音中斷），已忽略殘缺的樣本".to_string());
+            }
+        }
+        return Ok(probe);
+    }
+
+    let ffmpeg = locate_ffmpeg().ok_or_else(|| {
+        "ffmpeg not found on PATH. Install via WinGet/Homebrew/apt and retry.".to_string()
+    })?;
+    let output = no_window(&ffmpeg)
+        .args(["-hide_banner", "-i", path.to_string_lossy().as_ref()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("ffmpeg spawn: {e}"))?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let info = parse_stream_info(&stderr);
+    probe.duration_sec = parse_ffmpeg_duration(&stderr);
+    probe.container = info.container;
+    probe.codec = info.codec;
+    probe.sample_rate = info.sample_rate;
+    probe.channels = info.channels;
+
+    if probe.container.is_none() {
+        let reason = stderr
+            .lines()
+            .rev()
+            .map(str::trim)
+            .find(|l| !l.is_empty())
+            .unwrap_or("unknown format");
+        probe.error = Some(format!("無法辨識的音訊檔: {reason}"));
+        return Ok(probe);
+    }
+    if probe.codec.is_none() {
+        probe.error = Some("檔案中沒有音訊串流".to_string());
+        return Ok(probe);
+    }
+
+    let head = decode_errors(&ffmpeg, path, false);
+    let tail = decode_errors(&ffmpeg, path, true);
+    match (head, tail) {
+        (Err(e), _) => probe.error = Some(format!("音訊無法解碼: {e}")),
+        (Ok(head), tail) => {
+            probe.playable = true;
+            let tail = tail.unwrap_or_else(|e| vec![e]);
+            if let Some(first) = head.first().or(tail.first()) {
+                probe.error = Some(format!("部分音訊資料損毀: {first}"));
+            }
+        }
+    }
+    if probe.playable && probe.duration_sec.is_none_or(|d| d <= 0.0) {
+        probe.playable = false;
+        probe.error = Some("無法取得錄音長度，檔案可能未正確結束".to_string());
+    }
+    Ok(probe)
+}
+
+fn temp_pcm_target(temp_dir: &Path, source: &Path) -> PathBuf {
+    let stem = source
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("import");
+    temp_dir.join(format!("{}.audio.pcm", stem))
+}
+
+/// Open `source` for decoding; its extension is the format hint.
+pub fn open_audio(source: &Path) -> Result<AudioDecoder, String> {
+    let ext = supported_audio_extension(source)?;
+    let file =
+        std::fs::File::open(source).map_err(|e| format!("open {}: {e}", source.display()))?;
+    AudioDecoder::open(Box::new(file), Some(&ext))
+}
+
+/// Decode `decoder` to 16 kHz mono s16le at `pcm_path`, invoking
+/// `on_progress(processed_sec)` for every decoded packet; an `Err` from
+/// it stops the decode. Returns the sample count; a failed or stopped
+/// decode leaves no partial file behind.
+pub fn decode_audio_to_pcm_file<F: FnMut(f64) -> Result<(), String>>(
+    decoder: AudioDecoder,
+    pcm_path: &Path,
+    mut on_progress: F,
+) -> Result<u64, String> {
+    let file = std::fs::File::create(pcm_path)
+        .map_err(|e| format!("create {}: {e}", pcm_path.display()))?;
+    let mut out = BufWriter::new(file);
+    let result = decoder
+        .decode_16k_mono(|samples, sec| {
+            for sample in samples {
+                out.write_all(&sample.to_le_bytes())
+                    .map_err(|e| format!("write pcm: {e}"))?;
+            }
+            on_progress(sec)
+        })
+        .and_then(|count| {
+            out.flush().map_err(|e| format!("write pcm: {e}"))?;
+            Ok(count)
+        });
+    if result.is_err() {
+        drop(out);
+        let _ = std::fs::remove_file(pcm_path);
+    }
+    result
+}
+
+// ============================================================
+// Tauri commands
+// ============================================================
+
+/// Duration in seconds of any media file ffmpeg can open, or `None`
+/// when the container doesn't carry one.
+#[tauri::command]
+pub async fn probe_audio_duration(audio_path: String) -> Result<Option<f64>, String> {
+    let path = PathBuf::from(&audio_path);
+    if !path.exists() {
+        return Err(format!("audio not found: {audio_path}"));
+    }
+    tokio::task::spawn_blocking(move || probe_media_duration(&path))
+        .await
+        .map_err(|e| format!("probe task: {e}"))?
+}
+
+/// Format, duration and damage check for a recording, so the player
+/// can refuse a broken file with a reason up front.
+#[tauri::command]
+pub async fn probe_audio(path: String) -> Result<AudioProbe, String> {
+    let path = PathBuf::from(path);
+    tokio::task::spawn_blocking(move || probe_audio_inner(&path))
+        .await
+        .map_err(|e| format!("probe task: {e}"))?
+}
+
+/// Decode a consumer audio file into a 16 kHz mono PCM temp file,
+/// emitting `audio-import-progress` as it goes (at most every
+/// [`PROGRESS_INTERVAL`]). `cancel_task` stops it between packets. The
+/// result is consumed with `read_pcm_slice` and cleaned up with
+/// `delete_temp_pcm`.
+#[tauri::command]
+pub async fn import_audio_file_to_temp(
+    app: tauri::AppHandle,
+    audio_path: String,
+) -> Result<PcmExtractResult, String> {
+    let source = PathBuf::from(&audio_path);
+    if !source.exists() {
+        return Err(format!("audio not found: {audio_path}"));
+    }
+    let decoder = open_audio(&source)?;
+    let temp_dir = app_temp_pcm_dir()?;
+    std::fs::create_dir_all(&temp_dir).map_err(|e| format!("mkdir temp_pcm: {e}"))?;
+    let pcm_path = temp_pcm_target(&temp_dir, &source);
+
+    let label = source
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| audio_path.clone());
+    let task = Task::start(&app, TaskKind::Import, label).cancellable();
+    let result_path = pcm_path.clone();
+    let sample_count = tokio::task::spawn_blocking(move || {
+        let duration = decoder.duration_sec;
+        let source_str = source.to_string_lossy().to_string();
+        let emit = |processed_sec: f64, done: bool| {
+            let fraction = duration
+                .filter(|d| *d > 0.0)
+                .map(|d| (processed_sec / d).clamp(0.0, 1.0));
+            task.set_fraction(fraction);
+            let _ = app.emit(
+                "audio-import-progress",
+                AudioImportProgress {
+                    source_path: source_str.clone(),
+                    processed_sec,
+                    duration_sec: duration,
+                    fraction,
+                    done,
+                },
+            );
+        };
+        emit(0.0, false);
+        let mut last_emit = Instant::now();
+        let result = decode_audio_to_pcm_file(decoder, &pcm_path, |sec| {
+            if task.is_cancelled() {
+                return Err(CANCELLED.to_string());
+            }
+            if last_emit.elapsed() >= PROGRESS_INTERVAL {
+                last_emit = Instant::now();
+                emit(sec, false);
+            }
+            Ok(())
+        });
+        if let Ok(samples) = result {
+            emit(samples as f64 / TARGET_SAMPLE_RATE as f64, true);
+        }
+        task.finish(&result);
+        result
+    })
+    .await
+    .map_err(|e| format!("decode task: {e}"))??;
+
+    Ok(PcmExtractResult {
+        pcm_path: result_path.to_string_lossy().to_string(),
+        sample_count,
+        duration_sec: sample_count as f64 / TARGET_SAMPLE_RATE as f64,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_duration_from_ffmpeg_header_dump() {
+        let stderr = "Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'memo.m4a':\n  \
+                      Metadata:\n    major_brand     : M4A \n  \
+                      Duration: 01:02:03.50, start: 0.000000, bitrate: 64 kb/s\n";
+        assert_eq!(parse_ffmpeg_duration(stderr), Some(3723.5));
+        assert_eq!(
+            parse_ffmpeg_duration("  Duration: N/A, bitrate: N/A\n"),
+            None
+        );
+        assert_eq!(parse_ffmpeg_duration("no header here"), None);
+    }
+
+    #[test]
+    fn accepts_phone_formats_and_rejects_video_and_scratch() {
+        for name in ["memo.M4A", "a.mp3", "b.ogg", "d.flac", "e.wav"] {
+            assert!(supported_audio_extension(Path::new(name)).is_ok(), "{name}");
+        }
+        assert!(supported_audio_extension(Path::new("lecture.mp4")).is_err());
+        assert!(supported_audio_extension(Path::new("memo.opus")).is_err());
+        assert!(supported_audio_extension(Path::new("scratch.pcm")).is_err());
+        assert!(supported_audio_extension(Path::new("noext")).is_err());
+    }
+
+    #[test]
+    fn temp_target_does_not_collide_with_video_extract_name() {
+        let dir = Path::new("/tmp/temp_pcm");
+        let p = temp_pcm_target(dir, Path::new("/x/lecture.m4a"));
+        assert_eq!(p, dir.join("lecture.audio.pcm"));
+    }
+
+    #[test]
+    fn parses_stream_info_from_header_dump() {
+        let stderr = "Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'memo.m4a':\n  \
+                      Duration: 00:10:00.00, start: 0.000000, bitrate: 128 kb/s\n  \
+                      Stream #0:0[0x1](und): Audio: aac (LC) (mp4a / 0x6134706D), 44100 Hz, stereo, fltp, 128 kb/s (default)\n";
+        assert_eq!(
+            parse_stream_info(stderr),
+            StreamInfo {
+                container: Some("mov".into()),
+                codec: Some("aac".into()),
+                sample_rate: Some(44_100),
+                channels: Some(2),
+            }
+        );
+        let opus =
+            "Input #0, ogg, from 'a.ogg':\n  Stream #0:0: Audio: opus, 48000 Hz, mono, fltp\n";
+        let info = parse_stream_info(opus);
+        assert_eq!(
+            (info.codec.as_deref(), info.channels),
+            (Some("opus"), Some(1))
+        );
+        assert_eq!(channels_from_layout("5.1(side)"), Some(6));
+        assert_eq!(channels_from_layout("3 channels"), Some(3));
+        assert_eq!(
+            parse_stream_info("x: Invalid data found when processing input"),
+            StreamInfo::default()
+        );
+    }
+
+    #[test]
+    fn probes_wav_natively_and_flags_empty_files() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let wav = tmp.path().join("a.wav");
+        let mut bytes = crate::recording::wrap_pcm_as_wav(&[0u8; 32_001], 16_000, 1);
+        bytes.truncate(44 + 32_001);
+        std::fs::write(&wav, bytes).unwrap();
+        let probe = probe_audio_inner(&wav).unwrap();
+        assert!(probe.playable);
+        assert_eq!(probe.codec.as_deref(), Some("pcm_s16le"));
+        assert_eq!((probe.sample_rate, probe.channels), (Some(16_000), Some(1)));
+        assert_eq!(probe.duration_sec, Some(1.0));
+        assert!(probe.error.is_some(), "odd trailing byte is reported");
+
+        let header_only = tmp.path().join("b.wav");
+        std::fs::write(
+            &header_only,
+            crate::recording::wrap_pcm_as_wav(&[], 16_000, 1),
+        )
+        .unwrap();
+        let probe = probe_audio_inner(&header_only).unwrap();
+        assert!(!probe.playable);
+
+        let empty = tmp.path().join("c.m4a");
+        std::fs::write(&empty, b"").unwrap();
+        let probe = probe_audio_inner(&empty).unwrap();
+        assert!(!probe.playable && probe.size_bytes == 0);
+
+        assert!(probe_audio_inner(&tmp.path().join("missing.wav")).is_err());
+    }
+
+    #[test]
+    fn stopped_decode_leaves_no_pcm_behind() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let wav = tmp.path().join("memo.wav");
+        std::fs::write(
+            &wav,
+            crate::recording::wrap_pcm_as_wav(&vec![0u8; 96_000], 48_000, 1),
+        )
+        .unwrap();
+        let pcm = tmp.path().join("memo.audio.pcm");
+
+        let result = decode_audio_to_pcm_file(open_audio(&wav).unwrap(), &pcm, |_| {
+            Err(CANCELLED.to_string())
+        });
+        assert_eq!(result, Err(CANCELLED.to_string()));
+        assert!(!pcm.exists());
+    }
+}
@@ -0,0 +1,346 @@
+//! Podcast-style export: the lecture audio as `.m4a` or `.ogg` with
+//! embedded chapter markers, so it can be skimmed in any standard
+//! player (Apple Podcasts, VLC, Pocket Casts …) instead of only in-app.
+//!
+//! Chapters come from the note's topic sections (each has a title and a
+//! start timestamp from the clustering pass) and/or caller-supplied
+//! bookmarks such as exam marks. Both lists are merged, sorted and
+//! thinned out so no chapter is shorter than [`MIN_CHAPTER_MS`]; the
+//! first chapter is stretched back to 0 because players treat anything
+//! before it as "no chapter".
+//!
+//! Markers are handed to ffmpeg as an FFMETADATA file. The mp4 muxer
+//! writes them as a QuickTime chapter track (what iOS / macOS read), the
+//! ogg muxer as `CHAPTERxxx` Vorbis comments.
+
+use super::audio_import::probe_media_duration;
+use super::compress::check_duration;
+use super::video_import::locate_ffmpeg;
+use crate::utils::command::no_window;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Stdio;
+
+/// Chapters closer together than this are merged into the earlier one;
+/// a 2-second chapter is just noise in a player's chapter list.
+pub const MIN_CHAPTER_MS: u64 = 5_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChapterFormat {
+    M4a,
+    Ogg,
+}
+
+impl ChapterFormat {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "m4a" | "aac" | "mp4" => Ok(Self::M4a),
+            "ogg" | "opus" => Ok(Self::Ogg),
+            other => Err(format!("不支援的章節匯出格式: {} (m4a / ogg)", other)),
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::M4a => "m4a",
+            Self::Ogg => "ogg",
+        }
+    }
+
+    fn ffmpeg_args(self) -> Vec<String> {
+        match self {
+            // `ipod` is the mp4 flavour podcast apps expect for .m4a;
+            // faststart moves the moov atom (and chapter list) up front.
+            Self::M4a => vec![
+                "-c:a".into(),
+                "aac".into(),
+                "-b:a".into(),
+                "96k".into(),
+                "-movflags".into(),
+                "+faststart".into(),
+                "-f".into(),
+                "ipod".into(),
+            ],
+            Self::Ogg => vec![
+                "-c:a".into(),
+                "libopus".into(),
+                "-b:a".into(),
+                "48k".into(),
+                "-ar".into(),
+                "48000".into(),
+                "-f".into(),
+                "ogg".into(),
+            ],
+        }
+    }
+}
+
+/// A chapter start as supplied by the caller (bookmark) or read from a
+/// note section.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChapterMark {
+    pub start_sec: f64,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Chapter {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChapterExportResult {
+    pub output_path: String,
+    pub duration_ms: u64,
+    pub chapters: Vec<Chapter>,
+}
+
+/// Chapter starts from a note's `content` JSON (`sections[].timestamp`
+/// in seconds, `sections[].title`). Malformed content yields nothing
+/// rather than an error; the export still works without topic chapters.
+pub fn marks_from_note_content(content: &str) -> Vec<ChapterMark> {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(content) else {
+        return Vec::new();
+    };
+    value
+        .get("sections")
+        .and_then(|s| s.as_array())
+        .map(|sections| {
+            sections
+                .iter()
+                .filter_map(|s| {
+                    Some(ChapterMark {
+                        start_sec: s.get("timestamp")?.as_f64()?,
+                        title: s.get("title")?.as_str()?.to_string(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Turn unordered marks into contiguous chapters covering
+/// `0..duration_ms`.
+pub fn build_chapters(marks: &[ChapterMark], duration_ms: u64) -> Vec<Chapter> {
+    let mut starts: Vec<(u64, String)> = marks
+        .iter()
+        .filter(|m| m.start_sec.is_finite() && m.start_sec >= 0.0)
+        .map(|m| {
+            (
+                (m.start_sec * 1000.0).round() as u64,
+                m.title.trim().to_string(),
+            )
+        })
+        .filter(|(ms, _)| *ms < duration_ms)
+        .collect();
+    starts.sort_by_key(|(ms, _)| *ms);
+
+    let mut kept: Vec<(u64, String)> = Vec::new();
+    for (ms, title) in starts {
+        match kept.last_mut() {
+            Some((prev, prev_title)) if ms < *prev + MIN_CHAPTER_MS => {
+                if prev_title.is_empty() {
+                    *prev_title = title;
+                }
+            }
+            _ => kept.push((ms, title)),
+        }
+    }
+    // The tail chapter must not be a sliver either.
+    if kept.len() > 1
+        && kept
+            .last()
+            .is_some_and(|(ms, _)| duration_ms - ms < MIN_CHAPTER_MS)
+    {
+        kept.pop();
+    }
+    if let Some(first) = kept.first_mut() {
+        first.0 = 0;
+    }
+
+    let ends: Vec<u64> = kept
+        .iter()
+        .skip(1)
+        .map(|(ms, _)| *ms)
+        .chain(std::iter::once(duration_ms))
+        .collect();
+    kept.into_iter()
+        .zip(ends)
+        .enumerate()
+        .map(|(i, ((start_ms, title), end_ms))| Chapter {
+            start_ms,
+            end_ms,
+            title: if title.is_empty() {
+                format!("第 {} 章", i + 1)
+            } else {
+                title
+            },
+        })
+        .collect()
+}
+
+/// FFMETADATA escapes `=`, `;`, `#`, `\` and newlines with a backslash.
+fn escape_metadata(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            out.push('\\');
+        }
+        if c != '\r' {
+            out.push(c);
+        }
+    }
+    out
+}
+
+pub fn ffmetadata(title: &str, chapters: &[Chapter]) -> String {
+    let mut out = format!(";FFMETADATA1\ntitle={}\n", escape_metadata(title));
+    for c in chapters {
+        out.push_str(&format!(
+            "\n[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={}\n",
+            c.start_ms,
+            c.end_ms,
+            escape_metadata(&c.title)
+        ));
+    }
+    out
+}
+
+/// Encode `src` into `dest` with `chapters` embedded. Writes to a
+/// `.part` sibling and renames once ffmpeg succeeded and the duration
+/// matches the source. Returns the output duration in ms.
+pub fn export_with_chapters_inner(
+    src: &Path,
+    dest: &Path,
+    format: ChapterFormat,
+    title: &str,
+    chapters: &[Chapter],
+    source_duration_sec: f64,
+) -> Result<u64, String> {
+    let ffmpeg = locate_ffmpeg().ok_or_else(|| {
+        "ffmpeg not found on PATH. Install via WinGet/Homebrew/apt and retry.".to_string()
+    })?;
+    let part = dest.with_extension(format!("{}.part", format.extension()));
+    let meta = dest.with_extension("ffmeta.txt");
+    std::fs::write(&meta, ffmetadata(title, chapters))
+        .map_err(|e| format!("write {}: {e}", meta.display()))?;
+
+    let mut args: Vec<String> = vec![
+        "-hide_banner".into(),
+        "-nostats".into(),
+        "-y".into(),
+        "-i".into(),
+        src.to_string_lossy().to_string(),
+        "-f".into(),
+        "ffmetadata".into(),
+        "-i".into(),
+        meta.to_string_lossy().to_string(),
+        "-map".into(),
+        "0:a:0".into(),
+        "-map_metadata".into(),
+        "1".into(),
+        "-map_chapters".into(),
+        "1".into(),
+        "-vn".into(),
+    ];
+    args.extend(format.ffmpeg_args());
+    args.push(part.to_string_lossy().to_string());
+
+    let output = no_window(&ffmpeg)
+        .args(&args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output();
+    let _ = std::fs::remove_file(&meta);
+    let output = output.map_err(|e| format!("ffmpeg spawn: {e}"))?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&part);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let tail: Vec<&str> = stderr.lines().rev().take(20).collect();
+        let tail: Vec<&str> = tail.into_iter().rev().collect();
+        return Err(format!(
+            "ffmpeg exited {:?}: {}",
+            output.status.code(),
+            tail.join("\n")
+        ));
+    }
+
+    let actual = probe_media_duration(&part).ok().flatten();
+    if let Err(e) = check_duration(Some(source_duration_sec), actual) {
+        let _ = std::fs::remove_file(&part);
+        return Err(e);
+    }
+    std::fs::rename(&part, dest).map_err(|e| {
+        let _ = std::fs::remove_file(&part);
+        format!("rename {} -> {}: {e}", part.display(), dest.display())
+    })?;
+    Ok((actual.unwrap_or(source_duration_sec) * 1000.0).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mark(start_sec: f64, title: &str) -> ChapterMark {
+        ChapterMark {
+            start_sec,
+            title: title.to_string(),
+        }
+    }
+
+    #[test]
+    fn chapters_are_sorted_contiguous_and_start_at_zero() {
+        let marks = vec![mark(600.0, "Proofs"), mark(12.0, "Intro"), mark(300.0, "")];
+        let ch = build_chapters(&marks, 900_000);
+        assert_eq!(ch.len(), 3);
+        assert_eq!(ch[0].start_ms, 0);
+        assert_eq!(ch[0].title, "Intro");
+        assert_eq!(ch[1].title, "第 2 章");
+        assert_eq!(ch[0].end_ms, ch[1].start_ms);
+        assert_eq!(ch[1].end_ms, 600_000);
+        assert_eq!(ch[2].end_ms, 900_000);
+    }
+
+    #[test]
+    fn close_and_out_of_range_marks_are_dropped() {
+        let marks = vec![
+            mark(100.0, "Topic"),
+            mark(102.0, "Exam mark"),
+            mark(-1.0, "bad"),
+            mark(f64::NAN, "bad"),
+            mark(898.0, "Too late"),
+            mark(1000.0, "Past the end"),
+        ];
+        let ch = build_chapters(&marks, 900_000);
+        let titles: Vec<&str> = ch.iter().map(|c| c.title.as_str()).collect();
+        assert_eq!(titles, vec!["Topic"]);
+        assert_eq!((ch[0].start_ms, ch[0].end_ms), (0, 900_000));
+        assert!(build_chapters(&[], 900_000).is_empty());
+    }
+
+    #[test]
+    fn metadata_escapes_special_characters() {
+        let ch = vec![Chapter {
+            start_ms: 0,
+            end_ms: 5_000,
+            title: "a=b; #1 \\ x\r\ny".to_string(),
+        }];
+        let meta = ffmetadata("Week 3", &ch);
+        assert!(meta.starts_with(";FFMETADATA1\ntitle=Week 3\n"));
+        assert!(meta.contains("START=0\nEND=5000\n"));
+        assert!(meta.contains("title=a\\=b\\; \\#1 \\\\ x\\\ny\n"));
+    }
+
+    #[test]
+    fn note_sections_become_marks() {
+        let content = r#"{"sections":[{"title":"Intro","content":"","timestamp":0},
+            {"title":"Limits","timestamp":312.5},{"timestamp":5}]}"#;
+        let marks = marks_from_note_content(content);
+        assert_eq!(marks.len(), 2);
+        assert_eq!(marks[1].title, "Limits");
+        assert_eq!(marks[1].start_sec, 312.5);
+        assert!(marks_from_note_content("not json").is_empty());
+    }
+}
@@ -0,0 +1,212 @@
+//! Post-lecture transcoding of the finalized WAV to Opus or FLAC.
+//!
+//! A two-hour lecture at 48 kHz mono i16 is ~700 MB of WAV; a semester
+//! of them fills a 256 GB laptop. Opus at 32 kb/s is ~30 MB for the same
+//! lecture and indistinguishable for speech; FLAC is the lossless option
+//! for people who want to keep the original (~40–60 % of WAV).
+//!
+//! Same ffmpeg shell-out as `video_import` / `audio_import`. Timestamps
+//! stay valid because nothing here changes the timeline: no trimming,
+//! no resampling for FLAC, and Opus' encoder pre-skip is written into
+//! the container so decoders drop it. `transcode_audio_inner` still
+//! double-checks by comparing probed durations before the caller swaps
+//! `audio_path` over — a truncated encode (disk full mid-write) must
+//! never replace the original.
+
+use super::audio_import::probe_media_duration;
+use super::video_import::locate_ffmpeg;
+use crate::utils::command::no_window;
+use serde::Serialize;
+use std::path::Path;
+use std::process::Stdio;
+
+/// Allowed drift between source and encoded duration. Opus frames are
+/// 20 ms and WebM timestamps are ms-granular, so anything beyond a
+/// fraction of a second means the encode didn't finish.
+const MAX_DURATION_DRIFT_SEC: f64 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioCodec {
+    Opus,
+    Flac,
+}
+
+impl AudioCodec {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "opus" => Ok(Self::Opus),
+            "flac" => Ok(Self::Flac),
+            other => Err(format!("不支援的音訊編碼: {} (opus / flac)", other)),
+        }
+    }
+
+    /// Inverse of [`Self::extension`], for re-encoding an already
+    /// compressed recording in its own format (trim / merge).
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_ascii_lowercase().as_str() {
+            "webm" | "opus" | "ogg" => Some(Self::Opus),
+            "flac" => Some(Self::Flac),
+            _ => None,
+        }
+    }
+
+    /// WebM rather than Ogg for Opus: both the Chromium (WebView2) and
+    /// WebKit webviews play WebM/Opus in `<audio>`; WebKit's Ogg support
+    /// is much more recent.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Opus => "webm",
+            Self::Flac => "flac",
+        }
+    }
+
+    pub(crate) fn ffmpeg_args(self, bitrate_kbps: Option<u32>) -> Vec<String> {
+        match self {
+            Self::Opus => {
+                let kbps = bitrate_kbps.unwrap_or(32).clamp(6, 256);
+                vec![
+                    "-c:a".into(),
+                    "libopus".into(),
+                    "-b:a".into(),
+                    format!("{}k", kbps),
+                    "-vbr".into(),
+                    "on".into(),
+                    "-application".into(),
+                    "voip".into(),
+                    // libopus only accepts 8/12/16/24/48 kHz; 44.1 kHz
+                    // sources would otherwise fail to open the encoder.
+                    "-ar".into(),
+                    "48000".into(),
+                    "-f".into(),
+                    "webm".into(),
+                ]
+            }
+            // Bitrate is meaningless for lossless; ignore it.
+            Self::Flac => vec![
+                "-c:a".into(),
+                "flac".into(),
+                "-compression_level".into(),
+                "8".into(),
+                "-f".into(),
+                "flac".into(),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CompressResult {
+    /// Stored (audio_dir-relative when possible) path now on the lecture.
+    pub audio_path: String,
+    pub original_bytes: u64,
+    pub compressed_bytes: u64,
+}
+
+/// Transcode `src` into `dest`. Writes to a `.part` sibling and renames
+/// only after ffmpeg succeeds and the durations agree. Returns the size
+/// of the encoded file.
+pub fn transcode_audio_inner(
+    src: &Path,
+    dest: &Path,
+    codec: AudioCodec,
+    bitrate_kbps: Option<u32>,
+) -> Result<u64, String> {
+    let ffmpeg = locate_ffmpeg().ok_or_else(|| {
+        "ffmpeg not found on PATH. Install via WinGet/Homebrew/apt and retry.".to_string()
+    })?;
+    let part = dest.with_extension(format!("{}.part", codec.extension()));
+
+    let mut args: Vec<String> = vec![
+        "-hide_banner".into(),
+        "-nostats".into(),
+        "-y".into(),
+        "-i".into(),
+        src.to_string_lossy().to_string(),
+        "-vn".into(),
+    ];
+    args.extend(codec.ffmpeg_args(bitrate_kbps));
+    args.push(part.to_string_lossy().to_string());
+
+    let output = no_window(&ffmpeg)
+        .args(&args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("ffmpeg spawn: {e}"))?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&part);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let tail: Vec<&str> = stderr.lines().rev().take(20).collect();
+        let tail: Vec<&str> = tail.into_iter().rev().collect();
+        return Err(format!(
+            "ffmpeg exited {:?}: {}",
+            output.status.code(),
+            tail.join("\n")
+        ));
+    }
+
+    let src_duration = probe_media_duration(src).ok().flatten();
+    let out_duration = probe_media_duration(&part).ok().flatten();
+    if let Err(e) = check_duration(src_duration, out_duration) {
+        let _ = std::fs::remove_file(&part);
+        return Err(e);
+    }
+
+    std::fs::rename(&part, dest).map_err(|e| {
+        let _ = std::fs::remove_file(&part);
+        format!("rename {} -> {}: {e}", part.display(), dest.display())
+    })?;
+    std::fs::metadata(dest)
+        .map(|m| m.len())
+        .map_err(|e| format!("stat {}: {e}", dest.display()))
+}
+
+pub(crate) fn check_duration(src: Option<f64>, out: Option<f64>) -> Result<(), String> {
+    match (src, out) {
+        (Some(s), Some(o)) if (s - o).abs() > MAX_DURATION_DRIFT_SEC => Err(format!(
+            "轉檔後長度不符 (原始 {:.2}s, 轉檔 {:.2}s)，保留原檔",
+            s, o
+        )),
+        // A source with a known duration must produce an output with one.
+        (Some(_), None) => Err("無法讀取轉檔後的音訊長度，保留原檔".to_string()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_codec_names_case_insensitively() {
+        assert_eq!(AudioCodec::parse("Opus").unwrap(), AudioCodec::Opus);
+        assert_eq!(AudioCodec::parse(" flac ").unwrap(), AudioCodec::Flac);
+        assert!(AudioCodec::parse("mp3").is_err());
+        assert_eq!(AudioCodec::from_extension("WEBM"), Some(AudioCodec::Opus));
+        assert_eq!(AudioCodec::from_extension("wav"), None);
+    }
+
+    #[test]
+    fn opus_bitrate_is_clamped_and_defaulted() {
+        let args = AudioCodec::Opus.ffmpeg_args(None);
+        assert!(args.contains(&"32k".to_string()));
+        let args = AudioCodec::Opus.ffmpeg_args(Some(1));
+        assert!(args.contains(&"6k".to_string()));
+        let args = AudioCodec::Opus.ffmpeg_args(Some(10_000));
+        assert!(args.contains(&"256k".to_string()));
+    }
+
+    #[test]
+    fn flac_ignores_bitrate() {
+        let args = AudioCodec::Flac.ffmpeg_args(Some(64));
+        assert!(!args.iter().any(|a| a.ends_with('k')));
+    }
+
+    #[test]
+    fn duration_check_rejects_truncated_encodes() {
+        assert!(check_duration(Some(3600.0), Some(3600.02)).is_ok());
+        assert!(check_duration(Some(3600.0), Some(1800.0)).is_err());
+        assert!(check_duration(Some(3600.0), None).is_err());
+        assert!(check_duration(None, None).is_ok());
+    }
+}
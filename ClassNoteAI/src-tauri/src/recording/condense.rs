@@ -0,0 +1,529 @@
+//! Silence-stripped ("condensed") export for revision listening.
+//!
+//! A 90-minute lecture typically has 20–30 minutes of dead air: board
+//! writing, handing out sheets, waiting for the projector. The export
+//! keeps only the VAD speech spans (padded so words aren't clipped, with
+//! short pauses left in so it doesn't sound chopped) and optionally
+//! speeds the result up with ffmpeg's `atempo`.
+//!
+//! Speech spans are detected once per recording and stored as a JSON
+//! sidecar under `{app_data}/cache/vad/`, invalidated like the waveform
+//! cache when the audio file's size or mtime changes. Detection runs on
+//! 16 kHz mono, the rate both VAD backends expect.
+//!
+//! Alongside the audio the caller gets a [`TimeMapEntry`] list mapping
+//! each kept span of the original to where it starts in the export (at
+//! the export's speed), so subtitles can be re-timed onto the condensed
+//! file.
+
+use super::audio_import::probe_media_duration;
+use super::compress::{check_duration, AudioCodec};
+use super::video_import::locate_ffmpeg;
+use super::waveform::{parse_wav_header, source_signature, WavFormat};
+use crate::audio::resample::Resampler;
+use crate::utils::command::no_window;
+use crate::vad::{self, SpeechSegment};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+const VAD_RATE: u32 = 16_000;
+/// Kept before / after each speech span so onsets and trailing
+/// consonants survive.
+pub const PAD_MS: u64 = 250;
+/// Pauses shorter than this stay in; cutting every breath makes the
+/// result tiring to listen to.
+pub const MIN_GAP_MS: u64 = 700;
+/// `atempo` accepts 0.5–2.0 per instance; past 2x speech stops being
+/// useful for revision anyway.
+pub const MIN_SPEED: f64 = 0.5;
+pub const MAX_SPEED: f64 = 2.0;
+
+/// One kept span: `[source_start_ms, source_end_ms)` of the original
+/// plays from `output_start_ms` in the export.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeMapEntry {
+    pub source_start_ms: u64,
+    pub source_end_ms: u64,
+    pub output_start_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CondenseResult {
+    pub output_path: String,
+    /// Written next to the audio when requested.
+    pub map_path: Option<String>,
+    pub source_duration_ms: u64,
+    pub duration_ms: u64,
+    pub speed: f64,
+    pub map: Vec<TimeMapEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedSegments {
+    source_len: u64,
+    source_mtime_ms: i64,
+    duration_ms: u64,
+    segments: Vec<SpeechSegment>,
+}
+
+/// Extension of the export: WAV stays WAV when no re-encode is needed,
+/// otherwise the source codec (Opus / FLAC) or lossless FLAC.
+pub fn condensed_extension(src: &Path, speed: f64) -> &'static str {
+    let ext = src
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    if ext == "wav" && speed == 1.0 {
+        return "wav";
+    }
+    AudioCodec::from_extension(&ext)
+        .unwrap_or(AudioCodec::Flac)
+        .extension()
+}
+
+/// Pad every speech span by [`PAD_MS`], clamp to the recording, and
+/// merge spans closer than [`MIN_GAP_MS`]. Input need not be sorted.
+pub fn keep_ranges(segments: &[SpeechSegment], total_ms: u64) -> Vec<(u64, u64)> {
+    let mut spans: Vec<(u64, u64)> = segments
+        .iter()
+        .map(|s| {
+            (
+                s.start_ms.saturating_sub(PAD_MS),
+                (s.end_ms + PAD_MS).min(total_ms),
+            )
+        })
+        .filter(|(a, b)| a < b)
+        .collect();
+    spans.sort_unstable();
+    let mut out: Vec<(u64, u64)> = Vec::with_capacity(spans.len());
+    for (start, end) in spans {
+        match out.last_mut() {
+            Some(last) if start <= last.1 + MIN_GAP_MS => last.1 = last.1.max(end),
+            _ => out.push((start, end)),
+        }
+    }
+    out
+}
+
+/// Where each kept range lands in an export played at `speed`.
+pub fn build_time_map(ranges: &[(u64, u64)], speed: f64) -> Vec<TimeMapEntry> {
+    let mut kept = 0u64;
+    ranges
+        .iter()
+        .map(|&(start, end)| {
+            let entry = TimeMapEntry {
+                source_start_ms: start,
+                source_end_ms: end,
+                output_start_ms: (kept as f64 / speed).round() as u64,
+            };
+            kept += end - start;
+            entry
+        })
+        .collect()
+}
+
+fn cache_path(cache_dir: &Path, lecture_id: &str) -> PathBuf {
+    cache_dir.join(format!("{}.json", lecture_id))
+}
+
+/// Speech spans of `audio_path` plus its duration, from the sidecar
+/// cache when it still matches the file.
+pub fn speech_segments_inner(
+    audio_path: &Path,
+    cache_dir: &Path,
+    lecture_id: &str,
+) -> Result<(Vec<SpeechSegment>, u64), String> {
+    super::validate_lecture_id(lecture_id).map_err(|e| e.to_string())?;
+    let (source_len, source_mtime_ms) = source_signature(audio_path)?;
+    let cache_file = cache_path(cache_dir, lecture_id);
+    if let Ok(text) = std::fs::read_to_string(&cache_file) {
+        if let Ok(cached) = serde_json::from_str::<CachedSegments>(&text) {
+            if cached.source_len == source_len && cached.source_mtime_ms == source_mtime_ms {
+                return Ok((cached.segments, cached.duration_ms));
+            }
+        }
+    }
+
+    let pcm = decode_16k_mono(audio_path)?;
+    let duration_ms = pcm.len() as u64 * 1000 / VAD_RATE as u64;
+    let (segments, backend) = vad::detect_speech_segments_adaptive(&pcm, None);
+    println!(
+        "[condense] {} speech segments via {:?} for {}",
+        segments.len(),
+        backend,
+        lecture_id
+    );
+
+    let cached = CachedSegments {
+        source_len,
+        source_mtime_ms,
+        duration_ms,
+        segments,
+    };
+    if std::fs::create_dir_all(cache_dir).is_ok() {
+        if let Ok(json) = serde_json::to_string(&cached) {
+            let _ = std::fs::write(&cache_file, json);
+        }
+    }
+    Ok((cached.segments, cached.duration_ms))
+}
+
+fn decode_16k_mono(path: &Path) -> Result<Vec<i16>, String> {
+    let mut file = File::open(path).map_err(|e| format!("open {}: {e}", path.display()))?;
+    match parse_wav_header(&mut file) {
+        Some(fmt) => wav_to_16k_mono(file, fmt),
+        None => ffmpeg_to_16k_mono(path),
+    }
+}
+
+fn wav_to_16k_mono(mut file: File, fmt: WavFormat) -> Result<Vec<i16>, String> {
+    file.seek(SeekFrom::Start(fmt.data_offset))
+        .map_err(|e| format!("seek: {e}"))?;
+    let mut resampler = Resampler::new(fmt.sample_rate, VAD_RATE)?;
+    let frame_bytes = fmt.channels as usize * 2;
+    let mut reader = BufReader::new(file).take(fmt.data_len);
+    let mut buf = vec![0u8; frame_bytes * 8192];
+    let mut carry: Vec<u8> = Vec::new();
+    let mut out = Vec::new();
+    loop {
+        let n = reader
+            .read(&mut buf)
+            .map_err(|e| format!("read wav: {e}"))?;
+        if n == 0 {
+            break;
+        }
+        carry.extend_from_slice(&buf[..n]);
+        let whole = carry.len() / frame_bytes * frame_bytes;
+        let mono: Vec<i16> = carry[..whole]
+            .chunks_exact(frame_bytes)
+            .map(|frame| {
+                let sum: i32 = frame
+                    .chunks_exact(2)
+                    .map(|c| i16::from_le_bytes([c[0], c[1]]) as i32)
+                    .sum();
+                (sum / fmt.channels as i32) as i16
+            })
+            .collect();
+        carry.drain(..whole);
+        out.extend(resampler.process_i16(&mono));
+    }
+    out.extend(resampler.flush_i16());
+    Ok(out)
+}
+
+fn ffmpeg_to_16k_mono(path: &Path) -> Result<Vec<i16>, String> {
+    let ffmpeg = locate_ffmpeg().ok_or_else(|| {
+        "ffmpeg not found on PATH. Install via WinGet/Homebrew/apt and retry.".to_string()
+    })?;
+    let rate = VAD_RATE.to_string();
+    let output = no_window(&ffmpeg)
+        .args([
+            "-hide_banner",
+            "-nostats",
+            "-i",
+            path.to_string_lossy().as_ref(),
+            "-vn",
+            "-ac",
+            "1",
+            "-ar",
+            rate.as_str(),
+            "-f",
+            "s16le",
+            "-acodec",
+            "pcm_s16le",
+            "-",
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .output()
+        .map_err(|e| format!("ffmpeg spawn: {e}"))?;
+    if !output.status.success() {
+        return Err(format!("ffmpeg exited {:?}", output.status.code()));
+    }
+    Ok(output
+        .stdout
+        .chunks_exact(2)
+        .map(|c| i16::from_le_bytes([c[0], c[1]]))
+        .collect())
+}
+
+/// Write the `ranges` of `src` back to back into `dest` at `speed`.
+/// `dest`'s extension must come from [`condensed_extension`]. Returns
+/// the output duration.
+pub fn condense_audio_inner(
+    src: &Path,
+    dest: &Path,
+    ranges: &[(u64, u64)],
+    speed: f64,
+) -> Result<u64, String> {
+    if ranges.is_empty() {
+        return Err("錄音中未偵測到語音，無法匯出精簡版".to_string());
+    }
+    if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+        return Err(format!(
+            "播放速度需介於 {MIN_SPEED} 與 {MAX_SPEED} 之間: {speed}"
+        ));
+    }
+    let mut file = File::open(src).map_err(|e| format!("open {}: {e}", src.display()))?;
+    let is_wav_dest = dest
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("wav"));
+    match parse_wav_header(&mut file) {
+        Some(fmt) if is_wav_dest && speed == 1.0 => condense_wav(file, fmt, dest, ranges),
+        _ => condense_via_ffmpeg(src, dest, ranges, speed),
+    }
+}
+
+fn condense_wav(
+    mut file: File,
+    fmt: WavFormat,
+    dest: &Path,
+    ranges: &[(u64, u64)],
+) -> Result<u64, String> {
+    let block_align = fmt.channels as u64 * 2;
+    let total_frames = fmt.data_len / block_align;
+    let rate = fmt.sample_rate.max(1) as u64;
+    let frame_ranges: Vec<(u64, u64)> = ranges
+        .iter()
+        .map(|&(a, b)| {
+            (
+                (a * rate / 1000).min(total_frames),
+                (b * rate / 1000).min(total_frames),
+            )
+        })
+        .filter(|(a, b)| a < b)
+        .collect();
+    let frames: u64 = frame_ranges.iter().map(|(a, b)| b - a).sum();
+    let data_size = u32::try_from(frames * block_align)
+        .map_err(|_| "精簡後的音訊超過 WAV 4GB 上限".to_string())?;
+
+    let part = dest.with_extension("wav.part");
+    let result = (|| -> std::io::Result<()> {
+        let mut out = BufWriter::new(File::create(&part)?);
+        super::write_wav_header(&mut out, data_size, fmt.sample_rate, fmt.channels)?;
+        for &(a, b) in &frame_ranges {
+            file.seek(SeekFrom::Start(fmt.data_offset + a * block_align))?;
+            let len = (b - a) * block_align;
+            let copied = std::io::copy(&mut (&mut file).take(len), &mut out)?;
+            if copied != len {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    format!("expected {} bytes, copied {}", len, copied),
+                ));
+            }
+        }
+        out.flush()?;
+        out.get_ref().sync_all()
+    })();
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&part);
+        return Err(format!("write {}: {e}", part.display()));
+    }
+    std::fs::rename(&part, dest).map_err(|e| {
+        let _ = std::fs::remove_file(&part);
+        format!("rename {} -> {}: {e}", part.display(), dest.display())
+    })?;
+    Ok(frames * 1000 / rate)
+}
+
+/// `aselect` keeps the spans, `asetpts` closes the gaps, `atempo`
+/// applies the speed.
+fn condense_filter(ranges: &[(u64, u64)], speed: f64) -> String {
+    let select: Vec<String> = ranges
+        .iter()
+        .map(|&(a, b)| {
+            format!(
+                "between(t,{:.3},{:.3})",
+                a as f64 / 1000.0,
+                b as f64 / 1000.0
+            )
+        })
+        .collect();
+    let mut f = format!("aselect='{}',asetpts=N/SR/TB", select.join("+"));
+    if speed != 1.0 {
+        f.push_str(&format!(",atempo={speed}"));
+    }
+    f
+}
+
+fn condense_via_ffmpeg(
+    src: &Path,
+    dest: &Path,
+    ranges: &[(u64, u64)],
+    speed: f64,
+) -> Result<u64, String> {
+    let ffmpeg = locate_ffmpeg().ok_or_else(|| {
+        "ffmpeg not found on PATH. Install via WinGet/Homebrew/apt and retry.".to_string()
+    })?;
+    let ext = dest.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let codec =
+        AudioCodec::from_extension(ext).ok_or_else(|| format!("不支援的匯出格式: {}", ext))?;
+    let part = dest.with_extension(format!("{}.part", codec.extension()));
+
+    let mut args: Vec<String> = vec![
+        "-hide_banner".into(),
+        "-nostats".into(),
+        "-y".into(),
+        "-i".into(),
+        src.to_string_lossy().to_string(),
+        "-vn".into(),
+        "-af".into(),
+        condense_filter(ranges, speed),
+    ];
+    args.extend(codec.ffmpeg_args(None));
+    args.push(part.to_string_lossy().to_string());
+
+    let output = no_window(&ffmpeg)
+        .args(&args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("ffmpeg spawn: {e}"))?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&part);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let tail: Vec<&str> = stderr.lines().rev().take(20).collect();
+        let tail: Vec<&str> = tail.into_iter().rev().collect();
+        return Err(format!(
+            "ffmpeg exited {:?}: {}",
+            output.status.code(),
+            tail.join("\n")
+        ));
+    }
+
+    let kept_ms: u64 = ranges.iter().map(|(a, b)| b - a).sum();
+    let expected = kept_ms as f64 / 1000.0 / speed;
+    let actual = probe_media_duration(&part).ok().flatten();
+    if let Err(e) = check_duration(Some(expected), actual) {
+        let _ = std::fs::remove_file(&part);
+        return Err(e);
+    }
+    std::fs::rename(&part, dest).map_err(|e| {
+        let _ = std::fs::remove_file(&part);
+        format!("rename {} -> {}: {e}", part.display(), dest.display())
+    })?;
+    Ok((actual.unwrap_or(expected) * 1000.0).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn seg(start_ms: u64, end_ms: u64) -> SpeechSegment {
+        SpeechSegment {
+            start_sample: 0,
+            end_sample: 0,
+            start_ms,
+            end_ms,
+            avg_energy: 0.1,
+        }
+    }
+
+    #[test]
+    fn ranges_are_padded_clamped_and_merged() {
+        let segments = [
+            seg(5_000, 6_000),
+            seg(100, 900),
+            seg(6_500, 7_000),
+            seg(9_800, 12_000),
+        ];
+        assert_eq!(
+            keep_ranges(&segments, 10_000),
+            vec![(0, 1_150), (4_750, 7_250), (9_550, 10_000)]
+        );
+    }
+
+    #[test]
+    fn time_map_accumulates_kept_audio_at_speed() {
+        let ranges = [(1_000, 2_500), (4_000, 5_000), (9_000, 9_300)];
+        let at_1x = build_time_map(&ranges, 1.0);
+        assert_eq!(
+            at_1x.iter().map(|e| e.output_start_ms).collect::<Vec<_>>(),
+            vec![0, 1_500, 2_500]
+        );
+        let at_1_5x = build_time_map(&ranges, 1.5);
+        assert_eq!(
+            at_1_5x
+                .iter()
+                .map(|e| e.output_start_ms)
+                .collect::<Vec<_>>(),
+            vec![0, 1_000, 1_667]
+        );
+        assert_eq!(at_1_5x[2].source_start_ms, 9_000);
+    }
+
+    #[test]
+    fn wav_condense_copies_only_kept_frames() {
+        let tmp = TempDir::new().unwrap();
+        let src = tmp.path().join("src.wav");
+        // 1 kHz mono so 1 ms == 1 frame; sample value == frame index.
+        let pcm: Vec<u8> = (0..3_000i16).flat_map(|i| i.to_le_bytes()).collect();
+        std::fs::write(&src, crate::recording::wrap_pcm_as_wav(&pcm, 1_000, 1)).unwrap();
+        assert_eq!(condensed_extension(&src, 1.0), "wav");
+        assert_eq!(condensed_extension(&src, 1.5), "flac");
+
+        let dest = tmp.path().join("out.wav");
+        let ms = condense_audio_inner(&src, &dest, &[(100, 200), (2_500, 4_000)], 1.0).unwrap();
+        assert_eq!(ms, 600);
+        let bytes = std::fs::read(&dest).unwrap();
+        let samples: Vec<i16> = bytes[44..]
+            .chunks_exact(2)
+            .map(|c| i16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        assert_eq!(samples.len(), 600);
+        assert_eq!(
+            (samples[0], samples[99], samples[100], samples[599]),
+            (100, 199, 2_500, 2_999)
+        );
+    }
+
+    #[test]
+    fn condense_rejects_empty_ranges_and_bad_speed() {
+        let tmp = TempDir::new().unwrap();
+        let src = tmp.path().join("src.wav");
+        std::fs::write(&src, crate::recording::wrap_pcm_as_wav(&[0; 200], 1_000, 1)).unwrap();
+        let dest = tmp.path().join("out.wav");
+        assert!(condense_audio_inner(&src, &dest, &[], 1.0).is_err());
+        assert!(condense_audio_inner(&src, &dest, &[(0, 50)], 3.0).is_err());
+    }
+
+    #[test]
+    fn speech_segments_are_cached_per_source_signature() {
+        let tmp = TempDir::new().unwrap();
+        let src = tmp.path().join("src.wav");
+        // 2 s silence, 3 s loud square wave, 2 s silence at 16 kHz.
+        let samples: Vec<i16> = (0..7 * 16_000)
+            .map(|i| {
+                if (2 * 16_000..5 * 16_000).contains(&i) {
+                    if (i / 20) % 2 == 0 {
+                        8_000
+                    } else {
+                        -8_000
+                    }
+                } else {
+                    0
+                }
+            })
+            .collect();
+        let pcm: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        std::fs::write(&src, crate::recording::wrap_pcm_as_wav(&pcm, 16_000, 1)).unwrap();
+
+        let cache = tmp.path().join("vad");
+        let (segments, duration_ms) = speech_segments_inner(&src, &cache, "l1").unwrap();
+        assert_eq!(duration_ms, 7_000);
+        assert!(!segments.is_empty());
+        assert!(segments
+            .iter()
+            .all(|s| s.start_ms >= 1_500 && s.end_ms <= 5_500));
+        assert!(cache_path(&cache, "l1").exists());
+
+        let (again, _) = speech_segments_inner(&src, &cache, "l1").unwrap();
+        assert_eq!(again.len(), segments.len());
+        assert!(speech_segments_inner(&src, &cache, "../x").is_err());
+    }
+}
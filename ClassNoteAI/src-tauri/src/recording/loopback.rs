@@ -0,0 +1,121 @@
+//! System-audio ("loopback") capture support per platform.
+//!
+//! Capture itself stays in the webview like the mic path
+//! (`audioRecorder.ts`); what differs per OS is *how* the webview can
+//! get at the system mix:
+//!
+//! - Windows: WebView2 is Chromium, whose `getDisplayMedia` with
+//!   `systemAudio: "include"` is backed by WASAPI loopback on the
+//!   default render endpoint. No driver needed.
+//! - macOS: WKWebView's `getDisplayMedia` never delivers system audio
+//!   and ScreenCaptureKit audio isn't reachable from the webview, so
+//!   the supported route is a virtual loopback device (BlackHole) that
+//!   shows up as an ordinary audio input.
+//! - Linux: PulseAudio / PipeWire expose a "Monitor of …" source for
+//!   every output, which again looks like a normal input device.
+//!
+//! The frontend asks [`get_loopback_capture_support`] once and either
+//! goes through `getDisplayMedia` or picks the input whose label
+//! matches one of `device_label_hints`.
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LoopbackMethod {
+    /// `navigator.mediaDevices.getDisplayMedia({ audio: true, ... })`.
+    DisplayMedia,
+    /// A loopback input device picked via `getUserMedia`.
+    VirtualDevice,
+    Unsupported,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LoopbackSupport {
+    pub platform: String,
+    pub method: LoopbackMethod,
+    /// Case-insensitive substrings identifying loopback inputs in
+    /// `enumerateDevices()` labels. Empty for `DisplayMedia`.
+    pub device_label_hints: Vec<String>,
+    /// Setup instructions shown when no matching device is found.
+    pub guidance: Option<String>,
+}
+
+pub fn loopback_support_for(os: &str) -> LoopbackSupport {
+    let hints = |h: &[&str]| h.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    match os {
+        "windows" => LoopbackSupport {
+            platform: os.to_string(),
+            method: LoopbackMethod::DisplayMedia,
+            device_label_hints: Vec::new(),
+            guidance: Some("開始錄音時會跳出分享視窗，請勾選「同時分享系統音訊」。".to_string()),
+        },
+        "macos" => LoopbackSupport {
+            platform: os.to_string(),
+            method: LoopbackMethod::VirtualDevice,
+            device_label_hints: hints(&[
+                "blackhole",
+                "loopback",
+                "soundflower",
+                "background music",
+            ]),
+            guidance: Some(
+                "macOS 需要虛擬音訊裝置才能錄製系統聲音：安裝 BlackHole 2ch \
+                 (brew install blackhole-2ch)，再到「音訊 MIDI 設定」建立包含 \
+                 BlackHole 與喇叭的「多重輸出裝置」並設為系統輸出。"
+                    .to_string(),
+            ),
+        },
+        "linux" => LoopbackSupport {
+            platform: os.to_string(),
+            method: LoopbackMethod::VirtualDevice,
+            device_label_hints: hints(&["monitor of"]),
+            guidance: Some(
+                "找不到 PulseAudio / PipeWire 的「Monitor of …」輸入來源；請用 \
+                 pavucontrol 確認監聽來源未被停用。"
+                    .to_string(),
+            ),
+        },
+        other => LoopbackSupport {
+            platform: other.to_string(),
+            method: LoopbackMethod::Unsupported,
+            device_label_hints: Vec::new(),
+            guidance: Some("此平台不支援錄製系統音訊。".to_string()),
+        },
+    }
+}
+
+/// How the frontend should capture system audio on this machine.
+#[tauri::command]
+pub fn get_loopback_capture_support() -> LoopbackSupport {
+    loopback_support_for(std::env::consts::OS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_uses_display_media_without_hints() {
+        let s = loopback_support_for("windows");
+        assert_eq!(s.method, LoopbackMethod::DisplayMedia);
+        assert!(s.device_label_hints.is_empty());
+    }
+
+    #[test]
+    fn macos_and_linux_look_for_virtual_devices() {
+        let mac = loopback_support_for("macos");
+        assert_eq!(mac.method, LoopbackMethod::VirtualDevice);
+        assert!(mac.device_label_hints.iter().any(|h| h == "blackhole"));
+        let linux = loopback_support_for("linux");
+        assert!(linux.device_label_hints.iter().any(|h| h == "monitor of"));
+    }
+
+    #[test]
+    fn method_serializes_kebab_case() {
+        let json = serde_json::to_string(&loopback_support_for("windows")).unwrap();
+        assert!(json.contains("\"display-media\""));
+        let json = serde_json::to_string(&loopback_support_for("ios")).unwrap();
+        assert!(json.contains("\"unsupported\""));
+    }
+}
@@ -0,0 +1,309 @@
+//! Concatenating several recordings into one lecture timeline.
+//!
+//! The usual case is a class recorded in pieces — the app was stopped
+//! over the break, or crashed and a second lecture was started to keep
+//! going. [`concat_audio_inner`] joins the files end to end and reports
+//! where each one starts in the merged timeline, so the caller can move
+//! the later pieces' subtitles over with the right offset.
+//!
+//! When every input is a 16-bit WAV with the same rate and channel
+//! count (all our own recordings on one machine), the data chunks are
+//! copied back to back — exact offsets, no re-encode. Anything else is
+//! normalised by ffmpeg's `concat` filter to 48 kHz mono and written as
+//! FLAC, since mixed sources have no common lossless container short of
+//! re-encoding anyway.
+
+use super::audio_import::probe_media_duration;
+use super::compress::check_duration;
+use super::video_import::locate_ffmpeg;
+use super::waveform::{parse_wav_header, WavFormat};
+use crate::utils::command::no_window;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MergeResult {
+    pub audio_path: String,
+    pub duration_ms: u64,
+    /// Start of each input (in the order given) within the merged audio.
+    pub offsets_ms: Vec<u64>,
+    /// Subtitles moved over from the lectures the inputs belonged to.
+    pub subtitles_moved: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConcatOutcome {
+    pub offsets_ms: Vec<u64>,
+    pub duration_ms: u64,
+}
+
+fn wav_format(path: &Path) -> Option<WavFormat> {
+    let mut file = File::open(path).ok()?;
+    parse_wav_header(&mut file)
+}
+
+/// `"wav"` when the inputs can be joined losslessly as-is, else `"flac"`.
+pub fn merged_extension(srcs: &[PathBuf]) -> &'static str {
+    if uniform_wav(srcs).is_some() {
+        "wav"
+    } else {
+        "flac"
+    }
+}
+
+fn uniform_wav(srcs: &[PathBuf]) -> Option<Vec<WavFormat>> {
+    let formats: Vec<WavFormat> = srcs.iter().map(|p| wav_format(p)).collect::<Option<_>>()?;
+    let first = formats.first()?;
+    let (rate, channels) = (first.sample_rate, first.channels);
+    formats
+        .iter()
+        .all(|f| f.sample_rate == rate && f.channels == channels)
+        .then_some(formats)
+}
+
+/// Join `srcs` in order into `dest`. `dest`'s extension must match
+/// [`merged_extension`] for the same inputs.
+pub fn concat_audio_inner(srcs: &[PathBuf], dest: &Path) -> Result<ConcatOutcome, String> {
+    if srcs.len() < 2 {
+        return Err("至少需要兩個錄音檔才能合併".to_string());
+    }
+    for p in srcs {
+        if !p.is_file() {
+            return Err(format!("找不到錄音檔: {}", p.display()));
+        }
+    }
+    match uniform_wav(srcs) {
+        Some(formats) => concat_wav(srcs, &formats, dest),
+        None => concat_via_ffmpeg(srcs, dest),
+    }
+}
+
+fn concat_wav(
+    srcs: &[PathBuf],
+    formats: &[WavFormat],
+    dest: &Path,
+) -> Result<ConcatOutcome, String> {
+    let rate = formats[0].sample_rate.max(1) as u64;
+    let channels = formats[0].channels;
+    let block_align = channels as u64 * 2;
+
+    let mut offsets_ms = Vec::with_capacity(srcs.len());
+    let mut frames_so_far = 0u64;
+    for f in formats {
+        offsets_ms.push(frames_so_far * 1000 / rate);
+        frames_so_far += f.data_len / block_align;
+    }
+    let data_len = frames_so_far * block_align;
+    let data_size =
+        u32::try_from(data_len).map_err(|_| "合併後的音訊超過 WAV 4GB 上限".to_string())?;
+
+    let part = dest.with_extension("wav.part");
+    let result = (|| -> std::io::Result<()> {
+        let mut out = BufWriter::new(File::create(&part)?);
+        super::write_wav_header(&mut out, data_size, formats[0].sample_rate, channels)?;
+        for (src, f) in srcs.iter().zip(formats) {
+            let mut file = File::open(src)?;
+            file.seek(SeekFrom::Start(f.data_offset))?;
+            // Whole frames only, so a torn tail on one piece can't shift
+            // every later sample by a byte.
+            let len = f.data_len / block_align * block_align;
+            let copied = std::io::copy(&mut BufReader::new(file).take(len), &mut out)?;
+            if copied != len {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    format!(
+                        "{}: expected {} bytes, copied {}",
+                        src.display(),
+                        len,
+                        copied
+                    ),
+                ));
+            }
+        }
+        out.flush()?;
+        out.get_ref().sync_all()
+    })();
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&part);
+        return Err(format!("write {}: {e}", part.display()));
+    }
+    std::fs::rename(&part, dest).map_err(|e| {
+        let _ = std::fs::remove_file(&part);
+        format!("rename {} -> {}: {e}", part.display(), dest.display())
+    })?;
+    Ok(ConcatOutcome {
+        offsets_ms,
+        duration_ms: frames_so_far * 1000 / rate,
+    })
+}
+
+fn concat_via_ffmpeg(srcs: &[PathBuf], dest: &Path) -> Result<ConcatOutcome, String> {
+    let ffmpeg = locate_ffmpeg().ok_or_else(|| {
+        "ffmpeg not found on PATH. Install via WinGet/Homebrew/apt and retry.".to_string()
+    })?;
+
+    let mut offsets_ms = Vec::with_capacity(srcs.len());
+    let mut total_sec = 0.0f64;
+    for p in srcs {
+        offsets_ms.push((total_sec * 1000.0).round() as u64);
+        let d =
+            probe_media_duration(p)?.ok_or_else(|| format!("無法讀取錄音長度: {}", p.display()))?;
+        total_sec += d;
+    }
+
+    let mut args: Vec<String> = vec!["-hide_banner".into(), "-nostats".into(), "-y".into()];
+    for p in srcs {
+        args.push("-i".into());
+        args.push(p.to_string_lossy().to_string());
+    }
+    args.push("-filter_complex".into());
+    args.push(concat_filter(srcs.len()));
+    args.extend(
+        [
+            "-map",
+            "[out]",
+            "-c:a",
+            "flac",
+            "-compression_level",
+            "8",
+            "-f",
+            "flac",
+        ]
+        .iter()
+        .map(|s| s.to_string()),
+    );
+    let part = dest.with_extension("flac.part");
+    args.push(part.to_string_lossy().to_string());
+
+    let output = no_window(&ffmpeg)
+        .args(&args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("ffmpeg spawn: {e}"))?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&part);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let tail: Vec<&str> = stderr.lines().rev().take(20).collect();
+        let tail: Vec<&str> = tail.into_iter().rev().collect();
+        return Err(format!(
+            "ffmpeg exited {:?}: {}",
+            output.status.code(),
+            tail.join("\n")
+        ));
+    }
+
+    let actual = probe_media_duration(&part).ok().flatten();
+    if let Err(e) = check_duration(Some(total_sec), actual) {
+        let _ = std::fs::remove_file(&part);
+        return Err(e);
+    }
+    std::fs::rename(&part, dest).map_err(|e| {
+        let _ = std::fs::remove_file(&part);
+        format!("rename {} -> {}: {e}", part.display(), dest.display())
+    })?;
+    Ok(ConcatOutcome {
+        offsets_ms,
+        duration_ms: (actual.unwrap_or(total_sec) * 1000.0).round() as u64,
+    })
+}
+
+/// Every input is brought to 48 kHz mono s16 first; `concat` refuses
+/// inputs whose formats differ.
+fn concat_filter(n: usize) -> String {
+    let mut f = String::new();
+    for i in 0..n {
+        f.push_str(&format!(
+            "[{i}:a]aresample=48000,aformat=sample_fmts=s16:channel_layouts=mono[a{i}];"
+        ));
+    }
+    for i in 0..n {
+        f.push_str(&format!("[a{i}]"));
+    }
+    f.push_str(&format!("concat=n={n}:v=0:a=1[out]"));
+    f
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_wav(path: &Path, frames: usize, value: i16, sample_rate: u32, channels: u16) {
+        let pcm: Vec<u8> = (0..frames * channels as usize)
+            .flat_map(|_| value.to_le_bytes())
+            .collect();
+        std::fs::write(
+            path,
+            crate::recording::wrap_pcm_as_wav(&pcm, sample_rate, channels),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn uniform_wavs_are_joined_with_exact_offsets() {
+        let tmp = TempDir::new().unwrap();
+        let a = tmp.path().join("a.wav");
+        let b = tmp.path().join("b.wav");
+        let c = tmp.path().join("c.wav");
+        write_wav(&a, 1_500, 1, 1_000, 1);
+        write_wav(&b, 250, 2, 1_000, 1);
+        write_wav(&c, 1_000, 3, 1_000, 1);
+        let srcs = vec![a, b, c];
+        assert_eq!(merged_extension(&srcs), "wav");
+
+        let dest = tmp.path().join("out.wav");
+        let outcome = concat_audio_inner(&srcs, &dest).unwrap();
+        assert_eq!(
+            outcome,
+            ConcatOutcome {
+                offsets_ms: vec![0, 1_500, 1_750],
+                duration_ms: 2_750,
+            }
+        );
+
+        let bytes = std::fs::read(&dest).unwrap();
+        let samples: Vec<i16> = bytes[44..]
+            .chunks_exact(2)
+            .map(|c| i16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        assert_eq!(samples.len(), 2_750);
+        assert_eq!((samples[1_499], samples[1_500], samples[1_750]), (1, 2, 3));
+    }
+
+    #[test]
+    fn mismatched_formats_need_a_reencode() {
+        let tmp = TempDir::new().unwrap();
+        let a = tmp.path().join("a.wav");
+        let b = tmp.path().join("b.wav");
+        write_wav(&a, 100, 0, 16_000, 1);
+        write_wav(&b, 100, 0, 48_000, 1);
+        assert_eq!(merged_extension(&[a.clone(), b]), "flac");
+        let m4a = tmp.path().join("c.m4a");
+        std::fs::write(&m4a, b"not a wav").unwrap();
+        assert_eq!(merged_extension(&[a, m4a]), "flac");
+    }
+
+    #[test]
+    fn needs_at_least_two_existing_inputs() {
+        let tmp = TempDir::new().unwrap();
+        let a = tmp.path().join("a.wav");
+        write_wav(&a, 10, 0, 16_000, 1);
+        let dest = tmp.path().join("o.wav");
+        assert!(concat_audio_inner(std::slice::from_ref(&a), &dest).is_err());
+        assert!(concat_audio_inner(&[a, tmp.path().join("missing.wav")], &dest).is_err());
+    }
+
+    #[test]
+    fn concat_filter_labels_every_input() {
+        assert_eq!(
+            concat_filter(2),
+            "[0:a]aresample=48000,aformat=sample_fmts=s16:channel_layouts=mono[a0];\
+             [1:a]aresample=48000,aformat=sample_fmts=s16:channel_layouts=mono[a1];\
+             [a0][a1]concat=n=2:v=0:a=1[out]"
+        );
+    }
+}
@@ -2,7 +2,9 @@
 //!
 //! Also hosts `video_import` — extracting 16kHz mono i16 PCM out of a
 //! pre-recorded video file (ffmpeg shell-out) so imported lectures
-//! feed the same Whisper transcription pipeline as live recordings.
+//! feed the same Whisper transcription pipeline as live recordings —
+//! and `audio_import`, the same thing for phone / consumer audio files
+//! with duration probing and progress events.
 //!
 //! Before v0.5.2, audio only lived in the frontend `recordedChunks: Int16Array[]`
 //! buffer until the user hit Stop — a crash, power loss, or accidental window
@@ -31,8 +33,22 @@
 //!   operates on a `&Path` instead of reading the global paths module. This
 //!   is what makes the recovery / stitching logic actually testable from
 //!   `cargo test --lib`, which is the whole point of PR #38.
-
+//! - `segments` is the opt-in successor to the single `.pcm` scratch:
+//!   rotating closed WAV segments + a manifest, so a crash loses at most
+//!   the active segment. `finalize_recording` / the orphan scan / discard
+//!   handle either layout transparently.
+
+pub mod audio_import;
+pub mod chapters;
+pub mod compress;
+pub mod condense;
+pub mod loopback;
+pub mod merge;
+pub mod power;
+pub mod segments;
+pub mod trim;
 pub mod video_import;
+pub mod waveform;
 
 use serde::{Deserialize, Serialize};
 use std::fs::{self, File, OpenOptions};
@@ -354,6 +370,9 @@ pub fn finalize_recording_inner(
     final_path: &Path,
 ) -> std::io::Result<u64> {
     let lecture_id = validate_lecture_id(lecture_id)?;
+    if segments::has_segments(in_progress_dir, lecture_id) {
+        return segments::stitch_segments_inner(in_progress_dir, lecture_id, final_path);
+    }
     let p = pcm_path(in_progress_dir, lecture_id);
     let meta = read_meta_or_default(in_progress_dir, lecture_id);
 
@@ -402,15 +421,22 @@ pub fn find_orphaned_recordings_inner(
     for entry in fs::read_dir(in_progress_dir)? {
         let entry = entry?;
         let path = entry.path();
-        if path.extension().and_then(|e| e.to_str()) != Some("pcm") {
-            continue;
-        }
+        let ext = path.extension().and_then(|e| e.to_str());
         let lecture_id = match path.file_stem().and_then(|s| s.to_str()) {
             Some(s) => s.to_string(),
             None => continue,
         };
-        let bytes = entry.metadata()?.len();
-        let meta = read_meta_or_default(in_progress_dir, &lecture_id);
+        let (bytes, meta) = match ext {
+            Some("pcm") if path.is_file() => (
+                entry.metadata()?.len(),
+                read_meta_or_default(in_progress_dir, &lecture_id),
+            ),
+            Some("segments") if path.is_dir() => match segments::probe_segments(&path) {
+                Some(found) => (found.1, found.0),
+                None => continue,
+            },
+            _ => continue,
+        };
         // Duration = bytes / (sample_rate * channels * bytes_per_sample).
         let bytes_per_sec = meta.sample_rate as u64 * meta.channels as u64 * 2;
         let duration_seconds = if bytes_per_sec > 0 {
@@ -447,7 +473,7 @@ pub fn discard_orphaned_recording_inner(
     let _ = fs::remove_file(pcm_path(in_progress_dir, lecture_id));
     let _ = fs::remove_file(meta_path(in_progress_dir, lecture_id));
     let _ = fs::remove_file(transcript_path(in_progress_dir, lecture_id));
-    Ok(())
+    segments::discard_segments_inner(in_progress_dir, lecture_id)
 }
 
 // ----- Tauri command wrappers ------------------------------------------
@@ -458,14 +484,28 @@ pub async fn append_pcm_chunk(
     data: Vec<i16>,
     sample_rate: u32,
     channels: u16,
+    segment_minutes: Option<u32>,
 ) -> Result<u64, String> {
     let dir = crate::paths::get_in_progress_audio_dir()?;
-    append_pcm_chunk_inner(&dir, &lecture_id, &data, sample_rate, channels)
-        .map_err(|e| format!("Failed to append PCM chunk: {}", e))
+    match segment_minutes {
+        Some(minutes) => segments::append_pcm_segmented_inner(
+            &dir,
+            &lecture_id,
+            &data,
+            sample_rate,
+            channels,
+            minutes.max(1).saturating_mul(60),
+        ),
+        None => append_pcm_chunk_inner(&dir, &lecture_id, &data, sample_rate, channels),
+    }
+    .map_err(|e| format!("Failed to append PCM chunk: {}", e))
 }
 
 #[tauri::command]
 pub async fn finalize_recording(lecture_id: String, final_path: String) -> Result<u64, String> {
+    // Capture is over by the time the frontend finalizes; don't rely on
+    // it also remembering `release_sleep_guard`.
+    power::release_inner();
     let in_progress = crate::paths::get_in_progress_audio_dir()?;
     let audio_dir = crate::paths::get_audio_dir()?;
 
@@ -503,6 +543,31 @@ pub async fn finalize_recording(lecture_id: String, final_path: String) -> Resul
         .map_err(|e| format!("Failed to finalize recording: {}", e))
 }
 
+/// Stitch whatever is left of an interrupted session (segments or the
+/// legacy single `.pcm`) into a fresh `lecture_<id>_<ts>.wav` under
+/// audio_dir. Returns the absolute path; the caller attaches it to the
+/// lecture row the same way it does after a normal Stop.
+#[tauri::command]
+pub async fn recover_incomplete_recording(lecture_id: String) -> Result<String, String> {
+    let in_progress = crate::paths::get_in_progress_audio_dir()?;
+    let audio_dir = crate::paths::get_audio_dir()?;
+    let lecture_id =
+        validate_lecture_id(&lecture_id).map_err(|e| format!("Invalid lecture_id: {}", e))?;
+    if !segments::has_segments(&in_progress, lecture_id)
+        && !pcm_path(&in_progress, lecture_id).exists()
+    {
+        return Err(format!(
+            "No incomplete recording for lecture {}",
+            lecture_id
+        ));
+    }
+    let ts = chrono::Utc::now().timestamp_millis();
+    let wav_path = audio_dir.join(format!("lecture_{}_{}.wav", lecture_id, ts));
+    finalize_recording_inner(&in_progress, lecture_id, &wav_path)
+        .map_err(|e| format!("Failed to recover recording: {}", e))?;
+    Ok(wav_path.to_string_lossy().to_string())
+}
+
 #[tauri::command]
 pub async fn find_orphaned_recordings() -> Result<Vec<OrphanedRecording>, String> {
     let dir = crate::paths::get_in_progress_audio_dir()?;
@@ -948,4 +1013,33 @@ mod tests {
         let (_tmp, dir) = fresh();
         assert!(discard_transcript_segments_inner(&dir, "../escape").is_err());
     }
+
+    #[test]
+    fn orphan_scan_and_finalize_cover_segmented_sessions() {
+        let (tmp, dir) = fresh();
+        segments::append_pcm_segmented_inner(&dir, "seg-lec", &vec![0i16; 32_000], 16_000, 1, 1)
+            .unwrap();
+        append_pcm_chunk_inner(&dir, "flat-lec", &vec![0i16; 16_000], 16_000, 1).unwrap();
+
+        let orphans = find_orphaned_recordings_inner(&dir).unwrap();
+        assert_eq!(orphans.len(), 2);
+        let seg = orphans.iter().find(|o| o.lecture_id == "seg-lec").unwrap();
+        assert_eq!(seg.duration_seconds, 2);
+
+        let out = tmp.path().join("audio").join("seg.wav");
+        assert_eq!(
+            finalize_recording_inner(&dir, "seg-lec", &out).unwrap(),
+            44 + 64_000
+        );
+        assert!(!segments::has_segments(&dir, "seg-lec"));
+        assert!(pcm_path(&dir, "flat-lec").exists());
+    }
+
+    #[test]
+    fn discard_removes_segmented_session() {
+        let (_tmp, dir) = fresh();
+        segments::append_pcm_segmented_inner(&dir, "lec", &[1, 2, 3], 16_000, 1, 60).unwrap();
+        discard_orphaned_recording_inner(&dir, "lec").unwrap();
+        assert!(!segments::has_segments(&dir, "lec"));
+    }
 }
@@ -0,0 +1,229 @@
+//! Keep the machine awake while a lecture is being captured.
+//!
+//! An idle laptop on battery goes to sleep after a few minutes without
+//! keyboard / mouse input — exactly the situation in a lecture hall.
+//! Sleep suspends the webview's `AudioContext`, so the recording just
+//! stops growing and the user finds a 12-minute file after a 90-minute
+//! class. While capture is active we hold an OS power assertion:
+//!
+//! - macOS: `IOPMAssertionCreateWithName(PreventUserIdleSystemSleep)`.
+//! - Windows: `SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED)`.
+//!   The state belongs to the calling thread, so it is set and cleared
+//!   on a dedicated parked thread rather than on whichever tokio worker
+//!   ran the command.
+//! - Linux: a `systemd-inhibit --what=idle:sleep` child, killed on
+//!   release. Without systemd the guard is simply unavailable.
+//!
+//! Only idle sleep is blocked. The display may still turn off, and an
+//! explicit sleep (lid close, power menu) is always honoured. The
+//! assertion dies with the process, so a crash can't leave the machine
+//! stuck awake.
+
+use std::sync::Mutex;
+
+/// The one guard held for the active recording.
+static GUARD: Mutex<Option<PowerAssertion>> = Mutex::new(None);
+
+/// An acquired assertion; released on drop.
+pub struct PowerAssertion {
+    #[cfg(target_os = "macos")]
+    id: u32,
+    #[cfg(windows)]
+    _release: std::sync::mpsc::Sender<()>,
+    #[cfg(target_os = "linux")]
+    child: std::process::Child,
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    use std::ffi::{c_char, c_void, CString};
+
+    type CFStringRef = *const c_void;
+
+    const K_CF_STRING_ENCODING_UTF8: u32 = 0x0800_0100;
+    const K_IOPM_ASSERTION_LEVEL_ON: u32 = 255;
+
+    #[link(name = "CoreFoundation", kind = "framework")]
+    extern "C" {
+        fn CFStringCreateWithCString(
+            alloc: *const c_void,
+            c_str: *const c_char,
+            encoding: u32,
+        ) -> CFStringRef;
+        fn CFRelease(cf: *const c_void);
+    }
+
+    #[link(name = "IOKit", kind = "framework")]
+    extern "C" {
+        fn IOPMAssertionCreateWithName(
+            assertion_type: CFStringRef,
+            level: u32,
+            name: CFStringRef,
+            id: *mut u32,
+        ) -> i32;
+        fn IOPMAssertionRelease(id: u32) -> i32;
+    }
+
+    fn cf_string(s: &str) -> Option<CFStringRef> {
+        let c = CString::new(s.replace('\0', "")).ok()?;
+        let r = unsafe {
+            CFStringCreateWithCString(std::ptr::null(), c.as_ptr(), K_CF_STRING_ENCODING_UTF8)
+        };
+        (!r.is_null()).then_some(r)
+    }
+
+    pub fn create(reason: &str) -> Result<u32, String> {
+        let kind = cf_string("PreventUserIdleSystemSleep")
+            .ok_or_else(|| "CFStringCreateWithCString failed".to_string())?;
+        let Some(name) = cf_string(reason) else {
+            unsafe { CFRelease(kind) };
+            return Err("CFStringCreateWithCString failed".to_string());
+        };
+        let mut id = 0u32;
+        let rc =
+            unsafe { IOPMAssertionCreateWithName(kind, K_IOPM_ASSERTION_LEVEL_ON, name, &mut id) };
+        unsafe {
+            CFRelease(name);
+            CFRelease(kind);
+        }
+        if rc == 0 {
+            Ok(id)
+        } else {
+            Err(format!("IOPMAssertionCreateWithName failed: 0x{rc:08x}"))
+        }
+    }
+
+    pub fn release(id: u32) {
+        unsafe {
+            IOPMAssertionRelease(id);
+        }
+    }
+}
+
+impl PowerAssertion {
+    #[cfg(target_os = "macos")]
+    pub fn acquire(reason: &str) -> Result<Self, String> {
+        macos::create(reason).map(|id| Self { id })
+    }
+
+    #[cfg(windows)]
+    pub fn acquire(_reason: &str) -> Result<Self, String> {
+        use windows_sys::Win32::System::Power::{
+            SetThreadExecutionState, ES_CONTINUOUS, ES_SYSTEM_REQUIRED,
+        };
+
+        let (release_tx, release_rx) = std::sync::mpsc::channel::<()>();
+        let (ready_tx, ready_rx) = std::sync::mpsc::channel::<bool>();
+        std::thread::Builder::new()
+            .name("sleep-guard".into())
+            .spawn(move || {
+                let ok =
+                    unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED) } != 0;
+                let _ = ready_tx.send(ok);
+                if !ok {
+                    return;
+                }
+                // Returns once the sender is dropped.
+                let _ = release_rx.recv();
+                unsafe {
+                    SetThreadExecutionState(ES_CONTINUOUS);
+                }
+            })
+            .map_err(|e| format!("spawn sleep-guard thread: {e}"))?;
+        match ready_rx.recv() {
+            Ok(true) => Ok(Self {
+                _release: release_tx,
+            }),
+            _ => Err("SetThreadExecutionState failed".to_string()),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn acquire(reason: &str) -> Result<Self, String> {
+        use std::process::{Command, Stdio};
+
+        let mut child = Command::new("systemd-inhibit")
+            .args([
+                "--what=idle:sleep",
+                "--who=ClassNoteAI",
+                &format!("--why={reason}"),
+                "--mode=block",
+                "sleep",
+                "infinity",
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("systemd-inhibit: {e}"))?;
+        // A missing logind makes it exit immediately; give it a moment
+        // so that shows up as an error instead of a silent no-op.
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        if let Ok(Some(status)) = child.try_wait() {
+            return Err(format!("systemd-inhibit exited: {status}"));
+        }
+        Ok(Self { child })
+    }
+
+    #[cfg(not(any(target_os = "macos", windows, target_os = "linux")))]
+    pub fn acquire(_reason: &str) -> Result<Self, String> {
+        Err("此平台不支援防止休眠".to_string())
+    }
+}
+
+impl Drop for PowerAssertion {
+    fn drop(&mut self) {
+        #[cfg(target_os = "macos")]
+        macos::release(self.id);
+        // Windows: dropping `_release` wakes the guard thread.
+        #[cfg(target_os = "linux")]
+        {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+/// Hold the recording guard. Idempotent: a second call while one is
+/// held keeps the existing assertion.
+pub fn acquire_inner(reason: &str) -> Result<(), String> {
+    let mut guard = GUARD.lock().map_err(|e| format!("sleep guard lock: {e}"))?;
+    if guard.is_none() {
+        *guard = Some(PowerAssertion::acquire(reason)?);
+        println!("[SleepGuard] acquired: {}", reason);
+    }
+    Ok(())
+}
+
+/// Drop the recording guard if one is held.
+pub fn release_inner() {
+    let taken = GUARD.lock().ok().and_then(|mut g| g.take());
+    if taken.is_some() {
+        println!("[SleepGuard] released");
+    }
+}
+
+/// Keep the system from idle-sleeping while `lecture_id` is recording.
+/// Returns `false` (not an error) when the OS offers no way to do it,
+/// so the UI can warn instead of refusing to record.
+#[tauri::command]
+pub async fn acquire_sleep_guard(lecture_id: String) -> Result<bool, String> {
+    let reason = format!("ClassNoteAI is recording lecture {}", lecture_id);
+    match tokio::task::spawn_blocking(move || acquire_inner(&reason))
+        .await
+        .map_err(|e| format!("sleep guard task join error: {e}"))?
+    {
+        Ok(()) => Ok(true),
+        Err(e) => {
+            eprintln!("[SleepGuard] unavailable: {}", e);
+            Ok(false)
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn release_sleep_guard() -> Result<(), String> {
+    tokio::task::spawn_blocking(release_inner)
+        .await
+        .map_err(|e| format!("sleep guard task join error: {e}"))
+}
@@ -0,0 +1,371 @@
+//! Segmented autosave — rotating WAV segments + recovery manifest.
+//!
+//! Follow-up to #52. The single growing `{lecture_id}.pcm` scratch file
+//! survives a crash, but only as raw PCM that needs the finalize step to
+//! become playable, and one bad write near the end (full disk, AV
+//! scanner holding the handle) can leave the whole file in question.
+//! With segmentation, the in-progress audio for a lecture lives in
+//! `{in_progress}/{lecture_id}.segments/`:
+//!
+//! ```text
+//! manifest.json      format + list of closed segments
+//! seg_00000.wav      closed, header patched, playable on its own
+//! seg_00001.wav
+//! active.wav         currently being appended to
+//! ```
+//!
+//! `active.wav` is created with a placeholder header and samples are
+//! appended raw. When it reaches `segment_seconds` of audio its header
+//! sizes are patched and it is renamed to the next `seg_NNNNN.wav`. A
+//! crash therefore loses at most whatever was still in the OS buffers
+//! for the active segment; everything rotated earlier is a complete WAV.
+//!
+//! Recovery trusts the files on disk over the manifest: a crash between
+//! the rename and the manifest write must not drop a segment, so
+//! `stitch_segments_inner` lists `seg_*.wav` itself and only reads the
+//! manifest for the sample format. Header size fields are ignored on
+//! read for the same reason — data length is always `file_len - 44`.
+
+use super::{validate_lecture_id, write_wav_header, RecordingMeta, BITS_PER_SAMPLE};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+const WAV_HEADER_LEN: u64 = 44;
+const ACTIVE_NAME: &str = "active.wav";
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// Default rotation length when the caller doesn't specify one.
+pub const DEFAULT_SEGMENT_SECONDS: u32 = 5 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentEntry {
+    pub index: u32,
+    pub file: String,
+    pub data_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentManifest {
+    pub lecture_id: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub segment_seconds: u32,
+    pub started_at: String,
+    #[serde(default)]
+    pub segments: Vec<SegmentEntry>,
+}
+
+impl SegmentManifest {
+    fn segment_bytes(&self) -> u64 {
+        self.segment_seconds as u64
+            * self.sample_rate as u64
+            * self.channels as u64
+            * (BITS_PER_SAMPLE as u64 / 8)
+    }
+
+    fn meta(&self) -> RecordingMeta {
+        RecordingMeta {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            started_at: self.started_at.clone(),
+        }
+    }
+}
+
+pub(crate) fn segments_dir(in_progress_dir: &Path, lecture_id: &str) -> PathBuf {
+    in_progress_dir.join(format!("{}.segments", lecture_id))
+}
+
+fn segment_name(index: u32) -> String {
+    format!("seg_{:05}.wav", index)
+}
+
+fn read_manifest(dir: &Path) -> Option<SegmentManifest> {
+    fs::read_to_string(dir.join(MANIFEST_NAME))
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+}
+
+/// tmp + rename so a crash mid-write never leaves a half manifest.
+fn write_manifest(dir: &Path, manifest: &SegmentManifest) -> std::io::Result<()> {
+    let json = serde_json::to_string_pretty(manifest)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+    let tmp = dir.join(format!("{}.tmp", MANIFEST_NAME));
+    fs::write(&tmp, json)?;
+    fs::rename(&tmp, dir.join(MANIFEST_NAME))
+}
+
+/// Closed segment files on disk, sorted by index.
+fn list_closed_segments(dir: &Path) -> std::io::Result<Vec<(u32, PathBuf)>> {
+    let mut out = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let Some(idx) = name
+            .strip_prefix("seg_")
+            .and_then(|rest| rest.strip_suffix(".wav"))
+            .and_then(|n| n.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        out.push((idx, path));
+    }
+    out.sort_by_key(|(idx, _)| *idx);
+    Ok(out)
+}
+
+fn data_len(path: &Path) -> u64 {
+    fs::metadata(path)
+        .map(|m| m.len().saturating_sub(WAV_HEADER_LEN))
+        .unwrap_or(0)
+}
+
+/// Patch the RIFF/data size fields of a WAV we wrote ourselves.
+fn patch_header(path: &Path, manifest: &SegmentManifest) -> std::io::Result<u64> {
+    let data = data_len(path);
+    let mut f = OpenOptions::new().write(true).open(path)?;
+    f.seek(SeekFrom::Start(0))?;
+    write_wav_header(
+        &mut f,
+        data.min(u32::MAX as u64) as u32,
+        manifest.sample_rate,
+        manifest.channels,
+    )?;
+    f.flush()?;
+    Ok(data)
+}
+
+/// Close `active.wav` into the next numbered segment.
+fn rotate(dir: &Path, manifest: &mut SegmentManifest) -> std::io::Result<()> {
+    let active = dir.join(ACTIVE_NAME);
+    let data_bytes = patch_header(&active, manifest)?;
+    let index = list_closed_segments(dir)?
+        .last()
+        .map(|(i, _)| i + 1)
+        .unwrap_or(0);
+    let file = segment_name(index);
+    fs::rename(&active, dir.join(&file))?;
+    manifest.segments.push(SegmentEntry {
+        index,
+        file,
+        data_bytes,
+    });
+    write_manifest(dir, manifest)
+}
+
+/// Append samples to the lecture's active segment, rotating once it
+/// holds `segment_seconds` of audio. Returns total data bytes across
+/// all segments so the caller can show "N minutes persisted".
+pub fn append_pcm_segmented_inner(
+    in_progress_dir: &Path,
+    lecture_id: &str,
+    samples: &[i16],
+    sample_rate: u32,
+    channels: u16,
+    segment_seconds: u32,
+) -> std::io::Result<u64> {
+    let lecture_id = validate_lecture_id(lecture_id)?;
+    let dir = segments_dir(in_progress_dir, lecture_id);
+    fs::create_dir_all(&dir)?;
+
+    let mut manifest = match read_manifest(&dir) {
+        Some(m) => m,
+        None => {
+            let m = SegmentManifest {
+                lecture_id: lecture_id.to_string(),
+                sample_rate,
+                channels,
+                segment_seconds: segment_seconds.max(1),
+                started_at: chrono::Utc::now().to_rfc3339(),
+                segments: Vec::new(),
+            };
+            write_manifest(&dir, &m)?;
+            m
+        }
+    };
+
+    let active = dir.join(ACTIVE_NAME);
+    if !active.exists() {
+        let mut f = File::create(&active)?;
+        // Placeholder sizes; patched on rotation / stitch.
+        write_wav_header(&mut f, 0, manifest.sample_rate, manifest.channels)?;
+    }
+
+    let mut buf = Vec::with_capacity(samples.len() * 2);
+    for &s in samples {
+        buf.extend_from_slice(&s.to_le_bytes());
+    }
+    let mut f = OpenOptions::new().append(true).open(&active)?;
+    f.write_all(&buf)?;
+    f.flush()?;
+    drop(f);
+
+    if data_len(&active) >= manifest.segment_bytes() {
+        rotate(&dir, &mut manifest)?;
+    }
+
+    total_data_bytes(&dir)
+}
+
+fn total_data_bytes(dir: &Path) -> std::io::Result<u64> {
+    let mut total: u64 = list_closed_segments(dir)?
+        .iter()
+        .map(|(_, p)| data_len(p))
+        .sum();
+    let active = dir.join(ACTIVE_NAME);
+    if active.exists() {
+        total += data_len(&active);
+    }
+    Ok(total)
+}
+
+/// Whether this lecture has a segmented session on disk.
+pub fn has_segments(in_progress_dir: &Path, lecture_id: &str) -> bool {
+    segments_dir(in_progress_dir, lecture_id).is_dir()
+}
+
+/// Format + total data bytes for the orphan scan, without stitching.
+pub(crate) fn probe_segments(dir: &Path) -> Option<(RecordingMeta, u64)> {
+    let manifest = read_manifest(dir)?;
+    let total = total_data_bytes(dir).ok()?;
+    Some((manifest.meta(), total))
+}
+
+/// Concatenate every closed segment plus the active tail into one WAV
+/// at `final_path`, then remove the segments dir. Returns bytes written.
+pub fn stitch_segments_inner(
+    in_progress_dir: &Path,
+    lecture_id: &str,
+    final_path: &Path,
+) -> std::io::Result<u64> {
+    let lecture_id = validate_lecture_id(lecture_id)?;
+    let dir = segments_dir(in_progress_dir, lecture_id);
+    let manifest = read_manifest(&dir).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("no segment manifest for {}", lecture_id),
+        )
+    })?;
+
+    let mut parts: Vec<PathBuf> = list_closed_segments(&dir)?
+        .into_iter()
+        .map(|(_, p)| p)
+        .collect();
+    let active = dir.join(ACTIVE_NAME);
+    if active.exists() && data_len(&active) > 0 {
+        parts.push(active);
+    }
+
+    let data_size: u64 = parts.iter().map(|p| data_len(p)).sum();
+    if data_size > u32::MAX as u64 {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "recording is too large for a single WAV file",
+        ));
+    }
+
+    if let Some(parent) = final_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut output = File::create(final_path)?;
+    write_wav_header(
+        &mut output,
+        data_size as u32,
+        manifest.sample_rate,
+        manifest.channels,
+    )?;
+    for part in &parts {
+        let mut input = File::open(part)?;
+        input.seek(SeekFrom::Start(WAV_HEADER_LEN))?;
+        // `take` guards against a segment growing while we copy (it
+        // shouldn't — recording is stopped — but the header we already
+        // wrote must stay truthful).
+        std::io::copy(&mut (&mut input).take(data_len(part)), &mut output)?;
+    }
+    output.flush()?;
+
+    let _ = fs::remove_dir_all(&dir);
+    Ok(WAV_HEADER_LEN + data_size)
+}
+
+/// Remove a segmented session without stitching.
+pub fn discard_segments_inner(in_progress_dir: &Path, lecture_id: &str) -> std::io::Result<()> {
+    let lecture_id = validate_lecture_id(lecture_id)?;
+    let _ = fs::remove_dir_all(segments_dir(in_progress_dir, lecture_id));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn fresh() -> (TempDir, PathBuf) {
+        let tmp = TempDir::new().unwrap();
+        let dir = tmp.path().join("in-progress");
+        (tmp, dir)
+    }
+
+    #[test]
+    fn rotates_after_segment_length_and_keeps_closed_segments_playable() {
+        let (_tmp, dir) = fresh();
+        // 1 s segments at 4 Hz mono → 8 bytes per segment.
+        append_pcm_segmented_inner(&dir, "lec", &[1, 2, 3, 4], 4, 1, 1).unwrap();
+        append_pcm_segmented_inner(&dir, "lec", &[5, 6], 4, 1, 1).unwrap();
+
+        let seg_dir = segments_dir(&dir, "lec");
+        let closed = list_closed_segments(&seg_dir).unwrap();
+        assert_eq!(closed.len(), 1);
+        let wav = fs::read(&closed[0].1).unwrap();
+        assert_eq!(&wav[0..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes([wav[40], wav[41], wav[42], wav[43]]), 8);
+        assert_eq!(data_len(&seg_dir.join(ACTIVE_NAME)), 4);
+
+        let manifest = read_manifest(&seg_dir).unwrap();
+        assert_eq!(manifest.segments.len(), 1);
+    }
+
+    #[test]
+    fn stitch_concatenates_closed_segments_and_active_tail_in_order() {
+        let (tmp, dir) = fresh();
+        append_pcm_segmented_inner(&dir, "lec", &[1, 2, 3, 4], 4, 1, 1).unwrap();
+        append_pcm_segmented_inner(&dir, "lec", &[5, 6, 7, 8], 4, 1, 1).unwrap();
+        append_pcm_segmented_inner(&dir, "lec", &[9], 4, 1, 1).unwrap();
+
+        let out = tmp.path().join("final.wav");
+        let bytes = stitch_segments_inner(&dir, "lec", &out).unwrap();
+        assert_eq!(bytes, 44 + 18);
+        let wav = fs::read(&out).unwrap();
+        let samples: Vec<i16> = wav[44..]
+            .chunks_exact(2)
+            .map(|c| i16::from_le_bytes([c[0], c[1]]))
+            .collect();
+        assert_eq!(samples, vec![1, 2, 3, 4, 5, 6, 7, 8, 9]);
+        assert!(!segments_dir(&dir, "lec").exists());
+    }
+
+    /// Crash between the rename and the manifest write: the segment is
+    /// on disk but not listed. Recovery must still include it.
+    #[test]
+    fn stitch_trusts_disk_over_stale_manifest() {
+        let (tmp, dir) = fresh();
+        append_pcm_segmented_inner(&dir, "lec", &[1, 2, 3, 4], 4, 1, 1).unwrap();
+        let seg_dir = segments_dir(&dir, "lec");
+        let mut m = read_manifest(&seg_dir).unwrap();
+        m.segments.clear();
+        write_manifest(&seg_dir, &m).unwrap();
+
+        let out = tmp.path().join("final.wav");
+        assert_eq!(stitch_segments_inner(&dir, "lec", &out).unwrap(), 44 + 8);
+    }
+
+    #[test]
+    fn segmented_append_rejects_path_traversal() {
+        let (_tmp, dir) = fresh();
+        assert!(append_pcm_segmented_inner(&dir, "../evil", &[1], 16_000, 1, 60).is_err());
+    }
+}
@@ -0,0 +1,255 @@
+//! Cutting dead air off the ends of a lecture recording.
+//!
+//! Recordings routinely start five minutes before class ("is this
+//! on?") and run on through the break-out chatter afterwards. Trimming
+//! keeps `[start_ms, end_ms)` of the audio and the caller shifts every
+//! subtitle timestamp back by `start_ms` in the same DB transaction
+//! that swaps `audio_path`, so playback and subtitle highlighting stay
+//! in sync.
+//!
+//! WAV (everything we record ourselves) is cut natively on frame
+//! boundaries — exact to the sample and no ffmpeg needed. Compressed
+//! recordings (`compress_lecture_audio` output) go through ffmpeg with
+//! output-side seeking, which decodes and discards rather than jumping
+//! to the nearest packet, then re-encode in the same codec.
+
+use super::audio_import::probe_media_duration;
+use super::compress::{check_duration, AudioCodec};
+use super::video_import::locate_ffmpeg;
+use super::waveform::parse_wav_header;
+use crate::utils::command::no_window;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::process::Stdio;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TrimResult {
+    pub audio_path: String,
+    pub duration_ms: u64,
+    /// Subtitles kept and moved back by `start_ms`.
+    pub subtitles_shifted: usize,
+    /// Subtitles that fell outside the kept range and were dropped.
+    pub subtitles_removed: usize,
+}
+
+/// Extension the trimmed file should get for a given source: WAV stays
+/// WAV, Opus / FLAC keep their codec, anything else (imported m4a/mp3)
+/// becomes lossless FLAC rather than a second lossy generation.
+pub fn trimmed_extension(src: &Path) -> &'static str {
+    let ext = src
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    if ext == "wav" {
+        return "wav";
+    }
+    AudioCodec::from_extension(&ext)
+        .unwrap_or(AudioCodec::Flac)
+        .extension()
+}
+
+/// Write `[start_ms, end_ms)` of `src` to `dest`. `end_ms` past the end
+/// of the recording is clamped. Returns the duration actually written.
+pub fn trim_audio_inner(
+    src: &Path,
+    dest: &Path,
+    start_ms: u64,
+    end_ms: u64,
+) -> Result<u64, String> {
+    if end_ms <= start_ms {
+        return Err(format!(
+            "裁剪範圍無效: start {} ms >= end {} ms",
+            start_ms, end_ms
+        ));
+    }
+    let mut file = File::open(src).map_err(|e| format!("open {}: {e}", src.display()))?;
+    match parse_wav_header(&mut file) {
+        Some(fmt) => trim_wav(file, fmt, dest, start_ms, end_ms),
+        None => trim_via_ffmpeg(src, dest, start_ms, end_ms),
+    }
+}
+
+fn trim_wav(
+    mut file: File,
+    fmt: super::waveform::WavFormat,
+    dest: &Path,
+    start_ms: u64,
+    end_ms: u64,
+) -> Result<u64, String> {
+    let block_align = fmt.channels as u64 * 2;
+    let total_frames = fmt.data_len / block_align;
+    let rate = fmt.sample_rate.max(1) as u64;
+    let start_frame = start_ms * rate / 1000;
+    let end_frame = (end_ms * rate / 1000).min(total_frames);
+    if start_frame >= end_frame {
+        return Err("裁剪起點超出錄音長度".to_string());
+    }
+    let data_len = (end_frame - start_frame) * block_align;
+    let data_size =
+        u32::try_from(data_len).map_err(|_| "裁剪後的音訊超過 WAV 4GB 上限".to_string())?;
+
+    file.seek(SeekFrom::Start(fmt.data_offset + start_frame * block_align))
+        .map_err(|e| format!("seek: {e}"))?;
+    let part = dest.with_extension("wav.part");
+    let result = (|| -> std::io::Result<()> {
+        let mut out = BufWriter::new(File::create(&part)?);
+        super::write_wav_header(&mut out, data_size, fmt.sample_rate, fmt.channels)?;
+        let copied = std::io::copy(&mut BufReader::new(file).take(data_len), &mut out)?;
+        if copied != data_len {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                format!("expected {} bytes, copied {}", data_len, copied),
+            ));
+        }
+        out.flush()?;
+        out.get_ref().sync_all()
+    })();
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&part);
+        return Err(format!("write {}: {e}", part.display()));
+    }
+    std::fs::rename(&part, dest).map_err(|e| {
+        let _ = std::fs::remove_file(&part);
+        format!("rename {} -> {}: {e}", part.display(), dest.display())
+    })?;
+    Ok((end_frame - start_frame) * 1000 / rate)
+}
+
+fn trim_via_ffmpeg(src: &Path, dest: &Path, start_ms: u64, end_ms: u64) -> Result<u64, String> {
+    let ffmpeg = locate_ffmpeg().ok_or_else(|| {
+        "ffmpeg not found on PATH. Install via WinGet/Homebrew/apt and retry.".to_string()
+    })?;
+    let source_sec = probe_media_duration(src)?;
+    let end_sec = match source_sec {
+        Some(total) => (end_ms as f64 / 1000.0).min(total),
+        None => end_ms as f64 / 1000.0,
+    };
+    let start_sec = start_ms as f64 / 1000.0;
+    if start_sec >= end_sec {
+        return Err("裁剪起點超出錄音長度".to_string());
+    }
+
+    let ext = dest.extension().and_then(|e| e.to_str()).unwrap_or("");
+    let codec =
+        AudioCodec::from_extension(ext).ok_or_else(|| format!("不支援的裁剪輸出格式: {}", ext))?;
+    let part = dest.with_extension(format!("{}.part", codec.extension()));
+
+    let mut args: Vec<String> = vec![
+        "-hide_banner".into(),
+        "-nostats".into(),
+        "-y".into(),
+        "-i".into(),
+        src.to_string_lossy().to_string(),
+        "-ss".into(),
+        format!("{:.3}", start_sec),
+        "-to".into(),
+        format!("{:.3}", end_sec),
+        "-vn".into(),
+    ];
+    args.extend(codec.ffmpeg_args(None));
+    args.push(part.to_string_lossy().to_string());
+
+    let output = no_window(&ffmpeg)
+        .args(&args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("ffmpeg spawn: {e}"))?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&part);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let tail: Vec<&str> = stderr.lines().rev().take(20).collect();
+        let tail: Vec<&str> = tail.into_iter().rev().collect();
+        return Err(format!(
+            "ffmpeg exited {:?}: {}",
+            output.status.code(),
+            tail.join("\n")
+        ));
+    }
+
+    let expected = end_sec - start_sec;
+    let actual = probe_media_duration(&part).ok().flatten();
+    if let Err(e) = check_duration(Some(expected), actual) {
+        let _ = std::fs::remove_file(&part);
+        return Err(e);
+    }
+    std::fs::rename(&part, dest).map_err(|e| {
+        let _ = std::fs::remove_file(&part);
+        format!("rename {} -> {}: {e}", part.display(), dest.display())
+    })?;
+    Ok((actual.unwrap_or(expected) * 1000.0).round() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_ramp_wav(path: &Path, frames: usize, sample_rate: u32, channels: u16) {
+        let mut pcm = Vec::with_capacity(frames * channels as usize * 2);
+        for i in 0..frames {
+            for _ in 0..channels {
+                pcm.extend_from_slice(&(i as i16).to_le_bytes());
+            }
+        }
+        std::fs::write(
+            path,
+            crate::recording::wrap_pcm_as_wav(&pcm, sample_rate, channels),
+        )
+        .unwrap();
+    }
+
+    fn read_samples(path: &Path) -> Vec<i16> {
+        std::fs::read(path).unwrap()[44..]
+            .chunks_exact(2)
+            .map(|c| i16::from_le_bytes([c[0], c[1]]))
+            .collect()
+    }
+
+    #[test]
+    fn wav_trim_is_frame_exact() {
+        let tmp = TempDir::new().unwrap();
+        let (src, dest) = (tmp.path().join("a.wav"), tmp.path().join("b.wav"));
+        // 1 s at 1 kHz: sample i == frame index i.
+        write_ramp_wav(&src, 1_000, 1_000, 1);
+        let dur = trim_audio_inner(&src, &dest, 250, 750).unwrap();
+        assert_eq!(dur, 500);
+        let out = read_samples(&dest);
+        assert_eq!(out.len(), 500);
+        assert_eq!(out[0], 250);
+        assert_eq!(*out.last().unwrap(), 749);
+        assert!(!tmp.path().join("b.wav.part").exists());
+    }
+
+    #[test]
+    fn wav_trim_clamps_end_and_keeps_channels_together() {
+        let tmp = TempDir::new().unwrap();
+        let (src, dest) = (tmp.path().join("a.wav"), tmp.path().join("b.wav"));
+        write_ramp_wav(&src, 1_000, 1_000, 2);
+        let dur = trim_audio_inner(&src, &dest, 900, 60_000).unwrap();
+        assert_eq!(dur, 100);
+        let out = read_samples(&dest);
+        assert_eq!(out.len(), 200);
+        assert_eq!(&out[..2], &[900, 900]);
+    }
+
+    #[test]
+    fn rejects_empty_or_out_of_range_cuts() {
+        let tmp = TempDir::new().unwrap();
+        let (src, dest) = (tmp.path().join("a.wav"), tmp.path().join("b.wav"));
+        write_ramp_wav(&src, 1_000, 1_000, 1);
+        assert!(trim_audio_inner(&src, &dest, 500, 500).is_err());
+        assert!(trim_audio_inner(&src, &dest, 2_000, 3_000).is_err());
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn trimmed_extension_keeps_codec_or_falls_back_to_flac() {
+        assert_eq!(trimmed_extension(Path::new("x.WAV")), "wav");
+        assert_eq!(trimmed_extension(Path::new("x.webm")), "webm");
+        assert_eq!(trimmed_extension(Path::new("x.m4a")), "flac");
+    }
+}
@@ -104,7 +104,7 @@ pub fn extract_pcm_16k_mono(video_path: &Path) -> Result<Vec<i16>, String> {
 /// Locate ffmpeg via PATH, with a Windows-specific WinGet fallback to
 /// match `recording/audio_capture.rs`'s lookup. Cross-platform shape:
 /// macOS/Linux just use `which`.
-fn locate_ffmpeg() -> Option<PathBuf> {
+pub(crate) fn locate_ffmpeg() -> Option<PathBuf> {
     let probe = if cfg!(windows) { "where" } else { "which" };
     if let Ok(out) = no_window(probe).arg("ffmpeg").output() {
         if out.status.success() {
@@ -157,7 +157,7 @@ fn supported_media_extension(path: &Path) -> Result<String, String> {
     }
 }
 
-fn app_temp_pcm_dir() -> Result<PathBuf, String> {
+pub(crate) fn app_temp_pcm_dir() -> Result<PathBuf, String> {
     Ok(crate::paths::get_app_data_dir()?.join("temp_pcm"))
 }
 
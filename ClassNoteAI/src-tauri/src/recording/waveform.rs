@@ -0,0 +1,437 @@
+//! Min/max peak arrays for the playback waveform.
+//!
+//! The player used to `fetch` the whole WAV into JS and run
+//! `decodeAudioData` just to draw the waveform — for a two-hour lecture
+//! that's ~700 MB through IPC and a multi-second main-thread stall.
+//! Instead the renderer asks for `buckets` (≈ the canvas width in
+//! pixels) and gets two small arrays back.
+//!
+//! Peaks are computed in two stages so the file is read exactly once
+//! regardless of how many bucket counts are requested later:
+//!   1. Stream the audio into fixed 10 ms min/max blocks
+//!      ([`PeakAccumulator`]). WAV files we wrote ourselves are parsed
+//!      directly; anything else (Opus / FLAC after
+//!      `compress_lecture_audio`, imported m4a) is decoded by ffmpeg to
+//!      8 kHz mono — plenty for a visual envelope.
+//!   2. Fold the blocks into the requested bucket count
+//!      ([`reduce_to_buckets`]).
+//!
+//! Results are cached as JSON sidecars under `{app_data}/cache/waveforms/`
+//! keyed by lecture id and bucket count, and invalidated when the audio
+//! file's size or mtime changes (re-recording, trim, compress).
+
+use super::video_import::locate_ffmpeg;
+use crate::utils::command::no_window;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+/// Rate ffmpeg decodes non-WAV sources to for peak extraction.
+const DECODE_RATE: u32 = 8_000;
+pub const MAX_BUCKETS: usize = 20_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaveformPeaks {
+    pub buckets: usize,
+    pub duration_sec: f64,
+    /// Per-bucket minimum, normalised to -1.0..=1.0.
+    pub min: Vec<f32>,
+    /// Per-bucket maximum, normalised to -1.0..=1.0.
+    pub max: Vec<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedPeaks {
+    source_len: u64,
+    source_mtime_ms: i64,
+    peaks: WaveformPeaks,
+}
+
+/// Fixed-size (10 ms) min/max blocks over an i16 frame stream.
+pub struct PeakAccumulator {
+    block: usize,
+    count: usize,
+    cur_min: i16,
+    cur_max: i16,
+    mins: Vec<i16>,
+    maxs: Vec<i16>,
+    total: u64,
+}
+
+impl PeakAccumulator {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            block: (sample_rate as usize / 100).max(1),
+            count: 0,
+            cur_min: i16::MAX,
+            cur_max: i16::MIN,
+            mins: Vec::new(),
+            maxs: Vec::new(),
+            total: 0,
+        }
+    }
+
+    /// One frame whose channels span `lo..=hi` (mono: `lo == hi`).
+    pub fn push_frame(&mut self, lo: i16, hi: i16) {
+        self.cur_min = self.cur_min.min(lo);
+        self.cur_max = self.cur_max.max(hi);
+        self.count += 1;
+        self.total += 1;
+        if self.count == self.block {
+            self.close_block();
+        }
+    }
+
+    fn close_block(&mut self) {
+        self.mins.push(self.cur_min);
+        self.maxs.push(self.cur_max);
+        self.cur_min = i16::MAX;
+        self.cur_max = i16::MIN;
+        self.count = 0;
+    }
+
+    /// Returns `(mins, maxs, total_frames)`.
+    pub fn finish(mut self) -> (Vec<i16>, Vec<i16>, u64) {
+        if self.count > 0 {
+            self.close_block();
+        }
+        (self.mins, self.maxs, self.total)
+    }
+}
+
+/// Fold fine-grained blocks into `buckets` min/max pairs. Every block
+/// lands in exactly one bucket; when there are fewer blocks than
+/// buckets, blocks are repeated so the array still spans the timeline.
+pub fn reduce_to_buckets(mins: &[i16], maxs: &[i16], buckets: usize) -> (Vec<f32>, Vec<f32>) {
+    let n = mins.len().min(maxs.len());
+    if n == 0 || buckets == 0 {
+        return (vec![0.0; buckets], vec![0.0; buckets]);
+    }
+    let mut out_min = Vec::with_capacity(buckets);
+    let mut out_max = Vec::with_capacity(buckets);
+    for b in 0..buckets {
+        let start = b * n / buckets;
+        let end = ((b + 1) * n / buckets).max(start + 1).min(n);
+        let lo = mins[start..end].iter().copied().min().unwrap_or(0);
+        let hi = maxs[start..end].iter().copied().max().unwrap_or(0);
+        out_min.push(lo as f32 / 32_768.0);
+        out_max.push(hi as f32 / 32_767.0);
+    }
+    (out_min, out_max)
+}
+
+pub(crate) struct WavFormat {
+    pub(crate) channels: u16,
+    pub(crate) sample_rate: u32,
+    pub(crate) data_offset: u64,
+    pub(crate) data_len: u64,
+}
+
+/// Minimal RIFF walk for 16-bit PCM. Tolerates a zero / 0xFFFFFFFF data
+/// size (crash-recovered or still-being-written files) by falling back
+/// to "until end of file".
+pub(crate) fn parse_wav_header<R: Read + Seek>(r: &mut R) -> Option<WavFormat> {
+    let file_len = r.seek(SeekFrom::End(0)).ok()?;
+    r.seek(SeekFrom::Start(0)).ok()?;
+    let mut riff = [0u8; 12];
+    r.read_exact(&mut riff).ok()?;
+    if &riff[0..4] != b"RIFF" || &riff[8..12] != b"WAVE" {
+        return None;
+    }
+    let mut fmt: Option<(u16, u16, u32, u16)> = None;
+    loop {
+        let mut hdr = [0u8; 8];
+        r.read_exact(&mut hdr).ok()?;
+        let size = u32::from_le_bytes([hdr[4], hdr[5], hdr[6], hdr[7]]) as u64;
+        let body_start = r.stream_position().ok()?;
+        match &hdr[0..4] {
+            b"fmt " => {
+                let mut body = [0u8; 16];
+                r.read_exact(&mut body).ok()?;
+                fmt = Some((
+                    u16::from_le_bytes([body[0], body[1]]),
+                    u16::from_le_bytes([body[2], body[3]]),
+                    u32::from_le_bytes([body[4], body[5], body[6], body[7]]),
+                    u16::from_le_bytes([body[14], body[15]]),
+                ));
+            }
+            b"data" => {
+                let (tag, channels, sample_rate, bits) = fmt?;
+                // 1 = PCM, 0xFFFE = WAVE_FORMAT_EXTENSIBLE (PCM subformat
+                // assumed; we only read 16-bit).
+                if !(tag == 1 || tag == 0xFFFE) || bits != 16 || channels == 0 {
+                    return None;
+                }
+                let remaining = file_len.saturating_sub(body_start);
+                let data_len = if size == 0 || size == u32::MAX as u64 || size > remaining {
+                    remaining
+                } else {
+                    size
+                };
+                return Some(WavFormat {
+                    channels,
+                    sample_rate,
+                    data_offset: body_start,
+                    data_len,
+                });
+            }
+            _ => {}
+        }
+        // Chunks are word-aligned.
+        r.seek(SeekFrom::Start(body_start + size + (size & 1)))
+            .ok()?;
+    }
+}
+
+fn accumulate_interleaved<R: Read>(
+    mut r: R,
+    channels: u16,
+    limit: Option<u64>,
+    acc: &mut PeakAccumulator,
+) -> std::io::Result<()> {
+    let frame_bytes = channels as usize * 2;
+    let mut buf = vec![0u8; frame_bytes * 4096];
+    let mut carry: Vec<u8> = Vec::new();
+    let mut left = limit.unwrap_or(u64::MAX);
+    while left > 0 {
+        let want = (buf.len() as u64).min(left) as usize;
+        let n = r.read(&mut buf[..want])?;
+        if n == 0 {
+            break;
+        }
+        left -= n as u64;
+        carry.extend_from_slice(&buf[..n]);
+        let whole = carry.len() / frame_bytes * frame_bytes;
+        for frame in carry[..whole].chunks_exact(frame_bytes) {
+            // Keep the envelope of all channels rather than averaging:
+            // an average would under-draw content panned hard to one side.
+            let mut lo = i16::MAX;
+            let mut hi = i16::MIN;
+            for c in frame.chunks_exact(2) {
+                let s = i16::from_le_bytes([c[0], c[1]]);
+                lo = lo.min(s);
+                hi = hi.max(s);
+            }
+            acc.push_frame(lo, hi);
+        }
+        carry.drain(..whole);
+    }
+    Ok(())
+}
+
+fn peaks_from_wav(path: &Path) -> Result<Option<(Vec<i16>, Vec<i16>, f64)>, String> {
+    let mut file = File::open(path).map_err(|e| format!("open {}: {e}", path.display()))?;
+    let Some(fmt) = parse_wav_header(&mut file) else {
+        return Ok(None);
+    };
+    file.seek(SeekFrom::Start(fmt.data_offset))
+        .map_err(|e| format!("seek: {e}"))?;
+    let mut acc = PeakAccumulator::new(fmt.sample_rate);
+    accumulate_interleaved(
+        BufReader::new(file),
+        fmt.channels,
+        Some(fmt.data_len),
+        &mut acc,
+    )
+    .map_err(|e| format!("read {}: {e}", path.display()))?;
+    let (mins, maxs, _) = acc.finish();
+    let frames = fmt.data_len / (fmt.channels as u64 * 2);
+    Ok(Some((
+        mins,
+        maxs,
+        frames as f64 / fmt.sample_rate.max(1) as f64,
+    )))
+}
+
+fn peaks_via_ffmpeg(path: &Path) -> Result<(Vec<i16>, Vec<i16>, f64), String> {
+    let ffmpeg = locate_ffmpeg().ok_or_else(|| {
+        "ffmpeg not found on PATH. Install via WinGet/Homebrew/apt and retry.".to_string()
+    })?;
+    let rate = DECODE_RATE.to_string();
+    let mut child = no_window(&ffmpeg)
+        .args([
+            "-hide_banner",
+            "-nostats",
+            "-i",
+            path.to_string_lossy().as_ref(),
+            "-vn",
+            "-ac",
+            "1",
+            "-ar",
+            rate.as_str(),
+            "-f",
+            "s16le",
+            "-acodec",
+            "pcm_s16le",
+            "-",
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("ffmpeg spawn: {e}"))?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| "ffmpeg stdout unavailable".to_string())?;
+    let mut acc = PeakAccumulator::new(DECODE_RATE);
+    accumulate_interleaved(BufReader::new(stdout), 1, None, &mut acc)
+        .map_err(|e| format!("read ffmpeg output: {e}"))?;
+    let status = child.wait().map_err(|e| format!("ffmpeg wait: {e}"))?;
+    if !status.success() {
+        return Err(format!("ffmpeg exited {:?}", status.code()));
+    }
+    let (mins, maxs, total) = acc.finish();
+    Ok((mins, maxs, total as f64 / DECODE_RATE as f64))
+}
+
+pub(crate) fn source_signature(path: &Path) -> Result<(u64, i64), String> {
+    let meta = std::fs::metadata(path).map_err(|e| format!("stat {}: {e}", path.display()))?;
+    let mtime_ms = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    Ok((meta.len(), mtime_ms))
+}
+
+fn cache_path(cache_dir: &Path, lecture_id: &str, buckets: usize) -> PathBuf {
+    cache_dir.join(format!("{}_{}.json", lecture_id, buckets))
+}
+
+/// Compute (or load cached) peaks for `audio_path`. `cache_dir` is
+/// `{app_data}/cache/waveforms`; a failed cache write is not an error.
+pub fn generate_waveform_inner(
+    audio_path: &Path,
+    cache_dir: &Path,
+    lecture_id: &str,
+    buckets: usize,
+) -> Result<WaveformPeaks, String> {
+    super::validate_lecture_id(lecture_id).map_err(|e| e.to_string())?;
+    let buckets = buckets.clamp(1, MAX_BUCKETS);
+    let (source_len, source_mtime_ms) = source_signature(audio_path)?;
+    let cache_file = cache_path(cache_dir, lecture_id, buckets);
+
+    if let Ok(text) = std::fs::read_to_string(&cache_file) {
+        if let Ok(cached) = serde_json::from_str::<CachedPeaks>(&text) {
+            if cached.source_len == source_len && cached.source_mtime_ms == source_mtime_ms {
+                return Ok(cached.peaks);
+            }
+        }
+    }
+
+    let (mins, maxs, duration_sec) = match peaks_from_wav(audio_path)? {
+        Some(found) => found,
+        None => peaks_via_ffmpeg(audio_path)?,
+    };
+    let (min, max) = reduce_to_buckets(&mins, &maxs, buckets);
+    let peaks = WaveformPeaks {
+        buckets,
+        duration_sec,
+        min,
+        max,
+    };
+
+    let cached = CachedPeaks {
+        source_len,
+        source_mtime_ms,
+        peaks,
+    };
+    if std::fs::create_dir_all(cache_dir).is_ok() {
+        if let Ok(json) = serde_json::to_string(&cached) {
+            let _ = std::fs::write(&cache_file, json);
+        }
+    }
+    Ok(cached.peaks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_wav(path: &Path, samples: &[i16], sample_rate: u32, channels: u16) {
+        let mut pcm = Vec::with_capacity(samples.len() * 2);
+        for s in samples {
+            pcm.extend_from_slice(&s.to_le_bytes());
+        }
+        std::fs::write(
+            path,
+            crate::recording::wrap_pcm_as_wav(&pcm, sample_rate, channels),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn reduce_keeps_extremes_in_the_right_bucket() {
+        let mins = vec![-1, -2, -30_000, -4];
+        let maxs = vec![1, 20_000, 3, 4];
+        let (lo, hi) = reduce_to_buckets(&mins, &maxs, 2);
+        assert_eq!(lo.len(), 2);
+        assert!(lo[1] < -0.9 && lo[0] > -0.01);
+        assert!(hi[0] > 0.6 && hi[1] < 0.01);
+    }
+
+    #[test]
+    fn reduce_stretches_short_input_over_all_buckets() {
+        let (lo, hi) = reduce_to_buckets(&[-100], &[100], 5);
+        assert_eq!(lo.len(), 5);
+        assert!(hi.iter().all(|&h| h > 0.0));
+    }
+
+    #[test]
+    fn wav_peaks_report_duration_and_loud_section() {
+        let tmp = TempDir::new().unwrap();
+        let wav = tmp.path().join("lecture.wav");
+        // 1 s silence, 1 s full-scale square wave, 16 kHz mono.
+        let mut samples = vec![0i16; 16_000];
+        samples.extend((0..16_000).map(|i| if i % 2 == 0 { 30_000 } else { -30_000 }));
+        write_wav(&wav, &samples, 16_000, 1);
+
+        let peaks = generate_waveform_inner(&wav, &tmp.path().join("cache"), "l1", 4).unwrap();
+        assert!((peaks.duration_sec - 2.0).abs() < 1e-9);
+        assert_eq!(peaks.max.len(), 4);
+        assert!(peaks.max[0] < 0.01 && peaks.max[3] > 0.9);
+        assert!(peaks.min[3] < -0.9);
+    }
+
+    #[test]
+    fn cache_is_reused_until_the_source_changes() {
+        let tmp = TempDir::new().unwrap();
+        let wav = tmp.path().join("lecture.wav");
+        let cache = tmp.path().join("cache");
+        write_wav(&wav, &vec![1_000i16; 1_600], 16_000, 1);
+        generate_waveform_inner(&wav, &cache, "l1", 8).unwrap();
+        assert!(cache_path(&cache, "l1", 8).exists());
+
+        // Different length → signature mismatch → recomputed.
+        write_wav(&wav, &vec![20_000i16; 3_200], 16_000, 1);
+        let peaks = generate_waveform_inner(&wav, &cache, "l1", 8).unwrap();
+        assert!((peaks.duration_sec - 0.2).abs() < 1e-9);
+        assert!(peaks.max[0] > 0.5);
+    }
+
+    #[test]
+    fn stereo_wav_uses_loudest_channel() {
+        let tmp = TempDir::new().unwrap();
+        let wav = tmp.path().join("stereo.wav");
+        // Left silent, right loud.
+        let samples: Vec<i16> = (0..3_200)
+            .map(|i| if i % 2 == 0 { 0 } else { 25_000 })
+            .collect();
+        write_wav(&wav, &samples, 16_000, 2);
+        let peaks = generate_waveform_inner(&wav, &tmp.path().join("c"), "s", 2).unwrap();
+        assert!((peaks.duration_sec - 0.1).abs() < 1e-9);
+        assert!(peaks.max.iter().all(|&m| m > 0.7));
+    }
+
+    #[test]
+    fn rejects_path_like_lecture_ids() {
+        let tmp = TempDir::new().unwrap();
+        let wav = tmp.path().join("a.wav");
+        write_wav(&wav, &[0; 10], 16_000, 1);
+        assert!(generate_waveform_inner(&wav, tmp.path(), "../x", 10).is_err());
+    }
+}
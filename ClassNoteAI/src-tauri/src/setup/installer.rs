@@ -4,13 +4,42 @@ use futures_util::StreamExt;
  *
  * Handles automated installation of system dependencies and model downloads.
  * Uses Tauri events for progress reporting.
+ *
+ * Every file a run will download is recorded with the app's other
+ * resumable downloads (`downloads::control`) before it starts, so quitting
+ * mid-way (or a network error) leaves the partial file and its record
+ * behind. The record is checkpointed with the bytes done while the file
+ * downloads. The next run continues those files with a Range request
+ * from the bytes on disk, and the wizard asks for [`pending_downloads`]
+ * on launch to pick the run up again.
+ *
+ * Installing a requirement that [`check_all_requirements`] found
+ * `Corrupted` repairs it: only the embedding model's broken files are
+ * downloaded again. The translation model is only published as one
+ * archive, so its repair fetches and extracts the whole archive.
+ *
+ * [`check_all_requirements`]: super::requirements::check_all_requirements
  */
-use std::path::Path;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
 use tauri::Emitter;
 use tokio::sync::mpsc;
 
-use super::progress::Progress;
+use super::progress::{Progress, PROGRESS_EVENT};
+use super::requirements::{broken_files, EMBEDDING_MODEL_FILES};
+use super::state::{self, StepState};
+use crate::downloads::control::{self, DownloadSource};
+
+const WHISPER_URL: &str = "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin";
+const TRANSLATION_URL: &str = "https://github.com/sklonely/ClassNoteAI/releases/download/v0.1.2-models/m2m100-418M-ct2-int8.zip";
+const EMBEDDING_BASE_URL: &str = "https://huggingface.co/BAAI/bge-small-en-v1.5/resolve/main";
+/// How often a running download checkpoints its progress.
+const CHECKPOINT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+/// Free space to leave on top of what a requirement needs, so finishing
+/// the install doesn't leave the disk completely full.
+const DISK_HEADROOM_MB: u64 = 100;
+const MB: u64 = 1024 * 1024;
 
 // Global cancellation flag
 static CANCEL_FLAG: AtomicBool = AtomicBool::new(false);
@@ -31,12 +60,192 @@ fn is_cancelled() -> bool {
     CANCEL_FLAG.load(Ordering::SeqCst)
 }
 
+/// A file the setup wizard downloaded in a run that didn't finish.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SetupDownload {
+    /// Where the file goes; identifies the download.
+    pub dest: String,
+    /// The setup requirement it belongs to, e.g. `whisper_model`.
+    pub requirement_id: String,
+    pub url: String,
+    /// Bytes on disk at the last checkpoint.
+    pub bytes_done: u64,
+    pub total_bytes: Option<u64>,
+}
+
+fn setup_source(req_id: &str, url: &str, dest: &Path) -> DownloadSource {
+    DownloadSource::Setup {
+        requirement_id: req_id.to_string(),
+        url: url.to_string(),
+        dest: dest.to_string_lossy().into_owned(),
+    }
+}
+
+/// Downloads a previous run queued and didn't finish, in queue order.
+pub fn pending_downloads() -> Vec<SetupDownload> {
+    control::pending()
+        .into_iter()
+        .filter_map(|p| match p.source {
+            DownloadSource::Setup {
+                requirement_id,
+                url,
+                dest,
+            } => Some(SetupDownload {
+                dest,
+                requirement_id,
+                url,
+                bytes_done: p.bytes_done,
+                total_bytes: p.total_bytes,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The queued download of `dest`, if a previous run left one.
+fn queued(dest: &Path) -> Option<SetupDownload> {
+    let dest = dest.to_string_lossy();
+    pending_downloads().into_iter().find(|d| d.dest == dest)
+}
+
+/// The files requirement `req_id` downloads, as `(url, dest)`.
+fn requirement_files(req_id: &str) -> Result<Vec<(String, PathBuf)>, String> {
+    Ok(match req_id {
+        "whisper_model" => vec![(
+            WHISPER_URL.to_string(),
+            crate::paths::get_whisper_models_dir()?.join("ggml-base.bin"),
+        )],
+        "translation_model" => vec![(
+            TRANSLATION_URL.to_string(),
+            translation_model_dir()?.with_extension("zip"),
+        )],
+        "embedding_model" => {
+            let dest_dir = embedding_model_dir()?;
+            EMBEDDING_MODEL_FILES
+                .iter()
+                .map(|(file, _)| {
+                    (
+                        format!("{}/{}", EMBEDDING_BASE_URL, file),
+                        dest_dir.join(file),
+                    )
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    })
+}
+
+/// The files of `req_id` this run downloads: for the embedding model
+/// the broken ones and any an earlier run left partly downloaded, for
+/// the rest all of them.
+async fn files_to_download(req_id: &str) -> Result<Vec<(String, PathBuf)>, String> {
+    let files = requirement_files(req_id)?;
+    if req_id != "embedding_model" {
+        return Ok(files);
+    }
+    let broken = broken_files(&embedding_model_dir()?, EMBEDDING_MODEL_FILES);
+    let mut needed = Vec::new();
+    for (url, dest) in files {
+        let is_queued = queued(&dest).is_some();
+        let is_broken = dest
+            .file_name()
+            .is_some_and(|name| broken.iter().any(|b| name == b.as_str()));
+        if is_queued || is_broken {
+            needed.push((url, dest));
+        }
+    }
+    Ok(needed)
+}
+
+/// Roughly the disk space requirement `req_id` takes while installing,
+/// in MB. Archives count twice: the extracted files sit next to the zip
+/// until it's deleted.
+fn install_footprint_mb(req_id: &str) -> u64 {
+    match req_id {
+        "whisper_model" => 150,
+        "translation_model" => 440 * 2,
+        "embedding_model" => 135,
+        _ => 0,
+    }
+}
+
+/// The error to fail with when `available` bytes can't fit `needed`.
+fn check_free_space(needed: u64, available: u64) -> Result<(), String> {
+    if available >= needed {
+        return Ok(());
+    }
+    Err(format!(
+        "磁碟空間不足：需要約 {} MB，目前只剩 {} MB，請清出空間後再試",
+        needed.div_ceil(MB),
+        available / MB
+    ))
+}
+
+/// Fail before downloading anything for `req_id` if its files won't fit,
+/// rather than part-way through with a truncated file on disk. Bytes
+/// already downloaded by an earlier run count as done. If free space
+/// can't be read, the install goes ahead.
+async fn ensure_free_space(req_id: &str) -> Result<(), String> {
+    let footprint = install_footprint_mb(req_id) * MB;
+    if footprint == 0 {
+        return Ok(());
+    }
+    let files = requirement_files(req_id)?;
+    // The destination may not exist yet on a first run.
+    let Some(probe) = files
+        .first()
+        .and_then(|(_, dest)| dest.ancestors().find(|p| p.exists()))
+        .map(Path::to_path_buf)
+    else {
+        return Ok(());
+    };
+    let mut on_disk = 0;
+    for (url, dest) in &files {
+        on_disk += resume_offset(url, dest).await;
+    }
+    let needed = footprint.saturating_sub(on_disk) + DISK_HEADROOM_MB * MB;
+
+    let available = match super::requirements::available_disk_bytes(&probe) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            log::warn!("[Setup] {}; skipping the free-space check", e);
+            return Ok(());
+        }
+    };
+    check_free_space(needed, available).map_err(|e| {
+        log::warn!("[Setup] {} ({}, {})", e, req_id, probe.display());
+        e
+    })?;
+    Ok(())
+}
+
+fn translation_model_dir() -> Result<PathBuf, String> {
+    Ok(crate::paths::get_translation_models_dir()?.join("m2m100-418M-ct2-int8"))
+}
+
+fn embedding_model_dir() -> Result<PathBuf, String> {
+    Ok(crate::paths::get_embedding_models_dir()?.join("bge-small-en-v1.5"))
+}
+
+/// Where to continue `dest` from: the bytes on disk, if the queue says
+/// they're a partial download of `url`; otherwise 0.
+async fn resume_offset(url: &str, dest: &Path) -> u64 {
+    match queued(dest) {
+        Some(download) if download.url == url => tokio::fs::metadata(dest)
+            .await
+            .map(|m| m.len())
+            .unwrap_or(0),
+        _ => 0,
+    }
+}
+
 // NOTE: Removed unused install functions:
 // - install_homebrew() and install_with_brew()
 // These were for development-time dependencies (Homebrew, CMake, FFmpeg)
 // that end users don't need. The app is self-contained after packaging.
 
-/// Download a file with progress reporting
+/// Download a file with progress reporting, continuing a partial file
+/// the download queue knows about.
 pub async fn download_file(
     url: &str,
     dest: &Path,
@@ -59,48 +268,103 @@ pub async fn download_file(
             .map_err(|e| format!("Failed to create directory: {}", e))?;
     }
 
+    let resume_from = resume_offset(url, dest).await;
+    let source = setup_source(task_id, url, dest);
+    control::queue(&source);
+
     // Create HTTP client
-    let client = reqwest::Client::new();
-    let response = client
-        .get(url)
+    let client = crate::downloads::proxy::client_builder()
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let mut request = client.get(url);
+    if resume_from > 0 {
+        println!("[Setup] Resuming {:?} from byte {}", dest, resume_from);
+        request = request.header("Range", format!("bytes={}-", resume_from));
+    }
+    let response = request
         .send()
         .await
         .map_err(|e| format!("Failed to start download: {}", e))?;
 
-    if !response.status().is_success() {
-        return Err(format!(
-            "Download failed with status: {}",
-            response.status()
-        ));
+    let status = response.status();
+    if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE && resume_from > 0 {
+        // Nothing past what's on disk: the quit came after the last byte.
+        control::forget(&source.task_id());
+        progress_tx
+            .send(Progress::completed(task_id, task_name))
+            .await
+            .ok();
+        return Ok(());
+    }
+    if !status.is_success() {
+        return Err(format!("Download failed with status: {}", status));
     }
 
-    let total_size = response.content_length().unwrap_or(0);
-    let mut downloaded: u64 = 0;
+    // A server that ignores the Range header sends the whole file again.
+    let resumed = resume_from > 0 && status == reqwest::StatusCode::PARTIAL_CONTENT;
+    let mut downloaded: u64 = if resumed { resume_from } else { 0 };
+    let total_size = response
+        .content_length()
+        .map(|len| len + downloaded)
+        .unwrap_or(0);
     let mut last_progress_update = std::time::Instant::now();
-    let mut last_downloaded: u64 = 0;
-
-    // Create output file
-    let mut file = tokio::fs::File::create(dest)
-        .await
-        .map_err(|e| format!("Failed to create file: {}", e))?;
+    let mut last_downloaded: u64 = downloaded;
+    let mut last_checkpoint = std::time::Instant::now();
+    let checkpoint = |downloaded: u64| {
+        control::checkpoint(
+            &source.task_id(),
+            downloaded,
+            (total_size > 0).then_some(total_size),
+        )
+    };
+
+    // Open (or create) the output file
+    let mut file = if resumed {
+        tokio::fs::OpenOptions::new()
+            .append(true)
+            .open(dest)
+            .await
+            .map_err(|e| format!("Failed to open file: {}", e))?
+    } else {
+        tokio::fs::File::create(dest)
+            .await
+            .map_err(|e| format!("Failed to create file: {}", e))?
+    };
 
     let mut stream = response.bytes_stream();
 
+    use tokio::io::AsyncWriteExt;
     while let Some(chunk_result) = stream.next().await {
         if is_cancelled() {
             // Clean up partial file
+            drop(file);
             tokio::fs::remove_file(dest).await.ok();
+            control::forget(&source.task_id());
             return Err("Download cancelled".to_string());
         }
 
-        let chunk = chunk_result.map_err(|e| format!("Download error: {}", e))?;
+        let chunk = match chunk_result {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                // Keep what arrived for the next attempt.
+                file.flush().await.ok();
+                checkpoint(downloaded);
+                return Err(format!("Download error: {}", e));
+            }
+        };
 
-        use tokio::io::AsyncWriteExt;
         file.write_all(&chunk)
             .await
             .map_err(|e| format!("Write error: {}", e))?;
 
         downloaded += chunk.len() as u64;
+        crate::downloads::throttle::throttle(chunk.len()).await;
+
+        if last_checkpoint.elapsed() >= CHECKPOINT_INTERVAL {
+            file.flush().await.ok();
+            checkpoint(downloaded);
+            last_checkpoint = std::time::Instant::now();
+        }
 
         // Update progress every 100ms
         if last_progress_update.elapsed().as_millis() >= 100 {
@@ -117,6 +381,11 @@ pub async fn download_file(
         }
     }
 
+    file.flush()
+        .await
+        .map_err(|e| format!("Write error: {}", e))?;
+    control::forget(&source.task_id());
+
     progress_tx
         .send(Progress::completed(task_id, task_name))
         .await
@@ -152,7 +421,7 @@ pub async fn download_and_extract_model(
     let zip_path = temp_zip.clone();
     let dest = dest_dir.to_path_buf();
 
-    tokio::task::spawn_blocking(move || {
+    let extracted = tokio::task::spawn_blocking(move || {
         let file =
             std::fs::File::open(&zip_path).map_err(|e| format!("Failed to open zip: {}", e))?;
 
@@ -171,7 +440,13 @@ pub async fn download_and_extract_model(
         Ok::<(), String>(())
     })
     .await
-    .map_err(|e| format!("Task error: {}", e))??;
+    .map_err(|e| format!("Task error: {}", e))?;
+    if let Err(e) = extracted {
+        // A zip that downloaded in full and won't extract is corrupt;
+        // resuming it would only fail again.
+        tokio::fs::remove_file(&temp_zip).await.ok();
+        return Err(e);
+    }
 
     progress_tx
         .send(Progress::completed(task_id, task_name))
@@ -193,88 +468,229 @@ pub async fn install_requirements(
     let window_clone = window.clone();
     tokio::spawn(async move {
         while let Some(progress) = rx.recv().await {
-            window_clone.emit("setup-progress", &progress).ok();
+            window_clone.emit(PROGRESS_EVENT, &progress).ok();
         }
     });
 
+    let queued: Vec<_> = requirement_ids
+        .iter()
+        .map(|req_id| Ok((req_id, requirement_files(req_id)?)))
+        .collect::<Result<_, String>>()?;
+
+    let result = install_each(&requirement_ids, &tx).await;
+    if is_cancelled() {
+        // A cancelled run isn't one to pick up on the next launch.
+        for (req_id, files) in &queued {
+            for (url, dest) in files {
+                control::forget(&setup_source(req_id, url, dest).task_id());
+            }
+        }
+    }
+    result
+}
+
+/// Run the install steps for `requirement_ids` (see [`super::state`]).
+/// A step that fails is rolled back and the rest still run; the error
+/// lists every step that failed.
+async fn install_each(
+    requirement_ids: &[String],
+    tx: &mpsc::Sender<Progress>,
+) -> Result<(), String> {
+    let mut steps = state::load().await.unwrap_or_else(|e| {
+        log::warn!("[Setup] {}; starting every step over", e);
+        state::InstallSteps::new()
+    });
+    // Only model downloads are tracked; the rest have nothing to resume
+    // or roll back.
+    let tracked: Vec<String> = requirement_ids
+        .iter()
+        .filter(|id| install_footprint_mb(id) > 0)
+        .cloned()
+        .collect();
+    state::begin(&mut steps, &tracked);
+    save_steps(&steps).await;
+
+    // Queue every download before starting, so a quit during the first
+    // file still remembers the rest.
+    for req_id in requirement_ids {
+        if steps.get(req_id) == Some(&StepState::Completed) {
+            continue;
+        }
+        for (url, dest) in files_to_download(req_id).await? {
+            control::queue(&setup_source(req_id, &url, &dest));
+        }
+    }
+
+    let mut failed = Vec::new();
     for req_id in requirement_ids {
         if is_cancelled() {
             return Err("Installation cancelled by user".to_string());
         }
+        if steps.get(req_id) == Some(&StepState::Completed) {
+            println!("[Setup] {} already installed by an earlier run", req_id);
+            tx.send(Progress::completed(req_id, task_name(req_id)))
+                .await
+                .ok();
+            continue;
+        }
+
+        let is_tracked = steps.contains_key(req_id);
+        if is_tracked {
+            steps.insert(req_id.clone(), StepState::Running);
+            save_steps(&steps).await;
+        }
+        let result = install_one(req_id, tx).await;
+        if let Err(e) = &result {
+            rollback(req_id).await;
+            if !is_cancelled() {
+                tx.send(Progress::failed(req_id, task_name(req_id), e))
+                    .await
+                    .ok();
+            }
+        }
+        if is_tracked {
+            let state = match &result {
+                Ok(()) => StepState::Completed,
+                // Not failed: nothing went wrong with it.
+                Err(_) if is_cancelled() => StepState::Pending,
+                Err(e) => StepState::Failed { error: e.clone() },
+            };
+            steps.insert(req_id.clone(), state);
+            save_steps(&steps).await;
+        }
+        match result {
+            Err(e) if is_cancelled() => return Err(e),
+            Err(e) => failed.push(format!("{}: {}", task_name(req_id), e)),
+            Ok(()) => {}
+        }
+    }
+
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} 個項目安裝失敗，重試時只會重新安裝這些項目。{}",
+            failed.len(),
+            failed.join("；")
+        ))
+    }
+}
+
+/// Save the step states; like the download queue, only logged on failure.
+async fn save_steps(steps: &state::InstallSteps) {
+    if let Err(e) = state::save(steps).await {
+        log::warn!("[Setup] install state not saved: {}", e);
+    }
+}
+
+/// What the wizard calls requirement `req_id`'s task.
+fn task_name(req_id: &str) -> &str {
+    match req_id {
+        "whisper_model" => "下載 Whisper 模型",
+        "translation_model" => "下載翻譯模型",
+        "embedding_model" => "下載 Embedding 模型",
+        "vc_runtime" => "Visual C++ 執行階段",
+        other => other,
+    }
+}
+
+/// Undo what a failed or cancelled step left half done. Partial
+/// downloads stay for the next run to continue; a half-extracted model
+/// directory would look installed, so it goes.
+async fn rollback(req_id: &str) {
+    if req_id != "translation_model" {
+        return;
+    }
+    let Ok(dir) = translation_model_dir() else {
+        return;
+    };
+    if dir.exists() {
+        match tokio::fs::remove_dir_all(&dir).await {
+            Ok(()) => println!("[Setup] Rolled back {:?}", dir),
+            Err(e) => log::warn!("[Setup] Failed to roll back {:?}: {}", dir, e),
+        }
+    }
+}
 
-        match req_id.as_str() {
-            // NOTE: Removed homebrew, cmake, ffmpeg install handlers
-            // These are development dependencies, not needed for end users
-            "whisper_model" => {
-                // Whisper models are single .bin files from Hugging Face
-                // 使用統一路徑: {app_data}/models/whisper/
-                let whisper_dir = crate::paths::get_whisper_models_dir()?;
-                std::fs::create_dir_all(&whisper_dir)
-                    .map_err(|e| format!("創建目錄失敗: {}", e))?;
-
-                let dest_file = whisper_dir.join("ggml-base.bin");
+async fn install_one(req_id: &str, tx: &mpsc::Sender<Progress>) -> Result<(), String> {
+    ensure_free_space(req_id).await?;
+
+    match req_id {
+        // NOTE: Removed homebrew, cmake, ffmpeg install handlers
+        // These are development dependencies, not needed for end users
+        "whisper_model" => {
+            // Whisper models are single .bin files from Hugging Face
+            // 使用統一路徑: {app_data}/models/whisper/
+            let whisper_dir = crate::paths::get_whisper_models_dir()?;
+            std::fs::create_dir_all(&whisper_dir).map_err(|e| format!("創建目錄失敗: {}", e))?;
+
+            let dest_file = whisper_dir.join("ggml-base.bin");
+            download_file(
+                WHISPER_URL,
+                &dest_file,
+                "whisper_model",
+                task_name("whisper_model"),
+                tx.clone(),
+            )
+            .await?;
+        }
+        "translation_model" => {
+            // Translation model from GitHub Releases (ZIP file to extract)
+            // 使用統一路徑: {app_data}/models/translation/
+            let dest = translation_model_dir()?;
+            download_and_extract_model(
+                TRANSLATION_URL,
+                &dest,
+                "translation_model",
+                task_name("translation_model"),
+                tx.clone(),
+            )
+            .await?;
+        }
+        "embedding_model" => {
+            // Embedding model from Hugging Face (individual files).
+            // v0.5.2: switched from nomic-embed-text-v1 to BAAI/bge-small-en-v1.5.
+            // Reason: nomic uses NomicBert (rotary embeddings + SwiGLU), which
+            // Candle's stock BertModel::load cannot decode — it always fails
+            // with "cannot find tensor embeddings.position_embeddings.weight".
+            // bge-small-en-v1.5 is a standard BERT (384-d, ~130 MB) that loads
+            // cleanly. Cross-lingual zh→en queries are translated upstream.
+            let dest_dir = embedding_model_dir()?;
+            std::fs::create_dir_all(&dest_dir).map_err(|e| format!("創建目錄失敗: {}", e))?;
+
+            for (url, dest_file) in files_to_download("embedding_model").await? {
+                if is_cancelled() {
+                    return Err("Installation cancelled by user".to_string());
+                }
+
+                let local_file = dest_file.file_name().unwrap_or_default().to_string_lossy();
                 download_file(
-                    "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin",
+                    &url,
                     &dest_file,
-                    "whisper_model",
-                    "下載 Whisper 模型",
+                    "embedding_model",
+                    &format!("{} ({})", task_name("embedding_model"), local_file),
                     tx.clone(),
                 )
                 .await?;
             }
-            "translation_model" => {
-                // Translation model from GitHub Releases (ZIP file to extract)
-                // 使用統一路徑: {app_data}/models/translation/
-                let translation_dir = crate::paths::get_translation_models_dir()?;
-                let dest = translation_dir.join("m2m100-418M-ct2-int8");
-                download_and_extract_model(
-                    "https://github.com/sklonely/ClassNoteAI/releases/download/v0.1.2-models/m2m100-418M-ct2-int8.zip",
-                    &dest,
-                    "translation_model",
-                    "下載翻譯模型",
-                    tx.clone()
-                ).await?;
-            }
-            "embedding_model" => {
-                // Embedding model from Hugging Face (individual files).
-                // v0.5.2: switched from nomic-embed-text-v1 to BAAI/bge-small-en-v1.5.
-                // Reason: nomic uses NomicBert (rotary embeddings + SwiGLU), which
-                // Candle's stock BertModel::load cannot decode — it always fails
-                // with "cannot find tensor embeddings.position_embeddings.weight".
-                // bge-small-en-v1.5 is a standard BERT (384-d, ~33 MB) that loads
-                // cleanly. Cross-lingual zh→en queries are translated upstream.
-                let embedding_dir = crate::paths::get_embedding_models_dir()?;
-                let dest_dir = embedding_dir.join("bge-small-en-v1.5");
-                std::fs::create_dir_all(&dest_dir).map_err(|e| format!("創建目錄失敗: {}", e))?;
-
-                let base_url = "https://huggingface.co/BAAI/bge-small-en-v1.5/resolve/main";
-                let files = [
-                    ("model.safetensors", "model.safetensors"),
-                    ("tokenizer.json", "tokenizer.json"),
-                    ("config.json", "config.json"),
-                ];
-
-                for (remote_file, local_file) in &files {
-                    if is_cancelled() {
-                        return Err("Installation cancelled by user".to_string());
-                    }
-
-                    let url = format!("{}/{}", base_url, remote_file);
-                    let dest_file = dest_dir.join(local_file);
-
-                    download_file(
-                        &url,
-                        &dest_file,
-                        "embedding_model",
-                        &format!("下載 Embedding 模型 ({})", local_file),
-                        tx.clone(),
-                    )
-                    .await?;
-                }
-            }
-            _ => {
-                println!("[Setup] Unknown requirement: {}", req_id);
-            }
+        }
+        "vc_runtime" => {
+            // Microsoft's installer needs elevation and its own UI, so
+            // point the user at it instead of running it silently, and
+            // carry on with the model downloads.
+            tx.send(Progress::failed(
+                "vc_runtime",
+                task_name("vc_runtime"),
+                &format!(
+                    "請從 {} 下載並安裝 Visual C++ 可轉散發套件，完成後重新檢查",
+                    super::requirements::VC_REDIST_URL
+                ),
+            ))
+            .await
+            .ok();
+        }
+        _ => {
+            println!("[Setup] Unknown requirement: {}", req_id);
         }
     }
 
@@ -296,4 +712,13 @@ mod tests {
         reset_cancel_flag();
         assert!(!is_cancelled());
     }
+
+    #[test]
+    fn check_free_space_reports_what_is_missing() {
+        assert!(check_free_space(500 * MB, 500 * MB).is_ok());
+        let err = check_free_space(980 * MB + 1, 300 * MB).unwrap_err();
+        assert!(err.contains("981 MB"), "{}", err);
+        assert!(err.contains("300 MB"), "{}", err);
+        assert_eq!(install_footprint_mb("disk_space"), 0);
+    }
 }
@@ -9,12 +9,15 @@ pub mod progress;
  * 3. Automated installation with progress reporting
  */
 pub mod requirements;
+mod state;
 
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
 // Re-export commonly used types
-pub use installer::{cancel_current_installation, install_requirements};
+pub use installer::{
+    cancel_current_installation, install_requirements, pending_downloads, SetupDownload,
+};
 pub use requirements::{check_all_requirements, Requirement, RequirementStatus};
 
 /// Overall setup status
@@ -100,7 +103,28 @@ pub async fn save_setup_status(complete: bool) -> Result<(), String> {
 
 /// Get the full setup status
 pub async fn get_setup_status() -> Result<SetupStatus, String> {
-    let requirements = check_all_requirements().await?;
+    let mut requirements = check_all_requirements().await?;
+
+    // A model whose install step hasn't completed may only be partly on
+    // disk, which the file checks can't tell apart from installed (or
+    // take for corrupted).
+    match state::load().await {
+        Ok(steps) => {
+            for id in state::unfinished(&steps) {
+                if let Some(req) = requirements.iter_mut().find(|r| {
+                    r.id == id
+                        && matches!(
+                            r.status,
+                            RequirementStatus::Installed | RequirementStatus::Corrupted { .. }
+                        )
+                }) {
+                    println!("[Setup] {} install didn't finish; not installed", id);
+                    req.status = RequirementStatus::NotInstalled;
+                }
+            }
+        }
+        Err(e) => log::warn!("[Setup] install state unavailable: {}", e),
+    }
 
     let is_complete = requirements
         .iter()
@@ -2,9 +2,15 @@
  * Progress Reporting Module
  *
  * Handles progress tracking and reporting for installations and downloads.
+ * The setup installer and the standalone translation / embedding model
+ * downloads all report on [`PROGRESS_EVENT`], so the frontend has one
+ * progress shape to handle.
  */
 use serde::{Deserialize, Serialize};
 
+/// The event every [`Progress`] is emitted on.
+pub const PROGRESS_EVENT: &str = "setup-progress";
+
 /// Progress status
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ProgressStatus {
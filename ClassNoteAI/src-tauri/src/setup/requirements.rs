@@ -2,12 +2,38 @@
  * Requirements Detection Module
  *
  * Handles detection of all system and application requirements:
- * - System: Homebrew, CMake, FFmpeg
+ * - System: OS version (macOS / Windows build / Linux glibc), disk space
+ * - Runtime: Visual C++ runtime (Windows)
  * - Models: Whisper, CTranslate2 translation model
  */
 use crate::utils::command::no_window;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Oldest glibc the Linux build runs on: Ubuntu 22.04, the oldest
+/// distribution with the webkit2gtk-4.1 Tauri 2 needs.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+const MIN_GLIBC: (u32, u32) = (2, 35);
+
+/// DLLs of the Visual C++ 2015-2022 runtime. The app itself and the
+/// bundled ONNX Runtime link against them.
+#[cfg(target_os = "windows")]
+const VC_RUNTIME_DLLS: &[&str] = &["vcruntime140.dll", "vcruntime140_1.dll", "msvcp140.dll"];
+/// Microsoft's permanent link to the latest x64 redistributable.
+pub const VC_REDIST_URL: &str = "https://aka.ms/vs/17/release/vc_redist.x64.exe";
+
+/// Files the translation model directory needs, each with the smallest
+/// size a complete copy has.
+pub const TRANSLATION_MODEL_FILES: &[(&str, u64)] = &[
+    ("model.bin", 100 * 1024 * 1024),
+    ("shared_vocabulary.json", 1),
+];
+/// Files the embedding model directory needs, as above.
+pub const EMBEDDING_MODEL_FILES: &[(&str, u64)] = &[
+    ("model.safetensors", crate::embedding::MIN_MODEL_BYTES),
+    ("tokenizer.json", 1),
+    ("config.json", 1),
+];
 
 /// Requirement category
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -29,6 +55,9 @@ pub enum RequirementStatus {
     NotInstalled,
     /// Installed but outdated
     Outdated { current: String, required: String },
+    /// Installed, but these files are missing or truncated; repairing
+    /// downloads them again
+    Corrupted { files: Vec<String> },
     /// Error checking status
     Error(String),
 }
@@ -59,12 +88,15 @@ pub struct Requirement {
 // These were for development-time dependencies that end users don't need.
 // The app is self-contained after packaging.
 
-/// Check OS version (macOS or Windows)
+/// Check OS version
 ///
 /// - macOS: requires 11.0 (Big Sur) or later via `sw_vers -productVersion`.
 /// - Windows: requires build 17763 (Windows 10 1809, WebView2 baseline) or
 ///   later. Parsed from `cmd /c ver`, which prints e.g.
 ///   `Microsoft Windows [Version 10.0.22631.4890]`.
+/// - Linux: requires glibc [`MIN_GLIBC`] or later via
+///   `getconf GNU_LIBC_VERSION`, which prints e.g. `glibc 2.35`. musl
+///   distributions have no answer and get an error status.
 pub fn check_os_version() -> RequirementStatus {
     #[cfg(target_os = "macos")]
     {
@@ -155,149 +187,223 @@ pub fn check_os_version() -> RequirementStatus {
         }
     }
 
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    #[cfg(target_os = "linux")]
     {
-        RequirementStatus::Installed // Other platforms (Linux etc.) — no check.
+        match no_window("getconf").arg("GNU_LIBC_VERSION").output() {
+            Ok(output) => {
+                let raw = String::from_utf8_lossy(&output.stdout).trim().to_string();
+                if !output.status.success() {
+                    return RequirementStatus::Error(
+                        "Failed to get glibc version (not a glibc system?)".to_string(),
+                    );
+                }
+                match parse_glibc_version(&raw) {
+                    Some(version) if version >= MIN_GLIBC => {
+                        println!("[Setup] {} (OK)", raw);
+                        RequirementStatus::Installed
+                    }
+                    Some((major, minor)) => RequirementStatus::Outdated {
+                        current: format!("glibc {}.{}", major, minor),
+                        required: format!("glibc {}.{}", MIN_GLIBC.0, MIN_GLIBC.1),
+                    },
+                    None => {
+                        RequirementStatus::Error(format!("Failed to parse glibc version: {}", raw))
+                    }
+                }
+            }
+            Err(e) => RequirementStatus::Error(format!("Failed to check glibc version: {}", e)),
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        RequirementStatus::Installed // Other platforms — no check.
+    }
+}
+
+/// `(major, minor)` from `getconf GNU_LIBC_VERSION` output (`glibc 2.35`).
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_glibc_version(raw: &str) -> Option<(u32, u32)> {
+    let version = raw.trim().strip_prefix("glibc")?.trim();
+    let mut parts = version.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    Some((major, minor))
+}
+
+/// Check that the Visual C++ runtime DLLs are installed, in the system
+/// directory or next to the executable.
+#[cfg(target_os = "windows")]
+pub fn check_vc_runtime() -> RequirementStatus {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt;
+    use windows_sys::Win32::System::SystemInformation::GetSystemDirectoryW;
+
+    let mut buf = [0u16; 260];
+    // SAFETY: the buffer and its length match; the return value is the
+    // number of characters written, or the size needed if it's too small.
+    let len = unsafe { GetSystemDirectoryW(buf.as_mut_ptr(), buf.len() as u32) } as usize;
+    if len == 0 || len > buf.len() {
+        return RequirementStatus::Error("Failed to get the system directory".to_string());
+    }
+    let mut dirs = vec![PathBuf::from(OsString::from_wide(&buf[..len]))];
+    if let Some(exe_dir) = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+    {
+        dirs.push(exe_dir);
+    }
+
+    let missing: Vec<&str> = VC_RUNTIME_DLLS
+        .iter()
+        .copied()
+        .filter(|dll| !dirs.iter().any(|dir| dir.join(dll).exists()))
+        .collect();
+    if missing.is_empty() {
+        println!("[Setup] Visual C++ runtime: OK");
+        RequirementStatus::Installed
+    } else {
+        println!("[Setup] Visual C++ runtime missing: {}", missing.join(", "));
+        RequirementStatus::NotInstalled
     }
 }
 
 /// Check available disk space on the drive hosting the app data directory.
 pub fn check_disk_space(required_mb: u64) -> RequirementStatus {
-    #[cfg(target_os = "macos")]
-    {
-        match no_window("df").args(["-m", "/"]).output() {
-            Ok(output) => {
-                if output.status.success() {
-                    let output_str = String::from_utf8_lossy(&output.stdout);
-                    if let Some(line) = output_str.lines().nth(1) {
-                        let parts: Vec<&str> = line.split_whitespace().collect();
-                        if parts.len() >= 4 {
-                            if let Ok(available_mb) = parts[3].parse::<u64>() {
-                                if available_mb >= required_mb {
-                                    println!(
-                                        "[Setup] Disk space: {}MB available (need {}MB)",
-                                        available_mb, required_mb
-                                    );
-                                    return RequirementStatus::Installed;
-                                } else {
-                                    return RequirementStatus::Outdated {
-                                        current: format!("{}MB", available_mb),
-                                        required: format!("{}MB", required_mb),
-                                    };
-                                }
-                            }
-                        }
-                    }
-                    RequirementStatus::Error("Failed to parse disk space".to_string())
-                } else {
-                    RequirementStatus::Error("Failed to check disk space".to_string())
+    let probe = disk_probe_path();
+    match available_disk_bytes(&probe) {
+        Ok(bytes) => {
+            let available_mb = bytes / 1024 / 1024;
+            if available_mb >= required_mb {
+                println!(
+                    "[Setup] Disk space ({}): {}MB available (need {}MB)",
+                    probe.display(),
+                    available_mb,
+                    required_mb
+                );
+                RequirementStatus::Installed
+            } else {
+                RequirementStatus::Outdated {
+                    current: format!("{}MB", available_mb),
+                    required: format!("{}MB", required_mb),
                 }
             }
-            Err(e) => RequirementStatus::Error(format!("Failed to check disk space: {}", e)),
         }
+        Err(e) => RequirementStatus::Error(e),
     }
+}
 
-    #[cfg(target_os = "windows")]
+/// The app data directory, or its nearest existing ancestor on first
+/// run before it has been created.
+fn disk_probe_path() -> PathBuf {
+    let fallback = if cfg!(windows) { "C:\\" } else { "/" };
+    crate::paths::get_app_data_dir()
+        .ok()
+        .and_then(|dir| dir.ancestors().find(|p| p.exists()).map(Path::to_path_buf))
+        .unwrap_or_else(|| PathBuf::from(fallback))
+}
+
+/// Free bytes on the volume holding `path` (which must exist).
+///
+/// `df -Pk` on macOS / Linux (POSIX output, so one line per filesystem
+/// even for long device names); `GetDiskFreeSpaceExW` on Windows, which
+/// takes any directory on the volume and honours per-user quotas.
+pub fn available_disk_bytes(path: &Path) -> Result<u64, String> {
+    #[cfg(unix)]
     {
-        use std::path::Component;
-        // Resolve the drive the app data lives on; fall back to "C:\".
-        let probe_path: String = crate::paths::get_app_data_dir()
-            .ok()
-            .and_then(|p| {
-                p.components().next().and_then(|c| match c {
-                    Component::Prefix(pref) => {
-                        Some(format!("{}\\", pref.as_os_str().to_string_lossy()))
-                    }
-                    _ => None,
-                })
-            })
-            .unwrap_or_else(|| "C:\\".to_string());
-
-        // Use [System.IO.DriveInfo] to get free bytes. Works on every
-        // supported Windows build without needing wmic or fsutil.
-        //
-        // The probe path is passed as a PowerShell script-block parameter
-        // (`& { param($p) ... } "<path>"`). The earlier form used
-        // `$args[0]` inside a plain -Command string, but `$args` is ONLY
-        // populated when PowerShell is invoked with -File (or a script
-        // block) — under -Command "<string>" the trailing positional
-        // arguments get tokenised as additional script statements, which
-        // fails with `ParserError: UnexpectedToken 'C:\'`. This quirk
-        // silently broke disk-space detection on every Windows install:
-        // the PowerShell call exited 1, RequirementStatus became Error,
-        // and the setup wizard showed "insufficient disk space" to users
-        // who actually had hundreds of GB free (reported: 2026-04-18).
-        //
-        // Using a script block with `param($p)` explicitly binds the
-        // positional arg to a variable, which works under both -Command
-        // and -File, and cleanly sidesteps any interpretation of
-        // backticks / $ / apostrophes that might appear in a Windows
-        // path with an unusual username.
-        match no_window("powershell")
-            .args([
-                "-NoProfile",
-                "-NonInteractive",
-                "-Command",
-                "& { param($p) [System.IO.DriveInfo]::new($p).AvailableFreeSpace }",
-                &probe_path,
-            ])
+        let output = no_window("df")
+            .arg("-Pk")
+            .arg(path)
             .output()
-        {
-            Ok(output) => {
-                if !output.status.success() {
-                    return RequirementStatus::Error(format!(
-                        "Failed to check disk space: {}",
-                        String::from_utf8_lossy(&output.stderr).trim()
-                    ));
-                }
-                let trimmed = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                match trimmed.parse::<u64>() {
-                    Ok(bytes) => {
-                        let available_mb = bytes / 1024 / 1024;
-                        if available_mb >= required_mb {
-                            println!(
-                                "[Setup] Disk space ({}): {}MB available (need {}MB)",
-                                probe_path, available_mb, required_mb
-                            );
-                            RequirementStatus::Installed
-                        } else {
-                            RequirementStatus::Outdated {
-                                current: format!("{}MB", available_mb),
-                                required: format!("{}MB", required_mb),
-                            }
-                        }
-                    }
-                    Err(_) => RequirementStatus::Error(format!(
-                        "Failed to parse disk space output: {}",
-                        trimmed
-                    )),
-                }
-            }
-            Err(e) => RequirementStatus::Error(format!("Failed to check disk space: {}", e)),
+            .map_err(|e| format!("Failed to check disk space: {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to check disk space: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
         }
+        parse_df_available_kb(&String::from_utf8_lossy(&output.stdout))
+            .map(|kb| kb * 1024)
+            .ok_or_else(|| "Failed to parse disk space".to_string())
     }
 
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    #[cfg(windows)]
     {
-        RequirementStatus::Installed
+        use std::os::windows::ffi::OsStrExt;
+        use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+        let wide: Vec<u16> = path
+            .as_os_str()
+            .encode_wide()
+            .chain(std::iter::once(0))
+            .collect();
+        let mut available: u64 = 0;
+        // SAFETY: `wide` is NUL-terminated and outlives the call; the
+        // totals we don't need may be null.
+        let ok = unsafe {
+            GetDiskFreeSpaceExW(
+                wide.as_ptr(),
+                &mut available,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            )
+        };
+        if ok == 0 {
+            return Err(format!(
+                "Failed to check disk space: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        Ok(available)
     }
-}
 
-/// Check if a model exists at the given path
-pub fn check_model(model_path: &Path, expected_files: &[&str]) -> RequirementStatus {
-    if !model_path.exists() {
-        return RequirementStatus::NotInstalled;
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = path;
+        Err("Disk space check not supported on this platform".to_string())
     }
+}
 
-    // Check for expected files
-    for file in expected_files {
-        let file_path = model_path.join(file);
-        if !file_path.exists() {
-            return RequirementStatus::NotInstalled;
-        }
+/// "Available" column (KiB) of `df -Pk` output for a single path.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn parse_df_available_kb(stdout: &str) -> Option<u64> {
+    stdout
+        .lines()
+        .nth(1)?
+        .split_whitespace()
+        .nth(3)?
+        .parse()
+        .ok()
+}
+
+/// Check if a model exists at the given path. A model with some of its
+/// files missing or shorter than `expected_files` allows is `Corrupted`
+/// rather than installed, so it's repaired before it fails to load.
+pub fn check_model(model_path: &Path, expected_files: &[(&str, u64)]) -> RequirementStatus {
+    let broken = broken_files(model_path, expected_files);
+    if broken.is_empty() {
+        RequirementStatus::Installed
+    } else if expected_files
+        .iter()
+        .all(|(file, _)| !model_path.join(file).exists())
+    {
+        RequirementStatus::NotInstalled
+    } else {
+        RequirementStatus::Corrupted { files: broken }
     }
+}
 
-    RequirementStatus::Installed
+/// The `expected_files` under `model_path` that are missing or smaller
+/// than a complete copy.
+pub fn broken_files(model_path: &Path, expected_files: &[(&str, u64)]) -> Vec<String> {
+    expected_files
+        .iter()
+        .filter(|(file, min_bytes)| {
+            !std::fs::metadata(model_path.join(file))
+                .is_ok_and(|m| m.is_file() && m.len() >= *min_bytes)
+        })
+        .map(|(file, _)| file.to_string())
+        .collect()
 }
 
 /// Check all requirements and return their status
@@ -331,7 +437,21 @@ pub async fn check_all_requirements() -> Result<Vec<Requirement>, String> {
         install_size_mb: 0,
         install_source: None,
     };
-    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    #[cfg(target_os = "linux")]
+    let os_req = Requirement {
+        id: "os_version".to_string(),
+        name: "glibc 版本".to_string(),
+        description: format!(
+            "需要 glibc {}.{} (Ubuntu 22.04) 或更高版本",
+            MIN_GLIBC.0, MIN_GLIBC.1
+        ),
+        category: RequirementCategory::System,
+        status: check_os_version(),
+        is_optional: false,
+        install_size_mb: 0,
+        install_source: None,
+    };
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
     let os_req = Requirement {
         id: "os_version".to_string(),
         name: "作業系統版本".to_string(),
@@ -355,6 +475,18 @@ pub async fn check_all_requirements() -> Result<Vec<Requirement>, String> {
         install_source: None,
     });
 
+    #[cfg(target_os = "windows")]
+    requirements.push(Requirement {
+        id: "vc_runtime".to_string(),
+        name: "Visual C++ 執行階段".to_string(),
+        description: "語音辨識引擎需要 Microsoft Visual C++ 2015-2022 可轉散發套件".to_string(),
+        category: RequirementCategory::Runtime,
+        status: check_vc_runtime(),
+        is_optional: false,
+        install_size_mb: 25,
+        install_source: Some(VC_REDIST_URL.to_string()),
+    });
+
     // NOTE: Removed system dependencies (Homebrew, CMake, FFmpeg)
     // These are only needed at development/compile time, not for end users.
     // The app is self-contained after packaging - whisper-rs and ct2rs
@@ -383,8 +515,7 @@ pub async fn check_all_requirements() -> Result<Vec<Requirement>, String> {
     // 使用統一路徑: {app_data}/models/translation/
     let translation_dir = crate::paths::get_translation_models_dir()?;
     let translation_path = translation_dir.join("m2m100-418M-ct2-int8");
-    let translation_status =
-        check_model(&translation_path, &["model.bin", "shared_vocabulary.json"]);
+    let translation_status = check_model(&translation_path, TRANSLATION_MODEL_FILES);
     println!("[Setup] Translation model status: {:?}", translation_status);
 
     requirements.push(Requirement {
@@ -405,20 +536,17 @@ pub async fn check_all_requirements() -> Result<Vec<Requirement>, String> {
     // upstream before embedding, so an English-only embedder is appropriate.
     let embedding_dir = crate::paths::get_embedding_models_dir()?;
     let embedding_path = embedding_dir.join("bge-small-en-v1.5");
-    let embedding_status = check_model(
-        &embedding_path,
-        &["model.safetensors", "tokenizer.json", "config.json"],
-    );
+    let embedding_status = check_model(&embedding_path, EMBEDDING_MODEL_FILES);
     println!("[Setup] Embedding model status: {:?}", embedding_status);
 
     requirements.push(Requirement {
         id: "embedding_model".to_string(),
         name: "BGE-small-en Embedding 模型".to_string(),
-        description: "文本嵌入模型，用於 RAG 檢索與 PDF 對齊 (~33MB，標準 BERT)".to_string(),
+        description: "文本嵌入模型，用於 RAG 檢索與 PDF 對齊 (~130MB，標準 BERT)".to_string(),
         category: RequirementCategory::Model,
         status: embedding_status,
         is_optional: true, // Optional - PDF alignment feature
-        install_size_mb: 33,
+        install_size_mb: 130,
         install_source: Some("https://huggingface.co/BAAI/bge-small-en-v1.5".to_string()),
     });
 
@@ -484,6 +612,56 @@ mod tests {
         }
     }
 
+    #[test]
+    fn df_output_parses_available_column() {
+        let out = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                   /dev/disk3s5     482797652 301234560 170123456      64% /System/Volumes/Data\n";
+        assert_eq!(parse_df_available_kb(out), Some(170123456));
+        assert_eq!(parse_df_available_kb("Filesystem\n"), None);
+
+        let here = std::env::current_dir().unwrap();
+        assert!(available_disk_bytes(&here).unwrap() > 0);
+    }
+
+    #[test]
+    fn check_model_tells_corrupted_from_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let files: &[(&str, u64)] = &[("model.bin", 8), ("vocab.json", 1)];
+        assert!(matches!(
+            check_model(&dir.path().join("absent"), files),
+            RequirementStatus::NotInstalled
+        ));
+        assert!(matches!(
+            check_model(dir.path(), files),
+            RequirementStatus::NotInstalled
+        ));
+
+        std::fs::write(dir.path().join("model.bin"), b"trunc").unwrap();
+        match check_model(dir.path(), files) {
+            RequirementStatus::Corrupted { files } => {
+                assert_eq!(files, ["model.bin", "vocab.json"])
+            }
+            other => panic!("expected Corrupted, got {:?}", other),
+        }
+
+        std::fs::write(dir.path().join("model.bin"), b"complete").unwrap();
+        std::fs::write(dir.path().join("vocab.json"), b"{}").unwrap();
+        assert!(matches!(
+            check_model(dir.path(), files),
+            RequirementStatus::Installed
+        ));
+    }
+
+    #[test]
+    fn glibc_version_parses_getconf_output() {
+        assert_eq!(parse_glibc_version("glibc 2.35\n"), Some((2, 35)));
+        assert_eq!(parse_glibc_version("glibc 2.39.1"), Some((2, 39)));
+        assert_eq!(parse_glibc_version("glibc 2.31"), Some((2, 31)));
+        assert!(parse_glibc_version("glibc 2.31").unwrap() < MIN_GLIBC);
+        assert_eq!(parse_glibc_version(""), None);
+        assert_eq!(parse_glibc_version("musl libc 1.2.4"), None);
+    }
+
     #[test]
     fn test_check_os_version() {
         let status = check_os_version();
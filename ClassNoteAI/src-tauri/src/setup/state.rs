@@ -0,0 +1,144 @@
+/**
+ * Install State Module
+ *
+ * Records how far an installation got, one step per model requirement,
+ * under the `setup.install_steps` setting. A step is `Running` while it
+ * downloads, `Completed` once its files are all in place and `Failed`
+ * (rolled back) when it errored. Installing the same requirements again
+ * skips the completed steps, so a retry only redoes what failed; the
+ * record is cleared once every step has completed.
+ *
+ * Until its step completes a model doesn't count as installed, even
+ * though a partial file for it may already be on disk.
+ */
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::storage;
+
+const INSTALL_STEPS_SETTING: &str = "setup.install_steps";
+const SETTINGS_USER: &str = "default_user";
+
+/// Where one requirement's install got to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum StepState {
+    /// Queued by a run that hasn't reached it yet.
+    Pending,
+    /// Started; also what a step stays at if the app quits mid-way.
+    Running,
+    Completed,
+    Failed {
+        error: String,
+    },
+}
+
+/// Step state by requirement id.
+pub type InstallSteps = BTreeMap<String, StepState>;
+
+/// The steps of the current installation; empty when there isn't one.
+pub async fn load() -> Result<InstallSteps, String> {
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let Some(json) = db
+        .get_setting(INSTALL_STEPS_SETTING, SETTINGS_USER)
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(InstallSteps::new());
+    };
+    serde_json::from_str(&json).map_err(|e| format!("無法解析安裝進度: {}", e))
+}
+
+/// Save `steps`, or clear the record once nothing is left unfinished.
+pub async fn save(steps: &InstallSteps) -> Result<(), String> {
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    if is_finished(steps) {
+        return db
+            .delete_setting(INSTALL_STEPS_SETTING)
+            .map_err(|e| e.to_string());
+    }
+    let json = serde_json::to_string(steps).map_err(|e| e.to_string())?;
+    db.save_setting(INSTALL_STEPS_SETTING, &json, SETTINGS_USER)
+        .map_err(|e| e.to_string())
+}
+
+/// Whether every step has completed (or there are none).
+pub fn is_finished(steps: &InstallSteps) -> bool {
+    steps.values().all(|s| *s == StepState::Completed)
+}
+
+/// Add the steps of a run over `ids` to `steps`. Completed steps stay
+/// completed so the run skips them; everything else starts over.
+pub fn begin(steps: &mut InstallSteps, ids: &[String]) {
+    for id in ids {
+        let state = steps.entry(id.clone()).or_insert(StepState::Pending);
+        if *state != StepState::Completed {
+            *state = StepState::Pending;
+        }
+    }
+}
+
+/// Requirements whose files may be partial: started and not completed.
+pub fn unfinished(steps: &InstallSteps) -> impl Iterator<Item = &str> {
+    steps
+        .iter()
+        .filter(|(_, s)| **s != StepState::Completed)
+        .map(|(id, _)| id.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn begin_keeps_completed_steps_and_restarts_the_rest() {
+        let mut steps = InstallSteps::new();
+        steps.insert("whisper_model".into(), StepState::Completed);
+        steps.insert(
+            "translation_model".into(),
+            StepState::Failed {
+                error: "Download error".into(),
+            },
+        );
+        begin(
+            &mut steps,
+            &[
+                "whisper_model".into(),
+                "translation_model".into(),
+                "embedding_model".into(),
+            ],
+        );
+        assert_eq!(steps["whisper_model"], StepState::Completed);
+        assert_eq!(steps["translation_model"], StepState::Pending);
+        assert_eq!(steps["embedding_model"], StepState::Pending);
+        assert_eq!(
+            unfinished(&steps).collect::<Vec<_>>(),
+            ["embedding_model", "translation_model"]
+        );
+        assert!(!is_finished(&steps));
+
+        for state in steps.values_mut() {
+            *state = StepState::Completed;
+        }
+        assert!(is_finished(&steps));
+        assert!(is_finished(&InstallSteps::new()));
+    }
+
+    #[test]
+    fn step_state_serializes_with_a_tag() {
+        let json = serde_json::to_string(&StepState::Failed {
+            error: "磁碟空間不足".into(),
+        })
+        .unwrap();
+        assert_eq!(json, r#"{"state":"failed","error":"磁碟空間不足"}"#);
+    }
+}
@@ -0,0 +1,86 @@
+//! Ordered release of the in-process models when the app quits.
+//!
+//! Exiting with models still loaded leaves their GPU buffers to
+//! whatever static / atexit teardown happens to run, in no particular
+//! order. On macOS that ended in `ggml_metal_device_free` aborts on quit
+//! and on update-restart: the Metal device was freed while a model
+//! still held buffers on it. So on the way out we drop them ourselves,
+//! consumers before what they consume:
+//!
+//! 1. the live pipeline (drives ASR, feeds translation),
+//! 2. the Nemotron / Parakeet ASR model,
+//! 3. the TranslateGemma sidecar (llama.cpp, separate process),
+//! 4. the embedding model (Candle; Metal on macOS).
+//!
+//! The steps run on a worker thread and [`run`] waits for them for at
+//! most [`EXIT_GRACE`], so one stuck behind a busy lock can't hang quit.
+//! Every exit path calls [`run`]; only the first call does the work,
+//! later ones just wait for it.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long exit waits for the teardown before going ahead anyway.
+pub const EXIT_GRACE: Duration = Duration::from_secs(3);
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+static FINISHED: (Mutex<bool>, Condvar) = (Mutex::new(false), Condvar::new());
+
+/// Release every loaded model in order. Returns `false` if the grace
+/// period ran out first (the process then exits with the rest still
+/// loaded, as it did before).
+pub fn run() -> bool {
+    if !STARTED.swap(true, Ordering::SeqCst) {
+        let spawned = std::thread::Builder::new()
+            .name("shutdown".into())
+            .spawn(|| {
+                release_all();
+                let (lock, cvar) = &FINISHED;
+                *lock.lock().unwrap_or_else(|p| p.into_inner()) = true;
+                cvar.notify_all();
+            });
+        if let Err(e) = spawned {
+            log::error!("[shutdown] could not start teardown thread: {e}");
+            return false;
+        }
+    }
+
+    let (lock, cvar) = &FINISHED;
+    let guard = lock.lock().unwrap_or_else(|p| p.into_inner());
+    let (guard, _) = cvar
+        .wait_timeout_while(guard, EXIT_GRACE, |done| !*done)
+        .unwrap_or_else(|p| p.into_inner());
+    if !*guard {
+        log::warn!(
+            "[shutdown] teardown still running after {:?}; exiting anyway",
+            EXIT_GRACE
+        );
+    }
+    *guard
+}
+
+fn release_all() {
+    step("live pipeline", || {
+        // Errs when no session is running — nothing to stop then.
+        let _ = tauri::async_runtime::block_on(crate::pipeline::stop_live_pipeline());
+    });
+    step("ASR model", crate::asr::parakeet_engine::unload);
+    step(
+        "translation sidecar",
+        crate::translation::gemma_sidecar::shutdown,
+    );
+    step("embedding model", || {
+        let service = tauri::async_runtime::block_on(crate::EMBEDDING_SERVICE.lock()).take();
+        drop(service);
+    });
+}
+
+fn step(name: &str, f: impl FnOnce()) {
+    let started = Instant::now();
+    f();
+    log::info!(
+        "[shutdown] {name} released in {} ms",
+        started.elapsed().as_millis()
+    );
+}
@@ -0,0 +1,434 @@
+//! Startup self-test and safe mode.
+//!
+//! A broken update used to leave users on a white screen: a migration
+//! that can't run, an ONNX Runtime that crashes on first inference, or
+//! a truncated model that takes the process down as it loads. None of
+//! that is recoverable from inside the UI if the UI never comes up.
+//!
+//! So `setup` runs a quick self-test before loading anything heavy:
+//!
+//! - **database** — open it (which runs the migrations);
+//! - **models** — every model on disk has the right size and header;
+//! - **inference** — one Silero VAD pass over a second of silence,
+//!   which exercises the ONNX Runtime the ASR model shares.
+//!
+//! If a check fails, or the previous [`MAX_UNFINISHED_BOOTS`] launches
+//! never got as far as the frontend calling [`mark_complete`], or
+//! [`SAFE_MODE_ENV`] is set, the app starts in **safe mode**: no model
+//! is loaded at startup, and [`repair`] offers the fixes for what
+//! failed. The crash-loop case skips the inference smoke test itself,
+//! since that may be what crashed.
+
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::asr::parakeet_model;
+use crate::translation::gemma_model;
+use crate::{paths, storage, vad};
+
+/// Set to `1` to force safe mode for one launch.
+pub const SAFE_MODE_ENV: &str = "CLASSNOTEAI_SAFE_MODE";
+/// Launches in a row that never reached the UI before safe mode.
+pub const MAX_UNFINISHED_BOOTS: u32 = 2;
+const BOOT_FILE: &str = "startup.json";
+
+/// Repair ids, see [`repair`].
+pub const REPAIR_DATABASE: &str = "database";
+pub const REPAIR_MODELS: &str = "models";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    /// Degraded but safe to continue (e.g. a download in progress).
+    Warning,
+    Failed,
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: Option<String>,
+    /// Repair id that addresses a failure, if there is one.
+    pub repair: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupStatus {
+    pub safe_mode: bool,
+    /// Why safe mode was entered; empty otherwise.
+    pub reasons: Vec<String>,
+    pub checks: Vec<Check>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct BootState {
+    unfinished_boots: u32,
+}
+
+static STATUS: OnceLock<StartupStatus> = OnceLock::new();
+
+fn boot_file() -> Result<PathBuf, String> {
+    Ok(paths::get_app_data_dir()?.join(BOOT_FILE))
+}
+
+fn read_boot_state(path: &Path) -> BootState {
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn write_boot_state(path: &Path, state: &BootState) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("create {}: {}", parent.display(), e))?;
+    }
+    let json = serde_json::to_vec(state).map_err(|e| e.to_string())?;
+    fs::write(path, json).map_err(|e| format!("write {}: {}", path.display(), e))
+}
+
+/// Count this launch as unfinished and decide whether safe mode is
+/// forced before any check runs. Call first thing in `setup`.
+pub fn begin() -> Option<String> {
+    let unfinished = match boot_file() {
+        Ok(path) => {
+            let mut state = read_boot_state(&path);
+            let previous = state.unfinished_boots;
+            state.unfinished_boots += 1;
+            if let Err(e) = write_boot_state(&path, &state) {
+                log::warn!("[startup] could not record boot: {}", e);
+            }
+            previous
+        }
+        Err(_) => 0,
+    };
+    forced_reason(std::env::var(SAFE_MODE_ENV).ok().as_deref(), unfinished)
+}
+
+fn forced_reason(env: Option<&str>, unfinished_boots: u32) -> Option<String> {
+    if env.is_some_and(|v| v == "1" || v.eq_ignore_ascii_case("true")) {
+        return Some(format!("{} 已設定", SAFE_MODE_ENV));
+    }
+    (unfinished_boots >= MAX_UNFINISHED_BOOTS)
+        .then(|| format!("前 {} 次啟動都沒有完成", unfinished_boots))
+}
+
+/// The frontend rendered: this launch counts as a good one.
+pub fn mark_complete() -> Result<(), String> {
+    write_boot_state(&boot_file()?, &BootState::default())
+}
+
+/// Run the self-test, decide on safe mode, and remember the result for
+/// [`status`]. `silero_model` is the bundled VAD model, if any.
+pub fn run(forced: Option<String>, silero_model: Option<&Path>) -> &'static StartupStatus {
+    let checks = vec![
+        check_database(),
+        check_models(),
+        if forced.is_some() {
+            Check {
+                name: "inference",
+                status: CheckStatus::Skipped,
+                detail: Some("安全模式下不執行推論測試".to_string()),
+                repair: None,
+            }
+        } else {
+            check_inference(silero_model)
+        },
+    ];
+    let mut reasons: Vec<String> = forced.into_iter().collect();
+    reasons.extend(
+        checks
+            .iter()
+            .filter(|c| c.status == CheckStatus::Failed)
+            .map(|c| format!("{}: {}", c.name, c.detail.as_deref().unwrap_or("failed"))),
+    );
+    let status = StartupStatus {
+        safe_mode: !reasons.is_empty(),
+        reasons,
+        checks,
+    };
+    if status.safe_mode {
+        log::warn!("[startup] safe mode: {}", status.reasons.join("; "));
+    } else {
+        log::info!("[startup] self-test passed");
+    }
+    STATUS.get_or_init(|| status)
+}
+
+/// Result of this launch's self-test (an empty pass if it hasn't run).
+pub fn status() -> StartupStatus {
+    STATUS.get().cloned().unwrap_or(StartupStatus {
+        safe_mode: false,
+        reasons: Vec::new(),
+        checks: Vec::new(),
+    })
+}
+
+pub fn is_safe_mode() -> bool {
+    STATUS.get().is_some_and(|s| s.safe_mode)
+}
+
+fn check_database() -> Check {
+    let result = paths::get_database_path()
+        .and_then(|path| storage::Database::new(&path).map_err(|e| e.to_string()));
+    match result {
+        Ok(_) => ok("database"),
+        Err(e) => Check {
+            name: "database",
+            status: CheckStatus::Failed,
+            detail: Some(e),
+            repair: Some(REPAIR_DATABASE),
+        },
+    }
+}
+
+fn check_models() -> Check {
+    let broken = broken_model_files();
+    if !broken.is_empty() {
+        return Check {
+            name: "models",
+            status: CheckStatus::Failed,
+            detail: Some(format!(
+                "模型檔案損壞：{}",
+                broken
+                    .iter()
+                    .map(|p| p.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )),
+            repair: Some(REPAIR_MODELS),
+        };
+    }
+    let partial: Vec<&str> = parakeet_model::Variant::all()
+        .iter()
+        .filter(|&&v| !parakeet_model::is_present(v) && parakeet_model::bytes_on_disk(v) > 0)
+        .map(|v| v.label())
+        .collect();
+    if partial.is_empty() {
+        ok("models")
+    } else {
+        Check {
+            name: "models",
+            status: CheckStatus::Warning,
+            detail: Some(format!("ASR 模型下載未完成：{}", partial.join(", "))),
+            repair: None,
+        }
+    }
+}
+
+/// Model files that can't be loaded as they are: ASR files larger than
+/// the published size, GGUF files without the GGUF magic, and a
+/// safetensors file whose header doesn't fit. Partial downloads (too
+/// small) are left alone — the downloader resumes them.
+pub fn broken_model_files() -> Vec<PathBuf> {
+    let mut broken = Vec::new();
+    for &variant in parakeet_model::Variant::all() {
+        let Ok(dir) = parakeet_model::model_dir(variant) else {
+            continue;
+        };
+        for file in variant.files() {
+            let path = dir.join(file.name);
+            if fs::metadata(&path).is_ok_and(|m| !m.is_file() || m.len() > file.size) {
+                broken.push(path);
+            }
+        }
+    }
+    for &variant in gemma_model::Variant::all() {
+        if let Ok(path) = gemma_model::target_path_for(variant) {
+            if gemma_model::is_present_for(variant) && !gguf_header_ok(&path) {
+                broken.push(path);
+            }
+        }
+    }
+    if let Ok(dir) = paths::get_embedding_models_dir() {
+        let path = crate::embedding::EmbeddingModelConfig::bge_small(dir).model_path();
+        if path.exists() && !safetensors_header_ok(&path) {
+            broken.push(path);
+        }
+    }
+    broken
+}
+
+fn check_inference(silero_model: Option<&Path>) -> Check {
+    if !vad::silero::is_initialised() {
+        return match silero_model {
+            // The model is bundled, so the runtime is what failed.
+            Some(path) if path.exists() => Check {
+                name: "inference",
+                status: CheckStatus::Failed,
+                detail: Some("ONNX Runtime 無法載入 Silero VAD".to_string()),
+                repair: None,
+            },
+            _ => Check {
+                name: "inference",
+                status: CheckStatus::Warning,
+                detail: Some("未隨附 Silero VAD，略過推論測試".to_string()),
+                repair: None,
+            },
+        };
+    }
+    match vad::silero::try_detect_speech_segments(&[0i16; 16_000]) {
+        Ok(_) => ok("inference"),
+        Err(e) => Check {
+            name: "inference",
+            status: CheckStatus::Failed,
+            detail: Some(e),
+            repair: None,
+        },
+    }
+}
+
+fn ok(name: &'static str) -> Check {
+    Check {
+        name,
+        status: CheckStatus::Ok,
+        detail: None,
+        repair: None,
+    }
+}
+
+fn read_prefix<const N: usize>(path: &Path) -> Option<[u8; N]> {
+    let mut buf = [0u8; N];
+    File::open(path).ok()?.read_exact(&mut buf).ok()?;
+    Some(buf)
+}
+
+fn gguf_header_ok(path: &Path) -> bool {
+    read_prefix::<4>(path).is_some_and(|magic| &magic == b"GGUF")
+}
+
+/// safetensors: an 8-byte little-endian header length, then that many
+/// bytes of JSON.
+fn safetensors_header_ok(path: &Path) -> bool {
+    let (Some(prefix), Ok(meta)) = (read_prefix::<9>(path), fs::metadata(path)) else {
+        return false;
+    };
+    let header_len = u64::from_le_bytes(prefix[..8].try_into().unwrap_or_default());
+    header_len > 0 && header_len <= meta.len().saturating_sub(8) && prefix[8] == b'{'
+}
+
+/// Apply one of the repairs offered by a failed check. Returns a short
+/// description of what was done.
+pub fn repair(action: &str) -> Result<String, String> {
+    match action {
+        REPAIR_DATABASE => {
+            let path = paths::get_database_path()?;
+            let moved = move_aside(
+                &path,
+                &chrono::Local::now().format("%Y%m%d-%H%M%S").to_string(),
+            )?;
+            Ok(format!(
+                "已將資料庫移到 {}，下次啟動會建立新的資料庫",
+                moved.display()
+            ))
+        }
+        REPAIR_MODELS => {
+            let broken = broken_model_files();
+            for path in &broken {
+                let removed = if path.is_dir() {
+                    fs::remove_dir_all(path)
+                } else {
+                    fs::remove_file(path)
+                };
+                removed.map_err(|e| format!("remove {}: {}", path.display(), e))?;
+            }
+            Ok(format!(
+                "已刪除 {} 個損壞的模型檔，請重新下載",
+                broken.len()
+            ))
+        }
+        other => Err(format!("未知的修復動作: {}", other)),
+    }
+}
+
+/// Rename `db` (and its `-wal` / `-shm` companions) to
+/// `<name>.broken-<stamp>`, keeping the data for manual recovery.
+fn move_aside(db: &Path, stamp: &str) -> Result<PathBuf, String> {
+    let target = db.with_file_name(format!(
+        "{}.broken-{}",
+        db.file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("classnoteai.db"),
+        stamp
+    ));
+    for suffix in ["", "-wal", "-shm"] {
+        let from = PathBuf::from(format!("{}{}", db.display(), suffix));
+        if from.exists() {
+            let to = PathBuf::from(format!("{}{}", target.display(), suffix));
+            fs::rename(&from, &to)
+                .map_err(|e| format!("move {} → {}: {}", from.display(), to.display(), e))?;
+        }
+    }
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn safe_mode_is_forced_by_env_or_a_crash_loop() {
+        assert_eq!(forced_reason(None, 0), None);
+        assert_eq!(forced_reason(None, MAX_UNFINISHED_BOOTS - 1), None);
+        assert!(forced_reason(None, MAX_UNFINISHED_BOOTS).is_some());
+        assert!(forced_reason(Some("1"), 0).is_some());
+        assert_eq!(forced_reason(Some("0"), 0), None);
+    }
+
+    #[test]
+    fn boot_counter_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(BOOT_FILE);
+        assert_eq!(read_boot_state(&path).unfinished_boots, 0);
+        write_boot_state(
+            &path,
+            &BootState {
+                unfinished_boots: 3,
+            },
+        )
+        .unwrap();
+        assert_eq!(read_boot_state(&path).unfinished_boots, 3);
+    }
+
+    #[test]
+    fn model_headers_are_validated() {
+        let dir = tempfile::tempdir().unwrap();
+        let gguf = dir.path().join("m.gguf");
+        fs::write(&gguf, b"GGUF\x03\x00\x00\x00").unwrap();
+        assert!(gguf_header_ok(&gguf));
+        fs::write(&gguf, b"<html>").unwrap();
+        assert!(!gguf_header_ok(&gguf));
+
+        let st = dir.path().join("model.safetensors");
+        let mut bytes = 2u64.to_le_bytes().to_vec();
+        bytes.extend_from_slice(b"{}");
+        fs::write(&st, &bytes).unwrap();
+        assert!(safetensors_header_ok(&st));
+        let mut truncated = 100u64.to_le_bytes().to_vec();
+        truncated.extend_from_slice(b"{}");
+        fs::write(&st, &truncated).unwrap();
+        assert!(!safetensors_header_ok(&st));
+    }
+
+    #[test]
+    fn database_is_moved_aside_with_its_wal() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = dir.path().join("classnoteai.db");
+        fs::write(&db, "db").unwrap();
+        fs::write(dir.path().join("classnoteai.db-wal"), "wal").unwrap();
+
+        let moved = move_aside(&db, "20261015-120000").unwrap();
+        assert!(!db.exists());
+        assert_eq!(fs::read_to_string(&moved).unwrap(), "db");
+        assert!(dir
+            .path()
+            .join("classnoteai.db.broken-20261015-120000-wal")
+            .exists());
+    }
+}
@@ -0,0 +1,171 @@
+//! Pre-update backups.
+//!
+//! Before an update installs, [`backup_before_update`] snapshots the
+//! SQLite database and the externalized transcripts (see
+//! `transcript_store`) into `{app_data}/backups/{timestamp}-pre-update-{version}/`.
+//! Audio, video, documents and models are left out: they are large and
+//! an update never rewrites them. Only the newest [`BACKUPS_KEPT`]
+//! snapshots are kept.
+//!
+//! The database is copied with `VACUUM INTO`, which gives a consistent
+//! file even while another connection is writing. A snapshot is built
+//! under a `.partial` name and renamed once complete, so a crash
+//! mid-backup never leaves something that looks like a usable backup.
+//!
+//! Pure helpers take explicit paths (the `*_inner` convention of
+//! `transcript_store`) so they're testable without the global paths
+//! module.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+
+/// How many pre-update snapshots to keep.
+pub const BACKUPS_KEPT: usize = 5;
+const MARKER: &str = "-pre-update-";
+const PARTIAL_SUFFIX: &str = ".partial";
+/// Entries of the app data directory copied next to the database.
+const DATA_ENTRIES: &[&str] = &["transcripts"];
+
+/// Snapshot the data an update could damage before installing
+/// `version`. Returns the backup directory.
+pub fn backup_before_update(version: &str) -> Result<PathBuf, String> {
+    let app_data = crate::paths::get_app_data_dir()?;
+    let backup = backup_inner(
+        &crate::paths::get_backups_dir()?,
+        &crate::paths::get_database_path()?,
+        &app_data,
+        version,
+    )?;
+    println!("[Backup] Pre-update backup at {}", backup.display());
+    Ok(backup)
+}
+
+pub(crate) fn backup_inner(
+    backups_dir: &Path,
+    db_path: &Path,
+    app_data: &Path,
+    version: &str,
+) -> Result<PathBuf, String> {
+    let version: String = version
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-'))
+        .collect();
+    let name = format!(
+        "{}{}{}",
+        chrono::Local::now().format("%Y%m%d-%H%M%S"),
+        MARKER,
+        version
+    );
+    let dest = backups_dir.join(&name);
+    let partial = backups_dir.join(format!("{}{}", name, PARTIAL_SUFFIX));
+    let _ = fs::remove_dir_all(&partial);
+    fs::create_dir_all(&partial).map_err(|e| format!("無法建立備份資料夾: {}", e))?;
+
+    let result = (|| {
+        if db_path.exists() {
+            snapshot_db(db_path, &partial.join("classnoteai.db"))?;
+        }
+        let entries: Vec<String> = DATA_ENTRIES
+            .iter()
+            .filter(|name| app_data.join(name).exists())
+            .map(|name| name.to_string())
+            .collect();
+        // Also runs `PRAGMA quick_check` on the database snapshot.
+        crate::paths::relocate::copy_verified(
+            app_data,
+            &partial,
+            &entries,
+            &AtomicBool::new(false),
+            |_, _| {},
+        )?;
+        fs::rename(&partial, &dest).map_err(|e| format!("無法完成備份: {}", e))
+    })();
+    if let Err(e) = result {
+        let _ = fs::remove_dir_all(&partial);
+        return Err(e);
+    }
+
+    prune_inner(backups_dir, BACKUPS_KEPT);
+    Ok(dest)
+}
+
+/// A consistent copy of the database at `dest`, even while it is open.
+pub(crate) fn snapshot_db(db_path: &Path, dest: &Path) -> Result<(), String> {
+    let conn = rusqlite::Connection::open(db_path).map_err(|e| format!("無法開啟數據庫: {}", e))?;
+    conn.execute("VACUUM INTO ?1", [dest.to_string_lossy().into_owned()])
+        .map_err(|e| format!("數據庫備份失敗: {}", e))?;
+    Ok(())
+}
+
+/// Remove all but the newest `keep` snapshots, and any a crash left
+/// unfinished.
+pub(crate) fn prune_inner(backups_dir: &Path, keep: usize) {
+    let Ok(entries) = fs::read_dir(backups_dir) else {
+        return;
+    };
+    let mut snapshots = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !name.contains(MARKER) {
+            continue;
+        }
+        if name.ends_with(PARTIAL_SUFFIX) {
+            let _ = fs::remove_dir_all(entry.path());
+        } else {
+            snapshots.push(name);
+        }
+    }
+    // Names start with the timestamp, so they sort oldest first.
+    snapshots.sort();
+    let excess = snapshots.len().saturating_sub(keep);
+    for name in &snapshots[..excess] {
+        if let Err(e) = fs::remove_dir_all(backups_dir.join(name)) {
+            log::warn!("[Backup] Could not prune {}: {}", name, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_up_the_database_and_transcripts_and_prunes_old_snapshots() {
+        let tmp = tempfile::tempdir().unwrap();
+        let app_data = tmp.path().join("data");
+        let backups = app_data.join("backups");
+        fs::create_dir_all(app_data.join("transcripts/lec-1")).unwrap();
+        fs::create_dir_all(app_data.join("audio")).unwrap();
+        fs::write(app_data.join("transcripts/lec-1/subs.json.gz"), b"gz").unwrap();
+        fs::write(app_data.join("audio/lec-1.wav"), b"wav").unwrap();
+        let db_path = app_data.join("classnoteai.db");
+        let conn = rusqlite::Connection::open(&db_path).unwrap();
+        conn.execute_batch("CREATE TABLE t (v TEXT); INSERT INTO t VALUES ('kept');")
+            .unwrap();
+
+        for old in ["20260101-000000", "20260102-000000"] {
+            fs::create_dir_all(backups.join(format!("{}{}0.7.0", old, MARKER))).unwrap();
+        }
+        fs::create_dir_all(backups.join(format!("20260103-000000{}0.7.0.partial", MARKER)))
+            .unwrap();
+
+        let dest = backup_inner(&backups, &db_path, &app_data, "0.8.0").unwrap();
+        assert!(dest.to_string_lossy().ends_with("-pre-update-0.8.0"));
+        let copy = rusqlite::Connection::open(dest.join("classnoteai.db")).unwrap();
+        let v: String = copy.query_row("SELECT v FROM t", [], |r| r.get(0)).unwrap();
+        assert_eq!(v, "kept");
+        assert!(dest.join("transcripts/lec-1/subs.json.gz").is_file());
+        assert!(!dest.join("audio").exists());
+
+        prune_inner(&backups, 2);
+        let mut left: Vec<_> = fs::read_dir(&backups)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(left.len(), 2);
+        assert!(left[0].starts_with("20260102"));
+        assert_eq!(left[1], dest.file_name().unwrap().to_string_lossy());
+    }
+}
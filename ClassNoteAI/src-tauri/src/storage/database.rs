@@ -1,4 +1,7 @@
-use crate::storage::models::{Course, Lecture, Note, Setting, Subtitle};
+use crate::storage::models::{
+    Course, Lecture, Note, NoteConflict, Setting, SlideAlignment, Subtitle, Tombstone,
+};
+use crate::storage::transcript_store::TranscriptPointer;
 use chrono::Utc;
 use rusqlite::{Connection, Result as SqlResult};
 use std::path::PathBuf;
@@ -836,6 +839,88 @@ impl Database {
             ));
         }
 
+        // Externalized transcripts — pointer + checksum for lectures whose
+        // subtitles were moved out to `{app_data}/transcripts/` (see
+        // `storage::transcript_store`). No row = subtitles live in SQLite.
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS transcript_blobs (
+                lecture_id TEXT PRIMARY KEY,
+                path TEXT NOT NULL,
+                sha256 TEXT NOT NULL,
+                byte_len INTEGER NOT NULL,
+                raw_len INTEGER NOT NULL,
+                segment_count INTEGER NOT NULL,
+                externalized_at TEXT NOT NULL,
+                FOREIGN KEY (lecture_id) REFERENCES lectures(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        // Which slide page was on screen when, computed once by the
+        // alignment pass and read back during playback. `[start_ms,
+        // end_ms)` on the lecture's recording; ranges don't overlap.
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS slide_alignments (
+                lecture_id TEXT NOT NULL,
+                page_number INTEGER NOT NULL,
+                start_ms INTEGER NOT NULL,
+                end_ms INTEGER NOT NULL,
+                confidence REAL,
+                PRIMARY KEY (lecture_id, start_ms),
+                FOREIGN KEY (lecture_id) REFERENCES lectures(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        // Setup wizard downloads are recorded with the other resumable
+        // downloads in `downloads::control`'s state file now.
+        self.conn.execute("DROP TABLE IF EXISTS setup_downloads", [])?;
+
+        // 增量同步進度：每個伺服器、使用者、資料類型已推送到的最後一筆
+        // (updated_at, id)。見 `sync::push`。
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS sync_watermarks (
+                server_url TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                entity TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                last_id TEXT NOT NULL,
+                synced_at TEXT NOT NULL,
+                PRIMARY KEY (server_url, user_id, entity)
+            )",
+            [],
+        )?;
+
+        // 同步衝突時保留的筆記副本。見 `sync::conflicts`。
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS note_conflicts (
+                id TEXT PRIMARY KEY,
+                lecture_id TEXT NOT NULL,
+                side TEXT NOT NULL,
+                title TEXT NOT NULL,
+                content TEXT NOT NULL,
+                generated_at TEXT NOT NULL,
+                conflicted_at TEXT NOT NULL,
+                server_url TEXT NOT NULL,
+                resolved INTEGER NOT NULL DEFAULT 0,
+                FOREIGN KEY (lecture_id) REFERENCES lectures(id) ON DELETE CASCADE
+            )",
+            [],
+        )?;
+
+        // 永久刪除的課程與課堂（墓碑），推送時告知伺服器，並擋下同步帶回
+        // 的舊資料。見 `sync::push`。
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS sync_tombstones (
+                entity TEXT NOT NULL,
+                id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                purged_at TEXT NOT NULL,
+                PRIMARY KEY (entity, id)
+            )",
+            [],
+        )?;
+
         // ===== v0.8.0 (Phase 7 Sprint 3.f-RS-2) schema migration =====
         // PLAN §8.2: lectures + notes + settings + subtitles 一次到位。
         //
@@ -1247,6 +1332,92 @@ impl Database {
         Ok(())
     }
 
+    /// 只更新課堂的 audio_path（轉檔 / 剪輯後換檔用），不動其他欄位。
+    pub fn set_lecture_audio_path(&self, id: &str, audio_path: &str) -> SqlResult<usize> {
+        self.conn.execute(
+            "UPDATE lectures SET audio_path = ?1, updated_at = ?2 WHERE id = ?3",
+            rusqlite::params![audio_path, chrono::Utc::now().to_rfc3339(), id],
+        )
+    }
+
+    /// 裁剪錄音後同步時間軸：丟掉 `[start_sec, end_sec)` 以外的字幕，
+    /// 其餘字幕時間減去 `start_sec`，並換上新的 audio_path / duration。
+    /// 全部在同一個 transaction 裡，字幕與音檔不會對不上。
+    /// 回傳 (保留並平移的字幕數, 刪除的字幕數)。
+    pub fn trim_lecture_timeline(
+        &self,
+        lecture_id: &str,
+        start_sec: f64,
+        end_sec: f64,
+        audio_path: &str,
+        duration_sec: i64,
+    ) -> SqlResult<(usize, usize)> {
+        let tx = self.conn.unchecked_transaction()?;
+        let removed = tx.execute(
+            "DELETE FROM subtitles WHERE lecture_id = ?1 AND (timestamp < ?2 OR timestamp >= ?3)",
+            rusqlite::params![lecture_id, start_sec, end_sec],
+        )?;
+        let shifted = tx.execute(
+            "UPDATE subtitles SET timestamp = timestamp - ?2 WHERE lecture_id = ?1",
+            rusqlite::params![lecture_id, start_sec],
+        )?;
+        // Slide ranges are clipped to the kept span rather than dropped,
+        // so a slide that was up across the cut still covers the start.
+        let (start_ms, end_ms) = ((start_sec * 1000.0) as i64, (end_sec * 1000.0) as i64);
+        tx.execute(
+            "DELETE FROM slide_alignments WHERE lecture_id = ?1 AND (end_ms <= ?2 OR start_ms >= ?3)",
+            rusqlite::params![lecture_id, start_ms, end_ms],
+        )?;
+        tx.execute(
+            "UPDATE slide_alignments SET start_ms = MAX(start_ms, ?2) - ?2, end_ms = MIN(end_ms, ?3) - ?2
+             WHERE lecture_id = ?1",
+            rusqlite::params![lecture_id, start_ms, end_ms],
+        )?;
+        tx.execute(
+            "UPDATE lectures SET audio_path = ?1, duration = ?2, updated_at = ?3 WHERE id = ?4",
+            rusqlite::params![
+                audio_path,
+                duration_sec,
+                chrono::Utc::now().to_rfc3339(),
+                lecture_id
+            ],
+        )?;
+        tx.commit()?;
+        Ok((shifted, removed))
+    }
+
+    /// 合併錄音後同步時間軸：把 `moves` 中每個來源課堂的字幕搬到
+    /// `lecture_id`，時間加上該段錄音在合併檔中的起點（秒），並換上新的
+    /// audio_path / duration。同一個 transaction。回傳搬移的字幕數。
+    pub fn merge_lecture_timelines(
+        &self,
+        lecture_id: &str,
+        moves: &[(String, f64)],
+        audio_path: &str,
+        duration_sec: i64,
+    ) -> SqlResult<usize> {
+        let tx = self.conn.unchecked_transaction()?;
+        let mut moved = 0;
+        for (source_id, offset_sec) in moves {
+            moved += tx.execute(
+                "UPDATE subtitles SET lecture_id = ?1, timestamp = timestamp + ?2 WHERE lecture_id = ?3",
+                rusqlite::params![lecture_id, offset_sec, source_id],
+            )?;
+            // Their pages belong to the source lecture's PDF, not ours.
+            tx.execute(
+                "DELETE FROM slide_alignments WHERE lecture_id = ?1",
+                [source_id],
+            )?;
+        }
+        let now = chrono::Utc::now().to_rfc3339();
+        tx.execute(
+            "UPDATE lectures SET audio_path = ?1, duration = ?2, updated_at = ?3 WHERE id = ?4",
+            rusqlite::params![audio_path, duration_sec, now, lecture_id],
+        )?;
+        tx.commit()?;
+        Ok(moved)
+    }
+
     /// 獲取課程
     pub fn get_lecture(&self, id: &str) -> SqlResult<Option<Lecture>> {
         // v0.6.0: `video_path` appended at column index 11 (was last
@@ -1306,6 +1477,18 @@ impl Database {
         Ok(lectures)
     }
 
+    /// Every lecture's `pdf_path`, trashed lectures included, for
+    /// working out which converted documents are still in use.
+    pub fn list_pdf_paths(&self) -> SqlResult<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT pdf_path FROM lectures WHERE pdf_path IS NOT NULL AND pdf_path != ''",
+        )?;
+        let paths = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(paths)
+    }
+
     /// 刪除課程 (軟刪除)
     ///
     /// Phase 7 S3.f-RS-3: also stamps `deleted_at` (ms epoch) so the
@@ -1538,6 +1721,76 @@ impl Database {
         Ok(())
     }
 
+    // --- Externalized transcripts (storage::transcript_store) ---
+
+    pub fn get_transcript_pointer(&self, lecture_id: &str) -> SqlResult<Option<TranscriptPointer>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT lecture_id, path, sha256, byte_len, raw_len, segment_count, externalized_at \
+             FROM transcript_blobs WHERE lecture_id = ?1",
+        )?;
+        let mut rows = stmt.query_map([lecture_id], |row| {
+            Ok(TranscriptPointer {
+                lecture_id: row.get(0)?,
+                path: row.get(1)?,
+                sha256: row.get(2)?,
+                byte_len: row.get::<_, i64>(3)? as u64,
+                raw_len: row.get::<_, i64>(4)? as u64,
+                segment_count: row.get::<_, i64>(5)? as u64,
+                externalized_at: row.get(6)?,
+            })
+        })?;
+        rows.next().transpose()
+    }
+
+    /// Upsert the pointer and drop the lecture's subtitle rows in one
+    /// transaction. The caller has already written + verified the blob,
+    /// so either both happen or the rows stay where they were.
+    pub fn commit_transcript_pointer(&self, pointer: &TranscriptPointer) -> SqlResult<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO transcript_blobs \
+             (lecture_id, path, sha256, byte_len, raw_len, segment_count, externalized_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7) \
+             ON CONFLICT(lecture_id) DO UPDATE SET \
+                path = excluded.path, sha256 = excluded.sha256, \
+                byte_len = excluded.byte_len, raw_len = excluded.raw_len, \
+                segment_count = excluded.segment_count, \
+                externalized_at = excluded.externalized_at",
+            rusqlite::params![
+                pointer.lecture_id,
+                pointer.path,
+                pointer.sha256,
+                pointer.byte_len as i64,
+                pointer.raw_len as i64,
+                pointer.segment_count as i64,
+                pointer.externalized_at,
+            ],
+        )?;
+        tx.execute(
+            "DELETE FROM subtitles WHERE lecture_id = ?1",
+            [&pointer.lecture_id],
+        )?;
+        tx.commit()
+    }
+
+    /// Write externalized rows back into `subtitles` and drop the
+    /// pointer, atomically.
+    pub fn restore_externalized_subtitles(
+        &self,
+        lecture_id: &str,
+        subtitles: &[Subtitle],
+    ) -> SqlResult<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        for subtitle in subtitles {
+            self.save_subtitle(subtitle)?;
+        }
+        tx.execute(
+            "DELETE FROM transcript_blobs WHERE lecture_id = ?1",
+            [lecture_id],
+        )?;
+        tx.commit()
+    }
+
     /// 保存筆記
     pub fn save_note(&self, note: &Note) -> SqlResult<()> {
         self.conn.execute(
@@ -1573,6 +1826,21 @@ impl Database {
         }
     }
 
+    /// Like [`Self::get_note`] but also returns a trashed note, for
+    /// callers that must not write over one with `save_note`.
+    pub fn get_note_including_trashed(&self, lecture_id: &str) -> SqlResult<Option<Note>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT lecture_id, title, content, generated_at, is_deleted
+             FROM notes WHERE lecture_id = ?1",
+        )?;
+
+        match stmt.query_row([lecture_id], |row| Note::try_from(row)) {
+            Ok(note) => Ok(Some(note)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
     /// 刪除筆記
     pub fn delete_note(&self, lecture_id: &str) -> SqlResult<()> {
         self.conn
@@ -1603,61 +1871,19 @@ impl Database {
         Ok(())
     }
 
-    /// 獲取設置 — per-user via composite key, with a graceful fallback
-    /// for legacy single-user rows that pre-dated cp75.3 (those have
-    /// the bare key string and `user_id = 'default_user'`).
+    /// 獲取設置 — per-user via composite key. Rows from before cp75.3
+    /// (bare key) are rewritten to the scoped form by the
+    /// `settings-scoped-keys` post-update migration.
     pub fn get_setting(&self, key: &str, user_id: &str) -> SqlResult<Option<String>> {
-        // Primary lookup: scoped key for this user.
         let scoped = Self::scoped_setting_key(key, user_id);
-        {
-            let mut stmt = self
-                .conn
-                .prepare("SELECT value FROM settings WHERE key = ?1")?;
-            match stmt.query_row([scoped.as_str()], |row| row.get::<_, String>(0)) {
-                Ok(value) => return Ok(Some(value)),
-                Err(rusqlite::Error::QueryReturnedNoRows) => {
-                    // fall through to legacy lookup below
-                }
-                Err(e) => return Err(e),
-            }
-        }
-
-        // Legacy fallback: pre-cp75.3 rows used the bare key with
-        // user_id='default_user'. Migrate-on-read for the default user
-        // so subsequent saves use the new scoped form. For non-default
-        // users, do NOT read default_user's data — that would re-leak
-        // settings across accounts.
-        if user_id != "default_user" {
-            return Ok(None);
-        }
-        let legacy = {
-            let mut stmt = self
-                .conn
-                .prepare("SELECT value FROM settings WHERE key = ?1")?;
-            match stmt.query_row([key], |row| row.get::<_, String>(0)) {
-                Ok(value) => Some(value),
-                Err(rusqlite::Error::QueryReturnedNoRows) => None,
-                Err(e) => return Err(e),
-            }
-        };
-
-        // cp75.9 — migrate-on-read: when we hit a legacy row, write it
-        // back under the scoped key SO THE NEXT GET HITS THE PRIMARY
-        // PATH. Without this, every save through the new scoped path
-        // creates an *empty* scoped row (when the user's first edit
-        // doesn't include all the legacy fields), and after that the
-        // primary lookup wins → the legacy value (e.g. Calendar RSS URL)
-        // becomes invisible. Idempotent: re-running on an already-
-        // migrated row is a no-op (same value).
-        if let Some(value) = &legacy {
-            let updated_at = Utc::now().to_rfc3339();
-            let _ = self.conn.execute(
-                "INSERT OR REPLACE INTO settings (key, value, updated_at, user_id) \
-                 VALUES (?1, ?2, ?3, ?4)",
-                rusqlite::params![scoped, value, updated_at, user_id],
-            );
+        let mut stmt = self
+            .conn
+            .prepare("SELECT value FROM settings WHERE key = ?1")?;
+        match stmt.query_row([scoped.as_str()], |row| row.get::<_, String>(0)) {
+            Ok(value) => Ok(Some(value)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
         }
-        Ok(legacy)
     }
 
     /// 獲取所有設置
@@ -1689,6 +1915,15 @@ impl Database {
         Ok(())
     }
 
+    /// 更新本地使用者的伺服器同步狀態
+    pub fn set_local_user_sync_status(&self, username: &str, status: &str) -> SqlResult<()> {
+        self.conn.execute(
+            "UPDATE local_users SET sync_status = ?2 WHERE username = ?1",
+            rusqlite::params![username, status],
+        )?;
+        Ok(())
+    }
+
     /// 檢查本地使用者是否存在
     pub fn check_local_user(&self, username: &str) -> SqlResult<bool> {
         let mut stmt = self
@@ -1746,6 +1981,308 @@ impl Database {
         Ok(())
     }
 
+    /// 下一個待送出的動作（最早加入的 pending）
+    pub fn next_pending_action(&self) -> SqlResult<Option<(String, String, String, i32)>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, action_type, payload, retry_count FROM pending_actions \
+             WHERE status = 'pending' ORDER BY created_at ASC LIMIT 1",
+        )?;
+        match stmt.query_row([], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        }) {
+            Ok(action) => Ok(Some(action)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 待處理動作數量：(pending + processing + deferred, failed)
+    pub fn count_pending_actions(&self) -> SqlResult<(usize, usize)> {
+        self.conn.query_row(
+            "SELECT COALESCE(SUM(status IN ('pending', 'processing', 'deferred')), 0), \
+                    COALESCE(SUM(status = 'failed'), 0) FROM pending_actions",
+            [],
+            |row| Ok((row.get::<_, i64>(0)? as usize, row.get::<_, i64>(1)? as usize)),
+        )
+    }
+
+    /// 將狀態為 `from` 的動作改回 pending；`reset_retries` 時重設重試次數
+    pub fn requeue_pending_actions(&self, from: &str, reset_retries: bool) -> SqlResult<usize> {
+        let now = Utc::now().to_rfc3339();
+        self.conn.execute(
+            "UPDATE pending_actions SET status = 'pending', updated_at = ?2, \
+             retry_count = CASE WHEN ?3 THEN 0 ELSE retry_count END WHERE status = ?1",
+            rusqlite::params![from, now, reset_retries],
+        )
+    }
+
+    // --- Sync (delta push) ---
+    //
+    // `since` is the (updated_at, id) of the last row already pushed; rows
+    // come back in that order, after it, trashed ones included so the
+    // server hears about deletes. Timestamps are compared with julianday()
+    // because the frontend writes `...Z` and the backend `...+00:00`.
+    // Nothing of the `excluded_courses` comes back.
+
+    /// 自 `since` 之後變更過的科目（含已刪除）
+    pub fn list_courses_changed_since(
+        &self,
+        user_id: &str,
+        since: Option<(&str, &str)>,
+        excluded_courses: &[String],
+        limit: usize,
+    ) -> SqlResult<Vec<Course>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, user_id, title, description, keywords, syllabus_info, created_at, updated_at, is_deleted, canvas_course_id
+             FROM courses
+             WHERE user_id = ?1
+               AND (?2 IS NULL OR julianday(updated_at) > julianday(?2)
+                    OR (julianday(updated_at) = julianday(?2) AND id > ?3))
+               AND id NOT IN (SELECT value FROM json_each(?5))
+             ORDER BY julianday(updated_at), id LIMIT ?4",
+        )?;
+        let (at, id) = since.unzip();
+        let excluded = serde_json::to_string(excluded_courses).unwrap_or_default();
+        let courses = stmt
+            .query_map(
+                rusqlite::params![user_id, at, id, limit as i64, excluded],
+                |row| Course::try_from(row),
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(courses)
+    }
+
+    /// 自 `since` 之後變更過的課堂（含已刪除）
+    pub fn list_lectures_changed_since(
+        &self,
+        user_id: &str,
+        since: Option<(&str, &str)>,
+        excluded_courses: &[String],
+        limit: usize,
+    ) -> SqlResult<Vec<Lecture>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT l.id, l.course_id, l.title, l.date, l.duration, l.pdf_path, l.audio_path, l.status, l.created_at, l.updated_at, l.is_deleted, l.video_path
+             FROM lectures l
+             JOIN courses c ON l.course_id = c.id
+             WHERE c.user_id = ?1
+               AND (?2 IS NULL OR julianday(l.updated_at) > julianday(?2)
+                    OR (julianday(l.updated_at) = julianday(?2) AND l.id > ?3))
+               AND c.id NOT IN (SELECT value FROM json_each(?5))
+             ORDER BY julianday(l.updated_at), l.id LIMIT ?4",
+        )?;
+        let (at, id) = since.unzip();
+        let excluded = serde_json::to_string(excluded_courses).unwrap_or_default();
+        let lectures = stmt
+            .query_map(
+                rusqlite::params![user_id, at, id, limit as i64, excluded],
+                |row| Lecture::try_from(row),
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(lectures)
+    }
+
+    /// 自 `since` 之後變更過的筆記（含已刪除）。筆記沒有 updated_at，
+    /// 以 `generated_at` 與 `lecture_id` 排序。
+    pub fn list_notes_changed_since(
+        &self,
+        user_id: &str,
+        since: Option<(&str, &str)>,
+        excluded_courses: &[String],
+        limit: usize,
+    ) -> SqlResult<Vec<Note>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT n.lecture_id, n.title, n.content, n.generated_at, n.is_deleted
+             FROM notes n
+             JOIN lectures l ON n.lecture_id = l.id
+             JOIN courses c ON l.course_id = c.id
+             WHERE c.user_id = ?1
+               AND (?2 IS NULL OR julianday(n.generated_at) > julianday(?2)
+                    OR (julianday(n.generated_at) = julianday(?2) AND n.lecture_id > ?3))
+               AND c.id NOT IN (SELECT value FROM json_each(?5))
+             ORDER BY julianday(n.generated_at), n.lecture_id LIMIT ?4",
+        )?;
+        let (at, id) = since.unzip();
+        let excluded = serde_json::to_string(excluded_courses).unwrap_or_default();
+        let notes = stmt
+            .query_map(
+                rusqlite::params![user_id, at, id, limit as i64, excluded],
+                |row| Note::try_from(row),
+            )?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(notes)
+    }
+
+    /// `entity` 已推送到 `server_url` 的最後一筆 (updated_at, id)
+    pub fn get_sync_watermark(
+        &self,
+        server_url: &str,
+        user_id: &str,
+        entity: &str,
+    ) -> SqlResult<Option<(String, String)>> {
+        match self.conn.query_row(
+            "SELECT updated_at, last_id FROM sync_watermarks \
+             WHERE server_url = ?1 AND user_id = ?2 AND entity = ?3",
+            [server_url, user_id, entity],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        ) {
+            Ok(mark) => Ok(Some(mark)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 記錄 `entity` 已推送到 (updated_at, id)
+    pub fn set_sync_watermark(
+        &self,
+        server_url: &str,
+        user_id: &str,
+        entity: &str,
+        updated_at: &str,
+        last_id: &str,
+    ) -> SqlResult<()> {
+        let now = Utc::now().to_rfc3339();
+        self.conn.execute(
+            "INSERT OR REPLACE INTO sync_watermarks \
+             (server_url, user_id, entity, updated_at, last_id, synced_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![server_url, user_id, entity, updated_at, last_id, now],
+        )?;
+        Ok(())
+    }
+
+    /// 清除使用者在所有伺服器的同步進度，下次推送時全部重送
+    pub fn clear_sync_watermarks(&self, user_id: &str) -> SqlResult<usize> {
+        self.conn
+            .execute("DELETE FROM sync_watermarks WHERE user_id = ?1", [user_id])
+    }
+
+    /// 清除使用者在單一伺服器的同步進度
+    pub fn clear_server_sync_watermarks(
+        &self,
+        server_url: &str,
+        user_id: &str,
+    ) -> SqlResult<usize> {
+        self.conn.execute(
+            "DELETE FROM sync_watermarks WHERE server_url = ?1 AND user_id = ?2",
+            [server_url, user_id],
+        )
+    }
+
+    /// 最近一次推送到伺服器的時間
+    pub fn last_sync_push(&self, server_url: &str, user_id: &str) -> SqlResult<Option<String>> {
+        self.conn.query_row(
+            "SELECT MAX(synced_at) FROM sync_watermarks WHERE server_url = ?1 AND user_id = ?2",
+            [server_url, user_id],
+            |row| row.get(0),
+        )
+    }
+
+    /// 保存衝突副本。未解決的副本會取代同一課堂先前未解決的那份：
+    /// 伺服器上較新的版本才是使用者要選的。
+    pub fn save_note_conflict(&self, conflict: &NoteConflict) -> SqlResult<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        if !conflict.resolved {
+            tx.execute(
+                "DELETE FROM note_conflicts WHERE lecture_id = ?1 AND resolved = 0",
+                [&conflict.lecture_id],
+            )?;
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO note_conflicts \
+             (id, lecture_id, side, title, content, generated_at, conflicted_at, server_url, resolved) \
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            rusqlite::params![
+                conflict.id,
+                conflict.lecture_id,
+                conflict.side,
+                conflict.title,
+                conflict.content,
+                conflict.generated_at,
+                conflict.conflicted_at,
+                conflict.server_url,
+                conflict.resolved,
+            ],
+        )?;
+        tx.commit()
+    }
+
+    /// 列出使用者的衝突副本（新到舊）；`include_resolved` 為 false 時只列待選的
+    pub fn list_note_conflicts(
+        &self,
+        user_id: &str,
+        include_resolved: bool,
+    ) -> SqlResult<Vec<NoteConflict>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT n.id, n.lecture_id, n.side, n.title, n.content, n.generated_at, n.conflicted_at, n.server_url, n.resolved
+             FROM note_conflicts n
+             JOIN lectures l ON n.lecture_id = l.id
+             JOIN courses c ON l.course_id = c.id
+             WHERE c.user_id = ?1 AND (?2 OR n.resolved = 0)
+             ORDER BY n.conflicted_at DESC",
+        )?;
+        let conflicts = stmt
+            .query_map(rusqlite::params![user_id, include_resolved], |row| {
+                NoteConflict::try_from(row)
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(conflicts)
+    }
+
+    /// 獲取衝突副本
+    pub fn get_note_conflict(&self, id: &str) -> SqlResult<Option<NoteConflict>> {
+        match self.conn.query_row(
+            "SELECT id, lecture_id, side, title, content, generated_at, conflicted_at, server_url, resolved
+             FROM note_conflicts WHERE id = ?1",
+            [id],
+            |row| NoteConflict::try_from(row),
+        ) {
+            Ok(conflict) => Ok(Some(conflict)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 標記衝突已解決（副本仍保留）
+    pub fn set_note_conflict_resolved(&self, id: &str) -> SqlResult<()> {
+        self.conn
+            .execute("UPDATE note_conflicts SET resolved = 1 WHERE id = ?1", [id])?;
+        Ok(())
+    }
+
+    /// 永久刪除後留下的墓碑，依 (purged_at, entity/id) 排序，從 `since`
+    /// 之後開始
+    pub fn list_tombstones_since(
+        &self,
+        user_id: &str,
+        since: Option<(&str, &str)>,
+        limit: usize,
+    ) -> SqlResult<Vec<Tombstone>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT entity, id, purged_at FROM sync_tombstones
+             WHERE user_id = ?1
+               AND (?2 IS NULL OR purged_at > ?2
+                    OR (purged_at = ?2 AND entity || '/' || id > ?3))
+             ORDER BY purged_at, entity || '/' || id
+             LIMIT ?4",
+        )?;
+        let (at, key) = since.unzip();
+        let tombstones = stmt
+            .query_map(rusqlite::params![user_id, at, key, limit as i64], |row| {
+                Tombstone::try_from(row)
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(tombstones)
+    }
+
+    /// 是否已永久刪除；衝突處理時，伺服器送回屬於已刪除課堂的筆記會被略過
+    pub fn is_tombstoned(&self, entity: &str, id: &str) -> SqlResult<bool> {
+        self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sync_tombstones WHERE entity = ?1 AND id = ?2)",
+            [entity, id],
+            |row| row.get(0),
+        )
+    }
+
     // --- Trash Bin Functions ---
 
     /// 列出已刪除的課程
@@ -1932,6 +2469,24 @@ impl Database {
             rows.filter_map(|r| r.ok()).collect()
         };
 
+        add_tombstones(
+            &tx,
+            "lectures",
+            "SELECT l.id, c.user_id FROM lectures l \
+             JOIN courses c ON l.course_id = c.id \
+             WHERE c.user_id = ?2 \
+               AND ((l.is_deleted = 1 AND l.deleted_at IS NOT NULL AND l.deleted_at < ?1) \
+                 OR (c.is_deleted = 1 AND c.deleted_at IS NOT NULL AND c.deleted_at < ?1))",
+            rusqlite::params![cutoff, user_id],
+        )?;
+        add_tombstones(
+            &tx,
+            "courses",
+            "SELECT id, user_id FROM courses WHERE is_deleted = 1 \
+             AND deleted_at IS NOT NULL AND deleted_at < ?1 AND user_id = ?2",
+            rusqlite::params![cutoff, user_id],
+        )?;
+
         // Delete lectures owned by THIS user only.
         tx.execute(
             "DELETE FROM lectures WHERE id IN ( \
@@ -1987,6 +2542,13 @@ impl Database {
             if !trashed {
                 continue;
             }
+            add_tombstones(
+                &tx,
+                "lectures",
+                "SELECT l.id, c.user_id FROM lectures l \
+                 JOIN courses c ON l.course_id = c.id WHERE l.id = ?1",
+                [id],
+            )?;
             tx.execute(
                 "DELETE FROM lectures WHERE id = ?1 AND is_deleted = 1",
                 [id],
@@ -2000,16 +2562,36 @@ impl Database {
 
     /// 永久刪除課程 (物理刪除)
     pub fn purge_course(&self, id: &str) -> SqlResult<()> {
-        self.conn
-            .execute("DELETE FROM courses WHERE id = ?1", [id])?;
-        Ok(())
+        let tx = self.conn.unchecked_transaction()?;
+        add_tombstones(
+            &tx,
+            "lectures",
+            "SELECT l.id, c.user_id FROM lectures l \
+             JOIN courses c ON l.course_id = c.id WHERE c.id = ?1",
+            [id],
+        )?;
+        add_tombstones(
+            &tx,
+            "courses",
+            "SELECT id, user_id FROM courses WHERE id = ?1",
+            [id],
+        )?;
+        tx.execute("DELETE FROM courses WHERE id = ?1", [id])?;
+        tx.commit()
     }
 
     /// 永久刪除課堂 (物理刪除)
     pub fn purge_lecture(&self, id: &str) -> SqlResult<()> {
-        self.conn
-            .execute("DELETE FROM lectures WHERE id = ?1", [id])?;
-        Ok(())
+        let tx = self.conn.unchecked_transaction()?;
+        add_tombstones(
+            &tx,
+            "lectures",
+            "SELECT l.id, c.user_id FROM lectures l \
+             JOIN courses c ON l.course_id = c.id WHERE l.id = ?1",
+            [id],
+        )?;
+        tx.execute("DELETE FROM lectures WHERE id = ?1", [id])?;
+        tx.commit()
     }
 
     // ============================================================
@@ -2021,6 +2603,13 @@ impl Database {
         let deleted = self
             .conn
             .execute("DELETE FROM subtitles WHERE lecture_id = ?1", [lecture_id])?;
+        // An externalized transcript would otherwise be merged back in
+        // on the next read. The orphaned blob is overwritten on the next
+        // externalize or removed on purge.
+        self.conn.execute(
+            "DELETE FROM transcript_blobs WHERE lecture_id = ?1",
+            [lecture_id],
+        )?;
         Ok(deleted)
     }
 
@@ -2294,6 +2883,75 @@ impl Database {
             |row| row.get(0),
         )
     }
+
+    // ============================================================
+    // SLIDE ALIGNMENTS
+    // ============================================================
+
+    /// Replace the lecture's slide timeline in one transaction, so a
+    /// re-run of the alignment never leaves a half-written timeline.
+    pub fn replace_slide_alignments(
+        &self,
+        lecture_id: &str,
+        alignments: &[SlideAlignment],
+    ) -> SqlResult<()> {
+        let tx = self.conn.unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM slide_alignments WHERE lecture_id = ?1",
+            [lecture_id],
+        )?;
+        for a in alignments {
+            tx.execute(
+                "INSERT INTO slide_alignments (lecture_id, page_number, start_ms, end_ms, confidence)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                rusqlite::params![lecture_id, a.page_number, a.start_ms, a.end_ms, a.confidence],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// The lecture's slide timeline in playback order; empty when it
+    /// hasn't been aligned yet.
+    pub fn get_slide_alignments(&self, lecture_id: &str) -> SqlResult<Vec<SlideAlignment>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT page_number, start_ms, end_ms, confidence FROM slide_alignments
+             WHERE lecture_id = ?1 ORDER BY start_ms",
+        )?;
+        let rows = stmt
+            .query_map([lecture_id], |row| SlideAlignment::try_from(row))?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(rows)
+    }
+
+    /// Page on screen at `position_ms`, if the timeline covers it.
+    pub fn slide_page_at(&self, lecture_id: &str, position_ms: i64) -> SqlResult<Option<i64>> {
+        match self.conn.query_row(
+            "SELECT page_number FROM slide_alignments
+             WHERE lecture_id = ?1 AND start_ms <= ?2 AND end_ms > ?2",
+            rusqlite::params![lecture_id, position_ms],
+            |row| row.get(0),
+        ) {
+            Ok(page) => Ok(Some(page)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    // ============================================================
+    // HEALTH
+    // ============================================================
+
+    /// `PRAGMA quick_check`: `["ok"]` for a healthy file, otherwise the
+    /// first few problems SQLite found. Reads every page, so it is for
+    /// diagnostics, not the hot path.
+    pub fn quick_check(&self) -> SqlResult<Vec<String>> {
+        let mut stmt = self.conn.prepare("PRAGMA quick_check(20)")?;
+        let rows = stmt
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        Ok(rows)
+    }
 }
 
 /// Public shape for embedding rows returned across the Tauri boundary.
@@ -2320,6 +2978,32 @@ fn now_unix_ms() -> i64 {
         .unwrap_or(0)
 }
 
+/// Record a tombstone for each `(id, user_id)` row `select` returns, just
+/// before the rows are deleted for good.
+fn add_tombstones(
+    conn: &Connection,
+    entity: &str,
+    select: &str,
+    params: impl rusqlite::Params,
+) -> SqlResult<usize> {
+    let rows = {
+        let mut stmt = conn.prepare(select)?;
+        let rows = stmt.query_map(params, |r| {
+            Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?))
+        })?;
+        rows.collect::<SqlResult<Vec<_>>>()?
+    };
+    let now = Utc::now().to_rfc3339();
+    let mut insert = conn.prepare(
+        "INSERT OR REPLACE INTO sync_tombstones (entity, id, user_id, purged_at) \
+         VALUES (?1, ?2, ?3, ?4)",
+    )?;
+    for (id, user_id) in &rows {
+        insert.execute(rusqlite::params![entity, id, user_id, now])?;
+    }
+    Ok(rows.len())
+}
+
 fn pack_f32_le(vec: &[f32]) -> Vec<u8> {
     let mut out = Vec::with_capacity(vec.len() * 4);
     for &f in vec {
@@ -2648,29 +3332,188 @@ mod tests {
     }
 
     #[test]
-    fn test_settings_legacy_default_user_fallback() {
+    fn test_settings_legacy_rows_are_scoped_by_migration() {
         // cp75.3 — pre-migration rows had the bare key + user_id='default_user'.
-        // get_setting('default_user', ..) must still find them.
+        // The post-update migration moves them under default_user's scope.
         let (db, _temp) = create_test_db();
 
-        // Hand-write a legacy row directly (no scoped key prefix).
+        // Hand-write legacy rows directly (no scoped key prefix).
         db.conn
-            .execute(
+            .execute_batch(
                 "INSERT INTO settings (key, value, updated_at, user_id) \
-                 VALUES ('legacy_key', 'legacy_value', '2026-01-01T00:00:00Z', 'default_user')",
-                [],
+                 VALUES ('legacy_key', 'legacy_value', '2026-01-01T00:00:00Z', 'default_user'); \
+                 INSERT INTO settings (key, value, updated_at, user_id) \
+                 VALUES ('theme', 'light', '2026-01-01T00:00:00Z', 'default_user');",
             )
             .unwrap();
+        // Saved since through the scoped path: must survive the migration.
+        db.save_setting("theme", "dark", "default_user").unwrap();
+
+        crate::storage::migrations::scope_legacy_settings(&db.conn).unwrap();
 
-        // Default user can read it via the legacy fallback path.
         assert_eq!(
             db.get_setting("legacy_key", "default_user").unwrap(),
             Some("legacy_value".to_string())
         );
+        assert_eq!(
+            db.get_setting("theme", "default_user").unwrap(),
+            Some("dark".to_string())
+        );
         // Other users must NOT see legacy data (would be a cross-user leak).
         assert_eq!(db.get_setting("legacy_key", "alice").unwrap(), None);
     }
 
+    #[test]
+    fn test_pending_actions_are_requeued_and_counted() {
+        let (db, _temp) = create_test_db();
+
+        db.add_pending_action("a1", "AUTH_REGISTER", "{}").unwrap();
+        db.add_pending_action("a2", "AUTH_REGISTER", "{}").unwrap();
+        db.update_pending_action("a1", "processing", 1).unwrap();
+        db.update_pending_action("a2", "failed", 3).unwrap();
+        assert_eq!(db.count_pending_actions().unwrap(), (1, 1));
+        assert_eq!(db.next_pending_action().unwrap(), None);
+
+        // A quit mid-send: back in line, retries kept.
+        assert_eq!(db.requeue_pending_actions("processing", false).unwrap(), 1);
+        let next = db.next_pending_action().unwrap().unwrap();
+        assert_eq!((next.0.as_str(), next.3), ("a1", 1));
+
+        // A manual retry starts the count over.
+        assert_eq!(db.requeue_pending_actions("failed", true).unwrap(), 1);
+        assert_eq!(db.count_pending_actions().unwrap(), (2, 0));
+        let actions = db.list_pending_actions().unwrap();
+        assert_eq!(actions[1].4, 0);
+    }
+
+    #[test]
+    fn test_changed_since_pages_through_ties_and_trashed_rows() {
+        let (db, _temp) = create_test_db();
+        let stamp = "2026-03-01T09:00:00.000Z";
+        let mut ids = Vec::new();
+        for title in ["A", "B", "C"] {
+            let mut course = Course::new("alice".to_string(), title.to_string(), None, None, None);
+            course.updated_at = stamp.to_string();
+            db.save_course(&course).unwrap();
+            ids.push(course.id);
+        }
+        ids.sort();
+        db.delete_course(&ids[2]).unwrap();
+
+        // Two rows share a timestamp across the page boundary.
+        let first = db
+            .list_courses_changed_since("alice", None, &[], 2)
+            .unwrap();
+        assert_eq!(
+            first.iter().map(|c| &c.id).collect::<Vec<_>>(),
+            [&ids[0], &ids[1]]
+        );
+        let last = first.last().unwrap();
+        db.set_sync_watermark(
+            "https://a.edu",
+            "alice",
+            "courses",
+            &last.updated_at,
+            &last.id,
+        )
+        .unwrap();
+
+        let (at, id) = db
+            .get_sync_watermark("https://a.edu", "alice", "courses")
+            .unwrap()
+            .unwrap();
+        let rest = db
+            .list_courses_changed_since("alice", Some((&at, &id)), &[], 2)
+            .unwrap();
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].id, ids[2]);
+        assert!(rest[0].is_deleted);
+        assert!(db
+            .list_courses_changed_since("alice", Some((&rest[0].updated_at, &rest[0].id)), &[], 2)
+            .unwrap()
+            .is_empty());
+
+        // Watermarks are per server.
+        assert_eq!(
+            db.get_sync_watermark("https://b.edu", "alice", "courses")
+                .unwrap(),
+            None
+        );
+        assert!(db
+            .list_courses_changed_since("bob", None, &[], 10)
+            .unwrap()
+            .is_empty());
+
+        // An excluded course never comes back.
+        let kept = db
+            .list_courses_changed_since("alice", None, &ids[..1], 10)
+            .unwrap();
+        assert_eq!(
+            kept.iter().map(|c| &c.id).collect::<Vec<_>>(),
+            [&ids[1], &ids[2]]
+        );
+    }
+
+    #[test]
+    fn test_unresolved_note_conflict_replaces_the_previous_one() {
+        let (db, _temp) = create_test_db();
+        let course = Course::new("alice".to_string(), "Course".to_string(), None, None, None);
+        db.save_course(&course).unwrap();
+        let lecture = Lecture::new(course.id.clone(), "Lecture".to_string(), None);
+        db.save_lecture(&lecture, "alice").unwrap();
+
+        let copy = |id: &str, resolved: bool| NoteConflict {
+            id: id.to_string(),
+            lecture_id: lecture.id.clone(),
+            side: "server".to_string(),
+            title: "Notes".to_string(),
+            content: id.to_string(),
+            generated_at: "2026-03-01T09:00:00Z".to_string(),
+            conflicted_at: "2026-03-02T09:00:00Z".to_string(),
+            server_url: "https://a.edu".to_string(),
+            resolved,
+        };
+        db.save_note_conflict(&copy("kept", true)).unwrap();
+        db.save_note_conflict(&copy("old", false)).unwrap();
+        db.save_note_conflict(&copy("new", false)).unwrap();
+
+        let pending = db.list_note_conflicts("alice", false).unwrap();
+        assert_eq!(pending, [copy("new", false)]);
+        assert_eq!(db.list_note_conflicts("alice", true).unwrap().len(), 2);
+        assert!(db.list_note_conflicts("bob", true).unwrap().is_empty());
+
+        db.set_note_conflict_resolved("new").unwrap();
+        assert!(db.list_note_conflicts("alice", false).unwrap().is_empty());
+        assert!(db.get_note_conflict("new").unwrap().unwrap().resolved);
+    }
+
+    #[test]
+    fn test_purging_a_course_leaves_tombstones_for_it_and_its_lectures() {
+        let (db, _temp) = create_test_db();
+        let course = Course::new("alice".to_string(), "Course".to_string(), None, None, None);
+        db.save_course(&course).unwrap();
+        let lecture = Lecture::new(course.id.clone(), "Lecture".to_string(), None);
+        db.save_lecture(&lecture, "alice").unwrap();
+
+        db.purge_course(&course.id).unwrap();
+        assert!(db.is_tombstoned("courses", &course.id).unwrap());
+        assert!(db.is_tombstoned("lectures", &lecture.id).unwrap());
+        assert!(!db.is_tombstoned("lectures", &course.id).unwrap());
+
+        let first = db.list_tombstones_since("alice", None, 1).unwrap();
+        assert_eq!(first[0].entity, "courses");
+        let key = format!("{}/{}", first[0].entity, first[0].id);
+        let rest = db
+            .list_tombstones_since("alice", Some((&first[0].purged_at, &key)), 10)
+            .unwrap();
+        assert_eq!(rest.len(), 1);
+        assert_eq!(rest[0].id, lecture.id);
+        assert!(db
+            .list_tombstones_since("bob", None, 10)
+            .unwrap()
+            .is_empty());
+    }
+
     // ===== Note Tests =====
 
     #[test]
@@ -1394,4 +1394,140 @@ mod tests {
             "soft-deleted course must not appear, got {ids:?}"
         );
     }
+
+    #[test]
+    fn trim_lecture_timeline_shifts_kept_subtitles_and_drops_the_rest() {
+        use crate::storage::models::Subtitle;
+        let db = make_test_db();
+        seed_minimal(&db);
+        for ts in [5.0, 60.0, 125.5, 3_700.0] {
+            db.save_subtitle(&Subtitle::new(
+                "l1".into(),
+                ts,
+                format!("at {ts}"),
+                None,
+                "rough".into(),
+                None,
+            ))
+            .unwrap();
+        }
+
+        let (shifted, removed) = db
+            .trim_lecture_timeline("l1", 30.0, 3_600.0, "lecture_l1_trim.wav", 3_570)
+            .unwrap();
+        assert_eq!((shifted, removed), (2, 2));
+
+        let mut stamps: Vec<f64> = db
+            .get_subtitles("l1")
+            .unwrap()
+            .iter()
+            .map(|s| s.timestamp)
+            .collect();
+        stamps.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(stamps, vec![30.0, 95.5]);
+
+        let lecture = db.get_lecture("l1").unwrap().unwrap();
+        assert_eq!(lecture.audio_path.as_deref(), Some("lecture_l1_trim.wav"));
+        assert_eq!(lecture.duration, 3_570);
+    }
+
+    #[test]
+    fn merge_lecture_timelines_moves_later_subtitles_with_offset() {
+        use crate::storage::models::Subtitle;
+        let db = make_test_db();
+        seed_minimal(&db);
+        insert_lecture(&db, "l2", "c1");
+        for (lec, ts) in [("l1", 10.0), ("l2", 0.0), ("l2", 42.5)] {
+            db.save_subtitle(&Subtitle::new(
+                lec.into(),
+                ts,
+                "x".into(),
+                None,
+                "rough".into(),
+                None,
+            ))
+            .unwrap();
+        }
+
+        let moved = db
+            .merge_lecture_timelines("l1", &[("l2".into(), 3_000.0)], "merged.wav", 4_000)
+            .unwrap();
+        assert_eq!(moved, 2);
+
+        let mut stamps: Vec<f64> = db
+            .get_subtitles("l1")
+            .unwrap()
+            .iter()
+            .map(|s| s.timestamp)
+            .collect();
+        stamps.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(stamps, vec![10.0, 3_000.0, 3_042.5]);
+        assert!(db.get_subtitles("l2").unwrap().is_empty());
+        assert_eq!(db.get_lecture("l1").unwrap().unwrap().duration, 4_000);
+    }
+
+    fn slide(page_number: i64, start_ms: i64, end_ms: i64) -> crate::storage::SlideAlignment {
+        crate::storage::SlideAlignment {
+            page_number,
+            start_ms,
+            end_ms,
+            confidence: Some(0.5),
+        }
+    }
+
+    #[test]
+    fn slide_alignments_replace_and_look_up() {
+        let db = make_test_db();
+        seed_minimal(&db);
+        db.replace_slide_alignments("l1", &[slide(1, 0, 5_000), slide(2, 5_000, 9_000)])
+            .unwrap();
+        db.replace_slide_alignments("l1", &[slide(3, 2_000, 4_000), slide(1, 0, 2_000)])
+            .unwrap();
+
+        let rows = db.get_slide_alignments("l1").unwrap();
+        assert_eq!(rows, vec![slide(1, 0, 2_000), slide(3, 2_000, 4_000)]);
+        assert_eq!(db.slide_page_at("l1", 2_000).unwrap(), Some(3));
+        assert_eq!(db.slide_page_at("l1", 4_000).unwrap(), None);
+
+        // Gone with the lecture.
+        db.purge_lecture("l1").unwrap();
+        assert!(db.get_slide_alignments("l1").unwrap().is_empty());
+    }
+
+    #[test]
+    fn trim_and_merge_keep_slide_alignments_in_sync() {
+        let db = make_test_db();
+        seed_minimal(&db);
+        insert_lecture(&db, "l2", "c1");
+        db.replace_slide_alignments(
+            "l1",
+            &[
+                slide(1, 0, 20_000),
+                slide(2, 20_000, 50_000),
+                slide(3, 50_000, 90_000),
+            ],
+        )
+        .unwrap();
+        db.replace_slide_alignments("l2", &[slide(7, 0, 1_000)])
+            .unwrap();
+
+        db.trim_lecture_timeline("l1", 30.0, 60.0, "trim.wav", 30)
+            .unwrap();
+        assert_eq!(
+            db.get_slide_alignments("l1").unwrap(),
+            vec![slide(2, 0, 20_000), slide(3, 20_000, 30_000)]
+        );
+
+        db.merge_lecture_timelines("l1", &[("l2".into(), 30.0)], "merged.wav", 31)
+            .unwrap();
+        assert_eq!(db.get_slide_alignments("l1").unwrap().len(), 2);
+        assert!(db.get_slide_alignments("l2").unwrap().is_empty());
+    }
+
+    #[test]
+    fn quick_check_reports_ok_for_a_fresh_db() {
+        let db = make_test_db();
+        seed_minimal(&db);
+        assert_eq!(db.quick_check().unwrap(), vec!["ok".to_string()]);
+    }
 }
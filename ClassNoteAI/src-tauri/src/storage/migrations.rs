@@ -0,0 +1,206 @@
+//! Post-update migrations.
+//!
+//! One-off changes an update makes to existing data: a schema tweak,
+//! files moved to a new location, a setting renamed. Each entry of
+//! [`MIGRATIONS`] runs on the first launch of a build that includes it,
+//! and its outcome is recorded in the `app_migrations` table, so it
+//! never runs again once it has succeeded. One that fails is retried on
+//! the next launch, and the user hears about it through the migration
+//! notices.
+//!
+//! Tables and columns are still created by `Database::init_tables`,
+//! which every connection runs; this is for the changes that used to
+//! happen only when some code path touched the data.
+//!
+//! [`run_pending`] is called synchronously from the setup hook, before
+//! the startup self-test, the model autoloads and `init_db` publishing
+//! the database manager, so nothing sees data half-migrated.
+
+use chrono::Utc;
+use rusqlite::{Connection, OptionalExtension};
+use std::path::Path;
+
+pub struct Migration {
+    /// Recorded once it succeeds; never rename one that has shipped.
+    pub id: &'static str,
+    /// The release that introduced it.
+    pub version: &'static str,
+    run: fn(&Connection) -> Result<(), String>,
+}
+
+/// Oldest release first; pending ones run in this order.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        id: "settings-scoped-keys",
+        version: "0.7.1",
+        run: scope_legacy_settings,
+    },
+    Migration {
+        id: "legacy-models-dir",
+        version: "0.7.1",
+        run: move_legacy_models,
+    },
+];
+
+#[derive(Debug, PartialEq)]
+pub struct Failure {
+    pub id: &'static str,
+    pub error: String,
+}
+
+/// Run the migrations that haven't succeeded yet against the database
+/// at `db_path`. Failures are logged and queued as migration notices.
+pub fn run_pending(db_path: &Path) {
+    let result = Connection::open(db_path)
+        .map_err(|e| format!("無法開啟數據庫: {}", e))
+        .and_then(|conn| run_inner(&conn, MIGRATIONS, env!("CARGO_PKG_VERSION")));
+    match result {
+        Ok(failures) => {
+            for failure in failures {
+                eprintln!("[Migrations] {} failed: {}", failure.id, failure.error);
+                super::database::record_migration_notice(format!(
+                    "更新後的資料轉移「{}」失敗，下次啟動時會再試一次：{}",
+                    failure.id, failure.error
+                ));
+            }
+        }
+        Err(e) => eprintln!("[Migrations] Could not run: {}", e),
+    }
+}
+
+pub(crate) fn run_inner(
+    conn: &Connection,
+    migrations: &[Migration],
+    app_version: &str,
+) -> Result<Vec<Failure>, String> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS app_migrations (
+            id TEXT PRIMARY KEY,
+            version TEXT NOT NULL,
+            succeeded INTEGER NOT NULL,
+            attempts INTEGER NOT NULL,
+            last_error TEXT,
+            app_version TEXT NOT NULL,
+            ran_at TEXT NOT NULL
+        )",
+    )
+    .map_err(|e| format!("無法建立資料轉移紀錄: {}", e))?;
+
+    let mut failures = Vec::new();
+    for migration in migrations {
+        let succeeded: Option<bool> = conn
+            .query_row(
+                "SELECT succeeded FROM app_migrations WHERE id = ?1",
+                [migration.id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        if succeeded == Some(true) {
+            continue;
+        }
+
+        let result = (migration.run)(conn);
+        conn.execute(
+            "INSERT INTO app_migrations \
+             (id, version, succeeded, attempts, last_error, app_version, ran_at) \
+             VALUES (?1, ?2, ?3, 1, ?4, ?5, ?6) \
+             ON CONFLICT(id) DO UPDATE SET \
+                succeeded = excluded.succeeded, \
+                attempts = attempts + 1, \
+                last_error = excluded.last_error, \
+                app_version = excluded.app_version, \
+                ran_at = excluded.ran_at",
+            rusqlite::params![
+                migration.id,
+                migration.version,
+                result.is_ok(),
+                result.as_ref().err(),
+                app_version,
+                Utc::now().to_rfc3339(),
+            ],
+        )
+        .map_err(|e| format!("無法記錄資料轉移結果: {}", e))?;
+        match result {
+            Ok(()) => println!("[Migrations] {} ({}) done", migration.id, migration.version),
+            Err(error) => failures.push(Failure {
+                id: migration.id,
+                error,
+            }),
+        }
+    }
+    Ok(failures)
+}
+
+/// Settings saved before per-user settings (cp75.3) have the bare key;
+/// they all belong to `default_user`. A scoped row saved since wins.
+pub(crate) fn scope_legacy_settings(conn: &Connection) -> Result<(), String> {
+    let tx = conn.unchecked_transaction().map_err(|e| e.to_string())?;
+    tx.execute_batch(
+        "INSERT OR IGNORE INTO settings (key, value, updated_at, user_id)
+            SELECT 'default_user::' || key, value, updated_at, 'default_user'
+            FROM settings WHERE key NOT LIKE '%::%';
+         DELETE FROM settings WHERE key NOT LIKE '%::%';",
+    )
+    .map_err(|e| e.to_string())?;
+    tx.commit().map_err(|e| e.to_string())
+}
+
+fn move_legacy_models(_: &Connection) -> Result<(), String> {
+    crate::paths::legacy::migrate_legacy_models().map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static FLAKY_RUNS: AtomicUsize = AtomicUsize::new(0);
+
+    fn flaky(_: &Connection) -> Result<(), String> {
+        match FLAKY_RUNS.fetch_add(1, Ordering::SeqCst) {
+            0 => Err("disk full".to_string()),
+            _ => Ok(()),
+        }
+    }
+
+    #[test]
+    fn retries_failed_migrations_and_skips_succeeded_ones() {
+        let conn = Connection::open_in_memory().unwrap();
+        let migrations = [Migration {
+            id: "flaky",
+            version: "0.7.1",
+            run: flaky,
+        }];
+
+        let failures = run_inner(&conn, &migrations, "0.7.1").unwrap();
+        assert_eq!(
+            failures,
+            [Failure {
+                id: "flaky",
+                error: "disk full".to_string()
+            }]
+        );
+        assert!(run_inner(&conn, &migrations, "0.7.2").unwrap().is_empty());
+        assert!(run_inner(&conn, &migrations, "0.7.2").unwrap().is_empty());
+        assert_eq!(FLAKY_RUNS.load(Ordering::SeqCst), 2);
+
+        let (attempts, error, version): (u32, Option<String>, String) = conn
+            .query_row(
+                "SELECT attempts, last_error, app_version FROM app_migrations WHERE id = 'flaky'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!((attempts, error, version.as_str()), (2, None, "0.7.2"));
+    }
+
+    #[test]
+    fn registry_is_ordered_by_release() {
+        let parse = |v: &str| -> Vec<u32> { v.split('.').map(|n| n.parse().unwrap()).collect() };
+        for pair in MIGRATIONS.windows(2) {
+            assert!(parse(pair[0].version) <= parse(pair[1].version));
+            assert_ne!(pair[0].id, pair[1].id);
+        }
+    }
+}
@@ -1,16 +1,20 @@
+pub mod backup;
 pub mod database;
+pub mod migrations;
 pub mod models;
+pub mod transcript_store;
 
 #[cfg(test)]
 mod database_test;
 
 pub use database::{drain_migration_notices, Database, EmbeddingRow};
-pub use models::{Course, Lecture, Note, Setting, Subtitle};
+pub use models::{
+    Course, Lecture, Note, NoteConflict, Setting, SlideAlignment, Subtitle, Tombstone,
+};
 
 use rusqlite::Result as SqlResult;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tauri::Manager;
 use tokio::sync::Mutex;
 
 /// 數據庫路徑管理器
@@ -22,17 +26,17 @@ pub struct DatabaseManager {
 
 impl DatabaseManager {
     /// 初始化數據庫管理器
-    pub fn new(app: &tauri::AppHandle) -> SqlResult<Self> {
-        let app_data_dir = app
-            .path()
-            .app_data_dir()
-            .map_err(|e| rusqlite::Error::InvalidPath(PathBuf::from(e.to_string())))?;
+    pub fn new(_app: &tauri::AppHandle) -> SqlResult<Self> {
+        // Resolved through `paths` (not Tauri's app_data_dir) so a
+        // relocated data directory is picked up.
+        let db_path = crate::paths::get_database_path()
+            .map_err(|e| rusqlite::Error::InvalidPath(PathBuf::from(e)))?;
 
         // 確保目錄存在
-        std::fs::create_dir_all(&app_data_dir)
-            .map_err(|e| rusqlite::Error::InvalidPath(PathBuf::from(e.to_string())))?;
-
-        let db_path = app_data_dir.join("classnoteai.db");
+        if let Some(dir) = db_path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| rusqlite::Error::InvalidPath(PathBuf::from(e.to_string())))?;
+        }
 
         // 初始化數據庫表結構
         let db = Database::new(&db_path)?;
@@ -53,6 +57,14 @@ impl DatabaseManager {
 /// 全局數據庫管理器實例
 static DB_MANAGER: Mutex<Option<DatabaseManager>> = Mutex::const_new(None);
 
+/// Run the pending post-update migrations. Called from the setup hook
+/// before anything that reads app data (models included) starts.
+pub fn run_migrations(app: &tauri::AppHandle) -> SqlResult<()> {
+    let manager = DatabaseManager::new(app)?;
+    migrations::run_pending(&manager.db_path);
+    Ok(())
+}
+
 /// 初始化數據庫管理器
 pub async fn init_db(app: &tauri::AppHandle) -> SqlResult<()> {
     let manager = DatabaseManager::new(app)?;
@@ -288,6 +288,88 @@ impl TryFrom<&Row<'_>> for Note {
     }
 }
 
+/// A copy of a note kept from a sync conflict, so neither side's edits
+/// are lost. See `sync::conflicts`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NoteConflict {
+    pub id: String,
+    pub lecture_id: String,
+    /// Whose copy this is: `local` or `server`.
+    pub side: String,
+    pub title: String,
+    pub content: String,
+    pub generated_at: String,
+    pub conflicted_at: String,
+    /// The server the conflict was with.
+    pub server_url: String,
+    /// False while the user still has to choose which copy to keep.
+    pub resolved: bool,
+}
+
+impl TryFrom<&Row<'_>> for NoteConflict {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row<'_>) -> Result<Self, Self::Error> {
+        Ok(NoteConflict {
+            id: row.get(0)?,
+            lecture_id: row.get(1)?,
+            side: row.get(2)?,
+            title: row.get(3)?,
+            content: row.get(4)?,
+            generated_at: row.get(5)?,
+            conflicted_at: row.get(6)?,
+            server_url: row.get(7)?,
+            resolved: row.get(8)?,
+        })
+    }
+}
+
+/// A course or lecture deleted for good, remembered so a push can tell
+/// the server and a sync can't bring it back. See `sync::push`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tombstone {
+    /// `courses` or `lectures`.
+    pub entity: String,
+    pub id: String,
+    pub purged_at: String,
+}
+
+impl TryFrom<&Row<'_>> for Tombstone {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row<'_>) -> Result<Self, Self::Error> {
+        Ok(Tombstone {
+            entity: row.get(0)?,
+            id: row.get(1)?,
+            purged_at: row.get(2)?,
+        })
+    }
+}
+
+/// One stretch of a lecture's recording during which `page_number`
+/// (1-based) of its PDF was the slide being discussed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlideAlignment {
+    pub page_number: i64,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    /// Similarity score of the match, 0..1; `None` when set by hand.
+    pub confidence: Option<f64>,
+}
+
+impl TryFrom<&Row<'_>> for SlideAlignment {
+    type Error = rusqlite::Error;
+
+    fn try_from(row: &Row<'_>) -> Result<Self, Self::Error> {
+        Ok(SlideAlignment {
+            page_number: row.get(0)?,
+            start_ms: row.get(1)?,
+            end_ms: row.get(2)?,
+            confidence: row.get(3)?,
+        })
+    }
+}
+
 /// 設置項數據模型
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Setting {
@@ -0,0 +1,367 @@
+//! Externalized transcript storage.
+//!
+//! A two-hour lecture produces a few thousand subtitle rows, each
+//! carrying rough + fine text in two languages. After a semester that
+//! is most of the SQLite file, and every `VACUUM` / backup / page-cache
+//! miss on a slow classroom HDD pays for rows nobody is looking at.
+//!
+//! This module lets a lecture's subtitles be moved out of the
+//! `subtitles` table into a gzip-compressed JSON blob under
+//! `{app_data}/transcripts/`. The DB keeps a row in `transcript_blobs`
+//! (relative path + SHA-256 + sizes) and the blob is only read when the
+//! lecture is opened.
+//!
+//! Read contract: `load_subtitles_merged` returns blob rows merged with
+//! whatever still lives in the `subtitles` table, DB rows winning on id
+//! collision. That way edits / late fine-pass rows written after the
+//! externalize step are never shadowed by the older blob, and the next
+//! `externalize_lecture` folds them in.
+//!
+//! Pure helpers take a `&Path` for the transcripts dir (same `*_inner`
+//! convention as `recording`) so the round-trip is testable without the
+//! global paths module.
+
+use super::database::Database;
+use super::models::Subtitle;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// Pointer row persisted in `transcript_blobs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptPointer {
+    pub lecture_id: String,
+    /// File name relative to the transcripts dir. Relative so the app
+    /// data folder can be moved without rewriting every pointer.
+    pub path: String,
+    /// Lower-case hex SHA-256 of the compressed file bytes.
+    pub sha256: String,
+    /// Compressed size on disk.
+    pub byte_len: u64,
+    /// Uncompressed JSON size — lets the UI show "saved N MB".
+    pub raw_len: u64,
+    pub segment_count: u64,
+    pub externalized_at: String,
+}
+
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+    let digest = Sha256::digest(bytes);
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn blob_file_name(lecture_id: &str) -> String {
+    format!("{}.json.gz", lecture_id)
+}
+
+/// Serialize + gzip `subtitles` into `{dir}/{lecture_id}.json.gz`.
+///
+/// Written to a `.tmp` sibling first and renamed into place, so a crash
+/// mid-write leaves the previous blob (if any) intact rather than a
+/// truncated file whose checksum no longer matches its pointer.
+pub fn write_transcript_blob_inner(
+    dir: &Path,
+    lecture_id: &str,
+    subtitles: &[Subtitle],
+) -> Result<TranscriptPointer, String> {
+    validate_lecture_id(lecture_id)?;
+    fs::create_dir_all(dir).map_err(|e| format!("無法創建 transcripts 目錄: {}", e))?;
+
+    let json = serde_json::to_vec(subtitles).map_err(|e| format!("序列化字幕失敗: {}", e))?;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(&json)
+        .map_err(|e| format!("壓縮字幕失敗: {}", e))?;
+    let compressed = encoder.finish().map_err(|e| format!("壓縮字幕失敗: {}", e))?;
+
+    let name = blob_file_name(lecture_id);
+    let final_path = dir.join(&name);
+    let tmp_path = dir.join(format!("{}.tmp", name));
+    fs::write(&tmp_path, &compressed).map_err(|e| format!("寫入字幕檔失敗: {}", e))?;
+    fs::rename(&tmp_path, &final_path).map_err(|e| {
+        let _ = fs::remove_file(&tmp_path);
+        format!("寫入字幕檔失敗: {}", e)
+    })?;
+
+    Ok(TranscriptPointer {
+        lecture_id: lecture_id.to_string(),
+        path: name,
+        sha256: sha256_hex(&compressed),
+        byte_len: compressed.len() as u64,
+        raw_len: json.len() as u64,
+        segment_count: subtitles.len() as u64,
+        externalized_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+/// Read a blob back, refusing it if the checksum doesn't match the
+/// pointer. A mismatch means the file was truncated or swapped under
+/// us; returning garbage subtitles silently would be worse than an
+/// error the UI can surface.
+pub fn read_transcript_blob_inner(
+    dir: &Path,
+    pointer: &TranscriptPointer,
+) -> Result<Vec<Subtitle>, String> {
+    if pointer.path.contains('/') || pointer.path.contains('\\') || pointer.path.contains("..") {
+        return Err(format!("非法的字幕檔路徑: {}", pointer.path));
+    }
+    let path = dir.join(&pointer.path);
+    let compressed = fs::read(&path).map_err(|e| format!("讀取字幕檔失敗 {:?}: {}", path, e))?;
+    let actual = sha256_hex(&compressed);
+    if actual != pointer.sha256 {
+        return Err(format!(
+            "字幕檔校驗失敗 (lecture {}): expected {}, got {}",
+            pointer.lecture_id, pointer.sha256, actual
+        ));
+    }
+    let mut json = Vec::with_capacity(pointer.raw_len as usize);
+    GzDecoder::new(&compressed[..])
+        .read_to_end(&mut json)
+        .map_err(|e| format!("解壓字幕檔失敗: {}", e))?;
+    serde_json::from_slice(&json).map_err(|e| format!("解析字幕檔失敗: {}", e))
+}
+
+/// Merge blob rows with live DB rows. DB wins on id collision; result
+/// is ordered by timestamp like `Database::get_subtitles`.
+fn merge_subtitles(blob: Vec<Subtitle>, db_rows: Vec<Subtitle>) -> Vec<Subtitle> {
+    let mut by_id: HashMap<String, Subtitle> = HashMap::with_capacity(blob.len() + db_rows.len());
+    for s in blob {
+        by_id.insert(s.id.clone(), s);
+    }
+    for s in db_rows {
+        by_id.insert(s.id.clone(), s);
+    }
+    let mut out: Vec<Subtitle> = by_id.into_values().collect();
+    out.sort_by(|a, b| {
+        a.timestamp
+            .partial_cmp(&b.timestamp)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    out
+}
+
+/// Lazy read path used by `get_subtitles`: only touches the blob when
+/// the lecture actually has a pointer.
+pub fn load_subtitles_merged(
+    db: &Database,
+    dir: &Path,
+    lecture_id: &str,
+) -> Result<Vec<Subtitle>, String> {
+    let db_rows = db
+        .get_subtitles(lecture_id)
+        .map_err(|e| format!("獲取字幕失敗: {}", e))?;
+    let pointer = db
+        .get_transcript_pointer(lecture_id)
+        .map_err(|e| format!("讀取字幕指標失敗: {}", e))?;
+    match pointer {
+        None => Ok(db_rows),
+        Some(p) => {
+            let blob = read_transcript_blob_inner(dir, &p)?;
+            Ok(merge_subtitles(blob, db_rows))
+        }
+    }
+}
+
+/// Move every subtitle of `lecture_id` out of SQLite into a blob.
+///
+/// Order matters: the blob is written and verified on disk BEFORE the
+/// pointer + row deletion commit, so a failure at any point leaves the
+/// rows in the DB. Re-running on an already-externalized lecture folds
+/// any rows written since into a fresh blob.
+pub fn externalize_lecture(
+    db: &Database,
+    dir: &Path,
+    lecture_id: &str,
+) -> Result<TranscriptPointer, String> {
+    let merged = load_subtitles_merged(db, dir, lecture_id)?;
+    let pointer = write_transcript_blob_inner(dir, lecture_id, &merged)?;
+    // Read-back check: cheap relative to the I/O we just did, and it is
+    // the last moment the rows still exist in SQLite.
+    read_transcript_blob_inner(dir, &pointer)?;
+    db.commit_transcript_pointer(&pointer)
+        .map_err(|e| format!("保存字幕指標失敗: {}", e))?;
+    Ok(pointer)
+}
+
+/// Inverse of `externalize_lecture`: write the blob rows back into
+/// `subtitles`, drop the pointer, delete the file.
+pub fn internalize_lecture(db: &Database, dir: &Path, lecture_id: &str) -> Result<usize, String> {
+    let Some(pointer) = db
+        .get_transcript_pointer(lecture_id)
+        .map_err(|e| format!("讀取字幕指標失敗: {}", e))?
+    else {
+        return Ok(0);
+    };
+    let merged = load_subtitles_merged(db, dir, lecture_id)?;
+    db.restore_externalized_subtitles(lecture_id, &merged)
+        .map_err(|e| format!("還原字幕失敗: {}", e))?;
+    let _ = fs::remove_file(dir.join(&pointer.path));
+    Ok(merged.len())
+}
+
+/// Delete one subtitle of `lecture_id`. On an externalized lecture the
+/// row is (also) in the blob, where the next merged read would bring it
+/// back, so the blob is rewritten without it; like
+/// `externalize_lecture`, that folds the table rows in.
+pub fn delete_subtitle(
+    db: &Database,
+    dir: &Path,
+    lecture_id: &str,
+    subtitle_id: &str,
+) -> Result<(), String> {
+    let pointer = db
+        .get_transcript_pointer(lecture_id)
+        .map_err(|e| format!("讀取字幕指標失敗: {}", e))?;
+    if pointer.is_none() {
+        return db
+            .delete_subtitle_by_id(subtitle_id)
+            .map_err(|e| format!("刪除字幕失敗: {}", e));
+    }
+    let mut merged = load_subtitles_merged(db, dir, lecture_id)?;
+    let before = merged.len();
+    merged.retain(|s| s.id != subtitle_id);
+    if merged.len() == before {
+        return Ok(());
+    }
+    let pointer = write_transcript_blob_inner(dir, lecture_id, &merged)?;
+    read_transcript_blob_inner(dir, &pointer)?;
+    db.commit_transcript_pointer(&pointer)
+        .map_err(|e| format!("保存字幕指標失敗: {}", e))
+}
+
+/// Best-effort cleanup when a lecture is purged. The pointer row goes
+/// away with the FK cascade; the file does not.
+pub fn remove_blob_file(dir: &Path, lecture_id: &str) {
+    if validate_lecture_id(lecture_id).is_ok() {
+        let _ = fs::remove_file(dir.join(blob_file_name(lecture_id)));
+    }
+}
+
+fn validate_lecture_id(lecture_id: &str) -> Result<(), String> {
+    let ok = !lecture_id.is_empty()
+        && lecture_id.len() <= 128
+        && lecture_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if ok {
+        Ok(())
+    } else {
+        Err(format!("非法的 lecture_id: {:?}", lecture_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::database_test::{make_test_db, seed_minimal};
+    use tempfile::TempDir;
+
+    fn sub(id: &str, ts: f64, text: &str) -> Subtitle {
+        let mut s = Subtitle::new(
+            "l1".to_string(),
+            ts,
+            text.to_string(),
+            None,
+            "rough".to_string(),
+            None,
+        );
+        s.id = id.to_string();
+        s
+    }
+
+    #[test]
+    fn blob_round_trips_and_verifies_checksum() {
+        let tmp = TempDir::new().unwrap();
+        let subs = vec![sub("a", 1.0, "hello"), sub("b", 2.0, "world")];
+        let p = write_transcript_blob_inner(tmp.path(), "l1", &subs).unwrap();
+        assert_eq!(p.segment_count, 2);
+        assert_eq!(p.path, "l1.json.gz");
+
+        let back = read_transcript_blob_inner(tmp.path(), &p).unwrap();
+        assert_eq!(back.len(), 2);
+        assert_eq!(back[1].text_en, "world");
+    }
+
+    #[test]
+    fn tampered_blob_is_rejected() {
+        let tmp = TempDir::new().unwrap();
+        let p = write_transcript_blob_inner(tmp.path(), "l1", &[sub("a", 1.0, "x")]).unwrap();
+        fs::write(tmp.path().join(&p.path), b"not gzip").unwrap();
+        assert!(read_transcript_blob_inner(tmp.path(), &p).is_err());
+    }
+
+    #[test]
+    fn externalize_moves_rows_out_and_lazy_read_merges_new_rows() {
+        let tmp = TempDir::new().unwrap();
+        let db = make_test_db();
+        seed_minimal(&db);
+        db.save_subtitles(&[sub("a", 1.0, "one"), sub("b", 2.0, "two")])
+            .unwrap();
+
+        let p = externalize_lecture(&db, tmp.path(), "l1").unwrap();
+        assert_eq!(p.segment_count, 2);
+        assert!(db.get_subtitles("l1").unwrap().is_empty());
+
+        // A late edit lands in SQLite and must win over the blob copy.
+        db.save_subtitle(&sub("b", 2.0, "two (edited)")).unwrap();
+        db.save_subtitle(&sub("c", 3.0, "three")).unwrap();
+        let merged = load_subtitles_merged(&db, tmp.path(), "l1").unwrap();
+        let texts: Vec<&str> = merged.iter().map(|s| s.text_en.as_str()).collect();
+        assert_eq!(texts, vec!["one", "two (edited)", "three"]);
+    }
+
+    #[test]
+    fn internalize_restores_rows_and_drops_pointer() {
+        let tmp = TempDir::new().unwrap();
+        let db = make_test_db();
+        seed_minimal(&db);
+        db.save_subtitles(&[sub("a", 1.0, "one")]).unwrap();
+        externalize_lecture(&db, tmp.path(), "l1").unwrap();
+
+        assert_eq!(internalize_lecture(&db, tmp.path(), "l1").unwrap(), 1);
+        assert_eq!(db.get_subtitles("l1").unwrap().len(), 1);
+        assert!(db.get_transcript_pointer("l1").unwrap().is_none());
+        assert!(!tmp.path().join("l1.json.gz").exists());
+    }
+
+    #[test]
+    fn delete_reaches_rows_in_the_blob() {
+        let tmp = TempDir::new().unwrap();
+        let db = make_test_db();
+        seed_minimal(&db);
+        db.save_subtitles(&[sub("a", 1.0, "one"), sub("b", 2.0, "two")])
+            .unwrap();
+        externalize_lecture(&db, tmp.path(), "l1").unwrap();
+        db.save_subtitle(&sub("c", 3.0, "three")).unwrap();
+
+        delete_subtitle(&db, tmp.path(), "l1", "a").unwrap();
+        let merged = load_subtitles_merged(&db, tmp.path(), "l1").unwrap();
+        let ids: Vec<&str> = merged.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, vec!["b", "c"]);
+        let pointer = db.get_transcript_pointer("l1").unwrap().unwrap();
+        assert_eq!(pointer.segment_count, 2);
+
+        // Deleting what is gone is still fine.
+        delete_subtitle(&db, tmp.path(), "l1", "a").unwrap();
+    }
+
+    #[test]
+    fn pointer_path_with_separators_is_refused() {
+        let tmp = TempDir::new().unwrap();
+        let p = TranscriptPointer {
+            lecture_id: "l1".into(),
+            path: "../escape.json.gz".into(),
+            sha256: String::new(),
+            byte_len: 0,
+            raw_len: 0,
+            segment_count: 0,
+            externalized_at: String::new(),
+        };
+        assert!(read_transcript_blob_inner(tmp.path(), &p).is_err());
+    }
+}
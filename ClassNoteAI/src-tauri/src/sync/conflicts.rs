@@ -0,0 +1,519 @@
+//! Note conflicts found by a push.
+//!
+//! The server answers a push of notes with the ones it didn't take
+//! because its own copy changed since this client last pushed. What
+//! happens then is the user's [`ConflictPolicy`], saved per user as
+//! [`POLICY_SETTING`]:
+//!
+//! - `lww`: the copy written last wins.
+//! - `prefer_local`: this device's copy wins and overwrites the server's.
+//! - `ask`: nothing changes until the user picks one ([`resolve`]).
+//!
+//! Either way the other copy is kept in `note_conflicts` with the time of
+//! the conflict, so a note is never silently overwritten. Notes of a
+//! course the user left out of sync, or of a lecture trashed or purged
+//! here (see `sync_tombstones`), are ignored, and a server copy is never
+//! swapped in for one.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::{
+    client, crypto, offline, outbox, rejected_unless_ok, report, selection, with_db, Failure,
+};
+use crate::storage::{Database, Note, NoteConflict};
+
+pub const POLICY_SETTING: &str = "sync.conflict_policy";
+/// Outbox action that overwrites the server's copy of a note with ours,
+/// queued when the user keeps the local copy.
+pub const KEEP_LOCAL_ACTION: &str = "NOTE_KEEP_LOCAL";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    #[default]
+    Lww,
+    PreferLocal,
+    Ask,
+}
+
+impl ConflictPolicy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "lww" => Some(Self::Lww),
+            "prefer_local" => Some(Self::PreferLocal),
+            "ask" => Some(Self::Ask),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Lww => "lww",
+            Self::PreferLocal => "prefer_local",
+            Self::Ask => "ask",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    KeepServer,
+    KeepLocal,
+    Ask,
+}
+
+/// `NOTE_KEEP_LOCAL` payload.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct KeepLocal {
+    server_url: String,
+    username: String,
+    lecture_id: String,
+}
+
+pub async fn policy(user_id: &str) -> Result<ConflictPolicy, String> {
+    let user = user_id.to_string();
+    let value = with_db(move |db| db.get_setting(POLICY_SETTING, &user)).await?;
+    Ok(value
+        .as_deref()
+        .and_then(ConflictPolicy::parse)
+        .unwrap_or_default())
+}
+
+pub async fn set_policy(user_id: &str, policy: ConflictPolicy) -> Result<(), String> {
+    let user = user_id.to_string();
+    with_db(move |db| db.save_setting(POLICY_SETTING, policy.as_str(), &user)).await
+}
+
+/// Settle the notes the server pushed back from `server`, as it sent
+/// them: still encrypted if the user turned that on.
+pub(super) async fn handle(
+    client: &reqwest::Client,
+    server: &str,
+    username: &str,
+    key: Option<&crypto::SyncKey>,
+    rows: Vec<serde_json::Value>,
+) -> Result<(), Failure> {
+    let policy = policy(username).await.map_err(Failure::Rejected)?;
+    let selection = selection::get(username).await.map_err(Failure::Rejected)?;
+    if !selection.notes {
+        return Ok(());
+    }
+    for row in rows {
+        let remote = remote_note(key, row).map_err(Failure::Rejected)?;
+        let (sel, note) = (selection.clone(), remote.clone());
+        let local = match with_db(move |db| classify(db, &sel, &note))
+            .await
+            .map_err(Failure::Rejected)?
+        {
+            Incoming::Skip | Incoming::Same => continue,
+            Incoming::New => {
+                with_db(move |db| db.save_note(&remote))
+                    .await
+                    .map_err(Failure::Rejected)?;
+                continue;
+            }
+            Incoming::Differs(local) => local,
+        };
+
+        let outcome = decide(policy, &local, &remote);
+        report::update(|r| r.conflicts += 1);
+        println!(
+            "[sync] Note conflict on lecture {}: {:?}",
+            local.lecture_id, outcome
+        );
+        match outcome {
+            Outcome::KeepServer => {
+                let copy = conflicted_copy(&local, "local", server, true);
+                with_db(move |db| {
+                    db.save_note_conflict(&copy)?;
+                    db.save_note(&remote)
+                })
+                .await
+                .map_err(Failure::Rejected)?;
+            }
+            Outcome::KeepLocal => {
+                let copy = conflicted_copy(&remote, "server", server, true);
+                with_db(move |db| db.save_note_conflict(&copy))
+                    .await
+                    .map_err(Failure::Rejected)?;
+                overwrite_server(client, server, username, key, &local).await?;
+            }
+            Outcome::Ask => {
+                let copy = conflicted_copy(&remote, "server", server, false);
+                with_db(move |db| db.save_note_conflict(&copy))
+                    .await
+                    .map_err(Failure::Rejected)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Settle a conflict the user was asked about. Keeping the server's copy
+/// swaps it in locally (ours becomes the conflicted copy); keeping ours
+/// queues it to overwrite the server's.
+pub async fn resolve(conflict_id: &str, keep_local: bool, user_id: &str) -> Result<(), String> {
+    let (id, user) = (conflict_id.to_string(), user_id.to_string());
+    with_db(move |db| {
+        let Some(conflict) = db.get_note_conflict(&id)? else {
+            return Ok(Err("找不到此衝突".to_string()));
+        };
+        if db.find_lecture_owner(&conflict.lecture_id).as_deref() != Some(user.as_str()) {
+            return Ok(Err("無權處理此衝突".to_string()));
+        }
+        if keep_local {
+            let payload = serde_json::to_string(&KeepLocal {
+                server_url: conflict.server_url.clone(),
+                username: user.clone(),
+                lecture_id: conflict.lecture_id.clone(),
+            })
+            .unwrap_or_default();
+            let action_id = uuid::Uuid::new_v4().to_string();
+            db.add_pending_action(&action_id, KEEP_LOCAL_ACTION, &payload)?;
+        } else {
+            // The lecture may have been trashed or purged since the
+            // conflict was recorded; the server's copy must not bring
+            // its note back.
+            if !accepts_remote(db, &conflict.lecture_id)? {
+                return Ok(Err("此課堂已刪除，無法套用伺服器版本".to_string()));
+            }
+            if let Some(local) = db.get_note(&conflict.lecture_id)? {
+                db.save_note_conflict(&conflicted_copy(
+                    &local,
+                    "local",
+                    &conflict.server_url,
+                    true,
+                ))?;
+            }
+            db.save_note(&Note {
+                lecture_id: conflict.lecture_id.clone(),
+                title: conflict.title.clone(),
+                content: conflict.content.clone(),
+                generated_at: conflict.generated_at.clone(),
+                is_deleted: false,
+            })?;
+        }
+        db.set_note_conflict_resolved(&conflict.id)?;
+        Ok(Ok(()))
+    })
+    .await??;
+    if keep_local {
+        outbox::wake();
+    }
+    Ok(())
+}
+
+/// Run a queued `NOTE_KEEP_LOCAL`.
+pub(super) async fn keep_local(payload: &str) -> Result<(), Failure> {
+    let request: KeepLocal = serde_json::from_str(payload)
+        .map_err(|e| Failure::Rejected(format!("無效的動作內容: {}", e)))?;
+    let id = request.lecture_id.clone();
+    let local = with_db(move |db| db.get_note(&id))
+        .await
+        .map_err(Failure::Rejected)?
+        .ok_or_else(|| Failure::Rejected("找不到此筆記".to_string()))?;
+    let key = crypto::key_for(&request.username)
+        .await
+        .map_err(Failure::Rejected)?;
+    let server = request.server_url.trim_end_matches('/');
+    overwrite_server(
+        &client(REQUEST_TIMEOUT, server, &request.username)?,
+        server,
+        &request.username,
+        key.as_ref(),
+        &local,
+    )
+    .await
+}
+
+/// A note row from the server, decrypted. Nothing is compared with or
+/// saved over a local note before this.
+fn remote_note(key: Option<&crypto::SyncKey>, mut row: serde_json::Value) -> Result<Note, String> {
+    crypto::open_remote_row(key, "notes", &mut row)?;
+    serde_json::from_value(row).map_err(|e| format!("無效的伺服器筆記: {}", e))
+}
+
+/// Where a note the server pushed back stands against this device.
+#[derive(Debug)]
+enum Incoming {
+    /// Its lecture is missing, trashed or purged here, its course is
+    /// left out of sync, or the note itself is in the trash. Saving it
+    /// would break the lecture foreign key or bring trash back.
+    Skip,
+    /// Nothing here to conflict with.
+    New,
+    Same,
+    Differs(Note),
+}
+
+fn classify(
+    db: &Database,
+    selection: &selection::SyncSelection,
+    remote: &Note,
+) -> rusqlite::Result<Incoming> {
+    let id = remote.lecture_id.as_str();
+    if !accepts_remote(db, id)? {
+        return Ok(Incoming::Skip);
+    }
+    // `get_lecture` leaves out trashed lectures.
+    let Some(lecture) = db.get_lecture(id)? else {
+        return Ok(Incoming::Skip);
+    };
+    if selection.excludes_course(&lecture.course_id) {
+        return Ok(Incoming::Skip);
+    }
+    Ok(match db.get_note_including_trashed(id)? {
+        None => Incoming::New,
+        Some(local) if local.is_deleted => Incoming::Skip,
+        Some(local) if local.title == remote.title && local.content == remote.content => {
+            Incoming::Same
+        }
+        Some(local) => Incoming::Differs(local),
+    })
+}
+
+/// Whether a server copy of lecture `id`'s note may be written here: the
+/// lecture is live and neither it nor its course was purged. Notes have
+/// no tombstones of their own; they go with their lecture.
+fn accepts_remote(db: &Database, id: &str) -> rusqlite::Result<bool> {
+    if db.is_tombstoned("lectures", id)? {
+        return Ok(false);
+    }
+    Ok(match db.get_lecture(id)? {
+        Some(lecture) => !db.is_tombstoned("courses", &lecture.course_id)?,
+        None => false,
+    })
+}
+
+fn decide(policy: ConflictPolicy, local: &Note, remote: &Note) -> Outcome {
+    match policy {
+        ConflictPolicy::Lww if newer(&remote.generated_at, &local.generated_at) => {
+            Outcome::KeepServer
+        }
+        ConflictPolicy::Lww | ConflictPolicy::PreferLocal => Outcome::KeepLocal,
+        ConflictPolicy::Ask => Outcome::Ask,
+    }
+}
+
+/// Whether RFC 3339 time `a` is after `b`.
+fn newer(a: &str, b: &str) -> bool {
+    match (
+        chrono::DateTime::parse_from_rfc3339(a),
+        chrono::DateTime::parse_from_rfc3339(b),
+    ) {
+        (Ok(a), Ok(b)) => a > b,
+        _ => a > b,
+    }
+}
+
+fn conflicted_copy(note: &Note, side: &str, server: &str, resolved: bool) -> NoteConflict {
+    NoteConflict {
+        id: uuid::Uuid::new_v4().to_string(),
+        lecture_id: note.lecture_id.clone(),
+        side: side.to_string(),
+        title: note.title.clone(),
+        content: note.content.clone(),
+        generated_at: note.generated_at.clone(),
+        conflicted_at: chrono::Utc::now().to_rfc3339(),
+        server_url: server.to_string(),
+        resolved,
+    }
+}
+
+/// Push `note` with `force`, which has the server take it over its own.
+async fn overwrite_server(
+    client: &reqwest::Client,
+    server: &str,
+    username: &str,
+    key: Option<&crypto::SyncKey>,
+    note: &Note,
+) -> Result<(), Failure> {
+    let mut row = serde_json::to_value(note).map_err(|e| Failure::Rejected(e.to_string()))?;
+    if let Some(key) = key {
+        crypto::seal_row(key, "notes", &mut row).map_err(Failure::Rejected)?;
+    }
+    let response = client
+        .post(format!("{}/api/sync/push", server))
+        .json(&serde_json::json!({
+            "username": username,
+            "entity": "notes",
+            "rows": [row],
+            "force": true,
+        }))
+        .send()
+        .await
+        .map_err(offline)?;
+    rejected_unless_ok(response).await.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{Course, Lecture};
+
+    fn note(generated_at: &str) -> Note {
+        Note {
+            lecture_id: "lec-1".to_string(),
+            title: "Notes".to_string(),
+            content: generated_at.to_string(),
+            generated_at: generated_at.to_string(),
+            is_deleted: false,
+        }
+    }
+
+    /// A live lecture `lec-1` and a trashed `lec-2`, neither with a note.
+    fn db_with_lectures() -> Database {
+        let db = Database::open_in_memory().unwrap();
+        let course = Course::new(
+            "test_user".to_string(),
+            "Course".to_string(),
+            None,
+            None,
+            None,
+        );
+        db.save_course(&course).unwrap();
+        for id in ["lec-1", "lec-2"] {
+            let mut lecture = Lecture::new(course.id.clone(), id.to_string(), None);
+            lecture.id = id.to_string();
+            db.save_lecture(&lecture, "test_user").unwrap();
+        }
+        db.delete_lecture("lec-2").unwrap();
+        db
+    }
+
+    #[test]
+    fn notes_without_a_live_lecture_are_skipped() {
+        let db = db_with_lectures();
+        let selection = selection::SyncSelection::default();
+        let mut remote = note("2026-03-01T09:30:00Z");
+
+        assert!(matches!(
+            classify(&db, &selection, &remote).unwrap(),
+            Incoming::New
+        ));
+        for gone in ["lec-2", "lec-missing"] {
+            remote.lecture_id = gone.to_string();
+            assert!(matches!(
+                classify(&db, &selection, &remote).unwrap(),
+                Incoming::Skip
+            ));
+        }
+    }
+
+    #[test]
+    fn notes_of_purged_lectures_are_rejected() {
+        let db = db_with_lectures();
+        let selection = selection::SyncSelection::default();
+        let mut remote = note("2026-03-01T09:30:00Z");
+        remote.lecture_id = "lec-2".to_string();
+        let course_id = db.get_lecture("lec-1").unwrap().unwrap().course_id;
+        db.purge_lecture("lec-2").unwrap();
+
+        // Even once a lecture with that id is back, e.g. from a restored
+        // backup, the purge stands.
+        let mut lecture = Lecture::new(course_id, "lec-2".to_string(), None);
+        lecture.id = "lec-2".to_string();
+        db.save_lecture(&lecture, "test_user").unwrap();
+        assert!(matches!(
+            classify(&db, &selection, &remote).unwrap(),
+            Incoming::Skip
+        ));
+        assert!(!accepts_remote(&db, "lec-2").unwrap());
+        assert!(accepts_remote(&db, "lec-1").unwrap());
+    }
+
+    #[test]
+    fn a_trashed_note_is_not_brought_back() {
+        let db = db_with_lectures();
+        let selection = selection::SyncSelection::default();
+        let mut local = note("2026-03-01T09:00:00Z");
+        local.is_deleted = true;
+        db.save_note(&local).unwrap();
+
+        let remote = note("2026-03-01T09:30:00Z");
+        assert!(matches!(
+            classify(&db, &selection, &remote).unwrap(),
+            Incoming::Skip
+        ));
+
+        local.is_deleted = false;
+        db.save_note(&local).unwrap();
+        assert!(matches!(
+            classify(&db, &selection, &remote).unwrap(),
+            Incoming::Differs(note) if note.content == local.content
+        ));
+    }
+
+    #[test]
+    fn encrypted_server_copies_are_compared_and_saved_in_plain_text() {
+        let db = db_with_lectures();
+        let selection = selection::SyncSelection::default();
+        let local = note("2026-03-01T09:00:00Z");
+        db.save_note(&local).unwrap();
+
+        let key = crypto::derive_key("correct horse battery", "test_user").unwrap();
+        let mut row = serde_json::to_value(&local).unwrap();
+        crypto::seal_row(&key, "notes", &mut row).unwrap();
+
+        // Without the key the row can't be read, so it isn't applied.
+        assert!(remote_note(None, row.clone()).is_err());
+
+        let remote = remote_note(Some(&key), row).unwrap();
+        assert_eq!(
+            (remote.title.as_str(), remote.content.as_str()),
+            ("Notes", "2026-03-01T09:00:00Z")
+        );
+        assert!(matches!(
+            classify(&db, &selection, &remote).unwrap(),
+            Incoming::Same
+        ));
+
+        // A new note is saved as the text it decrypts to.
+        let mut row = serde_json::to_value(&remote).unwrap();
+        row["content"] = serde_json::json!("server copy");
+        crypto::seal_row(&key, "notes", &mut row).unwrap();
+        let remote = remote_note(Some(&key), row).unwrap();
+        db.save_note(&remote).unwrap();
+        assert_eq!(
+            db.get_note("lec-1").unwrap().unwrap().content,
+            "server copy"
+        );
+
+        // Plain rows, from before encryption was turned on, still apply.
+        let plain = serde_json::to_value(&local).unwrap();
+        assert_eq!(remote_note(None, plain).unwrap().content, local.content);
+    }
+
+    #[test]
+    fn each_policy_picks_its_side() {
+        // Different offsets; the server's copy is half an hour newer.
+        let local = note("2026-03-01T17:00:00+08:00");
+        let remote = note("2026-03-01T09:30:00Z");
+
+        assert_eq!(
+            decide(ConflictPolicy::Lww, &local, &remote),
+            Outcome::KeepServer
+        );
+        assert_eq!(
+            decide(ConflictPolicy::Lww, &remote, &local),
+            Outcome::KeepLocal
+        );
+        assert_eq!(
+            decide(ConflictPolicy::PreferLocal, &local, &remote),
+            Outcome::KeepLocal
+        );
+        assert_eq!(decide(ConflictPolicy::Ask, &local, &remote), Outcome::Ask);
+
+        for policy in [
+            ConflictPolicy::Lww,
+            ConflictPolicy::PreferLocal,
+            ConflictPolicy::Ask,
+        ] {
+            assert_eq!(ConflictPolicy::parse(policy.as_str()), Some(policy));
+        }
+        assert_eq!(ConflictPolicy::parse("newest"), None);
+    }
+}
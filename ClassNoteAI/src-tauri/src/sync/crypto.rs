@@ -0,0 +1,302 @@
+//! Optional end-to-end encryption of synced content.
+//!
+//! With it on, the title and content of notes are encrypted before they
+//! leave the device, so whoever runs the ClassNoteServer only sees ids,
+//! timestamps and how rows link up, which is what it needs to sync.
+//! Notes are the only text pushed; subtitles and chat stay on the device.
+//! The server's copies only come back as push conflicts, which
+//! [`super::conflicts`] decrypts with [`open_remote_row`] before they are
+//! compared or saved. The key is derived from a passphrase with
+//! Argon2id, salted with the username so every device of the user gets
+//! the same key from the same passphrase, and kept in the OS keychain;
+//! the passphrase itself is never stored.
+//!
+//! An encrypted field is `cnenc1:{key id}:{base64(nonce | AES-256-GCM
+//! ciphertext)}`, with the entity and row id as associated data so a
+//! field can't be moved to another row. The key id tells a wrong
+//! passphrase apart from damaged data.
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use super::with_db;
+
+pub const ENABLED_SETTING: &str = "sync.e2e_enabled";
+const KEYRING_SERVICE: &str = "ClassNoteAI";
+const PREFIX: &str = "cnenc1:";
+const NONCE_LEN: usize = 12;
+
+/// The fields encrypted in each entity's rows. Everything else stays
+/// readable to the server.
+pub const ENCRYPTED_FIELDS: &[(&str, &[&str])] = &[("notes", &["title", "content"])];
+
+#[derive(Clone)]
+pub struct SyncKey([u8; 32]);
+
+impl SyncKey {
+    /// Short fingerprint stored with every field.
+    fn id(&self) -> String {
+        Sha256::digest(self.0)[..4]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EncryptionStatus {
+    pub enabled: bool,
+    /// Whether this device has the key; false on a new device until the
+    /// passphrase is entered.
+    pub has_key: bool,
+}
+
+/// Derive the key for `username` from `passphrase`. Slow on purpose.
+pub fn derive_key(passphrase: &str, username: &str) -> Result<SyncKey, String> {
+    let salt = Sha256::digest(format!("classnote-sync:{}", username).as_bytes());
+    let mut key = [0u8; 32];
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+        .map_err(|e| format!("金鑰推導失敗: {}", e))?;
+    Ok(SyncKey(key))
+}
+
+/// Encrypt `value`, bound to `aad`.
+pub fn seal(key: &SyncKey, value: &str, aad: &str) -> Result<String, String> {
+    let cipher = Aes256Gcm::new_from_slice(&key.0).map_err(|e| e.to_string())?;
+    let nonce: [u8; NONCE_LEN] = rand::random();
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: value.as_bytes(),
+                aad: aad.as_bytes(),
+            },
+        )
+        .map_err(|_| "加密失敗".to_string())?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(format!(
+        "{}{}:{}",
+        PREFIX,
+        key.id(),
+        STANDARD.encode(sealed)
+    ))
+}
+
+/// Decrypt a [`seal`]ed value. Plain text, from before encryption was
+/// turned on, comes back as it is.
+pub fn open(key: &SyncKey, value: &str, aad: &str) -> Result<String, String> {
+    let Some(rest) = value.strip_prefix(PREFIX) else {
+        return Ok(value.to_string());
+    };
+    let (key_id, data) = rest
+        .split_once(':')
+        .ok_or_else(|| "加密內容格式錯誤".to_string())?;
+    if key_id != key.id() {
+        return Err("此內容以不同的同步加密密碼加密".to_string());
+    }
+    let sealed = STANDARD
+        .decode(data)
+        .map_err(|_| "加密內容格式錯誤".to_string())?;
+    if sealed.len() < NONCE_LEN {
+        return Err("加密內容格式錯誤".to_string());
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new_from_slice(&key.0).map_err(|e| e.to_string())?;
+    let plain = cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: aad.as_bytes(),
+            },
+        )
+        .map_err(|_| "解密失敗：內容已損毀".to_string())?;
+    String::from_utf8(plain).map_err(|_| "解密失敗：內容不是文字".to_string())
+}
+
+/// Encrypt the [`ENCRYPTED_FIELDS`] of a row of `entity` before a push.
+pub fn seal_row(key: &SyncKey, entity: &str, row: &mut serde_json::Value) -> Result<(), String> {
+    map_fields(entity, row, |value, aad| seal(key, value, aad))
+}
+
+/// Decrypt the [`ENCRYPTED_FIELDS`] of a row of `entity` the server sent
+/// back as a conflict.
+pub fn open_row(key: &SyncKey, entity: &str, row: &mut serde_json::Value) -> Result<(), String> {
+    map_fields(entity, row, |value, aad| open(key, value, aad))
+}
+
+/// [`open_row`] for a row the server sent, whether or not this device
+/// has the key. Without it a sealed field is an error rather than
+/// ciphertext saved as the note.
+pub fn open_remote_row(
+    key: Option<&SyncKey>,
+    entity: &str,
+    row: &mut serde_json::Value,
+) -> Result<(), String> {
+    match key {
+        Some(key) => open_row(key, entity, row),
+        None => map_fields(entity, row, |value, _| {
+            if value.starts_with(PREFIX) {
+                Err("伺服器上的內容已加密，請先在此裝置輸入同步加密密碼".to_string())
+            } else {
+                Ok(value.to_string())
+            }
+        }),
+    }
+}
+
+fn map_fields(
+    entity: &str,
+    row: &mut serde_json::Value,
+    f: impl Fn(&str, &str) -> Result<String, String>,
+) -> Result<(), String> {
+    let Some((_, fields)) = ENCRYPTED_FIELDS.iter().find(|(e, _)| *e == entity) else {
+        return Ok(());
+    };
+    // Notes are keyed by their lecture.
+    let id_field = if entity == "notes" {
+        "lecture_id"
+    } else {
+        "id"
+    };
+    let id = row
+        .get(id_field)
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+    let aad = format!("{}/{}", entity, id);
+    for field in *fields {
+        if let Some(slot) = row.get_mut(*field) {
+            if let Some(value) = slot.as_str() {
+                *slot = serde_json::Value::String(f(value, &aad)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+pub async fn status(username: &str) -> Result<EncryptionStatus, String> {
+    let user = username.to_string();
+    let enabled = with_db(move |db| db.get_setting(ENABLED_SETTING, &user))
+        .await?
+        .as_deref()
+        == Some("true");
+    Ok(EncryptionStatus {
+        enabled,
+        has_key: load_key(username)?.is_some(),
+    })
+}
+
+/// The key to push with: `None` when encryption is off, an error when it
+/// is on but this device doesn't have the key yet.
+pub async fn key_for(username: &str) -> Result<Option<SyncKey>, String> {
+    if !status(username).await?.enabled {
+        return Ok(None);
+    }
+    load_key(username)?
+        .map(Some)
+        .ok_or_else(|| "此裝置尚未輸入同步加密密碼".to_string())
+}
+
+/// Turn encryption on, or enter the passphrase on another device.
+/// Everything is pushed again, encrypted.
+pub async fn enable(username: &str, passphrase: &str) -> Result<(), String> {
+    if passphrase.chars().count() < 8 {
+        return Err("同步加密密碼至少需要 8 個字元".to_string());
+    }
+    let (pass, user) = (passphrase.to_string(), username.to_string());
+    let key = tokio::task::spawn_blocking(move || derive_key(&pass, &user))
+        .await
+        .map_err(|e| format!("key task join error: {e}"))??;
+    entry(username)?
+        .set_password(&STANDARD.encode(key.0))
+        .map_err(|e| format!("無法存入系統鑰匙圈: {}", e))?;
+    set_enabled(username, true).await
+}
+
+/// Turn encryption off and forget the key. Everything is pushed again,
+/// in plain text.
+pub async fn disable(username: &str) -> Result<(), String> {
+    match entry(username)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(e) => return Err(format!("無法從系統鑰匙圈移除: {}", e)),
+    }
+    set_enabled(username, false).await
+}
+
+async fn set_enabled(username: &str, enabled: bool) -> Result<(), String> {
+    let user = username.to_string();
+    with_db(move |db| {
+        db.save_setting(
+            ENABLED_SETTING,
+            if enabled { "true" } else { "false" },
+            &user,
+        )?;
+        db.clear_sync_watermarks(&user)
+    })
+    .await
+    .map(|_| ())
+}
+
+fn entry(username: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYRING_SERVICE, &format!("sync-key:{}", username))
+        .map_err(|e| format!("無法存取系統鑰匙圈: {}", e))
+}
+
+fn load_key(username: &str) -> Result<Option<SyncKey>, String> {
+    let encoded = match entry(username)?.get_password() {
+        Ok(encoded) => encoded,
+        Err(keyring::Error::NoEntry) => return Ok(None),
+        Err(e) => return Err(format!("無法讀取系統鑰匙圈: {}", e)),
+    };
+    let bytes = STANDARD
+        .decode(encoded)
+        .map_err(|_| "系統鑰匙圈中的同步金鑰已損毀".to_string())?;
+    let key: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| "系統鑰匙圈中的同步金鑰已損毀".to_string())?;
+    Ok(Some(SyncKey(key)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_fields_open_only_with_the_same_key_and_row() {
+        let key = derive_key("correct horse battery", "alice").unwrap();
+        let mut row = serde_json::json!({
+            "lecture_id": "lec-1",
+            "title": "Week 3",
+            "content": "{\"sections\":[]}",
+            "generated_at": "2026-03-01T09:00:00Z",
+        });
+        seal_row(&key, "notes", &mut row).unwrap();
+        let sealed = row["content"].as_str().unwrap().to_string();
+        assert!(sealed.starts_with(PREFIX));
+        assert_eq!(row["generated_at"], "2026-03-01T09:00:00Z");
+
+        // Same passphrase on another device: same key.
+        let again = derive_key("correct horse battery", "alice").unwrap();
+        let mut opened = row.clone();
+        open_row(&again, "notes", &mut opened).unwrap();
+        assert_eq!(opened["title"], "Week 3");
+        assert_eq!(opened["content"], "{\"sections\":[]}");
+
+        // Moved to another lecture, or a different passphrase.
+        assert!(open(&key, &sealed, "notes/lec-2").is_err());
+        let other = derive_key("wrong horse battery", "alice").unwrap();
+        assert!(open(&other, &sealed, "notes/lec-1")
+            .unwrap_err()
+            .contains("不同"));
+
+        // Rows pushed before encryption was turned on.
+        assert_eq!(open(&key, "plain", "notes/lec-1").unwrap(), "plain");
+    }
+}
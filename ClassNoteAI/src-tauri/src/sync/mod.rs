@@ -0,0 +1,191 @@
+//! Sync with ClassNoteServer.
+//!
+//! Changes bound for the server go into an outbox, the `pending_actions`
+//! table, and [`outbox`] sends them from a background task: on startup,
+//! whenever one is added, every [`outbox::FLUSH_INTERVAL`], and when the
+//! frontend asks (`flush_sync_queue`, e.g. on the browser `online`
+//! event). A network error leaves the action queued for the next flush;
+//! an action the server rejects is retried [`outbox::MAX_RETRIES`] times
+//! and then kept as `failed` until the user retries it.
+//!
+//! The frontend `offlineQueueService` used to run this loop itself, and
+//! an action it was sending when the app quit stayed `processing` for
+//! good (or was sent twice). Now only this module changes a row's
+//! status, and a launch puts anything left `processing` back in line.
+//!
+//! Every change of the queue goes out as a [`SyncStatus`] on
+//! [`STATUS_EVENT`]; an action the server accepted also goes out on
+//! [`COMPLETED_EVENT`]. What a flush is doing, down to rows and bytes,
+//! goes out on [`report::PROGRESS_EVENT`].
+//!
+//! Lecture recordings go up through the same outbox (`AUDIO_UPLOAD`) but
+//! in resumable chunks; see [`upload`]. Courses, lectures and notes go
+//! up as `SYNC_PUSH`, only the rows changed since the last push; see
+//! [`push`]. A note the server changed too is settled by the user's
+//! policy; see [`conflicts`]. Their text can be encrypted on the device
+//! first; see [`crypto`]. What syncs at all is up to the user; see
+//! [`selection`]. A user can sync with more than one server; see
+//! [`profiles`]. On a metered connection recordings wait for a better one
+//! while everything else still goes out; see [`network`].
+
+pub mod conflicts;
+pub mod crypto;
+pub mod network;
+pub mod outbox;
+pub mod profiles;
+pub mod push;
+pub mod report;
+pub mod selection;
+pub mod upload;
+
+use serde::Serialize;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+use crate::diagnostics::health::LAST_SYNC_SETTING;
+use crate::storage;
+
+pub const STATUS_EVENT: &str = "sync-status";
+pub const COMPLETED_EVENT: &str = "sync-action-completed";
+
+/// The outbox isn't per user, so neither is its bookkeeping.
+const SETTINGS_USER: &str = "default_user";
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncStatus {
+    /// Waiting or being sent, including recordings waiting for a
+    /// connection that isn't metered.
+    pub pending: usize,
+    /// Gave up after [`outbox::MAX_RETRIES`]; sent again on a retry.
+    pub failed: usize,
+    pub flushing: bool,
+    /// RFC 3339 time the server last accepted an action.
+    pub last_success_at: Option<String>,
+    /// Why the last attempt failed, until one succeeds.
+    pub last_error: Option<String>,
+    /// Whether the connection is metered; `None` when the OS can't tell.
+    pub metered: Option<bool>,
+}
+
+/// An action the server accepted.
+#[derive(Debug, Clone, Serialize)]
+pub struct CompletedAction {
+    pub id: String,
+    pub action_type: String,
+    pub payload: String,
+}
+
+#[derive(Debug, PartialEq)]
+enum Failure {
+    /// The server couldn't be reached; try again on the next flush.
+    Offline(String),
+    /// The server answered with an error; counts as a retry.
+    Rejected(String),
+    /// Not now, e.g. a recording on a metered connection; skipped until
+    /// the next flush without counting as a retry.
+    Deferred(String),
+}
+
+#[derive(Default)]
+struct Runtime {
+    flushing: bool,
+    last_error: Option<String>,
+}
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static RUNTIME: Mutex<Runtime> = Mutex::new(Runtime {
+    flushing: false,
+    last_error: None,
+});
+
+fn runtime() -> std::sync::MutexGuard<'static, Runtime> {
+    RUNTIME.lock().unwrap_or_else(|p| p.into_inner())
+}
+
+async fn with_db<T>(
+    f: impl FnOnce(&storage::Database) -> rusqlite::Result<T>,
+) -> Result<T, String> {
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    f(&db).map_err(|e| e.to_string())
+}
+
+/// An HTTP client for `server`, signed in as `username` when a profile
+/// saved credentials for them; see [`profiles`].
+fn client(timeout: Duration, server: &str, username: &str) -> Result<reqwest::Client, Failure> {
+    let mut headers = reqwest::header::HeaderMap::new();
+    match profiles::credential(server, username) {
+        Ok(Some(secret)) => {
+            let value = reqwest::header::HeaderValue::from_str(&format!("Bearer {}", secret))
+                .map_err(|_| Failure::Rejected("伺服器憑證含有無效字元".to_string()))?;
+            headers.insert(reqwest::header::AUTHORIZATION, value);
+        }
+        Ok(None) => {}
+        Err(e) => log::warn!("[sync] no credentials for {}: {}", server, e),
+    }
+    reqwest::Client::builder()
+        .timeout(timeout)
+        .default_headers(headers)
+        .build()
+        .map_err(|e| Failure::Offline(format!("HTTP client: {}", e)))
+}
+
+fn offline(e: reqwest::Error) -> Failure {
+    Failure::Offline(format!("無法連線到伺服器: {}", e))
+}
+
+async fn rejected_unless_ok(response: reqwest::Response) -> Result<reqwest::Response, Failure> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    Err(Failure::Rejected(format!(
+        "伺服器拒絕 ({}): {}",
+        status, body
+    )))
+}
+
+async fn json<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<T, Failure> {
+    response
+        .json()
+        .await
+        .map_err(|e| Failure::Rejected(format!("無效的伺服器回應: {}", e)))
+}
+
+pub async fn status() -> Result<SyncStatus, String> {
+    let ((pending, failed), last_success_at) = with_db(|db| {
+        Ok((
+            db.count_pending_actions()?,
+            db.get_setting(LAST_SYNC_SETTING, SETTINGS_USER)?,
+        ))
+    })
+    .await?;
+    let metered = network::metered().await;
+    let runtime = runtime();
+    Ok(SyncStatus {
+        pending,
+        failed,
+        flushing: runtime.flushing,
+        last_success_at,
+        last_error: runtime.last_error.clone(),
+        metered,
+    })
+}
+
+async fn emit_status() {
+    let Some(app) = APP.get() else {
+        return;
+    };
+    match status().await {
+        Ok(status) => {
+            let _ = app.emit(STATUS_EVENT, status);
+        }
+        Err(e) => log::warn!("[sync] status unavailable: {}", e),
+    }
+}
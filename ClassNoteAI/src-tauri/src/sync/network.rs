@@ -0,0 +1,102 @@
+//! Whether the connection is metered: a phone hotspot, cellular data, or
+//! a network the user marked as metered in the OS.
+//!
+//! Asked of the OS: NetworkManager's `Metered` property on Linux, the
+//! cost of the internet connection profile on Windows. macOS has no
+//! command-line way to ask, so there the answer is unknown, which syncs
+//! as usual. Answers are cached for [`CACHE_FOR`], since the outbox asks
+//! before every recording it sends.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub const CACHE_FOR: Duration = Duration::from_secs(30);
+
+static CACHE: Mutex<Option<(Instant, Option<bool>)>> = Mutex::new(None);
+
+/// `Some(true)` on a metered connection, `None` when the OS can't tell.
+pub async fn metered() -> Option<bool> {
+    if let Some((at, value)) = *CACHE.lock().unwrap_or_else(|p| p.into_inner()) {
+        if at.elapsed() < CACHE_FOR {
+            return value;
+        }
+    }
+    let value = tokio::task::spawn_blocking(detect).await.ok().flatten();
+    *CACHE.lock().unwrap_or_else(|p| p.into_inner()) = Some((Instant::now(), value));
+    value
+}
+
+#[cfg(target_os = "linux")]
+fn detect() -> Option<bool> {
+    let out = crate::utils::command::no_window("busctl")
+        .args([
+            "get-property",
+            "org.freedesktop.NetworkManager",
+            "/org/freedesktop/NetworkManager",
+            "org.freedesktop.NetworkManager",
+            "Metered",
+        ])
+        .output()
+        .ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    parse_nm_metered(&String::from_utf8_lossy(&out.stdout))
+}
+
+#[cfg(windows)]
+fn detect() -> Option<bool> {
+    const SCRIPT: &str = "$p = [Windows.Networking.Connectivity.NetworkInformation, \
+        Windows.Networking.Connectivity, ContentType=WindowsRuntime]::GetInternetConnectionProfile(); \
+        if ($p) { $p.GetConnectionCost().NetworkCostType }";
+    let out = crate::utils::command::no_window("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+        .output()
+        .ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    parse_cost_type(&String::from_utf8_lossy(&out.stdout))
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn detect() -> Option<bool> {
+    None
+}
+
+/// `busctl` prints `u N`, an `NMMetered`: 1 and 3 are (guessed) yes, 2
+/// and 4 (guessed) no, 0 unknown.
+#[cfg(any(target_os = "linux", test))]
+fn parse_nm_metered(output: &str) -> Option<bool> {
+    match output.trim().strip_prefix("u ")?.trim() {
+        "1" | "3" => Some(true),
+        "2" | "4" => Some(false),
+        _ => None,
+    }
+}
+
+/// A `NetworkCostType`: `Fixed` and `Variable` plans are metered.
+#[cfg(any(windows, test))]
+fn parse_cost_type(output: &str) -> Option<bool> {
+    match output.trim() {
+        "Fixed" | "Variable" => Some(true),
+        "Unrestricted" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn os_answers_map_to_metered() {
+        assert_eq!(parse_nm_metered("u 1\n"), Some(true));
+        assert_eq!(parse_nm_metered("u 4"), Some(false));
+        assert_eq!(parse_nm_metered("u 0"), None);
+        assert_eq!(parse_nm_metered(""), None);
+        assert_eq!(parse_cost_type("Variable\r\n"), Some(true));
+        assert_eq!(parse_cost_type("Unrestricted"), Some(false));
+        assert_eq!(parse_cost_type("Unknown"), None);
+    }
+}
@@ -0,0 +1,270 @@
+//! The background task that sends the `pending_actions` outbox.
+//!
+//! Actions go out one at a time, oldest first. Each action type has a
+//! handler in [`send`]; a type without one is dropped with a warning, as
+//! the frontend queue did. An action its handler defers is kept as
+//! `deferred` for the rest of the flush, so the ones after it still go.
+
+use serde::Deserialize;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+
+use super::{
+    client, conflicts, emit_status, push, report, runtime, upload, with_db, CompletedAction,
+    Failure, APP, COMPLETED_EVENT, SETTINGS_USER,
+};
+use crate::diagnostics::health::LAST_SYNC_SETTING;
+
+/// Rejections before an action is kept as `failed`.
+pub const MAX_RETRIES: i32 = 3;
+/// How often the outbox is flushed without being woken.
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+struct Action {
+    id: String,
+    action_type: String,
+    payload: String,
+    retry_count: i32,
+}
+
+fn wake_signal() -> &'static Notify {
+    static WAKE: OnceLock<Notify> = OnceLock::new();
+    WAKE.get_or_init(Notify::new)
+}
+
+/// Start the flush loop. Call once the database is initialized.
+pub fn start(app: AppHandle) {
+    if APP.set(app).is_err() {
+        return;
+    }
+    tauri::async_runtime::spawn(async {
+        // Whatever was being sent when the app last quit.
+        match with_db(|db| db.requeue_pending_actions("processing", false)).await {
+            Ok(0) => {}
+            Ok(n) => println!("[sync] Requeued {} interrupted action(s)", n),
+            Err(e) => log::warn!("[sync] could not requeue interrupted actions: {}", e),
+        }
+        loop {
+            flush().await;
+            tokio::select! {
+                _ = wake_signal().notified() => {}
+                _ = tokio::time::sleep(FLUSH_INTERVAL) => {}
+            }
+        }
+    });
+}
+
+/// Flush soon, e.g. after an action was added.
+pub fn wake() {
+    wake_signal().notify_one();
+}
+
+/// Put the failed actions back in line and flush.
+pub async fn retry_failed() -> Result<usize, String> {
+    let requeued = with_db(|db| db.requeue_pending_actions("failed", true)).await?;
+    wake();
+    Ok(requeued)
+}
+
+async fn flush() {
+    runtime().flushing = true;
+    report::begin();
+    emit_status().await;
+    if let Err(e) = with_db(|db| db.requeue_pending_actions("deferred", false)).await {
+        log::warn!("[sync] could not requeue deferred actions: {}", e);
+    }
+    let mut offline = false;
+    loop {
+        let action = match with_db(|db| db.next_pending_action()).await {
+            Ok(Some((id, action_type, payload, retry_count))) => Action {
+                id,
+                action_type,
+                payload,
+                retry_count,
+            },
+            Ok(None) => break,
+            Err(e) => {
+                runtime().last_error = Some(e);
+                break;
+            }
+        };
+        offline = process(&action).await;
+        emit_status().await;
+        if offline {
+            break;
+        }
+    }
+    report::finish(offline).await;
+    runtime().flushing = false;
+    emit_status().await;
+}
+
+/// Send `action` and record the outcome. Returns true when the server
+/// couldn't be reached, which ends this flush.
+async fn process(action: &Action) -> bool {
+    let id = action.id.clone();
+    let retries = action.retry_count;
+    if let Err(e) = with_db(move |db| db.update_pending_action(&id, "processing", retries)).await {
+        log::warn!("[sync] could not mark {} processing: {}", action.id, e);
+    }
+    report::update(|r| {
+        r.phase = report::phase_for(&action.action_type);
+        r.action_type = Some(action.action_type.clone());
+    });
+
+    let result = match send(&action.action_type, &action.payload).await {
+        Some(result) => result,
+        None => {
+            log::warn!("[sync] no handler for {}; dropping it", action.action_type);
+            let id = action.id.clone();
+            let _ = with_db(move |db| db.remove_pending_action(&id)).await;
+            return false;
+        }
+    };
+
+    match result {
+        Ok(()) => {
+            let id = action.id.clone();
+            let now = chrono::Utc::now().to_rfc3339();
+            let recorded = with_db(move |db| {
+                db.remove_pending_action(&id)?;
+                db.save_setting(LAST_SYNC_SETTING, &now, SETTINGS_USER)
+            })
+            .await;
+            if let Err(e) = recorded {
+                log::warn!("[sync] could not record {} as sent: {}", action.id, e);
+            }
+            runtime().last_error = None;
+            report::update(|r| r.actions_sent += 1);
+            println!("[sync] Sent {} ({})", action.action_type, action.id);
+            if let Some(app) = APP.get() {
+                let _ = app.emit(
+                    COMPLETED_EVENT,
+                    CompletedAction {
+                        id: action.id.clone(),
+                        action_type: action.action_type.clone(),
+                        payload: action.payload.clone(),
+                    },
+                );
+            }
+            false
+        }
+        Err(Failure::Offline(e)) => {
+            let id = action.id.clone();
+            let _ = with_db(move |db| db.update_pending_action(&id, "pending", retries)).await;
+            report::update(|r| r.add_error(e.clone()));
+            runtime().last_error = Some(e);
+            true
+        }
+        Err(Failure::Deferred(reason)) => {
+            println!(
+                "[sync] Deferred {} ({}): {}",
+                action.action_type, action.id, reason
+            );
+            let id = action.id.clone();
+            let _ = with_db(move |db| db.update_pending_action(&id, "deferred", retries)).await;
+            report::update(|r| r.actions_deferred += 1);
+            false
+        }
+        Err(Failure::Rejected(e)) => {
+            log::warn!(
+                "[sync] {} ({}) rejected: {}",
+                action.action_type,
+                action.id,
+                e
+            );
+            let (status, retries) = after_rejection(retries);
+            let id = action.id.clone();
+            let _ = with_db(move |db| db.update_pending_action(&id, status, retries)).await;
+            report::update(|r| {
+                r.actions_failed += 1;
+                r.add_error(format!("{}: {}", action.action_type, e));
+            });
+            runtime().last_error = Some(e);
+            if status == "pending" {
+                tokio::time::sleep(backoff(retries)).await;
+            }
+            false
+        }
+    }
+}
+
+/// The status and retry count an action is left with after the server
+/// rejected it.
+fn after_rejection(retry_count: i32) -> (&'static str, i32) {
+    let retries = retry_count + 1;
+    if retries >= MAX_RETRIES {
+        ("failed", retries)
+    } else {
+        ("pending", retries)
+    }
+}
+
+fn backoff(retries: i32) -> Duration {
+    Duration::from_secs(1 << retries.clamp(0, 6))
+}
+
+/// Run the handler for `action_type`; `None` if there is none.
+async fn send(action_type: &str, payload: &str) -> Option<Result<(), Failure>> {
+    match action_type {
+        "AUTH_REGISTER" => Some(auth_register(payload).await),
+        "AUDIO_UPLOAD" => Some(upload::upload_audio(payload).await),
+        "SYNC_PUSH" => Some(push::push_changes(payload).await),
+        conflicts::KEEP_LOCAL_ACTION => Some(conflicts::keep_local(payload).await),
+        _ => None,
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AuthRegister {
+    server_url: String,
+    username: String,
+}
+
+/// Register the local user on the server.
+async fn auth_register(payload: &str) -> Result<(), Failure> {
+    let request: AuthRegister = serde_json::from_str(payload)
+        .map_err(|e| Failure::Rejected(format!("無效的動作內容: {}", e)))?;
+    let server = request.server_url.trim_end_matches('/');
+    let url = format!("{}/api/auth/register", server);
+    let response = client(REQUEST_TIMEOUT, server, &request.username)?
+        .post(&url)
+        .json(&serde_json::json!({ "username": request.username }))
+        .send()
+        .await
+        .map_err(|e| Failure::Offline(format!("無法連線到伺服器: {}", e)))?;
+    match response.status().as_u16() {
+        200..=299 => {
+            let username = request.username.clone();
+            with_db(move |db| db.set_local_user_sync_status(&username, "synced"))
+                .await
+                .map_err(Failure::Rejected)
+        }
+        409 => Err(Failure::Rejected("Username conflict".to_string())),
+        status => Err(Failure::Rejected(format!(
+            "Registration failed: {}",
+            status
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejected_actions_fail_after_max_retries_with_growing_backoff() {
+        assert_eq!(after_rejection(0), ("pending", 1));
+        assert_eq!(
+            after_rejection(MAX_RETRIES - 2),
+            ("pending", MAX_RETRIES - 1)
+        );
+        assert_eq!(after_rejection(MAX_RETRIES - 1), ("failed", MAX_RETRIES));
+        assert!(backoff(2) > backoff(1));
+        assert_eq!(backoff(40), backoff(6));
+    }
+}
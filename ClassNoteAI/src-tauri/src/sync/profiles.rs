@@ -0,0 +1,225 @@
+//! Server profiles: the ClassNoteServers a user syncs with.
+//!
+//! A student may have an account on the server their class provides and
+//! another on their own. Each [`ServerProfile`] is a server URL and the
+//! account on it; the list is saved per user as [`PROFILES_SETTING`] and
+//! the one syncs go to as [`ACTIVE_SETTING`]. A profile's password or
+//! token is kept in the OS keychain and sent as a bearer token with every
+//! request to that server and account; see [`credential`].
+//!
+//! Sync state is already per server: the push watermarks are keyed by
+//! server URL and account, so switching profiles doesn't make either
+//! server miss or resend anything.
+
+use serde::{Deserialize, Serialize};
+
+use super::with_db;
+
+pub const PROFILES_SETTING: &str = "sync.profiles";
+pub const ACTIVE_SETTING: &str = "sync.active_profile";
+const KEYRING_SERVICE: &str = "ClassNoteAI";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServerProfile {
+    /// Empty when saving a new profile.
+    #[serde(default)]
+    pub id: String,
+    pub name: String,
+    pub server_url: String,
+    /// The account on that server.
+    pub username: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfileStatus {
+    #[serde(flatten)]
+    pub profile: ServerProfile,
+    pub active: bool,
+    pub has_credentials: bool,
+    /// RFC 3339 time of the last push to this server and account.
+    pub last_pushed_at: Option<String>,
+}
+
+pub async fn list(user_id: &str) -> Result<Vec<ProfileStatus>, String> {
+    let (profiles, active) = load(user_id).await?;
+    let mut statuses = Vec::with_capacity(profiles.len());
+    for profile in profiles {
+        let (server, username) = (profile.server_url.clone(), profile.username.clone());
+        let last_pushed_at = with_db(move |db| db.last_sync_push(&server, &username)).await?;
+        statuses.push(ProfileStatus {
+            active: active.as_deref() == Some(profile.id.as_str()),
+            has_credentials: credential(&profile.server_url, &profile.username)?.is_some(),
+            last_pushed_at,
+            profile,
+        });
+    }
+    Ok(statuses)
+}
+
+/// The profile syncs go to, if any.
+pub async fn active(user_id: &str) -> Result<Option<ServerProfile>, String> {
+    let (profiles, active) = load(user_id).await?;
+    Ok(active.and_then(|id| profiles.into_iter().find(|p| p.id == id)))
+}
+
+/// Add `profile`, or update the one with its id. `credentials` replaces
+/// the saved password or token; `Some("")` removes it and `None` keeps
+/// it. The first profile becomes the active one.
+pub async fn save(
+    user_id: &str,
+    mut profile: ServerProfile,
+    credentials: Option<String>,
+) -> Result<ServerProfile, String> {
+    profile.server_url = normalize_url(&profile.server_url)?;
+    profile.username = profile.username.trim().to_string();
+    if profile.username.is_empty() {
+        return Err("請輸入伺服器帳號".to_string());
+    }
+    if profile.name.trim().is_empty() {
+        profile.name = profile.server_url.clone();
+    }
+
+    let (mut profiles, mut active) = load(user_id).await?;
+    let previous = profiles.iter().position(|p| p.id == profile.id);
+    if profile.id.is_empty() || previous.is_none() {
+        profile.id = uuid::Uuid::new_v4().to_string();
+    }
+    let credentials = match (credentials, previous) {
+        (Some(c), _) => Some(c),
+        // Moved to another server or account: take the secret along.
+        (None, Some(i)) => {
+            let old = &profiles[i];
+            if old.server_url != profile.server_url || old.username != profile.username {
+                let secret = credential(&old.server_url, &old.username)?;
+                set_credential(&old.server_url, &old.username, "")?;
+                secret
+            } else {
+                None
+            }
+        }
+        (None, None) => None,
+    };
+    if let Some(secret) = credentials {
+        set_credential(&profile.server_url, &profile.username, &secret)?;
+    }
+
+    match previous {
+        Some(i) => profiles[i] = profile.clone(),
+        None => profiles.push(profile.clone()),
+    }
+    if active.is_none() {
+        active = Some(profile.id.clone());
+    }
+    store(user_id, &profiles, active.as_deref()).await?;
+    Ok(profile)
+}
+
+/// Remove a profile, its credentials and its sync state.
+pub async fn remove(user_id: &str, profile_id: &str) -> Result<(), String> {
+    let (mut profiles, mut active) = load(user_id).await?;
+    let Some(i) = profiles.iter().position(|p| p.id == profile_id) else {
+        return Err("找不到此伺服器設定".to_string());
+    };
+    let removed = profiles.remove(i);
+    set_credential(&removed.server_url, &removed.username, "")?;
+    if active.as_deref() == Some(profile_id) {
+        active = profiles.first().map(|p| p.id.clone());
+    }
+    store(user_id, &profiles, active.as_deref()).await?;
+    with_db(move |db| db.clear_server_sync_watermarks(&removed.server_url, &removed.username))
+        .await
+        .map(|_| ())
+}
+
+pub async fn set_active(user_id: &str, profile_id: &str) -> Result<(), String> {
+    let (profiles, _) = load(user_id).await?;
+    if !profiles.iter().any(|p| p.id == profile_id) {
+        return Err("找不到此伺服器設定".to_string());
+    }
+    store(user_id, &profiles, Some(profile_id)).await
+}
+
+/// The password or token saved for `username` on `server`.
+pub(super) fn credential(server: &str, username: &str) -> Result<Option<String>, String> {
+    match entry(server, username)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("無法讀取系統鑰匙圈: {}", e)),
+    }
+}
+
+/// Save `secret`, or forget it when empty.
+fn set_credential(server: &str, username: &str, secret: &str) -> Result<(), String> {
+    let entry = entry(server, username)?;
+    if secret.is_empty() {
+        return match entry.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(format!("無法從系統鑰匙圈移除: {}", e)),
+        };
+    }
+    entry
+        .set_password(secret)
+        .map_err(|e| format!("無法存入系統鑰匙圈: {}", e))
+}
+
+fn entry(server: &str, username: &str) -> Result<keyring::Entry, String> {
+    let account = format!("server:{}:{}", server.trim_end_matches('/'), username);
+    keyring::Entry::new(KEYRING_SERVICE, &account).map_err(|e| format!("無法存取系統鑰匙圈: {}", e))
+}
+
+fn normalize_url(url: &str) -> Result<String, String> {
+    let url = url.trim().trim_end_matches('/');
+    let parsed = reqwest::Url::parse(url).map_err(|_| format!("無效的伺服器網址: {}", url))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err(format!("無效的伺服器網址: {}", url));
+    }
+    Ok(url.to_string())
+}
+
+async fn load(user_id: &str) -> Result<(Vec<ServerProfile>, Option<String>), String> {
+    let user = user_id.to_string();
+    let (profiles, active) = with_db(move |db| {
+        Ok((
+            db.get_setting(PROFILES_SETTING, &user)?,
+            db.get_setting(ACTIVE_SETTING, &user)?,
+        ))
+    })
+    .await?;
+    let profiles = profiles
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    Ok((profiles, active.filter(|id| !id.is_empty())))
+}
+
+async fn store(
+    user_id: &str,
+    profiles: &[ServerProfile],
+    active: Option<&str>,
+) -> Result<(), String> {
+    let json = serde_json::to_string(profiles).map_err(|e| e.to_string())?;
+    let (user, active) = (user_id.to_string(), active.unwrap_or_default().to_string());
+    with_db(move |db| {
+        db.save_setting(PROFILES_SETTING, &json, &user)?;
+        db.save_setting(ACTIVE_SETTING, &active, &user)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn server_urls_are_checked_and_trimmed() {
+        assert_eq!(
+            normalize_url(" https://notes.example.edu/ ").unwrap(),
+            "https://notes.example.edu"
+        );
+        assert_eq!(
+            normalize_url("http://192.168.1.20:8080").unwrap(),
+            "http://192.168.1.20:8080"
+        );
+        assert!(normalize_url("notes.example.edu").is_err());
+        assert!(normalize_url("ftp://notes.example.edu").is_err());
+    }
+}
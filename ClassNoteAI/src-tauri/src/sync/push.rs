@@ -0,0 +1,210 @@
+//! Delta push of courses, lectures and notes to ClassNoteServer.
+//!
+//! Each entity keeps a watermark per server and user in the
+//! `sync_watermarks` table: the (updated_at, id) of the last row the
+//! server accepted. A push sends only the rows after it, oldest first,
+//! in batches of [`BATCH_SIZE`] to `POST /api/sync/push` as
+//! `{username, entity, rows}`, and moves the watermark after every batch,
+//! so one cut short by the network continues where it stopped. Trashed
+//! rows are sent too; their `is_deleted` is how the server hears about
+//! a delete. Rows deleted for good are gone, so their tombstones go last
+//! as `purged_items` rows of `{entity, id, purged_at}`.
+//!
+//! The server answers `{conflicts}`: the notes it kept because its own
+//! copy changed since this client's last push. Those go to
+//! [`conflicts::handle`] before the watermark moves.
+//!
+//! Note text is encrypted first when the user has turned that on; see
+//! [`crypto`]. Subtitles and chat aren't pushed. Types and courses the user left out
+//! of sync aren't sent; see [`selection`].
+//!
+//! Runs as the `SYNC_PUSH` outbox action.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::{
+    client, conflicts, crypto, offline, rejected_unless_ok, report, selection, with_db, Failure,
+};
+
+/// In this order, so the server has a row's course or lecture first and
+/// purges after everything else.
+pub const ENTITIES: &[&str] = &["courses", "lectures", "notes", "purged_items"];
+pub const BATCH_SIZE: usize = 200;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// (updated_at, id) of the last row pushed.
+type Watermark = (String, String);
+
+/// `SYNC_PUSH` payload.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncPush {
+    server_url: String,
+    username: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Pushed {
+    /// Notes rows, encrypted when the user has turned that on.
+    #[serde(default)]
+    conflicts: Vec<serde_json::Value>,
+}
+
+/// Push what changed since the last push.
+pub(super) async fn push_changes(payload: &str) -> Result<(), Failure> {
+    let request: SyncPush = serde_json::from_str(payload)
+        .map_err(|e| Failure::Rejected(format!("無效的動作內容: {}", e)))?;
+    let server = request.server_url.trim_end_matches('/').to_string();
+    let client = client(REQUEST_TIMEOUT, &server, &request.username)?;
+    let key = crypto::key_for(&request.username)
+        .await
+        .map_err(Failure::Rejected)?;
+    let selection = selection::get(&request.username)
+        .await
+        .map_err(Failure::Rejected)?;
+
+    for &entity in ENTITIES {
+        if !selection.allows(entity) {
+            continue;
+        }
+        let mut pushed = 0;
+        loop {
+            let (mut rows, last) = changed_since(
+                &server,
+                &request.username,
+                entity,
+                &selection.excluded_courses,
+            )
+            .await
+            .map_err(Failure::Rejected)?;
+            let Some((updated_at, last_id)) = last else {
+                break;
+            };
+            if let Some(key) = &key {
+                for row in &mut rows {
+                    crypto::seal_row(key, entity, row).map_err(Failure::Rejected)?;
+                }
+            }
+            let response = client
+                .post(format!("{}/api/sync/push", server))
+                .json(&serde_json::json!({
+                    "username": request.username,
+                    "entity": entity,
+                    "rows": rows,
+                }))
+                .send()
+                .await
+                .map_err(offline)?;
+            let body = rejected_unless_ok(response)
+                .await?
+                .text()
+                .await
+                .map_err(offline)?;
+            // An empty body is fine: no conflicts.
+            let answer: Pushed = if body.trim().is_empty() {
+                Pushed::default()
+            } else {
+                serde_json::from_str(&body)
+                    .map_err(|e| Failure::Rejected(format!("無效的伺服器回應: {}", e)))?
+            };
+            if entity == "notes" && !answer.conflicts.is_empty() {
+                conflicts::handle(
+                    &client,
+                    &server,
+                    &request.username,
+                    key.as_ref(),
+                    answer.conflicts,
+                )
+                .await?;
+            }
+
+            let (s, u) = (server.clone(), request.username.clone());
+            with_db(move |db| db.set_sync_watermark(&s, &u, entity, &updated_at, &last_id))
+                .await
+                .map_err(Failure::Rejected)?;
+            pushed += rows.len();
+            report::update(|r| *r.entities.entry(entity.to_string()).or_default() += rows.len());
+            if rows.len() < BATCH_SIZE {
+                break;
+            }
+        }
+        if pushed > 0 {
+            println!("[sync] Pushed {} changed {}", pushed, entity);
+        }
+    }
+    Ok(())
+}
+
+/// The next batch of `entity` rows after the watermark, outside
+/// `excluded_courses`, and the watermark of its last row.
+async fn changed_since(
+    server: &str,
+    username: &str,
+    entity: &'static str,
+    excluded_courses: &[String],
+) -> Result<(Vec<serde_json::Value>, Option<Watermark>), String> {
+    let (server, username) = (server.to_string(), username.to_string());
+    let excluded = excluded_courses.to_vec();
+    with_db(move |db| {
+        let since = db.get_sync_watermark(&server, &username, entity)?;
+        let since = since.as_ref().map(|(at, id)| (at.as_str(), id.as_str()));
+        Ok(match entity {
+            "courses" => batch(
+                db.list_courses_changed_since(&username, since, &excluded, BATCH_SIZE)?,
+                |c| (c.updated_at.clone(), c.id.clone()),
+            ),
+            "lectures" => batch(
+                db.list_lectures_changed_since(&username, since, &excluded, BATCH_SIZE)?,
+                |l| (l.updated_at.clone(), l.id.clone()),
+            ),
+            "notes" => batch(
+                db.list_notes_changed_since(&username, since, &excluded, BATCH_SIZE)?,
+                |n| (n.generated_at.clone(), n.lecture_id.clone()),
+            ),
+            "purged_items" => batch(
+                db.list_tombstones_since(&username, since, BATCH_SIZE)?,
+                |t| (t.purged_at.clone(), format!("{}/{}", t.entity, t.id)),
+            ),
+            _ => (Vec::new(), None),
+        })
+    })
+    .await
+}
+
+fn batch<T: Serialize>(
+    items: Vec<T>,
+    watermark: impl Fn(&T) -> Watermark,
+) -> (Vec<serde_json::Value>, Option<Watermark>) {
+    let last = items.last().map(watermark);
+    let rows = items
+        .iter()
+        .filter_map(|item| serde_json::to_value(item).ok())
+        .collect();
+    (rows, last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_ends_at_the_last_row() {
+        #[derive(Serialize)]
+        struct Row {
+            id: &'static str,
+            at: &'static str,
+        }
+        let (rows, last) = batch(
+            vec![Row { id: "a", at: "t1" }, Row { id: "b", at: "t2" }],
+            |r| (r.at.to_string(), r.id.to_string()),
+        );
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1]["id"], "b");
+        assert_eq!(last, Some(("t2".to_string(), "b".to_string())));
+
+        let (rows, last) = batch(Vec::<Row>::new(), |r| (r.at.to_string(), r.id.to_string()));
+        assert!(rows.is_empty());
+        assert_eq!(last, None);
+    }
+}
@@ -0,0 +1,169 @@
+//! What a flush of the outbox did, for the UI.
+//!
+//! Each flush that sends at least one action builds a [`SyncReport`]:
+//! the phase it is in, rows pushed per entity, conflicts, recording
+//! bytes uploaded and the errors it ran into. Every change goes out on
+//! [`PROGRESS_EVENT`], and the finished report is saved as
+//! [`REPORT_SETTING`] for `get_last_sync_report`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use tauri::Emitter;
+
+use super::{with_db, APP, SETTINGS_USER};
+
+pub const PROGRESS_EVENT: &str = "sync-progress";
+pub const REPORT_SETTING: &str = "sync.last_report";
+/// Errors kept per report; a flush against a broken server can hit one
+/// per action.
+const MAX_ERRORS: usize = 20;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncPhase {
+    #[default]
+    Starting,
+    /// Sending an action other than the ones below.
+    Sending,
+    /// Pushing changed courses, lectures and notes.
+    Pushing,
+    /// Uploading a recording.
+    Uploading,
+    Finished,
+    /// Stopped because the server couldn't be reached.
+    Offline,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SyncReport {
+    pub phase: SyncPhase,
+    /// The action being sent.
+    pub action_type: Option<String>,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub actions_sent: usize,
+    pub actions_failed: usize,
+    /// Left for a later flush, e.g. recordings on a metered connection.
+    #[serde(default)]
+    pub actions_deferred: usize,
+    /// Rows pushed, by entity (`courses`, `lectures`, `notes`,
+    /// `purged_items`).
+    pub entities: BTreeMap<String, usize>,
+    pub conflicts: usize,
+    pub bytes_uploaded: u64,
+    pub bytes_total: u64,
+    pub errors: Vec<String>,
+}
+
+impl SyncReport {
+    pub fn add_error(&mut self, error: String) {
+        if self.errors.len() < MAX_ERRORS {
+            self.errors.push(error);
+        }
+    }
+}
+
+static CURRENT: Mutex<Option<SyncReport>> = Mutex::new(None);
+
+fn current() -> std::sync::MutexGuard<'static, Option<SyncReport>> {
+    CURRENT.lock().unwrap_or_else(|p| p.into_inner())
+}
+
+/// Start the report of a flush. Nothing goes out until it sends an
+/// action.
+pub(super) fn begin() {
+    *current() = Some(SyncReport {
+        started_at: chrono::Utc::now().to_rfc3339(),
+        ..SyncReport::default()
+    });
+}
+
+/// Change the report of the running flush and send it out.
+pub(super) fn update(f: impl FnOnce(&mut SyncReport)) {
+    let report = {
+        let mut current = current();
+        let Some(report) = current.as_mut() else {
+            return;
+        };
+        f(report);
+        report.clone()
+    };
+    emit(&report);
+}
+
+/// End the report of the running flush; saved if it sent anything.
+pub(super) async fn finish(offline: bool) {
+    let Some(mut report) = current().take() else {
+        return;
+    };
+    if report.actions_sent + report.actions_failed + report.actions_deferred == 0 && !offline {
+        return;
+    }
+    report.phase = if offline {
+        SyncPhase::Offline
+    } else {
+        SyncPhase::Finished
+    };
+    report.action_type = None;
+    report.finished_at = Some(chrono::Utc::now().to_rfc3339());
+    emit(&report);
+    let json = serde_json::to_string(&report).unwrap_or_default();
+    if let Err(e) = with_db(move |db| db.save_setting(REPORT_SETTING, &json, SETTINGS_USER)).await {
+        log::warn!("[sync] could not save the sync report: {}", e);
+    }
+}
+
+/// The running flush's report, else the last one saved.
+pub async fn last() -> Result<Option<SyncReport>, String> {
+    if let Some(report) = current().clone() {
+        if report.actions_sent + report.actions_failed > 0 || report.action_type.is_some() {
+            return Ok(Some(report));
+        }
+    }
+    let saved = with_db(|db| db.get_setting(REPORT_SETTING, SETTINGS_USER)).await?;
+    Ok(saved.and_then(|json| serde_json::from_str(&json).ok()))
+}
+
+/// The phase an action of `action_type` runs in.
+pub(super) fn phase_for(action_type: &str) -> SyncPhase {
+    match action_type {
+        "SYNC_PUSH" | "NOTE_KEEP_LOCAL" => SyncPhase::Pushing,
+        "AUDIO_UPLOAD" => SyncPhase::Uploading,
+        _ => SyncPhase::Sending,
+    }
+}
+
+fn emit(report: &SyncReport) {
+    if let Some(app) = APP.get() {
+        let _ = app.emit(PROGRESS_EVENT, report);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_round_trips_and_caps_errors() {
+        let mut report = SyncReport {
+            phase: SyncPhase::Uploading,
+            started_at: "2026-03-01T09:00:00Z".to_string(),
+            ..SyncReport::default()
+        };
+        report.entities.insert("notes".to_string(), 3);
+        for i in 0..MAX_ERRORS + 5 {
+            report.add_error(format!("error {}", i));
+        }
+        assert_eq!(report.errors.len(), MAX_ERRORS);
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["phase"], "uploading");
+        assert_eq!(json["entities"]["notes"], 3);
+        let back: SyncReport = serde_json::from_value(json).unwrap();
+        assert_eq!(back, report);
+
+        assert_eq!(phase_for("AUDIO_UPLOAD"), SyncPhase::Uploading);
+        assert_eq!(phase_for("AUTH_REGISTER"), SyncPhase::Sending);
+    }
+}
@@ -0,0 +1,94 @@
+//! What of a user's data syncs.
+//!
+//! A [`SyncSelection`] turns whole data types off (say, never upload
+//! recordings) and leaves out courses (a personal one that stays on this
+//! device). It is saved per user as [`SELECTION_SETTING`]. A push
+//! doesn't send what it leaves out, and a note the server sends back as a
+//! conflict is ignored when notes or its course are left out; there is
+//! no pull for it to apply to yet.
+//!
+//! Courses and lectures always sync unless their course is left out;
+//! everything else hangs off them.
+
+use serde::{Deserialize, Serialize};
+
+use super::with_db;
+
+pub const SELECTION_SETTING: &str = "sync.selection";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncSelection {
+    pub notes: bool,
+    /// Lecture recordings.
+    pub audio: bool,
+    /// Hold recordings back on a metered connection; see
+    /// [`super::network`].
+    pub defer_audio_on_metered: bool,
+    /// Ids of courses that never leave this device.
+    pub excluded_courses: Vec<String>,
+}
+
+impl Default for SyncSelection {
+    fn default() -> Self {
+        Self {
+            notes: true,
+            audio: true,
+            defer_audio_on_metered: true,
+            excluded_courses: Vec::new(),
+        }
+    }
+}
+
+impl SyncSelection {
+    /// Whether rows of `entity` sync at all.
+    pub fn allows(&self, entity: &str) -> bool {
+        match entity {
+            "notes" => self.notes,
+            "audio" => self.audio,
+            _ => true,
+        }
+    }
+
+    pub fn excludes_course(&self, course_id: &str) -> bool {
+        self.excluded_courses.iter().any(|id| id == course_id)
+    }
+}
+
+pub async fn get(user_id: &str) -> Result<SyncSelection, String> {
+    let user = user_id.to_string();
+    let value = with_db(move |db| db.get_setting(SELECTION_SETTING, &user)).await?;
+    Ok(value
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default())
+}
+
+/// Save `selection`. Watermarks are reset so a type or course turned
+/// back on is pushed in full, not only what changed after.
+pub async fn set(user_id: &str, selection: SyncSelection) -> Result<(), String> {
+    let json = serde_json::to_string(&selection).map_err(|e| e.to_string())?;
+    let user = user_id.to_string();
+    with_db(move |db| {
+        db.save_setting(SELECTION_SETTING, &json, &user)?;
+        db.clear_sync_watermarks(&user)
+    })
+    .await
+    .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_default_to_syncing() {
+        let selection: SyncSelection =
+            serde_json::from_str(r#"{"audio":false,"excluded_courses":["personal"]}"#).unwrap();
+        assert!(selection.allows("notes"));
+        assert!(selection.allows("courses"));
+        assert!(!selection.allows("audio"));
+        assert!(selection.defer_audio_on_metered);
+        assert!(selection.excludes_course("personal"));
+        assert!(!selection.excludes_course("cs101"));
+    }
+}
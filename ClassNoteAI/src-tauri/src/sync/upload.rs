@@ -0,0 +1,416 @@
+//! Resumable upload of lecture recordings to ClassNoteServer.
+//!
+//! A recording is hundreds of MB of WAV or Opus, over campus Wi-Fi that
+//! drops every few minutes, so it goes up in [`CHUNK_SIZE`] pieces the
+//! server acknowledges one at a time.
+//!
+//! The open upload is recorded in [`STATE_FILE`], so one cut short by the
+//! network or by quitting continues from what the server has instead of
+//! starting over. Uploads run as the `AUDIO_UPLOAD` outbox action, and
+//! progress goes out on [`PROGRESS_EVENT`]. One queued for a user who
+//! turned recordings off, or for a course left out of sync, is dropped;
+//! see [`selection`]. On a metered connection it waits, unless the user
+//! said not to.
+//!
+//! # Server contract
+//!
+//! The server side of these endpoints isn't in this repository. A server
+//! that doesn't have them answers the first request with 404 or 405; the
+//! upload then fails with [`NOT_SUPPORTED`] and the recording stays on
+//! this device. Every request carries `Authorization: Bearer {secret}`
+//! when the profile has a saved secret (see [`super::profiles`]). Bodies
+//! are JSON unless noted. Sizes and offsets are in bytes, and hashes are
+//! lowercase hex SHA-256.
+//!
+//! * `POST /api/uploads` with
+//!   `{"username", "lecture_id", "file_name", "size", "sha256"}` opens an
+//!   upload of the whole file. The answer is 2xx
+//!   `{"upload_id": string, "received": u64}`, where `received` is
+//!   normally 0. A server that already has bytes of the same file may
+//!   answer with more.
+//! * `GET /api/uploads/{upload_id}` answers 2xx `{"received": u64}`, the
+//!   number of bytes the server holds. Any other status means the upload
+//!   is gone (expired or unknown), and the client opens a new one.
+//! * `PUT /api/uploads/{upload_id}?offset={N}` sends raw bytes
+//!   (`application/octet-stream`, at most [`CHUNK_SIZE`]) that belong at
+//!   `N`. The [`CHUNK_HASH_HEADER`] header carries their SHA-256.
+//!   * On success the server stores them and answers 2xx
+//!     `{"received": N + len}`.
+//!   * When `N` isn't what the server holds, it stores nothing and
+//!     answers 409 `{"received": u64}` with its own count. The client
+//!     continues from there.
+//!   * On a hash mismatch it answers 4xx and stores nothing.
+//!   * A `received` beyond `size` is a protocol error, and the client
+//!     abandons the upload.
+//! * `POST /api/uploads/{upload_id}/complete` has no body. The server
+//!   checks the SHA-256 of everything it received against the one given
+//!   at open.
+//!   * When it matches, the answer is 2xx `{"file_id": string}`.
+//!   * Otherwise it answers 4xx, and the client starts a new upload next
+//!     time.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::Emitter;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use super::{
+    client, json, network, offline, rejected_unless_ok, report, selection, with_db, Failure, APP,
+};
+use crate::diagnostics::health::sha256_file;
+use crate::paths;
+
+/// `{app_data_dir}/uploads.json`.
+pub const STATE_FILE: &str = "uploads.json";
+pub const CHUNK_SIZE: u64 = 4 * 1024 * 1024;
+pub const CHUNK_HASH_HEADER: &str = "X-Chunk-SHA256";
+pub const PROGRESS_EVENT: &str = "sync-upload-progress";
+pub const NOT_SUPPORTED: &str = "此伺服器不支援錄音上傳";
+/// Per request; a chunk over a slow link takes a while.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(120);
+
+static STATE_LOCK: Mutex<()> = Mutex::new(());
+
+/// `AUDIO_UPLOAD` payload.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AudioUpload {
+    server_url: String,
+    username: String,
+    lecture_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadProgress {
+    pub lecture_id: String,
+    pub file_name: String,
+    pub uploaded: u64,
+    pub total: u64,
+}
+
+/// An upload the server has opened and not completed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct UploadRecord {
+    lecture_id: String,
+    server_url: String,
+    upload_id: String,
+    sha256: String,
+    size: u64,
+}
+
+#[derive(Deserialize)]
+struct Opened {
+    upload_id: String,
+    received: u64,
+}
+
+#[derive(Deserialize)]
+struct Received {
+    received: u64,
+}
+
+#[derive(Deserialize)]
+struct Completed {
+    file_id: String,
+}
+
+/// Upload the recording of the lecture named in `payload`.
+pub(super) async fn upload_audio(payload: &str) -> Result<(), Failure> {
+    let request: AudioUpload = serde_json::from_str(payload)
+        .map_err(|e| Failure::Rejected(format!("無效的動作內容: {}", e)))?;
+    let selection = selection::get(&request.username)
+        .await
+        .map_err(Failure::Rejected)?;
+    let id = request.lecture_id.clone();
+    let course_id = with_db(move |db| db.get_lecture(&id))
+        .await
+        .map_err(Failure::Rejected)?
+        .map(|l| l.course_id);
+    if !selection.allows("audio") || course_id.is_some_and(|c| selection.excludes_course(&c)) {
+        println!(
+            "[sync] Recording of lecture {} is left out of sync; not uploading",
+            request.lecture_id
+        );
+        return Ok(());
+    }
+    if selection.defer_audio_on_metered && network::metered().await == Some(true) {
+        return Err(Failure::Deferred(
+            "計量網路連線，錄音稍後再上傳".to_string(),
+        ));
+    }
+    let path = audio_file(&request.lecture_id)
+        .await
+        .map_err(Failure::Rejected)?;
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let size = tokio::fs::metadata(&path)
+        .await
+        .map_err(|e| Failure::Rejected(format!("無法讀取錄音檔: {}", e)))?
+        .len();
+    let hash_path = path.clone();
+    let sha256 = tokio::task::spawn_blocking(move || sha256_file(&hash_path))
+        .await
+        .map_err(|e| Failure::Rejected(format!("hash task join error: {e}")))?
+        .map_err(|e| Failure::Rejected(format!("無法讀取錄音檔: {}", e)))?;
+
+    let server = request.server_url.trim_end_matches('/').to_string();
+    let client = client(REQUEST_TIMEOUT, &server, &request.username)?;
+    let resumable = {
+        let _guard = STATE_LOCK.lock().unwrap_or_else(|p| p.into_inner());
+        state_path()
+            .map(|p| read_state(&p))
+            .unwrap_or_default()
+            .into_iter()
+            .find(|r| same_upload(r, &request.lecture_id, &server, &sha256, size))
+    };
+    let resumed = match resumable {
+        Some(record) => {
+            let url = format!("{}/api/uploads/{}", server, record.upload_id);
+            match client.get(&url).send().await.map_err(offline)? {
+                response if response.status().is_success() => {
+                    let received = json::<Received>(response).await?.received;
+                    Some((record.upload_id, received))
+                }
+                // Expired or unknown on the server: start over.
+                _ => None,
+            }
+        }
+        None => None,
+    };
+    let (upload_id, mut received) = match resumed {
+        Some(resumed) => resumed,
+        None => {
+            let response = client
+                .post(format!("{}/api/uploads", server))
+                .json(&serde_json::json!({
+                    "username": request.username,
+                    "lecture_id": request.lecture_id,
+                    "file_name": file_name,
+                    "size": size,
+                    "sha256": sha256,
+                }))
+                .send()
+                .await
+                .map_err(offline)?;
+            if matches!(response.status().as_u16(), 404 | 405) {
+                return Err(Failure::Rejected(NOT_SUPPORTED.to_string()));
+            }
+            let opened: Opened = json(rejected_unless_ok(response).await?).await?;
+            modify(|records| {
+                records.retain(|r| r.lecture_id != request.lecture_id);
+                records.push(UploadRecord {
+                    lecture_id: request.lecture_id.clone(),
+                    server_url: server.clone(),
+                    upload_id: opened.upload_id.clone(),
+                    sha256: sha256.clone(),
+                    size,
+                });
+            });
+            (opened.upload_id, opened.received)
+        }
+    };
+
+    report::update(|r| {
+        r.bytes_total += size;
+        r.bytes_uploaded += received;
+    });
+
+    let mut file = tokio::fs::File::open(&path)
+        .await
+        .map_err(|e| Failure::Rejected(format!("無法讀取錄音檔: {}", e)))?;
+    let progress = |uploaded| UploadProgress {
+        lecture_id: request.lecture_id.clone(),
+        file_name: file_name.clone(),
+        uploaded,
+        total: size,
+    };
+    while received < size {
+        let chunk = read_chunk(&mut file, received, CHUNK_SIZE.min(size - received))
+            .await
+            .map_err(|e| Failure::Rejected(format!("無法讀取錄音檔: {}", e)))?;
+        let response = client
+            .put(format!("{}/api/uploads/{}", server, upload_id))
+            .query(&[("offset", received)])
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+            .header(CHUNK_HASH_HEADER, chunk_hash(&chunk))
+            .body(chunk)
+            .send()
+            .await
+            .map_err(offline)?;
+        // 409: the server is somewhere else (it kept a chunk whose answer
+        // got lost); carry on from there.
+        let next = if response.status().as_u16() == 409 {
+            let at = json::<Received>(response).await?.received;
+            if at == received {
+                return Err(Failure::Rejected(format!(
+                    "伺服器不接受位置 {} 的區塊",
+                    received
+                )));
+            }
+            at
+        } else {
+            json::<Received>(rejected_unless_ok(response).await?)
+                .await?
+                .received
+        };
+        if next > size {
+            forget(&request.lecture_id);
+            return Err(Failure::Rejected(format!(
+                "伺服器回報收到 {} bytes，超過檔案大小 {}",
+                next, size
+            )));
+        }
+        report::update(|r| r.bytes_uploaded = r.bytes_uploaded.saturating_sub(received) + next);
+        received = next;
+        emit_progress(progress(received));
+    }
+
+    let response = client
+        .post(format!("{}/api/uploads/{}/complete", server, upload_id))
+        .send()
+        .await
+        .map_err(offline)?;
+    if !response.status().is_success() {
+        // The server's copy doesn't hash to ours; start over next time.
+        forget(&request.lecture_id);
+        return Err(Failure::Rejected(format!(
+            "伺服器無法完成上傳 ({})，下次將重新上傳",
+            response.status()
+        )));
+    }
+    let completed: Completed = json(response).await?;
+    forget(&request.lecture_id);
+    emit_progress(progress(size));
+    println!(
+        "[sync] Uploaded {} for lecture {} as {}",
+        file_name, request.lecture_id, completed.file_id
+    );
+    Ok(())
+}
+
+/// The recording of `lecture_id` on disk.
+async fn audio_file(lecture_id: &str) -> Result<PathBuf, String> {
+    let id = lecture_id.to_string();
+    let lecture = with_db(move |db| db.get_lecture(&id))
+        .await?
+        .ok_or_else(|| "找不到此課堂".to_string())?;
+    let audio_dir = paths::get_audio_dir()?;
+    lecture
+        .audio_path
+        .as_deref()
+        .and_then(|p| crate::resolve_stored_audio_path(&audio_dir, p))
+        .filter(|p| p.is_file())
+        .ok_or_else(|| "此課堂沒有可用的錄音檔".to_string())
+}
+
+async fn read_chunk(file: &mut tokio::fs::File, offset: u64, len: u64) -> std::io::Result<Vec<u8>> {
+    let mut chunk = vec![0u8; len as usize];
+    file.seek(SeekFrom::Start(offset)).await?;
+    file.read_exact(&mut chunk).await?;
+    Ok(chunk)
+}
+
+/// Whether `record` is an upload of this same file to this server.
+fn same_upload(
+    record: &UploadRecord,
+    lecture_id: &str,
+    server: &str,
+    sha256: &str,
+    size: u64,
+) -> bool {
+    record.lecture_id == lecture_id
+        && record.server_url == server
+        && record.sha256 == sha256
+        && record.size == size
+}
+
+fn chunk_hash(chunk: &[u8]) -> String {
+    Sha256::digest(chunk)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn emit_progress(progress: UploadProgress) {
+    if let Some(app) = APP.get() {
+        let _ = app.emit(PROGRESS_EVENT, progress);
+    }
+}
+
+fn state_path() -> Result<PathBuf, String> {
+    Ok(paths::get_app_data_dir()?.join(STATE_FILE))
+}
+
+fn read_state(path: &Path) -> Vec<UploadRecord> {
+    std::fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn write_state(path: &Path, records: &[UploadRecord]) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(records).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| format!("write {}: {}", path.display(), e))
+}
+
+fn modify(f: impl FnOnce(&mut Vec<UploadRecord>)) {
+    let _guard = STATE_LOCK.lock().unwrap_or_else(|p| p.into_inner());
+    let result = state_path().and_then(|path| {
+        let mut records = read_state(&path);
+        f(&mut records);
+        write_state(&path, &records)
+    });
+    if let Err(e) = result {
+        log::warn!("[sync] could not save upload state: {}", e);
+    }
+}
+
+fn forget(lecture_id: &str) {
+    modify(|records| records.retain(|r| r.lecture_id != lecture_id));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resumes_only_an_upload_of_the_same_file_to_the_same_server() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(STATE_FILE);
+        let record = UploadRecord {
+            lecture_id: "lec-1".to_string(),
+            server_url: "https://notes.example.edu".to_string(),
+            upload_id: "up-1".to_string(),
+            sha256: "abc".to_string(),
+            size: 10,
+        };
+        write_state(&path, std::slice::from_ref(&record)).unwrap();
+        let records = read_state(&path);
+        assert_eq!(records, [record.clone()]);
+
+        let server = "https://notes.example.edu";
+        assert!(same_upload(&records[0], "lec-1", server, "abc", 10));
+        // Re-recorded or compressed since: a different file.
+        assert!(!same_upload(&records[0], "lec-1", server, "def", 10));
+        assert!(!same_upload(&records[0], "lec-1", server, "abc", 11));
+        assert!(!same_upload(
+            &records[0],
+            "lec-1",
+            "https://home.lan",
+            "abc",
+            10
+        ));
+        assert!(!same_upload(&records[0], "lec-2", server, "abc", 10));
+
+        assert_eq!(
+            chunk_hash(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
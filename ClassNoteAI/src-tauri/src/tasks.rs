@@ -0,0 +1,536 @@
+//! Registry of long-running backend work.
+//!
+//! Downloads, document conversions, imports and exports each used to
+//! report on an event channel of their own (`download-progress-{type}`,
+//! `parakeet-download-progress`, `conversion-progress`, …) and most
+//! could not be stopped once started. Each of them now registers a
+//! [`Task`] here, which gives it an id, a [`TaskInfo`] snapshot that
+//! `list_tasks` returns, and a cancel flag that `cancel_task` sets.
+//! Every change goes out as one [`TaskInfo`] on [`EVENT`]; progress
+//! updates are throttled to [`PROGRESS_INTERVAL`].
+//!
+//! Async work is wrapped in [`Task::run`], which drops the future as
+//! soon as the task is cancelled (a dropped download keeps its partial
+//! file, so the next attempt resumes it). Blocking work polls
+//! [`Task::cancel_flag`] itself. Pausing is a cancel that ends in
+//! [`TaskState::Paused`] instead; the owner restarts the work under the
+//! same id (see `downloads::control`).
+//!
+//! The feature-specific events are still emitted alongside for the
+//! screens that listen to them.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::Notify;
+
+pub const EVENT: &str = "task-updated";
+
+/// Error a task fails with when it was cancelled.
+pub const CANCELLED: &str = "已取消";
+
+/// Error a task fails with when it was paused.
+pub const PAUSED: &str = "已暫停";
+
+/// Minimum gap between two progress-only events for one task. Work that
+/// also reports on an event of its own throttles that to the same gap.
+pub const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Finished tasks kept for `list_tasks`; the oldest are dropped first.
+const KEEP_FINISHED: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskKind {
+    Download,
+    Conversion,
+    Import,
+    Export,
+    /// Moving the app data directory.
+    Relocation,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskState {
+    /// Waiting behind other work (e.g. the conversion queue).
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+    /// Stopped by `pause`; resuming starts a new run under the same id.
+    Paused,
+}
+
+impl TaskState {
+    /// No longer running, so the id can be registered again.
+    pub fn is_finished(self) -> bool {
+        matches!(
+            self,
+            Self::Completed | Self::Failed | Self::Cancelled | Self::Paused
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TaskInfo {
+    pub id: String,
+    pub kind: TaskKind,
+    /// Short description, e.g. the model or file name.
+    pub label: String,
+    pub state: TaskState,
+    /// 0..=1; `None` while the total isn't known.
+    pub progress: Option<f64>,
+    /// Current step or position, whatever the task reports.
+    pub detail: Option<String>,
+    pub error: Option<String>,
+    /// Whether `cancel_task` does anything for this task.
+    pub cancellable: bool,
+    /// Whether it can be paused and resumed later.
+    pub pausable: bool,
+    /// RFC 3339, local time.
+    pub started_at: String,
+    pub finished_at: Option<String>,
+}
+
+/// Cancel flag shared by a [`Task`] and its registry entry.
+#[derive(Default)]
+struct Control {
+    cancelled: AtomicBool,
+    /// Set before `cancelled` when the cancel is a pause.
+    paused: AtomicBool,
+    notify: Notify,
+}
+
+impl Control {
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        self.notify.notify_waiters();
+    }
+
+    fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+        self.cancel();
+    }
+}
+
+struct Entry {
+    info: TaskInfo,
+    control: Arc<Control>,
+    /// Registration order, so finished tasks are pruned oldest first.
+    seq: u64,
+}
+
+#[derive(Default)]
+struct Registry {
+    entries: HashMap<String, Entry>,
+    next_seq: u64,
+}
+
+impl Registry {
+    fn insert(&mut self, info: TaskInfo) -> Result<Arc<Control>, String> {
+        if self
+            .entries
+            .get(&info.id)
+            .is_some_and(|e| !e.info.state.is_finished())
+        {
+            return Err(format!("任務 {} 已在執行中", info.id));
+        }
+        let control = Arc::new(Control::default());
+        self.next_seq += 1;
+        self.entries.insert(
+            info.id.clone(),
+            Entry {
+                info,
+                control: control.clone(),
+                seq: self.next_seq,
+            },
+        );
+        self.prune();
+        Ok(control)
+    }
+
+    fn update(&mut self, id: &str, f: impl FnOnce(&mut TaskInfo)) -> Option<TaskInfo> {
+        let entry = self.entries.get_mut(id)?;
+        f(&mut entry.info);
+        let info = entry.info.clone();
+        if info.state.is_finished() {
+            self.prune();
+        }
+        Some(info)
+    }
+
+    /// `Ok(false)` if the task is unknown or already finished.
+    fn cancel(&self, id: &str) -> Result<bool, String> {
+        match self.entries.get(id) {
+            Some(e) if e.info.state.is_finished() => Ok(false),
+            Some(e) if !e.info.cancellable => Err(format!("任務「{}」無法取消", e.info.label)),
+            Some(e) => {
+                e.control.cancel();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// `Ok(false)` if the task is unknown or already finished.
+    fn pause(&self, id: &str) -> Result<bool, String> {
+        match self.entries.get(id) {
+            Some(e) if e.info.state.is_finished() => Ok(false),
+            Some(e) if !e.info.pausable => Err(format!("任務「{}」無法暫停", e.info.label)),
+            Some(e) => {
+                e.control.pause();
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Unfinished tasks first, then finished ones; newest first within each.
+    fn list(&self) -> Vec<TaskInfo> {
+        let mut entries: Vec<&Entry> = self.entries.values().collect();
+        entries.sort_by_key(|e| (e.info.state.is_finished(), std::cmp::Reverse(e.seq)));
+        entries.into_iter().map(|e| e.info.clone()).collect()
+    }
+
+    fn prune(&mut self) {
+        let mut finished: Vec<(u64, String)> = self
+            .entries
+            .values()
+            .filter(|e| e.info.state.is_finished())
+            .map(|e| (e.seq, e.info.id.clone()))
+            .collect();
+        if finished.len() <= KEEP_FINISHED {
+            return;
+        }
+        finished.sort();
+        let excess = finished.len() - KEEP_FINISHED;
+        for (_, id) in finished.into_iter().take(excess) {
+            self.entries.remove(&id);
+        }
+    }
+}
+
+static REGISTRY: OnceLock<Mutex<Registry>> = OnceLock::new();
+
+fn registry() -> std::sync::MutexGuard<'static, Registry> {
+    REGISTRY
+        .get_or_init(|| Mutex::new(Registry::default()))
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+}
+
+pub fn list() -> Vec<TaskInfo> {
+    registry().list()
+}
+
+/// Ask a running task to stop. Returns `false` if it isn't running.
+pub fn cancel(id: &str) -> Result<bool, String> {
+    registry().cancel(id)
+}
+
+/// Stop a running task so it can be resumed later. Returns `false` if
+/// it isn't running.
+pub fn pause(id: &str) -> Result<bool, String> {
+    registry().pause(id)
+}
+
+/// Handle held by the code doing the work. Dropping it without
+/// [`Task::finish`] marks the task failed (or cancelled), so an early
+/// `?` return can't leave it "running" forever.
+pub struct Task {
+    id: String,
+    app: AppHandle,
+    control: Arc<Control>,
+    finished: AtomicBool,
+    last_emit: Mutex<Option<Instant>>,
+}
+
+impl Task {
+    /// Register a task under a fresh id; it starts out running.
+    pub fn start(app: &AppHandle, kind: TaskKind, label: impl Into<String>) -> Self {
+        Self::start_with_id(app, uuid::Uuid::new_v4().to_string(), kind, label)
+            .expect("fresh task id is unique")
+    }
+
+    /// Register a task under a caller-chosen id (e.g. a conversion job
+    /// id the frontend already knows).
+    pub fn start_with_id(
+        app: &AppHandle,
+        id: String,
+        kind: TaskKind,
+        label: impl Into<String>,
+    ) -> Result<Self, String> {
+        let info = TaskInfo {
+            id: id.clone(),
+            kind,
+            label: label.into(),
+            state: TaskState::Running,
+            progress: None,
+            detail: None,
+            error: None,
+            cancellable: false,
+            pausable: false,
+            started_at: chrono::Local::now().to_rfc3339(),
+            finished_at: None,
+        };
+        let control = registry().insert(info.clone())?;
+        let _ = app.emit(EVENT, &info);
+        Ok(Self {
+            id,
+            app: app.clone(),
+            control,
+            finished: AtomicBool::new(false),
+            last_emit: Mutex::new(None),
+        })
+    }
+
+    /// Mark the task as stoppable by `cancel_task`. Only for work that
+    /// actually watches [`Self::cancel_flag`] or runs under [`Self::run`].
+    pub fn cancellable(self) -> Self {
+        self.update(true, |info| info.cancellable = true);
+        self
+    }
+
+    /// Mark the task as stoppable by `pause`. The owner has to be able to
+    /// start it again where it left off; implies [`Self::cancellable`].
+    pub fn pausable(self) -> Self {
+        self.update(true, |info| {
+            info.cancellable = true;
+            info.pausable = true;
+        });
+        self
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.control.cancelled.load(Ordering::SeqCst)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.control.paused.load(Ordering::SeqCst)
+    }
+
+    /// For blocking code that polls for cancellation.
+    pub fn cancel_flag(&self) -> &AtomicBool {
+        &self.control.cancelled
+    }
+
+    /// Resolves once the task has been cancelled.
+    pub async fn cancelled(&self) {
+        loop {
+            let notified = self.control.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+
+    pub fn set_queued(&self, detail: Option<String>) {
+        self.update(true, |info| {
+            info.state = TaskState::Queued;
+            info.detail = detail;
+        });
+    }
+
+    /// Report a new step; moves a queued task to running.
+    pub fn set_detail(&self, detail: Option<String>) {
+        self.update(true, |info| {
+            info.state = TaskState::Running;
+            info.detail = detail;
+        });
+    }
+
+    /// Byte- or item-count progress. Throttled.
+    pub fn set_progress(&self, done: u64, total: u64) {
+        let fraction = (total > 0).then(|| (done as f64 / total as f64).clamp(0.0, 1.0));
+        self.set_fraction(fraction);
+    }
+
+    /// Fractional progress. Throttled.
+    pub fn set_fraction(&self, fraction: Option<f64>) {
+        self.update(false, |info| {
+            info.state = TaskState::Running;
+            info.progress = fraction.map(|f| f.clamp(0.0, 1.0));
+        });
+    }
+
+    /// Record the outcome. Only the first call counts.
+    pub fn finish<T>(&self, result: &Result<T, String>) {
+        if self.finished.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let cancelled = self.is_cancelled();
+        let paused = self.is_paused();
+        self.update(true, |info| {
+            info.finished_at = Some(chrono::Local::now().to_rfc3339());
+            match result {
+                Ok(_) => {
+                    info.state = TaskState::Completed;
+                    info.progress = Some(1.0);
+                }
+                Err(_) if paused => info.state = TaskState::Paused,
+                Err(_) if cancelled => info.state = TaskState::Cancelled,
+                Err(e) => {
+                    info.state = TaskState::Failed;
+                    info.error = Some(e.clone());
+                }
+            }
+        });
+    }
+
+    /// Await `work` unless the task is cancelled first, in which case
+    /// `work` is dropped and the result is [`CANCELLED`] (or [`PAUSED`]).
+    /// Finishes the task either way, and makes it cancellable while it
+    /// runs.
+    pub async fn run<T, F>(&self, work: F) -> Result<T, String>
+    where
+        F: Future<Output = Result<T, String>>,
+    {
+        self.update(true, |info| info.cancellable = true);
+        let result = tokio::select! {
+            result = work => result,
+            _ = self.cancelled() => {
+                Err(if self.is_paused() { PAUSED } else { CANCELLED }.to_string())
+            }
+        };
+        self.finish(&result);
+        result
+    }
+
+    fn update(&self, force: bool, f: impl FnOnce(&mut TaskInfo)) {
+        if !force {
+            let mut last = self.last_emit.lock().unwrap_or_else(|p| p.into_inner());
+            if last.is_some_and(|t| t.elapsed() < PROGRESS_INTERVAL) {
+                // Keep the snapshot current; only the event is skipped.
+                registry().update(&self.id, f);
+                return;
+            }
+            *last = Some(Instant::now());
+        }
+        if let Some(info) = registry().update(&self.id, f) {
+            let _ = self.app.emit(EVENT, &info);
+        }
+    }
+}
+
+impl Drop for Task {
+    fn drop(&mut self) {
+        if !self.finished.load(Ordering::SeqCst) {
+            self.finish::<()>(&Err("任務意外中止".to_string()));
+        }
+    }
+}
+
+/// Snapshot of every running task and the most recently finished ones.
+#[tauri::command]
+pub fn list_tasks() -> Vec<TaskInfo> {
+    list()
+}
+
+/// Stop a task. Returns `false` if it had already finished.
+#[tauri::command]
+pub fn cancel_task(id: String) -> Result<bool, String> {
+    cancel(&id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(id: &str, state: TaskState, cancellable: bool) -> TaskInfo {
+        TaskInfo {
+            id: id.to_string(),
+            kind: TaskKind::Download,
+            label: id.to_string(),
+            state,
+            progress: None,
+            detail: None,
+            error: None,
+            cancellable,
+            pausable: cancellable,
+            started_at: String::new(),
+            finished_at: None,
+        }
+    }
+
+    #[test]
+    fn cancel_sets_the_flag_of_running_cancellable_tasks_only() {
+        let mut registry = Registry::default();
+        let running = registry
+            .insert(info("a", TaskState::Running, true))
+            .unwrap();
+        registry
+            .insert(info("b", TaskState::Running, false))
+            .unwrap();
+        registry
+            .insert(info("c", TaskState::Completed, true))
+            .unwrap();
+
+        assert_eq!(registry.cancel("a"), Ok(true));
+        assert!(running.cancelled.load(Ordering::SeqCst));
+        assert!(registry.cancel("b").is_err());
+        assert_eq!(registry.cancel("c"), Ok(false));
+        assert_eq!(registry.cancel("missing"), Ok(false));
+    }
+
+    #[test]
+    fn pause_marks_the_cancel_as_a_pause() {
+        let mut registry = Registry::default();
+        let running = registry
+            .insert(info("a", TaskState::Running, true))
+            .unwrap();
+        let mut plain = info("b", TaskState::Running, true);
+        plain.pausable = false;
+        registry.insert(plain).unwrap();
+
+        assert_eq!(registry.pause("a"), Ok(true));
+        assert!(running.paused.load(Ordering::SeqCst));
+        assert!(running.cancelled.load(Ordering::SeqCst));
+        assert!(registry.pause("b").is_err());
+
+        registry.update("a", |i| i.state = TaskState::Paused);
+        assert_eq!(registry.pause("a"), Ok(false));
+        assert!(registry.insert(info("a", TaskState::Running, true)).is_ok());
+    }
+
+    #[test]
+    fn running_ids_are_unique_but_finished_ones_can_be_reused() {
+        let mut registry = Registry::default();
+        registry
+            .insert(info("a", TaskState::Running, true))
+            .unwrap();
+        assert!(registry
+            .insert(info("a", TaskState::Running, true))
+            .is_err());
+        registry.update("a", |i| i.state = TaskState::Failed);
+        assert!(registry.insert(info("a", TaskState::Running, true)).is_ok());
+    }
+
+    #[test]
+    fn list_puts_running_first_and_prunes_old_finished_tasks() {
+        let mut registry = Registry::default();
+        registry
+            .insert(info("live", TaskState::Running, true))
+            .unwrap();
+        for n in 0..KEEP_FINISHED + 5 {
+            registry
+                .insert(info(&format!("done-{n}"), TaskState::Completed, false))
+                .unwrap();
+        }
+        let listed = registry.list();
+        assert_eq!(listed.len(), KEEP_FINISHED + 1);
+        assert_eq!(listed[0].id, "live");
+        assert_eq!(listed[1].id, format!("done-{}", KEEP_FINISHED + 4));
+        assert!(!listed.iter().any(|t| t.id == "done-0"));
+    }
+}
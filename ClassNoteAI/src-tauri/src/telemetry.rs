@@ -0,0 +1,445 @@
+//! Opt-in, anonymous usage telemetry.
+//!
+//! Off until the user turns it on in Settings. When on, it collects
+//! exactly two things:
+//!
+//! - **counters** — how often a named event happened
+//!   (`pipeline.live.start`, `translation.failed`, …). Names are
+//!   validated against a tight charset so no transcript, path or
+//!   title can ride along in one;
+//! - **crash fingerprints** — a truncated SHA-256 of each new crash
+//!   report's panic location and digit-stripped message. The report
+//!   itself never leaves the machine.
+//!
+//! Counters accumulate in memory and are spooled into
+//! `{app_data_dir}/telemetry/queue.json` as batches (on every flush
+//! and on exit). A flush POSTs the queued batches to the configured
+//! endpoint and clears them on a 2xx; without an endpoint they just
+//! stay queued, capped at [`MAX_QUEUED_BATCHES`]. Each batch carries a
+//! random install id (not tied to the account), app version and
+//! platform — nothing else.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{crash, paths, storage};
+
+/// Settings keys. Telemetry is per install, so they're stored for the
+/// default user regardless of who is signed in.
+pub const ENABLED_SETTING: &str = "telemetry.enabled";
+pub const ENDPOINT_SETTING: &str = "telemetry.endpoint";
+const INSTALL_ID_SETTING: &str = "telemetry.install_id";
+/// Id of the newest crash report already fingerprinted.
+const LAST_CRASH_SETTING: &str = "telemetry.last_crash_id";
+const SETTINGS_USER: &str = "default_user";
+
+const QUEUE_FILE: &str = "queue.json";
+/// Oldest batches are dropped past this, so an install that opted in
+/// without an endpoint doesn't grow the queue forever.
+pub const MAX_QUEUED_BATCHES: usize = 50;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Batch {
+    pub install_id: String,
+    pub app_version: String,
+    pub platform: String,
+    /// RFC 3339 bounds of the period the counts cover.
+    pub period_start: String,
+    pub period_end: String,
+    pub counts: BTreeMap<String, u64>,
+    /// Crash fingerprint → occurrences.
+    pub crashes: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TelemetrySettings {
+    pub enabled: bool,
+    pub endpoint: Option<String>,
+    pub install_id: Option<String>,
+    pub queued_batches: usize,
+}
+
+#[derive(Default)]
+struct State {
+    enabled: bool,
+    endpoint: Option<String>,
+    install_id: String,
+    period_start: Option<String>,
+    counts: BTreeMap<String, u64>,
+    crashes: BTreeMap<String, u64>,
+}
+
+static STATE: OnceLock<Mutex<State>> = OnceLock::new();
+
+fn state() -> MutexGuard<'static, State> {
+    STATE
+        .get_or_init(|| Mutex::new(State::default()))
+        .lock()
+        .unwrap_or_else(|p| p.into_inner())
+}
+
+fn queue_path() -> Result<PathBuf, String> {
+    Ok(paths::get_telemetry_dir()?.join(QUEUE_FILE))
+}
+
+/// Count one occurrence of `name`. A no-op while telemetry is off or
+/// when `name` isn't a valid event name.
+pub fn record(name: &str) {
+    if !is_valid_event_name(name) {
+        log::warn!("[telemetry] rejected event name {:?}", name);
+        return;
+    }
+    let mut state = state();
+    if !state.enabled {
+        return;
+    }
+    if state.period_start.is_none() {
+        state.period_start = Some(chrono::Utc::now().to_rfc3339());
+    }
+    *state.counts.entry(name.to_string()).or_default() += 1;
+}
+
+/// `[a-z0-9._:-]{1,64}` — enough for dotted event names, too narrow
+/// for free text.
+pub fn is_valid_event_name(name: &str) -> bool {
+    (1..=64).contains(&name.len())
+        && name
+            .bytes()
+            .all(|b| matches!(b, b'a'..=b'z' | b'0'..=b'9' | b'.' | b'_' | b':' | b'-'))
+}
+
+/// Stable, content-free id for a crash: location plus the message with
+/// digit runs collapsed (indices and sizes vary between occurrences of
+/// the same bug), hashed and truncated.
+pub fn crash_fingerprint(report: &crash::CrashReport) -> String {
+    let mut message = String::with_capacity(report.message.len());
+    for c in report.message.chars() {
+        if c.is_ascii_digit() {
+            if !message.ends_with('#') {
+                message.push('#');
+            }
+        } else {
+            message.push(c);
+        }
+    }
+    let digest = Sha256::digest(format!(
+        "{}|{}",
+        report.location.as_deref().unwrap_or(""),
+        message
+    ));
+    digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Load settings, pick up crashes since the last run, and start the
+/// periodic flush. Called once the database is initialised.
+pub async fn init() {
+    if let Err(e) = load_settings().await {
+        log::warn!("[telemetry] init skipped: {}", e);
+        return;
+    }
+    if let Err(e) = collect_new_crashes().await {
+        log::warn!("[telemetry] crash scan failed: {}", e);
+    }
+    tauri::async_runtime::spawn(async {
+        let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = flush().await {
+                log::warn!("[telemetry] flush failed: {}", e);
+            }
+        }
+    });
+}
+
+async fn load_settings() -> Result<(), String> {
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let get = |key: &str| db.get_setting(key, SETTINGS_USER).ok().flatten();
+
+    let mut state = state();
+    state.enabled = get(ENABLED_SETTING).as_deref() == Some("true");
+    state.endpoint = get(ENDPOINT_SETTING).filter(|e| !e.trim().is_empty());
+    state.install_id = get(INSTALL_ID_SETTING).unwrap_or_default();
+    Ok(())
+}
+
+async fn collect_new_crashes() -> Result<(), String> {
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let last = db
+        .get_setting(LAST_CRASH_SETTING, SETTINGS_USER)
+        .map_err(|e| e.to_string())?;
+    let reports = crash::reports_after(&crash::crash_dir()?, last.as_deref());
+    let Some(newest) = reports.last() else {
+        return Ok(());
+    };
+
+    {
+        // Crashes from before the opt-in are skipped, not saved for later.
+        let mut state = state();
+        if state.enabled {
+            for report in &reports {
+                *state.crashes.entry(crash_fingerprint(report)).or_default() += 1;
+            }
+            if state.period_start.is_none() {
+                state.period_start = Some(chrono::Utc::now().to_rfc3339());
+            }
+        }
+    }
+    db.save_setting(LAST_CRASH_SETTING, &newest.id, SETTINGS_USER)
+        .map_err(|e| e.to_string())
+}
+
+/// Current settings plus how many batches are waiting.
+pub fn settings() -> TelemetrySettings {
+    let queued_batches = queue_path()
+        .map(|path| read_queue(&path).len())
+        .unwrap_or(0);
+    let state = state();
+    TelemetrySettings {
+        enabled: state.enabled,
+        endpoint: state.endpoint.clone(),
+        install_id: (!state.install_id.is_empty()).then(|| state.install_id.clone()),
+        queued_batches,
+    }
+}
+
+/// Turn telemetry on or off and set the upload endpoint. Turning it
+/// off drops everything collected so far; turning it on for the first
+/// time generates the install id.
+pub async fn configure(
+    enabled: bool,
+    endpoint: Option<String>,
+) -> Result<TelemetrySettings, String> {
+    let endpoint = endpoint
+        .map(|e| e.trim().to_string())
+        .filter(|e| !e.is_empty());
+    if let Some(url) = &endpoint {
+        let parsed = reqwest::Url::parse(url).map_err(|e| format!("無效的 endpoint: {}", e))?;
+        if parsed.scheme() != "https" && parsed.host_str() != Some("localhost") {
+            return Err("endpoint 必須使用 https".to_string());
+        }
+    }
+
+    let manager = storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    let mut install_id = db
+        .get_setting(INSTALL_ID_SETTING, SETTINGS_USER)
+        .map_err(|e| e.to_string())?
+        .unwrap_or_default();
+    if enabled && install_id.is_empty() {
+        install_id = uuid::Uuid::new_v4().to_string();
+        db.save_setting(INSTALL_ID_SETTING, &install_id, SETTINGS_USER)
+            .map_err(|e| e.to_string())?;
+    }
+    db.save_setting(
+        ENABLED_SETTING,
+        if enabled { "true" } else { "false" },
+        SETTINGS_USER,
+    )
+    .map_err(|e| e.to_string())?;
+    db.save_setting(
+        ENDPOINT_SETTING,
+        endpoint.as_deref().unwrap_or(""),
+        SETTINGS_USER,
+    )
+    .map_err(|e| e.to_string())?;
+
+    {
+        let mut state = state();
+        state.enabled = enabled;
+        state.endpoint = endpoint;
+        state.install_id = install_id;
+        if !enabled {
+            state.period_start = None;
+            state.counts.clear();
+            state.crashes.clear();
+        }
+    }
+    if !enabled {
+        if let Ok(path) = queue_path() {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+    Ok(settings())
+}
+
+/// Move the in-memory counters into the on-disk queue.
+pub fn spool() -> Result<(), String> {
+    let batch = {
+        let mut state = state();
+        if !state.enabled || (state.counts.is_empty() && state.crashes.is_empty()) {
+            return Ok(());
+        }
+        Batch {
+            install_id: state.install_id.clone(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            platform: format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH),
+            period_start: state
+                .period_start
+                .take()
+                .unwrap_or_else(|| chrono::Utc::now().to_rfc3339()),
+            period_end: chrono::Utc::now().to_rfc3339(),
+            counts: std::mem::take(&mut state.counts),
+            crashes: std::mem::take(&mut state.crashes),
+        }
+    };
+    append_to_queue(&queue_path()?, batch)
+}
+
+/// Spool, then upload everything queued. Returns the number of batches
+/// sent (0 when telemetry is off or no endpoint is configured).
+pub async fn flush() -> Result<usize, String> {
+    spool()?;
+    let endpoint = {
+        let state = state();
+        if !state.enabled {
+            return Ok(0);
+        }
+        match &state.endpoint {
+            Some(endpoint) => endpoint.clone(),
+            None => return Ok(0),
+        }
+    };
+    let path = queue_path()?;
+    let batches = read_queue(&path);
+    if batches.is_empty() {
+        return Ok(0);
+    }
+
+    let client = reqwest::Client::builder()
+        .timeout(UPLOAD_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resp = client
+        .post(&endpoint)
+        .json(&serde_json::json!({ "batches": batches }))
+        .send()
+        .await
+        .map_err(|e| format!("telemetry upload: {}", e))?;
+    if !resp.status().is_success() {
+        return Err(format!("telemetry upload: HTTP {}", resp.status()));
+    }
+
+    // Batches spooled while the upload was in flight stay queued.
+    let sent = batches.len();
+    let remaining: Vec<Batch> = read_queue(&path).into_iter().skip(sent).collect();
+    write_queue(&path, &remaining)?;
+    Ok(sent)
+}
+
+fn read_queue(path: &Path) -> Vec<Batch> {
+    std::fs::read(path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn write_queue(path: &Path, batches: &[Batch]) -> Result<(), String> {
+    if batches.is_empty() {
+        return match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("remove {}: {}", path.display(), e))
+            }
+            _ => Ok(()),
+        };
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("create {}: {}", parent.display(), e))?;
+    }
+    let json = serde_json::to_vec(batches).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| format!("write {}: {}", path.display(), e))
+}
+
+fn append_to_queue(path: &Path, batch: Batch) -> Result<(), String> {
+    let mut batches = read_queue(path);
+    batches.push(batch);
+    let excess = batches.len().saturating_sub(MAX_QUEUED_BATCHES);
+    write_queue(path, &batches[excess..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn batch(n: u64) -> Batch {
+        Batch {
+            install_id: "00000000-0000-0000-0000-000000000000".into(),
+            app_version: "0.7.1".into(),
+            platform: "macos-aarch64".into(),
+            period_start: "2026-10-15T00:00:00Z".into(),
+            period_end: "2026-10-15T01:00:00Z".into(),
+            counts: BTreeMap::from([("pipeline.live.start".to_string(), n)]),
+            crashes: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn event_names_cannot_carry_free_text() {
+        assert!(is_valid_event_name("pipeline.live.start"));
+        assert!(is_valid_event_name("translation.failed:gemma"));
+        assert!(!is_valid_event_name(""));
+        assert!(!is_valid_event_name("Lecture 3 notes"));
+        assert!(!is_valid_event_name("/Users/alice/audio.wav"));
+        assert!(!is_valid_event_name(&"a".repeat(65)));
+    }
+
+    #[test]
+    fn crash_fingerprint_ignores_varying_numbers() {
+        let mut report = crash::CrashReport {
+            id: "crash-20261015-093012-417".into(),
+            created_at: "2026-10-15T09:30:12+08:00".into(),
+            app_version: "0.7.1".into(),
+            platform: "macos-aarch64".into(),
+            thread: "main".into(),
+            message: "index out of bounds: the len is 3 but the index is 17".into(),
+            location: Some("src/pipeline/mod.rs:120:9".into()),
+            backtrace: String::new(),
+            loaded_models: vec![],
+        };
+        let first = crash_fingerprint(&report);
+        report.message = "index out of bounds: the len is 12 but the index is 400".into();
+        assert_eq!(crash_fingerprint(&report), first);
+        assert_eq!(first.len(), 16);
+
+        report.location = Some("src/pipeline/mod.rs:121:9".into());
+        assert_ne!(crash_fingerprint(&report), first);
+    }
+
+    #[test]
+    fn queue_keeps_only_the_newest_batches() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("telemetry").join(QUEUE_FILE);
+        assert!(read_queue(&path).is_empty());
+
+        for n in 0..(MAX_QUEUED_BATCHES as u64 + 2) {
+            append_to_queue(&path, batch(n)).unwrap();
+        }
+        let queued = read_queue(&path);
+        assert_eq!(queued.len(), MAX_QUEUED_BATCHES);
+        assert_eq!(queued[0], batch(2));
+
+        write_queue(&path, &[]).unwrap();
+        assert!(!path.exists());
+    }
+}
@@ -26,7 +26,7 @@ use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
 
 use crate::paths;
-use crate::whisper::download::ModelDownloadConfig;
+use crate::whisper::download::{hugging_face_config, ModelDownloadConfig};
 
 /// TranslateGemma quant variant selectors. Q4_K_M GGUFs sourced from
 /// SandLogicTechnologies's HuggingFace mirror that matches our 4B
@@ -133,17 +133,17 @@ pub fn is_present_for(variant: Variant) -> bool {
 }
 
 pub fn download_config_for(variant: Variant) -> Result<ModelDownloadConfig, String> {
-    Ok(ModelDownloadConfig {
-        url: variant.url().to_string(),
-        output_path: target_path_for(variant)?,
+    Ok(hugging_face_config(
+        variant.url(),
+        target_path_for(variant)?,
         // For 12B/27B we leave expected_size unset to disable strict
         // size verification — the ±5% guard in is_present_for handles
         // post-download integrity. For 4B keep the strict check.
-        expected_size: match variant {
+        match variant {
             Variant::B4 => Some(variant.expected_size()),
             Variant::B12 | Variant::B27 => None,
         },
-    })
+    ))
 }
 
 /// Return the first variant that's already on disk. Used by the sidecar
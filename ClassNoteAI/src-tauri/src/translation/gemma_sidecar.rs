@@ -72,6 +72,23 @@ fn spawn_lock() -> &'static Mutex<()> {
     SPAWN_LOCK.get_or_init(|| Mutex::new(()))
 }
 
+/// OS process id of the supervised sidecar, if one was spawned.
+pub fn child_pid() -> Option<u32> {
+    let guard = child_lock().lock().unwrap_or_else(|p| p.into_inner());
+    guard.as_ref().map(|child| child.id())
+}
+
+/// Whether we hold a sidecar handle, without waiting for the lock or
+/// reaping an exited child. `None` when the lock is busy. For the crash
+/// reporter; everything else wants [`is_running`].
+pub fn try_has_child() -> Option<bool> {
+    match child_lock().try_lock() {
+        Ok(guard) => Some(guard.is_some()),
+        Err(std::sync::TryLockError::Poisoned(p)) => Some(p.into_inner().is_some()),
+        Err(std::sync::TryLockError::WouldBlock) => None,
+    }
+}
+
 /// True if a sidecar process is currently running under our supervision.
 /// Cheap probe; doesn't HTTP-check.
 pub fn is_running() -> bool {
@@ -431,6 +448,7 @@ pub async fn ensure_running(
     // 3. Wait for /health
     if wait_for_health(port).await {
         println!("[gemma_sidecar] sidecar ready on :{port}");
+        crate::downloads::installed::mark_used(std::path::Path::new(model_path));
         match decision {
             SpawnDecision::JustSpawned => BringUpResult::Spawned,
             SpawnDecision::AlreadySpawned => BringUpResult::AlreadyRunning,
@@ -449,7 +467,7 @@ pub async fn ensure_running(
 /// `None` when we can't determine an app-data dir — caller then falls
 /// back to inheriting the parent's stderr (visible in dev terminals).
 pub fn sidecar_log_path() -> Option<PathBuf> {
-    let dir = paths::get_app_data_dir().ok()?.join("logs");
+    let dir = paths::get_logs_dir().ok()?;
     if let Err(e) = std::fs::create_dir_all(&dir) {
         eprintln!("[gemma_sidecar] mkdir {} failed: {e}", dir.display());
         return None;
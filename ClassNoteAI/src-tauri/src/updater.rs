@@ -1,7 +1,19 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::cmp::Ordering;
+use std::path::PathBuf;
+use std::time::Duration;
 use tauri::{AppHandle, Emitter};
-use tauri_plugin_updater::UpdaterExt;
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+/// Release channels, most stable first. Each publishes its own
+/// `latest.json`; a channel also gets the releases of the ones before it.
+const CHANNELS: [&str; 3] = ["stable", "beta", "alpha"];
+const CHANNEL_SETTING: &str = "update.channel";
+const SETTINGS_USER: &str = "default_user";
+/// GitHub release of a tag, for notes the update manifest doesn't carry.
+const RELEASES_API: &str = "https://api.github.com/repos/sklonely/ClassNoteAI/releases/tags";
+const NOTES_TIMEOUT: Duration = Duration::from_secs(15);
 
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -12,10 +24,108 @@ pub struct UpdateCheckResult {
     pub date: Option<String>,
 }
 
+/// What changed in a release. `migration_warnings` are the entries of
+/// the notes' data migration section, for the update dialog to show
+/// before the user installs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReleaseNotes {
+    pub version: String,
+    pub notes: String,
+    pub date: Option<String>,
+    pub migration_warnings: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    body: Option<String>,
+    published_at: Option<String>,
+}
+
 fn validate_channel(channel: &str) -> Result<(), String> {
-    match channel {
-        "stable" | "beta" | "alpha" => Ok(()),
-        _ => Err(format!("invalid update channel: {}", channel)),
+    channels_through(channel).map(|_| ())
+}
+
+/// The channels whose releases `channel` is offered: itself and the
+/// more stable ones, so a tester on beta still gets a stable release
+/// that is newer than the last beta.
+fn channels_through(channel: &str) -> Result<&'static [&'static str], String> {
+    CHANNELS
+        .iter()
+        .position(|c| *c == channel)
+        .map(|i| &CHANNELS[..=i])
+        .ok_or_else(|| format!("invalid update channel: {}", channel))
+}
+
+/// Order `major.minor.patch[-pre]` versions, a leading `v` allowed. A
+/// pre-release sorts before its release; its dot-separated identifiers
+/// compare numerically when both are numbers. Unparseable parts count
+/// as 0.
+fn compare_versions(a: &str, b: &str) -> Ordering {
+    fn split(version: &str) -> (Vec<u64>, Option<&str>) {
+        let version = version.trim().trim_start_matches('v');
+        let version = version.split('+').next().unwrap_or_default();
+        let (core, pre) = match version.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (version, None),
+        };
+        let core = core.split('.').map(|n| n.parse().unwrap_or(0)).collect();
+        (core, pre)
+    }
+
+    let (core_a, pre_a) = split(a);
+    let (core_b, pre_b) = split(b);
+    let len = core_a.len().max(core_b.len());
+    for i in 0..len {
+        let ord = core_a.get(i).unwrap_or(&0).cmp(core_b.get(i).unwrap_or(&0));
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+    match (pre_a, pre_b) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(pa), Some(pb)) => {
+            let mut ids_a = pa.split('.');
+            let mut ids_b = pb.split('.');
+            loop {
+                let ord = match (ids_a.next(), ids_b.next()) {
+                    (None, None) => return Ordering::Equal,
+                    (None, Some(_)) => return Ordering::Less,
+                    (Some(_), None) => return Ordering::Greater,
+                    (Some(x), Some(y)) => match (x.parse::<u64>(), y.parse::<u64>()) {
+                        (Ok(x), Ok(y)) => x.cmp(&y),
+                        (Ok(_), Err(_)) => Ordering::Less,
+                        (Err(_), Ok(_)) => Ordering::Greater,
+                        (Err(_), Err(_)) => x.cmp(y),
+                    },
+                };
+                if ord != Ordering::Equal {
+                    return ord;
+                }
+            }
+        }
+    }
+}
+
+/// The channel saved with [`set_update_channel`]; `stable` until one
+/// is, or when the setting can't be read.
+pub async fn saved_channel() -> String {
+    let saved = match crate::storage::get_db_manager().await {
+        Ok(manager) => manager
+            .get_db()
+            .and_then(|db| db.get_setting(CHANNEL_SETTING, SETTINGS_USER))
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    match saved {
+        Ok(Some(channel)) if validate_channel(&channel).is_ok() => channel,
+        Ok(_) => "stable".to_string(),
+        Err(e) => {
+            log::warn!("[updater] could not read the update channel: {}", e);
+            "stable".to_string()
+        }
     }
 }
 
@@ -30,7 +140,10 @@ fn updater_target() -> Option<String> {
 fn build_updater(app: &AppHandle, channel: &str) -> Result<tauri_plugin_updater::Updater, String> {
     validate_channel(channel)?;
 
-    let mut builder = app.updater_builder();
+    // Windows exits straight into the installer without a `RunEvent::Exit`.
+    let mut builder = app.updater_builder().on_before_exit(|| {
+        crate::shutdown::run();
+    });
     if let Some(target) = updater_target() {
         builder = builder.target(target);
     }
@@ -49,13 +162,68 @@ fn build_updater(app: &AppHandle, channel: &str) -> Result<tauri_plugin_updater:
         .map_err(|e| format!("{}", e))
 }
 
+/// The newest update offered to `channel` across its manifest and those
+/// of the more stable channels. Only a failure to fetch `channel`'s own
+/// manifest is an error; the others are skipped.
+async fn newest_update(app: &AppHandle, channel: &str) -> Result<Option<Update>, String> {
+    let mut newest: Option<Update> = None;
+    for candidate in channels_through(channel)? {
+        let update = match build_updater(app, candidate)?.check().await {
+            Ok(update) => update,
+            Err(e) if *candidate == channel => return Err(format!("{}", e)),
+            Err(e) => {
+                log::warn!("[updater] {} manifest unavailable: {}", candidate, e);
+                continue;
+            }
+        };
+        if let Some(update) = update {
+            let is_newer = match &newest {
+                Some(n) => compare_versions(&update.version, &n.version) == Ordering::Greater,
+                None => true,
+            };
+            if is_newer {
+                newest = Some(update);
+            }
+        }
+    }
+    Ok(newest)
+}
+
+async fn resolve_channel(channel: Option<String>) -> String {
+    match channel {
+        Some(channel) => channel,
+        None => saved_channel().await,
+    }
+}
+
+/// Switch the channel updates come from; checks that don't name one
+/// use it.
+#[tauri::command]
+pub async fn set_update_channel(channel: String) -> Result<String, String> {
+    validate_channel(&channel)?;
+    let manager = crate::storage::get_db_manager()
+        .await
+        .map_err(|e| format!("數據庫未初始化: {}", e))?;
+    let db = manager
+        .get_db()
+        .map_err(|e| format!("數據庫連接失敗: {}", e))?;
+    db.save_setting(CHANNEL_SETTING, &channel, SETTINGS_USER)
+        .map_err(|e| e.to_string())?;
+    Ok(channel)
+}
+
+#[tauri::command]
+pub async fn get_update_channel() -> String {
+    saved_channel().await
+}
+
 #[tauri::command]
 pub async fn check_update_for_channel(
     app: AppHandle,
-    channel: String,
+    channel: Option<String>,
 ) -> Result<UpdateCheckResult, String> {
-    let updater = build_updater(&app, &channel)?;
-    let update = updater.check().await.map_err(|e| format!("{}", e))?;
+    let channel = resolve_channel(channel).await;
+    let update = newest_update(&app, &channel).await?;
 
     Ok(match update {
         Some(update) => UpdateCheckResult {
@@ -73,15 +241,138 @@ pub async fn check_update_for_channel(
     })
 }
 
-#[tauri::command]
-pub async fn download_and_install_update(app: AppHandle, channel: String) -> Result<(), String> {
-    let updater = build_updater(&app, &channel)?;
-    let update = updater
-        .check()
+/// Where the notes of `version` are cached. Released notes don't change,
+/// so a cached copy is used as is.
+fn notes_cache_path(version: &str) -> Result<PathBuf, String> {
+    let valid = !version.is_empty()
+        && version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'));
+    if !valid {
+        return Err(format!("invalid version: {}", version));
+    }
+    Ok(crate::paths::get_cache_dir()?
+        .join("release_notes")
+        .join(format!("{}.json", version)))
+}
+
+fn read_cached_notes(version: &str) -> Option<ReleaseNotes> {
+    let json = std::fs::read_to_string(notes_cache_path(version).ok()?).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn cache_notes(notes: &ReleaseNotes) {
+    let result = notes_cache_path(&notes.version).and_then(|path| {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string(notes).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json).map_err(|e| e.to_string())
+    });
+    if let Err(e) = result {
+        log::warn!("[updater] release notes not cached: {}", e);
+    }
+}
+
+/// The notes and date of the GitHub release tagged `v{version}`.
+async fn fetch_release_notes(version: &str) -> Result<(String, Option<String>), String> {
+    let client = crate::downloads::proxy::client_builder()
+        .timeout(NOTES_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let release: GithubRelease = client
+        .get(format!("{}/v{}", RELEASES_API, version))
+        .header("User-Agent", "ClassNoteAI")
+        .header("Accept", "application/vnd.github+json")
+        .send()
         .await
-        .map_err(|e| format!("{}", e))?
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| format!("無法取得版本說明: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("無法解析版本說明: {}", e))?;
+    Ok((release.body.unwrap_or_default(), release.published_at))
+}
+
+/// The list entries under a Markdown heading about data migration
+/// (`Migration`, `遷移`, `資料轉移`), in order.
+fn migration_warnings(notes: &str) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let mut in_section = false;
+    for line in notes.lines() {
+        let line = line.trim();
+        if line.starts_with('#') {
+            let heading = line.to_lowercase();
+            in_section = ["migration", "遷移", "資料轉移"]
+                .iter()
+                .any(|word| heading.contains(word));
+        } else if in_section {
+            if let Some(entry) = line.strip_prefix("- ").or_else(|| line.strip_prefix("* ")) {
+                warnings.push(entry.trim().to_string());
+            }
+        }
+    }
+    warnings
+}
+
+/// The release notes of `version`, or of the update the saved channel
+/// offers when it's omitted (`None` when there is no update). Notes come
+/// from the update manifest, or from the GitHub release when the
+/// manifest has none or another version is asked for, and are cached.
+#[tauri::command]
+pub async fn get_release_notes(
+    app: AppHandle,
+    version: Option<String>,
+) -> Result<Option<ReleaseNotes>, String> {
+    let (version, from_manifest) = match version {
+        Some(version) => (version.trim().trim_start_matches('v').to_string(), None),
+        None => {
+            let channel = saved_channel().await;
+            let Some(update) = newest_update(&app, &channel).await? else {
+                return Ok(None);
+            };
+            let date = update.date.map(|date| date.to_string());
+            (update.version, update.body.map(|body| (body, date)))
+        }
+    };
+    if let Some(cached) = read_cached_notes(&version) {
+        return Ok(Some(cached));
+    }
+    // Reject a malformed version before it goes into a URL.
+    notes_cache_path(&version)?;
+
+    let (notes, date) = match from_manifest {
+        Some((body, date)) if !body.trim().is_empty() => (body, date),
+        _ => fetch_release_notes(&version).await?,
+    };
+    let notes = ReleaseNotes {
+        migration_warnings: migration_warnings(&notes),
+        version,
+        notes,
+        date,
+    };
+    cache_notes(&notes);
+    Ok(Some(notes))
+}
+
+#[tauri::command]
+pub async fn download_and_install_update(
+    app: AppHandle,
+    channel: Option<String>,
+) -> Result<(), String> {
+    let channel = resolve_channel(channel).await;
+    let update = newest_update(&app, &channel)
+        .await?
         .ok_or_else(|| "No update available.".to_string())?;
 
+    // Nothing installs without a backup of the data an update-time
+    // crash could damage.
+    let version = update.version.clone();
+    tokio::task::spawn_blocking(move || crate::storage::backup::backup_before_update(&version))
+        .await
+        .map_err(|e| format!("backup task join error: {}", e))?
+        .map_err(|e| format!("更新前備份失敗，已取消更新: {}", e))?;
+
     update
         .download_and_install(
             |chunk_length, content_length| {
@@ -100,5 +391,48 @@ pub async fn download_and_install_update(app: AppHandle, channel: String) -> Res
         .await
         .map_err(|e| format!("{}", e))?;
 
+    // `restart` skips `RunEvent::Exit`, so release the models here.
+    crate::shutdown::run();
     app.restart();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn channels_include_the_more_stable_ones() {
+        assert_eq!(channels_through("stable").unwrap(), ["stable"]);
+        assert_eq!(channels_through("beta").unwrap(), ["stable", "beta"]);
+        assert_eq!(channels_through("alpha").unwrap().len(), 3);
+        assert!(channels_through("nightly").is_err());
+    }
+
+    #[test]
+    fn migration_warnings_come_from_the_migration_section() {
+        let notes = "## 新功能\n- 雲端同步\n\n### ⚠ 資料遷移 (Data migration)\n\
+                     - 首次啟動會重建搜尋索引，約需數分鐘\n\
+                     * 舊版備份無法還原到此版本\n\n## 修正\n- 修正錄音中斷\n";
+        assert_eq!(
+            migration_warnings(notes),
+            [
+                "首次啟動會重建搜尋索引，約需數分鐘",
+                "舊版備份無法還原到此版本"
+            ]
+        );
+        assert!(migration_warnings("## Fixes\n- crash on start").is_empty());
+        assert!(notes_cache_path("../../etc").is_err());
+    }
+
+    #[test]
+    fn versions_order_pre_releases_before_their_release() {
+        use Ordering::*;
+        assert_eq!(compare_versions("0.8.0", "0.7.12"), Greater);
+        assert_eq!(compare_versions("v0.8.0", "0.8.0"), Equal);
+        assert_eq!(compare_versions("0.8.0-beta.2", "0.8.0"), Less);
+        assert_eq!(compare_versions("0.8.0-beta.10", "0.8.0-beta.2"), Greater);
+        assert_eq!(compare_versions("0.8.0-alpha.1", "0.8.0-beta.1"), Less);
+        assert_eq!(compare_versions("0.8.0-beta", "0.8.0-beta.1"), Less);
+        assert_eq!(compare_versions("0.8.1-beta.1", "0.8.0"), Greater);
+    }
+}
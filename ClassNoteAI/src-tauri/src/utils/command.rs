@@ -35,3 +35,16 @@ pub fn apply_no_window(#[allow(unused_mut)] mut cmd: Command) -> Command {
     }
     cmd
 }
+
+/// First match for `name` on PATH (`which` / `where`), if it exists.
+pub fn find_on_path(name: &str) -> Option<std::path::PathBuf> {
+    let probe = if cfg!(windows) { "where" } else { "which" };
+    let out = no_window(probe).arg(name).output().ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let text = String::from_utf8_lossy(&out.stdout);
+    let line = text.lines().next()?.trim();
+    let path = std::path::PathBuf::from(line);
+    (!line.is_empty() && path.exists()).then_some(path)
+}
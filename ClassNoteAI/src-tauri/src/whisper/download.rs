@@ -6,86 +6,102 @@
  * 2. 顯示下載進度
  * 3. 支持斷點續傳
  * 4. 驗證文件完整性
+ * 5. 大文件分段並行下載
  */
 use anyhow::Result;
+use std::fmt;
 use std::path::{Path, PathBuf};
 use tokio::fs::File;
 use tokio::io::{AsyncWriteExt, BufWriter};
 
+use crate::downloads::{mirrors, throttle};
+
 /// 模型下載配置
 pub struct ModelDownloadConfig {
     pub url: String,
     pub output_path: PathBuf,
     pub expected_size: Option<u64>, // 預期文件大小（字節）
+    /// 同一文件的其他來源，`url` 無法使用時依序嘗試（見 `downloads::mirrors`）
+    pub mirrors: Vec<String>,
 }
 
-/// Whisper Base 模型下載配置
-pub fn get_base_model_config(output_dir: &Path) -> ModelDownloadConfig {
+/// Hugging Face 上的模型文件，附帶 hf-mirror.com 的鏡像
+pub fn hugging_face_config(
+    url: &str,
+    output_path: PathBuf,
+    expected_size: Option<u64>,
+) -> ModelDownloadConfig {
     ModelDownloadConfig {
-        url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin".to_string(),
-        output_path: output_dir.join("ggml-base.bin"),
-        expected_size: Some(142_000_000), // 約 142MB
+        url: url.to_string(),
+        output_path,
+        expected_size,
+        mirrors: mirrors::hf_mirrors(url),
     }
 }
 
+/// Whisper Base 模型下載配置
+pub fn get_base_model_config(output_dir: &Path) -> ModelDownloadConfig {
+    hugging_face_config(
+        "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin",
+        output_dir.join("ggml-base.bin"),
+        Some(142_000_000), // 約 142MB
+    )
+}
+
 /// Whisper Small 模型下載配置
 pub fn get_small_model_config(output_dir: &Path) -> ModelDownloadConfig {
-    ModelDownloadConfig {
-        url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.bin".to_string(),
-        output_path: output_dir.join("ggml-small.bin"),
-        expected_size: Some(466_000_000), // 約 466MB
-    }
+    hugging_face_config(
+        "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.bin",
+        output_dir.join("ggml-small.bin"),
+        Some(466_000_000), // 約 466MB
+    )
 }
 
 /// Whisper Tiny 模型下載配置
 pub fn get_tiny_model_config(output_dir: &Path) -> ModelDownloadConfig {
-    ModelDownloadConfig {
-        url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-tiny.bin".to_string(),
-        output_path: output_dir.join("ggml-tiny.bin"),
-        expected_size: Some(75_000_000), // 約 75MB
-    }
+    hugging_face_config(
+        "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-tiny.bin",
+        output_dir.join("ggml-tiny.bin"),
+        Some(75_000_000), // 約 75MB
+    )
 }
 
 /// Whisper Medium 模型下載配置
 pub fn get_medium_model_config(output_dir: &Path) -> ModelDownloadConfig {
-    ModelDownloadConfig {
-        url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-medium.bin"
-            .to_string(),
-        output_path: output_dir.join("ggml-medium.bin"),
-        expected_size: Some(1_500_000_000), // 約 1.5GB
-    }
+    hugging_face_config(
+        "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-medium.bin",
+        output_dir.join("ggml-medium.bin"),
+        Some(1_500_000_000), // 約 1.5GB
+    )
 }
 
 /// Whisper Large 模型下載配置
 pub fn get_large_model_config(output_dir: &Path) -> ModelDownloadConfig {
-    ModelDownloadConfig {
-        url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3.bin"
-            .to_string(),
-        output_path: output_dir.join("ggml-large.bin"),
-        expected_size: Some(2_900_000_000), // 約 2.9GB
-    }
+    hugging_face_config(
+        "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3.bin",
+        output_dir.join("ggml-large.bin"),
+        Some(2_900_000_000), // 約 2.9GB
+    )
 }
 
 /// Whisper Small (Quantized q5_1) 模型下載配置
 /// 速度更快，內存佔用更低，精度損失極小
 pub fn get_small_quantized_model_config(output_dir: &Path) -> ModelDownloadConfig {
-    ModelDownloadConfig {
-        url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.en-q5_1.bin"
-            .to_string(),
-        output_path: output_dir.join("ggml-small-q5.bin"),
-        expected_size: Some(180_000_000), // 約 180MB (原版 466MB)
-    }
+    hugging_face_config(
+        "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-small.en-q5_1.bin",
+        output_dir.join("ggml-small-q5.bin"),
+        Some(180_000_000), // 約 180MB (原版 466MB)
+    )
 }
 
 /// Whisper Medium (Quantized q5_0) 模型下載配置
 /// 平衡速度與精度的最佳選擇
 pub fn get_medium_quantized_model_config(output_dir: &Path) -> ModelDownloadConfig {
-    ModelDownloadConfig {
-        url: "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-medium.en-q5_0.bin"
-            .to_string(),
-        output_path: output_dir.join("ggml-medium-q5.bin"),
-        expected_size: Some(530_000_000), // 約 530MB (原版 1.5GB)
-    }
+    hugging_face_config(
+        "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-medium.en-q5_0.bin",
+        output_dir.join("ggml-medium-q5.bin"),
+        Some(530_000_000), // 約 530MB (原版 1.5GB)
+    )
 }
 
 /// Whisper large-v3-turbo (Quantized q5_0) model download config.
@@ -94,13 +110,26 @@ pub fn get_medium_quantized_model_config(output_dir: &Path) -> ModelDownloadConf
 /// same accuracy, so this is the recommended option for users who want
 /// better-than-small accuracy without paying the full large-v3 cost.
 pub fn get_large_v3_turbo_quantized_model_config(output_dir: &Path) -> ModelDownloadConfig {
-    ModelDownloadConfig {
-        url:
-            "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3-turbo-q5_0.bin"
-                .to_string(),
-        output_path: output_dir.join("ggml-large-v3-turbo-q5_0.bin"),
-        expected_size: Some(574_000_000), // ~574 MB
-    }
+    hugging_face_config(
+        "https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-large-v3-turbo-q5_0.bin",
+        output_dir.join("ggml-large-v3-turbo-q5_0.bin"),
+        Some(574_000_000), // ~574 MB
+    )
+}
+
+/// 按模型類型名稱（`download_whisper_model` 的 `model_type`）取得下載配置
+pub fn config_for(model_type: &str, output_dir: &Path) -> Option<ModelDownloadConfig> {
+    Some(match model_type {
+        "tiny" => get_tiny_model_config(output_dir),
+        "base" => get_base_model_config(output_dir),
+        "small" => get_small_model_config(output_dir),
+        "medium" => get_medium_model_config(output_dir),
+        "large" => get_large_model_config(output_dir),
+        "small-q5" => get_small_quantized_model_config(output_dir),
+        "medium-q5" => get_medium_quantized_model_config(output_dir),
+        "large-v3-turbo-q5" => get_large_v3_turbo_quantized_model_config(output_dir),
+        _ => return None,
+    })
 }
 
 /// 下載進度信息
@@ -113,7 +142,22 @@ pub struct DownloadProgress {
     pub eta_seconds: Option<u64>, // 預估剩餘時間（秒）
 }
 
-/// 下載模型文件（支持斷點續傳和自動重試）
+/// 來源主機拒絕提供文件（HTTP 4xx）或連不上，換下一個來源而不是重試
+#[derive(Debug)]
+struct HostUnavailable(String);
+
+impl fmt::Display for HostUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for HostUnavailable {}
+
+/// 下載模型文件（支持斷點續傳、自動重試和鏡像切換）
+///
+/// 依序嘗試 `downloads::mirrors::candidates` 給出的來源：主機拒絕或連不上
+/// 就直接換下一個，其他錯誤先在同一來源重試。已下載的部分沿用到下一個來源。
 pub async fn download_model(
     config: &ModelDownloadConfig,
     progress_callback: Option<Box<dyn Fn(u64, u64) + Send + Sync>>,
@@ -121,37 +165,60 @@ pub async fn download_model(
     const MAX_RETRIES: u32 = 3;
     const RETRY_DELAY_SECS: u64 = 2;
 
-    for attempt in 1..=MAX_RETRIES {
-        match download_model_internal(config, progress_callback.as_ref()).await {
-            Ok(path) => return Ok(path),
-            Err(e) => {
-                if attempt < MAX_RETRIES {
-                    println!(
-                        "[下載] 嘗試 {} 失敗: {}，{} 秒後重試...",
-                        attempt, e, RETRY_DELAY_SECS
-                    );
-                    tokio::time::sleep(tokio::time::Duration::from_secs(RETRY_DELAY_SECS)).await;
-                } else {
-                    return Err(anyhow::anyhow!(
-                        "下載失敗（已重試 {} 次）: {}",
-                        MAX_RETRIES,
-                        e
-                    ));
+    let urls = mirrors::candidates(config).await;
+    let mut last_error = None;
+    for (i, url) in urls.iter().enumerate() {
+        if i > 0 {
+            println!("[下載] 改用來源 {}/{}: {}", i + 1, urls.len(), url);
+        }
+        for attempt in 1..=MAX_RETRIES {
+            match download_model_internal(config, url, progress_callback.as_ref()).await {
+                Ok(path) => return Ok(path),
+                Err(e) if e.is::<HostUnavailable>() => {
+                    println!("[下載] 來源無法使用: {}", e);
+                    last_error = Some(e);
+                    break;
+                }
+                Err(e) => {
+                    if attempt < MAX_RETRIES {
+                        println!(
+                            "[下載] 嘗試 {} 失敗: {}，{} 秒後重試...",
+                            attempt, e, RETRY_DELAY_SECS
+                        );
+                        tokio::time::sleep(tokio::time::Duration::from_secs(RETRY_DELAY_SECS))
+                            .await;
+                    } else {
+                        last_error = Some(anyhow::anyhow!(
+                            "下載失敗（已重試 {} 次）: {}",
+                            MAX_RETRIES,
+                            e
+                        ));
+                    }
                 }
             }
         }
     }
 
-    unreachable!()
+    let e = last_error.unwrap_or_else(|| anyhow::anyhow!("沒有可用的下載來源"));
+    if urls.len() > 1 {
+        Err(anyhow::anyhow!(
+            "所有 {} 個下載來源都失敗，最後一個錯誤: {}",
+            urls.len(),
+            e
+        ))
+    } else {
+        Err(e)
+    }
 }
 
 /// 內部下載實現
 async fn download_model_internal(
     config: &ModelDownloadConfig,
+    url: &str,
     progress_callback: Option<&Box<dyn Fn(u64, u64) + Send + Sync>>,
 ) -> Result<PathBuf> {
     println!("[下載] 開始下載模型");
-    println!("[下載] URL: {}", config.url);
+    println!("[下載] URL: {}", url);
     println!("[下載] 保存路徑: {:?}", config.output_path);
 
     // 創建輸出目錄
@@ -159,6 +226,11 @@ async fn download_model_internal(
         tokio::fs::create_dir_all(parent).await?;
     }
 
+    // 大文件優先分段並行下載
+    if let Some(path) = download_segmented(config, url, progress_callback).await? {
+        return Ok(path);
+    }
+
     // 檢查文件是否已存在（支持斷點續傳）
     let mut downloaded: u64 = 0;
     let mut file = if config.output_path.exists() {
@@ -186,12 +258,19 @@ async fn download_model_internal(
                 tokio::fs::remove_file(&config.output_path).await?;
                 File::create(&config.output_path).await?
             }
+        } else if existing_size > 0 {
+            // 沒有預期大小：同樣以 Range 續傳（例如暫停後繼續），
+            // 文件已完整時伺服器會回 416
+            println!(
+                "[下載] 發現已下載 {:.2} MB，嘗試續傳...",
+                existing_size as f64 / 1_000_000.0
+            );
+            downloaded = existing_size;
+            tokio::fs::OpenOptions::new()
+                .append(true)
+                .open(&config.output_path)
+                .await?
         } else {
-            // 沒有預期大小，檢查文件是否為空
-            if existing_size > 0 {
-                println!("[下載] 文件已存在，跳過下載");
-                return Ok(config.output_path.clone());
-            }
             File::create(&config.output_path).await?
         }
     } else {
@@ -199,25 +278,27 @@ async fn download_model_internal(
     };
 
     // 下載文件（支持斷點續傳）
-    let client = reqwest::Client::builder()
+    let client = crate::downloads::proxy::client_builder()
         .timeout(std::time::Duration::from_secs(300)) // 5 分鐘超時
         .build()
         .map_err(|e| anyhow::anyhow!("創建 HTTP 客戶端失敗: {}", e))?;
 
     // 如果已下載部分，使用 Range 請求繼續下載
-    let mut request = client.get(&config.url);
+    let mut request = client.get(url);
     if downloaded > 0 {
         request = request.header("Range", format!("bytes={}-", downloaded));
         println!("[下載] 使用斷點續傳，從字節 {} 開始", downloaded);
     }
 
-    let response = request
-        .send()
-        .await
-        .map_err(|e| anyhow::anyhow!("請求失敗: {}", e))?;
+    let response = request.send().await.map_err(request_error)?;
 
     let status = response.status();
 
+    if downloaded > 0 && status == 416 {
+        println!("[下載] 文件已存在且完整，跳過下載");
+        return Ok(config.output_path.clone());
+    }
+
     // cp75.12 — fail-fast on HTTP error responses. Without this guard
     // a 404 / 410 / 500 from the model host silently writes the error
     // body (typically a few KB of HTML) to disk and returns Ok, so the
@@ -231,12 +312,16 @@ async fn download_model_internal(
             .chars()
             .take(200)
             .collect::<String>();
-        return Err(anyhow::anyhow!(
+        let message = format!(
             "HTTP {} from {} — refused to write error body to disk. Response head: {}",
-            status,
-            config.url,
-            snippet,
-        ));
+            status, url, snippet,
+        );
+        // 4xx：這個來源沒有這個文件（或擋掉了我們），換下一個來源
+        return Err(if status.is_client_error() {
+            anyhow::Error::new(HostUnavailable(message))
+        } else {
+            anyhow::anyhow!(message)
+        });
     }
 
     // 檢查是否支持斷點續傳
@@ -313,6 +398,7 @@ async fn download_model_internal(
         let chunk = item.map_err(|e| anyhow::anyhow!("讀取數據失敗: {}", e))?;
         file.write_all(&chunk).await?;
         downloaded += chunk.len() as u64;
+        throttle::throttle(chunk.len()).await;
 
         // 計算下載速度和 ETA
         let now = std::time::Instant::now();
@@ -378,6 +464,353 @@ async fn download_model_internal(
     Ok(config.output_path.clone())
 }
 
+/// 大於此大小、且來源支持 Range 的文件分段並行下載（1.5–3 GB 的 Whisper 模型等）
+const PARALLEL_MIN_BYTES: u64 = 1_000_000_000;
+/// 分段數，即同時開的連線數
+const PARALLEL_SEGMENTS: usize = 4;
+/// 單一分段失敗時在同一來源上重試的次數
+const SEGMENT_RETRIES: u32 = 3;
+/// 每個分段每寫入這麼多就記錄一次進度
+const CHECKPOINT_BYTES: u64 = 8_000_000;
+
+/// 分段下載中的文件（`ggml-large.bin.download`），完成後才改名成模型文件，
+/// 所以下載到一半的模型不會被當成已下載
+pub fn segmented_part_path(output_path: &Path) -> PathBuf {
+    with_suffix(output_path, ".download")
+}
+
+/// 分段下載的進度檔（`ggml-large.bin.segments`）
+pub fn segments_path(output_path: &Path) -> PathBuf {
+    with_suffix(output_path, ".segments")
+}
+
+fn with_suffix(output_path: &Path, suffix: &str) -> PathBuf {
+    let mut name = output_path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    output_path.with_file_name(name)
+}
+
+/// 分段下載的進度：文件總大小和每段已寫入（且已落盤）的字節數。
+/// 文本格式，第一行是總大小，之後每行一段。
+#[derive(Debug, Clone, PartialEq)]
+struct Segments {
+    total: u64,
+    done: Vec<u64>,
+}
+
+impl Segments {
+    fn new(total: u64) -> Self {
+        Self {
+            total,
+            done: vec![0; PARALLEL_SEGMENTS],
+        }
+    }
+
+    /// 第 `i` 段的 `[start, end)`
+    fn range(&self, i: usize) -> (u64, u64) {
+        let n = self.done.len() as u64;
+        let i = i as u64;
+        (self.total * i / n, self.total * (i + 1) / n)
+    }
+
+    fn remaining(&self, i: usize) -> u64 {
+        let (start, end) = self.range(i);
+        end - start - self.done[i]
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let mut numbers = text.lines().map(|line| line.trim().parse::<u64>());
+        let total = numbers.next()?.ok()?;
+        let done = numbers.collect::<Result<Vec<_>, _>>().ok()?;
+        let segments = Self { total, done };
+        let valid = !segments.done.is_empty()
+            && (0..segments.done.len()).all(|i| {
+                let (start, end) = segments.range(i);
+                segments.done[i] <= end - start
+            });
+        valid.then_some(segments)
+    }
+
+    fn render(&self) -> String {
+        std::iter::once(self.total)
+            .chain(self.done.iter().copied())
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// `Content-Range: bytes 0-0/1533763059` 中的總大小
+fn content_range_total(headers: &reqwest::header::HeaderMap) -> Option<u64> {
+    let range = headers.get(reqwest::header::CONTENT_RANGE)?.to_str().ok()?;
+    range.rsplit_once('/')?.1.parse().ok()
+}
+
+fn request_error(e: reqwest::Error) -> anyhow::Error {
+    if e.is_connect() || e.is_timeout() {
+        anyhow::Error::new(HostUnavailable(format!("請求失敗: {}", e)))
+    } else {
+        anyhow::anyhow!("請求失敗: {}", e)
+    }
+}
+
+/// 分段並行下載時各段共用的狀態
+struct SegmentedDownload<'a> {
+    url: &'a str,
+    part_path: PathBuf,
+    state_path: PathBuf,
+    client: reqwest::Client,
+    /// 已落盤的進度，寫進度檔時持鎖，避免兩段同時寫同一個文件
+    state: tokio::sync::Mutex<Segments>,
+    /// 每段目前寫到的位置（含未記錄的部分），只用於進度回調
+    written: Vec<std::sync::atomic::AtomicU64>,
+    last_report: std::sync::Mutex<std::time::Instant>,
+    progress_callback: Option<&'a Box<dyn Fn(u64, u64) + Send + Sync>>,
+}
+
+impl SegmentedDownload<'_> {
+    fn report(&self, total: u64, force: bool) {
+        let Some(callback) = self.progress_callback else {
+            return;
+        };
+        {
+            let mut last = self.last_report.lock().unwrap_or_else(|p| p.into_inner());
+            if !force && last.elapsed().as_millis() < 500 {
+                return;
+            }
+            *last = std::time::Instant::now();
+        }
+        let downloaded = self
+            .written
+            .iter()
+            .map(|w| w.load(std::sync::atomic::Ordering::Relaxed))
+            .sum();
+        callback(downloaded, total);
+    }
+
+    /// 記錄第 `i` 段又有 `bytes` 已落盤
+    async fn checkpoint(&self, i: usize, bytes: u64) -> Result<()> {
+        let mut state = self.state.lock().await;
+        state.done[i] += bytes;
+        tokio::fs::write(&self.state_path, state.render()).await?;
+        Ok(())
+    }
+
+    /// 下載第 `i` 段，失敗時從已記錄的位置重試
+    async fn run_segment(&self, i: usize) -> Result<()> {
+        for attempt in 1..=SEGMENT_RETRIES {
+            match self.fetch_segment(i).await {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt < SEGMENT_RETRIES && !e.is::<HostUnavailable>() => {
+                    println!(
+                        "[下載] 分段 {} 第 {} 次失敗: {}，重試...",
+                        i + 1,
+                        attempt,
+                        e
+                    );
+                    tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        unreachable!()
+    }
+
+    async fn fetch_segment(&self, i: usize) -> Result<()> {
+        use futures_util::StreamExt;
+        use std::sync::atomic::Ordering;
+        use tokio::io::AsyncSeekExt;
+
+        let (start, end, done, total) = {
+            let state = self.state.lock().await;
+            let (start, end) = state.range(i);
+            (start, end, state.done[i], state.total)
+        };
+        self.written[i].store(done, Ordering::Relaxed);
+        if start + done == end {
+            return Ok(());
+        }
+
+        let response = self
+            .client
+            .get(self.url)
+            .header("Range", format!("bytes={}-{}", start + done, end - 1))
+            .send()
+            .await
+            .map_err(request_error)?;
+        let status = response.status();
+        if status != 206 {
+            let message = format!("HTTP {} from {}（分段 {}）", status, self.url, i + 1);
+            return Err(if status.is_client_error() {
+                anyhow::Error::new(HostUnavailable(message))
+            } else {
+                anyhow::anyhow!(message)
+            });
+        }
+
+        let mut file = tokio::fs::OpenOptions::new()
+            .write(true)
+            .open(&self.part_path)
+            .await?;
+        file.seek(std::io::SeekFrom::Start(start + done)).await?;
+        let mut file = BufWriter::new(file);
+        let mut written = done;
+        let mut unsaved = 0;
+        let mut stream = response.bytes_stream();
+        while let Some(item) = stream.next().await {
+            let chunk = item.map_err(|e| anyhow::anyhow!("讀取數據失敗: {}", e))?;
+            // 不寫過本段的結尾，即使伺服器多給了
+            let take = chunk.len().min((end - start - written) as usize);
+            file.write_all(&chunk[..take]).await?;
+            written += take as u64;
+            unsaved += take as u64;
+            self.written[i].store(written, Ordering::Relaxed);
+            self.report(total, false);
+            throttle::throttle(take).await;
+
+            if unsaved >= CHECKPOINT_BYTES {
+                file.flush().await?;
+                file.get_ref().sync_data().await?;
+                self.checkpoint(i, unsaved).await?;
+                unsaved = 0;
+            }
+            if start + written == end {
+                break;
+            }
+        }
+        file.flush().await?;
+        file.get_ref().sync_data().await?;
+        self.checkpoint(i, unsaved).await?;
+
+        if start + written < end {
+            return Err(anyhow::anyhow!(
+                "分段 {} 提前結束（{} / {} bytes）",
+                i + 1,
+                written,
+                end - start
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 分段並行下載：每段一條 Range 連線，直接寫進預先分配好大小的
+/// [`segmented_part_path`] 的各自位置，進度記在 [`segments_path`]，
+/// 中斷後各段從記錄處繼續。
+///
+/// 不適用時返回 `None`，由順序下載接手：文件不夠大、已有順序下載留下的部分文件、
+/// 或來源不支持 Range。
+async fn download_segmented(
+    config: &ModelDownloadConfig,
+    url: &str,
+    progress_callback: Option<&Box<dyn Fn(u64, u64) + Send + Sync>>,
+) -> Result<Option<PathBuf>> {
+    let part_path = segmented_part_path(&config.output_path);
+    let state_path = segments_path(&config.output_path);
+    let saved = tokio::fs::read_to_string(&state_path)
+        .await
+        .ok()
+        .and_then(|text| Segments::parse(&text));
+    if saved.is_none() {
+        if config.expected_size.unwrap_or(0) < PARALLEL_MIN_BYTES {
+            return Ok(None);
+        }
+        let existing = tokio::fs::metadata(&config.output_path).await;
+        if existing.is_ok_and(|m| m.len() > 0) {
+            return Ok(None);
+        }
+    }
+
+    let client = crate::downloads::proxy::client_builder()
+        .connect_timeout(std::time::Duration::from_secs(30))
+        .read_timeout(std::time::Duration::from_secs(60))
+        .build()
+        .map_err(|e| anyhow::anyhow!("創建 HTTP 客戶端失敗: {}", e))?;
+
+    // 探測來源是否支持 Range，順便取得準確的文件大小
+    let probe = client
+        .get(url)
+        .header("Range", "bytes=0-0")
+        .send()
+        .await
+        .map_err(request_error)?;
+    let status = probe.status();
+    if status.is_client_error() {
+        return Err(anyhow::Error::new(HostUnavailable(format!(
+            "HTTP {} from {}",
+            status, url
+        ))));
+    }
+    let total = if status == 206 {
+        content_range_total(probe.headers())
+    } else {
+        None
+    };
+    drop(probe);
+    let Some(total) = total else {
+        println!("[下載] 來源不支持 Range，改用單一連線下載");
+        if saved.is_some() {
+            let _ = tokio::fs::remove_file(&part_path).await;
+            let _ = tokio::fs::remove_file(&state_path).await;
+        }
+        return Ok(None);
+    };
+
+    let part_size = tokio::fs::metadata(&part_path).await.map(|m| m.len()).ok();
+    let segments = match saved {
+        Some(saved) if saved.total == total && part_size == Some(total) => {
+            println!(
+                "[下載] 繼續分段下載（已完成 {:.2} MB）",
+                saved.done.iter().sum::<u64>() as f64 / 1_000_000.0
+            );
+            saved
+        }
+        _ => {
+            let file = File::create(&part_path).await?;
+            file.set_len(total).await?;
+            let fresh = Segments::new(total);
+            tokio::fs::write(&state_path, fresh.render()).await?;
+            fresh
+        }
+    };
+    println!(
+        "[下載] 分 {} 段並行下載 {:.2} MB",
+        segments.done.len(),
+        total as f64 / 1_000_000.0
+    );
+
+    let download = SegmentedDownload {
+        url,
+        part_path: part_path.clone(),
+        state_path: state_path.clone(),
+        client,
+        written: segments
+            .done
+            .iter()
+            .map(|&d| std::sync::atomic::AtomicU64::new(d))
+            .collect(),
+        state: tokio::sync::Mutex::new(segments.clone()),
+        last_report: std::sync::Mutex::new(std::time::Instant::now()),
+        progress_callback,
+    };
+    futures_util::future::try_join_all(
+        (0..segments.done.len())
+            .filter(|&i| segments.remaining(i) > 0)
+            .map(|i| download.run_segment(i)),
+    )
+    .await?;
+    download.report(total, true);
+
+    // Windows 上改名不會覆蓋已有的文件
+    if tokio::fs::metadata(&config.output_path).await.is_ok() {
+        tokio::fs::remove_file(&config.output_path).await?;
+    }
+    tokio::fs::rename(&part_path, &config.output_path).await?;
+    tokio::fs::remove_file(&state_path).await?;
+    println!("[下載] ✅ 分段下載完成: {:?}", config.output_path);
+    Ok(Some(config.output_path.clone()))
+}
+
 /// 檢查模型文件是否存在且完整
 pub async fn check_model_file(model_path: &Path, expected_size: Option<u64>) -> Result<bool> {
     if !model_path.exists() {
@@ -427,3 +860,62 @@ pub async fn check_model_file(model_path: &Path, expected_size: Option<u64>) ->
         Ok(actual_size > 0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn segments_cover_the_file_and_round_trip() {
+        let mut segments = Segments::new(1_000_000_003);
+        let ranges: Vec<_> = (0..segments.done.len())
+            .map(|i| segments.range(i))
+            .collect();
+        assert_eq!(ranges[0].0, 0);
+        assert_eq!(ranges.last().unwrap().1, 1_000_000_003);
+        assert!(ranges.windows(2).all(|w| w[0].1 == w[1].0));
+
+        segments.done[1] = 42;
+        let parsed = Segments::parse(&segments.render()).unwrap();
+        assert_eq!(parsed, segments);
+        assert_eq!(parsed.remaining(1), ranges[1].1 - ranges[1].0 - 42);
+    }
+
+    #[test]
+    fn rejects_damaged_segment_state() {
+        assert_eq!(Segments::parse(""), None);
+        assert_eq!(Segments::parse("100"), None);
+        assert_eq!(Segments::parse("100\n10\nx"), None);
+        // 比分段本身還長
+        assert_eq!(Segments::parse("100\n51\n0"), None);
+    }
+
+    #[test]
+    fn reads_the_total_from_content_range() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        assert_eq!(content_range_total(&headers), None);
+        headers.insert(
+            reqwest::header::CONTENT_RANGE,
+            "bytes 0-0/1533763059".parse().unwrap(),
+        );
+        assert_eq!(content_range_total(&headers), Some(1_533_763_059));
+        headers.insert(
+            reqwest::header::CONTENT_RANGE,
+            "bytes 0-0/*".parse().unwrap(),
+        );
+        assert_eq!(content_range_total(&headers), None);
+    }
+
+    #[test]
+    fn segment_files_sit_next_to_the_model() {
+        let model = Path::new("/models/ggml-large.bin");
+        assert_eq!(
+            segmented_part_path(model),
+            Path::new("/models/ggml-large.bin.download")
+        );
+        assert_eq!(
+            segments_path(model),
+            Path::new("/models/ggml-large.bin.segments")
+        );
+    }
+}
@@ -360,6 +360,9 @@ function App() {
     if (appState !== 'ready' || !user) return;
     const t = setTimeout(async () => {
       try {
+        const { getStartupStatus } = await import('./services/diagnosticsService');
+        const startup = await getStartupStatus().catch(() => null);
+        if (startup?.safe_mode) return;
         const settings = await storageService.getAppSettings();
         const provider = settings?.translation?.provider || 'gemma';
         if (provider !== 'gemma') return;
@@ -413,6 +416,67 @@ function App() {
     return () => clearTimeout(t);
   }, [appState]);
 
+  // Startup self-test: once the UI is past loading this launch no longer
+  // counts as a possible crash loop. If the backend came up in safe mode
+  // say so and point at the repairs — models weren't loaded, so otherwise
+  // recording / translation would just look broken.
+  const booted = appState !== 'loading';
+  useEffect(() => {
+    if (!booted) return;
+    void (async () => {
+      try {
+        const { getStartupStatus, markStartupComplete } = await import(
+          './services/diagnosticsService'
+        );
+        await markStartupComplete();
+        const status = await getStartupStatus();
+        if (!status.safe_mode) return;
+        toastService.show({
+          message: '安全模式啟動',
+          detail: `${status.reasons.join('；')}。模型未載入，請到「設定 → 關於」檢視自我檢測並修復。`,
+          type: 'warning',
+          durationMs: 0,
+        });
+      } catch (err) {
+        console.warn('[App] startup status check failed (non-fatal):', err);
+      }
+    })();
+  }, [booted]);
+
+  // Offer the crash report from a Rust panic in the previous run, once.
+  // Clicking the toast opens a prefilled GitHub issue; either way the
+  // report is dismissed so it isn't offered on every launch.
+  useEffect(() => {
+    if (appState !== 'ready') return;
+    const t = setTimeout(async () => {
+      try {
+        const { getLastCrashReport, dismissCrashReport, crashReportIssueUrl } = await import(
+          './services/diagnosticsService'
+        );
+        const report = await getLastCrashReport();
+        if (!report) return;
+        await dismissCrashReport(report.id);
+        toastService.show({
+          message: '上次執行時程式發生錯誤',
+          detail: report.message,
+          type: 'warning',
+          durationMs: 0,
+          action: {
+            label: '回報問題',
+            onClick: () => {
+              void import('@tauri-apps/plugin-opener').then(({ openUrl }) =>
+                openUrl(crashReportIssueUrl(report)),
+              );
+            },
+          },
+        });
+      } catch (err) {
+        console.warn('[App] crash report check failed (non-fatal):', err);
+      }
+    }, 2500);
+    return () => clearTimeout(t);
+  }, [appState]);
+
   // v0.5.2 + cp75.27: crash-recovery scan + 30-day hard-delete sweep,
   // CHAINED. If the app died mid-recording last session, there's a .pcm
   // file on disk and a DB row stuck at status='recording'. We populate
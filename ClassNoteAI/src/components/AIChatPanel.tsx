@@ -4,6 +4,7 @@ import ReactMarkdown from 'react-markdown';
 import { ragService, IndexingProgress } from '../services/ragService';
 import { chatSessionService, ChatSession, ChatMessage } from '../services/chatSessionService';
 import { chatStream as llmChatStream, usageTracker } from '../services/llm';
+import { errorMessage } from '../services/appError';
 import s from './AIChatPanel.module.css';
 
 // 重新導出 ChatMessage 類型供其他組件使用
@@ -382,7 +383,7 @@ export default function AIChatPanel({
             // next to the error, which looks like two replies.
             const errorMessage: ChatMessage = {
                 ...assistantBase,
-                content: `抱歉，生成回答時發生錯誤：${error instanceof Error ? error.message : String(error)}`,
+                content: `抱歉，生成回答時發生錯誤：${errorMessage(error)}`,
             };
             setMessages(prev => prev.map(m => (m.id === assistantId ? errorMessage : m)));
             await chatSessionService.addMessage(session.id, errorMessage);
@@ -4,6 +4,7 @@ import {
     recordingRecoveryService,
     type RecoverableSession,
 } from '../services/recordingRecoveryService';
+import { errorMessage } from '../services/appError';
 import s from './RecoveryPromptModal.module.css';
 
 /**
@@ -46,7 +47,7 @@ export default function RecoveryPromptModal({
         } catch (err) {
             setActionState(session.lectureId, {
                 inFlight: false,
-                error: err instanceof Error ? err.message : String(err),
+                error: errorMessage(err),
             });
         }
     };
@@ -65,7 +66,7 @@ export default function RecoveryPromptModal({
         } catch (err) {
             setActionState(session.lectureId, {
                 inFlight: false,
-                error: err instanceof Error ? err.message : String(err),
+                error: errorMessage(err),
             });
         }
     };
@@ -130,7 +131,7 @@ export default function RecoveryPromptModal({
             } catch (err) {
                 setActionState(sess.lectureId, {
                     inFlight: false,
-                    error: err instanceof Error ? err.message : String(err),
+                    error: errorMessage(err),
                 });
             }
         });
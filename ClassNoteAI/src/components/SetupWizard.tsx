@@ -4,7 +4,7 @@
  * First-run setup wizard for detecting and installing dependencies.
  */
 
-import { useState, useCallback } from 'react';
+import { useState, useCallback, useEffect } from 'react';
 import {
     CheckCircle,
     XCircle,
@@ -22,6 +22,7 @@ import {
 import { openUrl } from '@tauri-apps/plugin-opener';
 import { setupService } from '../services/setupService';
 import { consentService } from '../services/consentService';
+import { errorMessage } from '../services/appError';
 import AIProviderSettings from './AIProviderSettings';
 import {
     SetupStatus,
@@ -29,6 +30,7 @@ import {
     Progress,
     isInstalled,
     isOutdated,
+    isCorrupted,
     isError,
     getProgressPercentage
 } from '../types/setup';
@@ -102,6 +104,9 @@ export default function SetupWizard({ onComplete }: SetupWizardProps) {
     const [includeOptional, setIncludeOptional] = useState(false);
     const [progressMap, setProgressMap] = useState<Record<string, Progress>>({});
     const [isInstalling, setIsInstalling] = useState(false);
+    // The requirements the last installation ran, for retrying it. The
+    // backend skips the ones that already completed.
+    const [installIds, setInstallIds] = useState<string[]>([]);
 
     // v0.5.1: first-run language pair. Persisted to AppSettings at the
     // end of the language step so transcriptionService picks it up when
@@ -162,7 +167,7 @@ export default function SetupWizard({ onComplete }: SetupWizardProps) {
             // landing pad regardless.
             setStep('gpu-check');
         } catch (err) {
-            setError(`環境檢查失敗: ${err}`);
+            setError(`環境檢查失敗: ${errorMessage(err)}`);
             setStep('review');
         }
     }, []);
@@ -202,21 +207,16 @@ export default function SetupWizard({ onComplete }: SetupWizardProps) {
         setStep('recording-consent');
     }, [checkRequirements]);
 
-    // Start installation
-    const startInstallation = useCallback(async () => {
-        if (!status) return;
-
+    const installRequirements = useCallback(async (setupStatus: SetupStatus, idsToInstall: string[]) => {
         setStep('installing');
         setIsInstalling(true);
         setError(null);
-
-        // Get IDs of requirements to install
-        const idsToInstall = setupService.getAllMissingIds(status, includeOptional);
+        setInstallIds(idsToInstall);
 
         // Initialize progress for all tasks
         const initialProgress: Record<string, Progress> = {};
         idsToInstall.forEach(id => {
-            const req = status.requirements.find(r => r.id === id);
+            const req = setupStatus.requirements.find(r => r.id === id);
             initialProgress[id] = {
                 task_id: id,
                 task_name: req?.name || id,
@@ -243,12 +243,50 @@ export default function SetupWizard({ onComplete }: SetupWizardProps) {
             await setupService.markComplete();
             setStep('complete');
         } catch (err) {
-            setError(`安裝失敗: ${err}`);
+            setError(`安裝失敗: ${errorMessage(err)}`);
         } finally {
             setIsInstalling(false);
             unlisten();
         }
-    }, [status, includeOptional]);
+    }, []);
+
+    // Start installation
+    const startInstallation = useCallback(async () => {
+        if (!status) return;
+        await installRequirements(status, setupService.getAllMissingIds(status, includeOptional));
+    }, [status, includeOptional, installRequirements]);
+
+    const retryInstallation = useCallback(async () => {
+        if (!status) return;
+        await installRequirements(status, installIds);
+    }, [status, installIds, installRequirements]);
+
+    // Downloads the app quit in the middle of are still queued in the
+    // backend; pick them up where they stopped instead of making the
+    // user walk through the wizard again.
+    useEffect(() => {
+        let cancelled = false;
+        (async () => {
+            try {
+                const pending = await setupService.pendingDownloads();
+                if (cancelled || pending.length === 0) return;
+                const setupStatus = await setupService.checkStatus();
+                if (cancelled) return;
+                setStatus(setupStatus);
+                const missing = setupService.getAllMissingIds(setupStatus, true);
+                const ids = [...new Set(pending.map(d => d.requirement_id))]
+                    .filter(id => missing.includes(id));
+                if (ids.length > 0) {
+                    await installRequirements(setupStatus, ids);
+                }
+            } catch (err) {
+                console.warn('[SetupWizard] could not resume setup downloads:', err);
+            }
+        })();
+        return () => {
+            cancelled = true;
+        };
+    }, [installRequirements]);
 
     // Cancel installation
     const cancelInstallation = useCallback(async () => {
@@ -278,7 +316,7 @@ export default function SetupWizard({ onComplete }: SetupWizardProps) {
         if (isInstalled(req.status)) {
             return <CheckCircle className="w-5 h-5 text-green-500" />;
         }
-        if (isOutdated(req.status)) {
+        if (isOutdated(req.status) || isCorrupted(req.status)) {
             return <AlertTriangle className="w-5 h-5 text-yellow-500" />;
         }
         if (isError(req.status)) {
@@ -1032,9 +1070,22 @@ export default function SetupWizard({ onComplete }: SetupWizardProps) {
                                         {req.is_optional && <span className="optional-badge">可選</span>}
                                     </span>
                                     <span className="requirement-desc">{req.description}</span>
+                                    {typeof req.status === 'object' && 'Corrupted' in req.status && (
+                                        <span className="requirement-desc">
+                                            檔案損壞或不完整：{req.status.Corrupted.files.join(', ')}
+                                        </span>
+                                    )}
                                 </div>
                             </div>
                             <div className="requirement-status">
+                                {isCorrupted(req.status) && (
+                                    <button
+                                        className="requirement-repair"
+                                        onClick={() => installRequirements(status, [req.id])}
+                                    >
+                                        <RefreshCw className="w-3 h-3" /> 修復
+                                    </button>
+                                )}
                                 {getStatusIcon(req)}
                                 {!isInstalled(req.status) && req.install_size_mb > 0 && (
                                     <span className="requirement-size">~{req.install_size_mb}MB</span>
@@ -1160,13 +1211,23 @@ export default function SetupWizard({ onComplete }: SetupWizardProps) {
                     ))}
                 </div>
 
-                <button
-                    className="setup-button secondary"
-                    onClick={cancelInstallation}
-                    disabled={!isInstalling}
-                >
-                    取消安裝
-                </button>
+                {error && !isInstalling ? (
+                    <button
+                        className="setup-button primary"
+                        onClick={retryInstallation}
+                    >
+                        <RefreshCw className="w-4 h-4" />
+                        重試失敗的項目
+                    </button>
+                ) : (
+                    <button
+                        className="setup-button secondary"
+                        onClick={cancelInstallation}
+                        disabled={!isInstalling}
+                    >
+                        取消安裝
+                    </button>
+                )}
             </div>
         );
     };
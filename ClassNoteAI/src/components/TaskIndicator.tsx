@@ -6,7 +6,9 @@
  *
  * Dual-source registry — see ClassNoteAI/docs/design/h18-deep/H18-TASKINDICATOR-MERGE.md:
  *   - taskTrackerService (Phase 7) — summarize / index / export
- *   - offlineQueueService (legacy) — AUTH_REGISTER / PURGE_ITEM / TASK_CREATE
+ *   - offlineQueueService — the backend sync outbox (AUTH_REGISTER,
+ *     AUDIO_UPLOAD, SYNC_PUSH, ...); its `sync-progress` report fills in
+ *     progress and detail for the action being sent
  *
  * Both feeds adapt into a UnifiedTask shape. SUMMARIZE_LECTURE and
  * INDEX_LECTURE on the queue side are filtered out (per MERGE §6.1) — the
@@ -24,6 +26,7 @@ import { useEffect, useMemo, useRef, useState } from 'react';
 import {
     offlineQueueService,
     type PendingAction,
+    type SyncReport,
 } from '../services/offlineQueueService';
 import {
     taskTrackerService,
@@ -54,6 +57,9 @@ interface UnifiedTask {
 
 const OFFLINE_QUEUE_LABELS: Record<string, string> = {
     AUTH_REGISTER: '用戶註冊',
+    AUDIO_UPLOAD: '錄音上傳',
+    SYNC_PUSH: '同步變更',
+    NOTE_KEEP_LOCAL: '保留本機筆記',
     PURGE_ITEM: '永久刪除',
     TASK_CREATE: '任務建立',
     // SUMMARIZE_LECTURE / INDEX_LECTURE are filtered out before adapt
@@ -88,7 +94,30 @@ function adaptTracker(e: TaskTrackerEntry): UnifiedTask | null {
     };
 }
 
-function adaptQueue(item: PendingAction): UnifiedTask | null {
+const SYNC_ENTITY_LABELS: Record<string, string> = {
+    courses: '課程',
+    lectures: '課堂',
+    notes: '筆記',
+    purged_items: '永久刪除',
+};
+
+/** What the backend's flush has done so far, from its `sync-progress` report. */
+function describeSyncReport(report: SyncReport): string | null {
+    if (report.phase === 'uploading' && report.bytes_total > 0) {
+        const mb = (bytes: number) => (bytes / 1024 / 1024).toFixed(1);
+        return `${mb(report.bytes_uploaded)} / ${mb(report.bytes_total)} MB`;
+    }
+    if (report.phase === 'pushing') {
+        const parts = Object.entries(report.entities).map(
+            ([entity, count]) => `${SYNC_ENTITY_LABELS[entity] ?? entity} ${count}`,
+        );
+        if (report.conflicts > 0) parts.push(`衝突 ${report.conflicts}`);
+        return parts.length > 0 ? parts.join(' · ') : null;
+    }
+    return null;
+}
+
+function adaptQueue(item: PendingAction, report: SyncReport | null): UnifiedTask | null {
     // V15 dedupe — those two action types are queue's "restart-replay"
     // bookkeeping. The tracker owns the actual row.
     if (
@@ -99,7 +128,7 @@ function adaptQueue(item: PendingAction): UnifiedTask | null {
     }
 
     const status: UnifiedStatus =
-        item.status === 'pending'
+        item.status === 'pending' || item.status === 'deferred'
             ? 'queued'
             : item.status === 'processing'
               ? 'running'
@@ -107,18 +136,33 @@ function adaptQueue(item: PendingAction): UnifiedTask | null {
                 ? 'failed'
                 : /* completed */ 'done';
 
+    // The action the backend is sending right now has a live report.
+    const live =
+        item.status === 'processing' && report?.action_type === item.actionType
+            ? report
+            : null;
+
     const progress =
         item.status === 'completed'
             ? 1
-            : item.status === 'processing'
-              ? 0.5
-              : 0;
+            : live && live.bytes_total > 0
+              ? live.bytes_uploaded / live.bytes_total
+              : item.status === 'processing'
+                ? 0.5
+                : 0;
+
+    const label = OFFLINE_QUEUE_LABELS[item.actionType] ?? item.actionType;
+    const detail = live
+        ? describeSyncReport(live)
+        : item.status === 'deferred'
+          ? '等待非計量網路'
+          : null;
 
     return {
         id: `queue:${item.id}`,
         source: 'offline-queue',
         kind: item.actionType,
-        label: OFFLINE_QUEUE_LABELS[item.actionType] ?? item.actionType,
+        label: detail ? `${label} · ${detail}` : label,
         progress,
         status,
         cancelable: false, // queue is non-cancellable per MERGE §1
@@ -211,6 +255,7 @@ function SpinnerIcon() {
 export default function TaskIndicator() {
     const [queueItems, setQueueItems] = useState<PendingAction[]>([]);
     const [trackerItems, setTrackerItems] = useState<TaskTrackerEntry[]>([]);
+    const [syncReport, setSyncReport] = useState<SyncReport | null>(null);
     const [isOnline, setIsOnline] = useState(
         typeof navigator !== 'undefined' ? navigator.onLine : true,
     );
@@ -256,6 +301,11 @@ export default function TaskIndicator() {
         };
     }, []);
 
+    // ─── backend sync progress ──────────────────────────────────────
+    // Rows pushed / bytes uploaded for the action being sent, so its row
+    // shows more than a spinner.
+    useEffect(() => offlineQueueService.onProgress(setSyncReport), []);
+
     // ─── taskTrackerService subscribe ───────────────────────────────
     // Service fires immediate snapshot on subscribe. We keep ALL entries
     // (not just getActive) so failed rows survive in the dropdown until
@@ -287,7 +337,7 @@ export default function TaskIndicator() {
             .map(adaptTracker)
             .filter((x): x is UnifiedTask => x !== null);
         const queue = queueItems
-            .map(adaptQueue)
+            .map((item) => adaptQueue(item, syncReport))
             .filter((x): x is UnifiedTask => x !== null);
         const STATUS_ORDER: Record<UnifiedStatus, number> = {
             running: 0,
@@ -304,7 +354,7 @@ export default function TaskIndicator() {
             }
             return a.startedAt - b.startedAt;
         });
-    }, [trackerItems, queueItems]);
+    }, [trackerItems, queueItems, syncReport]);
 
     const activeCount = useMemo(
         () =>
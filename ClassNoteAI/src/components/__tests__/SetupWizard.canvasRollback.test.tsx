@@ -61,6 +61,7 @@ vi.mock('../../services/setupService', () => ({
         checkStatus: checkStatusMock,
         installAll: vi.fn(() => Promise.resolve()),
         markComplete: vi.fn(() => Promise.resolve()),
+        pendingDownloads: vi.fn(() => Promise.resolve([])),
         onProgress: vi.fn(() => Promise.resolve(() => {})),
         startInstallation: vi.fn(() => Promise.resolve()),
         cancelInstallation: vi.fn(() => Promise.resolve()),
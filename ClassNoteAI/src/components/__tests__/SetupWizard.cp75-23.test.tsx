@@ -33,6 +33,7 @@ vi.mock('../../services/setupService', () => ({
         ),
         installAll: vi.fn(() => Promise.resolve()),
         markComplete: vi.fn(() => Promise.resolve()),
+        pendingDownloads: vi.fn(() => Promise.resolve([])),
     },
 }));
 
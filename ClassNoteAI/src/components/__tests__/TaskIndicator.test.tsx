@@ -34,6 +34,7 @@ vi.mock('../../services/offlineQueueService', () => ({
     init: vi.fn(() => Promise.resolve()),
     listActions: vi.fn(() => Promise.resolve([])),
     subscribe: vi.fn((_cb: (count: number) => void) => () => {}),
+    onProgress: vi.fn(() => () => {}),
   },
 }));
 
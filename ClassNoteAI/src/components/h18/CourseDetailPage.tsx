@@ -25,6 +25,7 @@ import { invoke } from '@tauri-apps/api/core';
 import { authService } from '../../services/authService';
 import { storageService } from '../../services/storageService';
 import { toastService } from '../../services/toastService';
+import { exportAnkiDeck, exportMarkdownBundle } from '../../services/exportService';
 import type { Course, Lecture } from '../../types';
 import { courseColor } from './courseColor';
 import CanvasRemindersPanel from './CanvasRemindersPanel';
@@ -33,6 +34,7 @@ import CanvasItemPreviewModal, {
 } from './CanvasItemPreviewModal';
 import { LectureContextMenu } from './LectureContextMenu';
 import { LectureEditDialog } from './LectureEditDialog';
+import { errorMessage } from '../../services/appError';
 import s from './CourseDetailPage.module.css';
 
 export interface CourseDetailPageProps {
@@ -204,12 +206,35 @@ export default function CourseDetailPage({
                 `「${menuState.lecture.title}」已移到垃圾桶`,
             );
         } catch (err) {
-            toastService.error('刪除失敗', String(err));
+            toastService.error('刪除失敗', errorMessage(err));
         } finally {
             setMenuState(null);
         }
     };
 
+    const handleExportAnki = async () => {
+        try {
+            const r = await exportAnkiDeck(courseId);
+            if (r) {
+                toastService.success(
+                    `已匯出 ${r.cards} 張 Anki 卡片`,
+                    r.clips > 0 ? `${r.path}（含 ${r.clips} 段錄音）` : r.path,
+                );
+            }
+        } catch (err) {
+            toastService.error('匯出失敗', errorMessage(err));
+        }
+    };
+
+    const handleExportBundle = async () => {
+        try {
+            const r = await exportMarkdownBundle(courseId);
+            if (r) toastService.success(`已匯出 ${r.lectures} 堂課的 Markdown`, r.path);
+        } catch (err) {
+            toastService.error('匯出失敗', errorMessage(err));
+        }
+    };
+
     const handleEditSubmit = async (updates: {
         title: string;
         date: string;
@@ -487,6 +512,22 @@ export default function CourseDetailPage({
                         >
                             ✎ 編輯
                         </button>
+                        <button
+                            type="button"
+                            onClick={handleExportAnki}
+                            className={s.editCourseBtn}
+                            title="把各堂筆記的問答匯出成 Anki 卡片組（含原句錄音）"
+                        >
+                            ⇩ Anki
+                        </button>
+                        <button
+                            type="button"
+                            onClick={handleExportBundle}
+                            className={s.editCourseBtn}
+                            title="匯出成 Markdown 資料夾（含錄音片段與投影片），可直接放進 Obsidian / Logseq"
+                        >
+                            ⇩ Markdown
+                        </button>
                     </div>
                     <h1 className={s.heroTitle}>{course.title}</h1>
                     <div className={s.heroStats}>
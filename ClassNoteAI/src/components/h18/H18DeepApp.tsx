@@ -66,6 +66,7 @@ import {
     storeLastReview,
     type LastReviewState,
 } from './aiContextDerivation';
+import { errorMessage } from '../../services/appError';
 import s from './H18DeepApp.module.css';
 
 // (Placeholder helper removed in P6.9 — every nav target now has a real component)
@@ -482,7 +483,7 @@ export default function H18DeepApp() {
                     } catch (err) {
                         toastService.error(
                             '結束舊錄音失敗',
-                            err instanceof Error ? err.message : String(err),
+                            errorMessage(err),
                         );
                         return;
                     }
@@ -11,7 +11,7 @@
  *  - lecture       → storageService.getLecture(id)
  *  - subtitles     → storageService.getSubtitles(id)
  *  - notes         → storageService.getNote(id)  (Note shape with sections + summary)
- *  - audio src     → resolveOrRecoverAudioPath → convertFileSrc
+ *  - audio src     → resolveOrRecoverAudioPath → probeAudio → convertFileSrc
  *
  * 留白：
  *  - bilink concept hover (RVBilink) — concept extraction 沒做
@@ -26,7 +26,7 @@ import ReactMarkdown from 'react-markdown';
 import remarkGfm from 'remark-gfm';
 import rehypeSanitize from 'rehype-sanitize';
 import { storageService } from '../../services/storageService';
-import { resolveOrRecoverAudioPath } from '../../services/audioPathService';
+import { probeAudio, resolveOrRecoverAudioPath } from '../../services/audioPathService';
 import type {
     Course,
     Lecture,
@@ -60,6 +60,7 @@ import {
 import { recordingSessionService } from '../../services/recordingSessionService';
 import { H18EmptyState } from './H18EmptyState';
 import { RegenerateMenu, type RegenerateTarget } from './RegenerateMenu';
+import { errorMessage } from '../../services/appError';
 import s from './H18ReviewPage.module.css';
 
 export interface H18ReviewPageProps {
@@ -339,15 +340,31 @@ export default function H18ReviewPage({
             return;
         }
         resolveOrRecoverAudioPath(lecture.id, lecture.audio_path)
-            .then((rec) => {
+            .then(async (rec) => {
                 if (cancelled) return;
-                if (rec.resolvedPath) {
-                    setAudioSrc(convertFileSrc(rec.resolvedPath));
-                    setAudioErr(null);
-                } else {
+                if (!rec.resolvedPath) {
                     setAudioSrc(null);
                     setAudioErr('音訊檔不存在或路徑遺失');
+                    return;
                 }
+                // Check the file before handing it to <audio>: a torn or
+                // empty recording otherwise just shows 00:00 / 00:00.
+                // A failed probe (e.g. no ffmpeg) doesn't block playback.
+                const probe = await probeAudio(rec.resolvedPath).catch((err) => {
+                    console.warn('[H18ReviewPage] probe_audio failed:', err);
+                    return null;
+                });
+                if (cancelled) return;
+                if (probe && !probe.playable) {
+                    setAudioSrc(null);
+                    setAudioErr(probe.error || '音訊檔已損毀，無法播放');
+                    return;
+                }
+                if (probe?.error) {
+                    console.warn('[H18ReviewPage] audio plays but looks damaged:', probe.error);
+                }
+                setAudioSrc(convertFileSrc(rec.resolvedPath));
+                setAudioErr(null);
             })
             .catch((err) => {
                 console.warn('[H18ReviewPage] resolveOrRecoverAudioPath failed:', err);
@@ -1707,7 +1724,7 @@ async function runSummary(
         });
         taskTrackerService.complete(taskId);
     } catch (err) {
-        const msg = err instanceof Error ? err.message : String(err);
+        const msg = errorMessage(err);
         taskTrackerService.fail(taskId, msg);
     } finally {
         // cp75.35 — always tear down the tracker subscription so we
@@ -8,14 +8,18 @@
  * Items (top → bottom):
  *   1. 編輯              — caller opens LectureEditDialog (S3c-2)
  *   2. 重新命名           — caller toggles inline rename state
- *   3. 匯出 ▸             — submenu: SRT 字幕 / Markdown
- *                           leaves call exportService.exportLecture()
+ *   3. 匯出 ▸             — submenu: SRT 字幕 / Markdown / PDF 筆記 / Word (DOCX)
+ *                           leaves call exportService.exportLecture() /
+ *                           exportNote()
  *                           directly; result is reported via toastService
  *   4. 移動到其他課程 ▸   — submenu lists every course OTHER than the
  *                           lecture's current course; selecting one calls
  *                           onMoveToCourse(newCourseId)
- *   5. ─sep
- *   6. 刪除               — confirmService.ask first; if user confirms,
+ *   5. 上傳錄音            — queues AUDIO_UPLOAD to the active sync server
+ *                           (offlineQueueService.uploadLectureAudio);
+ *                           disabled when the lecture has no recording
+ *   6. ─sep
+ *   7. 刪除               — confirmService.ask first; if user confirms,
  *                           onDelete() is awaited (caller drives the
  *                           actual storage call + list refresh)
  *
@@ -30,11 +34,13 @@
  *     no extra wiring needed here.
  */
 
-import { exportLecture } from '../../services/exportService';
+import { exportLecture, exportNote } from '../../services/exportService';
 import { toastService } from '../../services/toastService';
 import { confirmService } from '../../services/confirmService';
+import { offlineQueueService } from '../../services/offlineQueueService';
 import { H18ContextMenu, type H18ContextMenuItem } from './H18ContextMenu';
 import type { Course, Lecture } from '../../types';
+import { errorMessage } from '../../services/appError';
 
 export interface LectureContextMenuProps {
     lecture: Lecture;
@@ -83,7 +89,7 @@ export function LectureContextMenu({
                     await onMoveToCourse(c.id);
                     toastService.success('已移動', `已移到「${c.title}」`);
                 } catch (err) {
-                    toastService.error('移動失敗', String(err));
+                    toastService.error('移動失敗', errorMessage(err));
                 }
             },
         }));
@@ -113,7 +119,7 @@ export function LectureContextMenu({
                             // dialog — treat as silent no-op (no success toast).
                             if (r) toastService.success('已匯出 SRT', r.path);
                         } catch (err) {
-                            toastService.error('匯出失敗', String(err));
+                            toastService.error('匯出失敗', errorMessage(err));
                         }
                     },
                 },
@@ -125,7 +131,31 @@ export function LectureContextMenu({
                             const r = await exportLecture(lecture.id, 'md');
                             if (r) toastService.success('已匯出 Markdown', r.path);
                         } catch (err) {
-                            toastService.error('匯出失敗', String(err));
+                            toastService.error('匯出失敗', errorMessage(err));
+                        }
+                    },
+                },
+                {
+                    id: 'export-pdf',
+                    label: 'PDF 筆記',
+                    onClick: async () => {
+                        try {
+                            const r = await exportNote(lecture.id, 'pdf');
+                            if (r) toastService.success('已匯出 PDF', r.path);
+                        } catch (err) {
+                            toastService.error('匯出失敗', errorMessage(err));
+                        }
+                    },
+                },
+                {
+                    id: 'export-docx',
+                    label: 'Word (DOCX)',
+                    onClick: async () => {
+                        try {
+                            const r = await exportNote(lecture.id, 'docx');
+                            if (r) toastService.success('已匯出 Word 文件', r.path);
+                        } catch (err) {
+                            toastService.error('匯出失敗', errorMessage(err));
                         }
                     },
                 },
@@ -140,6 +170,26 @@ export function LectureContextMenu({
             // submenu length is 0 (handled by H18ContextMenu itself).
             submenu: moveSubmenu,
         },
+        {
+            id: 'upload-audio',
+            label: '上傳錄音',
+            disabled: !lecture.audio_path,
+            onClick: async () => {
+                try {
+                    const id = await offlineQueueService.uploadLectureAudio(lecture.id);
+                    if (id) {
+                        toastService.info('已排入上傳', '斷線或重開 App 後會從中斷處接著傳。');
+                    } else {
+                        toastService.warning(
+                            '尚未設定同步伺服器',
+                            '到「設定 → 同步」填好伺服器後再上傳。',
+                        );
+                    }
+                } catch (err) {
+                    toastService.error('上傳失敗', errorMessage(err));
+                }
+            },
+        },
         { id: 'sep', label: '─', disabled: true },
         {
             id: 'delete',
@@ -156,7 +206,7 @@ export function LectureContextMenu({
                 try {
                     await onDelete();
                 } catch (err) {
-                    toastService.error('刪除失敗', String(err));
+                    toastService.error('刪除失敗', errorMessage(err));
                 }
             },
         },
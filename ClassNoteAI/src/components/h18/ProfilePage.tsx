@@ -33,6 +33,7 @@ import {
     PAudio,
     PData,
     PIntegrations,
+    PSync,
     PKeyboard,
     PAbout,
 } from './ProfilePanes';
@@ -46,6 +47,7 @@ export type ProfileTab =
     | 'audio'
     | 'data'
     | 'integrations'
+    | 'sync'
     | 'keyboard'
     | 'about';
 
@@ -78,6 +80,7 @@ const SETTINGS: NavItem[] = [
     { id: 'audio', label: '音訊與字幕', hint: '麥克風 · 字幕' },
     { id: 'keyboard', label: '鍵盤', hint: '快捷鍵綁定' },
     { id: 'integrations', label: '整合', hint: 'Canvas · LMS RSS' },
+    { id: 'sync', label: '同步', hint: '伺服器 · 衝突' },
     { id: 'data', label: '資料管理', hint: '匯入匯出 · 回收桶' },
     { id: 'about', label: '關於與更新', hint: '版本 · 診斷' },
 ];
@@ -167,6 +170,7 @@ export default function ProfilePage({
                 {tab === 'audio' && <PAudio />}
                 {tab === 'keyboard' && <PKeyboard />}
                 {tab === 'integrations' && <PIntegrations />}
+                {tab === 'sync' && <PSync />}
                 {tab === 'data' && <PData />}
                 {tab === 'about' && <PAbout />}
             </main>
@@ -19,8 +19,10 @@ import { invoke } from '@tauri-apps/api/core';
 import { authService } from '../../services/authService';
 import { storageService } from '../../services/storageService';
 import { confirmService } from '../../services/confirmService';
+import { confirmModelLoad } from '../../services/memoryService';
 import { toastService } from '../../services/toastService';
 import type { Course, Lecture } from '../../types';
+import type { ReleaseNotes } from '../../services/updateService';
 import { useAppSettings } from './useAppSettings';
 import LayoutPreviewSVG, { type Variant } from './LayoutPreviewSVG';
 import {
@@ -39,6 +41,27 @@ import {
     type ActionId,
 } from '../../services/__contracts__/keymapService.contract';
 import { comboFromEvent } from '../../utils/kbd';
+import { errorKind, errorMessage } from '../../services/appError';
+import {
+    backgroundTaskService,
+    type InstalledModel,
+    type MirrorSettings,
+    type ModelUpdate,
+    type PendingDownload,
+    type PreferredHost,
+    type ProxyMode,
+    type ProxySettings,
+} from '../../services/backgroundTaskService';
+import {
+    offlineQueueService,
+    type ConflictPolicy,
+    type NoteConflict,
+    type ServerProfileStatus,
+} from '../../services/offlineQueueService';
+import {
+    denoisePreviewService,
+    type DenoisePreview,
+} from '../../services/denoisePreviewService';
 import s from './ProfilePage.module.css';
 
 /* ────────── provider credential helpers ───────── */
@@ -201,16 +224,18 @@ export function PInput({
     placeholder,
     monospace,
     wide,
+    password,
 }: {
     value: string;
     onChange?: (v: string) => void;
     placeholder?: string;
     monospace?: boolean;
     wide?: boolean;
+    password?: boolean;
 }) {
     return (
         <input
-            type="text"
+            type={password ? 'password' : 'text'}
             value={value}
             onChange={(e) => onChange?.(e.target.value)}
             placeholder={placeholder}
@@ -403,6 +428,38 @@ interface BuildFeaturesShape {
     gpu_vulkan: boolean;
 }
 
+const MIRROR_OPTIONS: { value: PreferredHost; label: string }[] = [
+    { value: 'origin', label: '官方' },
+    { value: 'hf_mirror', label: 'hf-mirror' },
+    { value: 'custom', label: '自訂' },
+];
+
+const PROXY_OPTIONS: { value: ProxyMode; label: string }[] = [
+    { value: 'system', label: '系統' },
+    { value: 'manual', label: '手動' },
+    { value: 'off', label: '不使用' },
+];
+
+/** Download speed limits offered, in bytes per second (0 = unlimited). */
+const RATE_LIMIT_LABEL: Record<number, string> = {
+    0: '不限速',
+    500_000: '500 KB/s',
+    1_000_000: '1 MB/s',
+    2_000_000: '2 MB/s',
+    5_000_000: '5 MB/s',
+    10_000_000: '10 MB/s',
+};
+
+/** The paused / interrupted Nemotron download of `variant`, if any. */
+function parakeetDownloadOf(
+    pending: PendingDownload[],
+    variant: 'int8' | 'fp32',
+): PendingDownload | undefined {
+    return pending.find(
+        (p) => p.source.model === 'parakeet' && p.source.variant === variant,
+    );
+}
+
 export function PTranscribe() {
     const { settings, update } = useAppSettings();
     const exp = settings?.experimental || {};
@@ -437,6 +494,10 @@ export function PTranscribe() {
         downloaded: number;
         total: number;
     } | null>(null);
+    /** Variants with a paused (or quit-interrupted) download to resume. */
+    const [parakeetPending, setParakeetPending] = useState<
+        Record<'int8' | 'fp32', boolean>
+    >({ int8: false, fp32: false });
 
     const refreshParakeetStatus = useCallback(async () => {
         try {
@@ -445,6 +506,11 @@ export function PTranscribe() {
                 'get_parakeet_status',
             );
             setParakeetStatus(status);
+            const pending = await backgroundTaskService.listDownloads();
+            setParakeetPending({
+                int8: !!parakeetDownloadOf(pending, 'int8'),
+                fp32: !!parakeetDownloadOf(pending, 'fp32'),
+            });
         } catch (err) {
             console.warn('[PTranscribe] get_parakeet_status failed:', err);
         }
@@ -481,6 +547,102 @@ export function PTranscribe() {
         void loadFeatures(false);
     }, []);
 
+    // Which host model downloads try first (downloads/mirrors.rs).
+    const [mirrors, setMirrors] = useState<MirrorSettings | null>(null);
+    const [customMirror, setCustomMirror] = useState('');
+
+    useEffect(() => {
+        backgroundTaskService
+            .getMirrors()
+            .then((m) => {
+                setMirrors(m);
+                setCustomMirror(m.custom_url ?? '');
+            })
+            .catch((err) =>
+                console.warn('[PTranscribe] get_download_mirrors failed:', err),
+            );
+    }, []);
+
+    // Proxy for model downloads (downloads/proxy.rs).
+    const [proxy, setProxy] = useState<ProxySettings | null>(null);
+    // 'manual' only opens the form; it's saved with the URL.
+    const [proxyMode, setProxyMode] = useState<ProxyMode>('system');
+    const [proxyDraft, setProxyDraft] = useState({
+        url: '',
+        username: '',
+        password: '',
+        noProxy: '',
+    });
+
+    const showProxy = (p: ProxySettings) => {
+        setProxy(p);
+        setProxyMode(p.mode);
+        setProxyDraft({
+            url: p.url ?? '',
+            username: p.username ?? '',
+            password: '',
+            noProxy: p.no_proxy ?? '',
+        });
+    };
+
+    useEffect(() => {
+        backgroundTaskService
+            .getProxy()
+            .then(showProxy)
+            .catch((err) =>
+                console.warn('[PTranscribe] get_download_proxy failed:', err),
+            );
+    }, []);
+
+    const saveProxy = async (mode: ProxyMode) => {
+        try {
+            const saved = await backgroundTaskService.setProxy({
+                mode,
+                url: proxyDraft.url,
+                username: proxyDraft.username,
+                // '' keeps the saved password (see ProxySettings.password).
+                password:
+                    proxyDraft.password ||
+                    (proxy?.password === '' && proxyDraft.username ? '' : null),
+                no_proxy: proxyDraft.noProxy,
+            });
+            showProxy(saved);
+        } catch (err) {
+            toastService.error('無法儲存下載代理', errorMessage(err));
+        }
+    };
+
+    const [rateLimit, setRateLimit] = useState<number | null>(null);
+
+    useEffect(() => {
+        backgroundTaskService
+            .getRateLimit()
+            .then(setRateLimit)
+            .catch((err) =>
+                console.warn('[PTranscribe] get_download_rate_limit failed:', err),
+            );
+    }, []);
+
+    const saveRateLimit = async (label: string) => {
+        const entry = Object.entries(RATE_LIMIT_LABEL).find(([, l]) => l === label);
+        if (!entry) return;
+        try {
+            setRateLimit(await backgroundTaskService.setRateLimit(Number(entry[0])));
+        } catch (err) {
+            toastService.error('無法儲存下載限速', errorMessage(err));
+        }
+    };
+
+    const saveMirrors = async (next: MirrorSettings) => {
+        try {
+            const saved = await backgroundTaskService.setMirrors(next);
+            setMirrors(saved);
+            setCustomMirror(saved.custom_url ?? '');
+        } catch (err) {
+            toastService.error('無法儲存下載來源', errorMessage(err));
+        }
+    };
+
     const setVariant = async (next: 'int8' | 'fp32') => {
         // Don't let the user switch to a variant they haven't downloaded
         // yet — the runtime would just fall back to first_present and
@@ -549,10 +711,18 @@ export function PTranscribe() {
                     `${target === 'fp32' ? 'FP32' : 'INT8'} 已下載；可在卡片切換為使用中。`,
                 );
             } catch (err) {
-                toastService.error(
-                    '模型下載失敗',
-                    (err as Error)?.message || String(err),
-                );
+                const kind = errorKind(err);
+                if (kind === 'paused') {
+                    toastService.info('下載已暫停', '按「繼續下載」從中斷處接著下載。');
+                } else if (kind === 'cancelled') {
+                    toastService.info('已取消下載', '已下載的部分已刪除。');
+                } else {
+                    toastService.error(
+                        '模型下載失敗',
+                        (err as Error)?.message || String(err),
+                    );
+                }
+                await refreshParakeetStatus();
             } finally {
                 if (unlisten) unlisten();
                 setParakeetDownloading(null);
@@ -562,6 +732,33 @@ export function PTranscribe() {
         [parakeetDownloading, refreshParakeetStatus],
     );
 
+    const handlePauseVariant = async (target: 'int8' | 'fp32') => {
+        const pending = await backgroundTaskService.listDownloads();
+        const id = parakeetDownloadOf(pending, target)?.id;
+        if (id) await backgroundTaskService.pauseDownload(id);
+    };
+
+    const handleCancelVariant = async (target: 'int8' | 'fp32') => {
+        const ok = await confirmService.ask({
+            title: '取消下載',
+            message: `要取消 ${target === 'fp32' ? 'FP32' : 'INT8'} 的下載並刪除已下載的部分嗎？`,
+            confirmLabel: '取消下載',
+            cancelLabel: '繼續保留',
+        });
+        if (!ok) return;
+        const pending = await backgroundTaskService.listDownloads();
+        const id = parakeetDownloadOf(pending, target)?.id;
+        if (!id) return;
+        try {
+            await backgroundTaskService.cancelDownload(id);
+        } catch (err) {
+            toastService.error('無法取消下載', errorMessage(err));
+        }
+        // A running download reports back through handleDownloadVariant;
+        // a paused one has no caller waiting, so refresh here.
+        if (parakeetDownloading !== target) await refreshParakeetStatus();
+    };
+
     return (
         <div>
             <PHeader
@@ -595,6 +792,9 @@ export function PTranscribe() {
                         }
                         onSelect={() => setVariant('int8')}
                         onDownload={() => handleDownloadVariant('int8')}
+                        resumable={parakeetPending.int8}
+                        onPause={() => void handlePauseVariant('int8')}
+                        onCancel={() => void handleCancelVariant('int8')}
                     />
                     <ModelCard
                         name="parakeet-fp32"
@@ -612,9 +812,129 @@ export function PTranscribe() {
                         }
                         onSelect={() => setVariant('fp32')}
                         onDownload={() => handleDownloadVariant('fp32')}
+                        resumable={parakeetPending.fp32}
+                        onPause={() => void handlePauseVariant('fp32')}
+                        onCancel={() => void handleCancelVariant('fp32')}
+                    />
+                </div>
+            </PRow>
+            <PRow
+                label="下載來源"
+                hint="先從哪裡下載模型；來源拒絕或連不上時會自動改用其他來源。無法連上 huggingface.co（例如中國大陸）請選 hf-mirror，或填入自訂的 Hugging Face 鏡像。"
+                right={
+                    mirrors && (
+                        <PSeg
+                            value={mirrors.prefer}
+                            options={MIRROR_OPTIONS}
+                            onChange={(prefer) =>
+                                void saveMirrors({
+                                    ...mirrors,
+                                    prefer,
+                                    custom_url: customMirror,
+                                })
+                            }
+                        />
+                    )
+                }
+            >
+                <div style={{ marginTop: 8, display: 'flex', gap: 6 }}>
+                    <PInput
+                        value={customMirror}
+                        onChange={setCustomMirror}
+                        placeholder="https://hf.example.edu"
+                        monospace
+                        wide
                     />
+                    <PBtn
+                        disabled={!mirrors}
+                        onClick={() =>
+                            mirrors &&
+                            void saveMirrors({ ...mirrors, custom_url: customMirror })
+                        }
+                    >
+                        儲存
+                    </PBtn>
                 </div>
             </PRow>
+            <PRow
+                label="下載代理"
+                hint="學校或公司網路需要代理才能連外時使用。「系統」沿用作業系統與 HTTP_PROXY 環境變數的設定。"
+                right={
+                    proxy && (
+                        <PSeg
+                            value={proxyMode}
+                            options={PROXY_OPTIONS}
+                            onChange={(mode) =>
+                                mode === 'manual'
+                                    ? setProxyMode(mode)
+                                    : void saveProxy(mode)
+                            }
+                        />
+                    )
+                }
+            >
+                {proxy && proxyMode === 'manual' && (
+                    <div
+                        style={{
+                            marginTop: 8,
+                            display: 'flex',
+                            flexWrap: 'wrap',
+                            gap: 6,
+                        }}
+                    >
+                        <PInput
+                            value={proxyDraft.url}
+                            onChange={(url) => setProxyDraft((d) => ({ ...d, url }))}
+                            placeholder="http://proxy.example.edu:3128"
+                            monospace
+                            wide
+                        />
+                        <PInput
+                            value={proxyDraft.username}
+                            onChange={(username) =>
+                                setProxyDraft((d) => ({ ...d, username }))
+                            }
+                            placeholder="使用者名稱（選填）"
+                        />
+                        <PInput
+                            value={proxyDraft.password}
+                            onChange={(password) =>
+                                setProxyDraft((d) => ({ ...d, password }))
+                            }
+                            placeholder={
+                                proxy.password === '' ? '已儲存密碼' : '密碼（選填）'
+                            }
+                            password
+                        />
+                        <PInput
+                            value={proxyDraft.noProxy}
+                            onChange={(noProxy) =>
+                                setProxyDraft((d) => ({ ...d, noProxy }))
+                            }
+                            placeholder="不經代理的主機，例如 localhost,.example.edu"
+                            monospace
+                            wide
+                        />
+                        <PBtn onClick={() => void saveProxy('manual')}>儲存</PBtn>
+                    </div>
+                )}
+            </PRow>
+            <PRow
+                label="下載限速"
+                hint="上課時下載模型，避免佔滿網路影響即時翻譯。對進行中的下載立即生效。"
+                right={
+                    rateLimit !== null && (
+                        <PSelect
+                            value={
+                                RATE_LIMIT_LABEL[rateLimit] ??
+                                `${Math.round(rateLimit / 1000)} KB/s`
+                            }
+                            options={Object.values(RATE_LIMIT_LABEL)}
+                            onChange={(label) => void saveRateLimit(label)}
+                        />
+                    )
+                }
+            />
 
             <PHead>GPU / 效能</PHead>
             <PRow
@@ -779,6 +1099,9 @@ function ModelCard({
     progress,
     onSelect,
     onDownload,
+    resumable,
+    onPause,
+    onCancel,
 }: {
     name: string;
     size: string;
@@ -796,12 +1119,19 @@ function ModelCard({
     onSelect?: () => void;
     /** Click handler for the「下載」action button. */
     onDownload?: () => void;
+    /** A paused / interrupted download of this variant can be continued. */
+    resumable?: boolean;
+    /** Pause the running download; resuming goes through `onDownload`. */
+    onPause?: () => void;
+    /** Stop the download and delete what it has written. */
+    onCancel?: () => void;
 }) {
     // cp75.10 — card behaviour:
     //   - loaded + !active → click row OR button to switch
     //   - !loaded         → click button to download (row click is no-op)
     //   - downloading     → button shows progress / disabled
     //   - active          → 使用中 (no action)
+    //   - downloading / resumable → extra 暫停 / 取消 buttons
     const cardInteractive = !!loaded && !!onSelect && !active;
     const showDownload = !loaded;
     const handleRowClick = () => {
@@ -831,7 +1161,30 @@ function ModelCard({
             : '下載中…'
         : loaded
         ? '切換'
+        : resumable
+        ? '繼續下載'
         : actionLabel || '下載';
+    const sideBtn = (label: string, onClick?: () => void) => (
+        <button
+            type="button"
+            onClick={(e) => {
+                e.stopPropagation();
+                onClick?.();
+            }}
+            style={{
+                padding: '4px 10px',
+                fontSize: 11,
+                borderRadius: 4,
+                border: '1px solid var(--h18-border-soft)',
+                background: 'var(--h18-surface)',
+                color: 'var(--h18-text-mid)',
+                cursor: 'pointer',
+                fontFamily: 'var(--h18-font-mono)',
+            }}
+        >
+            {label}
+        </button>
+    );
     return (
         <div
             role={cardInteractive ? 'button' : undefined}
@@ -869,33 +1222,37 @@ function ModelCard({
             <div style={{ fontSize: 10, color: 'var(--h18-text-mid)', lineHeight: 1.4 }}>
                 {hint}
             </div>
-            <button
-                type="button"
-                onClick={handleBtnClick}
-                disabled={active || (downloading && showDownload)}
-                style={{
-                    padding: '4px 10px',
-                    fontSize: 11,
-                    borderRadius: 4,
-                    border: '1px solid var(--h18-border-soft)',
-                    background: active
-                        ? 'var(--h18-surface)'
-                        : 'var(--h18-surface)',
-                    color: active
-                        ? 'var(--h18-text-dim)'
-                        : showDownload
-                        ? 'var(--h18-accent)'
-                        : 'var(--h18-text)',
-                    cursor: active
-                        ? 'default'
-                        : downloading
-                        ? 'wait'
-                        : 'pointer',
-                    fontFamily: 'var(--h18-font-mono)',
-                }}
-            >
-                {btnLabel}
-            </button>
+            <div style={{ display: 'flex', gap: 6 }}>
+                <button
+                    type="button"
+                    onClick={handleBtnClick}
+                    disabled={active || (downloading && showDownload)}
+                    style={{
+                        padding: '4px 10px',
+                        fontSize: 11,
+                        borderRadius: 4,
+                        border: '1px solid var(--h18-border-soft)',
+                        background: active
+                            ? 'var(--h18-surface)'
+                            : 'var(--h18-surface)',
+                        color: active
+                            ? 'var(--h18-text-dim)'
+                            : showDownload
+                            ? 'var(--h18-accent)'
+                            : 'var(--h18-text)',
+                        cursor: active
+                            ? 'default'
+                            : downloading
+                            ? 'wait'
+                            : 'pointer',
+                        fontFamily: 'var(--h18-font-mono)',
+                    }}
+                >
+                    {btnLabel}
+                </button>
+                {showDownload && downloading && onPause && sideBtn('暫停', onPause)}
+                {showDownload && (downloading || resumable) && onCancel && sideBtn('取消', onCancel)}
+            </div>
         </div>
     );
 }
@@ -1116,6 +1473,7 @@ export function PTranslate() {
     const handleStartSidecar = async () => {
         const modelPath = resolveSelectedGemmaModelPath();
         if (!modelPath) return;
+        if (!(await confirmModelLoad(`translation:${t?.gemma_variant ?? '4b'}`))) return;
         setSidecarBusy('starting');
         setSidecarMsg(null);
         try {
@@ -2316,7 +2674,7 @@ export function PKeyboard() {
             // stay in capture mode so the user can try again.
             void import('../../services/toastService').then(
                 ({ toastService }) => {
-                    toastService.warning('快捷鍵衝突', String(err));
+                    toastService.warning('快捷鍵衝突', errorMessage(err));
                 },
             );
         }
@@ -2381,6 +2739,14 @@ interface AudioDevice {
     label?: string;
 }
 
+const SOURCE_OPTIONS = [
+    { value: 'microphone', label: '麥克風' },
+    { value: 'system', label: '系統音訊' },
+    { value: 'both', label: '麥克風＋系統音訊' },
+] as const;
+
+const GAIN_OPTIONS = [25, 50, 75, 100, 150, 200] as const;
+
 const FONT_SIZE_OPTIONS = [
     { value: 12, label: '小' },
     { value: 16, label: '標準' },
@@ -2388,6 +2754,9 @@ const FONT_SIZE_OPTIONS = [
     { value: 26, label: '超大' },
 ] as const;
 
+/** Length of the clip recorded for the noise-suppression preview. */
+const DENOISE_PREVIEW_SECONDS = 5;
+
 export function PAudio() {
     const { settings, update } = useAppSettings();
     const sub = settings?.subtitle;
@@ -2422,6 +2791,41 @@ export function PAudio() {
               audioCfg.device_id)
         : '預設裝置';
 
+    const exp = settings?.experimental || {};
+    const [preview, setPreview] = useState<DenoisePreview | null>(null);
+    const [previewState, setPreviewState] = useState<
+        'idle' | 'recording' | 'playing'
+    >('idle');
+    useEffect(() => () => denoisePreviewService.stop(), []);
+
+    const recordPreview = async () => {
+        setPreviewState('recording');
+        try {
+            setPreview(
+                await denoisePreviewService.record({
+                    seconds: DENOISE_PREVIEW_SECONDS,
+                    deviceId: audioCfg?.device_id,
+                    source: audioCfg?.source,
+                }),
+            );
+        } catch (err) {
+            toastService.error('無法錄製試聽片段', errorMessage(err));
+        } finally {
+            setPreviewState('idle');
+        }
+    };
+
+    const playPreview = async (clip: Int16Array) => {
+        setPreviewState('playing');
+        try {
+            await denoisePreviewService.play(clip);
+        } catch (err) {
+            toastService.error('無法播放試聽片段', errorMessage(err));
+        } finally {
+            setPreviewState('idle');
+        }
+    };
+
     return (
         <div>
             <PHeader
@@ -2430,6 +2834,58 @@ export function PAudio() {
             />
 
             <PHead first>麥克風</PHead>
+            <PRow
+                label="錄音來源"
+                hint="系統音訊：錄下電腦正在播放的聲音（Zoom / Teams 線上課程）"
+                right={
+                    <PSelect
+                        value={
+                            SOURCE_OPTIONS.find(
+                                (o) => o.value === (audioCfg?.source ?? 'microphone'),
+                            )?.label ?? '麥克風'
+                        }
+                        options={SOURCE_OPTIONS.map((o) => o.label)}
+                        onChange={(label) =>
+                            update({
+                                audio: {
+                                    ...audioCfg,
+                                    sample_rate: audioCfg?.sample_rate ?? 48000,
+                                    chunk_duration:
+                                        audioCfg?.chunk_duration ?? 5,
+                                    source:
+                                        SOURCE_OPTIONS.find((o) => o.label === label)
+                                            ?.value ?? 'microphone',
+                                },
+                            })
+                        }
+                    />
+                }
+            />
+            {audioCfg?.source === 'both' &&
+                (['mic_gain', 'system_gain'] as const).map((key) => (
+                    <PRow
+                        key={key}
+                        label={key === 'mic_gain' ? '麥克風音量' : '系統音訊音量'}
+                        hint="混音時此來源的增益"
+                        right={
+                            <PSelect
+                                value={`${Math.round((audioCfg?.[key] ?? 1) * 100)}%`}
+                                options={GAIN_OPTIONS.map((g) => `${g}%`)}
+                                onChange={(label) =>
+                                    update({
+                                        audio: {
+                                            ...audioCfg,
+                                            sample_rate: audioCfg?.sample_rate ?? 48000,
+                                            chunk_duration:
+                                                audioCfg?.chunk_duration ?? 5,
+                                            [key]: parseInt(label, 10) / 100,
+                                        },
+                                    })
+                                }
+                            />
+                        }
+                    />
+                ))}
             <PRow
                 label="輸入裝置"
                 hint={
@@ -2450,6 +2906,11 @@ export function PAudio() {
                                     sample_rate: audioCfg?.sample_rate ?? 48000,
                                     chunk_duration: audioCfg?.chunk_duration ?? 5,
                                     device_id: d?.deviceId,
+                                    auto_switch_detection:
+                                        audioCfg?.auto_switch_detection,
+                                    source: audioCfg?.source,
+                                    mic_gain: audioCfg?.mic_gain,
+                                    system_gain: audioCfg?.system_gain,
                                 },
                             });
                         }}
@@ -2470,6 +2931,9 @@ export function PAudio() {
                                         audioCfg?.chunk_duration ?? 5,
                                     device_id: audioCfg?.device_id,
                                     auto_switch_detection: v,
+                                    source: audioCfg?.source,
+                                    mic_gain: audioCfg?.mic_gain,
+                                    system_gain: audioCfg?.system_gain,
                                 },
                             })
                         }
@@ -2477,6 +2941,72 @@ export function PAudio() {
                 }
             />
 
+            <PHead>收音處理</PHead>
+            <PRow
+                label="自動音量調整"
+                hint="講者離麥克風較遠時，轉錄前自動拉高音量（實驗性）"
+                right={
+                    <PToggle
+                        on={exp.normalizeGain === true}
+                        onChange={(v) =>
+                            update({ experimental: { ...exp, normalizeGain: v } })
+                        }
+                    />
+                }
+            />
+            <PRow
+                label="背景降噪"
+                hint="轉錄前壓低冷氣、風扇等持續性雜音（實驗性）"
+                right={
+                    <PToggle
+                        on={exp.denoiseInput === true}
+                        onChange={(v) =>
+                            update({ experimental: { ...exp, denoiseInput: v } })
+                        }
+                    />
+                }
+            />
+            <PRow
+                label="降噪試聽"
+                hint={
+                    previewState === 'recording'
+                        ? `錄音中… 請說話 ${DENOISE_PREVIEW_SECONDS} 秒`
+                        : preview
+                          ? '比較原始與降噪後的片段'
+                          : `用目前的輸入裝置錄 ${DENOISE_PREVIEW_SECONDS} 秒，比較降噪前後`
+                }
+                right={
+                    <div style={{ display: 'flex', gap: 6 }}>
+                        <PBtn
+                            disabled={previewState !== 'idle'}
+                            onClick={() => void recordPreview()}
+                        >
+                            {previewState === 'recording'
+                                ? '錄音中…'
+                                : preview
+                                  ? '重新錄製'
+                                  : '錄製試聽'}
+                        </PBtn>
+                        {preview && (
+                            <>
+                                <PBtn
+                                    disabled={previewState !== 'idle'}
+                                    onClick={() => void playPreview(preview.original)}
+                                >
+                                    播放原始
+                                </PBtn>
+                                <PBtn
+                                    disabled={previewState !== 'idle'}
+                                    onClick={() => void playPreview(preview.denoised)}
+                                >
+                                    播放降噪後
+                                </PBtn>
+                            </>
+                        )}
+                    </div>
+                }
+            />
+
             <PHead>字幕外觀</PHead>
             <PRow
                 label="字幕字級"
@@ -2559,11 +3089,110 @@ interface CourseGroup {
     lectures: Lecture[];
 }
 
+const MODEL_ENGINE_LABEL: Record<InstalledModel['engine'], string> = {
+    whisper: 'Whisper',
+    parakeet: 'Parakeet',
+    translategemma: 'TranslateGemma',
+    translation: '翻譯 (M2M100)',
+    embedding: 'Embedding',
+    other: '其他',
+};
+
+function formatModelSize(bytes: number): string {
+    if (bytes >= 1e9) return `${(bytes / 1e9).toFixed(2)} GB`;
+    return `${Math.max(1, Math.round(bytes / 1e6))} MB`;
+}
+
 export function PData() {
     const [trashedLectures, setTrashedLectures] = useState<Lecture[]>([]);
     const [trashedCourses, setTrashedCourses] = useState<Course[]>([]);
     const [selected, setSelected] = useState<Set<string>>(new Set());
     const [busy, setBusy] = useState(false);
+    const [models, setModels] = useState<InstalledModel[]>([]);
+    const [deletingModel, setDeletingModel] = useState<string | null>(null);
+    /** null until checked. */
+    const [modelUpdates, setModelUpdates] = useState<ModelUpdate[] | null>(null);
+    const [checkingUpdates, setCheckingUpdates] = useState(false);
+    const [applyingUpdate, setApplyingUpdate] = useState<string | null>(null);
+
+    const loadModels = async () => {
+        try {
+            setModels(await backgroundTaskService.listInstalledModels());
+        } catch (err) {
+            console.warn('[PData] list_installed_models failed:', err);
+        }
+    };
+
+    useEffect(() => {
+        void loadModels();
+    }, []);
+
+    const handleDeleteModel = async (model: InstalledModel) => {
+        if (deletingModel) return;
+        const ok = await confirmService.ask({
+            title: '刪除模型',
+            message: `「${MODEL_ENGINE_LABEL[model.engine]} ${model.variant}」(${formatModelSize(model.size)}) 會從磁碟刪除，之後要用需要重新下載。`,
+            confirmLabel: '刪除',
+            cancelLabel: '取消',
+            variant: 'danger',
+        });
+        if (!ok) return;
+
+        setDeletingModel(model.id);
+        try {
+            const freed = await backgroundTaskService.deleteModel(model.id);
+            toastService.success('已刪除模型', `釋放 ${formatModelSize(freed)}`);
+        } catch (err) {
+            if (errorKind(err) === 'busy') {
+                toastService.warning('模型使用中', errorMessage(err));
+            } else {
+                toastService.error('刪除模型失敗', errorMessage(err));
+            }
+        } finally {
+            setDeletingModel(null);
+            await loadModels();
+        }
+    };
+
+    const handleCheckUpdates = async () => {
+        if (checkingUpdates) return;
+        setCheckingUpdates(true);
+        try {
+            setModelUpdates(await backgroundTaskService.checkModelUpdates());
+        } catch (err) {
+            toastService.error('檢查模型更新失敗', errorMessage(err));
+        } finally {
+            setCheckingUpdates(false);
+        }
+    };
+
+    const handleApplyUpdate = async (update: ModelUpdate) => {
+        if (applyingUpdate) return;
+        setApplyingUpdate(update.installed_id);
+        try {
+            await backgroundTaskService.applyModelUpdate(update.installed_id);
+            toastService.success(
+                update.kind === 'update' ? '模型已更新' : '已下載新版模型',
+                update.kind === 'update'
+                    ? `${update.label} ${update.version}`
+                    : `${update.label} ${update.version}，舊版模型可在上方刪除`,
+            );
+            setModelUpdates((prev) =>
+                prev?.filter((u) => u.installed_id !== update.installed_id) ?? null,
+            );
+        } catch (err) {
+            if (errorKind(err) === 'busy') {
+                toastService.warning('模型使用中', errorMessage(err));
+            } else {
+                toastService.error('更新模型失敗', errorMessage(err));
+            }
+        } finally {
+            setApplyingUpdate(null);
+            await loadModels();
+        }
+    };
+
+    const modelsTotal = models.reduce((sum, m) => sum + m.size, 0);
 
     const loadTrash = async () => {
         // cp75.19 — pass the current user's id, NOT `null`.
@@ -2701,7 +3330,7 @@ export function PData() {
                     new CustomEvent('classnote-courses-changed'),
                 );
             } catch (err) {
-                toastService.error('還原失敗', String(err));
+                toastService.error('還原失敗', errorMessage(err));
             } finally {
                 setBusy(false);
             }
@@ -2717,7 +3346,7 @@ export function PData() {
             await loadTrash();
             toastService.success('已還原', `「${lecture.title}」已還原`);
         } catch (err) {
-            toastService.error('還原失敗', String(err));
+            toastService.error('還原失敗', errorMessage(err));
         } finally {
             setBusy(false);
         }
@@ -2739,7 +3368,7 @@ export function PData() {
             );
             window.dispatchEvent(new CustomEvent('classnote-courses-changed'));
         } catch (err) {
-            toastService.error('還原失敗', String(err));
+            toastService.error('還原失敗', errorMessage(err));
         } finally {
             setBusy(false);
         }
@@ -2902,7 +3531,7 @@ export function PData() {
             );
             window.dispatchEvent(new CustomEvent('classnote-courses-changed'));
         } catch (err) {
-            toastService.error('永久刪除失敗', String(err));
+            toastService.error('永久刪除失敗', errorMessage(err));
         }
     };
 
@@ -2931,7 +3560,7 @@ export function PData() {
         <div>
             <PHeader
                 title="資料管理"
-                hint="本機資料的匯入、匯出、備份；已刪除課程在這裡找得回來。"
+                hint="本機資料的匯入、匯出、備份與下載的模型；已刪除課程在這裡找得回來。"
             />
 
             <PHead first>匯入 / 匯出</PHead>
@@ -2954,6 +3583,146 @@ export function PData() {
                 }
             />
 
+            <PHead>模型</PHead>
+            <PRow
+                label={`已安裝模型 · ${formatModelSize(modelsTotal)}`}
+                hint="下載過的語音辨識、翻譯與 embedding 模型。刪除可以釋放空間，之後用到時會重新下載。"
+            >
+                {models.length === 0 ? (
+                    <div
+                        style={{
+                            marginTop: 8,
+                            padding: 16,
+                            borderRadius: 8,
+                            background: 'var(--h18-surface2)',
+                            border: '1px dashed var(--h18-border-soft)',
+                            color: 'var(--h18-text-dim)',
+                            fontSize: 12,
+                            textAlign: 'center',
+                        }}
+                    >
+                        還沒有下載任何模型。
+                    </div>
+                ) : (
+                    <div
+                        className={s.trashList}
+                        style={{ marginTop: 10, gap: 4 }}
+                    >
+                        {models.map((m) => (
+                            <div
+                                key={m.id}
+                                style={{
+                                    display: 'grid',
+                                    gridTemplateColumns: '1fr auto auto auto',
+                                    gap: 10,
+                                    alignItems: 'center',
+                                    fontSize: 12,
+                                    padding: '4px 6px',
+                                }}
+                            >
+                                <span
+                                    title={m.path}
+                                    style={{
+                                        color: 'var(--h18-text)',
+                                        overflow: 'hidden',
+                                        textOverflow: 'ellipsis',
+                                        whiteSpace: 'nowrap',
+                                    }}
+                                >
+                                    {MODEL_ENGINE_LABEL[m.engine]} · {m.variant}
+                                </span>
+                                <span className={s.trashMeta}>
+                                    {m.last_used
+                                        ? `上次使用 ${m.last_used.slice(0, 10)}`
+                                        : '尚未使用'}
+                                </span>
+                                <span className={s.trashMeta}>
+                                    {formatModelSize(m.size)}
+                                </span>
+                                <PBtn
+                                    danger
+                                    disabled={deletingModel !== null}
+                                    onClick={() => handleDeleteModel(m)}
+                                >
+                                    {deletingModel === m.id ? '刪除中…' : '刪除'}
+                                </PBtn>
+                            </div>
+                        ))}
+                    </div>
+                )}
+            </PRow>
+            <PRow
+                label="模型更新"
+                hint={
+                    modelUpdates === null
+                        ? '和發布的模型清單比對，看看有沒有新版或更小的量化版本。第一次檢查要計算檔案雜湊，可能需要一點時間。'
+                        : modelUpdates.length === 0
+                          ? '已安裝的模型都是最新版。'
+                          : `${modelUpdates.length} 個模型有新版本。`
+                }
+                right={
+                    <PBtn
+                        disabled={checkingUpdates || models.length === 0}
+                        onClick={handleCheckUpdates}
+                    >
+                        {checkingUpdates ? '檢查中…' : '檢查更新'}
+                    </PBtn>
+                }
+            >
+                {modelUpdates && modelUpdates.length > 0 && (
+                    <div
+                        className={s.trashList}
+                        style={{ marginTop: 10, gap: 8 }}
+                    >
+                        {modelUpdates.map((u) => (
+                            <div
+                                key={u.installed_id}
+                                style={{
+                                    display: 'grid',
+                                    gridTemplateColumns: '1fr auto auto',
+                                    gap: 10,
+                                    alignItems: 'start',
+                                    fontSize: 12,
+                                    padding: '4px 6px',
+                                }}
+                            >
+                                <div style={{ minWidth: 0 }}>
+                                    <div style={{ color: 'var(--h18-text)' }}>
+                                        {u.label} · {u.version}
+                                        {u.kind === 'upgrade' && (
+                                            <span className={s.trashMeta}>
+                                                {' '}（取代 {u.installed_id}）
+                                            </span>
+                                        )}
+                                    </div>
+                                    {u.changelog && (
+                                        <div
+                                            className={s.trashMeta}
+                                            style={{ whiteSpace: 'pre-wrap', marginTop: 2 }}
+                                        >
+                                            {u.changelog}
+                                        </div>
+                                    )}
+                                </div>
+                                <span className={s.trashMeta}>
+                                    {formatModelSize(u.size)}
+                                </span>
+                                <PBtn
+                                    disabled={applyingUpdate !== null}
+                                    onClick={() => handleApplyUpdate(u)}
+                                >
+                                    {applyingUpdate === u.installed_id
+                                        ? '下載中…'
+                                        : u.kind === 'update'
+                                          ? '更新'
+                                          : '下載新版'}
+                                </PBtn>
+                            </div>
+                        ))}
+                    </div>
+                )}
+            </PRow>
+
             <PHead>回收桶</PHead>
             <PRow
                 label={`垃圾桶 · ${totalCount}`}
@@ -3344,6 +4113,247 @@ export function PIntegrations() {
     );
 }
 
+/* ════════════════════════════════════════════════════════════════
+ * PSync — 雲端同步 (src-tauri/src/sync)
+ *
+ * 伺服器：目前只有一個 active profile 會被同步；密碼 / token 存在 OS
+ * keychain，這裡只送出不讀回。「立即同步」排一個 SYNC_PUSH 到 active
+ * profile (syncActiveProfile)，進度走 TaskIndicator。
+ *
+ * 衝突：兩邊都改過的筆記照 conflict policy 處理；選「每次詢問」時
+ * 兩份都留著，等使用者在這裡挑 (list_note_conflicts /
+ * resolve_note_conflict)。每次同步跑完重新拉一次清單。
+ * ════════════════════════════════════════════════════════════════ */
+
+const CONFLICT_POLICY_OPTIONS: { value: ConflictPolicy; label: string }[] = [
+    { value: 'lww', label: '較新的為準' },
+    { value: 'prefer_local', label: '本機為準' },
+    { value: 'ask', label: '每次詢問' },
+];
+
+export function PSync() {
+    const [profiles, setProfiles] = useState<ServerProfileStatus[]>([]);
+    const [serverUrl, setServerUrl] = useState('');
+    const [account, setAccount] = useState('');
+    const [credentials, setCredentials] = useState('');
+    const [savingProfile, setSavingProfile] = useState(false);
+    const [policy, setPolicy] = useState<ConflictPolicy>('lww');
+    const [conflicts, setConflicts] = useState<NoteConflict[]>([]);
+    const [resolvingId, setResolvingId] = useState<string | null>(null);
+
+    const active = profiles.find((p) => p.active) ?? null;
+
+    const loadProfiles = useCallback(async () => {
+        try {
+            const list = await offlineQueueService.listProfiles();
+            setProfiles(list);
+            const current = list.find((p) => p.active);
+            if (current) {
+                setServerUrl(current.server_url);
+                setAccount(current.username);
+            }
+        } catch (err) {
+            console.warn('[PSync] list_server_profiles failed:', err);
+        }
+    }, []);
+
+    const loadConflicts = useCallback(async () => {
+        try {
+            setConflicts(await offlineQueueService.listNoteConflicts());
+        } catch (err) {
+            console.warn('[PSync] list_note_conflicts failed:', err);
+        }
+    }, []);
+
+    useEffect(() => {
+        offlineQueueService
+            .getConflictPolicy()
+            .then(setPolicy)
+            .catch((err) => console.warn('[PSync] get policy failed:', err));
+        void loadProfiles();
+        void loadConflicts();
+        return offlineQueueService.onProgress((report) => {
+            if (report.phase !== 'finished') return;
+            void loadProfiles();
+            void loadConflicts();
+        });
+    }, [loadProfiles, loadConflicts]);
+
+    const handleSaveProfile = async () => {
+        const url = serverUrl.trim().replace(/\/+$/, '');
+        let name = url;
+        try {
+            name = new URL(url).host;
+        } catch {
+            toastService.error('伺服器網址無效', '請填完整網址，例如 https://sync.example.edu');
+            return;
+        }
+        setSavingProfile(true);
+        try {
+            await offlineQueueService.saveProfile(
+                {
+                    id: active?.id ?? '',
+                    name: active?.name || name,
+                    server_url: url,
+                    username: account.trim(),
+                },
+                credentials ? credentials : undefined,
+            );
+            setCredentials('');
+            toastService.success('已儲存同步伺服器');
+            await loadProfiles();
+        } catch (err) {
+            toastService.error('無法儲存同步伺服器', errorMessage(err));
+        } finally {
+            setSavingProfile(false);
+        }
+    };
+
+    const handleSyncNow = async () => {
+        try {
+            const id = await offlineQueueService.syncActiveProfile();
+            if (id) {
+                toastService.info('已排入同步', '進度顯示在背景工作清單。');
+            } else {
+                toastService.warning('尚未設定同步伺服器', '先填好伺服器網址與帳號並儲存。');
+            }
+        } catch (err) {
+            toastService.error('無法開始同步', errorMessage(err));
+        }
+    };
+
+    const handlePolicy = async (next: ConflictPolicy) => {
+        const prev = policy;
+        setPolicy(next);
+        try {
+            await offlineQueueService.setConflictPolicy(next);
+        } catch (err) {
+            setPolicy(prev);
+            toastService.error('無法儲存衝突處理方式', errorMessage(err));
+        }
+    };
+
+    const handleResolve = async (conflict: NoteConflict, keep: 'local' | 'server') => {
+        setResolvingId(conflict.id);
+        try {
+            await offlineQueueService.resolveNoteConflict(conflict.id, keep);
+            toastService.success(
+                keep === 'local' ? '已保留本機版本' : '已改用伺服器版本',
+                '另一份留作衝突副本。',
+            );
+            await loadConflicts();
+        } catch (err) {
+            toastService.error('無法處理衝突', errorMessage(err));
+        } finally {
+            setResolvingId(null);
+        }
+    };
+
+    return (
+        <div>
+            <PHeader
+                title="同步"
+                hint="把課程、課堂與筆記同步到自架伺服器。"
+            />
+
+            <PHead first>伺服器</PHead>
+            <PRow
+                label="伺服器網址"
+                right={
+                    <PInput
+                        placeholder="https://sync.example.edu"
+                        value={serverUrl}
+                        onChange={setServerUrl}
+                        monospace
+                        wide
+                    />
+                }
+            />
+            <PRow
+                label="帳號"
+                right={<PInput value={account} onChange={setAccount} />}
+            />
+            <PRow
+                label="密碼或 token"
+                hint={
+                    active?.has_credentials
+                        ? '已存在系統鑰匙圈；留空則沿用。'
+                        : '存在系統鑰匙圈，不會寫進資料庫。'
+                }
+                right={
+                    <>
+                        <PInput value={credentials} onChange={setCredentials} password />
+                        <PBtn
+                            disabled={savingProfile || !serverUrl.trim() || !account.trim()}
+                            onClick={handleSaveProfile}
+                        >
+                            {savingProfile ? '儲存中…' : '儲存'}
+                        </PBtn>
+                    </>
+                }
+            />
+            <PRow
+                label="同步"
+                hint={
+                    active
+                        ? active.last_pushed_at
+                            ? `上次同步 ${new Date(active.last_pushed_at).toLocaleString()}`
+                            : '尚未同步過。'
+                        : '儲存伺服器後才能同步。'
+                }
+                right={
+                    <PBtn primary disabled={!active} onClick={handleSyncNow}>
+                        立即同步
+                    </PBtn>
+                }
+            />
+
+            <PHead>筆記衝突</PHead>
+            <PRow
+                label="兩邊都改過時"
+                hint="較新的為準 / 本機為準：另一份留作衝突副本。每次詢問：兩份都不動，等你在下方選。"
+                right={
+                    <PSeg
+                        value={policy}
+                        options={CONFLICT_POLICY_OPTIONS}
+                        onChange={handlePolicy}
+                    />
+                }
+            />
+            {conflicts.length === 0 ? (
+                <p style={{ fontSize: 11, color: 'var(--h18-text-dim)', marginTop: 6 }}>
+                    沒有待處理的衝突。
+                </p>
+            ) : (
+                conflicts.map((c) => (
+                    <PRow
+                        key={c.id}
+                        label={c.title || '(未命名筆記)'}
+                        hint={`伺服器上的版本 · ${new Date(c.conflicted_at).toLocaleString()}`}
+                        right={
+                            <>
+                                <PBtn
+                                    disabled={resolvingId === c.id}
+                                    onClick={() => handleResolve(c, 'local')}
+                                >
+                                    保留本機
+                                </PBtn>
+                                <PBtn
+                                    primary
+                                    disabled={resolvingId === c.id}
+                                    onClick={() => handleResolve(c, 'server')}
+                                >
+                                    用伺服器版本
+                                </PBtn>
+                            </>
+                        }
+                    />
+                ))
+            )}
+        </div>
+    );
+}
+
 /* ════════════════════════════════════════════════════════════════
  * PAbout — 關於與更新
  * ════════════════════════════════════════════════════════════════ */
@@ -3358,6 +4368,7 @@ export function PAbout() {
         version?: string;
         message?: string;
     }>({ kind: 'idle' });
+    const [releaseNotes, setReleaseNotes] = useState<ReleaseNotes | null>(null);
 
     useEffect(() => {
         import('@tauri-apps/api/app')
@@ -3389,6 +4400,10 @@ export function PAbout() {
                     kind: 'available',
                     version: result.version,
                 });
+                updateService
+                    .getReleaseNotes(result.version)
+                    .then(setReleaseNotes)
+                    .catch((err) => console.warn('[PAbout] release notes failed:', err));
             } else {
                 setUpdateState({ kind: 'latest' });
             }
@@ -3506,6 +4521,22 @@ export function PAbout() {
                     <PBtn primary>下載</PBtn>
                 </div>
             )}
+            {updateState.kind === 'available' &&
+                releaseNotes?.version === updateState.version?.replace(/^v/, '') && (
+                    <div className={s.aboutReleaseNotes}>
+                        {releaseNotes.migrationWarnings.length > 0 && (
+                            <div className={s.aboutReleaseNotesWarning}>
+                                <strong>⚠ 此版本會轉移資料，更新前請先備份：</strong>
+                                <ul>
+                                    {releaseNotes.migrationWarnings.map((w) => (
+                                        <li key={w}>{w}</li>
+                                    ))}
+                                </ul>
+                            </div>
+                        )}
+                        <div className={s.aboutReleaseNotesBody}>{releaseNotes.notes}</div>
+                    </div>
+                )}
             {updateState.kind === 'latest' && (
                 <div className={s.aboutUpdateBanner}>
                     <span className={s.aboutUpdateBannerIcon}>✓</span>
@@ -3536,14 +4567,17 @@ export function PAbout() {
                     <PSelect
                         value={updates?.channel || 'stable'}
                         options={['stable', 'beta', 'alpha']}
-                        onChange={(v) =>
+                        onChange={(v) => {
                             update({
                                 updates: {
                                     ...(updates || {}),
                                     channel: v as 'stable' | 'beta' | 'alpha',
                                 },
-                            })
-                        }
+                            });
+                            invoke('set_update_channel', { channel: v }).catch((err) =>
+                                console.warn('[ProfilePanes] set_update_channel failed:', err),
+                            );
+                        }}
                     />
                 }
             />
@@ -100,6 +100,7 @@ vi.mock('../../../services/storageService', () => ({
 
 vi.mock('../../../services/audioPathService', () => ({
     resolveOrRecoverAudioPath: vi.fn(async () => ({ resolvedPath: null })),
+    probeAudio: vi.fn(async () => ({ playable: true, error: null })),
 }));
 
 vi.mock('@tauri-apps/api/core', () => ({
@@ -89,6 +89,7 @@ vi.mock('../../../services/storageService', () => ({
 // audioPathService — never resolves so audio tab stays in "no audio".
 vi.mock('../../../services/audioPathService', () => ({
     resolveOrRecoverAudioPath: vi.fn(async () => ({ resolvedPath: null })),
+    probeAudio: vi.fn(async () => ({ playable: true, error: null })),
 }));
 
 // convertFileSrc — short-circuit.
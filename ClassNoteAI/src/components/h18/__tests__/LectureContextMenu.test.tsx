@@ -6,10 +6,11 @@
  *
  *   1. 編輯              → onEdit (caller opens LectureEditDialog)
  *   2. 重新命名           → onRename (caller toggles inline rename)
- *   3. 匯出 ▸             → SRT 字幕 / Markdown   (calls exportService)
+ *   3. 匯出 ▸             → SRT 字幕 / Markdown / PDF 筆記 / Word (DOCX)   (calls exportService)
  *   4. 移動到其他課程 ▸   → list of OTHER courses (calls onMoveToCourse)
- *   5. ─sep
- *   6. 刪除               → confirmService.ask → onDelete
+ *   5. 上傳錄音            → offlineQueueService.uploadLectureAudio
+ *   6. ─sep
+ *   7. 刪除               → confirmService.ask → onDelete
  *
  * Specs covered (覆蓋 sub-agent prompt 列出的 11 個)：
  *   1. mounts + renders 5 leaf items (編輯 / 重命名 / 匯出 / 移動 / 刪除)
@@ -33,6 +34,7 @@ import { render, screen, fireEvent, waitFor } from '@testing-library/react';
 // ── module mocks: must be hoisted before importing the SUT ──────────
 vi.mock('../../../services/exportService', () => ({
     exportLecture: vi.fn(() => Promise.resolve({ path: '/tmp/x.srt', size: 10 })),
+    exportNote: vi.fn(() => Promise.resolve({ path: '/tmp/x.pdf' })),
 }));
 
 vi.mock('../../../services/toastService', () => ({
@@ -50,11 +52,18 @@ vi.mock('../../../services/confirmService', () => ({
     },
 }));
 
+vi.mock('../../../services/offlineQueueService', () => ({
+    offlineQueueService: {
+        uploadLectureAudio: vi.fn(() => Promise.resolve('action-1')),
+    },
+}));
+
 import { LectureContextMenu } from '../LectureContextMenu';
 import type { Course, Lecture } from '../../../types';
-import { exportLecture } from '../../../services/exportService';
+import { exportLecture, exportNote } from '../../../services/exportService';
 import { toastService } from '../../../services/toastService';
 import { confirmService } from '../../../services/confirmService';
+import { offlineQueueService } from '../../../services/offlineQueueService';
 
 function makeLecture(over: Partial<Lecture> = {}): Lecture {
     return {
@@ -204,6 +213,26 @@ describe('LectureContextMenu', () => {
         );
     });
 
+    // ── 5b'. 點 PDF 筆記 → exportNote('pdf') called ──────────────────
+    it('clicking PDF 筆記 calls exportNote("pdf")', async () => {
+        const { lecture } = setup();
+        fireEvent.mouseEnter(screen.getByText('匯出'));
+        fireEvent.click(screen.getByText('PDF 筆記'));
+        await waitFor(() =>
+            expect(exportNote).toHaveBeenCalledWith(lecture.id, 'pdf'),
+        );
+    });
+
+    // ── 5b''. 點 Word (DOCX) → exportNote('docx') called ─────────────
+    it('clicking Word (DOCX) calls exportNote("docx")', async () => {
+        const { lecture } = setup();
+        fireEvent.mouseEnter(screen.getByText('匯出'));
+        fireEvent.click(screen.getByText('Word (DOCX)'));
+        await waitFor(() =>
+            expect(exportNote).toHaveBeenCalledWith(lecture.id, 'docx'),
+        );
+    });
+
     // ── 5c. export 失敗 → toast.error ────────────────────────────────
     it('shows toast.error when exportLecture rejects', async () => {
         (exportLecture as unknown as ReturnType<typeof vi.fn>).mockRejectedValueOnce(
@@ -319,4 +348,20 @@ describe('LectureContextMenu', () => {
         const menu = screen.getByRole('menu');
         expect(menu.getAttribute('aria-label')).toContain('專屬標題');
     });
+
+    // ── 13. 上傳錄音 → AUDIO_UPLOAD；沒錄音時 disabled ─────────────
+    it('clicking 上傳錄音 queues the recording upload', async () => {
+        setup({ lecture: makeLecture({ audio_path: '/rec/lec-1.wav' }) });
+        fireEvent.click(screen.getByText('上傳錄音'));
+        await waitFor(() =>
+            expect(offlineQueueService.uploadLectureAudio).toHaveBeenCalledWith('lec-1'),
+        );
+        await waitFor(() => expect(toastService.info).toHaveBeenCalled());
+    });
+
+    it('上傳錄音 does nothing for a lecture without a recording', () => {
+        setup();
+        fireEvent.click(screen.getByText('上傳錄音'));
+        expect(offlineQueueService.uploadLectureAudio).not.toHaveBeenCalled();
+    });
 });
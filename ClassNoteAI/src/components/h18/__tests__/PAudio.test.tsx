@@ -0,0 +1,124 @@
+/**
+ * PAudio · 收音處理 tests.
+ *
+ * The gain toggle writes `experimental.normalizeGain` and the
+ * noise-suppression toggle `experimental.denoiseInput`; the
+ * preview records a clip through denoisePreviewService and plays the
+ * original or the denoised side back.
+ */
+
+import { describe, it, expect, vi, beforeEach } from 'vitest';
+import { render, screen, fireEvent, act } from '@testing-library/react';
+
+const { mockUpdate, mockPreview, mockToast } = vi.hoisted(() => ({
+    mockUpdate: vi.fn(),
+    mockPreview: {
+        record: vi.fn(async (_options: unknown) => ({
+            original: Int16Array.from([1, 2, 3]),
+            denoised: Int16Array.from([0, 1, 0]),
+        })),
+        play: vi.fn(async (_clip: Int16Array) => undefined),
+        stop: vi.fn(),
+    },
+    mockToast: {
+        success: vi.fn(),
+        error: vi.fn(),
+        info: vi.fn(),
+        warning: vi.fn(),
+        show: vi.fn(),
+    },
+}));
+
+vi.mock('../useAppSettings', () => ({
+    useAppSettings: () => ({
+        settings: {
+            audio: { sample_rate: 48000, chunk_duration: 5, device_id: 'mic-2' },
+            experimental: { logLevel: 'info' },
+        },
+        loading: false,
+        update: mockUpdate,
+        reload: vi.fn(),
+    }),
+}));
+
+vi.mock('../../../services/denoisePreviewService', () => ({
+    denoisePreviewService: mockPreview,
+}));
+
+vi.mock('../../../services/toastService', () => ({
+    toastService: mockToast,
+}));
+
+import { PAudio } from '../ProfilePanes';
+
+async function flush(times = 4) {
+    for (let i = 0; i < times; i++) {
+        // eslint-disable-next-line @typescript-eslint/no-empty-function
+        await act(async () => {});
+    }
+}
+
+function toggleFor(label: string): HTMLElement {
+    const row = screen.getByText(label).parentElement?.parentElement;
+    const toggle = row?.querySelector('button[aria-pressed]');
+    if (!toggle) throw new Error(`no toggle for ${label}`);
+    return toggle as HTMLElement;
+}
+
+beforeEach(() => {
+    vi.clearAllMocks();
+});
+
+describe('PAudio · 自動音量調整', () => {
+    it('turns gain normalisation on without dropping other experimental settings', () => {
+        render(<PAudio />);
+
+        fireEvent.click(toggleFor('自動音量調整'));
+
+        expect(mockUpdate).toHaveBeenCalledWith({
+            experimental: { logLevel: 'info', normalizeGain: true },
+        });
+    });
+});
+
+describe('PAudio · 背景降噪', () => {
+    it('turns noise suppression on without dropping other experimental settings', () => {
+        render(<PAudio />);
+
+        fireEvent.click(toggleFor('背景降噪'));
+
+        expect(mockUpdate).toHaveBeenCalledWith({
+            experimental: { logLevel: 'info', denoiseInput: true },
+        });
+    });
+
+    it('records a preview from the configured input and plays either side', async () => {
+        render(<PAudio />);
+        expect(screen.queryByText('播放降噪後')).toBeNull();
+
+        fireEvent.click(screen.getByText('錄製試聽'));
+        await flush();
+
+        expect(mockPreview.record).toHaveBeenCalledWith(
+            expect.objectContaining({ seconds: 5, deviceId: 'mic-2' }),
+        );
+        fireEvent.click(screen.getByText('播放降噪後'));
+        await flush();
+        expect(mockPreview.play).toHaveBeenCalledWith(Int16Array.from([0, 1, 0]));
+
+        fireEvent.click(screen.getByText('播放原始'));
+        await flush();
+        expect(mockPreview.play).toHaveBeenLastCalledWith(Int16Array.from([1, 2, 3]));
+    });
+
+    it('reports a capture that fails', async () => {
+        mockPreview.record.mockRejectedValueOnce(new Error('permission denied'));
+        render(<PAudio />);
+
+        fireEvent.click(screen.getByText('錄製試聽'));
+        await flush();
+
+        expect(mockToast.error).toHaveBeenCalledWith('無法錄製試聽片段', 'permission denied');
+        expect(screen.getByText('錄製試聽').closest('button')).not.toBeDisabled();
+    });
+});
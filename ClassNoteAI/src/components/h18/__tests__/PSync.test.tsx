@@ -0,0 +1,137 @@
+/**
+ * PSync tests.
+ *
+ * 「立即同步」queues a SYNC_PUSH to the active server profile. Note
+ * conflicts the backend left for the user (`ask` policy) are listed
+ * here and settled with resolve_note_conflict; the policy itself is
+ * saved per user.
+ */
+
+import { describe, it, expect, vi, beforeEach } from 'vitest';
+import { render, screen, fireEvent, act } from '@testing-library/react';
+import type {
+    NoteConflict,
+    ServerProfileStatus,
+} from '../../../services/offlineQueueService';
+
+const { mockQueue, mockToast } = vi.hoisted(() => ({
+    mockQueue: {
+        getConflictPolicy: vi.fn(async () => 'ask'),
+        setConflictPolicy: vi.fn(async (_policy: string) => undefined),
+        listNoteConflicts: vi.fn(async (): Promise<NoteConflict[]> => []),
+        resolveNoteConflict: vi.fn(async (_id: string, _keep: string) => undefined),
+        onProgress: vi.fn((_listener: unknown) => () => undefined),
+        listProfiles: vi.fn(async (): Promise<ServerProfileStatus[]> => []),
+        saveProfile: vi.fn(async (profile: unknown, _credentials?: string) => profile),
+        syncActiveProfile: vi.fn(async (): Promise<string | null> => 'action-1'),
+    },
+    mockToast: {
+        success: vi.fn(),
+        error: vi.fn(),
+        info: vi.fn(),
+        warning: vi.fn(),
+        show: vi.fn(),
+    },
+}));
+
+vi.mock('../../../services/offlineQueueService', () => ({
+    offlineQueueService: mockQueue,
+}));
+
+vi.mock('../../../services/toastService', () => ({
+    toastService: mockToast,
+}));
+
+import { PSync } from '../ProfilePanes';
+
+const CONFLICT: NoteConflict = {
+    id: 'conflict-1',
+    lecture_id: 'lec-1',
+    side: 'server',
+    title: 'Week 3 notes',
+    content: 'server copy',
+    generated_at: '2026-03-01T09:30:00Z',
+    conflicted_at: '2026-03-01T10:00:00Z',
+    server_url: 'https://sync.example.edu',
+    resolved: false,
+};
+
+async function flush(times = 4) {
+    for (let i = 0; i < times; i++) {
+        // eslint-disable-next-line @typescript-eslint/no-empty-function
+        await act(async () => {});
+    }
+}
+
+beforeEach(() => {
+    vi.clearAllMocks();
+});
+
+const PROFILE: ServerProfileStatus = {
+    id: 'profile-1',
+    name: 'sync.example.edu',
+    server_url: 'https://sync.example.edu',
+    username: 'alice',
+    active: true,
+    has_credentials: true,
+    last_pushed_at: null,
+};
+
+describe('PSync · server', () => {
+    it('queues a push to the active profile', async () => {
+        mockQueue.listProfiles.mockResolvedValue([PROFILE]);
+
+        render(<PSync />);
+        await flush();
+        expect(screen.getByDisplayValue('https://sync.example.edu')).toBeInTheDocument();
+
+        fireEvent.click(screen.getByText('立即同步'));
+        await flush();
+
+        expect(mockQueue.syncActiveProfile).toHaveBeenCalledTimes(1);
+        expect(mockToast.info).toHaveBeenCalledWith('已排入同步', expect.any(String));
+    });
+
+    it('cannot sync before a server is saved', async () => {
+        mockQueue.listProfiles.mockResolvedValue([]);
+
+        render(<PSync />);
+        await flush();
+
+        expect(screen.getByText('立即同步').closest('button')).toBeDisabled();
+    });
+});
+
+describe('PSync · note conflicts', () => {
+    it('says so when nothing waits on a choice', async () => {
+        render(<PSync />);
+        await flush();
+
+        expect(screen.getByText('沒有待處理的衝突。')).toBeInTheDocument();
+    });
+
+    it('keeps the chosen side and reloads the list', async () => {
+        mockQueue.listNoteConflicts.mockResolvedValueOnce([CONFLICT]);
+
+        render(<PSync />);
+        await flush();
+        expect(screen.getByText('Week 3 notes')).toBeInTheDocument();
+
+        fireEvent.click(screen.getByText('用伺服器版本'));
+        await flush();
+
+        expect(mockQueue.resolveNoteConflict).toHaveBeenCalledWith('conflict-1', 'server');
+        expect(mockQueue.listNoteConflicts).toHaveBeenCalledTimes(2);
+        expect(screen.getByText('沒有待處理的衝突。')).toBeInTheDocument();
+    });
+
+    it('saves the policy', async () => {
+        render(<PSync />);
+        await flush();
+
+        fireEvent.click(screen.getByText('本機為準'));
+        await flush();
+
+        expect(mockQueue.setConflictPolicy).toHaveBeenCalledWith('prefer_local');
+    });
+});
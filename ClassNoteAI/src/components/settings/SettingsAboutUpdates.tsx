@@ -13,6 +13,9 @@ import {
   FolderOpen,
   Bug,
   Package,
+  BarChart3,
+  ShieldAlert,
+  Wrench,
 } from "lucide-react";
 import { Card } from "./shared";
 import { setupService } from "../../services/setupService";
@@ -23,13 +26,43 @@ import {
   redactLogContent,
   buildGithubIssueUrl,
 } from "../../services/logDiagnostics";
+import {
+  telemetryService,
+  type TelemetrySettings,
+} from "../../services/telemetryService";
+import {
+  getStartupStatus,
+  repairStartup,
+  exitSafeMode,
+  getLogLevels,
+  setLogLevels,
+  formatModuleLevels,
+  parseModuleLevels,
+  LOG_LEVELS,
+  type LogLevel,
+  type StartupStatus,
+} from "../../services/diagnosticsService";
 import type { ReleaseChannel } from "../../services/updateService";
 import type { Lecture } from "../../types";
+import { errorMessage } from "../../services/appError";
 
 interface Props {
   appVersion: string;
 }
 
+const STARTUP_CHECK_LABELS: Record<string, string> = {
+  database: "資料庫",
+  models: "模型檔案",
+  inference: "推論測試",
+};
+
+const STARTUP_STATUS_LABELS: Record<string, string> = {
+  ok: "正常",
+  warning: "注意",
+  failed: "失敗",
+  skipped: "略過",
+};
+
 export default function SettingsAboutUpdates({ appVersion }: Props) {
   const [isCheckingUpdate, setIsCheckingUpdate] = useState(false);
   const [updateInfo, setUpdateInfo] = useState<{
@@ -42,11 +75,67 @@ export default function SettingsAboutUpdates({ appVersion }: Props) {
   const [channel, setChannel] = useState<ReleaseChannel>("stable");
   const [logPreview, setLogPreview] = useState<string | null>(null);
   const [isExporting, setIsExporting] = useState(false);
+  const [isCheckingHealth, setIsCheckingHealth] = useState(false);
   const [exportIncludeAudio, setExportIncludeAudio] = useState(false);
   const [lectures, setLectures] = useState<Lecture[]>([]);
   const [selectedLectureId, setSelectedLectureId] = useState<string>("");
   const [logHits, setLogHits] = useState<Record<string, number>>({});
   const [hasGithubProvider, setHasGithubProvider] = useState(true);
+  const [telemetry, setTelemetry] = useState<TelemetrySettings | null>(null);
+  const [telemetryEndpoint, setTelemetryEndpoint] = useState("");
+  const [isSavingTelemetry, setIsSavingTelemetry] = useState(false);
+  const [startup, setStartup] = useState<StartupStatus | null>(null);
+  const [repairing, setRepairing] = useState<string | null>(null);
+  const [logLevel, setLogLevel] = useState<LogLevel>("info");
+  const [moduleLevels, setModuleLevels] = useState("");
+  const [isSavingLogLevels, setIsSavingLogLevels] = useState(false);
+
+  useEffect(() => {
+    let cancelled = false;
+    telemetryService
+      .getSettings()
+      .then((current) => {
+        if (cancelled) return;
+        setTelemetry(current);
+        setTelemetryEndpoint(current.endpoint ?? "");
+      })
+      .catch((e) => {
+        console.warn("[SettingsAboutUpdates] Failed to read telemetry settings:", e);
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, []);
+
+  useEffect(() => {
+    let cancelled = false;
+    getLogLevels()
+      .then((levels) => {
+        if (cancelled) return;
+        setLogLevel(levels.default);
+        setModuleLevels(formatModuleLevels(levels.modules));
+      })
+      .catch((e) => {
+        console.warn("[SettingsAboutUpdates] Failed to read log levels:", e);
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, []);
+
+  useEffect(() => {
+    let cancelled = false;
+    getStartupStatus()
+      .then((status) => {
+        if (!cancelled) setStartup(status);
+      })
+      .catch((e) => {
+        console.warn("[SettingsAboutUpdates] Failed to read startup status:", e);
+      });
+    return () => {
+      cancelled = true;
+    };
+  }, []);
 
   // Load the user's current channel selection on mount. Default to
   // stable on any failure — see getReleaseChannel() for the same
@@ -146,7 +235,7 @@ export default function SettingsAboutUpdates({ appVersion }: Props) {
       setChannel(prev);
       toastService.error(
         "無法儲存更新通道設定",
-        e instanceof Error ? e.message : String(e),
+        errorMessage(e),
       );
     }
   };
@@ -198,29 +287,118 @@ export default function SettingsAboutUpdates({ appVersion }: Props) {
 
   const handleCopyLog = async () => {
     try {
-      const raw = await invoke<string>("read_recent_log", { lines: 500 });
+      const raw = await invoke<string>("get_recent_logs", { lines: 500 });
       const { redacted, hits } = redactLogContent(raw);
       setLogPreview(redacted);
       setLogHits(hits);
     } catch (e) {
       toastService.error(
         "無法讀取診斷 log",
-        e instanceof Error ? e.message : String(e),
+        errorMessage(e),
       );
     }
   };
 
+  const handleCopyHealthReport = async () => {
+    setIsCheckingHealth(true);
+    try {
+      const { runHealthCheck, formatHealthReport } = await import(
+        "../../services/diagnosticsService"
+      );
+      const report = await runHealthCheck();
+      await navigator.clipboard.writeText(formatHealthReport(report));
+      toastService.success("系統健康檢查已複製", "可直接貼到 issue 或支援對話");
+    } catch (e) {
+      toastService.error(
+        "無法完成系統健康檢查",
+        errorMessage(e),
+      );
+    } finally {
+      setIsCheckingHealth(false);
+    }
+  };
+
+  const handleSaveTelemetry = async (enabled: boolean) => {
+    setIsSavingTelemetry(true);
+    try {
+      const endpoint = telemetryEndpoint.trim() || null;
+      setTelemetry(await telemetryService.setSettings(enabled, endpoint));
+    } catch (e) {
+      toastService.error(
+        "無法更新使用統計設定",
+        errorMessage(e),
+      );
+    } finally {
+      setIsSavingTelemetry(false);
+    }
+  };
+
+  const handleStartupRepair = async (action: string) => {
+    const ok = await confirmService.ask({
+      title: action === "database" ? "重建資料庫？" : "刪除損壞的模型檔？",
+      message:
+        action === "database"
+          ? "目前的資料庫檔案會改名保留（.broken-…），下次啟動會建立新的空白資料庫。\n\n" +
+            "已同步到雲端的資料可以再同步回來；只存在本機的課堂需要從保留的檔案手動救回。"
+          : "無法讀取的模型檔會被刪除，之後可在設定中重新下載。",
+      confirmLabel: action === "database" ? "重建" : "刪除",
+      variant: action === "database" ? "danger" : "default",
+    });
+    if (!ok) return;
+    setRepairing(action);
+    try {
+      const result = await repairStartup(action);
+      toastService.show({
+        message: "修復完成，請以正常模式重新啟動",
+        detail: result,
+        type: "success",
+        durationMs: 0,
+      });
+    } catch (e) {
+      toastService.error("修復失敗", errorMessage(e));
+    } finally {
+      setRepairing(null);
+    }
+  };
+
+  const handleExitSafeMode = async () => {
+    try {
+      await exitSafeMode();
+    } catch (e) {
+      toastService.error("無法重新啟動", errorMessage(e));
+    }
+  };
+
   const handleOpenLogFolder = async () => {
     try {
       await invoke("open_log_folder");
     } catch (e) {
       toastService.error(
         "無法開啟 log 資料夾",
-        e instanceof Error ? e.message : String(e),
+        errorMessage(e),
       );
     }
   };
 
+  const handleSaveLogLevels = async () => {
+    setIsSavingLogLevels(true);
+    try {
+      const saved = await setLogLevels({
+        default: logLevel,
+        modules: parseModuleLevels(moduleLevels),
+      });
+      setModuleLevels(formatModuleLevels(saved.modules));
+      toastService.success("日誌等級已套用");
+    } catch (e) {
+      toastService.error(
+        "無法設定日誌等級",
+        errorMessage(e),
+      );
+    } finally {
+      setIsSavingLogLevels(false);
+    }
+  };
+
   const handleExportPackage = async () => {
     if (!selectedLectureId) {
       toastService.error("請先選擇要匯出的講座");
@@ -244,7 +422,7 @@ export default function SettingsAboutUpdates({ appVersion }: Props) {
     } catch (e) {
       toastService.error(
         "匯出診斷封包失敗",
-        e instanceof Error ? e.message : String(e),
+        errorMessage(e),
       );
     } finally {
       setIsExporting(false);
@@ -258,7 +436,7 @@ export default function SettingsAboutUpdates({ appVersion }: Props) {
     } catch (e) {
       toastService.error(
         "無法開啟 GitHub issue 頁面",
-        e instanceof Error ? e.message : String(e),
+        errorMessage(e),
       );
     }
   };
@@ -273,7 +451,7 @@ export default function SettingsAboutUpdates({ appVersion }: Props) {
     } catch (e) {
       toastService.error(
         "無法複製診斷 log",
-        e instanceof Error ? e.message : String(e),
+        errorMessage(e),
       );
     }
   };
@@ -309,7 +487,7 @@ export default function SettingsAboutUpdates({ appVersion }: Props) {
       toastService.success('Setup wizard 已重置', '重新載入中…');
       setTimeout(() => window.location.reload(), 600);
     } catch (e) {
-      toastService.error('重置失敗', e instanceof Error ? e.message : String(e));
+      toastService.error('重置失敗', errorMessage(e));
       setIsResettingSetup(false);
     }
   };
@@ -482,6 +660,54 @@ export default function SettingsAboutUpdates({ appVersion }: Props) {
               <FolderOpen className="w-4 h-4" />
               開啟 log 資料夾
             </button>
+            <button
+              onClick={handleCopyHealthReport}
+              disabled={isCheckingHealth}
+              className="w-full sm:col-span-2 flex items-center justify-center gap-2 px-4 py-3 bg-white dark:bg-slate-800 border border-gray-300 dark:border-gray-600 text-gray-700 dark:text-gray-200 hover:bg-gray-50 dark:hover:bg-slate-700 disabled:opacity-50 rounded-lg transition-colors"
+            >
+              <Copy className="w-4 h-4" />
+              {isCheckingHealth ? "檢查中（含模型雜湊，可能需要數十秒）…" : "複製系統健康檢查"}
+            </button>
+          </div>
+
+          <div className="border-t border-gray-200 dark:border-gray-700 pt-4 space-y-3">
+            <div className="space-y-1">
+              <div className="text-sm font-medium text-gray-800 dark:text-gray-100">
+                日誌等級
+              </div>
+              <p className="text-sm text-gray-500 dark:text-gray-400">
+                立即生效，不需重新啟動。可針對單一模組調整，例如 asr=debug。
+              </p>
+            </div>
+            <div className="grid grid-cols-1 gap-2 sm:grid-cols-3">
+              <select
+                value={logLevel}
+                onChange={(e) => setLogLevel(e.target.value as LogLevel)}
+                disabled={isSavingLogLevels}
+                className="w-full rounded-lg border border-gray-300 dark:border-gray-600 bg-white dark:bg-slate-800 px-3 py-2 text-sm text-gray-900 dark:text-gray-100 disabled:opacity-50"
+              >
+                {LOG_LEVELS.map((level) => (
+                  <option key={level} value={level}>
+                    {level}
+                  </option>
+                ))}
+              </select>
+              <input
+                type="text"
+                value={moduleLevels}
+                onChange={(e) => setModuleLevels(e.target.value)}
+                disabled={isSavingLogLevels}
+                placeholder="模組=等級，以逗號分隔"
+                className="w-full sm:col-span-2 rounded-lg border border-gray-300 dark:border-gray-600 bg-white dark:bg-slate-800 px-3 py-2 text-sm font-mono text-gray-900 dark:text-gray-100 disabled:opacity-50"
+              />
+            </div>
+            <button
+              onClick={handleSaveLogLevels}
+              disabled={isSavingLogLevels}
+              className="w-full flex items-center justify-center gap-2 px-4 py-2 bg-white dark:bg-slate-800 border border-gray-300 dark:border-gray-600 text-gray-700 dark:text-gray-200 hover:bg-gray-50 dark:hover:bg-slate-700 disabled:opacity-50 rounded-lg transition-colors"
+            >
+              {isSavingLogLevels ? "套用中…" : "套用日誌等級"}
+            </button>
           </div>
 
           <div className="border-t border-gray-200 dark:border-gray-700 pt-4 space-y-3">
@@ -585,6 +811,116 @@ export default function SettingsAboutUpdates({ appVersion }: Props) {
         </div>
       </Card>
 
+      <Card
+        title="啟動自我檢測"
+        icon={<ShieldAlert className="w-5 h-5 text-amber-500" />}
+      >
+        <div className="space-y-4">
+          {startup?.safe_mode ? (
+            <div className="p-3 rounded-lg bg-amber-50 dark:bg-amber-900/20 border border-amber-200 dark:border-amber-800 text-sm text-amber-800 dark:text-amber-200">
+              目前為安全模式：模型未載入，錄音轉錄與翻譯暫停。
+              <div className="text-xs mt-1">{startup.reasons.join("；")}</div>
+            </div>
+          ) : (
+            <p className="text-sm text-gray-500 dark:text-gray-400">
+              每次啟動會檢查資料庫、模型檔案與一次小型推論；失敗時以安全模式啟動。
+            </p>
+          )}
+          <ul className="space-y-2">
+            {(startup?.checks ?? []).map((check) => (
+              <li
+                key={check.name}
+                className="flex items-center justify-between gap-3 text-sm"
+              >
+                <div className="min-w-0">
+                  <span className="font-medium text-gray-700 dark:text-gray-300">
+                    {STARTUP_CHECK_LABELS[check.name] ?? check.name}
+                  </span>
+                  <span
+                    className={
+                      check.status === "failed"
+                        ? "ml-2 text-red-600 dark:text-red-400"
+                        : check.status === "warning"
+                        ? "ml-2 text-amber-600 dark:text-amber-400"
+                        : "ml-2 text-gray-500 dark:text-gray-400"
+                    }
+                  >
+                    {STARTUP_STATUS_LABELS[check.status] ?? check.status}
+                  </span>
+                  {check.detail && (
+                    <div className="text-xs text-gray-500 dark:text-gray-400 truncate">
+                      {check.detail}
+                    </div>
+                  )}
+                </div>
+                {check.status === "failed" && check.repair && (
+                  <button
+                    onClick={() => handleStartupRepair(check.repair!)}
+                    disabled={repairing !== null}
+                    className="shrink-0 flex items-center gap-1 px-3 py-1.5 bg-white dark:bg-slate-800 border border-gray-300 dark:border-gray-600 text-gray-700 dark:text-gray-200 hover:bg-gray-50 dark:hover:bg-slate-700 disabled:opacity-50 rounded-lg transition-colors"
+                  >
+                    <Wrench className="w-4 h-4" />
+                    {repairing === check.repair ? "修復中..." : "修復"}
+                  </button>
+                )}
+              </li>
+            ))}
+          </ul>
+          {startup?.safe_mode && (
+            <button
+              onClick={handleExitSafeMode}
+              className="w-full flex items-center justify-center gap-2 px-4 py-3 bg-amber-600 hover:bg-amber-700 text-white rounded-lg transition-colors"
+            >
+              <RotateCcw className="w-4 h-4" />
+              以正常模式重新啟動
+            </button>
+          )}
+        </div>
+      </Card>
+
+      <Card
+        title="匿名使用統計"
+        icon={<BarChart3 className="w-5 h-5 text-emerald-500" />}
+      >
+        <div className="space-y-4">
+          <p className="text-sm text-gray-500 dark:text-gray-400">
+            只傳送功能使用次數與當機指紋（雜湊值），不含逐字稿、筆記、檔名或帳號。預設關閉，關閉時會刪除尚未送出的資料。
+          </p>
+          <label className="flex items-center gap-2 text-sm text-gray-700 dark:text-gray-300">
+            <input
+              type="checkbox"
+              checked={telemetry?.enabled ?? false}
+              onChange={(e) => handleSaveTelemetry(e.target.checked)}
+              disabled={telemetry === null || isSavingTelemetry}
+              className="h-4 w-4 rounded border-gray-300 text-violet-600 focus:ring-violet-500"
+            />
+            協助改善 ClassNoteAI：傳送匿名使用統計
+          </label>
+          <div className="flex gap-2">
+            <input
+              type="url"
+              value={telemetryEndpoint}
+              onChange={(e) => setTelemetryEndpoint(e.target.value)}
+              placeholder="https://… （未設定時只在本機排隊，不會上傳）"
+              disabled={!telemetry?.enabled || isSavingTelemetry}
+              className="flex-1 rounded-lg border border-gray-300 dark:border-gray-600 bg-white dark:bg-slate-800 px-3 py-2 text-sm text-gray-900 dark:text-gray-100 disabled:opacity-50"
+            />
+            <button
+              onClick={() => handleSaveTelemetry(true)}
+              disabled={!telemetry?.enabled || isSavingTelemetry}
+              className="px-4 py-2 bg-white dark:bg-slate-800 border border-gray-300 dark:border-gray-600 text-gray-700 dark:text-gray-200 hover:bg-gray-50 dark:hover:bg-slate-700 disabled:opacity-50 rounded-lg transition-colors"
+            >
+              儲存
+            </button>
+          </div>
+          {telemetry?.enabled && (
+            <p className="text-xs text-gray-500 dark:text-gray-400">
+              安裝 ID：{telemetry.install_id ?? "—"} · 待送出批次：{telemetry.queued_batches}
+            </p>
+          )}
+        </div>
+      </Card>
+
       <Card
         title="開發者選項"
         icon={<Cpu className="w-5 h-5 text-orange-500" />}
@@ -1,4 +1,4 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
 import {
   Database,
   Download,
@@ -6,15 +6,66 @@ import {
   AlertCircle,
   Trash2,
   ChevronRight,
+  FolderOpen,
 } from "lucide-react";
 import { storageService } from "../../services/storageService";
+import { backgroundTaskService } from "../../services/backgroundTaskService";
+import { confirmService } from "../../services/confirmService";
+import { toastService } from "../../services/toastService";
 import { Card } from "./shared";
+import { errorMessage } from "../../services/appError";
 
 export default function SettingsDataManagement() {
   const [importStatus, setImportStatus] = useState<{
     success: boolean;
     message: string;
   } | null>(null);
+  const [dataDir, setDataDir] = useState<string | null>(null);
+  const [moveProgress, setMoveProgress] = useState<number | null>(null);
+
+  useEffect(() => {
+    storageService.getAppDataDir().then(setDataDir).catch(() => setDataDir(null));
+  }, []);
+
+  useEffect(() => {
+    const unlisten = backgroundTaskService.onUpdate((task) => {
+      if (task.kind === "relocation" && task.state === "running") {
+        setMoveProgress(task.progress ?? 0);
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const handleMove = async () => {
+    const target = await storageService.pickAppDataFolder();
+    if (!target) return;
+    const ok = await confirmService.ask({
+      title: "搬移資料",
+      message: `將資料庫、錄音、模型與文件複製到「${target}」，驗證完成後應用程式會自動重新啟動並改用新位置，舊資料於重新啟動後刪除。搬移期間請勿關閉應用程式。`,
+      confirmLabel: "開始搬移",
+    });
+    if (!ok) return;
+    setMoveProgress(0);
+    try {
+      // The backend restarts the app right after a successful move.
+      const report = await storageService.moveAppData(target);
+      toastService.show({
+        message: "資料已複製完成，正在重新啟動",
+        detail: `${report.files} 個檔案已搬到 ${report.to}`,
+        type: "success",
+        durationMs: 0,
+      });
+    } catch (error) {
+      toastService.error(
+        "搬移資料失敗",
+        errorMessage(error),
+      );
+    } finally {
+      setMoveProgress(null);
+    }
+  };
 
   const handleExport = async () => {
     try {
@@ -24,7 +75,7 @@ export default function SettingsDataManagement() {
     } catch (error) {
       setImportStatus({
         success: false,
-        message: `導出失敗：${error instanceof Error ? error.message : String(error)}`,
+        message: `導出失敗：${errorMessage(error)}`,
       });
       setTimeout(() => setImportStatus(null), 5000);
     }
@@ -42,7 +93,7 @@ export default function SettingsDataManagement() {
     } catch (error) {
       setImportStatus({
         success: false,
-        message: `導入失敗：${error instanceof Error ? error.message : String(error)}`,
+        message: `導入失敗：${errorMessage(error)}`,
       });
       setTimeout(() => setImportStatus(null), 5000);
     }
@@ -100,6 +151,31 @@ export default function SettingsDataManagement() {
         </div>
       </Card>
 
+      <Card
+        title="資料位置"
+        icon={<FolderOpen className="w-5 h-5 text-blue-500" />}
+      >
+        <div className="flex items-center justify-between gap-4">
+          <div className="min-w-0">
+            <p className="text-sm text-gray-500 dark:text-gray-400">
+              資料庫、錄音、模型與文件目前存放於：
+            </p>
+            <p className="text-xs font-mono text-gray-700 dark:text-gray-300 truncate">
+              {dataDir ?? "—"}
+            </p>
+          </div>
+          <button
+            onClick={handleMove}
+            disabled={moveProgress !== null}
+            className="shrink-0 px-4 py-2 bg-blue-100 text-blue-700 hover:bg-blue-200 rounded-lg text-sm font-medium transition dark:bg-blue-900/40 dark:text-blue-300 dark:hover:bg-blue-900/60 disabled:opacity-50"
+          >
+            {moveProgress !== null
+              ? `搬移中 ${Math.round(moveProgress * 100)}%`
+              : "搬移到其他位置"}
+          </button>
+        </div>
+      </Card>
+
       <Card
         title="回收桶"
         icon={<Trash2 className="w-5 h-5 text-red-500" />}
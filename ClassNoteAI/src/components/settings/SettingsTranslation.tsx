@@ -16,6 +16,7 @@ import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import { AppSettings } from "../../types";
 import { getBuildFeatures, type BuildFeatures } from "../../services/buildFeaturesService";
+import { confirmModelLoad } from "../../services/memoryService";
 import { Card, SegmentedControl } from "./shared";
 
 interface GemmaStatus {
@@ -145,6 +146,7 @@ export default function SettingsTranslation({ settings, setSettings }: Props) {
 
   const handleStartSidecar = useCallback(async () => {
     if (!status?.model_present) return;
+    if (!(await confirmModelLoad("translation:4b"))) return;
     setSidecarAction("starting");
     setSidecarMessage(null);
     try {
@@ -0,0 +1,25 @@
+import { describe, expect, it } from 'vitest';
+import { errorKind, errorMessage, isAppError } from '../appError';
+
+describe('appError', () => {
+  const rejected = {
+    kind: 'model_missing',
+    message: '尚未下載 Parakeet INT8 模型',
+    retryable: false,
+    context: 'INT8',
+  };
+
+  it('reads the message and kind of a command rejection', () => {
+    expect(isAppError(rejected)).toBe(true);
+    expect(errorMessage(rejected)).toBe('尚未下載 Parakeet INT8 模型');
+    expect(errorKind(rejected)).toBe('model_missing');
+  });
+
+  it('falls back for strings and Errors', () => {
+    expect(isAppError('boom')).toBe(false);
+    expect(errorMessage('boom')).toBe('boom');
+    expect(errorMessage(new Error('bang'))).toBe('bang');
+    expect(errorKind(new Error('bang'))).toBe('internal');
+    expect(errorKind(null)).toBe('internal');
+  });
+});
@@ -0,0 +1,74 @@
+/**
+ * autoAlignmentService — stored slide timeline.
+ *
+ * Pins the sample → range collapse, the playback lookup, and that
+ * `getTimeline` only embeds when storage has nothing for the lecture.
+ */
+
+import { describe, it, expect, vi, beforeEach } from 'vitest';
+import { invoke } from '@tauri-apps/api/core';
+
+vi.mock('../embeddingService', () => ({
+    generateLocalEmbedding: vi.fn(async () => [1, 0]),
+}));
+
+import { autoAlignmentService, buildTimeline, pageAt, type SlideAlignment } from '../autoAlignmentService';
+import { generateLocalEmbedding } from '../embeddingService';
+
+describe('buildTimeline', () => {
+    it('merges consecutive samples of a page and ends at the next page', () => {
+        const timeline = buildTimeline(
+            [
+                { timeMs: 20_000, page: 1, confidence: 0.6 },
+                { timeMs: 0, page: 1, confidence: 0.4 },
+                { timeMs: 40_000, page: 3, confidence: 0.9 },
+            ],
+            90_000,
+        );
+        expect(timeline).toEqual([
+            { page_number: 1, start_ms: 0, end_ms: 40_000, confidence: 0.5 },
+            { page_number: 3, start_ms: 40_000, end_ms: 90_000, confidence: 0.9 },
+        ]);
+        expect(buildTimeline([], 1000)).toEqual([]);
+    });
+
+    it('pageAt finds the range covering a position', () => {
+        const timeline: SlideAlignment[] = [
+            { page_number: 1, start_ms: 0, end_ms: 40_000, confidence: null },
+            { page_number: 3, start_ms: 50_000, end_ms: 90_000, confidence: null },
+        ];
+        expect(pageAt(timeline, 39_999)).toBe(1);
+        expect(pageAt(timeline, 45_000)).toBeNull();
+        expect(pageAt(timeline, 50_000)).toBe(3);
+        expect(pageAt(timeline, 90_000)).toBeNull();
+    });
+});
+
+describe('getTimeline', () => {
+    const subtitles = [{ timestamp: 0, text_en: 'sorting' }];
+
+    beforeEach(() => {
+        vi.mocked(invoke).mockReset();
+        vi.mocked(generateLocalEmbedding).mockClear();
+        autoAlignmentService.setPageEmbeddings([{ pageNumber: 2, text: 'sorting', embedding: [1, 0] }]);
+    });
+
+    it('returns stored alignments without embedding', async () => {
+        const stored = [{ page_number: 4, start_ms: 0, end_ms: 1000, confidence: null }];
+        vi.mocked(invoke).mockResolvedValueOnce(stored);
+        await expect(autoAlignmentService.getTimeline('L1', subtitles, 60_000)).resolves.toEqual(stored);
+        expect(generateLocalEmbedding).not.toHaveBeenCalled();
+        expect(invoke).toHaveBeenCalledTimes(1);
+    });
+
+    it('computes and saves when nothing is stored', async () => {
+        vi.mocked(invoke).mockResolvedValueOnce([]).mockResolvedValueOnce(undefined);
+        const timeline = await autoAlignmentService.getTimeline('L1', subtitles, 60_000);
+        expect(timeline).toEqual([{ page_number: 2, start_ms: 0, end_ms: 60_000, confidence: 1 }]);
+        expect(invoke).toHaveBeenLastCalledWith('save_slide_alignments', {
+            lectureId: 'L1',
+            alignments: timeline,
+            userId: 'default_user',
+        });
+    });
+});
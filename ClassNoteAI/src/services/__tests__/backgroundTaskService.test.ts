@@ -0,0 +1,116 @@
+import { describe, expect, it, vi } from 'vitest';
+import { invoke } from '@tauri-apps/api/core';
+import { backgroundTaskService, isFinished, type BackgroundTask } from '../backgroundTaskService';
+
+const task = (state: BackgroundTask['state']): BackgroundTask => ({
+  id: 't1',
+  kind: 'download',
+  label: 'Nemotron INT8',
+  state,
+  progress: 0.5,
+  detail: null,
+  error: null,
+  cancellable: true,
+  pausable: true,
+  started_at: '2026-10-15T09:00:00+08:00',
+  finished_at: null,
+});
+
+describe('backgroundTaskService', () => {
+  it('cancels by task id', async () => {
+    vi.mocked(invoke).mockResolvedValueOnce(true);
+    await expect(backgroundTaskService.cancel('t1')).resolves.toBe(true);
+    expect(invoke).toHaveBeenCalledWith('cancel_task', { id: 't1' });
+  });
+
+  it('pauses, resumes and cancels downloads by id', async () => {
+    const id = 'download:parakeet-nemotron-int8';
+    vi.mocked(invoke).mockResolvedValueOnce(true);
+    await expect(backgroundTaskService.pauseDownload(id)).resolves.toBe(true);
+    expect(invoke).toHaveBeenCalledWith('pause_download', { id });
+
+    vi.mocked(invoke).mockResolvedValueOnce('done');
+    await backgroundTaskService.resumeDownload(id);
+    expect(invoke).toHaveBeenCalledWith('resume_download', { id });
+
+    vi.mocked(invoke).mockResolvedValueOnce(true);
+    await backgroundTaskService.cancelDownload(id);
+    expect(invoke).toHaveBeenCalledWith('cancel_download', { id });
+  });
+
+  it('saves mirror settings', async () => {
+    const settings = { prefer: 'hf_mirror' as const, custom_url: null };
+    vi.mocked(invoke).mockResolvedValueOnce(settings);
+    await expect(backgroundTaskService.setMirrors(settings)).resolves.toEqual(settings);
+    expect(invoke).toHaveBeenCalledWith('set_download_mirrors', { settings });
+  });
+
+  it('sets the download rate limit in bytes per second', async () => {
+    vi.mocked(invoke).mockResolvedValueOnce(2_000_000);
+    await expect(backgroundTaskService.setRateLimit(2_000_000)).resolves.toBe(2_000_000);
+    expect(invoke).toHaveBeenCalledWith('set_download_rate_limit', { bytesPerSec: 2_000_000 });
+  });
+
+  it('saves proxy settings and gets them back redacted', async () => {
+    const settings = {
+      mode: 'manual' as const,
+      url: 'http://proxy.example.edu:3128',
+      username: 'student',
+      password: 'secret',
+    };
+    vi.mocked(invoke).mockResolvedValueOnce({ ...settings, password: '' });
+    await expect(backgroundTaskService.setProxy(settings)).resolves.toEqual({
+      ...settings,
+      password: '',
+    });
+    expect(invoke).toHaveBeenCalledWith('set_download_proxy', { settings });
+  });
+
+  it('lists installed models and deletes one by id', async () => {
+    const model = {
+      id: 'whisper/ggml-base.bin',
+      engine: 'whisper' as const,
+      variant: 'base',
+      path: '/data/models/whisper/ggml-base.bin',
+      size: 147_951_465,
+      last_used: null,
+    };
+    vi.mocked(invoke).mockResolvedValueOnce([model]);
+    await expect(backgroundTaskService.listInstalledModels()).resolves.toEqual([model]);
+    expect(invoke).toHaveBeenCalledWith('list_installed_models');
+
+    vi.mocked(invoke).mockResolvedValueOnce(model.size);
+    await expect(backgroundTaskService.deleteModel(model.id)).resolves.toBe(model.size);
+    expect(invoke).toHaveBeenCalledWith('delete_model', { id: model.id });
+  });
+
+  it('checks for model updates and applies one by installed id', async () => {
+    const update = {
+      installed_id: 'whisper/ggml-medium.bin',
+      id: 'whisper/ggml-medium-q5.bin',
+      kind: 'upgrade' as const,
+      label: 'Whisper Medium (q5)',
+      version: '2026.10',
+      size: 530_000_000,
+      changelog: '更小的量化版本，準確度幾乎相同。',
+    };
+    vi.mocked(invoke).mockResolvedValueOnce([update]);
+    await expect(backgroundTaskService.checkModelUpdates()).resolves.toEqual([update]);
+    expect(invoke).toHaveBeenCalledWith('check_model_updates');
+
+    vi.mocked(invoke).mockResolvedValueOnce(update.id);
+    await expect(backgroundTaskService.applyModelUpdate(update.installed_id)).resolves.toBe(
+      update.id,
+    );
+    expect(invoke).toHaveBeenCalledWith('apply_model_update', { id: update.installed_id });
+  });
+
+  it('treats completed, failed, cancelled and paused as finished', () => {
+    expect(isFinished(task('queued'))).toBe(false);
+    expect(isFinished(task('running'))).toBe(false);
+    expect(isFinished(task('completed'))).toBe(true);
+    expect(isFinished(task('failed'))).toBe(true);
+    expect(isFinished(task('cancelled'))).toBe(true);
+    expect(isFinished(task('paused'))).toBe(true);
+  });
+});
@@ -0,0 +1,29 @@
+import { describe, expect, it, vi } from 'vitest';
+import { invoke } from '@tauri-apps/api/core';
+import {
+  formatModuleLevels,
+  parseModuleLevels,
+  setLogLevels,
+} from '../diagnosticsService';
+
+describe('log level settings', () => {
+  it('round-trips module overrides through the text form', () => {
+    const modules = parseModuleLevels(' asr=DEBUG,\nreqwest = warn ,');
+    expect(modules).toEqual({ asr: 'debug', reqwest: 'warn' });
+    expect(formatModuleLevels(modules)).toBe('asr=debug, reqwest=warn');
+    expect(parseModuleLevels('')).toEqual({});
+  });
+
+  it('rejects malformed entries', () => {
+    expect(() => parseModuleLevels('asr')).toThrow('asr');
+    expect(() => parseModuleLevels('asr=loud')).toThrow('asr=loud');
+    expect(() => parseModuleLevels('=debug')).toThrow();
+  });
+
+  it('sends the levels to set_log_levels', async () => {
+    const levels = { default: 'info' as const, modules: { asr: 'debug' as const } };
+    vi.mocked(invoke).mockResolvedValueOnce(levels);
+    await expect(setLogLevels(levels)).resolves.toEqual(levels);
+    expect(invoke).toHaveBeenCalledWith('set_log_levels', { levels });
+  });
+});
@@ -7,6 +7,8 @@
  *   - exportLectureMarkdown: full sections / missing summary / missing sections /
  *     missing transcript / keywords / duration formatting
  *   - exportLecture: integration with mocked storageService + dialog/fs
+ *   - exportAnkiDeck: save dialog + `export_anki` invoke
+ *   - exportMarkdownBundle: folder picker + `export_markdown_bundle` invoke
  */
 
 import { describe, it, expect, vi, beforeEach } from 'vitest';
@@ -15,6 +17,8 @@ import {
     exportLectureSRT,
     exportLectureMarkdown,
     exportLecture,
+    exportAnkiDeck,
+    exportMarkdownBundle,
     type ExportSubtitle,
     type ExportLectureMeta,
     type ExportNote,
@@ -26,12 +30,14 @@ vi.mock('../storageService', () => ({
         getLecture: vi.fn(),
         getSubtitles: vi.fn(),
         getNote: vi.fn(),
+        getCourse: vi.fn(),
     },
 }));
 
 import { storageService } from '../storageService';
-import { save } from '@tauri-apps/plugin-dialog';
+import { open, save } from '@tauri-apps/plugin-dialog';
 import { writeTextFile } from '@tauri-apps/plugin-fs';
+import { invoke } from '@tauri-apps/api/core';
 
 describe('exportService', () => {
     beforeEach(() => {
@@ -338,4 +344,66 @@ describe('exportService', () => {
             expect(storageService.getNote).not.toHaveBeenCalled();
         });
     });
+
+    describe('exportAnkiDeck', () => {
+        it('asks for an .apkg path and invokes export_anki', async () => {
+            (storageService.getCourse as ReturnType<typeof vi.fn>).mockResolvedValueOnce({
+                id: 'c1',
+                title: '演算法',
+            });
+            (save as ReturnType<typeof vi.fn>).mockResolvedValueOnce('/tmp/演算法.apkg');
+            (invoke as ReturnType<typeof vi.fn>).mockResolvedValueOnce({
+                path: '/tmp/演算法.apkg',
+                cards: 3,
+                clips: 2,
+            });
+
+            const result = await exportAnkiDeck('c1');
+
+            expect(save).toHaveBeenCalledWith(
+                expect.objectContaining({ defaultPath: '演算法.apkg' }),
+            );
+            expect(invoke).toHaveBeenCalledWith(
+                'export_anki',
+                expect.objectContaining({
+                    courseId: 'c1',
+                    destPath: '/tmp/演算法.apkg',
+                    format: 'apkg',
+                }),
+            );
+            expect(result).toEqual({ path: '/tmp/演算法.apkg', cards: 3, clips: 2 });
+        });
+
+        it('returns null without invoking when the dialog is cancelled', async () => {
+            (storageService.getCourse as ReturnType<typeof vi.fn>).mockResolvedValueOnce({
+                id: 'c1',
+                title: '演算法',
+            });
+            (save as ReturnType<typeof vi.fn>).mockResolvedValueOnce(null);
+
+            expect(await exportAnkiDeck('c1', 'csv')).toBeNull();
+            expect(invoke).not.toHaveBeenCalledWith('export_anki', expect.anything());
+        });
+    });
+
+    describe('exportMarkdownBundle', () => {
+        it('passes the picked folder to export_markdown_bundle', async () => {
+            (open as ReturnType<typeof vi.fn>).mockResolvedValueOnce('/vault');
+
+            await exportMarkdownBundle('c1');
+
+            expect(open).toHaveBeenCalledWith(expect.objectContaining({ directory: true }));
+            expect(invoke).toHaveBeenCalledWith(
+                'export_markdown_bundle',
+                expect.objectContaining({ courseId: 'c1', destDir: '/vault' }),
+            );
+        });
+
+        it('returns null when no folder is picked', async () => {
+            (open as ReturnType<typeof vi.fn>).mockResolvedValueOnce(null);
+
+            expect(await exportMarkdownBundle('c1')).toBeNull();
+            expect(invoke).not.toHaveBeenCalledWith('export_markdown_bundle', expect.anything());
+        });
+    });
 });
@@ -0,0 +1,36 @@
+import { describe, expect, it, vi } from 'vitest';
+import { invoke } from '@tauri-apps/api/core';
+import { confirmModelLoad, memoryWarningFor, type MemoryUsage } from '../memoryService';
+
+const GB = 1024 ** 3;
+
+function usage(available: number | null): MemoryUsage {
+  return {
+    models: [
+      { id: 'asr:int8', kind: 'asr', loaded: true, approx_bytes: 0.9 * GB, measured: false },
+      { id: 'translation:4b', kind: 'translation', loaded: false, approx_bytes: 2.5 * GB, measured: false },
+    ],
+    loaded_bytes: 0.9 * GB,
+    process_rss_bytes: 1.5 * GB,
+    system_total_bytes: 8 * GB,
+    system_available_bytes: available,
+  };
+}
+
+describe('memoryService', () => {
+  it('warns only when the model would eat into the headroom', () => {
+    expect(memoryWarningFor(usage(6 * GB), 'translation:4b')).toBeNull();
+    expect(memoryWarningFor(usage(3 * GB), 'translation:4b')).toContain('2.5 GB');
+  });
+
+  it('never warns for loaded, unknown, or unmeasurable cases', () => {
+    expect(memoryWarningFor(usage(0.1 * GB), 'asr:int8')).toBeNull();
+    expect(memoryWarningFor(usage(0.1 * GB), 'translation:27b')).toBeNull();
+    expect(memoryWarningFor(usage(null), 'translation:4b')).toBeNull();
+  });
+
+  it('lets the load through when the usage query fails', async () => {
+    vi.mocked(invoke).mockRejectedValueOnce(new Error('no command'));
+    await expect(confirmModelLoad('translation:4b')).resolves.toBe(true);
+  });
+});
@@ -2,7 +2,7 @@
  * OfflineQueueService Unit Tests - Enhanced
  * 
  * Comprehensive tests covering:
- * - Processor registration and retrieval
+ * - Completion handlers for actions the backend sent
  * - Subscription lifecycle
  * - Queue operations (enqueue, list, process)
  * - Online status detection
@@ -51,50 +51,47 @@ describe('OfflineQueueService - Enhanced', () => {
     describe('Action Types', () => {
         const validTypes: ActionType[] = [
             'AUTH_REGISTER',
+            'AUDIO_UPLOAD',
+            'SYNC_PUSH',
+            'NOTE_KEEP_LOCAL',
             'PURGE_ITEM',
             'TASK_CREATE',
         ];
 
         it('should support all defined action types', async () => {
             for (const type of validTypes) {
-                // Register a processor for each type
+                // Register a completion handler for each type
                 expect(() =>
-                    offlineQueueService.registerProcessor(type, async () => { })
+                    offlineQueueService.onCompleted(type, () => { })
                 ).not.toThrow();
             }
         });
     });
 
-    // ===== Processor Registration Tests =====
-    describe('Processor Registration', () => {
-        it('should register processor without throwing', () => {
-            const processor = vi.fn(() => Promise.resolve());
-
-            expect(() =>
-                offlineQueueService.registerProcessor('AUTH_REGISTER', processor)
-            ).not.toThrow();
-        });
-
-        it('should accept async processors', () => {
-            const asyncProcessor = async () => {
-                await new Promise(resolve => setTimeout(resolve, 10));
-            };
-
-            expect(() =>
-                offlineQueueService.registerProcessor('PURGE_ITEM', asyncProcessor)
-            ).not.toThrow();
-        });
+    // ===== Completion Handler Tests =====
+    describe('Completion Handlers', () => {
+        it('should pass the parsed payload of completed actions to their handlers', async () => {
+            vi.resetModules();
+            const { listen } = await import('@tauri-apps/api/event');
+            const handlers = new Map<string, (event: any) => void>();
+            vi.mocked(listen).mockImplementation((event, handler) => {
+                handlers.set(event, handler as (event: any) => void);
+                return Promise.resolve(() => { });
+            });
+            const { offlineQueueService: fresh } = await import('../offlineQueueService');
 
-        it('should allow overwriting existing processor', () => {
-            const processor1 = vi.fn(() => Promise.resolve());
-            const processor2 = vi.fn(() => Promise.resolve());
+            const onRegister = vi.fn();
+            const onPurge = vi.fn();
+            fresh.onCompleted('AUTH_REGISTER', onRegister);
+            fresh.onCompleted('PURGE_ITEM', onPurge);
+            await new Promise(resolve => setTimeout(resolve, 0));
 
-            offlineQueueService.registerProcessor('TASK_CREATE', processor1);
+            handlers.get('sync-action-completed')!({
+                payload: { id: 'a1', action_type: 'AUTH_REGISTER', payload: '{"username":"amy"}' },
+            });
 
-            // Should not throw when registering again
-            expect(() =>
-                offlineQueueService.registerProcessor('TASK_CREATE', processor2)
-            ).not.toThrow();
+            expect(onRegister).toHaveBeenCalledWith({ username: 'amy' });
+            expect(onPurge).not.toHaveBeenCalled();
         });
     });
 
@@ -161,6 +158,23 @@ describe('OfflineQueueService - Enhanced', () => {
             expect(typeof offlineQueueService.processQueue).toBe('function');
         });
 
+        it('should store enqueued actions for the backend to send', async () => {
+            const id = await offlineQueueService.enqueue('AUTH_REGISTER', {
+                serverUrl: 'https://notes.example.edu',
+                username: 'amy',
+            });
+
+            expect(invokeCallHistory).toContainEqual({
+                cmd: 'add_pending_action',
+                args: {
+                    id,
+                    actionType: 'AUTH_REGISTER',
+                    payload: '{"serverUrl":"https://notes.example.edu","username":"amy"}',
+                    userId: 'default_user',
+                },
+            });
+        });
+
         it('should list empty actions when queue is empty', async () => {
             mockInvokeResults.set('list_pending_actions', []);
 
@@ -250,6 +264,72 @@ describe('OfflineQueueService - Enhanced', () => {
 
             await expect(offlineQueueService.processQueue()).resolves.not.toThrow();
         });
+
+        it('should queue a lecture audio upload to the active profile', async () => {
+            mockInvokeResults.set('list_server_profiles', [
+                { id: 'p-1', name: 'Class', server_url: 'https://notes.example.edu', username: 'alice', active: true, has_credentials: true, last_pushed_at: null },
+            ]);
+
+            await offlineQueueService.uploadLectureAudio('lec-1');
+
+            const call = invokeCallHistory.find(c => c.cmd === 'add_pending_action');
+            expect(call?.args.actionType).toBe('AUDIO_UPLOAD');
+            expect(JSON.parse(call?.args.payload)).toEqual({
+                serverUrl: 'https://notes.example.edu',
+                username: 'alice',
+                lectureId: 'lec-1',
+            });
+        });
+
+        it('should not queue an upload without a profile', async () => {
+            mockInvokeResults.set('list_server_profiles', []);
+
+            await expect(offlineQueueService.uploadLectureAudio('lec-1')).resolves.toBeNull();
+            expect(invokeCallHistory.some(c => c.cmd === 'add_pending_action')).toBe(false);
+        });
+
+        it('should push to the active profile with its own account', async () => {
+            mockInvokeResults.set('list_server_profiles', [
+                { id: 'p-1', name: 'Class', server_url: 'https://notes.example.edu', username: 'alice', active: false, has_credentials: false, last_pushed_at: null },
+                { id: 'p-2', name: 'Home', server_url: 'http://192.168.1.20:8080', username: 'alice-home', active: true, has_credentials: true, last_pushed_at: null },
+            ]);
+
+            await offlineQueueService.syncActiveProfile();
+
+            const call = invokeCallHistory.find(c => c.cmd === 'add_pending_action');
+            expect(call?.args.actionType).toBe('SYNC_PUSH');
+            expect(JSON.parse(call?.args.payload)).toEqual({
+                serverUrl: 'http://192.168.1.20:8080',
+                username: 'alice-home',
+            });
+        });
+
+        it('should pass the conflict policy and resolution for the current user', async () => {
+            await offlineQueueService.setConflictPolicy('ask');
+            await offlineQueueService.resolveNoteConflict('c-1', 'server');
+
+            expect(invokeCallHistory).toEqual([
+                { cmd: 'set_sync_conflict_policy', args: { policy: 'ask', userId: 'default_user' } },
+                {
+                    cmd: 'resolve_note_conflict',
+                    args: { conflictId: 'c-1', keep: 'server', userId: 'default_user' },
+                },
+            ]);
+        });
+
+        it('should ask the backend to flush, optionally retrying failed actions', async () => {
+            await offlineQueueService.processQueue();
+            await offlineQueueService.processQueue(true);
+
+            expect(invokeCallHistory).toContainEqual({
+                cmd: 'flush_sync_queue',
+                args: { retryFailed: false },
+            });
+            expect(invokeCallHistory).toContainEqual({
+                cmd: 'flush_sync_queue',
+                args: { retryFailed: true },
+            });
+        });
     });
 
     // ===== Edge Cases =====
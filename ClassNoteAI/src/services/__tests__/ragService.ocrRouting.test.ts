@@ -17,6 +17,7 @@ const {
     generateLocalEmbeddingMock,
     generateLocalEmbeddingsBatchMock,
     invalidateMock,
+    localRecognizePagesMock,
 } = vi.hoisted(() => ({
     getAppSettingsMock: vi.fn(),
     getSettingMock: vi.fn(),
@@ -34,6 +35,7 @@ const {
     generateLocalEmbeddingMock: vi.fn(),
     generateLocalEmbeddingsBatchMock: vi.fn(),
     invalidateMock: vi.fn(),
+    localRecognizePagesMock: vi.fn(),
 }));
 
 vi.mock('../llm', () => ({
@@ -49,6 +51,12 @@ vi.mock('../remoteOcrService', () => ({
     },
 }));
 
+vi.mock('../localOcrService', () => ({
+    localOcrService: {
+        recognizePages: localRecognizePagesMock,
+    },
+}));
+
 vi.mock('../pdfToImageService', () => ({
     pdfToImageService: {
         renderPages: renderPagesMock,
@@ -158,6 +166,7 @@ describe('ragService.indexLectureWithOCR routing', () => {
         generateLocalEmbeddingMock.mockResolvedValue([0.1, 0.2, 0.3]);
         generateLocalEmbeddingsBatchMock.mockResolvedValue([[0.1, 0.2, 0.3]]);
         invalidateMock.mockReturnValue(undefined);
+        localRecognizePagesMock.mockResolvedValue([]);
     });
 
     it('uses remote OCR when a cloud vision provider is available', async () => {
@@ -219,6 +228,44 @@ describe('ragService.indexLectureWithOCR routing', () => {
         fallbackSpy.mockRestore();
     });
 
+    it('fills image-only pages with local OCR on the PDF.js path', async () => {
+        isAvailableMock.mockResolvedValueOnce(false);
+        extractAllPagesTextMock.mockResolvedValueOnce([
+            { page: 1, text: 'PDF.js page text' },
+            { page: 2, text: '  ' },
+        ]);
+        localRecognizePagesMock.mockResolvedValueOnce([{ page: 2, text: 'Scanned page text' }]);
+        const fallbackSpy = vi
+            .spyOn(ragService, 'indexLectureFromPages')
+            .mockResolvedValueOnce({ chunksCount: 2, success: true });
+
+        await ragService.indexLectureWithOCR(
+            'lecture-scan',
+            new Uint8Array([1, 1, 2]).buffer,
+            null,
+            undefined,
+            true,
+        );
+
+        expect(localRecognizePagesMock).toHaveBeenCalledWith(
+            expect.any(ArrayBuffer),
+            expect.any(String),
+            [2],
+            expect.any(Array),
+        );
+        expect(fallbackSpy).toHaveBeenCalledWith(
+            'lecture-scan',
+            [
+                { pageNumber: 1, text: 'PDF.js page text' },
+                { pageNumber: 2, text: 'Scanned page text' },
+            ],
+            null,
+            undefined,
+        );
+
+        fallbackSpy.mockRestore();
+    });
+
     it('treats historical local OCR mode as off and never probes remote OCR', async () => {
         getAppSettingsMock.mockResolvedValueOnce({ ocr: { mode: 'local' } });
         const fallbackSpy = vi
@@ -238,6 +285,7 @@ describe('ragService.indexLectureWithOCR routing', () => {
         expect(recognizePagesMock).not.toHaveBeenCalled();
         expect(renderPagesMock).not.toHaveBeenCalled();
         expect(extractAllPagesTextMock).toHaveBeenCalledTimes(1);
+        expect(localRecognizePagesMock).not.toHaveBeenCalled();
         expect(fallbackSpy).toHaveBeenCalledWith(
             'lecture-legacy',
             [{ pageNumber: 1, text: 'PDF.js page text' }],
@@ -0,0 +1,45 @@
+import { describe, expect, it, vi } from 'vitest';
+import { invoke } from '@tauri-apps/api/core';
+import { setupService } from '../setupService';
+
+describe('setupService', () => {
+  it('lists the setup downloads still queued', async () => {
+    const pending = [
+      {
+        dest: '/data/models/whisper/ggml-base.bin',
+        requirement_id: 'whisper_model',
+        url: 'https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin',
+        bytes_done: 52_428_800,
+        total_bytes: 147_951_465,
+      },
+    ];
+    vi.mocked(invoke).mockResolvedValueOnce(pending);
+    await expect(setupService.pendingDownloads()).resolves.toEqual(pending);
+    expect(invoke).toHaveBeenCalledWith('list_setup_downloads');
+  });
+
+  it('counts a corrupted model as missing so installing repairs it', () => {
+    const requirement = {
+      name: 'BGE-small-en Embedding 模型',
+      description: '',
+      category: 'Model' as const,
+      is_optional: true,
+      install_size_mb: 130,
+      install_source: null,
+    };
+    const status = {
+      is_complete: true,
+      requirements: [
+        {
+          ...requirement,
+          id: 'embedding_model',
+          status: { Corrupted: { files: ['model.safetensors'] } },
+        },
+        { ...requirement, id: 'whisper_model', status: 'Installed' as const },
+      ],
+      total_download_size_mb: 130,
+      estimated_time_minutes: 15,
+    };
+    expect(setupService.getAllMissingIds(status, true)).toEqual(['embedding_model']);
+  });
+});
@@ -361,6 +361,19 @@ describe('StorageService', () => {
 
             expect(invoke).toHaveBeenCalledWith('delete_subtitle', {
                 id: 'sub-1',
+                lectureId: null,
+                userId: 'test_user',
+            });
+        });
+
+        it('deleteSubtitle passes the lecture for externalized transcripts', async () => {
+            setMockInvokeResult('delete_subtitle', undefined);
+
+            await storageService.deleteSubtitle('sub-1', 'lec-1');
+
+            expect(invoke).toHaveBeenCalledWith('delete_subtitle', {
+                id: 'sub-1',
+                lectureId: 'lec-1',
                 userId: 'test_user',
             });
         });
@@ -557,4 +570,14 @@ describe('StorageService', () => {
             });
         });
     });
+
+    describe('App Data Location', () => {
+        it('should move app data by calling move_app_data with the new dir', async () => {
+            const report = { from: '/a', to: '/Volumes/SSD/com.classnoteai', files: 3, bytes: 42, restart_required: true };
+            setMockInvokeResult('move_app_data', report);
+
+            await expect(storageService.moveAppData('/Volumes/SSD')).resolves.toEqual(report);
+            expect(invoke).toHaveBeenCalledWith('move_app_data', { newDir: '/Volumes/SSD' });
+        });
+    });
 });
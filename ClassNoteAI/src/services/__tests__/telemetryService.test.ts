@@ -0,0 +1,35 @@
+import { describe, expect, it, vi } from 'vitest';
+import { invoke } from '@tauri-apps/api/core';
+import { telemetryService } from '../telemetryService';
+
+describe('telemetryService', () => {
+  it('sends only the event name', async () => {
+    vi.mocked(invoke).mockResolvedValueOnce(undefined);
+    telemetryService.track('recording.stop.saved');
+    await vi.waitFor(() =>
+      expect(invoke).toHaveBeenCalledWith('record_telemetry_event', {
+        name: 'recording.stop.saved',
+      }),
+    );
+  });
+
+  it('never surfaces a failed track call', async () => {
+    vi.mocked(invoke).mockRejectedValueOnce(new Error('boom'));
+    expect(() => telemetryService.track('x')).not.toThrow();
+    await new Promise((r) => setTimeout(r, 0));
+  });
+
+  it('passes opt-in and endpoint to the backend', async () => {
+    vi.mocked(invoke).mockResolvedValueOnce({
+      enabled: true,
+      endpoint: null,
+      install_id: 'id',
+      queued_batches: 0,
+    });
+    await telemetryService.setSettings(true, null);
+    expect(invoke).toHaveBeenCalledWith('set_telemetry_settings', {
+      enabled: true,
+      endpoint: null,
+    });
+  });
+});
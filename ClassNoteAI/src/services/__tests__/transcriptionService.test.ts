@@ -1,23 +1,23 @@
-// transcriptionService is a thin shim over asrPipeline + the
+// transcriptionService is a thin shim over livePipeline + the
 // subtitleStream → subtitleService bridge. These tests cover the
 // behavioural contract that LectureView / NotesView depend on:
 //
 //   * `pause()` followed by `addAudioChunk()` must NOT silently kick
-//     off a fresh `asrPipeline.start()` — the pre-fix code did exactly
+//     off a fresh `livePipeline.start()` — the pre-fix code did exactly
 //     that, killing the in-flight session and dropping the chunk that
 //     resumed playback.
 //   * `clear()` resets all state cleanly even mid-session.
 //
-// We mock asrPipeline so we can assert on its method calls without
-// spinning up a real Parakeet session.
+// We mock livePipeline so we can assert on its method calls without
+// spinning up the Rust pipeline.
 
 import { afterEach, beforeEach, describe, expect, it, vi } from 'vitest';
 
 const startMock = vi.fn().mockResolvedValue(undefined);
 const stopMock = vi.fn().mockResolvedValue(undefined);
 const pushAudioMock = vi.fn().mockResolvedValue(undefined);
-vi.mock('../streaming/asrPipeline', () => ({
-  asrPipeline: {
+vi.mock('../streaming/livePipeline', () => ({
+  livePipeline: {
     start: (...args: unknown[]) => startMock(...args),
     stop: () => stopMock(),
     pushAudio: (chunk: Int16Array) => pushAudioMock(chunk),
@@ -47,13 +47,20 @@ afterEach(async () => {
 });
 
 describe('transcriptionService.start / stop', () => {
-  it('start() calls asrPipeline.start once; idempotent on repeat', async () => {
+  it('start() calls livePipeline.start once; idempotent on repeat', async () => {
     await transcriptionService.start();
     await transcriptionService.start();
     expect(startMock).toHaveBeenCalledTimes(1);
   });
 
-  it('stop() calls asrPipeline.stop and is safe to repeat', async () => {
+  it('opens the pipeline for the current lecture', async () => {
+    transcriptionService.setLectureId('lec-1');
+    transcriptionService.setLanguages('en', 'zh');
+    await transcriptionService.start();
+    expect(startMock).toHaveBeenCalledWith('lec-1', 'en');
+  });
+
+  it('stop() calls livePipeline.stop and is safe to repeat', async () => {
     await transcriptionService.start();
     await transcriptionService.stop();
     await transcriptionService.stop();
@@ -62,7 +69,7 @@ describe('transcriptionService.start / stop', () => {
 });
 
 describe('transcriptionService.addAudioChunk — implicit start', () => {
-  it('fires asrPipeline.start when no session is active yet', async () => {
+  it('fires livePipeline.start when no session is active yet', async () => {
     transcriptionService.addAudioChunk(fakeChunk());
     // start() is called fire-and-forget; await one microtask so the
     // mock registers the invocation.
@@ -70,7 +77,7 @@ describe('transcriptionService.addAudioChunk — implicit start', () => {
     expect(startMock).toHaveBeenCalledTimes(1);
   });
 
-  it('forwards the chunk to asrPipeline.pushAudio', async () => {
+  it('forwards the chunk to livePipeline.pushAudio', async () => {
     await transcriptionService.start();
     const chunk = fakeChunk([42, 43, 44]);
     transcriptionService.addAudioChunk(chunk);
@@ -0,0 +1,39 @@
+import { describe, expect, it, vi } from 'vitest';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
+import { downloadTranslationModel } from '../translationModelService';
+import type { Progress } from '../../types/setup';
+
+const progress = (task_id: string, current: number): Progress => ({
+  task_id,
+  task_name: 'M2M100 (多語言翻譯, int8)',
+  status: 'InProgress',
+  current,
+  total: 400,
+  speed_bps: 5_000_000,
+  eta_seconds: null,
+  message: null,
+});
+
+describe('downloadTranslationModel', () => {
+  it('reports the setup-progress events of its own model', async () => {
+    const unlisten = vi.fn();
+    let emit: (p: Progress) => void = () => {};
+    vi.mocked(listen).mockImplementationOnce((event, handler) => {
+      expect(event).toBe('setup-progress');
+      emit = (payload) => handler({ event, id: 0, payload } as never);
+      return Promise.resolve(unlisten);
+    });
+    vi.mocked(invoke).mockImplementationOnce(async () => {
+      emit(progress('m2m100-418M-ct2-int8', 100));
+      emit(progress('bge-small-en-v1.5', 300));
+      return '翻譯模型下載成功';
+    });
+
+    const onProgress = vi.fn();
+    await downloadTranslationModel('m2m100-418M-ct2-int8', '', onProgress);
+
+    expect(onProgress.mock.calls).toEqual([[25], [100]]);
+    expect(unlisten).toHaveBeenCalled();
+  });
+});
@@ -1,6 +1,7 @@
 import { invoke } from '@tauri-apps/api/core';
 import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import { useEffect } from 'react';
+import { errorMessage } from './appError';
 
 const UI_ACTION_EVENT = 'agent-bridge-ui-action';
 const UPDATE_COMMAND = 'agent_bridge_update_ui_state';
@@ -173,7 +174,7 @@ export async function performAgentUiAction(action: AgentUiAction, doc: Document
 
     return failed(`unsupported action: ${action.kind}`);
   } catch (error) {
-    return failed(error instanceof Error ? error.message : String(error));
+    return failed(errorMessage(error));
   }
 }
 
@@ -7,6 +7,7 @@ import { ragService } from './ragService';
 import { storageService } from './storageService';
 import { videoImportService, type ImportProgress } from './videoImportService';
 import type { Note, Subtitle } from '../types';
+import { errorMessage } from './appError';
 
 const WORKFLOW_EVENT = 'agent-bridge-workflow';
 const COMPLETE_COMMAND = 'agent_bridge_complete_workflow';
@@ -110,7 +111,7 @@ export async function runAgentWorkflow(
     return {
       ...base,
       status: 'failed',
-      message: error instanceof Error ? error.message : String(error),
+      message: errorMessage(error),
     };
   }
 }
@@ -0,0 +1,50 @@
+/**
+ * The shape lib.rs commands reject with (`src-tauri/src/error.rs`).
+ *
+ * Branch on `kind` instead of matching the (mostly Chinese) message
+ * text; `message` is what to show the user. Commands outside lib.rs
+ * and plain JS failures still reject with a string or an `Error`, so
+ * use `errorMessage` / `errorKind` rather than reading fields directly.
+ */
+export type AppErrorKind =
+  | 'model_missing'
+  | 'disk_full'
+  | 'network'
+  | 'not_found'
+  | 'invalid_input'
+  | 'busy'
+  | 'unsupported'
+  | 'cancelled'
+  | 'paused'
+  | 'permission_denied'
+  | 'database'
+  | 'io'
+  | 'internal';
+
+export interface AppError {
+  kind: AppErrorKind;
+  message: string;
+  retryable: boolean;
+  /** What was being worked on (a model variant, a lecture id, a path). */
+  context?: string;
+}
+
+export function isAppError(error: unknown): error is AppError {
+  return (
+    typeof error === 'object' &&
+    error !== null &&
+    typeof (error as AppError).kind === 'string' &&
+    typeof (error as AppError).message === 'string'
+  );
+}
+
+/** User-facing text for anything a command or promise rejected with. */
+export function errorMessage(error: unknown): string {
+  if (error instanceof Error || isAppError(error)) return error.message;
+  return String(error);
+}
+
+/** `internal` for anything that isn't an `AppError`. */
+export function errorKind(error: unknown): AppErrorKind {
+  return isAppError(error) ? error.kind : 'internal';
+}
@@ -102,6 +102,24 @@ export async function resolveOrRecoverAudioPath(
   };
 }
 
+export interface AudioProbe {
+  path: string;
+  size_bytes: number;
+  duration_sec: number | null;
+  sample_rate: number | null;
+  channels: number | null;
+  codec: string | null;
+  container: string | null;
+  playable: boolean;
+  /** Reason when not playable; a damage note when it still plays. */
+  error: string | null;
+}
+
+/** Format + damage check before handing a file to the player. */
+export async function probeAudio(path: string): Promise<AudioProbe> {
+  return await invoke<AudioProbe>('probe_audio', { path });
+}
+
 export interface AudioLinkAuditResult {
   recoveredLectureIds: string[];
   unresolvedLectureIds: string[];
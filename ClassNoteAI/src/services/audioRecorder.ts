@@ -12,6 +12,22 @@ export interface AudioRecorderConfig {
   sampleRate?: number; // 採樣率，默認 48000（後續會轉換為 16kHz）
   channelCount?: number; // 聲道數，默認 1（Mono）
   deviceId?: string; // 設備 ID，可選
+  source?: AudioCaptureSource; // 錄音來源，默認 'microphone'
+  micGain?: number; // 'both' 模式下麥克風增益（線性），默認 1
+  systemGain?: number; // 'both' 模式下系統音訊增益（線性），默認 1
+}
+
+/** 'system' = loopback of what the computer is playing (Zoom / Teams).
+ *  'both' = mic and system audio mixed into one track (hybrid classes:
+ *  the professor's voice plus the videos they share). */
+export type AudioCaptureSource = 'microphone' | 'system' | 'both';
+
+/** Mirrors `recording::loopback::LoopbackSupport` on the Rust side. */
+export interface LoopbackSupport {
+  platform: string;
+  method: 'display-media' | 'virtual-device' | 'unsupported';
+  device_label_hints: string[];
+  guidance: string | null;
 }
 
 export interface AudioChunk {
@@ -32,10 +48,18 @@ export class AudioRecorder {
   private audioContext: AudioContext | null = null;
   private mediaStream: MediaStream | null = null;
   private sourceNode: MediaStreamAudioSourceNode | null = null;
+  // 'both' mode only: second stream + per-source gain stages feeding
+  // the processor. Multiple connections into one AudioNode input are
+  // summed by Web Audio, so the processor sees the mix.
+  private systemStream: MediaStream | null = null;
+  private systemSourceNode: MediaStreamAudioSourceNode | null = null;
+  private micGainNode: GainNode | null = null;
+  private systemGainNode: GainNode | null = null;
   private processorNode: ScriptProcessorNode | null = null;
   private gainNode: GainNode | null = null;
   private status: AudioRecorderStatus = 'idle';
   private config: Omit<Required<AudioRecorderConfig>, 'deviceId'> & { deviceId?: string };
+  private static loopbackSupport: Promise<LoopbackSupport> | null = null;
   private onChunkCallback: ((chunk: AudioChunk) => void) | null = null;
   private onStatusChangeCallback: ((status: AudioRecorderStatus) => void) | null = null;
   private onErrorCallback: ((error: Error) => void) | null = null;
@@ -63,6 +87,9 @@ export class AudioRecorder {
   private persistDisabled = false;
   private static readonly PERSIST_FLUSH_MS = 5_000;
   private static readonly PERSIST_MAX_CONSECUTIVE_FAILURES = 5;
+  // Rotate the on-disk scratch into closed WAV segments of this length
+  // so a crash loses at most the active segment (see recording/segments.rs).
+  private static readonly PERSIST_SEGMENT_MINUTES = 5;
   private static readonly IN_MEMORY_FALLBACK_SECONDS = 120;
   /** Hard cap on how many samples we'll hold in memory waiting to be
    *  flushed. ~10 minutes of 48 kHz mono i16 = ~55 MB; above that we
@@ -78,6 +105,9 @@ export class AudioRecorder {
       sampleRate: config.sampleRate || 48000,
       channelCount: config.channelCount || 1,
       deviceId: config.deviceId || undefined,
+      source: config.source || 'microphone',
+      micGain: config.micGain ?? 1,
+      systemGain: config.systemGain ?? 1,
     };
 
     // 初始化音頻處理器（目標採樣率：16kHz）
@@ -92,6 +122,22 @@ export class AudioRecorder {
     return this.config.deviceId || undefined;
   }
 
+  setSource(source: AudioCaptureSource): void {
+    this.config.source = source;
+  }
+
+  getSource(): AudioCaptureSource {
+    return this.config.source;
+  }
+
+  /** Per-source gains for 'both' mode. Applies live while recording. */
+  setSourceGains(micGain: number, systemGain: number): void {
+    this.config.micGain = Math.max(0, micGain);
+    this.config.systemGain = Math.max(0, systemGain);
+    if (this.micGainNode) this.micGainNode.gain.value = this.config.micGain;
+    if (this.systemGainNode) this.systemGainNode.gain.value = this.config.systemGain;
+  }
+
   /**
    * 初始化音頻上下文
    */
@@ -118,6 +164,10 @@ export class AudioRecorder {
    * 請求麥克風權限並獲取音頻流
    */
   private async getMediaStream(): Promise<MediaStream> {
+    if (this.config.source === 'system') {
+      return this.getSystemAudioStream();
+    }
+
     console.log('[AudioRecorder] 請求麥克風權限...');
 
     if (!navigator.mediaDevices || !navigator.mediaDevices.getUserMedia) {
@@ -165,6 +215,86 @@ export class AudioRecorder {
     }
   }
 
+  private static getLoopbackSupport(): Promise<LoopbackSupport> {
+    if (!AudioRecorder.loopbackSupport) {
+      AudioRecorder.loopbackSupport = invoke<LoopbackSupport>(
+        'get_loopback_capture_support',
+      ).catch((err) => {
+        AudioRecorder.loopbackSupport = null;
+        throw err;
+      });
+    }
+    return AudioRecorder.loopbackSupport;
+  }
+
+  /**
+   * 獲取系統音訊（loopback）串流
+   *
+   * Windows: WebView2's getDisplayMedia is backed by WASAPI loopback.
+   * The API insists on a video track, so we request the smallest one and
+   * stop it immediately. macOS / Linux: pick the virtual loopback input
+   * (BlackHole, PulseAudio "Monitor of …") and open it with every
+   * voice-processing stage off — echo cancellation would cancel the
+   * very audio we're trying to capture.
+   */
+  private async getSystemAudioStream(): Promise<MediaStream> {
+    const support = await AudioRecorder.getLoopbackSupport();
+    console.log('[AudioRecorder] 請求系統音訊:', support.method);
+
+    if (support.method === 'display-media') {
+      if (!navigator.mediaDevices?.getDisplayMedia) {
+        throw new Error('此環境不支援 getDisplayMedia，無法錄製系統音訊');
+      }
+      const stream = await navigator.mediaDevices.getDisplayMedia({
+        video: { width: 1, height: 1, frameRate: 1 },
+        audio: {
+          echoCancellation: false,
+          noiseSuppression: false,
+          autoGainControl: false,
+        },
+        systemAudio: 'include',
+      } as DisplayMediaStreamOptions);
+      stream.getVideoTracks().forEach((track) => {
+        track.stop();
+        stream.removeTrack(track);
+      });
+      if (stream.getAudioTracks().length === 0) {
+        throw new Error(
+          support.guidance ?? '分享畫面時未包含系統音訊，請勾選「同時分享系統音訊」。',
+        );
+      }
+      return stream;
+    }
+
+    if (support.method === 'virtual-device') {
+      const devices = await navigator.mediaDevices.enumerateDevices();
+      const loopback = devices.find(
+        (d) =>
+          d.kind === 'audioinput' &&
+          support.device_label_hints.some((hint) =>
+            d.label.toLowerCase().includes(hint),
+          ),
+      );
+      if (!loopback) {
+        throw new Error(support.guidance ?? '找不到系統音訊的虛擬輸入裝置');
+      }
+      const stream = await navigator.mediaDevices.getUserMedia({
+        audio: {
+          deviceId: { exact: loopback.deviceId },
+          sampleRate: this.config.sampleRate,
+          channelCount: this.config.channelCount,
+          echoCancellation: false,
+          noiseSuppression: false,
+          autoGainControl: false,
+        },
+      });
+      console.log('[AudioRecorder] 使用系統音訊裝置:', loopback.label);
+      return stream;
+    }
+
+    throw new Error(support.guidance ?? '此平台不支援錄製系統音訊');
+  }
+
   private async requestMediaStream(deviceId?: string): Promise<MediaStream> {
     const stream = await navigator.mediaDevices.getUserMedia({
       audio: this.buildAudioConstraints(deviceId),
@@ -356,6 +486,7 @@ export class AudioRecorder {
         data: Array.from(merged),
         sampleRate: sr,
         channels: 1,
+        segmentMinutes: AudioRecorder.PERSIST_SEGMENT_MINUTES,
       });
       // Success — reset the failure counter so a later hiccup gets N
       // fresh chances instead of using up a budget burnt earlier.
@@ -493,7 +624,20 @@ export class AudioRecorder {
       this.gainNode.gain.value = 0; // 設置增益為 0，靜音輸出
 
       // 連接節點
-      this.sourceNode.connect(this.processorNode);
+      if (this.config.source === 'both') {
+        this.systemStream = await this.getSystemAudioStream();
+        this.systemSourceNode = audioContext.createMediaStreamSource(this.systemStream);
+        this.micGainNode = audioContext.createGain();
+        this.micGainNode.gain.value = this.config.micGain;
+        this.systemGainNode = audioContext.createGain();
+        this.systemGainNode.gain.value = this.config.systemGain;
+        this.sourceNode.connect(this.micGainNode);
+        this.systemSourceNode.connect(this.systemGainNode);
+        this.micGainNode.connect(this.processorNode);
+        this.systemGainNode.connect(this.processorNode);
+      } else {
+        this.sourceNode.connect(this.processorNode);
+      }
       this.processorNode.connect(this.gainNode);
       this.gainNode.connect(audioContext.destination); // 連接到 destination 以激活處理
 
@@ -596,6 +740,18 @@ export class AudioRecorder {
       this.sourceNode = null;
     }
 
+    for (const node of [this.systemSourceNode, this.micGainNode, this.systemGainNode]) {
+      node?.disconnect();
+    }
+    this.systemSourceNode = null;
+    this.micGainNode = null;
+    this.systemGainNode = null;
+
+    if (this.systemStream) {
+      this.systemStream.getTracks().forEach(track => track.stop());
+      this.systemStream = null;
+    }
+
     // 停止音頻軌道
     if (this.mediaStream) {
       this.mediaStream.getTracks().forEach(track => {
@@ -27,34 +27,16 @@ class AuthService {
     }
 
     private registerProcessors(): void {
-        // Register AUTH_REGISTER processor
-        offlineQueueService.registerProcessor('AUTH_REGISTER', async (payload) => {
-            await this.registerOnServer(payload.serverUrl, payload.username);
-        });
-    }
-
-    private async registerOnServer(serverUrl: string, username: string): Promise<void> {
-        // Use Tauri plugin-http fetch to bypass macOS ATS restrictions
-        const { fetch: tauriFetch } = await import('@tauri-apps/plugin-http');
-        const response = await tauriFetch(`${serverUrl}/api/auth/register`, {
-            method: 'POST',
-            headers: { 'Content-Type': 'application/json' },
-            body: JSON.stringify({ username })
-        } as any);
-
-        if (response.ok) {
-            // Update user as verified
-            if (this.currentUser && this.currentUser.username === username) {
+        // The backend sync outbox sends AUTH_REGISTER to
+        // `{serverUrl}/api/auth/register`; mark the user verified once
+        // the server accepts it.
+        offlineQueueService.onCompleted('AUTH_REGISTER', (payload) => {
+            if (this.currentUser && this.currentUser.username === payload?.username) {
                 this.currentUser.isVerified = true;
                 this.saveUser(this.currentUser);
             }
             console.log('[AuthService] Server registration successful');
-        } else if (response.status === 409) {
-            console.warn('[AuthService] Username conflict on server');
-            throw new Error('Username conflict');
-        } else {
-            throw new Error(`Registration failed: ${response.status}`);
-        }
+        });
     }
 
     public subscribe(listener: (user: User | null) => void): () => void {
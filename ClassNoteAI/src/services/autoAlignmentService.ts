@@ -1,3 +1,5 @@
+import { invoke } from '@tauri-apps/api/core';
+import { authService } from './authService';
 import { generateLocalEmbedding } from './embeddingService';
 
 function cosineSimilarity(a: number[], b: number[]): number {
@@ -24,6 +26,67 @@ export interface AlignmentSuggestion {
     reason: string;
 }
 
+/** One stretch of the recording showing a slide (`slide_alignments` row). */
+export interface SlideAlignment {
+    page_number: number;
+    start_ms: number;
+    end_ms: number;
+    confidence: number | null;
+}
+
+/** A point-in-time page match, before collapsing into ranges. */
+export interface AlignmentSample {
+    timeMs: number;
+    page: number;
+    confidence: number;
+}
+
+/** Minimum similarity for a transcript window to count as showing a page. */
+const TIMELINE_MIN_SIMILARITY = 0.35;
+/** Transcript window embedded per timeline sample. */
+const TIMELINE_WINDOW_MS = 20_000;
+
+/**
+ * Collapse time-ordered samples into non-overlapping ranges: a range
+ * runs from the first sample of a page until the next sample showing a
+ * different page (or `endMs`). Confidence is the range's mean.
+ */
+export function buildTimeline(samples: AlignmentSample[], endMs: number): SlideAlignment[] {
+    const sorted = [...samples].sort((a, b) => a.timeMs - b.timeMs);
+    const out: SlideAlignment[] = [];
+    let scores: number[] = [];
+    for (const sample of sorted) {
+        const last = out[out.length - 1];
+        if (last && last.page_number === sample.page) {
+            scores.push(sample.confidence);
+            continue;
+        }
+        if (last) {
+            last.end_ms = sample.timeMs;
+            last.confidence = scores.reduce((a, b) => a + b, 0) / scores.length;
+        }
+        out.push({ page_number: sample.page, start_ms: sample.timeMs, end_ms: endMs, confidence: null });
+        scores = [sample.confidence];
+    }
+    const last = out[out.length - 1];
+    if (last) last.confidence = scores.reduce((a, b) => a + b, 0) / scores.length;
+    return out.filter((a) => a.end_ms > a.start_ms);
+}
+
+/** Page on screen at `ms`, or null outside the timeline. */
+export function pageAt(timeline: SlideAlignment[], ms: number): number | null {
+    let lo = 0;
+    let hi = timeline.length - 1;
+    while (lo <= hi) {
+        const mid = (lo + hi) >> 1;
+        const a = timeline[mid];
+        if (ms < a.start_ms) hi = mid - 1;
+        else if (ms >= a.end_ms) lo = mid + 1;
+        else return a.page_number;
+    }
+    return null;
+}
+
 type AlignmentListener = (suggestion: AlignmentSuggestion) => void;
 
 class AutoAlignmentService {
@@ -181,6 +244,63 @@ class AutoAlignmentService {
     public hasPageEmbeddings(): boolean {
         return this.pageEmbeddings.length > 0;
     }
+
+    /**
+     * Align a whole recorded lecture: embed the transcript in
+     * {@link TIMELINE_WINDOW_MS} windows and match each against the
+     * loaded page embeddings. Expensive (one embedding per window) —
+     * callers should go through {@link getTimeline}, which stores the
+     * result.
+     */
+    public async computeTimeline(
+        subtitles: { timestamp: number; text_en: string }[],
+        durationMs: number,
+    ): Promise<SlideAlignment[]> {
+        if (this.pageEmbeddings.length === 0 || subtitles.length === 0) return [];
+        const sorted = [...subtitles].sort((a, b) => a.timestamp - b.timestamp);
+        const samples: AlignmentSample[] = [];
+        let i = 0;
+        while (i < sorted.length) {
+            const windowStart = sorted[i].timestamp * 1000;
+            const texts: string[] = [];
+            while (i < sorted.length && sorted[i].timestamp * 1000 < windowStart + TIMELINE_WINDOW_MS) {
+                texts.push(sorted[i].text_en);
+                i++;
+            }
+            const text = texts.join(' ').trim();
+            if (!text) continue;
+            const best = this.findBestPage(await generateLocalEmbedding(text));
+            if (best && best.similarity > TIMELINE_MIN_SIMILARITY) {
+                samples.push({ timeMs: windowStart, page: best.page, confidence: best.similarity });
+            }
+        }
+        const end = Math.max(durationMs, sorted[sorted.length - 1].timestamp * 1000 + 1);
+        return buildTimeline(samples, end);
+    }
+
+    /**
+     * The lecture's slide timeline for playback: read from storage, and
+     * only computed (then stored) when the lecture has never been
+     * aligned. Empty when there's nothing to align against.
+     */
+    public async getTimeline(
+        lectureId: string,
+        subtitles: { timestamp: number; text_en: string }[],
+        durationMs: number,
+    ): Promise<SlideAlignment[]> {
+        const stored = await invoke<SlideAlignment[]>('get_slide_alignments', { lectureId });
+        if (stored && stored.length > 0) return stored;
+
+        const timeline = await this.computeTimeline(subtitles, durationMs);
+        if (timeline.length > 0) {
+            await invoke('save_slide_alignments', {
+                lectureId,
+                alignments: timeline,
+                userId: authService.getUser()?.username || 'default_user',
+            });
+        }
+        return timeline;
+    }
 }
 
 export const autoAlignmentService = new AutoAlignmentService();
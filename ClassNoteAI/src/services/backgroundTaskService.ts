@@ -0,0 +1,226 @@
+/**
+ * Long-running backend work (src-tauri/src/tasks.rs).
+ *
+ * Model downloads, document conversions, audio imports, exports and app
+ * data moves all register in one backend registry. {@link list} returns
+ * what is running plus the most recently finished tasks, and every change
+ * arrives on the `task-updated` event. The feature-specific progress
+ * events are still emitted for the screens that use them.
+ *
+ * Model downloads can also be paused and resumed later, even after a
+ * restart (src-tauri/src/downloads/control.rs); a paused download's task
+ * ends in the `paused` state under an id that {@link
+ * backgroundTaskService.resumeDownload} reuses.
+ *
+ * What those downloads leave on disk can be listed and deleted with
+ * {@link backgroundTaskService.listInstalledModels} /
+ * {@link backgroundTaskService.deleteModel}
+ * (src-tauri/src/downloads/installed.rs), and checked against the
+ * published model manifest with
+ * {@link backgroundTaskService.checkModelUpdates}
+ * (src-tauri/src/downloads/updates.rs).
+ *
+ * This is separate from `taskTrackerService`, which tracks work the
+ * frontend itself drives (summaries, indexing).
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+export type BackgroundTaskKind = 'download' | 'conversion' | 'import' | 'export' | 'relocation';
+
+export type BackgroundTaskState =
+    | 'queued'
+    | 'running'
+    | 'completed'
+    | 'failed'
+    | 'cancelled'
+    | 'paused';
+
+export interface BackgroundTask {
+    id: string;
+    kind: BackgroundTaskKind;
+    label: string;
+    state: BackgroundTaskState;
+    /** 0..1, or null while the total is unknown. */
+    progress: number | null;
+    detail: string | null;
+    error: string | null;
+    cancellable: boolean;
+    pausable: boolean;
+    started_at: string;
+    finished_at: string | null;
+}
+
+/** What a resumable model download fetches. */
+export type DownloadSource =
+    | { model: 'whisper'; model_type: string; output_dir: string }
+    | { model: 'parakeet'; variant: 'int8' | 'fp32' }
+    | { model: 'gemma'; variant: 'b4' | 'b12' | 'b27' };
+
+/** A download that was started and hasn't completed, failed or been cancelled. */
+export interface PendingDownload {
+    /** The id of its task. */
+    id: string;
+    source: DownloadSource;
+    /** False while running, or when the app quit mid-download. */
+    paused: boolean;
+    /** Bytes on disk at the last checkpoint (setup downloads only). */
+    bytes_done: number;
+    total_bytes: number | null;
+}
+
+/** Which host model downloads try first; the others stay as fallbacks. */
+export type PreferredHost = 'origin' | 'hf_mirror' | 'custom';
+
+export interface MirrorSettings {
+    prefer: PreferredHost;
+    /** Stands in for https://huggingface.co, e.g. `https://hf.example.edu`. */
+    custom_url?: string | null;
+}
+
+/** A model under the models directory. */
+export interface InstalledModel {
+    /** Path relative to the models directory, e.g. `whisper/ggml-base.bin`. */
+    id: string;
+    engine: 'whisper' | 'parakeet' | 'translategemma' | 'translation' | 'embedding' | 'other';
+    variant: string;
+    path: string;
+    /** Bytes on disk. */
+    size: number;
+    /** RFC 3339; null if it hasn't been loaded since usage was tracked. */
+    last_used: string | null;
+}
+
+/**
+ * A newer release of an installed model. An `update` replaces the model
+ * in place; an `upgrade` installs a different model that supersedes it
+ * (e.g. a new quantization) and leaves the old one for the user to delete.
+ */
+export interface ModelUpdate {
+    installed_id: string;
+    /** The model that gets installed; `installed_id` for an update. */
+    id: string;
+    kind: 'update' | 'upgrade';
+    label: string;
+    version: string;
+    /** Bytes to download. */
+    size: number;
+    changelog: string;
+}
+
+/** How model downloads reach the network. */
+export type ProxyMode = 'system' | 'manual' | 'off';
+
+export interface ProxySettings {
+    mode: ProxyMode;
+    /** e.g. `http://proxy.example.edu:3128`. */
+    url?: string | null;
+    username?: string | null;
+    /**
+     * Never returned: `''` when one is saved, null otherwise. Saving `''`
+     * keeps the saved password; null removes it.
+     */
+    password?: string | null;
+    /** Comma-separated hosts to reach directly, as in `NO_PROXY`. */
+    no_proxy?: string | null;
+}
+
+/** Not running any more; a paused task counts, since resuming starts a new run. */
+export function isFinished(task: BackgroundTask): boolean {
+    return (
+        task.state === 'completed' ||
+        task.state === 'failed' ||
+        task.state === 'cancelled' ||
+        task.state === 'paused'
+    );
+}
+
+export const backgroundTaskService = {
+    list(): Promise<BackgroundTask[]> {
+        return invoke<BackgroundTask[]>('list_tasks');
+    },
+
+    /** Resolves false when the task has already finished. */
+    cancel(id: string): Promise<boolean> {
+        return invoke<boolean>('cancel_task', { id });
+    },
+
+    listDownloads(): Promise<PendingDownload[]> {
+        return invoke<PendingDownload[]>('list_downloads');
+    },
+
+    /** Keeps the partial files. Resolves false when it isn't running. */
+    pauseDownload(id: string): Promise<boolean> {
+        return invoke<boolean>('pause_download', { id });
+    },
+
+    /** Resolves when the download finishes, like the command that started it. */
+    resumeDownload(id: string): Promise<string> {
+        return invoke<string>('resume_download', { id });
+    },
+
+    /** Stops the download and deletes its partial files. */
+    cancelDownload(id: string): Promise<boolean> {
+        return invoke<boolean>('cancel_download', { id });
+    },
+
+    getMirrors(): Promise<MirrorSettings> {
+        return invoke<MirrorSettings>('get_download_mirrors');
+    },
+
+    /** Resolves with the settings as saved (trimmed custom URL). */
+    setMirrors(settings: MirrorSettings): Promise<MirrorSettings> {
+        return invoke<MirrorSettings>('set_download_mirrors', { settings });
+    },
+
+    /** Bytes per second across all downloads; 0 = unlimited. */
+    getRateLimit(): Promise<number> {
+        return invoke<number>('get_download_rate_limit');
+    },
+
+    /** Applies to running downloads too. */
+    setRateLimit(bytesPerSec: number): Promise<number> {
+        return invoke<number>('set_download_rate_limit', { bytesPerSec });
+    },
+
+    getProxy(): Promise<ProxySettings> {
+        return invoke<ProxySettings>('get_download_proxy');
+    },
+
+    /** Resolves with the settings as saved, password redacted. */
+    setProxy(settings: ProxySettings): Promise<ProxySettings> {
+        return invoke<ProxySettings>('set_download_proxy', { settings });
+    },
+
+    listInstalledModels(): Promise<InstalledModel[]> {
+        return invoke<InstalledModel[]>('list_installed_models');
+    },
+
+    /**
+     * Resolves with the bytes freed. Rejects with kind `busy` while the
+     * model is loaded for a recording, served, or still downloading.
+     */
+    deleteModel(id: string): Promise<number> {
+        return invoke<number>('delete_model', { id });
+    },
+
+    /** Hashes the installed models the first time, so it can take a while. */
+    checkModelUpdates(): Promise<ModelUpdate[]> {
+        return invoke<ModelUpdate[]>('check_model_updates');
+    },
+
+    /**
+     * Installs the update offered for installed model `installedId` and
+     * resolves with the id of the model installed. Runs as a download
+     * task; rejects with kind `busy` while an update in place would
+     * replace a model in use.
+     */
+    applyModelUpdate(installedId: string): Promise<string> {
+        return invoke<string>('apply_model_update', { id: installedId });
+    },
+
+    onUpdate(cb: (task: BackgroundTask) => void): Promise<UnlistenFn> {
+        return listen<BackgroundTask>('task-updated', (event) => cb(event.payload));
+    },
+};
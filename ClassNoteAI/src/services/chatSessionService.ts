@@ -7,6 +7,7 @@
 import { invoke } from '@tauri-apps/api/core';
 import { chat as llmChat } from './llm';
 import { authService } from './authService';
+import { errorMessage } from './appError';
 
 export interface ChatMessage {
     id: string;
@@ -239,7 +240,7 @@ class ChatSessionService {
                 isDeleted: false,
             });
         } catch (e) {
-            const msg = e instanceof Error ? e.message : String(e);
+            const msg = errorMessage(e);
             if (/FOREIGN KEY|foreign key/i.test(msg)) {
                 console.warn(
                     `[ChatSessionService] lecture_id ${lectureId} FK failed; saving as global session. ` +
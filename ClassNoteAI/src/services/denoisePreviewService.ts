@@ -0,0 +1,99 @@
+/**
+ * Before / after preview for the noise-suppression setting
+ * (`experimental.denoiseInput`).
+ *
+ * A few seconds are captured from the configured input the same way a
+ * recording is (AudioRecorder → 16 kHz mono), run once through the
+ * Rust `audio::denoise` stage by `preview_denoise`, and both versions
+ * can then be played back to hear what the toggle would change.
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import { AudioRecorder, type AudioCaptureSource } from './audioRecorder';
+
+export const PREVIEW_SAMPLE_RATE = 16000;
+
+export interface DenoisePreview {
+    original: Int16Array;
+    denoised: Int16Array;
+}
+
+export interface CaptureOptions {
+    seconds: number;
+    deviceId?: string;
+    source?: AudioCaptureSource;
+}
+
+function concat(chunks: Int16Array[]): Int16Array {
+    const out = new Int16Array(chunks.reduce((n, c) => n + c.length, 0));
+    let offset = 0;
+    for (const chunk of chunks) {
+        out.set(chunk, offset);
+        offset += chunk.length;
+    }
+    return out;
+}
+
+class DenoisePreviewService {
+    private playback: AudioContext | null = null;
+    /** Settles the pending `play()` when playback is cut short. */
+    private ended: (() => void) | null = null;
+
+    /** Capture `seconds` of 16 kHz mono from the configured input. */
+    async capture({ seconds, deviceId, source }: CaptureOptions): Promise<Int16Array> {
+        const recorder = new AudioRecorder({ deviceId, source });
+        const chunks: Int16Array[] = [];
+        recorder.onChunk((chunk) => chunks.push(chunk.data));
+        try {
+            await recorder.start();
+            await new Promise((resolve) => setTimeout(resolve, seconds * 1000));
+        } finally {
+            // Stops the capture too.
+            await recorder.destroy();
+        }
+        return concat(chunks);
+    }
+
+    /** Record a clip and denoise it; both sides are 16 kHz mono. */
+    async record(options: CaptureOptions): Promise<DenoisePreview> {
+        const original = await this.capture(options);
+        const denoised = await invoke<number[]>('preview_denoise', {
+            audioData: Array.from(original),
+            sampleRate: PREVIEW_SAMPLE_RATE,
+        });
+        return { original, denoised: Int16Array.from(denoised) };
+    }
+
+    /** Play a 16 kHz clip; resolves when it ends. Stops any clip already
+     *  playing so "before" and "after" never overlap. */
+    async play(clip: Int16Array): Promise<void> {
+        this.stop();
+        const ctx = new AudioContext();
+        this.playback = ctx;
+        const buffer = ctx.createBuffer(1, Math.max(clip.length, 1), PREVIEW_SAMPLE_RATE);
+        const channel = buffer.getChannelData(0);
+        for (let i = 0; i < clip.length; i++) {
+            channel[i] = clip[i] / 0x8000;
+        }
+        const node = ctx.createBufferSource();
+        node.buffer = buffer;
+        node.connect(ctx.destination);
+        await new Promise<void>((resolve) => {
+            this.ended = resolve;
+            node.onended = () => resolve();
+            node.start();
+        });
+        if (this.playback === ctx) this.stop();
+    }
+
+    stop(): void {
+        const ctx = this.playback;
+        const ended = this.ended;
+        this.playback = null;
+        this.ended = null;
+        void ctx?.close().catch(() => undefined);
+        ended?.();
+    }
+}
+
+export const denoisePreviewService = new DenoisePreviewService();
@@ -1,8 +1,11 @@
 import { invoke } from "@tauri-apps/api/core";
 import { openUrl } from "@tauri-apps/plugin-opener";
 import { storageService } from "./storageService";
-import { redactLogContent } from "./logDiagnostics";
+import { buildGithubIssueUrl, redactLogContent } from "./logDiagnostics";
 import { resolveAudioPath } from "./audioPathService";
+import { audioDeviceService } from "./audioDeviceService";
+import { authService } from "./authService";
+import { errorMessage } from "./appError";
 
 interface DiagnosticPackageInput {
   lecture_meta_json: string;
@@ -32,7 +35,7 @@ export async function exportDiagnosticPackage(
     ? await resolveAudioPath(lecture.audio_path)
     : null;
 
-  const rawLog = await invoke<string>("read_recent_log", { lines: 2000 });
+  const rawLog = await invoke<string>("get_recent_logs", { lines: 2000 });
   const { redacted } = redactLogContent(rawLog);
   const metadata = {
     app_version: opts.appVersion,
@@ -61,3 +64,191 @@ export async function revealZipInFileManager(zipPath: string): Promise<void> {
   const parent = lastSeparator > 0 ? zipPath.slice(0, lastSeparator) : zipPath;
   await openUrl(parent);
 }
+
+/** A Rust panic captured by the backend's crash hook (`crash::CrashReport`). */
+export interface CrashReport {
+  id: string;
+  created_at: string;
+  app_version: string;
+  platform: string;
+  thread: string;
+  message: string;
+  location: string | null;
+  backtrace: string;
+  loaded_models: string[];
+}
+
+export async function getLastCrashReport(): Promise<CrashReport | null> {
+  return await invoke<CrashReport | null>("get_last_crash_report");
+}
+
+export async function dismissCrashReport(id: string): Promise<void> {
+  await invoke("dismiss_crash_report", { id });
+}
+
+/** Plain-text crash report for an issue body; paths are redacted like logs. */
+export function formatCrashReport(report: CrashReport): string {
+  const text = [
+    `Crash: ${report.message}`,
+    `At: ${report.location ?? "unknown"} (thread ${report.thread})`,
+    `When: ${report.created_at}`,
+    `Platform: ${report.platform}`,
+    `Loaded models: ${report.loaded_models.join(", ") || "none"}`,
+    "",
+    report.backtrace.trim(),
+  ].join("\n");
+  return redactLogContent(text).redacted;
+}
+
+export function crashReportIssueUrl(report: CrashReport): string {
+  return buildGithubIssueUrl(formatCrashReport(report), report.app_version);
+}
+
+/** One launch self-test check (`startup::Check`). */
+export interface StartupCheck {
+  name: string;
+  status: "ok" | "warning" | "failed" | "skipped";
+  detail: string | null;
+  /** Repair id to pass to `repairStartup`, when the failure has one. */
+  repair: string | null;
+}
+
+/** `get_startup_status` payload (`startup::StartupStatus`). */
+export interface StartupStatus {
+  safe_mode: boolean;
+  reasons: string[];
+  checks: StartupCheck[];
+}
+
+export async function getStartupStatus(): Promise<StartupStatus> {
+  return await invoke<StartupStatus>("get_startup_status");
+}
+
+/** Tell the backend this launch got as far as a rendered UI. */
+export async function markStartupComplete(): Promise<void> {
+  await invoke("mark_startup_complete");
+}
+
+/** Resolves to a short description of what the repair changed. */
+export async function repairStartup(action: string): Promise<string> {
+  return await invoke<string>("repair_startup", { action });
+}
+
+/** Restarts the app; the self-test decides again whether to use safe mode. */
+export async function exitSafeMode(): Promise<void> {
+  await invoke("exit_safe_mode");
+}
+
+export const LOG_LEVELS = ["off", "error", "warn", "info", "debug", "trace"] as const;
+export type LogLevel = (typeof LOG_LEVELS)[number];
+
+/** Runtime log verbosity (`logging::LogLevels`). */
+export interface LogLevels {
+  default: LogLevel;
+  /** Module path (`asr`, `asr::parakeet_engine`, `reqwest`) → level. */
+  modules: Record<string, LogLevel>;
+}
+
+export async function getLogLevels(): Promise<LogLevels> {
+  return await invoke<LogLevels>("get_log_levels");
+}
+
+/** Applies immediately and is kept across restarts. */
+export async function setLogLevels(levels: LogLevels): Promise<LogLevels> {
+  return await invoke<LogLevels>("set_log_levels", { levels });
+}
+
+/** `{asr: "debug", reqwest: "warn"}` → `"asr=debug, reqwest=warn"`. */
+export function formatModuleLevels(modules: Record<string, LogLevel>): string {
+  return Object.entries(modules)
+    .map(([module, level]) => `${module}=${level}`)
+    .join(", ");
+}
+
+/** Inverse of {@link formatModuleLevels}; throws on a malformed entry. */
+export function parseModuleLevels(text: string): Record<string, LogLevel> {
+  const modules: Record<string, LogLevel> = {};
+  for (const entry of text.split(/[,\n]/)) {
+    if (!entry.trim()) continue;
+    const [module, level, ...rest] = entry.split("=").map((part) => part.trim());
+    const normalized = level?.toLowerCase() as LogLevel;
+    if (!module || rest.length > 0 || !LOG_LEVELS.includes(normalized)) {
+      throw new Error(`無法解析「${entry.trim()}」，格式應為 模組=等級`);
+    }
+    modules[module] = normalized;
+  }
+  return modules;
+}
+
+/** `run_diagnostics` payload (`diagnostics::health::DiagnosticsReport`). */
+export interface DiagnosticsReport {
+  generated_at: string;
+  app_version: string;
+  build_variant: string;
+  platform: string;
+  database: {
+    ok: boolean;
+    size_bytes: number | null;
+    quick_check: string[];
+    error: string | null;
+  };
+  models: { path: string; size_bytes: number; sha256: string | null }[];
+  disk: { free_bytes: number | null; error: string | null };
+  endpoints: {
+    name: string;
+    url: string;
+    reachable: boolean;
+    status: number | null;
+    latency_ms: number | null;
+    error: string | null;
+  }[];
+  sync: {
+    last_success_at: string | null;
+    pending_actions: number;
+    failed_actions: number;
+  };
+}
+
+export interface HealthReport extends DiagnosticsReport {
+  /** Capture runs in the webview, so devices are listed here, not in Rust. */
+  audio: {
+    input_devices: number;
+    labels: string[];
+    permission: string;
+    error: string | null;
+  };
+}
+
+/**
+ * Backend health check plus the webview's audio inputs — the single
+ * payload support asks users to paste.
+ */
+export async function runHealthCheck(hashModels = true): Promise<HealthReport> {
+  const report = await invoke<DiagnosticsReport>("run_diagnostics", {
+    userId: authService.getUser()?.username || "default_user",
+    hashModels,
+  });
+  let audio: HealthReport["audio"];
+  try {
+    const devices = await audioDeviceService.getAudioInputDevices();
+    audio = {
+      input_devices: devices.length,
+      labels: devices.map((d) => d.label),
+      permission: audioDeviceService.getPermissionState(),
+      error: null,
+    };
+  } catch (e) {
+    audio = {
+      input_devices: 0,
+      labels: [],
+      permission: audioDeviceService.getPermissionState(),
+      error: errorMessage(e),
+    };
+  }
+  return { ...report, audio };
+}
+
+/** Pretty JSON of a health report, redacted like the log preview. */
+export function formatHealthReport(report: HealthReport): string {
+  return redactLogContent(JSON.stringify(report, null, 2)).redacted;
+}
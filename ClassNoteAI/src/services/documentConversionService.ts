@@ -0,0 +1,65 @@
+/**
+ * Office / iWork → PDF conversion (src-tauri/src/documents/convert.rs).
+ *
+ * Several files at once should go through {@link enqueue}: the backend
+ * converts them one at a time, since Keynote / Pages / Office can only
+ * export one document at a time. Progress for every file,
+ * queued or direct, arrives on the `conversion-progress` event.
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+export type ConversionStage =
+    | 'queued'
+    | 'launching'
+    | 'exporting'
+    | 'validating'
+    | 'retrying'
+    | 'done'
+    | 'failed'
+    | 'cancelled';
+
+export interface ConversionProgress {
+    jobId: string;
+    filePath: string;
+    stage: ConversionStage;
+    /** Queue position, app name, output path or error, depending on stage. */
+    message: string | null;
+}
+
+export interface QueuedConversion {
+    jobId: string;
+    filePath: string;
+}
+
+export interface CleanupReport {
+    removed: string[];
+    freed_bytes: number;
+}
+
+export const documentConversionService = {
+    /** Convert one file and resolve with the PDF path (reused if already converted). */
+    convert(filePath: string, jobId?: string): Promise<string> {
+        return invoke<string>('convert_to_pdf', { filePath, jobId });
+    },
+
+    /** Queue files for sequential conversion; results arrive as progress events. */
+    enqueue(filePaths: string[]): Promise<QueuedConversion[]> {
+        return invoke<QueuedConversion[]>('enqueue_conversions', { filePaths });
+    },
+
+    /** Resolves false when the job has already finished. */
+    cancel(jobId: string): Promise<boolean> {
+        return invoke<boolean>('cancel_conversion', { jobId });
+    },
+
+    /** Delete converted PDFs that no lecture refers to any more. */
+    cleanup(): Promise<CleanupReport> {
+        return invoke<CleanupReport>('cleanup_converted_documents');
+    },
+
+    onProgress(cb: (progress: ConversionProgress) => void): Promise<UnlistenFn> {
+        return listen<ConversionProgress>('conversion-progress', (event) => cb(event.payload));
+    },
+};
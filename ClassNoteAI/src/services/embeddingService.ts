@@ -1,5 +1,11 @@
 import { invoke } from "@tauri-apps/api/core";
-import { listen, type UnlistenFn } from "@tauri-apps/api/event";
+import type { UnlistenFn } from "@tauri-apps/api/event";
+import { errorKind, errorMessage } from "./appError";
+import { setupService } from "./setupService";
+import { getProgressPercentage } from "../types/setup";
+
+/** The `task_id` of the download's `setup-progress` events. */
+const DOWNLOAD_TASK_ID = 'bge-small-en-v1.5';
 
 class EmbeddingService {
     private isLoaded = false;
@@ -9,7 +15,7 @@ class EmbeddingService {
     private downloadPromise: Promise<void> | null = null;
     /** Latest progress value (0-100) for any UI that wants to poll while
      *  the download runs. Frontend consumers can alternatively subscribe
-     *  to the Tauri event `embedding_download_progress`. */
+     *  to `setup-progress` events with `task_id` `bge-small-en-v1.5`. */
     public downloadProgress: number = 0;
 
     /**
@@ -38,7 +44,7 @@ class EmbeddingService {
     private async doLoad(): Promise<void> {
         try {
             const modelsDir = await this.getModelDir();
-            // BAAI/bge-small-en-v1.5 — 標準 BERT (384-d, ~33MB)，Candle 原生支援。
+            // BAAI/bge-small-en-v1.5 — 標準 BERT (384-d, ~130MB)，Candle 原生支援。
             // v0.5.2 從 nomic-embed-text-v1 換過來，因為 nomic 是 NomicBert
             // 架構（rotary + SwiGLU），Candle 的 BertModel::load 無法載入。
             // 中文 query 由 ragService 先透過 LLM 翻譯成英文再 embed。
@@ -62,20 +68,13 @@ class EmbeddingService {
                 // "Model file not found" error. Download is a shared
                 // promise — concurrent `ensureLoaded` calls piggy-back.
                 //
-                // Match broadly: "not found" covers fresh upgrades,
-                // "損壞" / "下載未完成" covers the partial-download case
-                // that service.rs::new specifically rejects. All of these
-                // have the same fix (download the files) and the Rust
-                // downloader now detects + repairs truncated files so a
+                // The backend reports both a missing file (fresh upgrade)
+                // and a truncated one (interrupted download) as
+                // `model_missing`. Both have the same fix and the Rust
+                // downloader detects + repairs truncated files so a
                 // second attempt is safe.
-                const msg = String(err);
-                const needsDownload =
-                    msg.includes('not found') ||
-                    msg.includes('Model file not found') ||
-                    msg.includes('損壞') ||
-                    msg.includes('下載未完成');
-                if (!needsDownload) throw err;
-                console.warn('[EmbeddingService] 偵測到模型檔案缺失/不完整，自動下載 (~33MB)...');
+                if (errorKind(err) !== 'model_missing') throw err;
+                console.warn('[EmbeddingService] 偵測到模型檔案缺失/不完整，自動下載 (~130MB)...');
                 await this.ensureDownloaded();
                 // Retry. If this second attempt also fails, surface the
                 // real error — something is wrong beyond a missing file.
@@ -91,8 +90,8 @@ class EmbeddingService {
     }
 
     /** Trigger a fresh download of the bge-small-en-v1.5 model via the
-     *  existing Tauri command. Subscribes to the
-     *  `embedding_download_progress` event so callers can poll
+     *  existing Tauri command. Subscribes to its `setup-progress`
+     *  events so callers can poll
      *  `downloadProgress` for a progress bar. Deduplicated — calling
      *  this while a download is in flight returns the same promise. */
     async ensureDownloaded(): Promise<void> {
@@ -101,8 +100,10 @@ class EmbeddingService {
         let unlisten: UnlistenFn | null = null;
         this.downloadPromise = (async () => {
             try {
-                unlisten = await listen<number>('embedding_download_progress', (e) => {
-                    this.downloadProgress = e.payload;
+                unlisten = await setupService.onProgress((progress) => {
+                    if (progress.task_id === DOWNLOAD_TASK_ID && progress.status === 'InProgress') {
+                        this.downloadProgress = getProgressPercentage(progress);
+                    }
                 });
                 await invoke('download_embedding_model_cmd');
                 this.downloadProgress = 100;
@@ -193,7 +194,7 @@ export async function generateLocalEmbeddingsBatch(texts: string[]): Promise<num
     try {
         return await invoke<number[][]>('generate_embeddings_batch', { texts });
     } catch (err) {
-        const msg = err instanceof Error ? err.message : String(err);
+        const msg = errorMessage(err);
         if (msg.includes('not allowed') || msg.includes('invalid args') || msg.includes('generate_embeddings_batch')) {
             console.warn('[embeddingService] batch command unavailable, falling back to sequential loop:', msg);
             const out: number[][] = [];
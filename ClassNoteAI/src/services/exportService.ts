@@ -15,6 +15,9 @@
  *     Each missing piece is silently skipped so callers can pass partial
  *     `note` payloads (e.g. when the LLM summary failed).
  *
+ * Note formats that need real layout (PDF, DOCX) are rendered on the Rust side;
+ * {@link exportNote} only picks the destination and invokes the command.
+ *
  * The service is split into pure functions (`exportLectureSRT`,
  * `exportLectureMarkdown`) and an integration entrypoint (`exportLecture`)
  * so unit tests can exercise the formatting logic without touching Tauri.
@@ -240,3 +243,114 @@ export async function exportLecture(
     await writeTextFile(path, content);
     return { path, size: content.length };
 }
+
+/** Note exports rendered by a Rust command (src-tauri/src/notes/). */
+const NOTE_EXPORTS = {
+    pdf: { command: 'export_note_pdf', label: 'PDF', extension: 'pdf' },
+    docx: { command: 'export_note_docx', label: 'Word', extension: 'docx' },
+} as const;
+
+export type NoteExportFormat = keyof typeof NOTE_EXPORTS;
+
+/**
+ * Export the lecture's note via the Rust exporter for `format`. Prompts
+ * for a save path first; returns `null` if the user cancels.
+ * `template` is passed through for PDF (`standard` / `compact` / `letter`).
+ */
+export async function exportNote(
+    lectureId: string,
+    format: NoteExportFormat,
+    options: { template?: string } = {},
+): Promise<{ path: string } | null> {
+    const { storageService } = await import('./storageService');
+    const lecture = await storageService.getLecture(lectureId);
+    if (!lecture) throw new Error(`Lecture ${lectureId} not found`);
+
+    const spec = NOTE_EXPORTS[format];
+    const { save } = await import('@tauri-apps/plugin-dialog');
+    const destPath = await save({
+        defaultPath: `${lecture.title}.${spec.extension}`,
+        filters: [{ name: spec.label, extensions: [spec.extension] }],
+    });
+    if (!destPath) return null;
+
+    const { invoke } = await import('@tauri-apps/api/core');
+    const { authService } = await import('./authService');
+    const path = await invoke<string>(spec.command, {
+        lectureId,
+        destPath,
+        template: options.template,
+        userId: authService.getUser()?.username || 'default_user',
+    });
+    return { path };
+}
+
+export type AnkiExportFormat = 'apkg' | 'csv';
+
+export interface AnkiExportReport {
+    path: string;
+    cards: number;
+    /** Cards that got an audio clip of their source subtitle. */
+    clips: number;
+}
+
+/**
+ * Export the Q&A of every note in the course as an Anki deck. `apkg`
+ * imports directly; `csv` writes a folder with `cards.txt` plus the
+ * audio clips. Returns `null` if the user cancels the save dialog.
+ */
+export async function exportAnkiDeck(
+    courseId: string,
+    format: AnkiExportFormat = 'apkg',
+): Promise<AnkiExportReport | null> {
+    const { storageService } = await import('./storageService');
+    const course = await storageService.getCourse(courseId);
+    if (!course) throw new Error(`Course ${courseId} not found`);
+
+    const { save } = await import('@tauri-apps/plugin-dialog');
+    const destPath = await save({
+        defaultPath: format === 'apkg' ? `${course.title}.apkg` : course.title,
+        filters:
+            format === 'apkg' ? [{ name: 'Anki 卡片組', extensions: ['apkg'] }] : undefined,
+    });
+    if (!destPath) return null;
+
+    const { invoke } = await import('@tauri-apps/api/core');
+    const { authService } = await import('./authService');
+    return invoke<AnkiExportReport>('export_anki', {
+        courseId,
+        destPath,
+        format,
+        userId: authService.getUser()?.username || 'default_user',
+    });
+}
+
+export interface MarkdownBundleReport {
+    /** The course folder that was written. */
+    path: string;
+    lectures: number;
+    clips: number;
+    slides: number;
+}
+
+/**
+ * Export the course as a folder of Markdown files (one per lecture, plus
+ * an index) with section audio and slide images under `assets/`, ready
+ * to drop into an Obsidian / Logseq vault. Asks for the parent folder;
+ * returns `null` if the user cancels.
+ */
+export async function exportMarkdownBundle(
+    courseId: string,
+): Promise<MarkdownBundleReport | null> {
+    const { open } = await import('@tauri-apps/plugin-dialog');
+    const destDir = await open({ directory: true, title: '選擇匯出位置（例如 Obsidian vault）' });
+    if (typeof destDir !== 'string') return null;
+
+    const { invoke } = await import('@tauri-apps/api/core');
+    const { authService } = await import('./authService');
+    return invoke<MarkdownBundleReport>('export_markdown_bundle', {
+        courseId,
+        destDir,
+        userId: authService.getUser()?.username || 'default_user',
+    });
+}
@@ -0,0 +1,52 @@
+/**
+ * Local (Tesseract) OCR for PDF pages with no text layer.
+ *
+ * Thin wrapper over the Rust `ocr_pdf_pages` command
+ * (src-tauri/src/documents/ocr.rs). Used by ragService's pdfjs path to
+ * fill in scanned / image-only pages, so those still get indexed when
+ * no cloud vision provider is configured. Runs entirely on-device.
+ *
+ * The Rust side works on files, so the PDF bytes are written to a temp
+ * file named after their content hash first.
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+
+export interface LocalOcrPage {
+    page: number;
+    text: string;
+}
+
+interface OcrResult {
+    languages: string;
+    pages: LocalOcrPage[];
+}
+
+export const localOcrService = {
+    /**
+     * OCR `pages` (1-based) of `pdfData`. `languages` are hints such as
+     * `['zh-TW', 'en']`; the backend drops ones Tesseract lacks. Throws
+     * when Tesseract isn't installed — callers treat that as "no OCR".
+     */
+    async recognizePages(
+        pdfData: ArrayBuffer,
+        contentHash: string,
+        pages: number[],
+        languages: string[],
+    ): Promise<LocalOcrPage[]> {
+        if (pages.length === 0) return [];
+        const tempDir = await invoke<string>('get_temp_dir');
+        const sep = tempDir.includes('\\') ? '\\' : '/';
+        const path = `${tempDir}${sep}classnote-ocr${sep}${contentHash}.pdf`;
+        await invoke('write_temp_file', {
+            path,
+            data: Array.from(new Uint8Array(pdfData)),
+        });
+        const result = await invoke<OcrResult | null>('ocr_pdf_pages', {
+            path,
+            pages,
+            languages,
+        });
+        return result?.pages ?? [];
+    },
+};
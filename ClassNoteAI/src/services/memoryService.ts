@@ -0,0 +1,84 @@
+/**
+ * Per-model memory accounting (`get_memory_usage`) and the pre-load
+ * headroom check built on it.
+ *
+ * The backend lists every loaded model plus every model that's on disk
+ * but not loaded, each with an approximate footprint (weight size, or
+ * the measured RSS for the TranslateGemma sidecar). Before loading one
+ * more model we compare its footprint against what the OS says is
+ * still available — on 8 GB machines ASR + the 4B translator + the
+ * browser already leave little room, and the failure mode of going
+ * over is a swap storm mid-lecture rather than a clean error.
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import { confirmService } from './confirmService';
+
+export interface ModelMemory {
+    /** `asr:int8`, `embedding`, `translation:4b`, `translation:sidecar`, … */
+    id: string;
+    kind: 'asr' | 'embedding' | 'translation';
+    loaded: boolean;
+    approx_bytes: number;
+    /** true when approx_bytes is a measured resident set. */
+    measured: boolean;
+}
+
+export interface MemoryUsage {
+    models: ModelMemory[];
+    loaded_bytes: number;
+    process_rss_bytes: number | null;
+    system_total_bytes: number | null;
+    system_available_bytes: number | null;
+}
+
+/** Room left for the OS, the webview and audio buffers after a load. */
+export const HEADROOM_BYTES = 1024 ** 3;
+
+const GB = 1024 ** 3;
+const formatGb = (bytes: number) => `${(bytes / GB).toFixed(1)} GB`;
+
+export async function getMemoryUsage(): Promise<MemoryUsage> {
+    return invoke<MemoryUsage>('get_memory_usage');
+}
+
+/**
+ * Warning text if loading `modelId` would leave less than
+ * [`HEADROOM_BYTES`] free, else `null`. Unknown models, models that are
+ * already loaded, and platforms that don't report available memory
+ * never warn.
+ */
+export function memoryWarningFor(usage: MemoryUsage, modelId: string): string | null {
+    const model = usage.models.find((m) => m.id === modelId);
+    const available = usage.system_available_bytes;
+    if (!model || model.loaded || available == null) return null;
+    if (model.approx_bytes + HEADROOM_BYTES <= available) return null;
+    return (
+        `載入 ${modelId} 約需 ${formatGb(model.approx_bytes)}，` +
+        `目前可用記憶體只剩 ${formatGb(available)}` +
+        (usage.loaded_bytes > 0
+            ? `（已載入的模型約佔 ${formatGb(usage.loaded_bytes)}）`
+            : '') +
+        '。繼續可能讓系統變得很慢。'
+    );
+}
+
+/**
+ * Ask before loading `modelId` when memory is tight. Resolves `true`
+ * when it's fine to go ahead — including when the usage query itself
+ * fails, so a missing probe never blocks a load.
+ */
+export async function confirmModelLoad(modelId: string): Promise<boolean> {
+    let warning: string | null = null;
+    try {
+        warning = memoryWarningFor(await getMemoryUsage(), modelId);
+    } catch (err) {
+        console.warn('[memoryService] get_memory_usage failed:', err);
+    }
+    if (!warning) return true;
+    return confirmService.ask({
+        title: '記憶體可能不足',
+        message: warning,
+        confirmLabel: '仍要載入',
+    });
+}
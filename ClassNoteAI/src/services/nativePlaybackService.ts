@@ -0,0 +1,62 @@
+/**
+ * Rust-side lecture playback (src-tauri/src/playback).
+ *
+ * Only present in builds with the `native-playback` feature; callers
+ * check `isAvailable()` once and keep using `<audio>` otherwise. The
+ * backend emits `playback-position` every 100 ms while playing and once
+ * when playback reaches the end.
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+export interface PlaybackStatus {
+  lecture_id: string | null;
+  position_ms: number;
+  duration_ms: number;
+  playing: boolean;
+  ended: boolean;
+}
+
+class NativePlaybackService {
+  private available: Promise<boolean> | null = null;
+
+  isAvailable(): Promise<boolean> {
+    if (!this.available) {
+      this.available = invoke<boolean>('playback_is_available').catch(() => false);
+    }
+    return this.available;
+  }
+
+  load(lectureId: string): Promise<PlaybackStatus> {
+    return invoke<PlaybackStatus>('playback_load', { lectureId });
+  }
+
+  play(): Promise<PlaybackStatus> {
+    return invoke<PlaybackStatus>('playback_play');
+  }
+
+  pause(): Promise<PlaybackStatus> {
+    return invoke<PlaybackStatus>('playback_pause');
+  }
+
+  seek(positionMs: number): Promise<PlaybackStatus> {
+    return invoke<PlaybackStatus>('playback_seek', {
+      positionMs: Math.max(0, Math.round(positionMs)),
+    });
+  }
+
+  getPosition(): Promise<PlaybackStatus> {
+    return invoke<PlaybackStatus>('playback_get_position');
+  }
+
+  stop(): Promise<void> {
+    return invoke<void>('playback_stop');
+  }
+
+  onPosition(callback: (status: PlaybackStatus) => void): Promise<UnlistenFn> {
+    return listen<PlaybackStatus>('playback-position', (event) => callback(event.payload));
+  }
+}
+
+export const nativePlaybackService = new NativePlaybackService();
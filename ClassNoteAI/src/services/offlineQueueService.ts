@@ -1,12 +1,23 @@
+/**
+ * Offline queue — the frontend side of the backend sync outbox
+ * (src-tauri/src/sync).
+ *
+ * Actions are added here and stored in the `pending_actions` table; the
+ * backend sends them, retries them and reports on `sync-status`. This
+ * service used to run that loop itself, which left actions stuck in
+ * `processing` (or sent twice) when the app quit mid-request.
+ */
+
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 
 // cp75.34 — Avoid a top-level `import { authService } from './authService'`.
-// authService.ts imports this module at module load (to register the
-// AUTH_REGISTER processor). A static import here would either (a) break
+// authService.ts imports this module at module load (to react to
+// AUTH_REGISTER completions). A static import here would either (a) break
 // the cycle in jest/vitest (authService binding is undefined at the
-// time `new AuthService()` runs, blowing up offlineQueueService.registerProcessor),
-// or (b) under ESM with hoisting still hand us back an in-construction
-// stub. Using a setter-injection seam keeps the module decoupled.
+// time `new AuthService()` runs), or (b) under ESM with hoisting still
+// hand us back an in-construction stub. Using a setter-injection seam
+// keeps the module decoupled.
 type AuthFacade = { getUser(): { username: string } | null };
 let authFacade: AuthFacade | null = null;
 export function _setAuthFacade(facade: AuthFacade | null): void {
@@ -19,71 +30,175 @@ function currentUserIdOrDefault(): string {
 
 export type ActionType =
     | 'AUTH_REGISTER'
+    | 'AUDIO_UPLOAD'
+    | 'NOTE_KEEP_LOCAL'
     | 'PURGE_ITEM'
+    | 'SYNC_PUSH'
     | 'TASK_CREATE';
 
 export interface PendingAction {
     id: string;
     actionType: ActionType;
     payload: string; // JSON stringified
-    status: 'pending' | 'processing' | 'failed' | 'completed';
+    /** `deferred`: a recording waiting for a connection that isn't metered. */
+    status: 'pending' | 'processing' | 'deferred' | 'failed' | 'completed';
     retryCount: number;
 }
 
-type ActionProcessor = (payload: any) => Promise<void>;
+/** `get_sync_status` / `sync-status` payload (`sync::SyncStatus`). */
+export interface SyncStatus {
+    /** Waiting or being sent, deferred recordings included. */
+    pending: number;
+    /** Out of retries; sent again by `processQueue(true)`. */
+    failed: number;
+    flushing: boolean;
+    last_success_at: string | null;
+    last_error: string | null;
+    /** Whether the connection is metered; `null` when the OS can't tell. */
+    metered: boolean | null;
+}
+
+/** `sync-upload-progress` payload (`sync::upload::UploadProgress`). */
+export interface UploadProgress {
+    lecture_id: string;
+    file_name: string;
+    /** Bytes the server has acknowledged. */
+    uploaded: number;
+    total: number;
+}
+
+/** `sync-progress` / `get_last_sync_report` payload (`sync::report::SyncReport`). */
+export interface SyncReport {
+    phase: 'starting' | 'sending' | 'pushing' | 'uploading' | 'finished' | 'offline';
+    /** The action being sent. */
+    action_type: ActionType | null;
+    started_at: string;
+    finished_at: string | null;
+    actions_sent: number;
+    actions_failed: number;
+    /** Left for a later flush, e.g. recordings on a metered connection. */
+    actions_deferred: number;
+    /** Rows pushed, by entity. */
+    entities: Record<string, number>;
+    conflicts: number;
+    bytes_uploaded: number;
+    bytes_total: number;
+    errors: string[];
+}
+
+/** `get_sync_encryption` payload (`sync::crypto::EncryptionStatus`). */
+export interface SyncEncryptionStatus {
+    enabled: boolean;
+    /** False on a new device until the passphrase is entered there. */
+    has_key: boolean;
+}
+
+/** What syncs for the user (`sync::selection::SyncSelection`). */
+export interface SyncSelection {
+    notes: boolean;
+    /** Lecture recordings. */
+    audio: boolean;
+    /** Hold recordings back on a metered connection (hotspot, cellular). */
+    defer_audio_on_metered: boolean;
+    /** Courses that never leave this device. */
+    excluded_courses: string[];
+}
+
+/** A server the user syncs with (`sync::profiles::ServerProfile`). */
+export interface ServerProfile {
+    /** Empty for a profile not saved yet. */
+    id: string;
+    name: string;
+    server_url: string;
+    /** The account on that server. */
+    username: string;
+}
+
+/** `list_server_profiles` entry (`sync::profiles::ProfileStatus`). */
+export interface ServerProfileStatus extends ServerProfile {
+    active: boolean;
+    has_credentials: boolean;
+    /** Last push to this server and account. */
+    last_pushed_at: string | null;
+}
+
+/** How a note both sides changed is settled (`sync::conflicts`). */
+export type ConflictPolicy = 'lww' | 'prefer_local' | 'ask';
+
+/** A copy of a note kept from a sync conflict (`storage::NoteConflict`). */
+export interface NoteConflict {
+    id: string;
+    lecture_id: string;
+    /** Whose copy this is. */
+    side: 'local' | 'server';
+    title: string;
+    content: string;
+    generated_at: string;
+    conflicted_at: string;
+    server_url: string;
+    /** False while the user still has to pick a copy (`ask`). */
+    resolved: boolean;
+}
+
+/** `sync-action-completed` payload: an action the server accepted. */
+interface CompletedAction {
+    id: string;
+    action_type: ActionType;
+    payload: string;
+}
+
+type CompletionHandler = (payload: any) => void;
 
 class OfflineQueueService {
-    private processors: Map<ActionType, ActionProcessor> = new Map();
-    private isProcessing = false;
-    private maxRetries = 3;
+    private completionHandlers: Map<ActionType, CompletionHandler[]> = new Map();
     private listeners: ((count: number) => void)[] = [];
+    private progressListeners: ((report: SyncReport) => void)[] = [];
     private initialized = false;
 
     constructor() {
-        // Listen for online event
+        // The backend also flushes on its own every minute.
         if (typeof window !== 'undefined') {
             window.addEventListener('online', () => {
-                console.log('[OfflineQueue] Network online, processing queue...');
+                console.log('[OfflineQueue] Network online, flushing...');
                 this.processQueue();
             });
         }
     }
 
     /**
-     * Initialize queue - clean up stuck 'processing' tasks from previous session
+     * Start following the backend's status and completion events.
+     * Called by `subscribe` / `onProgress` / `onCompleted`; idempotent.
      */
     async init(): Promise<void> {
         if (this.initialized) return;
         this.initialized = true;
 
-        console.log('[OfflineQueue] Initializing, cleaning up stuck tasks...');
-        const actions = await this.listActions();
-
-        for (const action of actions) {
-            if (action.status === 'processing') {
-                // Reset to pending - was interrupted
-                // cp75.34 — userId required for Rust-side defense-in-depth gate.
-                await invoke('update_pending_action', {
-                    id: action.id,
-                    status: 'pending',
-                    retryCount: action.retryCount,
-                    userId: currentUserIdOrDefault()
-                });
-                console.log(`[OfflineQueue] Reset stuck task: ${action.actionType} (${action.id})`);
+        await listen<SyncStatus>('sync-status', (event) => {
+            this.notifyListeners(event.payload);
+        });
+        await listen<SyncReport>('sync-progress', (event) => {
+            this.progressListeners.forEach((l) => l(event.payload));
+        });
+        await listen<CompletedAction>('sync-action-completed', (event) => {
+            const { action_type, payload } = event.payload;
+            let parsed: unknown = null;
+            try {
+                parsed = JSON.parse(payload);
+            } catch {
+                // Handlers get null for a payload that isn't JSON.
             }
-        }
-
-        // If online, process queue
-        if (navigator.onLine) {
-            this.processQueue();
-        }
+            this.completionHandlers.get(action_type)?.forEach((handler) => handler(parsed));
+        });
     }
 
     /**
-     * Register a processor for a specific action type
+     * Call `handler` with the payload of every `actionType` action the
+     * server accepts.
      */
-    registerProcessor(actionType: ActionType, processor: ActionProcessor): void {
-        this.processors.set(actionType, processor);
+    onCompleted(actionType: ActionType, handler: CompletionHandler): void {
+        const handlers = this.completionHandlers.get(actionType) ?? [];
+        this.completionHandlers.set(actionType, [...handlers, handler]);
+        this.init().catch((e) => console.warn('[OfflineQueue] Failed to listen:', e));
     }
 
     /**
@@ -91,21 +206,44 @@ class OfflineQueueService {
      */
     subscribe(listener: (count: number) => void): () => void {
         this.listeners.push(listener);
-        this.notifyListeners();
+        this.init().catch((e) => console.warn('[OfflineQueue] Failed to listen:', e));
+        this.getStatus()
+            .then((status) => listener(status.pending + status.failed))
+            .catch(() => listener(0));
         return () => {
             this.listeners = this.listeners.filter(l => l !== listener);
         };
     }
 
-    private async notifyListeners(): Promise<void> {
-        const actions = await this.listActions();
-        // Include processing status as it means work is in progress
-        const count = actions.filter(a => a.status === 'pending' || a.status === 'failed' || a.status === 'processing').length;
+    /**
+     * Follow what the backend's flush is doing: phase, rows pushed,
+     * bytes uploaded, errors.
+     */
+    onProgress(listener: (report: SyncReport) => void): () => void {
+        this.progressListeners.push(listener);
+        this.init().catch((e) => console.warn('[OfflineQueue] Failed to listen:', e));
+        return () => {
+            this.progressListeners = this.progressListeners.filter(l => l !== listener);
+        };
+    }
+
+    /** The running flush's report, else the last finished one. */
+    getLastReport(): Promise<SyncReport | null> {
+        return invoke<SyncReport | null>('get_last_sync_report');
+    }
+
+    private notifyListeners(status: SyncStatus): void {
+        const count = status.pending + status.failed;
         this.listeners.forEach(l => l(count));
     }
 
+    getStatus(): Promise<SyncStatus> {
+        return invoke<SyncStatus>('get_sync_status');
+    }
+
     /**
-     * Add an action to the queue
+     * Add an action to the queue; the backend sends it right away when
+     * it can.
      */
     async enqueue(actionType: ActionType, payload: any): Promise<string> {
         const id = crypto.randomUUID();
@@ -120,14 +258,162 @@ class OfflineQueueService {
         });
 
         console.log(`[OfflineQueue] Enqueued: ${actionType} (${id})`);
-        this.notifyListeners();
+        return id;
+    }
 
-        // Try to process immediately if online
-        if (navigator.onLine) {
-            this.processQueue();
-        }
+    /**
+     * Queue the upload of a lecture's recording to the active profile's
+     * server and account. It goes up in chunks and picks up where it
+     * stopped after a dropped connection or a restart
+     * (src-tauri/src/sync/upload.rs). Resolves to `null` when there is
+     * no profile yet.
+     */
+    async uploadLectureAudio(lectureId: string): Promise<string | null> {
+        const active = await this.activeProfile();
+        if (!active) return null;
+        return this.enqueue('AUDIO_UPLOAD', {
+            serverUrl: active.server_url,
+            username: active.username,
+            lectureId,
+        });
+    }
 
-        return id;
+    /**
+     * Queue a push of the courses, lectures and notes changed since the
+     * last push to `serverUrl` (src-tauri/src/sync/push.rs).
+     */
+    pushChanges(serverUrl: string): Promise<string> {
+        return this.enqueue('SYNC_PUSH', {
+            serverUrl,
+            username: currentUserIdOrDefault(),
+        });
+    }
+
+    getConflictPolicy(): Promise<ConflictPolicy> {
+        return invoke<ConflictPolicy>('get_sync_conflict_policy', {
+            userId: currentUserIdOrDefault(),
+        });
+    }
+
+    setConflictPolicy(policy: ConflictPolicy): Promise<void> {
+        return invoke('set_sync_conflict_policy', {
+            policy,
+            userId: currentUserIdOrDefault(),
+        });
+    }
+
+    /** Conflicts waiting on a choice; `includeResolved` adds the kept copies. */
+    listNoteConflicts(includeResolved = false): Promise<NoteConflict[]> {
+        return invoke<NoteConflict[]>('list_note_conflicts', {
+            userId: currentUserIdOrDefault(),
+            includeResolved,
+        });
+    }
+
+    /** Keep one side of a conflict; the other stays as a conflicted copy. */
+    resolveNoteConflict(conflictId: string, keep: 'local' | 'server'): Promise<void> {
+        return invoke('resolve_note_conflict', {
+            conflictId,
+            keep,
+            userId: currentUserIdOrDefault(),
+        });
+    }
+
+    getEncryption(): Promise<SyncEncryptionStatus> {
+        return invoke<SyncEncryptionStatus>('get_sync_encryption', {
+            userId: currentUserIdOrDefault(),
+        });
+    }
+
+    /**
+     * Encrypt notes on this device before they are synced. The same passphrase unlocks them on the user's other
+     * devices; it is not stored, only the key derived from it (in the
+     * OS keychain). Everything is pushed again, encrypted.
+     */
+    enableEncryption(passphrase: string): Promise<void> {
+        return invoke('enable_sync_encryption', {
+            passphrase,
+            userId: currentUserIdOrDefault(),
+        });
+    }
+
+    disableEncryption(): Promise<void> {
+        return invoke('disable_sync_encryption', {
+            userId: currentUserIdOrDefault(),
+        });
+    }
+
+    getSelection(): Promise<SyncSelection> {
+        return invoke<SyncSelection>('get_sync_selection', {
+            userId: currentUserIdOrDefault(),
+        });
+    }
+
+    /**
+     * Turn data types or courses on or off for sync. What is turned off
+     * is neither pushed nor taken from the server; what is turned back
+     * on is pushed in full on the next sync.
+     */
+    setSelection(selection: SyncSelection): Promise<void> {
+        return invoke('set_sync_selection', {
+            selection,
+            userId: currentUserIdOrDefault(),
+        });
+    }
+
+    listProfiles(): Promise<ServerProfileStatus[]> {
+        return invoke<ServerProfileStatus[]>('list_server_profiles', {
+            userId: currentUserIdOrDefault(),
+        });
+    }
+
+    /**
+     * Add or update a server profile. `credentials` replaces its
+     * password or token (kept in the OS keychain; `''` removes it);
+     * left out, the saved one stays. The first profile becomes active.
+     */
+    saveProfile(profile: ServerProfile, credentials?: string): Promise<ServerProfile> {
+        return invoke<ServerProfile>('save_server_profile', {
+            profile,
+            credentials: credentials ?? null,
+            userId: currentUserIdOrDefault(),
+        });
+    }
+
+    removeProfile(profileId: string): Promise<void> {
+        return invoke('remove_server_profile', {
+            profileId,
+            userId: currentUserIdOrDefault(),
+        });
+    }
+
+    setActiveProfile(profileId: string): Promise<void> {
+        return invoke('set_active_server_profile', {
+            profileId,
+            userId: currentUserIdOrDefault(),
+        });
+    }
+
+    /**
+     * Queue a push to the active profile's server and account. Resolves
+     * to `null` when there is no profile yet.
+     */
+    async syncActiveProfile(): Promise<string | null> {
+        const active = await this.activeProfile();
+        if (!active) return null;
+        return this.enqueue('SYNC_PUSH', {
+            serverUrl: active.server_url,
+            username: active.username,
+        });
+    }
+
+    private async activeProfile(): Promise<ServerProfileStatus | null> {
+        const profiles = await this.listProfiles();
+        return profiles.find((p) => p.active) ?? null;
+    }
+
+    onUploadProgress(cb: (progress: UploadProgress) => void): Promise<UnlistenFn> {
+        return listen<UploadProgress>('sync-upload-progress', (event) => cb(event.payload));
     }
 
     /**
@@ -145,94 +431,15 @@ class OfflineQueueService {
     }
 
     /**
-     * Process all pending actions in the queue
+     * Ask the backend to send the queue now. `retryFailed` also sends
+     * the actions that ran out of retries.
      */
-    async processQueue(): Promise<void> {
-        if (this.isProcessing) {
-            console.log('[OfflineQueue] Already processing, skipping...');
-            return;
-        }
-
-        if (!navigator.onLine) {
+    async processQueue(retryFailed = false): Promise<void> {
+        if (!this.isOnline()) {
             console.log('[OfflineQueue] Offline, skipping...');
             return;
         }
-
-        this.isProcessing = true;
-        console.log('[OfflineQueue] Starting queue processing...');
-
-        try {
-            // Loop until no more pending items (to catch items added during processing)
-            let hasMore = true;
-            while (hasMore) {
-                const actions = await this.listActions();
-                const pendingActions = actions.filter(a => a.status === 'pending' || a.status === 'failed');
-
-                if (pendingActions.length === 0) {
-                    hasMore = false;
-                    break;
-                }
-
-                for (const action of pendingActions) {
-                    const processor = this.processors.get(action.actionType);
-                    if (!processor) {
-                        console.warn(`[OfflineQueue] No processor for: ${action.actionType}`);
-                        // Remove unhandled action to prevent infinite loop
-                        await invoke('remove_pending_action', { id: action.id });
-                        continue;
-                    }
-
-                    // Mark as processing
-                    // cp75.34 — userId required for Rust-side gate.
-                    await invoke('update_pending_action', {
-                        id: action.id,
-                        status: 'processing',
-                        retryCount: action.retryCount,
-                        userId: currentUserIdOrDefault()
-                    });
-
-                    try {
-                        const payload = JSON.parse(action.payload);
-                        await processor(payload);
-
-                        // Success - remove from queue
-                        await invoke('remove_pending_action', { id: action.id });
-                        console.log(`[OfflineQueue] Completed: ${action.actionType} (${action.id})`);
-
-                    } catch (error) {
-                        console.error(`[OfflineQueue] Failed: ${action.actionType} (${action.id})`, error);
-
-                        const newRetryCount = action.retryCount + 1;
-                        // cp75.34 — userId required for Rust-side gate.
-                        if (newRetryCount >= this.maxRetries) {
-                            // Max retries reached, mark as failed permanently
-                            await invoke('update_pending_action', {
-                                id: action.id,
-                                status: 'failed',
-                                retryCount: newRetryCount,
-                                userId: currentUserIdOrDefault()
-                            });
-                        } else {
-                            // Reset to pending for retry
-                            await invoke('update_pending_action', {
-                                id: action.id,
-                                status: 'pending',
-                                retryCount: newRetryCount,
-                                userId: currentUserIdOrDefault()
-                            });
-
-                            // Exponential backoff delay before next retry
-                            const delay = Math.pow(2, newRetryCount) * 1000;
-                            await new Promise(resolve => setTimeout(resolve, delay));
-                        }
-                    }
-                }
-            }
-        } finally {
-            this.isProcessing = false;
-            this.notifyListeners();
-            console.log('[OfflineQueue] Queue processing finished.');
-        }
+        await invoke('flush_sync_queue', { retryFailed });
     }
 
     /**
@@ -9,7 +9,8 @@
  * better MTEB scores than a multilingual 384-d encoder could.
  * No network calls — everything runs on-device.
  *
- * OCR: remote LLM vision when available, otherwise pdfjs text extraction.
+ * OCR: remote LLM vision when available, otherwise pdfjs text extraction
+ * with local Tesseract filling in pages that have no text layer.
  */
 
 import { chunkingService, TextChunk } from './chunkingService';
@@ -17,6 +18,7 @@ import { embeddingStorageService, SearchResult } from './embeddingStorageService
 import { generateLocalEmbedding, generateLocalEmbeddingsBatch } from './embeddingService';
 import { chat as llmChat, chatStream as llmChatStream, translateForRetrieval } from './llm';
 import { remoteOcrService } from './remoteOcrService';
+import { localOcrService } from './localOcrService';
 import { pdfToImageService } from './pdfToImageService';
 // `pdfService` pulls in pdfjs-dist at module load, which needs the
 // DOMMatrix browser API. Vitest's jsdom env doesn't polyfill that,
@@ -345,6 +347,36 @@ class RAGService {
                     // slide alignment.
                     const pdfSvc = await getPdfService();
                     const pagesText = await pdfSvc.extractAllPagesText(pdfData.slice(0));
+                    // Scanned / image-only pages have no text layer. Unless
+                    // OCR is off, run local Tesseract over just those; a
+                    // missing install leaves them empty as before.
+                    const emptyPages = mode === 'off'
+                        ? []
+                        : pagesText.filter((p) => !p.text.trim()).map((p) => p.page);
+                    if (emptyPages.length > 0) {
+                        onProgress?.({
+                            stage: 'chunking',
+                            current: 0,
+                            total: emptyPages.length,
+                            message: `本機 OCR 辨識 ${emptyPages.length} 頁掃描頁面...`,
+                        });
+                        try {
+                            const src = settings?.translation?.source_language;
+                            const hints = [src && src !== 'auto' ? src : 'en', 'zh-TW'];
+                            const ocrPages = await localOcrService.recognizePages(
+                                pdfData.slice(0),
+                                pdfHash,
+                                emptyPages,
+                                hints,
+                            );
+                            for (const r of ocrPages) {
+                                const target = pagesText.find((p) => p.page === r.page);
+                                if (target && r.text.trim()) target.text = r.text;
+                            }
+                        } catch (err) {
+                            console.warn('[RAGService] 本機 OCR 不可用，略過掃描頁面:', err);
+                        }
+                    }
                     const indexResult = await this.indexLectureFromPages(
                         lectureId,
                         pagesText.filter((p) => p.text.trim().length > 0).map((p) => ({ pageNumber: p.page, text: p.text })),
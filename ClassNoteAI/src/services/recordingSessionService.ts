@@ -44,14 +44,16 @@ import {
     type RecordingChangeDetail,
 } from './__contracts__/recordingSessionService.contract';
 
-import { AudioRecorder } from './audioRecorder';
+import { AudioRecorder, type AudioCaptureSource } from './audioRecorder';
 import { transcriptionService } from './transcriptionService';
 import { subtitleService } from './subtitleService';
 import { taskTrackerService } from './taskTrackerService';
+import { telemetryService } from './telemetryService';
 import { translationPipeline } from './streaming/translationPipeline';
 import { summarizeStream } from './llm/tasks';
 import { buildDeviceChangeWarning, type RecordingInputSnapshot } from './recordingDeviceMonitor';
 import { toRelativeSeconds } from '../utils/subtitleTimestamp';
+import { errorMessage } from './appError';
 
 // NOTE: `storageService` stays behind the existing dynamic-import
 // `storage()` helper. Static-importing it here makes it visible to the
@@ -281,15 +283,40 @@ class RecordingSessionServiceImpl implements RecordingSessionService {
                     err,
                 );
             }
+            // settings.audio.source: 'system' records the computer's own
+            // output (Zoom / Teams lectures) via loopback instead of the mic;
+            // 'both' mixes mic + system with the per-source gains.
+            let source: AudioCaptureSource = 'microphone';
+            let micGain = 1;
+            let systemGain = 1;
+            try {
+                const settings = await (await this.storage()).getAppSettings();
+                const a = settings?.audio;
+                if (a?.source === 'system' || a?.source === 'both') source = a.source;
+                micGain = a?.mic_gain ?? 1;
+                systemGain = a?.system_gain ?? 1;
+            } catch {
+                /* settings unavailable — keep the microphone */
+            }
             if (!this.recorder) {
                 this.recorder = new AudioRecorder(
-                    preferredDeviceId ? { deviceId: preferredDeviceId } : {},
+                    preferredDeviceId
+                        ? { deviceId: preferredDeviceId, source, micGain, systemGain }
+                        : { source, micGain, systemGain },
                 );
-            } else if (preferredDeviceId) {
+            } else {
+                if (preferredDeviceId) {
+                    try {
+                        this.recorder.setDeviceId(preferredDeviceId);
+                    } catch (err) {
+                        console.warn('[recordingSession] setDeviceId failed', err);
+                    }
+                }
                 try {
-                    this.recorder.setDeviceId(preferredDeviceId);
+                    this.recorder.setSource(source);
+                    this.recorder.setSourceGains(micGain, systemGain);
                 } catch (err) {
-                    console.warn('[recordingSession] setDeviceId failed', err);
+                    console.warn('[recordingSession] setSource failed', err);
                 }
             }
             // Wire mic chunks → ASR. (useRecordingSession had this — the
@@ -330,6 +357,10 @@ class RecordingSessionServiceImpl implements RecordingSessionService {
             // probably went to sleep, warn on resume.
             this.attachVisibilityListener();
 
+            // Keep the laptop from idle-sleeping mid-lecture; released in
+            // cleanupListeners() on every stop / failure path.
+            this.acquireSleepGuard(lectureId);
+
             // Flip state + start elapsed ticker.
             const now = Date.now();
             this.setState({
@@ -360,7 +391,7 @@ class RecordingSessionServiceImpl implements RecordingSessionService {
             }
             this.setState({
                 status: 'idle',
-                error: err instanceof Error ? err.message : String(err),
+                error: errorMessage(err),
             });
             throw err;
         }
@@ -424,7 +455,7 @@ class RecordingSessionServiceImpl implements RecordingSessionService {
             // start the elapsed timer — we're not actually recording.
             this.setState({
                 status: 'paused',
-                error: err instanceof Error ? err.message : String(err),
+                error: errorMessage(err),
             });
             throw err;
         }
@@ -802,6 +833,9 @@ class RecordingSessionServiceImpl implements RecordingSessionService {
         // Only emit once we've reached a terminal stopPhase. Anything
         // else is a programming error in stop()'s control flow.
         if (stopPhase !== 'failed' && stopPhase !== 'done') return;
+        telemetryService.track(
+            stopPhase === 'failed' ? 'recording.stop.failed' : 'recording.stop.saved',
+        );
         try {
             const toast = await this.toast();
             if (stopPhase === 'failed') {
@@ -1224,7 +1258,7 @@ class RecordingSessionServiceImpl implements RecordingSessionService {
 
             taskTrackerService.complete(taskId);
         } catch (err) {
-            const msg = err instanceof Error ? err.message : String(err);
+            const msg = errorMessage(err);
             taskTrackerService.fail(taskId, msg);
         }
     }
@@ -1280,7 +1314,7 @@ class RecordingSessionServiceImpl implements RecordingSessionService {
 
             taskTrackerService.complete(taskId);
         } catch (err) {
-            const msg = err instanceof Error ? err.message : String(err);
+            const msg = errorMessage(err);
             taskTrackerService.fail(taskId, msg);
         }
     }
@@ -1482,6 +1516,33 @@ class RecordingSessionServiceImpl implements RecordingSessionService {
         });
     }
 
+    /** OS power assertion (src-tauri/src/recording/power.rs) so an idle
+     *  laptop doesn't sleep and silently truncate the recording. Fire and
+     *  forget: a missing guard is worth a warning, never a failed start. */
+    private acquireSleepGuard(lectureId: string): void {
+        void import('@tauri-apps/api/core')
+            .then(({ invoke }) => invoke<boolean>('acquire_sleep_guard', { lectureId }))
+            .then(async (held) => {
+                if (held !== false) return;
+                const toast = await this.toast();
+                toast.warning(
+                    '無法阻止系統休眠',
+                    '錄音期間請保持電腦喚醒，或調整電源設定。',
+                );
+            })
+            .catch((err) => {
+                console.warn('[recordingSession] acquire_sleep_guard failed:', err);
+            });
+    }
+
+    private releaseSleepGuard(): void {
+        void import('@tauri-apps/api/core')
+            .then(({ invoke }) => invoke('release_sleep_guard'))
+            .catch((err) => {
+                console.warn('[recordingSession] release_sleep_guard failed:', err);
+            });
+    }
+
     /** S1.6 — visibilitychange. */
     private attachVisibilityListener(): void {
         if (typeof document === 'undefined') return;
@@ -1634,6 +1695,7 @@ class RecordingSessionServiceImpl implements RecordingSessionService {
         this.detachMicTrackListener();
         this.detachDeviceMonitor();
         this.detachVisibilityListener();
+        this.releaseSleepGuard();
         // cp75.22 — drop the subtitleService singleton's segment buffer
         // after we have unsubscribed. Done here (rather than at the
         // very end of stop()) so every cleanup path — happy 6-step
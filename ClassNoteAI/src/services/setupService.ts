@@ -6,7 +6,7 @@
 
 import { invoke } from '@tauri-apps/api/core';
 import { listen, UnlistenFn } from '@tauri-apps/api/event';
-import { SetupStatus, Progress, Requirement, isInstalled } from '../types/setup';
+import { SetupStatus, SetupDownload, Progress, Requirement, isInstalled } from '../types/setup';
 
 export const setupService = {
     /**
@@ -37,6 +37,15 @@ export const setupService = {
         return invoke('cancel_setup_installation');
     },
 
+    /**
+     * Downloads queued by an installation that hasn't finished, e.g.
+     * because the app quit. Installing their requirements again resumes
+     * them from the bytes already on disk.
+     */
+    async pendingDownloads(): Promise<SetupDownload[]> {
+        return invoke<SetupDownload[]>('list_setup_downloads');
+    },
+
     /**
      * Mark setup as complete
      */
@@ -4,6 +4,7 @@ import { save, open } from '@tauri-apps/plugin-dialog';
 import { authService } from './authService';
 import { extractSyllabus } from './llm';
 import { toastService } from './toastService';
+import { errorMessage } from './appError';
 // Note: pdfService is imported lazily inside generateCourseSyllabusInBackground.
 // Eager import pulls in pdfjs-dist at module-load time, which references
 // browser-only globals (DOMMatrix). Several vitest suites (consentService,
@@ -104,6 +105,15 @@ type CourseSyllabusLifecycle = 'idle' | 'generating' | 'failed' | 'ready';
 type CourseSyllabusSource = 'pdf' | 'description' | 'pdf+description';
 type CourseSyllabusRecord = Record<string, unknown>;
 
+/** Result of `move_app_data` (src-tauri/src/paths/relocate.rs). */
+export interface AppDataMoveReport {
+  from: string;
+  to: string;
+  files: number;
+  bytes: number;
+  restart_required: boolean;
+}
+
 function normalizeAppSettings(settings: AppSettings | (AppSettings & Record<string, unknown>)): AppSettings {
   const normalized = { ...settings } as AppSettings & Record<string, unknown>;
   const rawOcrMode = normalized.ocr?.mode as string | undefined;
@@ -266,11 +276,6 @@ function buildReadyCourseSyllabusInfo(
   return merged;
 }
 
-function getErrorMessage(error: unknown): string {
-  if (error instanceof Error) return error.message;
-  return String(error);
-}
-
 async function withTimeout<T>(promise: Promise<T>, timeoutMs: number, message: string): Promise<T> {
   let timer: ReturnType<typeof setTimeout> | undefined;
   try {
@@ -417,7 +422,7 @@ class StorageService {
         updated_at: new Date().toISOString(),
       });
     } catch (error) {
-      const message = getErrorMessage(error);
+      const message = errorMessage(error);
       const refreshedCourse = (await this.getCourse(course.id)) ?? course;
       await this.saveCourse({
         ...refreshedCourse,
@@ -675,10 +680,12 @@ class StorageService {
 
   /**
    * 刪除單條字幕（cp75.21: 傳 userId 給 Rust 端做 ownership check）
+   *
+   * `lectureId` 讓後端找得到已外部化 (transcript blob) 課堂裡的字幕。
    */
-  async deleteSubtitle(id: string): Promise<void> {
+  async deleteSubtitle(id: string, lectureId?: string): Promise<void> {
     const userId = authService.getUser()?.username || 'default_user';
-    await invoke('delete_subtitle', { id, userId });
+    await invoke('delete_subtitle', { id, lectureId: lectureId ?? null, userId });
   }
 
   /**
@@ -929,7 +936,7 @@ class StorageService {
 
           imported++;
         } catch (error) {
-          const errorMsg = `導入課程 ${lecture.id} 失敗: ${error instanceof Error ? error.message : String(error)}`;
+          const errorMsg = `導入課程 ${lecture.id} 失敗: ${errorMessage(error)}`;
           errors.push(errorMsg);
           console.error(errorMsg);
         }
@@ -941,7 +948,7 @@ class StorageService {
           try {
             await this.saveSetting(key, String(value));
           } catch (error) {
-            const errorMsg = `導入設置 ${key} 失敗: ${error instanceof Error ? error.message : String(error)}`;
+            const errorMsg = `導入設置 ${key} 失敗: ${errorMessage(error)}`;
             errors.push(errorMsg);
             console.error(errorMsg);
           }
@@ -950,7 +957,7 @@ class StorageService {
 
       return { imported, errors };
     } catch (error) {
-      throw new Error(`導入數據失敗: ${error instanceof Error ? error.message : String(error)}`);
+      throw new Error(`導入數據失敗: ${errorMessage(error)}`);
     }
   }
 
@@ -991,7 +998,7 @@ class StorageService {
       console.log('[StorageService] Note saved successfully');
     } catch (error) {
       console.error('[StorageService] Rust save_note failed:', error);
-      throw new Error(`保存筆記失敗: ${error}`);
+      throw new Error(`保存筆記失敗: ${errorMessage(error)}`);
     }
   }
 
@@ -1104,9 +1111,39 @@ class StorageService {
       return await this.importData(jsonData);
     } catch (error) {
       console.error('導入文件失敗:', error);
-      throw new Error(`導入文件失敗: ${error instanceof Error ? error.message : String(error)}`);
+      throw new Error(`導入文件失敗: ${errorMessage(error)}`);
     }
   }
+
+  // ========== App Data Location ==========
+
+  /**
+   * 應用程式資料目前所在目錄
+   */
+  async getAppDataDir(): Promise<string> {
+    return await invoke<string>('get_app_data_dir');
+  }
+
+  /**
+   * 選擇新的資料存放位置，取消時回傳 null
+   */
+  async pickAppDataFolder(): Promise<string | null> {
+    const dir = await open({ directory: true, title: '選擇資料存放位置' });
+    return typeof dir === 'string' ? dir : null;
+  }
+
+  /**
+   * 把全部資料複製到 newDir 並驗證；成功後應用程式隨即自動重新啟動，
+   * 避免之後的寫入只留在舊位置
+   */
+  async moveAppData(newDir: string): Promise<AppDataMoveReport> {
+    return await invoke<AppDataMoveReport>('move_app_data', { newDir });
+  }
+
+  async restartApp(): Promise<void> {
+    await invoke('restart_app');
+  }
+
   /**
    * 保存 OCR 結果
    * key: ocr_result_{lectureId}_{pageNumber}
@@ -77,10 +77,14 @@ export class AsrPipeline {
     // accented English but the renderer used to drop the setting on the
     // floor — Rust auto-loaded INT8 regardless. Now we forward.
     let preferredVariant: 'int8' | 'fp32' | undefined;
+    let normalizeGain = false;
+    let denoise = false;
     try {
       const { storageService } = await import('../storageService');
       const settings = await storageService.getAppSettings();
       preferredVariant = settings?.experimental?.parakeetVariant;
+      normalizeGain = settings?.experimental?.normalizeGain ?? false;
+      denoise = settings?.experimental?.denoiseInput ?? false;
     } catch (err) {
       console.warn('[asrPipeline] could not read parakeet variant pref:', err);
     }
@@ -89,6 +93,8 @@ export class AsrPipeline {
     await invoke('asr_start_session', {
       sessionId,
       preferredVariant: preferredVariant ?? null,
+      normalizeGain,
+      denoise,
     });
     this.sessionId = sessionId;
     this.startedAt = Date.now();
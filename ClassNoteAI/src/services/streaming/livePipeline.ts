@@ -0,0 +1,189 @@
+/**
+ * Renderer side of the Rust live pipeline (src-tauri/src/pipeline).
+ *
+ * VAD, sentence segmentation and rough translation all run in Rust
+ * now; this class only pushes captured PCM and forwards the
+ * `live-subtitle` events — already shaped as {@link SubtitleEvent} —
+ * onto `subtitleStream`, so existing consumers keep working.
+ *
+ * Pushes are chained: each `invoke` waits for the previous one, which
+ * keeps chunk order and lets the backend's bounded queue apply
+ * backpressure. `stop()` waits for the chain and for Rust to drain the
+ * ASR and translation stages, so nothing is still in flight afterwards.
+ *
+ * Rust also meters each push and emits `audio-level`; subscribe with
+ * {@link onAudioLevel}. When an ASR or translation call runs past the
+ * watchdog limit it emits `task-stalled` ({@link onTaskStalled}); a
+ * stalled ASR call is surfaced to the user once per session.
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import { subtitleStream, type SubtitleEvent } from './subtitleStream';
+
+const SAMPLE_RATE = 16000;
+
+interface LivePipelineConfig {
+  sample_rate: number;
+  preferred_variant?: 'int8' | 'fp32';
+  normalize_gain: boolean;
+  denoise: boolean;
+  source_lang?: string;
+  target_lang?: string;
+  provider?: 'local' | 'gemma' | 'google';
+  google_api_key?: string;
+  gemma_endpoint?: string;
+  /** Watchdog limits; backend defaults are 10 s / 30 s. */
+  asr_timeout_ms?: number;
+  translation_timeout_ms?: number;
+}
+
+async function readConfig(language?: string): Promise<LivePipelineConfig> {
+  const config: LivePipelineConfig = {
+    sample_rate: SAMPLE_RATE,
+    normalize_gain: false,
+    denoise: false,
+    source_lang: language,
+  };
+  try {
+    const { storageService } = await import('../storageService');
+    const settings = await storageService.getAppSettings();
+    config.preferred_variant = settings?.experimental?.parakeetVariant;
+    config.normalize_gain = settings?.experimental?.normalizeGain ?? false;
+    config.denoise = settings?.experimental?.denoiseInput ?? false;
+    const src = settings?.translation?.source_language;
+    if (!config.source_lang && src && src !== 'auto') config.source_lang = src;
+    config.target_lang = settings?.translation?.target_language || undefined;
+    config.provider = settings?.translation?.provider || undefined;
+    config.google_api_key = settings?.translation?.google_api_key || undefined;
+    config.gemma_endpoint = settings?.translation?.gemma_endpoint || undefined;
+  } catch (err) {
+    console.warn('[livePipeline] could not read settings, using defaults:', err);
+  }
+  return config;
+}
+
+/** `audio-level` payload, ~10 Hz per 100 ms window of pushed PCM. */
+export interface AudioLevel {
+  sessionId: string;
+  peakDbfs: number;
+  rmsDbfs: number;
+  clippedSamples: number;
+  clipping: boolean;
+}
+
+/** Subscribe to input levels for a VU meter / clipping warning. Only
+ *  fires while a pipeline session is receiving audio. */
+export function onAudioLevel(cb: (level: AudioLevel) => void): Promise<UnlistenFn> {
+  return listen<AudioLevel>('audio-level', (event) => cb(event.payload));
+}
+
+/** `task-stalled` payload: an inference call ran past its limit. */
+export interface TaskStalled {
+  sessionId: string;
+  task: 'asr' | 'translation';
+  /** Sentence id for translation; null for ASR audio. */
+  segmentId: string | null;
+  elapsedMs: number;
+  limitMs: number;
+  action: 'abandoned' | 'cancelled';
+  retry: boolean;
+}
+
+export function onTaskStalled(cb: (stalled: TaskStalled) => void): Promise<UnlistenFn> {
+  return listen<TaskStalled>('task-stalled', (event) => cb(event.payload));
+}
+
+export class LivePipeline {
+  private sessionId: string | null = null;
+  private pushChain: Promise<void> = Promise.resolve();
+  private unlisten: UnlistenFn | null = null;
+  private unlistenStalled: UnlistenFn | null = null;
+  private warnedAsrStall = false;
+
+  get active(): boolean {
+    return this.sessionId !== null;
+  }
+
+  async start(lectureId: string, language?: string): Promise<void> {
+    if (this.sessionId) {
+      console.warn('[livePipeline] start() called twice; stopping previous session first');
+      await this.stop();
+    }
+
+    // Listen before starting so `session_started` isn't missed.
+    this.unlisten = await listen<SubtitleEvent>('live-subtitle', (event) => {
+      subtitleStream.emit(event.payload);
+    });
+    this.warnedAsrStall = false;
+    this.unlistenStalled = await onTaskStalled((stalled) => this.handleStalled(stalled));
+    try {
+      this.sessionId = await invoke<string>('start_live_pipeline', {
+        lectureId,
+        config: await readConfig(language),
+      });
+    } catch (error) {
+      this.unlisten();
+      this.unlisten = null;
+      this.unlistenStalled?.();
+      this.unlistenStalled = null;
+      throw error;
+    }
+    this.pushChain = Promise.resolve();
+    console.log(`[livePipeline] session ${this.sessionId} ready for lecture ${lectureId}`);
+  }
+
+  pushAudio(pcm: Int16Array): Promise<void> {
+    if (!this.sessionId) {
+      console.warn('[livePipeline] pushAudio before start()');
+      return Promise.resolve();
+    }
+    const samples = Array.from(pcm);
+    this.pushChain = this.pushChain
+      .then(() => invoke<void>('live_pipeline_push_audio', { pcm: samples }))
+      .catch((e) => console.warn('[livePipeline] pushAudio failed:', e));
+    return this.pushChain;
+  }
+
+  async stop(): Promise<string> {
+    if (!this.sessionId) return '';
+    const id = this.sessionId;
+    await this.pushChain;
+    this.sessionId = null;
+
+    let transcript = '';
+    try {
+      transcript = await invoke<string>('stop_live_pipeline');
+      console.log(`[livePipeline] session ${id} final transcript: ${transcript.length} chars`);
+    } catch (e) {
+      console.warn('[livePipeline] stop failed (non-fatal):', e);
+    } finally {
+      // `session_ended` is emitted before the command returns.
+      if (this.unlisten) {
+        this.unlisten();
+        this.unlisten = null;
+      }
+      if (this.unlistenStalled) {
+        this.unlistenStalled();
+        this.unlistenStalled = null;
+      }
+    }
+    return transcript;
+  }
+
+  private handleStalled(stalled: TaskStalled): void {
+    console.warn('[livePipeline] task stalled:', stalled);
+    if (stalled.task !== 'asr' || this.warnedAsrStall) return;
+    this.warnedAsrStall = true;
+    void import('../toastService')
+      .then(({ toastService }) =>
+        toastService.warning(
+          '語音辨識暫時沒有回應',
+          '錄音仍在進行，音訊會先暫存，辨識恢復後自動補上。',
+        ),
+      )
+      .catch(() => {});
+  }
+}
+
+export const livePipeline = new LivePipeline();
@@ -27,6 +27,7 @@
 
 import { translateRough } from '../translationService';
 import { subtitleStream } from './subtitleStream';
+import { isAppError } from '../appError';
 
 interface TranslationJob {
   id: string;
@@ -41,12 +42,14 @@ function delay(ms: number): Promise<void> {
 }
 
 /**
- * Detect retryable errors from `translateRough`. Connection refused,
+ * Detect retryable errors from `translateRough`. `translate_rough`
+ * rejects with an `AppError` carrying its own `retryable` flag; for
+ * anything else fall back to the message: connection refused,
  * timeouts, and 5xx-ish messages are retryable; "model not loaded" or
- * "invalid input" are not. Heuristic — `translateRough` only surfaces
- * the message string from the Rust backend.
+ * "invalid input" are not.
  */
 function isRetryable(error: unknown): boolean {
+  if (isAppError(error)) return error.retryable;
   const msg = String((error as { message?: string })?.message ?? error).toLowerCase();
   return (
     msg.includes('未啟動') ||      // gemma_sidecar's friendly Chinese connect-error
@@ -109,7 +109,7 @@ class SubtitleService {
     if (this.lectureId) {
       try {
         const { storageService } = await import('./storageService');
-        await storageService.deleteSubtitle(segmentId);
+        await storageService.deleteSubtitle(segmentId, this.lectureId);
         console.log('[SubtitleService] 已從資料庫刪除字幕:', segmentId);
       } catch (error) {
         console.error('[SubtitleService] 刪除字幕失敗:', error);
@@ -0,0 +1,43 @@
+/**
+ * Frontend side of the opt-in telemetry module (`src-tauri/src/telemetry.rs`).
+ *
+ * Telemetry is off by default. `track()` only ever sends an event
+ * *name* — the backend drops it while the user hasn't opted in, and
+ * rejects anything that isn't `[a-z0-9._:-]{1,64}`, so callers can't
+ * leak content even by mistake. It is fire-and-forget: tracking must
+ * never break or slow the flow it observes.
+ */
+
+import { invoke } from '@tauri-apps/api/core';
+
+export interface TelemetrySettings {
+    enabled: boolean;
+    endpoint: string | null;
+    /** Random per-install id, created on first opt-in. */
+    install_id: string | null;
+    queued_batches: number;
+}
+
+export const telemetryService = {
+    getSettings(): Promise<TelemetrySettings> {
+        return invoke<TelemetrySettings>('get_telemetry_settings');
+    },
+
+    /** Opting out also discards everything collected but not yet sent. */
+    setSettings(enabled: boolean, endpoint: string | null): Promise<TelemetrySettings> {
+        return invoke<TelemetrySettings>('set_telemetry_settings', { enabled, endpoint });
+    },
+
+    /** Upload queued batches now; resolves to how many were sent. */
+    flush(): Promise<number> {
+        return invoke<number>('flush_telemetry');
+    },
+
+    track(name: string): void {
+        void Promise.resolve()
+            .then(() => invoke('record_telemetry_event', { name }))
+            .catch(() => {
+                // Telemetry is best-effort by definition.
+            });
+    },
+};
@@ -5,9 +5,10 @@
  * monolith with a thin facade over the new streaming pipeline. All real
  * work lives in the modules under `services/streaming/`:
  *
- *   - `asrPipeline`            owns Parakeet sidecar lifecycle + sessions
- *   - `sentenceAccumulator`    word stream -> complete sentence detection
- *   - `translationPipeline`    sentence -> translated, with rolling context
+ *   - `livePipeline`           bridge to the Rust live pipeline
+ *                              (src-tauri/src/pipeline): ASR session,
+ *                              VAD, sentence segmentation and rough
+ *                              translation, all ordered in one place
  *   - `subtitleStream`         append-only event bus
  *
  * This file exists only to preserve the public method names that
@@ -19,23 +20,21 @@
  *
  * Concepts that no longer exist in v2 (and so are no-ops here):
  *   - audio rolling buffer / 30 s window  -> not needed; sidecar streams
- *   - 3 commit strategies + stability counter -> SentenceAccumulator
- *   - inline translation in `commitStableText` -> TranslationPipeline
+ *   - 3 commit strategies + stability counter -> Rust segmenter
+ *   - inline translation in `commitStableText` -> Rust translator stage
  *   - audio-slice fine refinement -> dropped (Parakeet accuracy is good
  *     enough; refinement was a Whisper bandaid)
  */
 
 import { subtitleService } from './subtitleService';
-import { asrPipeline } from './streaming/asrPipeline';
+import { livePipeline } from './streaming/livePipeline';
 import { subtitleStream } from './streaming/subtitleStream';
 import type { AudioChunk } from './audioRecorder';
 
 class TranscriptionService {
-  // Retained as part of the public API for callers that may persist
-  // the active lecture id; v2 doesn't act on it directly because the
-  // streaming pipeline isn't lecture-aware (subtitles flow through
-  // subtitleStream which is session-keyed, not lecture-keyed). Exposed
-  // as a getter so tsc doesn't warn about the field being write-only.
+  // Lecture the live pipeline session is opened for. Subtitles still
+  // flow through subtitleStream keyed by session; the backend uses the
+  // lecture id to make a repeated start for the same lecture idempotent.
   private _lectureId: string | null = null;
   get lectureId(): string | null {
     return this._lectureId;
@@ -163,7 +162,7 @@ class TranscriptionService {
 
       try {
         const language = this.sourceLang === 'auto' ? undefined : this.sourceLang;
-        await asrPipeline.start(language);
+        await livePipeline.start(this._lectureId ?? 'unassigned', language);
         this.active = true;
       } catch (error) {
         if (this.unsubscribe) {
@@ -194,7 +193,7 @@ class TranscriptionService {
     if (!this.active) return;
     this.active = false;
     try {
-      await asrPipeline.stop();
+      await livePipeline.stop();
     } finally {
       if (this.unsubscribe) {
         this.unsubscribe();
@@ -237,7 +236,7 @@ class TranscriptionService {
       await this.startPromise;
     }
     if (!this.paused && this.active) {
-      await asrPipeline.pushAudio(chunk.data);
+      await livePipeline.pushAudio(chunk.data);
     }
   }
 
@@ -4,6 +4,9 @@
  */
 
 import { invoke } from '@tauri-apps/api/core';
+import type { UnlistenFn } from '@tauri-apps/api/event';
+import { setupService } from './setupService';
+import { getProgressPercentage } from '../types/setup';
 
 /**
  * 獲取可用的翻譯模型列表
@@ -74,23 +77,15 @@ export async function downloadTranslationModel(
   outputDir: string,
   onProgress?: (progress: number) => void
 ): Promise<string> {
+  let unlisten: UnlistenFn | null = null;
   try {
-    // 模擬進度更新（實際進度需要通過 Tauri 事件系統獲取）
-    let currentProgress = 0;
-    let progressInterval: ReturnType<typeof setInterval> | null = null;
-
+    // 進度經由 setup-progress 事件回報，task_id 為模型名稱
     if (onProgress) {
-      progressInterval = setInterval(() => {
-        if (currentProgress >= 95) {
-          if (progressInterval) {
-            clearInterval(progressInterval);
-          }
-          currentProgress = 95;
-        } else {
-          currentProgress += 5;
+      unlisten = await setupService.onProgress((progress) => {
+        if (progress.task_id === modelName && progress.status === 'InProgress') {
+          onProgress(getProgressPercentage(progress));
         }
-        onProgress(currentProgress);
-      }, 500);
+      });
     }
 
     const result = await invoke<string>('download_translation_model', {
@@ -98,10 +93,6 @@ export async function downloadTranslationModel(
       outputDir: outputDir,
     });
 
-    // 清理進度更新
-    if (progressInterval) {
-      clearInterval(progressInterval);
-    }
     if (onProgress) {
       onProgress(100); // 完成
     }
@@ -111,6 +102,8 @@ export async function downloadTranslationModel(
   } catch (error) {
     console.error('[TranslationModelService] 模型下載失敗:', error);
     throw error;
+  } finally {
+    if (unlisten) unlisten();
   }
 }
 
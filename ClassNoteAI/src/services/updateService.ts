@@ -56,6 +56,15 @@ export interface UpdateInfo {
     date?: string;
 }
 
+export interface ReleaseNotes {
+    version: string;
+    /** Markdown. */
+    notes: string;
+    date: string | null;
+    /** Entries of the notes' data migration section, to show before installing. */
+    migrationWarnings: string[];
+}
+
 export interface UpdateProgress {
     downloaded: number;
     total: number;
@@ -81,10 +90,16 @@ class UpdateService {
         }
     }
 
+    /**
+     * Also saved in the backend, which checks on this channel when no
+     * channel is passed. Beta and alpha are offered the newest release
+     * across their own and the more stable channels' manifests.
+     */
     async setReleaseChannel(channel: ReleaseChannel): Promise<void> {
         const settings = (await storageService.getAppSettings()) ?? ({} as any);
         const next = { ...settings, updates: { ...(settings.updates ?? {}), channel } };
         await storageService.saveAppSettings(next);
+        await invoke('set_update_channel', { channel });
     }
 
     /**
@@ -113,6 +128,15 @@ class UpdateService {
         }
     }
 
+    /**
+     * What changed in `version`, or in the update the saved channel
+     * offers when omitted; null when there is no update. Cached by the
+     * backend after the first fetch.
+     */
+    async getReleaseNotes(version?: string): Promise<ReleaseNotes | null> {
+        return invoke<ReleaseNotes | null>('get_release_notes', { version: version ?? null });
+    }
+
     /**
      * Download and install the update with progress callback.
      * Stable channel → Tauri updater plugin (signed, auto-install +
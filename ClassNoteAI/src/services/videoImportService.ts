@@ -4,6 +4,7 @@ import { subtitleStream, type SubtitleEvent } from './streaming/subtitleStream';
 import { storageService } from './storageService';
 import type { Lecture, Subtitle } from '../types';
 import { isAudioOnlyMediaPath } from '../utils/mediaFileTypes';
+import { errorMessage } from './appError';
 
 export type ImportStage =
     | 'staging'
@@ -282,7 +283,7 @@ export const videoImportService = {
         } catch (error) {
             report({
                 stage: 'error',
-                message: error instanceof Error ? error.message : String(error),
+                message: errorMessage(error),
             });
             throw error;
         } finally {
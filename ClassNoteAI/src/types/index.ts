@@ -231,6 +231,13 @@ export interface AppSettings {
   };
   audio: {
     device_id?: string;
+    /** 'system' records what the computer plays (online lectures)
+     *  instead of the microphone; 'both' mixes the two into one track.
+     *  Default 'microphone'. */
+    source?: "microphone" | "system" | "both";
+    /** Linear per-source gains used when source = 'both'. Default 1. */
+    mic_gain?: number;
+    system_gain?: number;
     sample_rate: number;
     chunk_duration: number;
     /** Watch for unplugged headset / muted device mid-recording and
@@ -409,6 +416,12 @@ export interface AppSettings {
     /** Local transcription model variant — Parakeet INT8 (default,
      *  smaller / faster) vs FP32 (more accurate, larger). */
     parakeetVariant?: 'int8' | 'fp32';
+    /** Run live audio through the Rust-side AGC before transcription.
+     *  Helps when the lecturer is far from the mic. Off by default. */
+    normalizeGain?: boolean;
+    /** Suppress steady background noise (HVAC, fans) before
+     *  transcription. Off by default; previewable in settings. */
+    denoiseInput?: boolean;
     /** Frontend logging verbosity. Echoes `console.*` and (if Tauri
      *  exposes a setter later) the Rust tracing subscriber level. */
     logLevel?: 'error' | 'warn' | 'info' | 'debug' | 'trace';
@@ -14,6 +14,7 @@ export type RequirementStatus =
     | 'Installed'
     | 'NotInstalled'
     | { Outdated: { current: string; required: string } }
+    | { Corrupted: { files: string[] } }
     | { Error: string };
 
 export interface Requirement {
@@ -52,6 +53,16 @@ export interface Progress {
     message: string | null;
 }
 
+/** A setup download that was started and hasn't finished or been cancelled. */
+export interface SetupDownload {
+    dest: string;
+    requirement_id: string;
+    url: string;
+    /** Bytes on disk at the last checkpoint. */
+    bytes_done: number;
+    total_bytes: number | null;
+}
+
 // Helper functions for type guards
 export function isInstalled(status: RequirementStatus): boolean {
     return status === 'Installed';
@@ -65,6 +76,11 @@ export function isOutdated(status: RequirementStatus): boolean {
     return typeof status === 'object' && 'Outdated' in status;
 }
 
+/** Installed but with missing or truncated files; installing it again repairs it. */
+export function isCorrupted(status: RequirementStatus): boolean {
+    return typeof status === 'object' && 'Corrupted' in status;
+}
+
 export function isError(status: RequirementStatus): boolean {
     return typeof status === 'object' && 'Error' in status;
 }